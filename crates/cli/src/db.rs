@@ -0,0 +1,612 @@
+//! `db` command: shows, applies and
+//! verifies the versioned schema
+//! migrations recorded in
+//! `schema_migrations`, runs
+//! retention pruning on demand, copies
+//! a database into another (possibly
+//! of the other dialect) and backs it
+//! up or restores it.
+
+use std::path::PathBuf;
+
+use clap::{
+  Args,
+  Subcommand,
+  ValueEnum
+};
+use pulsewire_core::app::retention::{
+  prune_all,
+  prune_archive
+};
+use pulsewire_core::domain::model::AppConfig;
+use pulsewire_core::infra::config::ConfigLoader;
+use pulsewire_core::infra::database::create_repo;
+use pulsewire_core::infra::backup::{
+  BackupKind,
+  backup_database,
+  restore_database
+};
+use pulsewire_core::infra::db_copy::{
+  TableCheck,
+  copy_database
+};
+use pulsewire_core::infra::migrations::{
+  self,
+  Component,
+  MigrationStatus
+};
+use pulsewire_core::infra::system_clock::SystemClock;
+use pulsewire_core::infra::time::format_epoch_ms;
+use pulsewire_core::ports::clock::Clock;
+
+#[derive(Subcommand)]
+pub enum DbCommand {
+  /// Show applied and pending
+  /// migrations.
+  Status(DbArgs),
+  /// Apply pending migrations.
+  Migrate(DbArgs),
+  /// Check the ledger against this
+  /// build; fails on unknown versions
+  /// or edited migrations.
+  Verify(DbArgs),
+  /// Apply the `[retention]` limits
+  /// once, even when the background
+  /// job is disabled.
+  Prune(PruneArgs),
+  /// Copy every fetcher and server
+  /// table into an empty database,
+  /// then verify row counts and
+  /// checksums.
+  Copy(CopyArgs),
+  /// Write a backup directory: a
+  /// `VACUUM INTO` snapshot for
+  /// SQLite, a logical dump for
+  /// Postgres, stamped with the schema
+  /// versions.
+  Backup(BackupArgs),
+  /// Restore a backup written by this
+  /// or an older build, then verify
+  /// row counts and checksums.
+  Restore(RestoreArgs)
+}
+
+#[derive(Args)]
+pub struct DbArgs {
+  /// Path to config.toml (defaults
+  /// to CONFIG_PATH or
+  /// crates/fetcher/res/config.
+  /// toml).
+  config_path:   Option<PathBuf>,
+  /// Limit to one component (default:
+  /// fetcher, then server).
+  #[arg(long, value_enum)]
+  component:     Option<DbComponent>,
+  /// Server schema name (Postgres
+  /// only).
+  #[arg(
+    long,
+    default_value = "server"
+  )]
+  server_schema: String
+}
+
+#[derive(Args)]
+pub struct PruneArgs {
+  /// Path to config.toml (defaults
+  /// to CONFIG_PATH or
+  /// crates/fetcher/res/config.
+  /// toml).
+  config_path: Option<PathBuf>,
+  /// Count prunable rows without
+  /// deleting them.
+  #[arg(long)]
+  dry_run:     bool
+}
+
+#[derive(Args)]
+pub struct CopyArgs {
+  /// Config naming the source
+  /// database.
+  #[arg(long)]
+  from:          PathBuf,
+  /// Config naming the target
+  /// database; it is migrated first
+  /// and must hold no rows.
+  #[arg(long)]
+  to:            PathBuf,
+  /// Server schema name (Postgres
+  /// only), on both sides.
+  #[arg(
+    long,
+    default_value = "server"
+  )]
+  server_schema: String,
+  /// Rows per read/insert batch.
+  #[arg(long, default_value_t = 500)]
+  batch_size:    u64
+}
+
+#[derive(Args)]
+pub struct BackupArgs {
+  /// Backup directory to create; must
+  /// not exist or be empty.
+  dest:          PathBuf,
+  /// Path to config.toml (defaults
+  /// to CONFIG_PATH or
+  /// crates/fetcher/res/config.
+  /// toml).
+  config_path:   Option<PathBuf>,
+  /// Server schema name (Postgres
+  /// only; default
+  /// `[backup].server_schema`).
+  #[arg(long)]
+  server_schema: Option<String>,
+  /// Dump SQLite logically as well,
+  /// so the backup restores into
+  /// Postgres.
+  #[arg(long)]
+  logical:       bool
+}
+
+#[derive(Args)]
+pub struct RestoreArgs {
+  /// Backup directory holding
+  /// `manifest.json`.
+  src:           PathBuf,
+  /// Path to config.toml (defaults
+  /// to CONFIG_PATH or
+  /// crates/fetcher/res/config.
+  /// toml).
+  config_path:   Option<PathBuf>,
+  /// Server schema name (Postgres
+  /// only; default
+  /// `[backup].server_schema`).
+  #[arg(long)]
+  server_schema: Option<String>,
+  /// Replace the data of a database
+  /// that already holds rows.
+  #[arg(long)]
+  force:         bool
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum DbComponent {
+  Fetcher,
+  Server
+}
+
+pub async fn run(
+  command: DbCommand
+) -> Result<(), String> {
+  let (args, action) = match command {
+    | DbCommand::Status(args) => {
+      (args, Action::Status)
+    }
+    | DbCommand::Migrate(args) => {
+      (args, Action::Migrate)
+    }
+    | DbCommand::Verify(args) => {
+      (args, Action::Verify)
+    }
+    | DbCommand::Prune(args) => {
+      return prune(args).await;
+    }
+    | DbCommand::Copy(args) => {
+      return copy(args).await;
+    }
+    | DbCommand::Backup(args) => {
+      return backup(args).await;
+    }
+    | DbCommand::Restore(args) => {
+      return restore(args).await;
+    }
+  };
+
+  let cfg_path =
+    crate::pick_config_path(
+      args.config_path
+    );
+
+  let app =
+    ConfigLoader::load(&cfg_path)
+      .await
+      .map_err(|e| e.to_string())?
+      .app;
+
+  let components = match args.component
+  {
+    | Some(DbComponent::Fetcher) => {
+      vec![Component::Fetcher]
+    }
+    | Some(DbComponent::Server) => {
+      vec![Component::Server]
+    }
+    | None => {
+      vec![
+        Component::Fetcher,
+        Component::Server,
+      ]
+    }
+  };
+
+  let mut problems = 0;
+
+  for component in components {
+    if action == Action::Migrate {
+      migrate(
+        &app,
+        component,
+        &args.server_schema
+      )
+      .await?;
+    }
+
+    let status =
+      migrations::database_status(
+        &app,
+        component,
+        &args.server_schema
+      )
+      .await?;
+
+    match action {
+      | Action::Status
+      | Action::Migrate => {
+        print_status(&app, &status);
+      }
+      | Action::Verify => {
+        problems += verify(&status);
+      }
+    }
+  }
+
+  if problems > 0 {
+    return Err(format!(
+      "verification failed: \
+       {problems} problem(s)"
+    ));
+  }
+
+  Ok(())
+}
+
+#[derive(
+  Clone, Copy, PartialEq, Eq,
+)]
+enum Action {
+  Status,
+  Migrate,
+  Verify
+}
+
+async fn migrate(
+  app: &AppConfig,
+  component: Component,
+  server_schema: &str
+) -> Result<(), String> {
+  match component {
+    | Component::Fetcher => {
+      create_repo(app.db_dialect, app)
+        .await?
+        .migrate(
+          &app.timezone,
+          app.default_poll_seconds
+        )
+        .await
+    }
+    | Component::Server => {
+      migrations::migrate_server(
+        app,
+        server_schema
+      )
+      .await
+      .map(|_| ())
+    }
+  }
+}
+
+async fn prune(
+  args: PruneArgs
+) -> Result<(), String> {
+  let cfg_path =
+    crate::pick_config_path(
+      args.config_path
+    );
+
+  let app =
+    ConfigLoader::load(&cfg_path)
+      .await
+      .map_err(|e| e.to_string())?
+      .app;
+
+  let repo =
+    create_repo(app.db_dialect, &app)
+      .await?;
+
+  repo
+    .migrate(
+      &app.timezone,
+      app.default_poll_seconds
+    )
+    .await?;
+
+  let totals = prune_all(
+    repo.as_ref(),
+    &app.retention,
+    SystemClock.now_epoch_ms().await,
+    args.dry_run
+  )
+  .await?;
+
+  let verb = if args.dry_run {
+    "would delete"
+  } else {
+    "deleted"
+  };
+
+  for (table, rows) in totals {
+    println!(
+      "{}: {verb} {rows}",
+      table.as_str()
+    );
+  }
+
+  if let Some(blobs) = prune_archive(
+    repo.as_ref(),
+    &app,
+    args.dry_run
+  )
+  .await?
+  {
+    println!(
+      "payload_archive: {verb} {blobs}"
+    );
+  }
+
+  Ok(())
+}
+
+async fn copy(
+  args: CopyArgs
+) -> Result<(), String> {
+  let from =
+    ConfigLoader::load(&args.from)
+      .await
+      .map_err(|e| e.to_string())?
+      .app;
+
+  let to = ConfigLoader::load(&args.to)
+    .await
+    .map_err(|e| e.to_string())?
+    .app;
+
+  let report = copy_database(
+    &from,
+    &to,
+    &args.server_schema,
+    args.batch_size
+  )
+  .await?;
+
+  for copied in &report.copied {
+    println!(
+      "{}: copied {}",
+      copied.table, copied.rows
+    );
+  }
+
+  for table in &report.skipped {
+    println!(
+      "{table}: skipped (not in \
+       source)"
+    );
+  }
+
+  print_checks(&report.checks)
+}
+
+/// Prints one line per table check;
+/// fails if any table differs.
+fn print_checks(
+  checks: &[TableCheck]
+) -> Result<(), String> {
+  let mut mismatches = 0;
+
+  for check in checks {
+    if check.matches() {
+      println!(
+        "ok: {} {} row(s), checksum {}",
+        check.table,
+        check.target_rows,
+        &check.target_checksum[..12]
+      );
+    } else {
+      mismatches += 1;
+
+      println!(
+        "error: {} source {} row(s) \
+         {} vs target {} row(s) {}",
+        check.table,
+        check.source_rows,
+        &check.source_checksum[..12],
+        check.target_rows,
+        &check.target_checksum[..12]
+      );
+    }
+  }
+
+  if mismatches > 0 {
+    return Err(format!(
+      "verification failed: \
+       {mismatches} table(s) differ"
+    ));
+  }
+
+  Ok(())
+}
+
+async fn backup(
+  args: BackupArgs
+) -> Result<(), String> {
+  let cfg_path =
+    crate::pick_config_path(
+      args.config_path
+    );
+
+  let app =
+    ConfigLoader::load(&cfg_path)
+      .await
+      .map_err(|e| e.to_string())?
+      .app;
+
+  let server_schema = args
+    .server_schema
+    .unwrap_or_else(|| {
+      app.backup.server_schema.clone()
+    });
+
+  let manifest = backup_database(
+    &app,
+    &args.dest,
+    &server_schema,
+    args.logical,
+    app.backup.zstd_level,
+    SystemClock.now_epoch_ms().await
+  )
+  .await?;
+
+  for table in &manifest.tables {
+    println!(
+      "{}: {} row(s), checksum {}",
+      table.name,
+      table.rows,
+      &table.checksum[..12]
+    );
+  }
+
+  println!(
+    "ok: {} backup written to {} \
+     (fetcher schema {}, server \
+     schema {})",
+    match manifest.kind {
+      | BackupKind::SqliteFile => {
+        "sqlite file"
+      }
+      | BackupKind::Logical => {
+        "logical"
+      }
+    },
+    args.dest.display(),
+    manifest.fetcher_version,
+    manifest
+      .server_version
+      .map(|v| v.to_string())
+      .unwrap_or_else(|| {
+        "absent".to_string()
+      })
+  );
+
+  Ok(())
+}
+
+async fn restore(
+  args: RestoreArgs
+) -> Result<(), String> {
+  let cfg_path =
+    crate::pick_config_path(
+      args.config_path
+    );
+
+  let app =
+    ConfigLoader::load(&cfg_path)
+      .await
+      .map_err(|e| e.to_string())?
+      .app;
+
+  let server_schema = args
+    .server_schema
+    .unwrap_or_else(|| {
+      app.backup.server_schema.clone()
+    });
+
+  let report = restore_database(
+    &app,
+    &args.src,
+    &server_schema,
+    args.force
+  )
+  .await?;
+
+  if report.checks.is_empty() {
+    println!(
+      "ok: restored {} row(s) from \
+       fetcher schema {} and migrated \
+       to this build",
+      report.manifest.total_rows(),
+      report.manifest.fetcher_version
+    );
+
+    return Ok(());
+  }
+
+  print_checks(&report.checks)
+}
+
+fn print_status(
+  app: &AppConfig,
+  status: &MigrationStatus
+) {
+  println!(
+    "{}: version {} (build knows {})",
+    status.component.as_str(),
+    status.current_version(),
+    status.latest_version()
+  );
+
+  for applied in &status.applied {
+    println!(
+      "  {:04}_{}  applied {}",
+      applied.version,
+      applied.name,
+      format_epoch_ms(
+        applied.applied_at_ms,
+        &app.timezone
+      )
+    );
+  }
+
+  for pending in status.pending() {
+    println!(
+      "  {:04}_{}  pending",
+      pending.version, pending.name
+    );
+  }
+}
+
+fn verify(
+  status: &MigrationStatus
+) -> usize {
+  let mut problems = status.problems();
+
+  if let Err(e) =
+    status.ensure_supported()
+  {
+    problems.push(e);
+  }
+
+  if problems.is_empty() {
+    println!(
+      "ok: {} schema at version {} \
+       matches this build",
+      status.component.as_str(),
+      status.current_version()
+    );
+  }
+
+  for problem in &problems {
+    println!("error: {problem}");
+  }
+
+  problems.len()
+}
@@ -0,0 +1,452 @@
+//! `feed status` and `report health`
+//! commands: feed health computed from
+//! the fetcher's stored history.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::{
+  Args,
+  Subcommand
+};
+use pulsewire_core::app::health::{
+  FeedHealth,
+  HealthWindow,
+  feed_health,
+  health_report
+};
+use pulsewire_core::domain::model::AppConfig;
+use pulsewire_core::infra::config::ConfigLoader;
+use pulsewire_core::infra::database::create_repo;
+use pulsewire_core::infra::time::format_epoch_ms;
+use pulsewire_core::ports::repo::Repo;
+
+#[derive(Subcommand)]
+pub enum FeedCommand {
+  /// Health, recent fetches, state
+  /// changes and payloads of one feed.
+  Status(FeedStatusArgs)
+}
+
+#[derive(Subcommand)]
+pub enum ReportCommand {
+  /// One health line per stored feed.
+  Health(ReportArgs)
+}
+
+#[derive(Args)]
+pub struct WindowArgs {
+  /// Newest fetch events per feed
+  /// used for success rate and
+  /// latency.
+  #[arg(long, default_value_t = 100)]
+  events:   u64,
+  /// Newest payloads per feed used for
+  /// items per fetch.
+  #[arg(long, default_value_t = 20)]
+  payloads: u64
+}
+
+#[derive(Args)]
+pub struct FeedStatusArgs {
+  /// Feed id.
+  feed:        String,
+  /// Path to config.toml (defaults
+  /// to CONFIG_PATH or
+  /// crates/fetcher/res/config.
+  /// toml).
+  config_path: Option<PathBuf>,
+  /// Rows listed per history section.
+  #[arg(long, default_value_t = 10)]
+  rows:        u64,
+  #[command(flatten)]
+  window:      WindowArgs
+}
+
+#[derive(Args)]
+pub struct ReportArgs {
+  /// Path to config.toml (defaults
+  /// to CONFIG_PATH or
+  /// crates/fetcher/res/config.
+  /// toml).
+  config_path: Option<PathBuf>,
+  /// Only list feeds that are parked
+  /// or backing off after errors.
+  #[arg(long)]
+  failing:     bool,
+  #[command(flatten)]
+  window:      WindowArgs
+}
+
+impl WindowArgs {
+  fn window(&self) -> HealthWindow {
+    HealthWindow {
+      events:   self.events,
+      payloads: self.payloads
+    }
+  }
+}
+
+pub async fn run_feed(
+  command: FeedCommand
+) -> Result<(), String> {
+  match command {
+    | FeedCommand::Status(args) => {
+      status(args).await
+    }
+  }
+}
+
+pub async fn run_report(
+  command: ReportCommand
+) -> Result<(), String> {
+  match command {
+    | ReportCommand::Health(args) => {
+      report(args).await
+    }
+  }
+}
+
+async fn open(
+  config_path: Option<PathBuf>
+) -> Result<
+  (Arc<dyn Repo>, AppConfig),
+  String
+> {
+  let cfg_path =
+    crate::pick_config_path(
+      config_path
+    );
+
+  let app =
+    ConfigLoader::load(&cfg_path)
+      .await
+      .map_err(|e| e.to_string())?
+      .app;
+
+  let repo =
+    create_repo(app.db_dialect, &app)
+      .await?;
+
+  repo
+    .migrate(
+      &app.timezone,
+      app.default_poll_seconds
+    )
+    .await?;
+
+  Ok((repo, app))
+}
+
+async fn status(
+  args: FeedStatusArgs
+) -> Result<(), String> {
+  let (repo, app) =
+    open(args.config_path).await?;
+
+  let zone = &app.timezone;
+
+  let repo = repo.as_ref();
+
+  let known = repo
+    .feed_categories()
+    .await?
+    .into_iter()
+    .any(|(id, _)| id == args.feed);
+
+  if !known {
+    return Err(format!(
+      "unknown feed '{}'",
+      args.feed
+    ));
+  }
+
+  let health = feed_health(
+    repo,
+    &args.feed,
+    args.window.window()
+  )
+  .await?;
+
+  print_summary(&health, &app);
+
+  let events = repo
+    .recent_events(
+      &args.feed, args.rows
+    )
+    .await?;
+
+  println!(
+    "recent fetches ({}):",
+    events.len()
+  );
+
+  for e in &events {
+    println!(
+      "  {} {:<4} {:>4} {:>6} backoff \
+       {}{}",
+      format_epoch_ms(
+        e.event_time_ms,
+        zone
+      ),
+      e.method,
+      opt(e.status),
+      e.latency_ms
+        .map(|ms| format!("{ms}ms"))
+        .unwrap_or_else(|| {
+          "-".to_string()
+        }),
+      e.backoff_index,
+      e.error_kind
+        .as_deref()
+        .map(|k| format!(" [{k}]"))
+        .unwrap_or_default()
+    );
+  }
+
+  let history = repo
+    .state_history(
+      &args.feed, args.rows
+    )
+    .await?;
+
+  println!(
+    "state changes ({}):",
+    history.len()
+  );
+
+  for h in &history {
+    println!(
+      "  {} {} backoff {} errors {} \
+       next {}{}",
+      format_epoch_ms(
+        h.recorded_at_ms,
+        zone
+      ),
+      h.state.phase,
+      h.state.backoff_index,
+      h.state.consecutive_error_count,
+      format_epoch_ms(
+        h.state.next_action_at_ms,
+        zone
+      ),
+      h.state
+        .note
+        .as_deref()
+        .map(|n| format!(" ({n})"))
+        .unwrap_or_default()
+    );
+  }
+
+  let payloads = repo
+    .payload_timeline(
+      &args.feed, args.rows
+    )
+    .await?;
+
+  println!(
+    "payloads ({}):",
+    payloads.len()
+  );
+
+  for p in &payloads {
+    println!(
+      "  #{} {} {} item(s){}",
+      p.payload_id,
+      format_epoch_ms(
+        p.fetched_at_ms,
+        zone
+      ),
+      p.item_count,
+      p.etag
+        .as_deref()
+        .map(|e| format!(" etag {e}"))
+        .unwrap_or_default()
+    );
+
+    for item in repo
+      .payload_items(p.payload_id)
+      .await?
+    {
+      println!(
+        "    - {}",
+        item
+          .title
+          .or(item.guid)
+          .unwrap_or_else(|| {
+            "(untitled)".to_string()
+          })
+      );
+    }
+  }
+
+  Ok(())
+}
+
+async fn report(
+  args: ReportArgs
+) -> Result<(), String> {
+  let (repo, app) =
+    open(args.config_path).await?;
+
+  let zone = &app.timezone;
+
+  let report = health_report(
+    repo.as_ref(),
+    args.window.window()
+  )
+  .await?;
+
+  let rows: Vec<&FeedHealth> = report
+    .iter()
+    .filter(|h| {
+      !args.failing
+        || h.parked
+        || h
+          .consecutive_errors
+          .is_some_and(|n| n > 0)
+    })
+    .collect();
+
+  println!(
+    "{:<24} {:>6} {:>8} {:>7} {:>7} \
+     {:<16} last new item",
+    "feed",
+    "ok%",
+    "p50",
+    "items",
+    "backoff",
+    "phase"
+  );
+
+  for h in &rows {
+    println!(
+      "{:<24} {:>6} {:>8} {:>7} {:>7} \
+       {:<16} {}",
+      h.feed_id,
+      h.success_rate
+        .map(|r| {
+          format!("{:.1}", r * 100.0)
+        })
+        .unwrap_or_else(|| {
+          "-".to_string()
+        }),
+      h.median_latency_ms
+        .map(|ms| format!("{ms}ms"))
+        .unwrap_or_else(|| {
+          "-".to_string()
+        }),
+      h.avg_items_per_fetch
+        .map(|n| format!("{n:.1}"))
+        .unwrap_or_else(|| {
+          "-".to_string()
+        }),
+      opt(h.backoff_index),
+      phase_label(h),
+      h.last_new_item_at_ms
+        .map(|ms| {
+          format_epoch_ms(ms, zone)
+        })
+        .unwrap_or_else(|| {
+          "-".to_string()
+        })
+    );
+  }
+
+  println!(
+    "ok: {} of {} feed(s) listed",
+    rows.len(),
+    report.len()
+  );
+
+  Ok(())
+}
+
+fn print_summary(
+  h: &FeedHealth,
+  app: &AppConfig
+) {
+  let time = |ms: Option<i64>| {
+    ms.map(|ms| {
+      format_epoch_ms(ms, &app.timezone)
+    })
+    .unwrap_or_else(|| "-".to_string())
+  };
+
+  println!("feed {}", h.feed_id);
+  println!(
+    "  phase: {}",
+    phase_label(h)
+  );
+  println!(
+    "  success rate: {} over {} \
+     fetch(es)",
+    h.success_rate
+      .map(|r| {
+        format!("{:.1}%", r * 100.0)
+      })
+      .unwrap_or_else(|| {
+        "-".to_string()
+      }),
+    h.events
+  );
+  println!(
+    "  median latency: {}",
+    h.median_latency_ms
+      .map(|ms| format!("{ms}ms"))
+      .unwrap_or_else(|| {
+        "-".to_string()
+      })
+  );
+  println!(
+    "  items per fetch: {}",
+    h.avg_items_per_fetch
+      .map(|n| format!("{n:.2}"))
+      .unwrap_or_else(|| {
+        "-".to_string()
+      })
+  );
+  println!(
+    "  last fetch: {}",
+    time(h.last_fetch_at_ms)
+  );
+  println!(
+    "  last new item: {}",
+    time(h.last_new_item_at_ms)
+  );
+  println!(
+    "  backoff: {} ({} consecutive \
+     error(s)), next action {}",
+    opt(h.backoff_index),
+    opt(h.consecutive_errors),
+    time(h.next_action_at_ms)
+  );
+
+  if h.parked {
+    println!(
+      "  parked: {} {}",
+      h.last_error_kind
+        .as_deref()
+        .unwrap_or("-"),
+      opt(h.last_error_status)
+    );
+  }
+}
+
+fn phase_label(
+  h: &FeedHealth
+) -> String {
+  if h.parked {
+    return "parked".to_string();
+  }
+
+  h.phase
+    .clone()
+    .unwrap_or_else(|| "-".to_string())
+}
+
+fn opt(value: Option<i64>) -> String {
+  value
+    .map(|v| v.to_string())
+    .unwrap_or_else(|| "-".to_string())
+}
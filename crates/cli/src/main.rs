@@ -1,3 +1,9 @@
+mod db;
+mod health;
+mod opml;
+mod redirects;
+mod reparse;
+
 use std::path::PathBuf;
 
 use clap::{
@@ -43,6 +49,75 @@ enum Command {
     /// actions.
     #[arg(long)]
     confirm:     bool
+  },
+  /// Report feeds with repeated
+  /// permanent redirects (301/308) or
+  /// archived after 410 Gone.
+  Redirects {
+    /// Path to config.toml (defaults
+    /// to CONFIG_PATH or
+    /// crates/fetcher/res/config.
+    /// toml).
+    config_path: Option<PathBuf>,
+    /// Minimum consecutive redirect
+    /// hits before a feed is listed.
+    #[arg(long, default_value_t = 2)]
+    min_hits:    i64,
+    /// Rewrite the listed feed URLs in
+    /// the feed TOML files.
+    #[arg(long)]
+    rewrite:     bool
+  },
+  /// Re-parse archived payload bodies
+  /// of a feed and backfill missing
+  /// items.
+  Reparse {
+    /// Path to config.toml (defaults
+    /// to CONFIG_PATH or
+    /// crates/fetcher/res/config.
+    /// toml).
+    config_path: Option<PathBuf>,
+    /// Feed id to reparse.
+    #[arg(long)]
+    feed:        String,
+    /// Oldest fetch to include
+    /// (YYYY-MM-DD or RFC 3339).
+    #[arg(long)]
+    since:       String
+  },
+  /// Convert an OPML subscription list
+  /// into a feed TOML file.
+  Opml {
+    /// OPML file to convert.
+    opml_path: PathBuf,
+    /// Category for every feed; must
+    /// be defined in categories.toml.
+    #[arg(long)]
+    category:  String,
+    /// Prefix for the generated feed
+    /// ids.
+    #[arg(long)]
+    id_prefix: Option<String>,
+    /// Write the file here instead of
+    /// stdout.
+    #[arg(long)]
+    out:       Option<PathBuf>
+  },
+  /// Show, apply or verify versioned
+  /// schema migrations.
+  Db {
+    #[command(subcommand)]
+    command: db::DbCommand
+  },
+  /// Inspect one feed's fetch history.
+  Feed {
+    #[command(subcommand)]
+    command: health::FeedCommand
+  },
+  /// Reports over all stored feeds.
+  Report {
+    #[command(subcommand)]
+    command: health::ReportCommand
   }
 }
 
@@ -112,6 +187,73 @@ async fn main() -> Result<(), String> {
         "ok: cleaned local artifacts"
       );
     }
+    | Command::Redirects {
+      config_path,
+      min_hits,
+      rewrite
+    } => {
+      let cfg_path =
+        pick_config_path(config_path);
+
+      let loaded =
+        ConfigLoader::load(&cfg_path)
+          .await
+          .map_err(|e| e.to_string())?;
+
+      redirects::run(
+        &cfg_path, loaded, min_hits,
+        rewrite
+      )
+      .await?;
+    }
+    | Command::Reparse {
+      config_path,
+      feed,
+      since
+    } => {
+      let cfg_path =
+        pick_config_path(config_path);
+
+      let loaded =
+        ConfigLoader::load(&cfg_path)
+          .await
+          .map_err(|e| e.to_string())?;
+
+      reparse::run(
+        loaded, &feed, &since
+      )
+      .await?;
+    }
+    | Command::Opml {
+      opml_path,
+      category,
+      id_prefix,
+      out
+    } => {
+      opml::run(
+        &opml_path,
+        &category,
+        id_prefix.as_deref(),
+        out.as_deref()
+      )
+      .await?;
+    }
+    | Command::Db {
+      command
+    } => {
+      db::run(command).await?;
+    }
+    | Command::Feed {
+      command
+    } => {
+      health::run_feed(command).await?;
+    }
+    | Command::Report {
+      command
+    } => {
+      health::run_report(command)
+        .await?;
+    }
   }
 
   Ok(())
@@ -0,0 +1,63 @@
+//! `opml` command: converts an OPML
+//! subscription list into a feed TOML
+//! file for the fetcher bundle.
+
+use std::path::Path;
+
+use pulsewire_core::feed::opml::parse_opml;
+use pulsewire_core::infra::config::opml_feeds_file;
+
+pub async fn run(
+  opml_path: &Path,
+  category: &str,
+  id_prefix: Option<&str>,
+  out: Option<&Path>
+) -> Result<(), String> {
+  let xml = tokio::fs::read_to_string(
+    opml_path
+  )
+  .await
+  .map_err(|e| {
+    format!(
+      "failed to read {}: {e}",
+      opml_path.display()
+    )
+  })?;
+
+  let outlines = parse_opml(&xml)?;
+
+  let file = opml_feeds_file(
+    &outlines, category, id_prefix
+  );
+
+  for reason in &file.skipped {
+    eprintln!("skipped {reason}");
+  }
+
+  match out {
+    | Some(path) => {
+      tokio::fs::write(
+        path,
+        &file.content
+      )
+      .await
+      .map_err(|e| {
+        format!(
+          "failed to write {}: {e}",
+          path.display()
+        )
+      })?;
+
+      println!(
+        "ok: wrote {} feed(s) to {} \
+         ({} skipped)",
+        file.feeds,
+        path.display(),
+        file.skipped.len()
+      );
+    }
+    | None => print!("{}", file.content)
+  }
+
+  Ok(())
+}
@@ -0,0 +1,152 @@
+//! `redirects` command: lists feeds
+//! with repeated permanent redirects or
+//! archived after 410 Gone, and can
+//! rewrite the feed TOML files.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use pulsewire_core::infra::config::{
+  ConfigLoader,
+  LoadedConfig,
+  rewrite_feed_urls
+};
+use pulsewire_core::infra::database::create_repo;
+use pulsewire_core::infra::time::format_epoch_ms;
+
+pub async fn run(
+  cfg_path: &Path,
+  loaded: LoadedConfig,
+  min_hits: i64,
+  rewrite: bool
+) -> Result<(), String> {
+  let app = loaded.app;
+
+  let repo =
+    create_repo(app.db_dialect, &app)
+      .await?;
+
+  repo
+    .migrate(
+      &app.timezone,
+      app.default_poll_seconds
+    )
+    .await?;
+
+  let redirects = repo
+    .feed_redirects(min_hits)
+    .await?;
+
+  let archived =
+    repo.archived_feeds().await?;
+
+  println!(
+    "redirected feeds ({}):",
+    redirects.len()
+  );
+
+  for row in &redirects {
+    println!(
+      "  {} [{} x{} since {}]\n    \
+       {}\n    -> {}",
+      row.feed_id,
+      row.status,
+      row.hit_count,
+      format_epoch_ms(
+        row.first_seen_at_ms,
+        &app.timezone
+      ),
+      row.url,
+      row.suggested_url
+    );
+  }
+
+  println!(
+    "archived feeds ({}):",
+    archived.len()
+  );
+
+  for row in &archived {
+    let at = row
+      .archived_at_ms
+      .map(|ms| {
+        format_epoch_ms(
+          ms,
+          &app.timezone
+        )
+      })
+      .unwrap_or_else(|| {
+        "-".to_string()
+      });
+
+    println!(
+      "  {} [410 at {}]\n    {}",
+      row.feed_id, at, row.url
+    );
+  }
+
+  if !rewrite || redirects.is_empty() {
+    return Ok(());
+  }
+
+  let moved: HashSet<String> =
+    redirects
+      .iter()
+      .map(|row| row.feed_id.clone())
+      .collect();
+
+  let changes: Vec<(String, String)> =
+    redirects
+      .into_iter()
+      .map(|row| {
+        (row.url, row.suggested_url)
+      })
+      .collect();
+
+  let files = rewrite_feed_urls(
+    &loaded.feeds_dir,
+    &changes
+  )
+  .await
+  .map_err(|e| e.to_string())?;
+
+  for file in &files {
+    println!(
+      "rewrote {}",
+      file.display()
+    );
+  }
+
+  // Store the new URLs now rather than
+  // at the next fetcher start, so the
+  // link state of moved feeds resets.
+  let reloaded =
+    ConfigLoader::load(cfg_path)
+      .await
+      .map_err(|e| e.to_string())?;
+
+  let feeds: Vec<_> = reloaded
+    .feeds
+    .into_iter()
+    .filter(|f| moved.contains(&f.id))
+    .collect();
+
+  let updated = feeds.len();
+
+  repo
+    .upsert_feeds_bulk(
+      feeds,
+      1000,
+      &app.timezone
+    )
+    .await?;
+
+  println!(
+    "ok: rewrote {} file(s) under {} \
+     and updated {updated} feed(s)",
+    files.len(),
+    loaded.feeds_dir.display()
+  );
+
+  Ok(())
+}
@@ -0,0 +1,52 @@
+//! `reparse` command: re-runs the feed
+//! parser over archived payload bodies
+//! and backfills missing items.
+
+use pulsewire_core::app::reparse::reparse_feed;
+use pulsewire_core::infra::config::LoadedConfig;
+use pulsewire_core::infra::database::create_repo;
+use pulsewire_core::infra::time::parse_date_ms;
+
+pub async fn run(
+  loaded: LoadedConfig,
+  feed_id: &str,
+  since: &str
+) -> Result<(), String> {
+  let app = loaded.app;
+
+  let since_ms = parse_date_ms(
+    since,
+    &app.timezone
+  )?;
+
+  let repo =
+    create_repo(app.db_dialect, &app)
+      .await?;
+
+  repo
+    .migrate(
+      &app.timezone,
+      app.default_poll_seconds
+    )
+    .await?;
+
+  let summary = reparse_feed(
+    repo.as_ref(),
+    &app,
+    feed_id,
+    since_ms
+  )
+  .await?;
+
+  println!(
+    "{feed_id}: {} archived body(s), \
+     {} missing, {} unparsable, {} \
+     item(s) added",
+    summary.bodies,
+    summary.missing,
+    summary.failed,
+    summary.inserted
+  );
+
+  Ok(())
+}
@@ -96,7 +96,8 @@ CREATE TABLE IF NOT EXISTS feed_items(
   published_at TIMESTAMPTZ NULL,
   category TEXT NULL,
   description TEXT NULL,
-  summary TEXT NULL
+  summary TEXT NULL,
+  diff TEXT NULL
 );
 
 CREATE INDEX IF NOT EXISTS idx_feed_items_payload ON feed_items(payload_id);
@@ -115,9 +116,26 @@ CREATE TABLE IF NOT EXISTS error_feeds(
 
 ALTER TABLE feed_state_current ADD COLUMN IF NOT EXISTS consecutive_error_count BIGINT NOT NULL DEFAULT 0;
 ALTER TABLE feed_state_history ADD COLUMN IF NOT EXISTS consecutive_error_count BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS diff TEXT NULL;
 
 CREATE TABLE IF NOT EXISTS source_cookies(
   feed_id TEXT PRIMARY KEY REFERENCES feeds(id) ON DELETE CASCADE,
   cookie_header TEXT NOT NULL,
   updated_at TIMESTAMPTZ NOT NULL
 );
+
+CREATE TABLE IF NOT EXISTS watch_snapshots(
+  feed_id TEXT PRIMARY KEY REFERENCES feeds(id) ON DELETE CASCADE,
+  content_text TEXT NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS feed_redirects(
+  feed_id TEXT PRIMARY KEY REFERENCES feeds(id) ON DELETE CASCADE,
+  from_url TEXT NOT NULL,
+  suggested_url TEXT NOT NULL,
+  status BIGINT NOT NULL,
+  hit_count BIGINT NOT NULL,
+  first_seen_at TIMESTAMPTZ NOT NULL,
+  last_seen_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS payload_archive(
+  content_hash TEXT PRIMARY KEY,
+  stored_at TIMESTAMPTZ NOT NULL,
+  size_bytes BIGINT NOT NULL,
+  data BYTEA NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_feed_payloads_content_hash ON feed_payloads(content_hash);
+CREATE INDEX IF NOT EXISTS idx_feed_payloads_feed_fetched ON feed_payloads(feed_id, fetched_at);
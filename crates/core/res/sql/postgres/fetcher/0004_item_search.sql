@@ -0,0 +1,37 @@
+ALTER TABLE feeds ADD COLUMN IF NOT EXISTS language TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS language TEXT NULL;
+
+CREATE OR REPLACE FUNCTION item_search_config(language TEXT)
+RETURNS regconfig
+LANGUAGE sql IMMUTABLE PARALLEL SAFE
+AS $$
+  SELECT CASE lower(split_part(split_part(coalesce(language, ''), '-', 1), '_', 1))
+    WHEN 'da' THEN 'pg_catalog.danish'
+    WHEN 'de' THEN 'pg_catalog.german'
+    WHEN 'en' THEN 'pg_catalog.english'
+    WHEN 'es' THEN 'pg_catalog.spanish'
+    WHEN 'fi' THEN 'pg_catalog.finnish'
+    WHEN 'fr' THEN 'pg_catalog.french'
+    WHEN 'hu' THEN 'pg_catalog.hungarian'
+    WHEN 'it' THEN 'pg_catalog.italian'
+    WHEN 'nb' THEN 'pg_catalog.norwegian'
+    WHEN 'nl' THEN 'pg_catalog.dutch'
+    WHEN 'nn' THEN 'pg_catalog.norwegian'
+    WHEN 'no' THEN 'pg_catalog.norwegian'
+    WHEN 'pt' THEN 'pg_catalog.portuguese'
+    WHEN 'ro' THEN 'pg_catalog.romanian'
+    WHEN 'ru' THEN 'pg_catalog.russian'
+    WHEN 'sv' THEN 'pg_catalog.swedish'
+    WHEN 'tr' THEN 'pg_catalog.turkish'
+    ELSE 'pg_catalog.simple'
+  END::regconfig
+$$;
+
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS search_vector tsvector
+  GENERATED ALWAYS AS (
+    setweight(to_tsvector(item_search_config(language), coalesce(title, '')), 'A')
+    || setweight(to_tsvector(item_search_config(language), coalesce(summary, '')), 'B')
+    || setweight(to_tsvector(item_search_config(language), coalesce(description, '')), 'C')
+  ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_feed_items_search ON feed_items USING GIN(search_vector);
@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS idx_feed_items_published ON feed_items((COALESCE(published_at, TIMESTAMPTZ 'epoch')), id);
+CREATE INDEX IF NOT EXISTS idx_feed_items_feed_published ON feed_items(feed_id, (COALESCE(published_at, TIMESTAMPTZ 'epoch')), id);
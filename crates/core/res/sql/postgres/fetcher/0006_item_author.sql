@@ -0,0 +1 @@
+ALTER TABLE feed_items ADD COLUMN IF NOT EXISTS author TEXT NULL;
@@ -0,0 +1,14 @@
+-- Feeds users added by URL through the server. The fetcher polls the
+-- approved ones alongside the feeds from its TOML config.
+CREATE TABLE IF NOT EXISTS feed_registry(
+  id TEXT PRIMARY KEY,
+  url TEXT NOT NULL UNIQUE,
+  title TEXT NULL,
+  added_by BIGINT NOT NULL,
+  status TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  reviewed_at TIMESTAMPTZ NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_feed_registry_status ON feed_registry(status);
+CREATE INDEX IF NOT EXISTS idx_feed_registry_added_by ON feed_registry(added_by);
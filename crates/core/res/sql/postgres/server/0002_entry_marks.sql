@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS entry_stars(
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  item_id BIGINT NOT NULL REFERENCES feed_items(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (user_id, item_id)
+);
+
+CREATE TABLE IF NOT EXISTS entry_queue(
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  item_id BIGINT NOT NULL REFERENCES feed_items(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (user_id, item_id)
+);
+
+CREATE TABLE IF NOT EXISTS labels(
+  id BIGSERIAL PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (user_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS entry_labels(
+  label_id BIGINT NOT NULL REFERENCES labels(id) ON DELETE CASCADE,
+  item_id BIGINT NOT NULL REFERENCES feed_items(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (label_id, item_id)
+);
+
+-- Retention checks stars by item.
+CREATE INDEX IF NOT EXISTS idx_entry_stars_item ON entry_stars(item_id);
+CREATE INDEX IF NOT EXISTS idx_entry_labels_item ON entry_labels(item_id);
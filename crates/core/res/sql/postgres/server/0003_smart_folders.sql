@@ -0,0 +1,12 @@
+-- A folder with a filter is a smart folder: its entries are picked by
+-- the filter expression rather than by folder_feeds.
+ALTER TABLE folders ADD COLUMN IF NOT EXISTS filter TEXT NULL;
+
+CREATE TABLE IF NOT EXISTS saved_searches(
+  id BIGSERIAL PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  query TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (user_id, name)
+);
@@ -0,0 +1,27 @@
+-- Filter rules act on entries matching a filter expression. Each rule
+-- has checked items up to checked_item_id, and newer items are checked
+-- when the user next reads their entries.
+CREATE TABLE IF NOT EXISTS filter_rules(
+  id BIGSERIAL PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  filter TEXT NOT NULL,
+  action TEXT NOT NULL,
+  label_id BIGINT NULL REFERENCES labels(id) ON DELETE CASCADE,
+  checked_item_id BIGINT NOT NULL DEFAULT 0,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (user_id, name)
+);
+
+-- Which rule fired for which entry. Hidden entries are the hide hits.
+CREATE TABLE IF NOT EXISTS rule_hits(
+  rule_id BIGINT NOT NULL REFERENCES filter_rules(id) ON DELETE CASCADE,
+  item_id BIGINT NOT NULL REFERENCES feed_items(id) ON DELETE CASCADE,
+  user_id BIGINT NOT NULL,
+  action TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (rule_id, item_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_rule_hits_user_item ON rule_hits(user_id, item_id);
+CREATE INDEX IF NOT EXISTS idx_rule_hits_item ON rule_hits(item_id);
@@ -0,0 +1,7 @@
+-- Per-user limits on feeds added by URL, overriding the configured
+-- default.
+CREATE TABLE IF NOT EXISTS feed_quotas(
+  user_id BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+  max_feeds BIGINT NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Fever API keys a user opted into. The key is the MD5 of
+-- username and password, so only its SHA-256 is kept.
+CREATE TABLE IF NOT EXISTS fever_keys(
+  user_id BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+  key_hash TEXT NOT NULL UNIQUE,
+  created_at TIMESTAMPTZ NOT NULL
+);
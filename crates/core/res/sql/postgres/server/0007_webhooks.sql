@@ -0,0 +1,40 @@
+-- Webhooks POST new entries of the user's subscriptions, narrowed by
+-- any of feed, folder, label and rule, to a URL. Like filter rules,
+-- each has checked items up to checked_item_id. A paused webhook
+-- queues nothing and its pending deliveries wait.
+CREATE TABLE IF NOT EXISTS webhooks(
+  id BIGSERIAL PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  url TEXT NOT NULL,
+  secret TEXT NOT NULL,
+  feed_id TEXT NULL,
+  folder_id BIGINT NULL REFERENCES folders(id) ON DELETE CASCADE,
+  label_id BIGINT NULL REFERENCES labels(id) ON DELETE CASCADE,
+  rule_id BIGINT NULL REFERENCES filter_rules(id) ON DELETE CASCADE,
+  checked_item_id BIGINT NOT NULL DEFAULT 0,
+  paused_at TIMESTAMPTZ NULL,
+  created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhooks_user ON webhooks(user_id);
+
+-- One event to send to a webhook and how sending it went. Pending
+-- deliveries are retried at next_attempt_at until delivered or, out of
+-- attempts, dead.
+CREATE TABLE IF NOT EXISTS webhook_deliveries(
+  id BIGSERIAL PRIMARY KEY,
+  webhook_id BIGINT NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+  event TEXT NOT NULL,
+  payload TEXT NOT NULL,
+  entry_count BIGINT NOT NULL,
+  status TEXT NOT NULL,
+  attempts BIGINT NOT NULL DEFAULT 0,
+  next_attempt_at TIMESTAMPTZ NOT NULL,
+  last_status BIGINT NULL,
+  last_error TEXT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  delivered_at TIMESTAMPTZ NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, id);
@@ -0,0 +1,17 @@
+-- A published feed serves one of the user's folders, labels, saved
+-- searches or their starred entries at /p/<token>/feed.{atom,rss,json}.
+-- source names which, and only the matching id is set. Only a hash of
+-- the token is kept, and deleting the row revokes the URLs.
+CREATE TABLE IF NOT EXISTS published_feeds(
+  id BIGSERIAL PRIMARY KEY,
+  user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  token_hash TEXT NOT NULL UNIQUE,
+  title TEXT NOT NULL,
+  source TEXT NOT NULL,
+  folder_id BIGINT NULL REFERENCES folders(id) ON DELETE CASCADE,
+  label_id BIGINT NULL REFERENCES labels(id) ON DELETE CASCADE,
+  search_id BIGINT NULL REFERENCES saved_searches(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_published_feeds_user ON published_feeds(user_id);
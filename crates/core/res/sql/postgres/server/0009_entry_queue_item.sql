@@ -0,0 +1,2 @@
+-- Retention checks the queue by item.
+CREATE INDEX IF NOT EXISTS idx_entry_queue_item ON entry_queue(item_id);
@@ -93,7 +93,8 @@ CREATE TABLE IF NOT EXISTS feed_items(
   published_at_ms INTEGER NULL,
   category TEXT NULL,
   description TEXT NULL,
-  summary TEXT NULL
+  summary TEXT NULL,
+  diff TEXT NULL
 );
 
 CREATE TABLE IF NOT EXISTS error_feeds(
@@ -115,3 +116,19 @@ CREATE TABLE IF NOT EXISTS source_cookies(
   cookie_header TEXT NOT NULL,
   updated_at_ms INTEGER NOT NULL
 );
+
+CREATE TABLE IF NOT EXISTS watch_snapshots(
+  feed_id TEXT PRIMARY KEY REFERENCES feeds(id) ON DELETE CASCADE,
+  content_text TEXT NOT NULL,
+  updated_at_ms INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS feed_redirects(
+  feed_id TEXT PRIMARY KEY REFERENCES feeds(id) ON DELETE CASCADE,
+  from_url TEXT NOT NULL,
+  suggested_url TEXT NOT NULL,
+  status INTEGER NOT NULL,
+  hit_count INTEGER NOT NULL,
+  first_seen_at_ms INTEGER NOT NULL,
+  last_seen_at_ms INTEGER NOT NULL
+);
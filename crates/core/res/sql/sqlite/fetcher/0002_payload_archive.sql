@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS payload_archive(
+  content_hash TEXT PRIMARY KEY,
+  stored_at_ms INTEGER NOT NULL,
+  size_bytes INTEGER NOT NULL,
+  data BLOB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_feed_payloads_content_hash ON feed_payloads(content_hash);
+CREATE INDEX IF NOT EXISTS idx_feed_payloads_feed_fetched ON feed_payloads(feed_id, fetched_at_ms);
@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS idx_fetch_events_feed_time ON fetch_events(feed_id, event_time_ms);
+CREATE INDEX IF NOT EXISTS idx_feed_state_history_feed_recorded ON feed_state_history(feed_id, recorded_at_ms);
@@ -0,0 +1,30 @@
+ALTER TABLE feeds ADD COLUMN language TEXT NULL;
+ALTER TABLE feed_items ADD COLUMN language TEXT NULL;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS feed_items_fts USING fts5(
+  title,
+  summary,
+  description,
+  content = 'feed_items',
+  content_rowid = 'id',
+  tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TRIGGER IF NOT EXISTS feed_items_fts_insert AFTER INSERT ON feed_items BEGIN
+  INSERT INTO feed_items_fts(rowid, title, summary, description)
+  VALUES (new.id, new.title, new.summary, new.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS feed_items_fts_delete AFTER DELETE ON feed_items BEGIN
+  INSERT INTO feed_items_fts(feed_items_fts, rowid, title, summary, description)
+  VALUES ('delete', old.id, old.title, old.summary, old.description);
+END;
+
+CREATE TRIGGER IF NOT EXISTS feed_items_fts_update AFTER UPDATE OF title, summary, description ON feed_items BEGIN
+  INSERT INTO feed_items_fts(feed_items_fts, rowid, title, summary, description)
+  VALUES ('delete', old.id, old.title, old.summary, old.description);
+  INSERT INTO feed_items_fts(rowid, title, summary, description)
+  VALUES (new.id, new.title, new.summary, new.description);
+END;
+
+INSERT INTO feed_items_fts(feed_items_fts) VALUES ('rebuild');
@@ -0,0 +1,16 @@
+-- Every archived body by feed and fetch time, whether or not it parsed, so
+-- reparsing does not depend on a payload row. Existing payloads seed it.
+CREATE TABLE IF NOT EXISTS payload_archive_index(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  feed_id TEXT NOT NULL REFERENCES feeds(id),
+  fetched_at_ms INTEGER NOT NULL,
+  content_hash TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_payload_archive_index_feed_fetched ON payload_archive_index(feed_id, fetched_at_ms);
+CREATE INDEX IF NOT EXISTS idx_payload_archive_index_content_hash ON payload_archive_index(content_hash);
+
+INSERT INTO payload_archive_index(feed_id, fetched_at_ms, content_hash)
+SELECT feed_id, fetched_at_ms, content_hash FROM feed_payloads
+WHERE content_hash IS NOT NULL
+ORDER BY id;
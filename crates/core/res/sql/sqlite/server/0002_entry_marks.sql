@@ -0,0 +1,39 @@
+CREATE TABLE IF NOT EXISTS entry_stars(
+  user_id INTEGER NOT NULL,
+  item_id INTEGER NOT NULL,
+  created_at TEXT NOT NULL,
+  PRIMARY KEY (user_id, item_id),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY (item_id) REFERENCES feed_items(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS entry_queue(
+  user_id INTEGER NOT NULL,
+  item_id INTEGER NOT NULL,
+  created_at TEXT NOT NULL,
+  PRIMARY KEY (user_id, item_id),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY (item_id) REFERENCES feed_items(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS labels(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  user_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  UNIQUE (user_id, name),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS entry_labels(
+  label_id INTEGER NOT NULL,
+  item_id INTEGER NOT NULL,
+  created_at TEXT NOT NULL,
+  PRIMARY KEY (label_id, item_id),
+  FOREIGN KEY (label_id) REFERENCES labels(id) ON DELETE CASCADE,
+  FOREIGN KEY (item_id) REFERENCES feed_items(id) ON DELETE CASCADE
+);
+
+-- Retention checks stars by item.
+CREATE INDEX IF NOT EXISTS idx_entry_stars_item ON entry_stars(item_id);
+CREATE INDEX IF NOT EXISTS idx_entry_labels_item ON entry_labels(item_id);
@@ -0,0 +1,13 @@
+-- A folder with a filter is a smart folder: its entries are picked by
+-- the filter expression rather than by folder_feeds.
+ALTER TABLE folders ADD COLUMN filter TEXT NULL;
+
+CREATE TABLE IF NOT EXISTS saved_searches(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  user_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  query TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  UNIQUE (user_id, name),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
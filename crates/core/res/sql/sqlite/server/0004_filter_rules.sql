@@ -0,0 +1,31 @@
+-- Filter rules act on entries matching a filter expression. Each rule
+-- has checked items up to checked_item_id, and newer items are checked
+-- when the user next reads their entries.
+CREATE TABLE IF NOT EXISTS filter_rules(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  user_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  filter TEXT NOT NULL,
+  action TEXT NOT NULL,
+  label_id INTEGER NULL,
+  checked_item_id INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL,
+  UNIQUE (user_id, name),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY (label_id) REFERENCES labels(id) ON DELETE CASCADE
+);
+
+-- Which rule fired for which entry. Hidden entries are the hide hits.
+CREATE TABLE IF NOT EXISTS rule_hits(
+  rule_id INTEGER NOT NULL,
+  item_id INTEGER NOT NULL,
+  user_id INTEGER NOT NULL,
+  action TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  PRIMARY KEY (rule_id, item_id),
+  FOREIGN KEY (rule_id) REFERENCES filter_rules(id) ON DELETE CASCADE,
+  FOREIGN KEY (item_id) REFERENCES feed_items(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_rule_hits_user_item ON rule_hits(user_id, item_id);
+CREATE INDEX IF NOT EXISTS idx_rule_hits_item ON rule_hits(item_id);
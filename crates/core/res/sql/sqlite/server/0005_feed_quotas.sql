@@ -0,0 +1,8 @@
+-- Per-user limits on feeds added by URL, overriding the configured
+-- default.
+CREATE TABLE IF NOT EXISTS feed_quotas(
+  user_id INTEGER PRIMARY KEY,
+  max_feeds INTEGER NOT NULL,
+  updated_at TEXT NOT NULL,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
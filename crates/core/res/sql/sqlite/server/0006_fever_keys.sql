@@ -0,0 +1,8 @@
+-- Fever API keys a user opted into. The key is the MD5 of
+-- username and password, so only its SHA-256 is kept.
+CREATE TABLE IF NOT EXISTS fever_keys(
+  user_id INTEGER PRIMARY KEY,
+  key_hash TEXT NOT NULL UNIQUE,
+  created_at TEXT NOT NULL,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
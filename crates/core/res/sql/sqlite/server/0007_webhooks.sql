@@ -0,0 +1,45 @@
+-- Webhooks POST new entries of the user's subscriptions, narrowed by
+-- any of feed, folder, label and rule, to a URL. Like filter rules,
+-- each has checked items up to checked_item_id. A paused webhook
+-- queues nothing and its pending deliveries wait.
+CREATE TABLE IF NOT EXISTS webhooks(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  user_id INTEGER NOT NULL,
+  url TEXT NOT NULL,
+  secret TEXT NOT NULL,
+  feed_id TEXT NULL,
+  folder_id INTEGER NULL,
+  label_id INTEGER NULL,
+  rule_id INTEGER NULL,
+  checked_item_id INTEGER NOT NULL DEFAULT 0,
+  paused_at TEXT NULL,
+  created_at TEXT NOT NULL,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY (folder_id) REFERENCES folders(id) ON DELETE CASCADE,
+  FOREIGN KEY (label_id) REFERENCES labels(id) ON DELETE CASCADE,
+  FOREIGN KEY (rule_id) REFERENCES filter_rules(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhooks_user ON webhooks(user_id);
+
+-- One event to send to a webhook and how sending it went. Pending
+-- deliveries are retried at next_attempt_at until delivered or, out of
+-- attempts, dead.
+CREATE TABLE IF NOT EXISTS webhook_deliveries(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  webhook_id INTEGER NOT NULL,
+  event TEXT NOT NULL,
+  payload TEXT NOT NULL,
+  entry_count INTEGER NOT NULL,
+  status TEXT NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  next_attempt_at TEXT NOT NULL,
+  last_status INTEGER NULL,
+  last_error TEXT NULL,
+  created_at TEXT NOT NULL,
+  delivered_at TEXT NULL,
+  FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, id);
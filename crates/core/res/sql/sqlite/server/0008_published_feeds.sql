@@ -0,0 +1,21 @@
+-- A published feed serves one of the user's folders, labels, saved
+-- searches or their starred entries at /p/<token>/feed.{atom,rss,json}.
+-- source names which, and only the matching id is set. Only a hash of
+-- the token is kept, and deleting the row revokes the URLs.
+CREATE TABLE IF NOT EXISTS published_feeds(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  user_id INTEGER NOT NULL,
+  token_hash TEXT NOT NULL UNIQUE,
+  title TEXT NOT NULL,
+  source TEXT NOT NULL,
+  folder_id INTEGER NULL,
+  label_id INTEGER NULL,
+  search_id INTEGER NULL,
+  created_at TEXT NOT NULL,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+  FOREIGN KEY (folder_id) REFERENCES folders(id) ON DELETE CASCADE,
+  FOREIGN KEY (label_id) REFERENCES labels(id) ON DELETE CASCADE,
+  FOREIGN KEY (search_id) REFERENCES saved_searches(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_published_feeds_user ON published_feeds(user_id);
@@ -0,0 +1,156 @@
+//! Scheduled backups: the fetcher
+//! writes one into `[backup].directory`
+//! once the newest there is
+//! `interval_seconds` old, then rotates
+//! the directory down to `keep`.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tracing::{
+  info,
+  warn
+};
+
+use crate::domain::model::AppConfig;
+use crate::infra::backup::{
+  BackupManifest,
+  backup_database,
+  backup_name,
+  list_backups,
+  read_manifest,
+  rotate_backups
+};
+use crate::ports::clock::Clock;
+
+/// Takes one backup and rotates old
+/// ones away. Returns the new backup
+/// and the removed directories.
+pub async fn run_once(
+  cfg: &AppConfig,
+  now_ms: i64
+) -> Result<
+  (
+    PathBuf,
+    BackupManifest,
+    Vec<PathBuf>
+  ),
+  String
+> {
+  let backup = &cfg.backup;
+
+  let dest = backup
+    .directory
+    .join(backup_name(now_ms));
+
+  let manifest = backup_database(
+    cfg,
+    &dest,
+    &backup.server_schema,
+    false,
+    backup.zstd_level,
+    now_ms
+  )
+  .await?;
+
+  let removed = rotate_backups(
+    &backup.directory,
+    backup.keep
+  )?;
+
+  Ok((dest, manifest, removed))
+}
+
+/// When the newest finished backup was
+/// taken, if any.
+fn last_backup_ms(
+  cfg: &AppConfig
+) -> Result<Option<i64>, String> {
+  list_backups(&cfg.backup.directory)?
+    .last()
+    .map(|dir| {
+      read_manifest(dir)
+        .map(|m| m.created_at_ms)
+    })
+    .transpose()
+}
+
+/// Backs up whenever one is due until
+/// the process exits; failures are
+/// logged and retried next interval.
+pub async fn run_forever<C>(
+  clock: Arc<C>,
+  cfg: Arc<AppConfig>
+) where
+  C: Clock + ?Sized + 'static
+{
+  let interval_ms =
+    (cfg.backup.interval_seconds
+      as i64)
+      .saturating_mul(1000);
+
+  loop {
+    let now_ms =
+      clock.now_epoch_ms().await;
+
+    let wait_ms =
+      match last_backup_ms(&cfg) {
+        | Ok(last) => {
+          last
+            .map(|last| {
+              last + interval_ms
+                - now_ms
+            })
+            .unwrap_or(0)
+        }
+        | Err(error) => {
+          warn!(
+            error = %error,
+            "Listing backups failed"
+          );
+
+          0
+        }
+      };
+
+    if wait_ms > 0 {
+      tokio::time::sleep(
+        Duration::from_millis(
+          wait_ms as u64
+        )
+      )
+      .await;
+
+      continue;
+    }
+
+    match run_once(&cfg, now_ms).await {
+      | Ok((
+        dest,
+        manifest,
+        removed
+      )) => {
+        info!(
+          path = %dest.display(),
+          rows = manifest.total_rows(),
+          removed = removed.len(),
+          "Backup written"
+        );
+      }
+      | Err(error) => {
+        warn!(
+            error = %error,
+            "Backup failed; retrying next interval"
+        );
+
+        tokio::time::sleep(
+          Duration::from_millis(
+            interval_ms as u64
+          )
+        )
+        .await;
+      }
+    }
+  }
+}
@@ -12,7 +12,7 @@ use crate::ports::repo::Repo;
 
 /// Bundles the runtime dependencies the
 /// scheduler needs (configuration,
-/// persistence, HTTP client, clock,
+/// persistence, HTTP clients, clock,
 /// randomness source, and watch
 /// metadata).
 pub struct AppContext<R, H, C, G>
@@ -25,6 +25,10 @@ where
   pub cfg: Arc<AppConfig>,
   pub repo:                Arc<R>,
   pub http:                Arc<H>,
+  /// Client for registry feeds, whose
+  /// URLs come from users: it refuses
+  /// internal destinations.
+  pub public_http:         Arc<H>,
   pub clock:               Arc<C>,
   pub rng:                 Arc<G>,
   pub watches_by_id:
@@ -58,6 +62,9 @@ where
       http:                Arc::clone(
         &self.http
       ),
+      public_http:         Arc::clone(
+        &self.public_http
+      ),
       clock:               Arc::clone(
         &self.clock
       ),
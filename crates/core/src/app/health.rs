@@ -0,0 +1,245 @@
+//! Feed health report built from the
+//! fetcher's own history: recent fetch
+//! events, stored payloads, the current
+//! state and the error list.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::ports::repo::{
+  ErrorFeedRow,
+  Repo
+};
+
+/// How much history one report looks
+/// at per feed.
+#[derive(Debug, Clone, Copy)]
+pub struct HealthWindow {
+  /// Newest fetch events considered
+  /// for success rate and latency.
+  pub events:   u64,
+  /// Newest payloads considered for
+  /// items per fetch.
+  pub payloads: u64
+}
+
+impl Default for HealthWindow {
+  fn default() -> Self {
+    Self {
+      events:   100,
+      payloads: 20
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedHealth {
+  pub feed_id:             String,
+  /// Events the rates below are
+  /// computed over.
+  pub events:              u64,
+  /// Share of events answered with a
+  /// 2xx or 3xx status.
+  pub success_rate:        Option<f64>,
+  pub median_latency_ms:   Option<i64>,
+  pub last_fetch_at_ms:    Option<i64>,
+  /// Fetch time of the newest payload
+  /// that added items.
+  pub last_new_item_at_ms: Option<i64>,
+  /// New items per stored payload.
+  pub avg_items_per_fetch: Option<f64>,
+  pub phase: Option<String>,
+  pub backoff_index:       Option<i64>,
+  pub consecutive_errors:  Option<i64>,
+  pub next_action_at_ms:   Option<i64>,
+  /// Parked after too many
+  /// consecutive errors.
+  pub parked:              bool,
+  pub last_error_kind: Option<String>,
+  pub last_error_status:   Option<i64>
+}
+
+pub async fn feed_health<R>(
+  repo: &R,
+  feed_id: &str,
+  window: HealthWindow
+) -> Result<FeedHealth, String>
+where
+  R: Repo + ?Sized
+{
+  let errors =
+    repo.error_feeds().await?;
+
+  let parked = errors
+    .into_iter()
+    .find(|e| e.feed_id == feed_id);
+
+  build(repo, feed_id, parked, window)
+    .await
+}
+
+/// Health of every stored feed,
+/// ordered by feed id.
+pub async fn health_report<R>(
+  repo: &R,
+  window: HealthWindow
+) -> Result<Vec<FeedHealth>, String>
+where
+  R: Repo + ?Sized
+{
+  let mut errors: HashMap<
+    String,
+    ErrorFeedRow
+  > = repo
+    .error_feeds()
+    .await?
+    .into_iter()
+    .map(|e| (e.feed_id.clone(), e))
+    .collect();
+
+  let mut feeds: Vec<String> = repo
+    .feed_categories()
+    .await?
+    .into_iter()
+    .map(|(feed_id, _)| feed_id)
+    .collect();
+
+  feeds.sort();
+
+  let mut report =
+    Vec::with_capacity(feeds.len());
+
+  for feed_id in feeds {
+    let parked =
+      errors.remove(&feed_id);
+
+    report.push(
+      build(
+        repo, &feed_id, parked, window
+      )
+      .await?
+    );
+  }
+
+  Ok(report)
+}
+
+async fn build<R>(
+  repo: &R,
+  feed_id: &str,
+  parked: Option<ErrorFeedRow>,
+  window: HealthWindow
+) -> Result<FeedHealth, String>
+where
+  R: Repo + ?Sized
+{
+  let events = repo
+    .recent_events(
+      feed_id,
+      window.events
+    )
+    .await?;
+
+  let payloads = repo
+    .payload_timeline(
+      feed_id,
+      window.payloads
+    )
+    .await?;
+
+  let state =
+    repo.latest_state(feed_id).await?;
+
+  let successes = events
+    .iter()
+    .filter(|e| {
+      e.status.is_some_and(|s| {
+        (200..400).contains(&s)
+      })
+    })
+    .count();
+
+  let mut latencies: Vec<i64> = events
+    .iter()
+    .filter_map(|e| e.latency_ms)
+    .collect();
+
+  let item_total: i64 = payloads
+    .iter()
+    .map(|p| p.item_count)
+    .sum();
+
+  Ok(FeedHealth {
+    feed_id:             feed_id
+      .to_string(),
+    events:              events.len()
+      as u64,
+    success_rate:        ratio(
+      successes as f64,
+      events.len()
+    ),
+    median_latency_ms:   median(
+      &mut latencies
+    ),
+    last_fetch_at_ms:    events
+      .first()
+      .map(|e| e.event_time_ms),
+    last_new_item_at_ms: payloads
+      .iter()
+      .find(|p| p.item_count > 0)
+      .map(|p| p.fetched_at_ms),
+    avg_items_per_fetch: ratio(
+      item_total as f64,
+      payloads.len()
+    ),
+    phase:               state
+      .as_ref()
+      .map(|s| s.phase.clone()),
+    backoff_index:       state
+      .as_ref()
+      .map(|s| s.backoff_index),
+    consecutive_errors:  state
+      .as_ref()
+      .map(|s| {
+        s.consecutive_error_count
+      }),
+    next_action_at_ms:   state
+      .as_ref()
+      .map(|s| s.next_action_at_ms),
+    parked:              parked
+      .is_some(),
+    last_error_kind:     parked
+      .as_ref()
+      .and_then(|e| {
+        e.last_error_kind.clone()
+      }),
+    last_error_status:   parked
+      .as_ref()
+      .and_then(|e| {
+        e.last_error_status
+      })
+  })
+}
+
+fn ratio(
+  part: f64,
+  total: usize
+) -> Option<f64> {
+  (total > 0)
+    .then(|| part / total as f64)
+}
+
+/// Lower median, so the value is one
+/// that was observed.
+fn median(
+  values: &mut [i64]
+) -> Option<i64> {
+  if values.is_empty() {
+    return None;
+  }
+
+  values.sort_unstable();
+
+  Some(values[(values.len() - 1) / 2])
+}
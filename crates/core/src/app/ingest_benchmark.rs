@@ -0,0 +1,276 @@
+//! Write side of `--ingest-benchmark`:
+//! stores a payload with items for
+//! every benchmark feed, then writes
+//! one fetch event and state snapshot
+//! per feed twice, once call by call
+//! and once through the write-behind
+//! batcher, with the scheduler's
+//! default parallelism.
+
+use std::sync::Arc;
+use std::time::{
+  Duration,
+  Instant
+};
+
+use futures::stream::{
+  self,
+  StreamExt,
+  TryStreamExt
+};
+
+use crate::domain::link_state::LinkState;
+use crate::domain::model::AppConfig;
+use crate::feed::parser::{
+  FeedItem,
+  FeedMetadata,
+  ParsedFeed
+};
+use crate::infra::write_behind::{
+  BatchStats,
+  WriteBehindRepo
+};
+use crate::ports::clock::Clock;
+use crate::ports::repo::Repo;
+
+const PARALLELISM: usize = 64;
+
+const ITEMS_PER_PAYLOAD: usize = 25;
+
+#[derive(Debug, Clone)]
+pub struct WriteBenchReport {
+  pub feeds:           usize,
+  pub items:           u64,
+  pub items_elapsed:   Duration,
+  /// Events plus snapshots written in
+  /// each of the two passes.
+  pub writes:          u64,
+  pub direct_elapsed:  Duration,
+  pub batched_elapsed: Duration,
+  /// Batches of the batched pass.
+  pub batches:         BatchStats
+}
+
+impl WriteBenchReport {
+  pub fn items_per_sec(&self) -> f64 {
+    per_sec(
+      self.items,
+      self.items_elapsed
+    )
+  }
+
+  pub fn direct_per_sec(&self) -> f64 {
+    per_sec(
+      self.writes,
+      self.direct_elapsed
+    )
+  }
+
+  pub fn batched_per_sec(&self) -> f64 {
+    per_sec(
+      self.writes,
+      self.batched_elapsed
+    )
+  }
+
+  /// Batched over direct throughput.
+  pub fn speedup(&self) -> f64 {
+    self.batched_per_sec()
+      / self
+        .direct_per_sec()
+        .max(f64::MIN_POSITIVE)
+  }
+}
+
+/// Runs the write passes against
+/// feeds that already exist in `repo`.
+pub async fn run(
+  repo: Arc<dyn Repo>,
+  clock: Arc<dyn Clock>,
+  cfg: &AppConfig,
+  feed_ids: &[String]
+) -> Result<WriteBenchReport, String> {
+  let now_ms =
+    clock.now_epoch_ms().await;
+
+  let started = Instant::now();
+
+  for_each_feed(feed_ids, |feed_id| {
+    let repo = repo.clone();
+
+    async move {
+      repo
+        .insert_payload_with_items(
+          &feed_id,
+          now_ms,
+          None,
+          None,
+          None,
+          &payload(&feed_id, now_ms),
+          &cfg.timezone
+        )
+        .await
+    }
+  })
+  .await?;
+
+  let items_elapsed = started.elapsed();
+
+  let direct_elapsed = write_pass(
+    repo.clone(),
+    cfg,
+    feed_ids,
+    now_ms
+  )
+  .await?;
+
+  let batched =
+    Arc::new(WriteBehindRepo::new(
+      repo,
+      &cfg.write_behind,
+      clock
+    ));
+
+  let batched_elapsed = write_pass(
+    batched.clone(),
+    cfg,
+    feed_ids,
+    now_ms
+  )
+  .await?;
+
+  Ok(WriteBenchReport {
+    feeds: feed_ids.len(),
+    items: (feed_ids.len()
+      * ITEMS_PER_PAYLOAD)
+      as u64,
+    items_elapsed,
+    writes: feed_ids.len() as u64 * 2,
+    direct_elapsed,
+    batched_elapsed,
+    batches: batched.stats()
+  })
+}
+
+/// One event then one snapshot per
+/// feed, as a HEAD or GET does.
+async fn write_pass(
+  repo: Arc<dyn Repo>,
+  cfg: &AppConfig,
+  feed_ids: &[String],
+  now_ms: i64
+) -> Result<Duration, String> {
+  let started = Instant::now();
+
+  for_each_feed(feed_ids, |feed_id| {
+    let repo = repo.clone();
+
+    async move {
+      let state = LinkState::initial(
+        feed_id.clone(),
+        cfg.default_poll_seconds,
+        cfg.max_poll_seconds,
+        cfg.jitter_fraction,
+        now_ms
+      );
+
+      repo
+        .insert_event(
+          &feed_id,
+          "GET",
+          Some(200),
+          None,
+          Some(120),
+          0,
+          state.next_action_at_ms,
+          None,
+          &cfg.timezone
+        )
+        .await?;
+
+      repo
+        .insert_state(
+          &state,
+          now_ms,
+          &cfg.timezone,
+          true
+        )
+        .await
+    }
+  })
+  .await?;
+
+  Ok(started.elapsed())
+}
+
+async fn for_each_feed<F, Fut>(
+  feed_ids: &[String],
+  task: F
+) -> Result<(), String>
+where
+  F: Fn(String) -> Fut,
+  Fut:
+    Future<Output = Result<(), String>>
+{
+  stream::iter(feed_ids.iter().cloned())
+    .map(|id| Ok::<_, String>(task(id)))
+    .try_buffer_unordered(PARALLELISM)
+    .try_collect::<()>()
+    .await
+}
+
+fn payload(
+  feed_id: &str,
+  now_ms: i64
+) -> ParsedFeed {
+  ParsedFeed {
+    metadata: FeedMetadata {
+      title:         Some(format!(
+        "Benchmark {feed_id}"
+      )),
+      link:          None,
+      description:   None,
+      language:      Some(
+        "en".to_string()
+      ),
+      updated_at_ms: Some(now_ms)
+    },
+    items:    (0..ITEMS_PER_PAYLOAD)
+      .map(|i| {
+        FeedItem {
+          title:           Some(format!(
+            "Item {i}"
+          )),
+          link:            Some(format!(
+            "https://example.org/{feed_id}/{i}"
+          )),
+          guid:            Some(format!(
+            "{feed_id}-{i}"
+          )),
+          published_at_ms: Some(
+            now_ms - i as i64 * 60_000
+          ),
+          category:        None,
+          author:          None,
+          description:     Some(
+            "Benchmark item\twith a tab \
+             and\na newline"
+              .to_string()
+          ),
+          summary:         None,
+          diff:            None
+        }
+      })
+      .collect()
+  }
+}
+
+fn per_sec(
+  count: u64,
+  elapsed: Duration
+) -> f64 {
+  count as f64
+    / elapsed
+      .as_secs_f64()
+      .max(f64::MIN_POSITIVE)
+}
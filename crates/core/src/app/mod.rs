@@ -1,5 +1,16 @@
-//! Application layer wiring and the
-//! scheduler loop.
+//! Application layer wiring, the
+//! scheduler loop, the retention,
+//! backup and feed registry jobs,
+//! archive reparsing, the feed health
+//! report, the ingest write
+//! benchmark and webhook delivery.
 
+pub mod backup;
 pub mod context;
+pub mod health;
+pub mod ingest_benchmark;
+pub mod registry;
+pub mod reparse;
+pub mod retention;
 pub mod scheduler;
+pub mod webhook;
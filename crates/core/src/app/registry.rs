@@ -0,0 +1,289 @@
+//! Feed registry: discovers and
+//! validates feeds users add by URL,
+//! and keeps the approved ones in the
+//! fetcher's schedule.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+use tracing::{
+  info,
+  warn
+};
+
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig
+};
+use crate::domain::registry::{
+  RegistryFeed,
+  RegistryStatus
+};
+use crate::feed::discovery::feed_links;
+use crate::feed::parser;
+use crate::ports::http::Http;
+use crate::ports::repo::Repo;
+
+/// Feed links tried on a page before
+/// giving up.
+const MAX_CANDIDATES: usize = 5;
+
+/// The only error a failed fetch
+/// reports.
+const FETCH_FAILED: &str =
+  "could not fetch feed";
+
+/// A feed found and parsed at a
+/// user-given URL.
+#[derive(Debug, Clone)]
+pub struct DiscoveredFeed {
+  pub url:   String,
+  pub title: Option<String>
+}
+
+/// Fetches `url` once and returns the
+/// feed it serves, or failing that the
+/// first working feed its HTML links
+/// to.
+pub async fn discover<H>(
+  http: &H,
+  url: &str
+) -> Result<DiscoveredFeed, String>
+where
+  H: Http + ?Sized
+{
+  let (page_url, body) =
+    fetch(http, url).await?;
+
+  if let Ok(feed) = parser::parse(&body)
+  {
+    return Ok(DiscoveredFeed {
+      url:   url.to_string(),
+      title: feed.metadata.title
+    });
+  }
+
+  let links = feed_links(
+    &String::from_utf8_lossy(&body),
+    &page_url
+  );
+
+  if links.is_empty() {
+    return Err(format!(
+      "no feed found at '{url}'"
+    ));
+  }
+
+  for link in links
+    .into_iter()
+    .take(MAX_CANDIDATES)
+  {
+    let Ok((_, body)) =
+      fetch(http, &link).await
+    else {
+      continue;
+    };
+
+    if let Ok(feed) =
+      parser::parse(&body)
+    {
+      return Ok(DiscoveredFeed {
+        url:   link,
+        title: feed.metadata.title
+      });
+    }
+  }
+
+  Err(format!(
+    "no valid feed found at '{url}'"
+  ))
+}
+
+/// GETs `url`, returning the URL that
+/// answered and the body.
+async fn fetch<H>(
+  http: &H,
+  url: &str
+) -> Result<(String, Vec<u8>), String>
+where
+  H: Http + ?Sized
+{
+  let domain = url_domain(url)
+    .ok_or_else(|| {
+      format!(
+        "invalid feed url '{url}'"
+      )
+    })?;
+
+  let res = http
+    .get(&domain, url, None, None)
+    .await;
+
+  // Upstream details stay in the log;
+  // the URL is the user's, but what it
+  // answered is not theirs to probe.
+  match (
+    res.error, res.status, res.body
+  ) {
+    | (
+      None,
+      Some(200..=299),
+      Some(body)
+    ) => {
+      Ok((
+        res.final_url.unwrap_or_else(
+          || url.to_string()
+        ),
+        body
+      ))
+    }
+    | (error, status, _) => {
+      warn!(
+        url,
+        ?error,
+        ?status,
+        "registry fetch failed"
+      );
+
+      Err(FETCH_FAILED.to_string())
+    }
+  }
+}
+
+/// Host of an http(s) URL.
+pub fn url_domain(
+  url: &str
+) -> Option<String> {
+  let parsed =
+    reqwest::Url::parse(url).ok()?;
+
+  if !matches!(
+    parsed.scheme(),
+    "http" | "https"
+  ) {
+    return None;
+  }
+
+  parsed
+    .host_str()
+    .map(|h| h.to_ascii_lowercase())
+}
+
+/// The feed definition polled for a
+/// registry feed.
+pub fn feed_config(
+  feed: &RegistryFeed,
+  category: &str,
+  base_poll_seconds: u64
+) -> Option<FeedConfig> {
+  Some(FeedConfig {
+    id: feed.id.clone(),
+    url: feed.url.clone(),
+    domain: url_domain(&feed.url)?,
+    category: category.to_string(),
+    base_poll_seconds,
+    provenance: Some(
+      "registry".to_string()
+    ),
+    tags: None,
+    language: None,
+    content_type: None,
+    cookie_path: None,
+    headers_path: None,
+    headers: None
+  })
+}
+
+/// Adds approved registry feeds the
+/// fetcher does not know yet to its
+/// feeds, returning how many. Feeds
+/// already there keep their settings.
+pub async fn sync_once<R>(
+  repo: &R,
+  cfg: &AppConfig
+) -> Result<usize, String>
+where
+  R: Repo + ?Sized
+{
+  let known: HashSet<String> = repo
+    .feed_categories()
+    .await?
+    .into_iter()
+    .map(|(id, _)| id)
+    .collect();
+
+  let feeds: Vec<FeedConfig> = repo
+    .registry_feeds(Some(
+      RegistryStatus::Approved
+    ))
+    .await?
+    .iter()
+    .filter(|f| !known.contains(&f.id))
+    .filter_map(|f| {
+      feed_config(
+        f,
+        &cfg.registry.category,
+        cfg.default_poll_seconds
+      )
+    })
+    .collect();
+
+  let count = feeds.len();
+
+  if count > 0 {
+    repo
+      .upsert_feeds_bulk(
+        feeds,
+        1_000,
+        &cfg.timezone
+      )
+      .await?;
+  }
+
+  Ok(count)
+}
+
+/// Re-reads the registry every
+/// `registry.interval_seconds` so no
+/// approved feed is left unpolled.
+pub async fn run_forever<R>(
+  repo: Arc<R>,
+  cfg: Arc<AppConfig>
+) where
+  R: Repo + ?Sized + 'static
+{
+  let mut interval =
+    tokio::time::interval(
+      Duration::from_secs(
+        cfg.registry.interval_seconds
+      )
+    );
+
+  interval.set_missed_tick_behavior(
+    MissedTickBehavior::Delay
+  );
+
+  loop {
+    interval.tick().await;
+
+    match sync_once(repo.as_ref(), &cfg)
+      .await
+    {
+      | Ok(0) => {}
+      | Ok(count) => {
+        info!(
+          feeds = count,
+          "Registry feeds added"
+        );
+      }
+      | Err(error) => {
+        warn!(
+            error = %error,
+            "Registry sync failed; retrying next interval"
+        );
+      }
+    }
+  }
+}
@@ -0,0 +1,263 @@
+//! Retention job: prunes fetcher
+//! history per feed in bounded batches
+//! so the scheduler keeps its database
+//! time.
+
+use std::sync::Arc;
+use std::time::{
+  Duration,
+  Instant
+};
+
+use tokio::time::MissedTickBehavior;
+use tracing::{
+  info,
+  warn
+};
+
+use crate::domain::model::AppConfig;
+use crate::domain::retention::{
+  RetentionConfig,
+  RetentionLimit,
+  RetentionTable
+};
+use crate::infra::archive::PayloadArchive;
+use crate::infra::metrics;
+use crate::ports::clock::Clock;
+use crate::ports::repo::Repo;
+
+/// Rows pruned (or, for a dry run,
+/// prunable) per table across all
+/// feeds.
+pub async fn prune_all<R>(
+  repo: &R,
+  retention: &RetentionConfig,
+  now_ms: i64,
+  dry_run: bool
+) -> Result<
+  Vec<(RetentionTable, u64)>,
+  String
+>
+where
+  R: Repo + ?Sized
+{
+  let feeds =
+    repo.feed_categories().await?;
+
+  let mut totals: Vec<(
+    RetentionTable,
+    u64
+  )> = RetentionTable::ALL
+    .iter()
+    .map(|t| (*t, 0))
+    .collect();
+
+  for (table, total) in &mut totals {
+    for (feed_id, category) in &feeds {
+      let limit = retention.limit_for(
+        feed_id, category, *table
+      );
+
+      if limit.is_unbounded() {
+        continue;
+      }
+
+      *total += if dry_run {
+        repo
+          .count_prunable(
+            *table,
+            feed_id,
+            limit,
+            now_ms,
+            &retention.server_schema
+          )
+          .await?
+      } else {
+        prune_feed(
+          repo, retention, *table,
+          feed_id, limit, now_ms
+        )
+        .await?
+      };
+    }
+  }
+
+  Ok(totals)
+}
+
+async fn prune_feed<R>(
+  repo: &R,
+  retention: &RetentionConfig,
+  table: RetentionTable,
+  feed_id: &str,
+  limit: RetentionLimit,
+  now_ms: i64
+) -> Result<u64, String>
+where
+  R: Repo + ?Sized
+{
+  let mut deleted = 0;
+
+  loop {
+    let started = Instant::now();
+
+    let rows = repo
+      .prune_batch(
+        table,
+        feed_id,
+        limit,
+        now_ms,
+        retention.batch_size,
+        &retention.server_schema
+      )
+      .await?;
+
+    metrics::record_db_time(
+      "prune_batch",
+      started.elapsed().as_millis()
+        as u64
+    );
+
+    metrics::record_pruned(
+      table.as_str(),
+      rows
+    );
+
+    deleted += rows;
+
+    if rows < retention.batch_size {
+      return Ok(deleted);
+    }
+
+    // Let fetch work interleave with
+    // a long prune.
+    tokio::task::yield_now().await;
+  }
+}
+
+/// Removes archived payload bodies no
+/// payload row references any more, so
+/// the archive follows the payload
+/// limits. `None` when the archive is
+/// disabled.
+pub async fn prune_archive<R>(
+  repo: &R,
+  cfg: &AppConfig,
+  dry_run: bool
+) -> Result<Option<u64>, String>
+where
+  R: Repo + ?Sized
+{
+  let Some(archive) =
+    PayloadArchive::from_config(
+      &cfg.archive
+    )
+  else {
+    return Ok(None);
+  };
+
+  let started = Instant::now();
+
+  let pruned = archive
+    .prune_orphans(
+      repo,
+      cfg.retention.batch_size,
+      dry_run
+    )
+    .await?;
+
+  metrics::record_db_time(
+    "prune_archive",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  if !dry_run {
+    metrics::record_pruned(
+      "payload_archive",
+      pruned
+    );
+  }
+
+  Ok(Some(pruned))
+}
+
+/// Prunes every `interval_seconds`
+/// until the process exits; failures
+/// are logged and retried next round.
+pub async fn run_forever<R, C>(
+  repo: Arc<R>,
+  clock: Arc<C>,
+  cfg: Arc<AppConfig>
+) where
+  R: Repo + ?Sized + 'static,
+  C: Clock + ?Sized + 'static
+{
+  let retention = &cfg.retention;
+
+  let mut interval =
+    tokio::time::interval(
+      Duration::from_secs(
+        retention.interval_seconds
+      )
+    );
+
+  interval.set_missed_tick_behavior(
+    MissedTickBehavior::Delay
+  );
+
+  loop {
+    interval.tick().await;
+
+    let now_ms =
+      clock.now_epoch_ms().await;
+
+    let pruned = match prune_all(
+      repo.as_ref(),
+      retention,
+      now_ms,
+      false
+    )
+    .await
+    {
+      | Ok(totals) => {
+        prune_archive(
+          repo.as_ref(),
+          &cfg,
+          false
+        )
+        .await
+        .map(|archived| {
+          (totals, archived)
+        })
+      }
+      | Err(error) => Err(error)
+    };
+
+    match pruned {
+      | Ok((totals, archived)) => {
+        let deleted: u64 = totals
+          .iter()
+          .map(|(_, n)| n)
+          .sum();
+
+        let archived =
+          archived.unwrap_or(0);
+
+        if deleted > 0 || archived > 0 {
+          info!(
+            deleted = deleted,
+            archived_blobs = archived,
+            "Retention pruned rows"
+          );
+        }
+      }
+      | Err(error) => {
+        warn!(
+            error = %error,
+            "Retention run failed; retrying next interval"
+        );
+      }
+    }
+  }
+}
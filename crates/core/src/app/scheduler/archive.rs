@@ -0,0 +1,77 @@
+//! Hands fetched bodies to the payload
+//! archive. Failures are logged, never
+//! fatal to the fetch.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::warn;
+
+use crate::domain::archive::ArchivedResponse;
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig,
+  GetResult
+};
+use crate::infra::archive::PayloadArchive;
+use crate::infra::metrics;
+use crate::ports::repo::Repo;
+
+/// Stores the raw response and indexes
+/// it under the feed and fetch time.
+pub async fn archive_body<R>(
+  cfg: &AppConfig,
+  repo: &Arc<R>,
+  feed: &FeedConfig,
+  res: &GetResult,
+  body: &[u8],
+  body_hash: Option<&str>,
+  now_ms: i64
+) where
+  R: Repo + ?Sized
+{
+  let (Some(archive), Some(hash)) = (
+    PayloadArchive::from_config(
+      &cfg.archive
+    ),
+    body_hash
+  ) else {
+    return;
+  };
+
+  let response = ArchivedResponse {
+    headers: res.headers.clone(),
+    body:    body.to_vec()
+  };
+
+  let started = Instant::now();
+
+  let stored = match archive
+    .put(
+      repo.as_ref(),
+      hash,
+      &response,
+      now_ms
+    )
+    .await
+  {
+    | Ok(_) => {
+      repo
+        .insert_archive_ref(
+          &feed.id, now_ms, hash
+        )
+        .await
+    }
+    | Err(e) => Err(e)
+  };
+
+  metrics::record_db_time(
+    "archive_put",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  if let Err(e) = stored {
+    warn!(feed_id = %feed.id, error = %e, "Failed to archive payload");
+  }
+}
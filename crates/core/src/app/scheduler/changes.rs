@@ -0,0 +1,88 @@
+//! Text snapshots for `any_change`
+//! watches: keeps the last normalized
+//! page text and diffs each new fetch
+//! against it.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::extract;
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig,
+  WatchConfig
+};
+use crate::domain::text_diff::line_diff;
+use crate::infra::metrics;
+use crate::ports::repo::Repo;
+
+/// Stores the page's text snapshot and
+/// returns the diff against the
+/// previous one. The first snapshot of
+/// a watch has nothing to diff against.
+pub(super) async fn watch_change_diff<
+  R
+>(
+  cfg: &AppConfig,
+  repo: &Arc<R>,
+  feed: &FeedConfig,
+  watch: &WatchConfig,
+  body: &[u8],
+  now_ms: i64
+) -> Result<Option<String>, String>
+where
+  R: Repo + ?Sized
+{
+  let current =
+    extract::snapshot_text(watch, body);
+
+  let started = Instant::now();
+
+  let previous = repo
+    .latest_watch_snapshot(&feed.id)
+    .await;
+
+  metrics::record_db_time(
+    "latest_watch_snapshot",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  let previous = previous?;
+
+  if previous.as_deref()
+    == Some(current.as_str())
+  {
+    return Ok(None);
+  }
+
+  let started = Instant::now();
+
+  let upsert_res = repo
+    .upsert_watch_snapshot(
+      &feed.id,
+      &current,
+      now_ms,
+      &cfg.timezone
+    )
+    .await;
+
+  metrics::record_db_time(
+    "upsert_watch_snapshot",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  upsert_res?;
+
+  Ok(previous.and_then(|previous| {
+    line_diff(
+      &previous,
+      &current,
+      usize::try_from(
+        watch.max_diff_bytes
+      )
+      .unwrap_or(usize::MAX)
+    )
+  }))
+}
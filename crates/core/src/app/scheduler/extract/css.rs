@@ -0,0 +1,269 @@
+use regex::Regex;
+use scraper::{
+  ElementRef,
+  Html,
+  Selector
+};
+
+use super::{
+  RawItem,
+  clean_value,
+  pattern
+};
+use crate::domain::model::{
+  WatchConfig,
+  WatchExtractorKind,
+  WatchItemIdentity
+};
+
+/// A per-field rule evaluated against
+/// a selected item element.
+enum FieldRule {
+  Css(Selector),
+  Regex(Regex)
+}
+
+pub(super) fn extract(
+  watch: &WatchConfig,
+  body: &[u8]
+) -> Vec<RawItem> {
+  let Some(item_selector) = watch
+    .item_selector
+    .as_deref()
+    .and_then(parse_selector)
+  else {
+    return Vec::new();
+  };
+
+  let title_rule = field_rule(
+    watch.title_selector.as_deref(),
+    watch.title_extractor
+  );
+
+  let link_rule = field_rule(
+    watch.link_selector.as_deref(),
+    watch.link_extractor
+  );
+
+  let summary_rule = field_rule(
+    watch.summary_selector.as_deref(),
+    watch.summary_extractor
+  );
+
+  let published_rule = field_rule(
+    watch.published_selector.as_deref(),
+    watch.published_extractor
+  );
+
+  let html =
+    String::from_utf8_lossy(body);
+  let document =
+    Html::parse_document(&html);
+
+  document
+    .select(&item_selector)
+    .map(|node| {
+      RawItem {
+        title:     extract_text(
+          &node,
+          title_rule.as_ref(),
+          "title",
+          watch
+        ),
+        link:      extract_link(
+          &node,
+          link_rule.as_ref(),
+          watch
+        ),
+        summary:   extract_text(
+          &node,
+          summary_rule.as_ref(),
+          "summary",
+          watch
+        ),
+        published: extract_published(
+          &node,
+          published_rule.as_ref(),
+          watch
+        ),
+        id:        identity_attr(
+          &node, watch
+        ),
+        text:      text_of(&node)
+      }
+    })
+    .collect()
+}
+
+fn field_rule(
+  selector: Option<&str>,
+  kind: Option<WatchExtractorKind>
+) -> Option<FieldRule> {
+  let selector = selector?;
+
+  match kind
+    .unwrap_or(WatchExtractorKind::Css)
+  {
+    | WatchExtractorKind::Css => {
+      parse_selector(selector)
+        .map(FieldRule::Css)
+    }
+    | WatchExtractorKind::Regex => {
+      Regex::new(selector)
+        .ok()
+        .map(FieldRule::Regex)
+    }
+    | WatchExtractorKind::Xpath => None
+  }
+}
+
+fn parse_selector(
+  raw: &str
+) -> Option<Selector> {
+  Selector::parse(raw).ok()
+}
+
+fn extract_text(
+  node: &ElementRef<'_>,
+  rule: Option<&FieldRule>,
+  field: &str,
+  watch: &WatchConfig
+) -> Option<String> {
+  match rule? {
+    | FieldRule::Css(selector) => {
+      let element =
+        node.select(selector).next()?;
+
+      clean_value(
+        &text_of(&element),
+        watch.normalize_whitespace
+      )
+    }
+    | FieldRule::Regex(regex) => {
+      pattern::capture_field(
+        regex,
+        field,
+        &node.html(),
+        watch.normalize_whitespace
+      )
+    }
+  }
+}
+
+fn text_of(
+  node: &ElementRef<'_>
+) -> String {
+  node
+    .text()
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn extract_link(
+  node: &ElementRef<'_>,
+  rule: Option<&FieldRule>,
+  watch: &WatchConfig
+) -> Option<String> {
+  let raw = match rule {
+    | Some(FieldRule::Css(sel)) => {
+      node
+        .select(sel)
+        .next()
+        .and_then(|n| {
+          n.value().attr("href")
+        })
+        .map(str::to_string)
+    }
+    | Some(FieldRule::Regex(regex)) => {
+      pattern::capture_field(
+        regex,
+        "link",
+        &node.html(),
+        watch.normalize_whitespace
+      )
+    }
+    | None => None
+  };
+
+  raw.or_else(|| {
+    node
+      .value()
+      .attr("href")
+      .map(str::to_string)
+  })
+}
+
+fn extract_published(
+  node: &ElementRef<'_>,
+  rule: Option<&FieldRule>,
+  watch: &WatchConfig
+) -> Option<String> {
+  match rule? {
+    | FieldRule::Css(selector) => {
+      let elem =
+        node.select(selector).next()?;
+
+      elem
+        .value()
+        .attr("datetime")
+        .map(str::to_string)
+        .or_else(|| {
+          clean_value(
+            &text_of(&elem),
+            watch.normalize_whitespace
+          )
+        })
+    }
+    | FieldRule::Regex(regex) => {
+      pattern::capture_field(
+        regex,
+        "published",
+        &node.html(),
+        watch.normalize_whitespace
+      )
+    }
+  }
+}
+
+fn identity_attr(
+  node: &ElementRef<'_>,
+  watch: &WatchConfig
+) -> Option<String> {
+  if watch.item_identity
+    != Some(WatchItemIdentity::Attr)
+  {
+    return None;
+  }
+
+  let attr = watch
+    .item_identity_attr
+    .as_deref()?;
+
+  node
+    .value()
+    .attr(attr)
+    .map(str::to_string)
+}
+
+pub(super) fn next_page(
+  selector: &str,
+  body: &[u8]
+) -> Option<String> {
+  let selector =
+    parse_selector(selector)?;
+  let html =
+    String::from_utf8_lossy(body);
+  let document =
+    Html::parse_document(&html);
+
+  let element = document
+    .select(&selector)
+    .next()?;
+
+  element
+    .value()
+    .attr("href")
+    .map(str::to_string)
+}
@@ -0,0 +1,258 @@
+//! Watch item extraction: turns a
+//! fetched page into synthetic feed
+//! items using the CSS, XPath or regex
+//! extractor configured on the watch,
+//! and text snapshots for diffing.
+
+mod css;
+mod pattern;
+mod snapshot;
+mod xpath;
+
+use std::collections::HashSet;
+
+pub(super) use snapshot::snapshot_text;
+
+use crate::domain::model::{
+  FeedConfig,
+  WatchConfig,
+  WatchExtractorKind,
+  WatchItemIdentity
+};
+use crate::feed::parser::FeedItem;
+
+/// Raw field values pulled out of one
+/// item node before normalization.
+#[derive(Debug, Default)]
+struct RawItem {
+  title:     Option<String>,
+  link:      Option<String>,
+  summary:   Option<String>,
+  published: Option<String>,
+  id:        Option<String>,
+  text:      String
+}
+
+pub(super) fn extract_watch_items(
+  feed: &FeedConfig,
+  watch: &WatchConfig,
+  body: &[u8],
+  now_ms: i64
+) -> Vec<FeedItem> {
+  let raw_items = match watch.extractor
+  {
+    | WatchExtractorKind::Css => {
+      css::extract(watch, body)
+    }
+    | WatchExtractorKind::Xpath => {
+      xpath::extract(watch, body)
+    }
+    | WatchExtractorKind::Regex => {
+      pattern::extract(watch, body)
+    }
+  };
+
+  let max_items = watch
+    .max_items_per_fetch
+    .unwrap_or(usize::MAX as u64)
+    as usize;
+
+  let mut seen = HashSet::new();
+  let mut items = Vec::new();
+
+  for raw in raw_items {
+    if items.len() >= max_items {
+      break;
+    }
+
+    let link = raw.link.map(|href| {
+      absolutize_link(
+        href,
+        &feed.url,
+        watch.strip_query_params
+      )
+    });
+
+    let title =
+      raw.title.or_else(|| {
+        non_empty(raw.text.clone())
+      });
+
+    let published_at_ms = raw
+      .published
+      .as_deref()
+      .and_then(|value| {
+        parse_published_at_ms(
+          value,
+          watch
+            .published_format
+            .as_deref()
+        )
+      })
+      .or(Some(now_ms));
+
+    let Some(identity) =
+      resolve_identity(
+        watch,
+        raw.id.as_deref(),
+        link.as_deref(),
+        title.as_deref(),
+        &raw.text
+      )
+    else {
+      continue;
+    };
+
+    if !seen.insert(identity.clone()) {
+      continue;
+    }
+
+    items.push(FeedItem {
+      title,
+      link,
+      guid: Some(format!(
+        "{}:{}",
+        feed.id, identity
+      )),
+      published_at_ms,
+      category: Some(
+        feed.category.clone()
+      ),
+      author: None,
+      description: raw.summary.clone(),
+      summary: raw.summary,
+      diff: None
+    });
+  }
+
+  items
+}
+
+/// Resolves the watch's next page link
+/// on `body` against the URL the page
+/// was fetched from.
+pub(super) fn extract_next_page(
+  watch: &WatchConfig,
+  body: &[u8],
+  page_url: &str
+) -> Option<String> {
+  let selector = watch
+    .next_page_selector
+    .as_deref()?;
+
+  let href = match watch.extractor {
+    | WatchExtractorKind::Css => {
+      css::next_page(selector, body)
+    }
+    | WatchExtractorKind::Xpath => {
+      xpath::next_page(selector, body)
+    }
+    | WatchExtractorKind::Regex => {
+      pattern::next_page(selector, body)
+    }
+  }?;
+
+  reqwest::Url::parse(page_url)
+    .ok()?
+    .join(href.trim())
+    .ok()
+    .map(String::from)
+}
+
+fn resolve_identity(
+  watch: &WatchConfig,
+  id: Option<&str>,
+  link: Option<&str>,
+  title: Option<&str>,
+  text: &str
+) -> Option<String> {
+  match watch.item_identity {
+    | Some(WatchItemIdentity::Href) => {
+      link.map(str::to_string)
+    }
+    | Some(WatchItemIdentity::Text) => {
+      title.map(str::to_string).or_else(
+        || non_empty(text.to_string())
+      )
+    }
+    | Some(WatchItemIdentity::Attr) => {
+      id.map(str::to_string)
+    }
+    | None => {
+      id.or(link)
+        .or(title)
+        .map(str::to_string)
+    }
+  }
+}
+
+fn absolutize_link(
+  mut href: String,
+  base_url: &str,
+  strip_query_params: bool
+) -> String {
+  if href.starts_with('/') {
+    let base =
+      base_url.trim_end_matches('/');
+    href = format!("{}{}", base, href);
+  }
+
+  if strip_query_params {
+    href = href
+      .split('?')
+      .next()
+      .unwrap_or(&href)
+      .to_string();
+  }
+
+  href
+}
+
+fn parse_published_at_ms(
+  raw: &str,
+  published_format: Option<&str>
+) -> Option<i64> {
+  if let Some(fmt) = published_format
+    && let Ok(dt) =
+      chrono::DateTime::parse_from_str(
+        raw, fmt
+      )
+  {
+    return Some(dt.timestamp_millis());
+  }
+
+  chrono::DateTime::parse_from_rfc3339(
+    raw
+  )
+  .map(|dt| dt.timestamp_millis())
+  .ok()
+}
+
+/// Trims a value and, when the watch
+/// asks for it, collapses inner
+/// whitespace runs to single spaces.
+fn clean_value(
+  raw: &str,
+  normalize_whitespace: bool
+) -> Option<String> {
+  let value = if normalize_whitespace {
+    raw
+      .split_whitespace()
+      .collect::<Vec<_>>()
+      .join(" ")
+  } else {
+    raw.trim().to_string()
+  };
+
+  non_empty(value)
+}
+
+fn non_empty(
+  value: String
+) -> Option<String> {
+  if value.is_empty() {
+    None
+  } else {
+    Some(value)
+  }
+}
@@ -0,0 +1,194 @@
+use regex::{
+  Captures,
+  Regex
+};
+
+use super::{
+  RawItem,
+  clean_value
+};
+use crate::domain::model::{
+  WatchConfig,
+  WatchExtractorKind
+};
+
+pub(super) fn extract(
+  watch: &WatchConfig,
+  body: &[u8]
+) -> Vec<RawItem> {
+  let Some(item_regex) = watch
+    .item_selector
+    .as_deref()
+    .and_then(|raw| {
+      Regex::new(raw).ok()
+    })
+  else {
+    return Vec::new();
+  };
+
+  let title_regex = field_regex(
+    watch.title_selector.as_deref(),
+    watch.title_extractor
+  );
+
+  let link_regex = field_regex(
+    watch.link_selector.as_deref(),
+    watch.link_extractor
+  );
+
+  let summary_regex = field_regex(
+    watch.summary_selector.as_deref(),
+    watch.summary_extractor
+  );
+
+  let published_regex = field_regex(
+    watch.published_selector.as_deref(),
+    watch.published_extractor
+  );
+
+  let text =
+    String::from_utf8_lossy(body);
+  let normalize =
+    watch.normalize_whitespace;
+
+  item_regex
+    .captures_iter(&text)
+    .map(|caps| {
+      let matched = caps
+        .get(0)
+        .map(|m| m.as_str())
+        .unwrap_or_default();
+
+      let field = |name: &str,
+                   regex: Option<
+        &Regex
+      >| {
+        field_value(
+          &caps, name, regex, matched,
+          normalize
+        )
+      };
+
+      RawItem {
+        title:     field(
+          "title",
+          title_regex.as_ref()
+        ),
+        link:      field(
+          "link",
+          link_regex.as_ref()
+        ),
+        summary:   field(
+          "summary",
+          summary_regex.as_ref()
+        ),
+        published: field(
+          "published",
+          published_regex.as_ref()
+        ),
+        id:        named(
+          &caps, "id", normalize
+        ),
+        text:      clean_value(
+          matched, normalize
+        )
+        .unwrap_or_default()
+      }
+    })
+    .collect()
+}
+
+/// Compiles a field's own regex, run
+/// against the item match. Without one
+/// the field comes from the item
+/// pattern's named group.
+fn field_regex(
+  selector: Option<&str>,
+  kind: Option<WatchExtractorKind>
+) -> Option<Regex> {
+  match (selector, kind) {
+    | (
+      Some(source),
+      None
+      | Some(WatchExtractorKind::Regex)
+    ) => Regex::new(source).ok(),
+    | _ => None
+  }
+}
+
+/// Applies a field regex to `haystack`
+/// and returns the group named after
+/// the field, else the first group,
+/// else the whole match.
+pub(super) fn capture_field(
+  regex: &Regex,
+  field: &str,
+  haystack: &str,
+  normalize_whitespace: bool
+) -> Option<String> {
+  let caps =
+    regex.captures(haystack)?;
+
+  let value = caps
+    .name(field)
+    .or_else(|| caps.get(1))
+    .or_else(|| caps.get(0))?;
+
+  clean_value(
+    value.as_str(),
+    normalize_whitespace
+  )
+}
+
+fn field_value(
+  caps: &Captures<'_>,
+  name: &str,
+  regex: Option<&Regex>,
+  matched: &str,
+  normalize_whitespace: bool
+) -> Option<String> {
+  match regex {
+    | Some(regex) => {
+      capture_field(
+        regex,
+        name,
+        matched,
+        normalize_whitespace
+      )
+    }
+    | None => {
+      named(
+        caps,
+        name,
+        normalize_whitespace
+      )
+    }
+  }
+}
+
+fn named(
+  caps: &Captures<'_>,
+  name: &str,
+  normalize_whitespace: bool
+) -> Option<String> {
+  caps.name(name).and_then(|m| {
+    clean_value(
+      m.as_str(),
+      normalize_whitespace
+    )
+  })
+}
+
+/// Finds the next page link with the
+/// `link` group, else the first group.
+pub(super) fn next_page(
+  source: &str,
+  body: &[u8]
+) -> Option<String> {
+  capture_field(
+    &Regex::new(source).ok()?,
+    "link",
+    &String::from_utf8_lossy(body),
+    true
+  )
+}
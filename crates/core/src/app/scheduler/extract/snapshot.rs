@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use scraper::{
+  Html,
+  Node,
+  Selector
+};
+
+use super::clean_value;
+use crate::domain::model::WatchConfig;
+
+/// Elements whose text never shows up
+/// on the rendered page.
+const HIDDEN_TAGS: [&str; 4] = [
+  "script", "style", "noscript",
+  "template"
+];
+
+/// Renders the page as one text line
+/// per text node, limited to
+/// `include_selectors` and skipping
+/// `exclude_selectors`. Lines are
+/// cleaned like extracted fields.
+pub(crate) fn snapshot_text(
+  watch: &WatchConfig,
+  body: &[u8]
+) -> String {
+  let html =
+    String::from_utf8_lossy(body);
+  let document =
+    Html::parse_document(&html);
+
+  let excluded: HashSet<_> = selectors(
+    watch.exclude_selectors.as_deref()
+  )
+  .iter()
+  .flat_map(|sel| document.select(sel))
+  .map(|el| el.id())
+  .collect();
+
+  let included = selectors(
+    watch.include_selectors.as_deref()
+  );
+
+  let roots: Vec<_> =
+    if included.is_empty() {
+      vec![document.root_element()]
+    } else {
+      included
+        .iter()
+        .flat_map(|sel| {
+          document.select(sel)
+        })
+        .collect()
+    };
+
+  let mut lines = Vec::new();
+
+  for root in roots {
+    for node in root.descendants() {
+      let Node::Text(text) =
+        node.value()
+      else {
+        continue;
+      };
+
+      let hidden = node
+        .ancestors()
+        .any(|parent| {
+          excluded
+            .contains(&parent.id())
+            || parent
+              .value()
+              .as_element()
+              .is_some_and(|el| {
+                HIDDEN_TAGS
+                  .contains(&el.name())
+              })
+        });
+
+      if hidden {
+        continue;
+      }
+
+      if watch.normalize_whitespace {
+        lines.extend(clean_value(
+          text, true
+        ));
+      } else {
+        lines.extend(
+          text.lines().filter_map(
+            |line| {
+              clean_value(line, false)
+            }
+          )
+        );
+      }
+    }
+  }
+
+  lines.join("\n")
+}
+
+fn selectors(
+  raw: Option<&[String]>
+) -> Vec<Selector> {
+  raw
+    .unwrap_or_default()
+    .iter()
+    .filter_map(|s| {
+      Selector::parse(s).ok()
+    })
+    .collect()
+}
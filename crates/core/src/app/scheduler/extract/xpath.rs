@@ -0,0 +1,276 @@
+use regex::Regex;
+use sxd_document::parser;
+use sxd_xpath::nodeset::Node;
+use sxd_xpath::{
+  Context,
+  Factory,
+  Value,
+  XPath
+};
+
+use super::{
+  RawItem,
+  clean_value,
+  pattern
+};
+use crate::domain::model::{
+  WatchConfig,
+  WatchExtractorKind,
+  WatchItemIdentity
+};
+
+/// Prefix bound to the XHTML namespace
+/// so XHTML pages can be queried with
+/// `//xhtml:div`.
+const XHTML_PREFIX: &str = "xhtml";
+const XHTML_NS: &str =
+  "http://www.w3.org/1999/xhtml";
+
+/// A per-field rule evaluated against
+/// a selected item node.
+enum FieldRule {
+  Xpath(XPath),
+  Regex(Regex)
+}
+
+pub(super) fn extract(
+  watch: &WatchConfig,
+  body: &[u8]
+) -> Vec<RawItem> {
+  let Some(item_xpath) = watch
+    .item_selector
+    .as_deref()
+    .and_then(build_xpath)
+  else {
+    return Vec::new();
+  };
+
+  let xml =
+    String::from_utf8_lossy(body);
+
+  let Ok(package) = parser::parse(&xml)
+  else {
+    return Vec::new();
+  };
+
+  let document = package.as_document();
+
+  let mut context = Context::new();
+  context.set_namespace(
+    XHTML_PREFIX,
+    XHTML_NS
+  );
+
+  let Ok(Value::Nodeset(nodes)) =
+    item_xpath.evaluate(
+      &context,
+      document.root()
+    )
+  else {
+    return Vec::new();
+  };
+
+  let title_rule = field_rule(
+    watch.title_selector.as_deref(),
+    watch.title_extractor
+  );
+
+  let link_rule = field_rule(
+    watch.link_selector.as_deref(),
+    watch.link_extractor
+  );
+
+  let summary_rule = field_rule(
+    watch.summary_selector.as_deref(),
+    watch.summary_extractor
+  );
+
+  let published_rule = field_rule(
+    watch.published_selector.as_deref(),
+    watch.published_extractor
+  );
+
+  let normalize =
+    watch.normalize_whitespace;
+
+  nodes
+    .document_order()
+    .into_iter()
+    .map(|node| {
+      let eval =
+        |rule: Option<&FieldRule>,
+         field: &str| {
+          eval_field(
+            &context, node, rule,
+            field, normalize
+          )
+        };
+
+      let link = eval(
+        link_rule.as_ref(),
+        "link"
+      )
+      .or_else(|| {
+        attribute(node, "href")
+      });
+
+      RawItem {
+        title: eval(
+          title_rule.as_ref(),
+          "title"
+        ),
+        link,
+        summary: eval(
+          summary_rule.as_ref(),
+          "summary"
+        ),
+        published: eval(
+          published_rule.as_ref(),
+          "published"
+        ),
+        id: identity_attr(node, watch),
+        text: clean_value(
+          &node.string_value(),
+          true
+        )
+        .unwrap_or_default()
+      }
+    })
+    .collect()
+}
+
+fn build_xpath(
+  raw: &str
+) -> Option<XPath> {
+  Factory::new().build(raw).ok()?
+}
+
+fn field_rule(
+  selector: Option<&str>,
+  kind: Option<WatchExtractorKind>
+) -> Option<FieldRule> {
+  let selector = selector?;
+
+  match kind.unwrap_or(
+    WatchExtractorKind::Xpath
+  ) {
+    | WatchExtractorKind::Xpath => {
+      build_xpath(selector)
+        .map(FieldRule::Xpath)
+    }
+    | WatchExtractorKind::Regex => {
+      Regex::new(selector)
+        .ok()
+        .map(FieldRule::Regex)
+    }
+    | WatchExtractorKind::Css => None
+  }
+}
+
+fn eval_field(
+  context: &Context<'_>,
+  node: Node<'_>,
+  rule: Option<&FieldRule>,
+  field: &str,
+  normalize_whitespace: bool
+) -> Option<String> {
+  match rule? {
+    | FieldRule::Xpath(xpath) => {
+      let value = xpath
+        .evaluate(context, node)
+        .ok()?;
+
+      let raw = match &value {
+        | Value::Nodeset(set)
+          if field == "link" =>
+        {
+          let first = set
+            .document_order_first()?;
+
+          attribute(first, "href")
+            .unwrap_or_else(|| {
+              first.string_value()
+            })
+        }
+        | _ => value.string()
+      };
+
+      clean_value(
+        &raw,
+        normalize_whitespace
+      )
+    }
+    | FieldRule::Regex(regex) => {
+      pattern::capture_field(
+        regex,
+        field,
+        &node.string_value(),
+        normalize_whitespace
+      )
+    }
+  }
+}
+
+fn attribute(
+  node: Node<'_>,
+  name: &str
+) -> Option<String> {
+  node
+    .element()?
+    .attribute_value(name)
+    .map(str::to_string)
+}
+
+fn identity_attr(
+  node: Node<'_>,
+  watch: &WatchConfig
+) -> Option<String> {
+  if watch.item_identity
+    != Some(WatchItemIdentity::Attr)
+  {
+    return None;
+  }
+
+  let attr = watch
+    .item_identity_attr
+    .as_deref()?;
+
+  attribute(node, attr)
+}
+
+pub(super) fn next_page(
+  selector: &str,
+  body: &[u8]
+) -> Option<String> {
+  let xpath = build_xpath(selector)?;
+  let xml =
+    String::from_utf8_lossy(body);
+  let package =
+    parser::parse(&xml).ok()?;
+  let document = package.as_document();
+
+  let mut context = Context::new();
+  context.set_namespace(
+    XHTML_PREFIX,
+    XHTML_NS
+  );
+
+  let value = xpath
+    .evaluate(&context, document.root())
+    .ok()?;
+
+  let raw = match &value {
+    | Value::Nodeset(set) => {
+      let first =
+        set.document_order_first()?;
+
+      attribute(first, "href")
+        .unwrap_or_else(|| {
+          first.string_value()
+        })
+    }
+    | _ => value.string()
+  };
+
+  clean_value(&raw, true)
+}
@@ -1,7 +1,17 @@
 mod actions;
+mod archive;
+mod changes;
 mod concurrency;
+mod extract;
 mod orchestrator;
+mod pagination;
 mod processing;
+mod redirects;
 mod state;
 
-pub use orchestrator::Scheduler;
+pub use concurrency::ConcurrencyGuards;
+pub use orchestrator::{
+  Scheduler,
+  TICK_INTERVAL_SECS
+};
+pub use processing::run_tick;
@@ -14,7 +14,8 @@ use crate::ports::http::Http;
 use crate::ports::random::RandomSource;
 use crate::ports::repo::Repo;
 
-const TICK_INTERVAL_SECS: u64 = 5;
+/// Pause between scheduler ticks.
+pub const TICK_INTERVAL_SECS: u64 = 5;
 const RETRY_BASE_SECS: u64 = 2;
 const RETRY_MAX_SECS: u64 = 60;
 const RETRY_EXP_CAP: u32 = 5;
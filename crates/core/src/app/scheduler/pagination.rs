@@ -0,0 +1,242 @@
+//! Paginated watches: follows the
+//! watch's `next_page_selector` from
+//! the first page up to `max_pages`,
+//! merging extracted items by identity.
+
+use std::collections::{
+  HashMap,
+  HashSet
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::extract;
+use crate::domain::model::{
+  FeedConfig,
+  WatchConfig
+};
+use crate::feed::parser::FeedItem;
+use crate::infra::metrics;
+use crate::ports::http::Http;
+use crate::ports::repo::Repo;
+
+/// Extracts items from the first page
+/// and any following pages. Runs under
+/// the caller's domain permit, so extra
+/// pages share its rate limits. Stops
+/// early once a page holds only
+/// identities already stored or seen
+/// on an earlier page.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn collect_watch_items<
+  R,
+  H
+>(
+  repo: &Arc<R>,
+  http: &Arc<H>,
+  feed: &FeedConfig,
+  watch: &WatchConfig,
+  cookie_header: Option<&str>,
+  extra_headers: Option<
+    &HashMap<String, String>
+  >,
+  body: &[u8],
+  now_ms: i64
+) -> Result<Vec<FeedItem>, String>
+where
+  R: Repo + ?Sized,
+  H: Http
+{
+  let mut items =
+    extract::extract_watch_items(
+      feed, watch, body, now_ms
+    );
+
+  if watch.max_pages <= 1 {
+    return Ok(items);
+  }
+
+  let mut seen: HashSet<String> = items
+    .iter()
+    .filter_map(|item| {
+      item.guid.clone()
+    })
+    .collect();
+
+  let mut visited =
+    HashSet::from([feed.url.clone()]);
+
+  let mut next_url =
+    extract::extract_next_page(
+      watch, body, &feed.url
+    );
+
+  let mut all_known = only_known_items(
+    repo,
+    feed,
+    &items,
+    &HashSet::new()
+  )
+  .await?;
+
+  let mut pages = 1;
+
+  while pages < watch.max_pages
+    && !all_known
+  {
+    let Some(url) = next_url.take()
+    else {
+      break;
+    };
+
+    if !visited.insert(url.clone()) {
+      break;
+    }
+
+    pages += 1;
+
+    tracing::debug!(feed_id = %feed.id, url = %url, page = pages, "GET next watch page");
+
+    // The watch's credentials belong to
+    // its own origin, wherever a next
+    // link points.
+    let (cookie_header, extra_headers) =
+      if same_origin(&url, &feed.url) {
+        (cookie_header, extra_headers)
+      } else {
+        (None, None)
+      };
+
+    let res = http
+      .get(
+        &feed.domain,
+        &url,
+        cookie_header,
+        extra_headers
+      )
+      .await;
+
+    metrics::record_http_result(
+      "get",
+      &feed.domain,
+      res.status,
+      res.latency_ms,
+      res.error.is_none()
+    );
+
+    let page_body = match res.body {
+      | Some(page_body)
+        if res.error.is_none() =>
+      {
+        page_body
+      }
+      | _ => {
+        tracing::debug!(feed_id = %feed.id, url = %url, status = ?res.status, error = ?res.error, "Watch page fetch failed; stopping pagination");
+        break;
+      }
+    };
+
+    let page_items =
+      extract::extract_watch_items(
+        feed, watch, &page_body, now_ms
+      );
+
+    next_url =
+      extract::extract_next_page(
+        watch, &page_body, &url
+      );
+
+    all_known = only_known_items(
+      repo,
+      feed,
+      &page_items,
+      &seen
+    )
+    .await?;
+
+    for item in page_items {
+      let is_new = item
+        .guid
+        .as_ref()
+        .is_none_or(|guid| {
+          seen.insert(guid.clone())
+        });
+
+      if is_new {
+        items.push(item);
+      }
+    }
+  }
+
+  if let Some(max_items) =
+    watch.max_items_per_fetch
+  {
+    items.truncate(max_items as usize);
+  }
+
+  Ok(items)
+}
+
+/// Whether `a` and `b` parse to URLs
+/// with the same scheme, host and
+/// port.
+fn same_origin(
+  a: &str,
+  b: &str
+) -> bool {
+  let origin = |url: &str| {
+    reqwest::Url::parse(url)
+      .ok()
+      .map(|u| u.origin())
+  };
+
+  origin(a).is_some_and(|o| {
+    o.is_tuple() && Some(o) == origin(b)
+  })
+}
+
+/// True when every item on a page was
+/// seen earlier in this run or is
+/// already stored for the feed. An
+/// empty page counts as fully known.
+async fn only_known_items<R>(
+  repo: &Arc<R>,
+  feed: &FeedConfig,
+  items: &[FeedItem],
+  seen: &HashSet<String>
+) -> Result<bool, String>
+where
+  R: Repo + ?Sized
+{
+  let unseen: Vec<String> = items
+    .iter()
+    .filter_map(|item| {
+      item.guid.clone()
+    })
+    .filter(|guid| !seen.contains(guid))
+    .collect();
+
+  if unseen.is_empty() {
+    return Ok(true);
+  }
+
+  let started = Instant::now();
+
+  let known = repo
+    .known_item_guids(&feed.id, &unseen)
+    .await;
+
+  metrics::record_db_time(
+    "known_item_guids",
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  let known = known?;
+
+  Ok(
+    unseen
+      .iter()
+      .all(|guid| known.contains(guid))
+  )
+}
@@ -29,6 +29,7 @@ use crate::domain::model::{
   WatchCheckMethod,
   WatchConfig
 };
+use crate::domain::registry::is_registry_feed_id;
 use crate::infra::metrics;
 use crate::infra::time::format_epoch_ms;
 use crate::ports::clock::Clock;
@@ -98,6 +99,9 @@ where
 
   let http = ctx.http.clone();
 
+  let public_http =
+    ctx.public_http.clone();
+
   let clock = ctx.clock.clone();
 
   let rng = ctx.rng.clone();
@@ -163,7 +167,13 @@ where
     .map(|feed| {
       let cfg = cfg.clone();
       let repo = repo.clone();
-      let http = http.clone();
+      let http = if is_registry_feed_id(
+        &feed.id
+      ) {
+        public_http.clone()
+      } else {
+        http.clone()
+      };
       let clock = clock.clone();
       let rng = rng.clone();
       let concurrency =
@@ -0,0 +1,94 @@
+//! Records permanent redirects seen on
+//! GET so moved feeds can be migrated
+//! to their new URL.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig,
+  GetResult,
+  RedirectHop
+};
+use crate::infra::metrics;
+use crate::ports::repo::Repo;
+
+/// Counts the permanent redirect on a
+/// successful GET, or clears a stale
+/// one once the feed URL answers
+/// directly again.
+pub(super) async fn track_redirects<R>(
+  cfg: &AppConfig,
+  repo: &Arc<R>,
+  feed: &FeedConfig,
+  res: &GetResult,
+  now_ms: i64
+) -> Result<(), String>
+where
+  R: Repo + ?Sized
+{
+  if res.error.is_some()
+    || res.status.is_none()
+  {
+    return Ok(());
+  }
+
+  let started = Instant::now();
+
+  let (query, db_res) =
+    match permanent_target(
+      &res.redirects
+    ) {
+      | Some(hop) => {
+        (
+          "record_feed_redirect",
+          repo
+            .record_feed_redirect(
+              &feed.id,
+              &feed.url,
+              &hop.to,
+              hop.status as i64,
+              now_ms,
+              &cfg.timezone
+            )
+            .await
+        )
+      }
+      | None
+        if res.redirects.is_empty() =>
+      {
+        (
+          "clear_feed_redirect",
+          repo
+            .clear_feed_redirect(
+              &feed.id
+            )
+            .await
+        )
+      }
+      | None => return Ok(())
+    };
+
+  metrics::record_db_time(
+    query,
+    started.elapsed().as_millis()
+      as u64
+  );
+
+  db_res
+}
+
+/// The last hop of the leading run of
+/// 301/308 responses; a temporary
+/// redirect ends the run.
+fn permanent_target(
+  hops: &[RedirectHop]
+) -> Option<&RedirectHop> {
+  hops
+    .iter()
+    .take_while(|hop| {
+      matches!(hop.status, 301 | 308)
+    })
+    .last()
+}
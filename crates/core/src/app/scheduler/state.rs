@@ -192,6 +192,9 @@ fn parse_phase(
     | "ErrorBackoff" => {
       Some(LinkPhase::ErrorBackoff)
     }
+    | "Archived" => {
+      Some(LinkPhase::Archived)
+    }
     | _ => None
   }
 }
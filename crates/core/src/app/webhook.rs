@@ -0,0 +1,107 @@
+//! Delivers webhook events: one
+//! signed POST per attempt, with the
+//! outcome recorded for retries and
+//! the delivery history.
+
+use crate::app::registry::url_domain;
+use crate::domain::webhook::delivery_headers;
+use crate::ports::http::Http;
+
+/// What a delivery to send holds.
+#[derive(Debug, Clone)]
+pub struct Delivery<'a> {
+  pub id:     i64,
+  pub url:    &'a str,
+  pub secret: &'a str,
+  pub event:  &'a str,
+  pub body:   &'a [u8]
+}
+
+/// How one attempt went.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct Attempt {
+  /// The receiver's status, if it
+  /// answered.
+  pub status: Option<u16>,
+  /// Why the attempt failed.
+  pub error:  Option<String>
+}
+
+impl Attempt {
+  pub fn delivered(&self) -> bool {
+    self.error.is_none()
+  }
+}
+
+/// POSTs the delivery, signed at
+/// `timestamp` (epoch seconds). Any
+/// 2xx answer counts as delivered.
+/// `http` should only reach public
+/// addresses, since the URL is the
+/// user's.
+pub async fn deliver<H>(
+  http: &H,
+  delivery: &Delivery<'_>,
+  timestamp: i64
+) -> Attempt
+where
+  H: Http + ?Sized
+{
+  let Some(domain) =
+    url_domain(delivery.url)
+  else {
+    return Attempt {
+      status: None,
+      error:  Some(format!(
+        "invalid webhook url '{}'",
+        delivery.url
+      ))
+    };
+  };
+
+  let headers = delivery_headers(
+    delivery.secret,
+    delivery.event,
+    delivery.id,
+    timestamp,
+    delivery.body
+  );
+
+  let res = http
+    .post(
+      &domain,
+      delivery.url,
+      &headers,
+      delivery.body.to_vec()
+    )
+    .await;
+
+  // Only the status is kept: the
+  // receiver's body is not the user's
+  // to read.
+  let error = match res.status {
+    | Some(200..=299) => None,
+    | Some(status) => {
+      Some(format!("status {status}"))
+    }
+    | None => {
+      Some(match res.error {
+        | Some(kind) => {
+          format!(
+            "no response: {kind:?}"
+          )
+        }
+        | None => {
+          "no response".to_string()
+        }
+      })
+    }
+  };
+
+  Attempt {
+    status: res.status,
+    error
+  }
+}
@@ -0,0 +1,112 @@
+//! Raw payload archive: configuration
+//! and the uncompressed record layout
+//! (response headers followed by the
+//! body, HTTP style).
+
+use std::path::PathBuf;
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum ArchiveStore {
+  Filesystem,
+  Database
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveConfig {
+  pub enabled:    bool,
+  pub store:      ArchiveStore,
+  /// Root of the blob tree for the
+  /// filesystem store.
+  pub directory:  PathBuf,
+  pub zstd_level: i32
+}
+
+/// One archived response.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct ArchivedResponse {
+  pub headers: Vec<(String, String)>,
+  pub body:    Vec<u8>
+}
+
+impl ArchivedResponse {
+  /// `Name: value` lines, a blank
+  /// line, then the body bytes.
+  pub fn encode(&self) -> Vec<u8> {
+    let mut out = Vec::with_capacity(
+      self.body.len() + 256
+    );
+
+    for (name, value) in &self.headers {
+      out.extend_from_slice(
+        name.as_bytes()
+      );
+      out.extend_from_slice(b": ");
+      out.extend_from_slice(
+        value
+          .replace('\n', " ")
+          .as_bytes()
+      );
+      out.push(b'\n');
+    }
+
+    out.push(b'\n');
+    out.extend_from_slice(&self.body);
+
+    out
+  }
+
+  pub fn decode(
+    bytes: &[u8]
+  ) -> Result<ArchivedResponse, String>
+  {
+    let mut headers = Vec::new();
+    let mut rest = bytes;
+
+    loop {
+      let Some(end) = rest
+        .iter()
+        .position(|b| *b == b'\n')
+      else {
+        return Err(
+          "archived response has no \
+           header terminator"
+            .to_string()
+        );
+      };
+
+      let line = &rest[..end];
+
+      rest = &rest[end + 1..];
+
+      if line.is_empty() {
+        break;
+      }
+
+      let line =
+        String::from_utf8_lossy(line);
+
+      let Some((name, value)) =
+        line.split_once(": ")
+      else {
+        return Err(format!(
+          "malformed archived header \
+           '{line}'"
+        ));
+      };
+
+      headers.push((
+        name.to_string(),
+        value.to_string()
+      ));
+    }
+
+    Ok(ArchivedResponse {
+      headers,
+      body: rest.to_vec()
+    })
+  }
+}
@@ -0,0 +1,22 @@
+//! Scheduled database backups: how
+//! often the fetcher takes one, where
+//! they go and how many are kept.
+
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+  pub enabled:          bool,
+  pub interval_seconds: u64,
+  /// Each backup is a timestamped
+  /// directory below this one.
+  pub directory:        PathBuf,
+  /// Newest backups kept; older ones
+  /// are removed after each run.
+  pub keep:             usize,
+  /// Postgres schema holding the
+  /// server tables dumped alongside
+  /// the fetcher's.
+  pub server_schema:    String,
+  pub zstd_level:       i32
+}
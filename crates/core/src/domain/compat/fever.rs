@@ -0,0 +1,483 @@
+//! The Fever API (version 3): one
+//! endpoint whose query flags pick
+//! the sections of the response, with
+//! `mark` requests to change read and
+//! saved state. Groups are plain
+//! folders; saved items are starred
+//! entries. Fever wants integer feed
+//! ids, so each feed gets a stable
+//! hash of its id.
+
+use md5::Md5;
+use serde::Serialize;
+use sha2::{
+  Digest,
+  Sha256
+};
+
+use super::{
+  CompatEntry,
+  CompatFeed,
+  form_value
+};
+
+pub const API_VERSION: i64 = 3;
+
+/// Items per `items` response.
+pub const ITEM_LIMIT: i64 = 50;
+
+/// The key a client sends: the MD5 of
+/// `username:password`, in hex.
+pub fn api_key(
+  username: &str,
+  password: &str
+) -> String {
+  hex::encode(Md5::digest(
+    format!("{username}:{password}")
+      .as_bytes()
+  ))
+}
+
+/// A feed's integer id: 48 bits of
+/// the SHA-256 of its id, so it stays
+/// exact as a JavaScript number.
+pub fn feed_id(id: &str) -> i64 {
+  let digest =
+    Sha256::digest(id.as_bytes());
+
+  digest[..6]
+    .iter()
+    .fold(0, |acc, b| {
+      (acc << 8) | i64::from(*b)
+    })
+}
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum ItemMark {
+  Read,
+  Unread,
+  Saved,
+  Unsaved
+}
+
+/// A `mark` request.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum Mark {
+  Item {
+    id:   i64,
+    mark: ItemMark
+  },
+  /// Marks the feed's items read,
+  /// up to `before` (epoch seconds).
+  Feed {
+    id:     i64,
+    before: Option<i64>
+  },
+  /// Marks the group's items read;
+  /// group `0` is every feed.
+  Group {
+    id:     i64,
+    before: Option<i64>
+  }
+}
+
+/// What a request asks for.
+#[derive(
+  Debug, Clone, Default, PartialEq, Eq,
+)]
+pub struct FeverRequest {
+  pub api_key:         Option<String>,
+  pub groups:          bool,
+  pub feeds:           bool,
+  pub favicons:        bool,
+  pub items:           bool,
+  pub links:           bool,
+  pub unread_item_ids: bool,
+  pub saved_item_ids:  bool,
+  pub since_id:        Option<i64>,
+  pub max_id:          Option<i64>,
+  pub with_ids:        Option<Vec<i64>>,
+  pub mark:            Option<Mark>
+}
+
+impl FeverRequest {
+  /// Reads the query and form pairs
+  /// of one request together.
+  pub fn parse(
+    pairs: &[(String, String)]
+  ) -> Result<Self, String> {
+    let has = |key: &str| {
+      pairs
+        .iter()
+        .any(|(k, _)| k == key)
+    };
+
+    let int = |key: &str| {
+      form_value(pairs, key)
+        .filter(|v| !v.is_empty())
+        .map(|v| parse_int(key, v))
+        .transpose()
+    };
+
+    let with_ids =
+      form_value(pairs, "with_ids")
+        .map(|ids| {
+          ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+          parse_int("with_ids", id)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+
+    let mark =
+      match form_value(pairs, "mark") {
+        | None => None,
+        | Some(kind) => {
+          let id = int("id")?
+            .ok_or("id required")?;
+
+          let mark_as =
+            form_value(pairs, "as")
+              .unwrap_or_default();
+
+          Some(match kind {
+            | "item" => {
+              Mark::Item {
+                id,
+                mark: match mark_as {
+                  | "read" => {
+                    ItemMark::Read
+                  }
+                  | "unread" => {
+                    ItemMark::Unread
+                  }
+                  | "saved" => {
+                    ItemMark::Saved
+                  }
+                  | "unsaved" => {
+                    ItemMark::Unsaved
+                  }
+                  | other => {
+                    return Err(
+                      format!(
+                        "invalid as: \
+                         {other}"
+                      )
+                    );
+                  }
+                }
+              }
+            }
+            | "feed" | "group" => {
+              if mark_as != "read" {
+                return Err(format!(
+                  "invalid as: \
+                   {mark_as}"
+                ));
+              }
+
+              let before =
+                int("before")?
+                  .filter(|b| *b > 0);
+
+              if kind == "feed" {
+                Mark::Feed {
+                  id,
+                  before
+                }
+              } else {
+                Mark::Group {
+                  id,
+                  before
+                }
+              }
+            }
+            | other => {
+              return Err(format!(
+                "invalid mark: {other}"
+              ));
+            }
+          })
+        }
+      };
+
+    Ok(Self {
+      api_key: form_value(
+        pairs, "api_key"
+      )
+      .map(|k| k.trim().to_lowercase())
+      .filter(|k| !k.is_empty()),
+      groups: has("groups"),
+      feeds: has("feeds"),
+      favicons: has("favicons"),
+      items: has("items"),
+      links: has("links"),
+      unread_item_ids: has(
+        "unread_item_ids"
+      ),
+      saved_item_ids: has(
+        "saved_item_ids"
+      ),
+      since_id: int("since_id")?,
+      max_id: int("max_id")?,
+      with_ids,
+      mark
+    })
+  }
+}
+
+fn parse_int(
+  key: &str,
+  value: &str
+) -> Result<i64, String> {
+  value.trim().parse().map_err(|_| {
+    format!("invalid {key}: {value}")
+  })
+}
+
+/// A response; sections left `None`
+/// are not sent.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeverResponse {
+  pub api_version:            i64,
+  pub auth:                   u8,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub last_refreshed_on_time:
+    Option<i64>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub groups: Option<Vec<Group>>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub feeds: Option<Vec<Feed>>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub feeds_groups:
+    Option<Vec<FeedsGroup>>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub favicons:
+    Option<Vec<serde_json::Value>>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub items: Option<Vec<Item>>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub total_items: Option<i64>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub links:
+    Option<Vec<serde_json::Value>>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub unread_item_ids: Option<String>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub saved_item_ids: Option<String>
+}
+
+impl FeverResponse {
+  /// The bare response for a key that
+  /// did (`auth`) or did not match.
+  pub fn new(
+    auth: bool,
+    now_secs: i64
+  ) -> Self {
+    Self {
+      api_version:
+        API_VERSION,
+      auth:                   u8::from(
+        auth
+      ),
+      last_refreshed_on_time: auth
+        .then_some(now_secs),
+      groups:                 None,
+      feeds:                  None,
+      feeds_groups:           None,
+      favicons:               None,
+      items:                  None,
+      total_items:            None,
+      links:                  None,
+      unread_item_ids:        None,
+      saved_item_ids:         None
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Group {
+  pub id:    i64,
+  pub title: String
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedsGroup {
+  pub group_id: i64,
+  /// Comma-separated feed ids.
+  pub feed_ids: String
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Feed {
+  pub id:                   i64,
+  pub favicon_id:           i64,
+  pub title:                String,
+  pub url:                  String,
+  pub site_url:             String,
+  pub is_spark:             u8,
+  pub last_updated_on_time: i64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Item {
+  pub id:              i64,
+  pub feed_id:         i64,
+  pub title:           String,
+  pub author:          String,
+  pub html:            String,
+  pub url:             String,
+  pub is_saved:        u8,
+  pub is_read:         u8,
+  pub created_on_time: i64
+}
+
+/// The folders of `feeds` as groups,
+/// by id, with their feeds.
+pub fn groups(
+  feeds: &[CompatFeed]
+) -> (Vec<Group>, Vec<FeedsGroup>) {
+  let mut groups: Vec<(
+    Group,
+    Vec<i64>
+  )> = Vec::new();
+
+  for feed in feeds {
+    for folder in &feed.folders {
+      let fid = feed_id(&feed.id);
+
+      match groups.iter_mut().find(
+        |(g, _)| g.id == folder.id
+      ) {
+        | Some((_, ids)) => {
+          ids.push(fid)
+        }
+        | None => {
+          groups.push((
+            Group {
+              id:    folder.id,
+              title: folder
+                .name
+                .clone()
+            },
+            vec![fid]
+          ))
+        }
+      }
+    }
+  }
+
+  groups.sort_by_key(|(g, _)| g.id);
+
+  groups
+    .into_iter()
+    .map(|(group, ids)| {
+      let feeds_group = FeedsGroup {
+        group_id: group.id,
+        feed_ids: id_list(&ids)
+      };
+
+      (group, feeds_group)
+    })
+    .unzip()
+}
+
+impl Feed {
+  pub fn new(
+    feed: &CompatFeed
+  ) -> Self {
+    Self {
+      id:                   feed_id(
+        &feed.id
+      ),
+      favicon_id:           0,
+      title:                feed
+        .title
+        .clone(),
+      url:                  feed
+        .url
+        .clone(),
+      site_url:             feed
+        .site_url
+        .clone(),
+      is_spark:             0,
+      last_updated_on_time: 0
+    }
+  }
+}
+
+impl Item {
+  pub fn new(
+    entry: &CompatEntry
+  ) -> Self {
+    Self {
+      id:              entry.id,
+      feed_id:         feed_id(
+        &entry.feed_id
+      ),
+      title:           entry
+        .title
+        .clone()
+        .unwrap_or_default(),
+      author:          entry
+        .author
+        .clone()
+        .unwrap_or_default(),
+      html:            entry
+        .content
+        .clone()
+        .unwrap_or_default(),
+      url:             entry
+        .link
+        .clone()
+        .unwrap_or_default(),
+      is_saved:        u8::from(
+        entry.starred
+      ),
+      is_read:         u8::from(
+        entry.read
+      ),
+      created_on_time: entry
+        .published_ms
+        .unwrap_or(0)
+        / 1000
+    }
+  }
+}
+
+/// Ids joined with commas.
+pub fn id_list(ids: &[i64]) -> String {
+  ids
+    .iter()
+    .map(i64::to_string)
+    .collect::<Vec<_>>()
+    .join(",")
+}
@@ -0,0 +1,686 @@
+//! The Google Reader API as clients
+//! like Reeder and NetNewsWire use it:
+//! stream ids, item ids, edit-tag and
+//! mark-all-as-read requests, and the
+//! JSON they expect back.
+//!
+//! Streams map onto pulsewire as
+//! - `user/-/state/com.google/
+//!   reading-list`: the user's
+//!   subscriptions;
+//! - `.../starred`: starred entries;
+//! - `.../read`: read entries;
+//! - `user/-/label/<name>`: the folder
+//!   called `name`;
+//! - `feed/<id>`: one subscribed feed.
+
+use serde::Serialize;
+
+use super::{
+  CompatEntry,
+  CompatFeed,
+  form_value,
+  form_values
+};
+
+pub const READING_LIST: &str =
+  "user/-/state/com.google/\
+   reading-list";
+pub const READ: &str =
+  "user/-/state/com.google/read";
+pub const STARRED: &str =
+  "user/-/state/com.google/starred";
+pub const KEPT_UNREAD: &str =
+  "user/-/state/com.google/kept-unread";
+
+const LABEL_PREFIX: &str =
+  "user/-/label/";
+const FEED_PREFIX: &str = "feed/";
+const ITEM_PREFIX: &str =
+  "tag:google.com,2005:reader/item/";
+
+/// Items per page when `n` is not
+/// given.
+pub const DEFAULT_COUNT: u32 = 20;
+
+/// A stream a request reads or marks.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub enum StreamId {
+  ReadingList,
+  Starred,
+  Read,
+  Label(String),
+  Feed(String)
+}
+
+impl StreamId {
+  /// Parses a stream id; the user
+  /// part may be `-` or a user id.
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    let id = normalize(raw.trim());
+
+    if let Some(feed) =
+      id.strip_prefix(FEED_PREFIX)
+      && !feed.is_empty()
+    {
+      return Ok(StreamId::Feed(
+        feed.to_string()
+      ));
+    }
+
+    if let Some(label) =
+      id.strip_prefix(LABEL_PREFIX)
+      && !label.is_empty()
+    {
+      return Ok(StreamId::Label(
+        label.to_string()
+      ));
+    }
+
+    match id.as_str() {
+      | READING_LIST => {
+        Ok(StreamId::ReadingList)
+      }
+      | STARRED => {
+        Ok(StreamId::Starred)
+      }
+      | READ => Ok(StreamId::Read),
+      | _ => {
+        Err(format!(
+          "unsupported stream: {raw}"
+        ))
+      }
+    }
+  }
+
+  pub fn to_id(&self) -> String {
+    match self {
+      | StreamId::ReadingList => {
+        READING_LIST.to_string()
+      }
+      | StreamId::Starred => {
+        STARRED.to_string()
+      }
+      | StreamId::Read => {
+        READ.to_string()
+      }
+      | StreamId::Label(name) => {
+        format!("{LABEL_PREFIX}{name}")
+      }
+      | StreamId::Feed(id) => {
+        format!("{FEED_PREFIX}{id}")
+      }
+    }
+  }
+}
+
+/// `user/<id>/..` with the user part
+/// replaced by `-`.
+fn normalize(id: &str) -> String {
+  let Some(rest) =
+    id.strip_prefix("user/")
+  else {
+    return id.to_string();
+  };
+
+  match rest.split_once('/') {
+    | Some((_, tail)) => {
+      format!("user/-/{tail}")
+    }
+    | None => id.to_string()
+  }
+}
+
+/// The long form of an item id.
+pub fn item_id(id: i64) -> String {
+  format!("{ITEM_PREFIX}{:016x}", id)
+}
+
+/// Reads an item id in its long form
+/// (hex) or short form (decimal).
+pub fn parse_item_id(
+  raw: &str
+) -> Result<i64, String> {
+  let raw = raw.trim();
+
+  let parsed = match raw
+    .strip_prefix(ITEM_PREFIX)
+  {
+    | Some(hex) => {
+      u64::from_str_radix(hex, 16)
+        .map(|id| id as i64)
+        .ok()
+    }
+    | None => raw.parse::<i64>().ok()
+  };
+
+  parsed.ok_or_else(|| {
+    format!("invalid item id: {raw}")
+  })
+}
+
+/// The token of an
+/// `Authorization: GoogleLogin
+/// auth=<token>` header value.
+pub fn auth_token(
+  header: &str
+) -> Option<&str> {
+  header
+    .trim()
+    .strip_prefix("GoogleLogin")?
+    .trim()
+    .strip_prefix("auth=")
+    .map(str::trim)
+    .filter(|t| !t.is_empty())
+}
+
+/// The `ClientLogin` response body.
+pub fn client_login_body(
+  token: &str
+) -> String {
+  ["SID", "LSID", "Auth"]
+    .iter()
+    .map(|key| {
+      format!("{key}={token}\n")
+    })
+    .collect()
+}
+
+/// A `stream/contents` or
+/// `stream/items/ids` request.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct StreamQuery {
+  pub stream:        StreamId,
+  /// `n`: items per page.
+  pub count:         u32,
+  /// `r=o`: oldest first.
+  pub oldest_first:  bool,
+  /// `xt=..read`: unread only.
+  pub exclude_read:  bool,
+  /// `it=..starred`: starred only.
+  pub only_starred:  bool,
+  /// `ot`: published at or after, in
+  /// epoch milliseconds.
+  pub newer_than_ms: Option<i64>,
+  /// `nt`: published before, in epoch
+  /// milliseconds.
+  pub older_than_ms: Option<i64>,
+  /// `c`: where the previous page
+  /// ended.
+  pub continuation:  Option<String>
+}
+
+impl StreamQuery {
+  pub fn parse(
+    stream: &str,
+    pairs: &[(String, String)]
+  ) -> Result<Self, String> {
+    let stream =
+      StreamId::parse(stream)?;
+
+    let count = form_value(pairs, "n")
+      .map(|n| {
+        n.parse::<u32>().map_err(|_| {
+          format!("invalid n: {n}")
+        })
+      })
+      .transpose()?
+      .unwrap_or(DEFAULT_COUNT);
+
+    let seconds = |key: &str| {
+      form_value(pairs, key)
+        .map(|v| {
+          v.parse::<i64>()
+            .map(|s| s * 1000)
+            .map_err(|_| {
+              format!(
+                "invalid {key}: {v}"
+              )
+            })
+        })
+        .transpose()
+    };
+
+    let excluded =
+      form_values(pairs, "xt");
+    let included =
+      form_values(pairs, "it");
+
+    Ok(Self {
+      stream,
+      count,
+      oldest_first: form_value(
+        pairs, "r"
+      ) == Some("o"),
+      exclude_read: excluded
+        .iter()
+        .any(|t| normalize(t) == READ),
+      only_starred: included
+        .iter()
+        .any(|t| {
+          normalize(t) == STARRED
+        }),
+      newer_than_ms: seconds("ot")?,
+      older_than_ms: seconds("nt")?,
+      continuation: form_value(
+        pairs, "c"
+      )
+      .filter(|c| !c.is_empty())
+      .map(String::from)
+    })
+  }
+}
+
+/// An `edit-tag` request: the items
+/// and the marks to set on them.
+/// Other tags are ignored.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct EditTag {
+  pub item_ids: Vec<i64>,
+  pub read:     Option<bool>,
+  pub starred:  Option<bool>
+}
+
+impl EditTag {
+  pub fn parse(
+    pairs: &[(String, String)]
+  ) -> Result<Self, String> {
+    let item_ids =
+      form_values(pairs, "i")
+        .into_iter()
+        .map(parse_item_id)
+        .collect::<Result<Vec<_>, _>>(
+        )?;
+
+    if item_ids.is_empty() {
+      return Err(
+        "i required".to_string()
+      );
+    }
+
+    let mut edit = EditTag {
+      item_ids,
+      read: None,
+      starred: None
+    };
+
+    for (key, on) in
+      [("a", true), ("r", false)]
+    {
+      for tag in form_values(pairs, key)
+      {
+        match normalize(tag).as_str() {
+          | READ => {
+            edit.read = Some(on)
+          }
+          | KEPT_UNREAD => {
+            edit.read = Some(!on)
+          }
+          | STARRED => {
+            edit.starred = Some(on)
+          }
+          | _ => {}
+        }
+      }
+    }
+
+    Ok(edit)
+  }
+}
+
+/// A `mark-all-as-read` request.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct MarkAllRead {
+  pub stream:        StreamId,
+  /// `ts`: only items published
+  /// before, in epoch milliseconds.
+  pub older_than_ms: Option<i64>
+}
+
+impl MarkAllRead {
+  pub fn parse(
+    pairs: &[(String, String)]
+  ) -> Result<Self, String> {
+    let stream = StreamId::parse(
+      form_value(pairs, "s")
+        .ok_or("s required")?
+    )?;
+
+    let older_than_ms =
+      form_value(pairs, "ts")
+        .filter(|ts| !ts.is_empty())
+        .map(|ts| {
+          ts.parse::<i64>()
+            .map(|us| us / 1000)
+            .map_err(|_| {
+              format!(
+                "invalid ts: {ts}"
+              )
+            })
+        })
+        .transpose()?;
+
+    Ok(Self {
+      stream,
+      older_than_ms
+    })
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+  pub user_id:         String,
+  pub user_name:       String,
+  pub user_profile_id: String,
+  pub user_email:      String
+}
+
+impl UserInfo {
+  pub fn new(
+    user_id: i64,
+    username: &str
+  ) -> Self {
+    Self {
+      user_id:         user_id
+        .to_string(),
+      user_name:       username
+        .to_string(),
+      user_profile_id: user_id
+        .to_string(),
+      user_email:      username
+        .to_string()
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionList {
+  pub subscriptions: Vec<Subscription>
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+  pub id:         String,
+  pub title:      String,
+  pub categories: Vec<Category>,
+  pub url:        String,
+  pub html_url:   String,
+  pub icon_url:   String
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Category {
+  pub id:    String,
+  pub label: String
+}
+
+impl SubscriptionList {
+  pub fn new(
+    feeds: &[CompatFeed]
+  ) -> Self {
+    Self {
+      subscriptions: feeds
+        .iter()
+        .map(|feed| {
+          Subscription {
+            id:         StreamId::Feed(
+              feed.id.clone()
+            )
+            .to_id(),
+            title:      feed
+              .title
+              .clone(),
+            categories: feed
+              .folders
+              .iter()
+              .map(|f| {
+                Category {
+                  id:
+                    StreamId::Label(
+                      f.name.clone()
+                    )
+                    .to_id(),
+                  label: f.name.clone()
+                }
+              })
+              .collect(),
+            url:        feed
+              .url
+              .clone(),
+            html_url:   feed
+              .site_url
+              .clone(),
+            icon_url:   String::new()
+          }
+        })
+        .collect()
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagList {
+  pub tags: Vec<Tag>
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tag {
+  pub id:   String,
+  #[serde(
+    rename = "type",
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub kind: Option<String>
+}
+
+impl TagList {
+  /// The starred state, then a label
+  /// per folder name.
+  pub fn new(
+    folders: &[String]
+  ) -> Self {
+    let mut tags = vec![Tag {
+      id:   STARRED.to_string(),
+      kind: None
+    }];
+
+    tags.extend(folders.iter().map(
+      |name| {
+        Tag {
+          id:   StreamId::Label(
+            name.clone()
+          )
+          .to_id(),
+          kind: Some(
+            "folder".to_string()
+          )
+        }
+      }
+    ));
+
+    Self {
+      tags
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamContents {
+  pub id:           String,
+  pub updated:      i64,
+  pub items:        Vec<Item>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub continuation: Option<String>
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+  pub id:              String,
+  pub crawl_time_msec: String,
+  pub timestamp_usec:  String,
+  pub published:       i64,
+  pub title:           String,
+  pub canonical:       Vec<Link>,
+  pub alternate:       Vec<Link>,
+  pub summary:         Content,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub author:          Option<String>,
+  pub categories:      Vec<String>,
+  pub origin:          Origin
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Link {
+  pub href: String
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Content {
+  pub content: String
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Origin {
+  pub stream_id: String,
+  pub title:     String,
+  pub html_url:  String
+}
+
+impl Item {
+  /// `feed` is the entry's feed when
+  /// the user subscribes to it; its
+  /// folders become label categories.
+  pub fn new(
+    entry: &CompatEntry,
+    feed: Option<&CompatFeed>
+  ) -> Self {
+    let published_ms =
+      entry.published_ms.unwrap_or(0);
+
+    let mut categories =
+      vec![READING_LIST.to_string()];
+
+    if entry.read {
+      categories.push(READ.to_string());
+    }
+
+    if entry.starred {
+      categories
+        .push(STARRED.to_string());
+    }
+
+    if let Some(feed) = feed {
+      categories.extend(
+        feed.folders.iter().map(|f| {
+          StreamId::Label(
+            f.name.clone()
+          )
+          .to_id()
+        })
+      );
+    }
+
+    let links: Vec<Link> = entry
+      .link
+      .iter()
+      .map(|href| {
+        Link {
+          href: href.clone()
+        }
+      })
+      .collect();
+
+    Self {
+      id: item_id(entry.id),
+      crawl_time_msec: published_ms
+        .to_string(),
+      timestamp_usec: (published_ms
+        * 1000)
+        .to_string(),
+      published: published_ms / 1000,
+      title: entry
+        .title
+        .clone()
+        .unwrap_or_default(),
+      canonical: links.clone(),
+      alternate: links,
+      summary: Content {
+        content: entry
+          .content
+          .clone()
+          .unwrap_or_default()
+      },
+      author: entry.author.clone(),
+      categories,
+      origin: Origin {
+        stream_id: StreamId::Feed(
+          entry.feed_id.clone()
+        )
+        .to_id(),
+        title:     feed
+          .map(|f| f.title.clone())
+          .unwrap_or_else(|| {
+            entry.feed_id.clone()
+          }),
+        html_url:  feed
+          .map(|f| f.site_url.clone())
+          .unwrap_or_default()
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemRefs {
+  pub item_refs:    Vec<ItemRef>,
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub continuation: Option<String>
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemRef {
+  /// The short (decimal) item id.
+  pub id:                String,
+  pub direct_stream_ids: Vec<String>,
+  pub timestamp_usec:    String
+}
+
+impl ItemRef {
+  pub fn new(
+    entry: &CompatEntry
+  ) -> Self {
+    Self {
+      id:                entry
+        .id
+        .to_string(),
+      direct_stream_ids: Vec::new(),
+      timestamp_usec:    (entry
+        .published_ms
+        .unwrap_or(0)
+        * 1000)
+        .to_string()
+    }
+  }
+}
@@ -0,0 +1,111 @@
+//! Wire formats of the Google Reader
+//! and Fever APIs that mobile readers
+//! speak, and how subscribed feeds and
+//! entries map onto them. The server
+//! does the storage; this holds the
+//! request parsing and response shapes.
+
+pub mod fever;
+pub mod greader;
+
+/// A plain folder a feed is filed in.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct CompatFolder {
+  pub id:   i64,
+  pub name: String
+}
+
+/// A feed the user subscribes to.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct CompatFeed {
+  pub id:       String,
+  pub url:      String,
+  /// The registry title when known,
+  /// else the feed id.
+  pub title:    String,
+  pub site_url: String,
+  pub folders:  Vec<CompatFolder>
+}
+
+/// A feed item with the user's marks.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct CompatEntry {
+  pub id:           i64,
+  pub feed_id:      String,
+  pub title:        Option<String>,
+  pub link:         Option<String>,
+  pub author:       Option<String>,
+  /// The description, else the
+  /// summary.
+  pub content:      Option<String>,
+  pub published_ms: Option<i64>,
+  pub read:         bool,
+  pub starred:      bool
+}
+
+/// The site a feed URL belongs to:
+/// its scheme and host.
+pub fn site_url(
+  feed_url: &str
+) -> String {
+  reqwest::Url::parse(feed_url)
+    .ok()
+    .filter(|url| url.has_host())
+    .map(|url| {
+      url.origin().ascii_serialization()
+        + "/"
+    })
+    .unwrap_or_default()
+}
+
+/// Decodes an
+/// `application/x-www-form-urlencoded`
+/// body or query string; keys may
+/// repeat.
+pub fn form_pairs(
+  input: &str
+) -> Vec<(String, String)> {
+  let Ok(mut url) =
+    reqwest::Url::parse("http://form/")
+  else {
+    return Vec::new();
+  };
+
+  url.set_query(Some(input));
+
+  url
+    .query_pairs()
+    .map(|(k, v)| {
+      (k.into_owned(), v.into_owned())
+    })
+    .collect()
+}
+
+/// The first value of `key`.
+pub fn form_value<'a>(
+  pairs: &'a [(String, String)],
+  key: &str
+) -> Option<&'a str> {
+  pairs
+    .iter()
+    .find(|(k, _)| k == key)
+    .map(|(_, v)| v.as_str())
+}
+
+/// Every value of `key`, in order.
+pub fn form_values<'a>(
+  pairs: &'a [(String, String)],
+  key: &str
+) -> Vec<&'a str> {
+  pairs
+    .iter()
+    .filter(|(k, _)| k == key)
+    .map(|(_, v)| v.as_str())
+    .collect()
+}
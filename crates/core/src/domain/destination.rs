@@ -0,0 +1,91 @@
+//! Which addresses user-given URLs may
+//! reach. Feeds added by URL and
+//! webhook endpoints are fetched from
+//! the server's network, so they must
+//! not point at loopback, private,
+//! link-local or otherwise internal
+//! addresses.
+
+use std::net::{
+  IpAddr,
+  Ipv4Addr,
+  Ipv6Addr
+};
+
+/// Whether `ip` is a globally routable
+/// unicast address. IPv4-mapped IPv6
+/// addresses are judged by their IPv4
+/// address.
+pub fn is_public_ip(
+  ip: IpAddr
+) -> bool {
+  match ip {
+    | IpAddr::V4(v4) => {
+      is_public_v4(v4)
+    }
+    | IpAddr::V6(v6) => {
+      match v6.to_ipv4_mapped() {
+        | Some(v4) => is_public_v4(v4),
+        | None => is_public_v6(v6)
+      }
+    }
+  }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+  let [a, b, ..] = ip.octets();
+
+  !(ip.is_unspecified()
+    || ip.is_loopback()
+    || ip.is_private()
+    || ip.is_link_local()
+    || ip.is_broadcast()
+    || ip.is_multicast()
+    || ip.is_documentation()
+    // "This network", 0.0.0.0/8.
+    || a == 0
+    // Carrier-grade NAT, 100.64.0.0/10.
+    || (a == 100 && (b & 0xc0) == 64)
+    // Protocol assignments,
+    // 192.0.0.0/24.
+    || ip.octets()[..3] == [192, 0, 0]
+    // Benchmarking, 198.18.0.0/15.
+    || (a == 198 && (b & 0xfe) == 18)
+    // Reserved, 240.0.0.0/4.
+    || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+  let first = ip.segments()[0];
+
+  !(ip.is_unspecified()
+    || ip.is_loopback()
+    || ip.is_multicast()
+    // Unique local, fc00::/7.
+    || (first & 0xfe00) == 0xfc00
+    // Link-local, fe80::/10.
+    || (first & 0xffc0) == 0xfe80
+    // Site-local, fec0::/10.
+    || (first & 0xffc0) == 0xfec0
+    // Documentation, 2001:db8::/32.
+    || (first == 0x2001
+      && ip.segments()[1] == 0xdb8)
+    // IPv4-compatible and NAT64
+    // addresses hide an IPv4 one.
+    || ip.to_ipv4().is_some()
+    || (first == 0x64
+      && ip.segments()[1] == 0xff9b))
+}
+
+/// The address in `url`'s host when it
+/// is an IP literal rather than a name.
+pub fn literal_ip(
+  url: &reqwest::Url
+) -> Option<IpAddr> {
+  url
+    .host_str()?
+    .trim_start_matches('[')
+    .trim_end_matches(']')
+    .parse()
+    .ok()
+}
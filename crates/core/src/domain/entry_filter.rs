@@ -0,0 +1,245 @@
+//! Entry filter syntax for smart
+//! folders and filter rules:
+//! space-separated conditions
+//! that must all hold, each negated by
+//! a leading `-`.
+//!
+//! - `feed:<id>`, `category:<name>`,
+//!   `tag:<tag>`: the item's feed.
+//! - `author:<text>`, `title:<text>`:
+//!   substring of that field.
+//! - bare words or `"phrases"`:
+//!   substring of the title, summary or
+//!   description.
+//! - `is:read`, `is:unread`.
+//! - `age:<7d` (newer than), `age:>7d`
+//!   (older than); units `h`, `d`, `w`.
+//!
+//! Text matches ignore ASCII case.
+
+use crate::domain::search::tokens;
+
+const QUALIFIERS: &[&str] = &[
+  "feed", "category", "tag", "author",
+  "title", "is", "age"
+];
+
+/// What one condition tests.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+
+pub enum Condition {
+  Feed(String),
+  /// The feed's category or the
+  /// item's own.
+  Category(String),
+  Tag(String),
+  Author(String),
+  Title(String),
+  /// Title, summary or description.
+  Text(String),
+  Read(bool),
+  /// Published within this many
+  /// milliseconds.
+  NewerThan(i64),
+  /// Published longer ago than this
+  /// many milliseconds.
+  OlderThan(i64)
+}
+
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+
+pub struct FilterTerm {
+  pub condition: Condition,
+  pub negated:   bool
+}
+
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+
+pub struct EntryFilter {
+  pub terms: Vec<FilterTerm>
+}
+
+/// What a filter rule does to the
+/// entries its filter matches.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub enum RuleAction {
+  Read,
+  Star,
+  /// Adds the rule's label.
+  Label,
+  /// Drops the entry from the user's
+  /// lists, search and counts.
+  Hide
+}
+
+impl RuleAction {
+  /// Reads `read`, `star`, `label` or
+  /// `hide`.
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    match raw {
+      | "read" => Ok(RuleAction::Read),
+      | "star" => Ok(RuleAction::Star),
+      | "label" => {
+        Ok(RuleAction::Label)
+      }
+      | "hide" => Ok(RuleAction::Hide),
+      | _ => {
+        Err(format!(
+          "invalid action: {raw}"
+        ))
+      }
+    }
+  }
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      | RuleAction::Read => "read",
+      | RuleAction::Star => "star",
+      | RuleAction::Label => "label",
+      | RuleAction::Hide => "hide"
+    }
+  }
+}
+
+/// Parses a filter expression.
+pub fn parse_filter(
+  expr: &str
+) -> Result<EntryFilter, String> {
+  let mut terms = Vec::new();
+
+  for token in tokens(expr, QUALIFIERS)
+  {
+    if token.value.is_empty() {
+      continue;
+    }
+
+    let value = token.value;
+
+    let condition = match token
+      .qualifier
+      .as_deref()
+    {
+      | Some("feed") => {
+        Condition::Feed(value)
+      }
+      | Some("category") => {
+        Condition::Category(value)
+      }
+      | Some("tag") => {
+        Condition::Tag(value)
+      }
+      | Some("author") => {
+        Condition::Author(value)
+      }
+      | Some("title") => {
+        Condition::Title(value)
+      }
+      | Some("is") => {
+        match value
+          .to_ascii_lowercase()
+          .as_str()
+        {
+          | "read" => {
+            Condition::Read(true)
+          }
+          | "unread" => {
+            Condition::Read(false)
+          }
+          | _ => {
+            return Err(format!(
+              "invalid is: value: \
+               {value}"
+            ));
+          }
+        }
+      }
+      | Some("age") => {
+        parse_age(&value)?
+      }
+      | _ => Condition::Text(value)
+    };
+
+    terms.push(FilterTerm {
+      condition,
+      negated: token.negated
+    });
+  }
+
+  if terms.is_empty() {
+    return Err(
+      "filter needs at least one \
+       condition"
+        .to_string()
+    );
+  }
+
+  Ok(EntryFilter {
+    terms
+  })
+}
+
+/// `<7d`, `>2w` or `12h`; no operator
+/// means newer than.
+fn parse_age(
+  value: &str
+) -> Result<Condition, String> {
+  let invalid =
+    || format!("invalid age: {value}");
+
+  let (older, rest) =
+    if let Some(rest) =
+      value.strip_prefix('>')
+    {
+      (true, rest)
+    } else {
+      (
+        false,
+        value
+          .strip_prefix('<')
+          .unwrap_or(value)
+      )
+    };
+
+  let unit_ms = match rest
+    .chars()
+    .last()
+    .map(|c| c.to_ascii_lowercase())
+  {
+    | Some('h') => 3_600_000,
+    | Some('d') => 86_400_000,
+    | Some('w') => 7 * 86_400_000,
+    | _ => return Err(invalid())
+  };
+
+  let count: i64 = rest
+    [..rest.len() - 1]
+    .parse()
+    .map_err(|_| invalid())?;
+
+  if count <= 0 {
+    return Err(invalid());
+  }
+
+  let ms = count
+    .checked_mul(unit_ms)
+    .ok_or_else(invalid)?;
+
+  Ok(
+    if older {
+      Condition::OlderThan(ms)
+    } else {
+      Condition::NewerThan(ms)
+    }
+  )
+}
@@ -18,7 +18,10 @@ pub enum LinkPhase {
   NeedsHead,
   NeedsGet,
   Sleeping,
-  ErrorBackoff
+  ErrorBackoff,
+  /// The source answered 410 Gone; the
+  /// feed is no longer scheduled.
+  Archived
 }
 
 #[derive(Debug, Clone)]
@@ -121,6 +124,12 @@ impl LinkState {
           state: state.clone()
         }
       }
+      | LinkPhase::Archived => {
+        NextAction::SleepUntil {
+          at_ms: state
+            .next_action_at_ms
+        }
+      }
     }
   }
 
@@ -145,7 +154,16 @@ impl LinkState {
       phase,
       note,
       consecutive_error_count
-    ) = if is_error {
+    ) = if is_gone(result.status) {
+      (
+        state.backoff_index,
+        LinkPhase::Archived,
+        Some("head-gone".to_string()),
+        state
+          .consecutive_error_count
+          .saturating_add(1)
+      )
+    } else if is_error {
       (
         state
           .backoff_index
@@ -252,7 +270,16 @@ impl LinkState {
       phase,
       note,
       consecutive_error_count
-    ) = if is_error {
+    ) = if is_gone(result.status) {
+      (
+        state.backoff_index,
+        LinkPhase::Archived,
+        Some("get-gone".to_string()),
+        state
+          .consecutive_error_count
+          .saturating_add(1)
+      )
+    } else if is_error {
       (
         state
           .backoff_index
@@ -410,3 +437,9 @@ pub fn compute_delay_seconds(
 fn is_error_status(code: u16) -> bool {
   (400..=599).contains(&code)
 }
+
+fn is_gone(
+  status: Option<u16>
+) -> bool {
+  status == Some(410)
+}
@@ -1,7 +1,31 @@
 //! Core domain types and logic:
 //! configuration models, link-state
-//! machine, and hashing helpers.
+//! machine, retention policy, payload
+//! archive records, backup schedule,
+//! write-behind batching, the feed
+//! registry, public destination
+//! checks, entry search and filter
+//! syntax, signed list cursors, entry
+//! stream scopes, the Google Reader
+//! and Fever wire formats, realtime
+//! event ids and logs, webhook
+//! signing and retries, hashing and
+//! text diff helpers.
 
+pub mod archive;
+pub mod backup;
+pub mod compat;
+pub mod cursor;
+pub mod destination;
+pub mod entry_filter;
 pub mod hashing;
 pub mod link_state;
 pub mod model;
+pub mod realtime;
+pub mod registry;
+pub mod retention;
+pub mod search;
+pub mod stream;
+pub mod text_diff;
+pub mod webhook;
+pub mod write_behind;
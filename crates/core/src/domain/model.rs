@@ -11,11 +11,62 @@ use serde::{
   Serialize
 };
 
+use crate::domain::archive::ArchiveConfig;
+use crate::domain::backup::BackupConfig;
+use crate::domain::registry::RegistryConfig;
+use crate::domain::retention::RetentionConfig;
+use crate::domain::write_behind::WriteBehindConfig;
+
 #[derive(
   Debug, Clone, Serialize, Deserialize,
 )]
 pub struct DomainConfig {
-  pub max_concurrent_requests: usize
+  pub max_concurrent_requests: usize,
+  pub http: Option<HttpProfile>
+}
+
+/// HTTP client overrides for one
+/// domain. Domains with equal profiles
+/// share a client.
+#[derive(
+  Debug,
+  Clone,
+  Default,
+  PartialEq,
+  Eq,
+  Hash,
+  Serialize,
+  Deserialize,
+)]
+pub struct HttpProfile {
+  pub connect_timeout_seconds:
+    Option<u64>,
+  pub read_timeout_seconds: Option<u64>,
+  pub proxy: Option<String>,
+  pub ca_bundle: Option<PathBuf>,
+  pub http_version: Option<HttpVersion>,
+  pub user_agent: Option<String>
+}
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq,
+  Hash,
+  Serialize,
+  Deserialize,
+)]
+pub enum HttpVersion {
+  Http1,
+  /// HTTP/2 where TLS negotiates it
+  /// through ALPN, else HTTP/1.1.
+  Http2,
+  /// Cleartext HTTP/2 sent without
+  /// negotiation, for `http://`
+  /// origins known to speak h2c.
+  H2c
 }
 
 #[derive(
@@ -68,11 +119,26 @@ pub struct WatchConfig {
   pub fetch_body_on_change:  bool,
   pub max_body_bytes: Option<u64>,
   pub max_items_per_fetch: Option<u64>,
+  pub next_page_selector:
+    Option<String>,
+  pub max_pages:             u64,
+  /// Cap on the stored `any_change`
+  /// diff.
+  pub max_diff_bytes:        u64,
   pub item_selector: Option<String>,
   pub item_identity:
     Option<WatchItemIdentity>,
   pub item_identity_attr:
     Option<String>,
+  pub extractor: WatchExtractorKind,
+  pub title_extractor:
+    Option<WatchExtractorKind>,
+  pub link_extractor:
+    Option<WatchExtractorKind>,
+  pub summary_extractor:
+    Option<WatchExtractorKind>,
+  pub published_extractor:
+    Option<WatchExtractorKind>,
   pub title_selector: Option<String>,
   pub link_selector: Option<String>,
   pub summary_selector: Option<String>,
@@ -152,6 +218,21 @@ pub enum WatchEmitMode {
   Digest
 }
 
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq,
+  Serialize,
+  Deserialize,
+)]
+pub enum WatchExtractorKind {
+  Css,
+  Xpath,
+  Regex
+}
+
 #[derive(
   Debug,
   Clone,
@@ -199,7 +280,12 @@ pub struct AppConfig {
   pub timezone: Tz,
   pub domains:
     HashMap<String, DomainConfig>,
-  pub state_history_sample_rate: f64
+  pub state_history_sample_rate: f64,
+  pub retention: RetentionConfig,
+  pub archive: ArchiveConfig,
+  pub backup: BackupConfig,
+  pub write_behind: WriteBehindConfig,
+  pub registry: RegistryConfig
 }
 
 #[derive(Debug, Clone)]
@@ -257,5 +343,19 @@ pub struct GetResult {
   pub last_modified:      Option<i64>, /* epoch millis */
   pub error: Option<ErrorKind>,
   pub latency_ms:         u64,
-  pub set_cookie_headers: Vec<String>
+  pub set_cookie_headers: Vec<String>,
+  /// All response headers, kept for
+  /// the payload archive.
+  pub headers: Vec<(String, String)>,
+  pub final_url: Option<String>,
+  pub redirects: Vec<RedirectHop>
+}
+
+/// One followed redirect: `from`
+/// answered `status` pointing at `to`.
+#[derive(Debug, Clone)]
+pub struct RedirectHop {
+  pub status: u16,
+  pub from:   String,
+  pub to:     String
 }
@@ -0,0 +1,206 @@
+//! Realtime update streams: the
+//! channel the fetcher signals new
+//! items on, the ids clients resume
+//! from, the log of recent read-state
+//! changes replayed on resume, and
+//! unread count deltas.
+
+use std::collections::{
+  BTreeMap,
+  BTreeSet,
+  VecDeque
+};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// Postgres channel the fetcher
+/// notifies, with the feed id as
+/// payload, when it stores items.
+pub const ITEMS_CHANNEL: &str =
+  "pulsewire_items";
+
+/// Where a client is in its stream:
+/// the newest item it was sent and
+/// the last read-state change it saw.
+/// Written `<item_id>-<seq>`.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub struct EventId {
+  pub item_id: i64,
+  pub seq:     u64
+}
+
+impl fmt::Display for EventId {
+  fn fmt(
+    &self,
+    f: &mut fmt::Formatter<'_>
+  ) -> fmt::Result {
+    write!(
+      f,
+      "{}-{}",
+      self.item_id, self.seq
+    )
+  }
+}
+
+impl FromStr for EventId {
+  type Err = String;
+
+  fn from_str(
+    raw: &str
+  ) -> Result<Self, Self::Err> {
+    let invalid = || {
+      format!("invalid event id: {raw}")
+    };
+
+    let (item_id, seq) = raw
+      .trim()
+      .split_once('-')
+      .ok_or_else(invalid)?;
+
+    Ok(EventId {
+      item_id: item_id
+        .parse()
+        .map_err(|_| invalid())?,
+      seq:     seq
+        .parse()
+        .map_err(|_| invalid())?
+    })
+  }
+}
+
+/// The most recent events, numbered
+/// in order. Numbering starts where
+/// the caller says, so a log started
+/// later (after a restart) continues
+/// above any earlier one and older
+/// positions read as lost.
+#[derive(Debug, Clone)]
+
+pub struct EventLog<T> {
+  /// Events at or below this are gone.
+  floor:    u64,
+  next:     u64,
+  capacity: usize,
+  events:   VecDeque<(u64, T)>
+}
+
+impl<T: Clone> EventLog<T> {
+  /// An empty log whose first event
+  /// is `first`, keeping at most
+  /// `capacity` events.
+  pub fn new(
+    first: u64,
+    capacity: usize
+  ) -> Self {
+    let first = first.max(1);
+
+    Self {
+      floor:    first - 1,
+      next:     first,
+      capacity: capacity.max(1),
+      events:   VecDeque::new()
+    }
+  }
+
+  /// The number of the latest event,
+  /// or the floor when there is none.
+  pub fn last(&self) -> u64 {
+    self.next - 1
+  }
+
+  /// Appends an event, dropping the
+  /// oldest when full, and returns its
+  /// number.
+  pub fn push(
+    &mut self,
+    event: T
+  ) -> u64 {
+    let seq = self.next;
+    self.next += 1;
+
+    self.events.push_back((seq, event));
+
+    if self.events.len() > self.capacity
+      && let Some((dropped, _)) =
+        self.events.pop_front()
+    {
+      self.floor = dropped;
+    }
+
+    seq
+  }
+
+  /// Events after `seq`, or `None` if
+  /// some were dropped or `seq` is not
+  /// from this log.
+  pub fn since(
+    &self,
+    seq: u64
+  ) -> Option<Vec<(u64, T)>> {
+    if seq < self.floor
+      || seq > self.last()
+    {
+      return None;
+    }
+
+    Some(
+      self
+        .events
+        .iter()
+        .filter(|(s, _)| *s > seq)
+        .cloned()
+        .collect()
+    )
+  }
+}
+
+/// A feed whose unread count changed.
+#[derive(
+  Debug, Clone, PartialEq, Eq, Serialize,
+)]
+
+pub struct UnreadChange {
+  pub feed_id:      String,
+  pub unread_count: i64,
+  pub delta:        i64
+}
+
+/// Feeds whose unread count differs
+/// between two per-feed counts; feeds
+/// missing from either count zero.
+pub fn unread_changes(
+  before: &BTreeMap<String, i64>,
+  after: &BTreeMap<String, i64>
+) -> Vec<UnreadChange> {
+  let feeds: BTreeSet<&String> = before
+    .keys()
+    .chain(after.keys())
+    .collect();
+
+  feeds
+    .into_iter()
+    .filter_map(|feed_id| {
+      let old = before
+        .get(feed_id)
+        .copied()
+        .unwrap_or(0);
+      let new = after
+        .get(feed_id)
+        .copied()
+        .unwrap_or(0);
+
+      (old != new).then(|| {
+        UnreadChange {
+          feed_id:      feed_id.clone(),
+          unread_count: new,
+          delta:        new - old
+        }
+      })
+    })
+    .collect()
+}
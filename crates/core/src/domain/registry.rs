@@ -0,0 +1,101 @@
+//! Feed registry: feeds users add by
+//! URL through the server, which the
+//! fetcher polls alongside its TOML
+//! feeds once approved.
+
+use crate::domain::hashing::sha256_hex;
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum RegistryStatus {
+  /// Waiting for an admin.
+  Pending,
+  Approved,
+  Rejected
+}
+
+impl RegistryStatus {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      | RegistryStatus::Pending => {
+        "pending"
+      }
+      | RegistryStatus::Approved => {
+        "approved"
+      }
+      | RegistryStatus::Rejected => {
+        "rejected"
+      }
+    }
+  }
+
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    match raw {
+      | "pending" => Ok(Self::Pending),
+      | "approved" => {
+        Ok(Self::Approved)
+      }
+      | "rejected" => {
+        Ok(Self::Rejected)
+      }
+      | other => {
+        Err(format!(
+          "unknown registry status \
+           '{other}'"
+        ))
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RegistryFeed {
+  pub id:             String,
+  pub url:            String,
+  /// The feed's own title when it was
+  /// validated.
+  pub title:          Option<String>,
+  /// Server user id of whoever added
+  /// it.
+  pub added_by:       i64,
+  pub status:         RegistryStatus,
+  pub created_at_ms:  i64,
+  pub reviewed_at_ms: Option<i64>
+}
+
+/// `[registry]` section: whether and
+/// how often the fetcher picks up
+/// approved registry feeds.
+#[derive(Debug, Clone)]
+pub struct RegistryConfig {
+  pub enabled:          bool,
+  /// Category the registry feeds are
+  /// scheduled under.
+  pub category:         String,
+  pub interval_seconds: u64
+}
+
+/// Stable feed id for a URL added
+/// through the registry; the `u-`
+/// prefix keeps it apart from config
+/// feed ids.
+pub fn registry_feed_id(
+  url: &str
+) -> String {
+  let digest =
+    sha256_hex(url.trim().as_bytes());
+
+  format!("u-{}", &digest[..16])
+}
+
+/// Whether `id` is a registry feed's,
+/// which the fetcher polls through a
+/// public-only client.
+pub fn is_registry_feed_id(
+  id: &str
+) -> bool {
+  id.starts_with("u-")
+}
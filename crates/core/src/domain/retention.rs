@@ -0,0 +1,184 @@
+//! Retention policy: per-table age and
+//! row-count limits for fetcher
+//! history, with category and feed
+//! overrides.
+
+use std::collections::HashMap;
+
+/// Tables the retention job prunes, in
+/// the order it visits them. Items go
+/// before payloads because deleting a
+/// payload cascades to its items.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum RetentionTable {
+  FetchEvents,
+  StateHistory,
+  Items,
+  Payloads,
+  /// Follows the payload limits.
+  ArchiveIndex
+}
+
+impl RetentionTable {
+  pub const ALL: [RetentionTable; 5] = [
+    RetentionTable::FetchEvents,
+    RetentionTable::StateHistory,
+    RetentionTable::Items,
+    RetentionTable::Payloads,
+    RetentionTable::ArchiveIndex
+  ];
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      | RetentionTable::FetchEvents => {
+        "fetch_events"
+      }
+      | RetentionTable::StateHistory => {
+        "feed_state_history"
+      }
+      | RetentionTable::Items => {
+        "feed_items"
+      }
+      | RetentionTable::Payloads => {
+        "feed_payloads"
+      }
+      | RetentionTable::ArchiveIndex => {
+        "payload_archive_index"
+      }
+    }
+  }
+}
+
+/// Limits for one table. Rows older
+/// than `max_age_days` or beyond the
+/// newest `max_rows` of a feed are
+/// pruned; `None` means unbounded.
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Default,
+  PartialEq,
+  Eq,
+)]
+pub struct RetentionLimit {
+  pub max_age_days: Option<u64>,
+  pub max_rows:     Option<u64>
+}
+
+impl RetentionLimit {
+  pub fn is_unbounded(&self) -> bool {
+    self.max_age_days.is_none()
+      && self.max_rows.is_none()
+  }
+
+  /// Field-wise fallback to a broader
+  /// limit.
+  pub fn or(
+    self,
+    fallback: RetentionLimit
+  ) -> RetentionLimit {
+    RetentionLimit {
+      max_age_days: self
+        .max_age_days
+        .or(fallback.max_age_days),
+      max_rows:     self
+        .max_rows
+        .or(fallback.max_rows)
+    }
+  }
+
+  /// Epoch millis before which rows
+  /// are too old, if an age is set.
+  pub fn cutoff_ms(
+    &self,
+    now_ms: i64
+  ) -> Option<i64> {
+    self.max_age_days.map(|days| {
+      now_ms.saturating_sub(
+        (days as i64)
+          .saturating_mul(86_400_000)
+      )
+    })
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+  pub fetch_events:  RetentionLimit,
+  pub state_history: RetentionLimit,
+  pub items:         RetentionLimit,
+  pub payloads:      RetentionLimit
+}
+
+impl RetentionPolicy {
+  pub fn limit(
+    &self,
+    table: RetentionTable
+  ) -> RetentionLimit {
+    match table {
+      | RetentionTable::FetchEvents => {
+        self.fetch_events
+      }
+      | RetentionTable::StateHistory => {
+        self.state_history
+      }
+      | RetentionTable::Items => {
+        self.items
+      }
+      | RetentionTable::Payloads
+      | RetentionTable::ArchiveIndex => {
+        self.payloads
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+  pub enabled:          bool,
+  pub interval_seconds: u64,
+  pub batch_size:       u64,
+  /// Postgres schema holding the
+  /// server's read state; items unread
+  /// there are never pruned.
+  pub server_schema:    String,
+  pub defaults:         RetentionPolicy,
+  pub categories:
+    HashMap<String, RetentionPolicy>,
+  pub feeds:
+    HashMap<String, RetentionPolicy>
+}
+
+impl RetentionConfig {
+  /// Limit for one feed's table: feed
+  /// override, then category, then the
+  /// section defaults.
+  pub fn limit_for(
+    &self,
+    feed_id: &str,
+    category: &str,
+    table: RetentionTable
+  ) -> RetentionLimit {
+    let scoped =
+      |map: &HashMap<
+        String,
+        RetentionPolicy
+      >,
+       key: &str| {
+        map
+          .get(key)
+          .map(|p| p.limit(table))
+          .unwrap_or_default()
+      };
+
+    scoped(&self.feeds, feed_id)
+      .or(scoped(
+        &self.categories,
+        category
+      ))
+      .or(self.defaults.limit(table))
+  }
+}
@@ -0,0 +1,342 @@
+//! Entry search syntax: words,
+//! `"quoted phrases"`, `-exclusions`,
+//! `OR` between alternatives, and the
+//! `title:` and `feed:` qualifiers.
+//! Parsed queries render as an SQLite
+//! FTS5 match expression or as
+//! Postgres `to_tsquery` text.
+
+/// A word or phrase to match.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+
+pub struct SearchTerm {
+  /// Consecutive words; more than one
+  /// makes a phrase.
+  pub words:      Vec<String>,
+  pub title_only: bool,
+  pub negated:    bool
+}
+
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+
+pub struct ParsedSearch {
+  /// Alternatives separated by `OR`;
+  /// all terms of one must match.
+  pub groups: Vec<Vec<SearchTerm>>,
+  /// `feed:` qualifiers; any of them.
+  pub feed_ids:          Vec<String>,
+  /// `-feed:` qualifiers.
+  pub excluded_feed_ids: Vec<String>
+}
+
+/// Parses `q`. Words are split on
+/// anything that is not a letter or a
+/// digit, so `foo-bar` searches the
+/// phrase "foo bar". Unknown
+/// qualifiers are searched as text.
+pub fn parse_search(
+  q: &str
+) -> Result<ParsedSearch, String> {
+  let mut parsed = ParsedSearch {
+    groups:            vec![Vec::new()],
+    feed_ids:          Vec::new(),
+    excluded_feed_ids: Vec::new()
+  };
+
+  for token in
+    tokens(q, &["title", "feed"])
+  {
+    if token.is_or() {
+      parsed.groups.push(Vec::new());
+      continue;
+    }
+
+    match token.qualifier.as_deref() {
+      | Some("feed") => {
+        if token.value.is_empty() {
+          continue;
+        }
+
+        if token.negated {
+          parsed
+            .excluded_feed_ids
+            .push(token.value);
+        } else {
+          parsed
+            .feed_ids
+            .push(token.value);
+        }
+      }
+      | qualifier => {
+        let words = words(&token.value);
+
+        if words.is_empty() {
+          continue;
+        }
+
+        if let Some(group) =
+          parsed.groups.last_mut()
+        {
+          group.push(SearchTerm {
+            words,
+            title_only: qualifier
+              == Some("title"),
+            negated: token.negated
+          });
+        }
+      }
+    }
+  }
+
+  parsed
+    .groups
+    .retain(|g| !g.is_empty());
+
+  if parsed.groups.is_empty() {
+    return Err(
+      "q needs at least one word to \
+       search for"
+        .to_string()
+    );
+  }
+
+  if parsed.groups.iter().any(|g| {
+    g.iter().all(|t| t.negated)
+  }) {
+    return Err(
+      "every OR alternative needs a \
+       word that is not excluded"
+        .to_string()
+    );
+  }
+
+  Ok(parsed)
+}
+
+impl ParsedSearch {
+  /// FTS5 match expression over the
+  /// `title` column and the rest.
+  pub fn fts5_match(&self) -> String {
+    self
+      .groups
+      .iter()
+      .map(|group| {
+        let (negated, positive): (
+          Vec<_>,
+          Vec<_>
+        ) = group
+          .iter()
+          .partition(|t| t.negated);
+
+        let positive = positive
+          .iter()
+          .map(|t| fts5_term(t))
+          .collect::<Vec<_>>()
+          .join(" AND ");
+
+        if negated.is_empty() {
+          format!("({positive})")
+        } else {
+          let negated = negated
+            .iter()
+            .map(|t| fts5_term(t))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+          format!(
+            "(({positive}) NOT \
+             ({negated}))"
+          )
+        }
+      })
+      .collect::<Vec<_>>()
+      .join(" OR ")
+  }
+
+  /// `to_tsquery` text; title terms
+  /// are limited to weight `A`.
+  pub fn tsquery(&self) -> String {
+    self
+      .groups
+      .iter()
+      .map(|group| {
+        let terms = group
+          .iter()
+          .map(tsquery_term)
+          .collect::<Vec<_>>()
+          .join(" & ");
+
+        format!("({terms})")
+      })
+      .collect::<Vec<_>>()
+      .join(" | ")
+  }
+}
+
+fn fts5_term(
+  term: &SearchTerm
+) -> String {
+  // Words hold no quotes, so the
+  // phrase needs no escaping.
+  let phrase = format!(
+    "\"{}\"",
+    term.words.join(" ")
+  );
+
+  if term.title_only {
+    format!("title : {phrase}")
+  } else {
+    phrase
+  }
+}
+
+fn tsquery_term(
+  term: &SearchTerm
+) -> String {
+  let weight = if term.title_only {
+    ":A"
+  } else {
+    ""
+  };
+
+  let phrase = term
+    .words
+    .iter()
+    .map(|w| format!("'{w}'{weight}"))
+    .collect::<Vec<_>>()
+    .join(" <-> ");
+
+  if term.negated {
+    format!("!({phrase})")
+  } else if term.words.len() > 1 {
+    format!("({phrase})")
+  } else {
+    phrase
+  }
+}
+
+fn words(value: &str) -> Vec<String> {
+  value
+    .split(|c: char| {
+      !c.is_alphanumeric()
+    })
+    .filter(|w| !w.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+/// One space-separated piece of a
+/// query: `-qualifier:value`, with the
+/// value optionally `"quoted"`.
+pub(crate) struct Token {
+  pub(crate) negated:   bool,
+  pub(crate) qualifier: Option<String>,
+  pub(crate) value:     String,
+  pub(crate) quoted:    bool
+}
+
+impl Token {
+  fn is_or(&self) -> bool {
+    !self.negated
+      && !self.quoted
+      && self.qualifier.is_none()
+      && self.value == "OR"
+  }
+}
+
+/// Splits `q` into tokens; only the
+/// given `qualifiers` are recognised,
+/// anything else stays in the value.
+pub(crate) fn tokens(
+  q: &str,
+  qualifiers: &[&str]
+) -> Vec<Token> {
+  let chars: Vec<char> =
+    q.chars().collect();
+  let mut out = Vec::new();
+  let mut i = 0;
+
+  while i < chars.len() {
+    if chars[i].is_whitespace() {
+      i += 1;
+      continue;
+    }
+
+    let negated = chars[i] == '-';
+
+    if negated {
+      i += 1;
+    }
+
+    // `name:` followed by a value.
+    let mut qualifier = None;
+    let mut j = i;
+
+    while j < chars.len()
+      && chars[j].is_ascii_alphabetic()
+    {
+      j += 1;
+    }
+
+    if j > i
+      && chars.get(j) == Some(&':')
+    {
+      let name: String = chars[i..j]
+        .iter()
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+      if qualifiers
+        .contains(&name.as_str())
+      {
+        qualifier = Some(name);
+        i = j + 1;
+      }
+    }
+
+    let quoted =
+      chars.get(i) == Some(&'"');
+
+    let value: String = if quoted {
+      let end = chars[i + 1..]
+        .iter()
+        .position(|&c| c == '"')
+        .map(|p| i + 1 + p)
+        .unwrap_or(chars.len());
+
+      let value = chars[i + 1..end]
+        .iter()
+        .collect();
+
+      i = end + 1;
+
+      value
+    } else {
+      let end = chars[i..]
+        .iter()
+        .position(|c| c.is_whitespace())
+        .map(|p| i + p)
+        .unwrap_or(chars.len());
+
+      let value =
+        chars[i..end].iter().collect();
+
+      i = end;
+
+      value
+    };
+
+    out.push(Token {
+      negated,
+      qualifier,
+      value: value.trim().to_string(),
+      quoted
+    });
+  }
+
+  out
+}
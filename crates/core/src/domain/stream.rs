@@ -0,0 +1,98 @@
+//! Entry streams: which feeds a user's
+//! entry lists, searches and counts
+//! draw from, and which read states
+//! they keep.
+
+/// Feeds an entry stream covers.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub enum StreamScope {
+  /// Every fetched feed; admins only.
+  All,
+  /// The user's subscriptions.
+  Subscriptions,
+  /// The user's subscriptions plus
+  /// entries they starred in feeds
+  /// they have since left.
+  SubscriptionsOrStarred,
+  /// The user's favorite feeds.
+  Favorites,
+  /// Feeds in one of the user's
+  /// folders.
+  Folder(i64)
+}
+
+impl StreamScope {
+  /// Reads a `scope` parameter:
+  /// `all`, `subscriptions`,
+  /// `favorites` or `folder:<id>`.
+  /// Defaults to subscriptions.
+  pub fn parse(
+    raw: Option<&str>
+  ) -> Result<Self, String> {
+    let raw = raw.unwrap_or_default();
+
+    match raw {
+      | "" | "subscriptions" => {
+        Ok(StreamScope::Subscriptions)
+      }
+      | "all" => Ok(StreamScope::All),
+      | "favorites" => {
+        Ok(StreamScope::Favorites)
+      }
+      | _ => {
+        raw
+          .strip_prefix("folder:")
+          .and_then(|id| {
+            id.parse().ok()
+          })
+          .map(StreamScope::Folder)
+          .ok_or_else(|| {
+            format!(
+              "invalid scope: {raw}"
+            )
+          })
+      }
+    }
+  }
+}
+
+/// Read states an entry stream keeps.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+
+pub enum ReadFilter {
+  All,
+  Read,
+  Unread
+}
+
+impl ReadFilter {
+  /// Reads a `read` parameter: `all`,
+  /// `read` or `unread`. Defaults to
+  /// all.
+  pub fn parse(
+    raw: Option<&str>
+  ) -> Result<Self, String> {
+    match raw {
+      | None | Some("all") => {
+        Ok(ReadFilter::All)
+      }
+      | Some("read") => {
+        Ok(ReadFilter::Read)
+      }
+      | Some("unread") => {
+        Ok(ReadFilter::Unread)
+      }
+      | Some(other) => {
+        Err(format!(
+          "invalid read filter: \
+           {other}"
+        ))
+      }
+    }
+  }
+}
@@ -0,0 +1,80 @@
+//! Line-level diffs between watch
+//! text snapshots.
+
+use similar::{
+  ChangeTag,
+  TextDiff
+};
+
+/// Default upper bound on a stored
+/// diff, overridden by a watch's
+/// `max_diff_bytes`. Longer diffs are
+/// cut at a line boundary and end with
+/// [`TRUNCATED_MARKER`].
+pub const DEFAULT_MAX_DIFF_BYTES: u64 =
+  16 * 1024;
+
+pub const TRUNCATED_MARKER: &str =
+  "~ diff truncated";
+
+/// Renders removed lines as `- ` and
+/// added lines as `+ `, skipping
+/// unchanged ones. Returns `None` when
+/// the snapshots match.
+pub fn line_diff(
+  old: &str,
+  new: &str,
+  max_bytes: usize
+) -> Option<String> {
+  let old = terminated(old);
+  let new = terminated(new);
+
+  let diff =
+    TextDiff::from_lines(&old, &new);
+
+  let mut out = String::new();
+  let mut changed = false;
+
+  for change in diff.iter_all_changes()
+  {
+    let prefix = match change.tag() {
+      | ChangeTag::Delete => "- ",
+      | ChangeTag::Insert => "+ ",
+      | ChangeTag::Equal => continue
+    };
+
+    changed = true;
+
+    let line = format!(
+      "{prefix}{}\n",
+      change
+        .value()
+        .trim_end_matches('\n')
+    );
+
+    if out.len() + line.len()
+      > max_bytes
+    {
+      out.push_str(TRUNCATED_MARKER);
+      break;
+    }
+
+    out.push_str(&line);
+  }
+
+  changed
+    .then(|| out.trim_end().to_string())
+}
+
+/// Ends non-empty text with a newline
+/// so a changed last line is not also
+/// reported as removed and re-added.
+fn terminated(text: &str) -> String {
+  if text.is_empty()
+    || text.ends_with('\n')
+  {
+    text.to_string()
+  } else {
+    format!("{text}\n")
+  }
+}
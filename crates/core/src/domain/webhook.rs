@@ -0,0 +1,203 @@
+//! Outgoing webhooks: how deliveries
+//! are signed, the states they move
+//! through and when failed ones are
+//! retried.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use hmac::{
+  Hmac,
+  Mac
+};
+use sha2::Sha256;
+
+/// The event sent for new entries.
+pub const NEW_ENTRIES_EVENT: &str =
+  "entries.new";
+
+pub const EVENT_HEADER: &str =
+  "X-Pulsewire-Event";
+pub const DELIVERY_HEADER: &str =
+  "X-Pulsewire-Delivery";
+pub const TIMESTAMP_HEADER: &str =
+  "X-Pulsewire-Timestamp";
+pub const SIGNATURE_HEADER: &str =
+  "X-Pulsewire-Signature";
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum DeliveryStatus {
+  /// Waiting for its next attempt.
+  Pending,
+  Delivered,
+  /// Out of attempts; kept for the
+  /// dead-letter view until sent
+  /// again by hand.
+  Dead
+}
+
+impl DeliveryStatus {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      | DeliveryStatus::Pending => {
+        "pending"
+      }
+      | DeliveryStatus::Delivered => {
+        "delivered"
+      }
+      | DeliveryStatus::Dead => "dead"
+    }
+  }
+
+  pub fn parse(
+    raw: &str
+  ) -> Result<Self, String> {
+    match raw {
+      | "pending" => Ok(Self::Pending),
+      | "delivered" => {
+        Ok(Self::Delivered)
+      }
+      | "dead" => Ok(Self::Dead),
+      | other => {
+        Err(format!(
+          "unknown delivery status \
+           '{other}'"
+        ))
+      }
+    }
+  }
+}
+
+/// How often and how far apart a
+/// delivery is attempted.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub struct RetryPolicy {
+  pub max_attempts: u32,
+  /// Wait after the first failure;
+  /// doubled after each further one.
+  pub backoff:      Duration,
+  pub max_backoff:  Duration
+}
+
+impl RetryPolicy {
+  /// The wait before the next attempt
+  /// after `attempts` failed ones, or
+  /// `None` once they are used up.
+  pub fn next_delay(
+    &self,
+    attempts: u32
+  ) -> Option<Duration> {
+    if attempts == 0
+      || attempts >= self.max_attempts
+    {
+      return None;
+    }
+
+    let factor = 1u32
+      .checked_shl(attempts - 1)
+      .unwrap_or(u32::MAX);
+
+    Some(
+      self
+        .backoff
+        .saturating_mul(factor)
+        .min(self.max_backoff)
+    )
+  }
+}
+
+/// The signature of a delivery:
+/// `sha256=` and the hex HMAC-SHA256
+/// of `<timestamp>.<body>` keyed with
+/// the webhook's secret.
+pub fn sign(
+  secret: &str,
+  timestamp: i64,
+  body: &[u8]
+) -> String {
+  format!(
+    "sha256={}",
+    hex::encode(
+      mac(secret, timestamp, body)
+        .finalize()
+        .into_bytes()
+    )
+  )
+}
+
+/// Checks a signature as a receiver
+/// would, in constant time.
+pub fn verify(
+  secret: &str,
+  timestamp: i64,
+  body: &[u8],
+  signature: &str
+) -> bool {
+  let Some(sig) = signature
+    .strip_prefix("sha256=")
+    .and_then(|h| hex::decode(h).ok())
+  else {
+    return false;
+  };
+
+  mac(secret, timestamp, body)
+    .verify_slice(&sig)
+    .is_ok()
+}
+
+/// Headers of a delivery attempt.
+pub fn delivery_headers(
+  secret: &str,
+  event: &str,
+  delivery_id: i64,
+  timestamp: i64,
+  body: &[u8]
+) -> HashMap<String, String> {
+  HashMap::from([
+    (
+      "Content-Type".to_string(),
+      "application/json".to_string()
+    ),
+    (
+      EVENT_HEADER.to_string(),
+      event.to_string()
+    ),
+    (
+      DELIVERY_HEADER.to_string(),
+      delivery_id.to_string()
+    ),
+    (
+      TIMESTAMP_HEADER.to_string(),
+      timestamp.to_string()
+    ),
+    (
+      SIGNATURE_HEADER.to_string(),
+      sign(secret, timestamp, body)
+    )
+  ])
+}
+
+fn mac(
+  secret: &str,
+  timestamp: i64,
+  body: &[u8]
+) -> Hmac<Sha256> {
+  let mut mac =
+    Hmac::<Sha256>::new_from_slice(
+      secret.as_bytes()
+    )
+    .expect(
+      "HMAC takes keys of any size"
+    );
+
+  mac.update(
+    format!("{timestamp}.").as_bytes()
+  );
+  mac.update(body);
+
+  mac
+}
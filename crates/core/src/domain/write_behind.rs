@@ -0,0 +1,16 @@
+//! Write-behind batching of fetch
+//! events and state snapshots: how
+//! many writes are grouped into one
+//! transaction and how long the first
+//! one may wait.
+
+#[derive(Debug, Clone)]
+pub struct WriteBehindConfig {
+  pub enabled:   bool,
+  /// Queued writes that trigger a
+  /// flush before the interval ends.
+  pub max_batch: usize,
+  /// Longest a queued write waits for
+  /// others to join its batch.
+  pub flush_ms:  u64
+}
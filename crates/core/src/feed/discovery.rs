@@ -0,0 +1,74 @@
+//! Finds the feeds an HTML page
+//! advertises with
+//! `<link rel="alternate">`.
+
+use scraper::{
+  Html,
+  Selector
+};
+
+/// Link types taken for feeds.
+const FEED_TYPES: &[&str] = &[
+  "application/rss+xml",
+  "application/atom+xml",
+  "application/feed+json",
+  "application/json"
+];
+
+/// Absolute URLs of the feeds `html`
+/// links to, in page order without
+/// duplicates; relative links resolve
+/// against `page_url`.
+pub fn feed_links(
+  html: &str,
+  page_url: &str
+) -> Vec<String> {
+  let Ok(selector) = Selector::parse(
+    "link[rel~=\"alternate\"][href]"
+  ) else {
+    return Vec::new();
+  };
+
+  let Ok(base) =
+    reqwest::Url::parse(page_url)
+  else {
+    return Vec::new();
+  };
+
+  let document =
+    Html::parse_document(html);
+
+  let mut links: Vec<String> =
+    Vec::new();
+
+  for node in document.select(&selector)
+  {
+    let kind = node
+      .value()
+      .attr("type")
+      .unwrap_or_default()
+      .trim()
+      .to_ascii_lowercase();
+
+    if !FEED_TYPES.contains(&&*kind) {
+      continue;
+    }
+
+    let Some(url) = node
+      .value()
+      .attr("href")
+      .and_then(|href| {
+        base.join(href.trim()).ok()
+      })
+      .map(String::from)
+    else {
+      continue;
+    };
+
+    if !links.contains(&url) {
+      links.push(url);
+    }
+  }
+
+  links
+}
@@ -1,3 +1,9 @@
-//! Feed parsing utilities.
+//! Feed parsing utilities, feed
+//! discovery in HTML pages, OPML
+//! subscription lists and published
+//! feed documents.
 
+pub mod discovery;
+pub mod opml;
 pub mod parser;
+pub mod publish;
@@ -0,0 +1,339 @@
+//! Reads and writes OPML 2.0
+//! subscription lists. Folders are
+//! flat here, so nested outlines map
+//! to folder paths joined with
+//! [`FOLDER_SEPARATOR`] and back.
+
+use sxd_document::dom::{
+  ChildOfElement,
+  ChildOfRoot,
+  Element
+};
+use sxd_document::parser;
+
+/// Joins the names of nested outlines
+/// into one folder name.
+pub const FOLDER_SEPARATOR: &str =
+  " / ";
+
+/// A feed outline.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub struct OpmlFeed {
+  pub title:    Option<String>,
+  pub xml_url:  String,
+  pub html_url: Option<String>,
+  /// The enclosing outlines' names,
+  /// joined; `None` at the top level.
+  pub folder:   Option<String>
+}
+
+/// The feed outlines of an OPML
+/// document in document order.
+/// Outlines without an `xmlUrl` are
+/// folders; empty ones are dropped.
+pub fn parse_opml(
+  xml: &str
+) -> Result<Vec<OpmlFeed>, String> {
+  let package = parser::parse(xml)
+    .map_err(|e| {
+      format!("invalid OPML: {e}")
+    })?;
+
+  let document = package.as_document();
+
+  let Some(opml) = document
+    .root()
+    .children()
+    .into_iter()
+    .find_map(|child| {
+      match child {
+        | ChildOfRoot::Element(e) => {
+          Some(e)
+        }
+        | _ => None
+      }
+    })
+    .filter(|e| {
+      e.name().local_part() == "opml"
+    })
+  else {
+    return Err(
+      "invalid OPML: missing <opml> \
+       root"
+        .to_string()
+    );
+  };
+
+  let Some(body) = child_elements(opml)
+    .into_iter()
+    .find(|e| {
+      e.name().local_part() == "body"
+    })
+  else {
+    return Err(
+      "invalid OPML: missing <body>"
+        .to_string()
+    );
+  };
+
+  let mut feeds = Vec::new();
+
+  collect_outlines(
+    body,
+    &mut Vec::new(),
+    &mut feeds
+  );
+
+  Ok(feeds)
+}
+
+/// An OPML 2.0 document listing
+/// `feeds`, nested by folder. Folders
+/// sort by path after the top-level
+/// feeds; feeds keep their order.
+pub fn write_opml(
+  title: &str,
+  feeds: &[OpmlFeed]
+) -> String {
+  let mut folders: Vec<(
+    Option<&str>,
+    Vec<&OpmlFeed>
+  )> = Vec::new();
+
+  for feed in feeds {
+    let folder = feed.folder.as_deref();
+
+    match folders
+      .iter_mut()
+      .find(|(f, _)| *f == folder)
+    {
+      | Some((_, list)) => {
+        list.push(feed)
+      }
+      | None => {
+        folders
+          .push((folder, vec![feed]))
+      }
+    }
+  }
+
+  let mut out = String::from(
+    "<?xml version=\"1.0\" \
+     encoding=\"UTF-8\"?>\n<opml \
+     version=\"2.0\">\n  <head>\n    \
+     <title>"
+  );
+
+  out.push_str(&escape(title));
+  out.push_str(
+    "</title>\n  </head>\n  <body>\n"
+  );
+
+  // Each folder path opens below the
+  // part it shares with the last one.
+  let mut open: Vec<&str> = Vec::new();
+
+  folders.sort_by_cached_key(
+    |(f, _)| {
+      f.map(|f| {
+        f.split(FOLDER_SEPARATOR)
+          .map(str::to_string)
+          .collect::<Vec<_>>()
+      })
+    }
+  );
+
+  for (folder, list) in &folders {
+    let path: Vec<&str> = folder
+      .map(|f| {
+        f.split(FOLDER_SEPARATOR)
+          .collect()
+      })
+      .unwrap_or_default();
+
+    let shared = open
+      .iter()
+      .zip(&path)
+      .take_while(|(a, b)| a == b)
+      .count();
+
+    while open.len() > shared {
+      open.pop();
+      indent(&mut out, open.len());
+      out.push_str("</outline>\n");
+    }
+
+    for name in &path[shared..] {
+      indent(&mut out, open.len());
+      out.push_str(&format!(
+        "<outline text=\"{0}\" \
+         title=\"{0}\">\n",
+        escape(name)
+      ));
+      open.push(name);
+    }
+
+    for feed in list {
+      indent(&mut out, open.len());
+      write_feed(&mut out, feed);
+    }
+  }
+
+  while !open.is_empty() {
+    open.pop();
+    indent(&mut out, open.len());
+    out.push_str("</outline>\n");
+  }
+
+  out.push_str("  </body>\n</opml>\n");
+  out
+}
+
+fn collect_outlines(
+  parent: Element<'_>,
+  path: &mut Vec<String>,
+  feeds: &mut Vec<OpmlFeed>
+) {
+  for outline in child_elements(parent)
+  {
+    if outline.name().local_part()
+      != "outline"
+    {
+      continue;
+    }
+
+    let title = attr(outline, "text")
+      .or_else(|| {
+        attr(outline, "title")
+      });
+
+    if let Some(xml_url) =
+      attr(outline, "xmlUrl")
+    {
+      feeds.push(OpmlFeed {
+        title,
+        xml_url,
+        html_url: attr(
+          outline, "htmlUrl"
+        ),
+        folder: (!path.is_empty())
+          .then(|| {
+            path.join(FOLDER_SEPARATOR)
+          })
+      });
+
+      // Feeds rarely nest; their
+      // children stay in the same
+      // folder.
+      collect_outlines(
+        outline, path, feeds
+      );
+    } else {
+      let pushed = title
+        .map(|name| path.push(name))
+        .is_some();
+
+      collect_outlines(
+        outline, path, feeds
+      );
+
+      if pushed {
+        path.pop();
+      }
+    }
+  }
+}
+
+fn child_elements(
+  element: Element<'_>
+) -> Vec<Element<'_>> {
+  element
+    .children()
+    .into_iter()
+    .filter_map(|child| {
+      match child {
+        | ChildOfElement::Element(
+          e
+        ) => Some(e),
+        | _ => None
+      }
+    })
+    .collect()
+}
+
+/// A trimmed, non-empty attribute;
+/// names match case-insensitively as
+/// exporters disagree on `xmlUrl`.
+fn attr(
+  element: Element<'_>,
+  name: &str
+) -> Option<String> {
+  element
+    .attributes()
+    .into_iter()
+    .find(|a| {
+      a.name()
+        .local_part()
+        .eq_ignore_ascii_case(name)
+    })
+    .map(|a| {
+      a.value().trim().to_string()
+    })
+    .filter(|v| !v.is_empty())
+}
+
+fn write_feed(
+  out: &mut String,
+  feed: &OpmlFeed
+) {
+  let title = escape(
+    feed
+      .title
+      .as_deref()
+      .unwrap_or(&feed.xml_url)
+  );
+
+  out.push_str(&format!(
+    "<outline type=\"rss\" \
+     text=\"{title}\" \
+     title=\"{title}\" xmlUrl=\"{}\"",
+    escape(&feed.xml_url)
+  ));
+
+  if let Some(html_url) = &feed.html_url
+  {
+    out.push_str(&format!(
+      " htmlUrl=\"{}\"",
+      escape(html_url)
+    ));
+  }
+
+  out.push_str("/>\n");
+}
+
+fn indent(
+  out: &mut String,
+  depth: usize
+) {
+  out.push_str(&"  ".repeat(depth + 2));
+}
+
+fn escape(value: &str) -> String {
+  let mut out =
+    String::with_capacity(value.len());
+
+  for c in value.chars() {
+    match c {
+      | '&' => out.push_str("&amp;"),
+      | '<' => out.push_str("&lt;"),
+      | '>' => out.push_str("&gt;"),
+      | '"' => out.push_str("&quot;"),
+      | '\'' => out.push_str("&apos;"),
+      | c => out.push(c)
+    }
+  }
+
+  out
+}
@@ -24,8 +24,10 @@ pub struct FeedItem {
   pub guid:            Option<String>,
   pub published_at_ms: Option<i64>,
   pub category:        Option<String>,
+  pub author:          Option<String>,
   pub description:     Option<String>,
-  pub summary:         Option<String>
+  pub summary:         Option<String>,
+  pub diff:            Option<String>
 }
 
 #[derive(Debug, Clone)]
@@ -74,6 +76,14 @@ pub fn parse(
       .first()
       .map(|c| c.term.clone());
 
+    let author = e
+      .authors
+      .first()
+      .map(|p| {
+        p.name.trim().to_string()
+      })
+      .filter(|name| !name.is_empty());
+
     let summary = e
       .summary
       .as_ref()
@@ -97,8 +107,10 @@ pub fn parse(
       guid: Some(e.id),
       published_at_ms: published,
       category,
+      author,
       description: desc.clone(),
-      summary: summary.or(content)
+      summary: summary.or(content),
+      diff: None
     });
   }
 
@@ -0,0 +1,555 @@
+//! Writes entries back out as Atom,
+//! RSS 2.0 or JSON Feed 1.1 documents
+//! for re-syndication, with the
+//! validators conditional GETs compare.
+
+use chrono::{
+  DateTime,
+  SecondsFormat,
+  Utc
+};
+use serde_json::{
+  Map,
+  Value,
+  json
+};
+
+use super::parser::FeedItem;
+use crate::domain::hashing::sha256_hex;
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum FeedFormat {
+  Atom,
+  Rss,
+  Json
+}
+
+impl FeedFormat {
+  /// The format of a published feed's
+  /// file name: `feed.atom`,
+  /// `feed.rss` or `feed.json`.
+  pub fn from_file(
+    name: &str
+  ) -> Option<Self> {
+    match name {
+      | "feed.atom" => Some(Self::Atom),
+      | "feed.rss" => Some(Self::Rss),
+      | "feed.json" => Some(Self::Json),
+      | _ => None
+    }
+  }
+
+  pub fn file_name(
+    self
+  ) -> &'static str {
+    match self {
+      | Self::Atom => "feed.atom",
+      | Self::Rss => "feed.rss",
+      | Self::Json => "feed.json"
+    }
+  }
+
+  pub fn content_type(
+    self
+  ) -> &'static str {
+    match self {
+      | Self::Atom => {
+        "application/atom+xml; \
+         charset=utf-8"
+      }
+      | Self::Rss => {
+        "application/rss+xml; \
+         charset=utf-8"
+      }
+      | Self::Json => {
+        "application/feed+json; \
+         charset=utf-8"
+      }
+    }
+  }
+}
+
+/// The feed-level fields of a
+/// published document.
+#[derive(Debug, Clone)]
+pub struct Channel {
+  pub title:         String,
+  /// Where the document is served;
+  /// also the feed's id.
+  pub url:           String,
+  /// Falls back to the newest item.
+  pub updated_at_ms: Option<i64>
+}
+
+/// Renders `items`, newest first, as
+/// a document of `format`. Items
+/// without a guid are identified by
+/// their link.
+pub fn render(
+  format: FeedFormat,
+  channel: &Channel,
+  items: &[FeedItem]
+) -> String {
+  match format {
+    | FeedFormat::Atom => {
+      render_atom(channel, items)
+    }
+    | FeedFormat::Rss => {
+      render_rss(channel, items)
+    }
+    | FeedFormat::Json => {
+      render_json(channel, items)
+    }
+  }
+}
+
+/// The newest publication time of
+/// `items`.
+pub fn last_modified_ms(
+  items: &[FeedItem]
+) -> Option<i64> {
+  items
+    .iter()
+    .filter_map(|i| i.published_at_ms)
+    .max()
+}
+
+/// A strong entity tag for a rendered
+/// document.
+pub fn etag(body: &str) -> String {
+  format!(
+    "\"{}\"",
+    &sha256_hex(body.as_bytes())[..32]
+  )
+}
+
+/// Whether a conditional GET may be
+/// answered with `304 Not Modified`.
+/// Only the entity tag is compared:
+/// starring or labelling an older
+/// entry changes the document without
+/// a newer date.
+pub fn not_modified(
+  if_none_match: Option<&str>,
+  etag: &str
+) -> bool {
+  if_none_match.is_some_and(|tags| {
+    tags.split(',').any(|tag| {
+      let tag = tag.trim();
+
+      tag == "*"
+        || tag.trim_start_matches("W/")
+          == etag
+    })
+  })
+}
+
+fn render_atom(
+  channel: &Channel,
+  items: &[FeedItem]
+) -> String {
+  let updated =
+    updated_ms(channel, items);
+
+  let mut out = String::from(
+    "<?xml version=\"1.0\" \
+     encoding=\"UTF-8\"?>\n"
+  );
+
+  out.push_str(
+    "<feed xmlns=\"http://www.w3.org/\
+     2005/Atom\">\n"
+  );
+  push_element(
+    &mut out,
+    2,
+    "title",
+    &channel.title
+  );
+  push_element(
+    &mut out,
+    2,
+    "id",
+    &channel.url
+  );
+  out.push_str(&format!(
+    "  <link rel=\"self\" \
+     href=\"{}\"/>\n",
+    escape(&channel.url)
+  ));
+  push_element(
+    &mut out,
+    2,
+    "updated",
+    &rfc3339(updated)
+  );
+
+  for item in items {
+    out.push_str("  <entry>\n");
+    push_element(
+      &mut out,
+      4,
+      "title",
+      item
+        .title
+        .as_deref()
+        .unwrap_or("")
+    );
+    push_element(
+      &mut out,
+      4,
+      "id",
+      &item_id(channel, item)
+    );
+
+    if let Some(link) = &item.link {
+      out.push_str(&format!(
+        "    <link rel=\"alternate\" \
+         href=\"{}\"/>\n",
+        escape(link)
+      ));
+    }
+
+    let published = item
+      .published_at_ms
+      .unwrap_or(updated);
+
+    push_element(
+      &mut out,
+      4,
+      "published",
+      &rfc3339(published)
+    );
+    push_element(
+      &mut out,
+      4,
+      "updated",
+      &rfc3339(published)
+    );
+
+    if let Some(author) = &item.author {
+      out.push_str(&format!(
+        "    <author><name>{}</name></\
+         author>\n",
+        escape(author)
+      ));
+    }
+
+    if let Some(category) =
+      &item.category
+    {
+      out.push_str(&format!(
+        "    <category term=\"{}\"/>\n",
+        escape(category)
+      ));
+    }
+
+    if let Some(summary) = &item.summary
+    {
+      push_html(
+        &mut out, "summary", summary
+      );
+    }
+
+    if let Some(content) =
+      &item.description
+    {
+      push_html(
+        &mut out, "content", content
+      );
+    }
+
+    out.push_str("  </entry>\n");
+  }
+
+  out.push_str("</feed>\n");
+  out
+}
+
+fn render_rss(
+  channel: &Channel,
+  items: &[FeedItem]
+) -> String {
+  let mut out = String::from(
+    "<?xml version=\"1.0\" \
+     encoding=\"UTF-8\"?>\n"
+  );
+
+  out.push_str(
+    "<rss version=\"2.0\" \
+     xmlns:atom=\"http://www.w3.org/\
+     2005/Atom\">\n  <channel>\n"
+  );
+  push_element(
+    &mut out,
+    4,
+    "title",
+    &channel.title
+  );
+  push_element(
+    &mut out,
+    4,
+    "link",
+    &channel.url
+  );
+  push_element(
+    &mut out,
+    4,
+    "description",
+    &channel.title
+  );
+  out.push_str(&format!(
+    "    <atom:link rel=\"self\" \
+     href=\"{}\"/>\n",
+    escape(&channel.url)
+  ));
+  push_element(
+    &mut out,
+    4,
+    "lastBuildDate",
+    &to_datetime(updated_ms(
+      channel, items
+    ))
+    .to_rfc2822()
+  );
+
+  for item in items {
+    out.push_str("    <item>\n");
+
+    if let Some(title) = &item.title {
+      push_element(
+        &mut out, 6, "title", title
+      );
+    }
+
+    if let Some(link) = &item.link {
+      push_element(
+        &mut out, 6, "link", link
+      );
+    }
+
+    out.push_str(&format!(
+      "      <guid \
+       isPermaLink=\"false\">{}</\
+       guid>\n",
+      escape(&item_id(channel, item))
+    ));
+
+    if let Some(ms) =
+      item.published_at_ms
+    {
+      push_element(
+        &mut out,
+        6,
+        "pubDate",
+        &to_datetime(ms).to_rfc2822()
+      );
+    }
+
+    if let Some(category) =
+      &item.category
+    {
+      push_element(
+        &mut out, 6, "category",
+        category
+      );
+    }
+
+    if let Some(text) = item
+      .summary
+      .as_ref()
+      .or(item.description.as_ref())
+    {
+      push_element(
+        &mut out,
+        6,
+        "description",
+        text
+      );
+    }
+
+    out.push_str("    </item>\n");
+  }
+
+  out
+    .push_str("  </channel>\n</rss>\n");
+  out
+}
+
+fn render_json(
+  channel: &Channel,
+  items: &[FeedItem]
+) -> String {
+  let items: Vec<Value> = items
+    .iter()
+    .map(|item| {
+      let mut entry = Map::new();
+
+      entry.insert(
+        "id".into(),
+        item_id(channel, item).into()
+      );
+
+      let fields = [
+        ("url", item.link.clone()),
+        ("title", item.title.clone()),
+        (
+          "summary",
+          item.summary.clone()
+        ),
+        (
+          "content_html",
+          item.description.clone()
+        ),
+        (
+          "date_published",
+          item
+            .published_at_ms
+            .map(rfc3339)
+        )
+      ];
+
+      for (key, value) in fields {
+        if let Some(value) = value {
+          entry.insert(
+            key.into(),
+            value.into()
+          );
+        }
+      }
+
+      if let Some(author) = &item.author
+      {
+        entry.insert(
+          "authors".into(),
+          json!([{ "name": author }])
+        );
+      }
+
+      if let Some(category) =
+        &item.category
+      {
+        entry.insert(
+          "tags".into(),
+          json!([category])
+        );
+      }
+
+      Value::Object(entry)
+    })
+    .collect();
+
+  let document = json!({
+    "version": "https://jsonfeed.org/version/1.1",
+    "title": channel.title,
+    "feed_url": channel.url,
+    "items": items
+  });
+
+  let mut out =
+    serde_json::to_string_pretty(
+      &document
+    )
+    .expect(
+      "JSON values always serialize"
+    );
+
+  out.push('\n');
+  out
+}
+
+/// The channel's update time, else
+/// its newest item's, else the epoch.
+fn updated_ms(
+  channel: &Channel,
+  items: &[FeedItem]
+) -> i64 {
+  channel
+    .updated_at_ms
+    .or_else(|| last_modified_ms(items))
+    .unwrap_or(0)
+}
+
+fn item_id(
+  channel: &Channel,
+  item: &FeedItem
+) -> String {
+  item
+    .guid
+    .clone()
+    .or_else(|| item.link.clone())
+    .unwrap_or_else(|| {
+      format!(
+        "{}#{}",
+        channel.url,
+        sha256_hex(
+          item
+            .title
+            .as_deref()
+            .unwrap_or("")
+            .as_bytes()
+        )
+      )
+    })
+}
+
+fn to_datetime(
+  ms: i64
+) -> DateTime<Utc> {
+  DateTime::from_timestamp_millis(ms)
+    .unwrap_or_default()
+}
+
+fn rfc3339(ms: i64) -> String {
+  to_datetime(ms).to_rfc3339_opts(
+    SecondsFormat::Secs,
+    true
+  )
+}
+
+fn push_element(
+  out: &mut String,
+  indent: usize,
+  name: &str,
+  text: &str
+) {
+  out.push_str(&format!(
+    "{:indent$}<{name}>{}</{name}>\n",
+    "",
+    escape(text)
+  ));
+}
+
+/// Escaped HTML in an Atom text
+/// construct.
+fn push_html(
+  out: &mut String,
+  name: &str,
+  html: &str
+) {
+  out.push_str(&format!(
+    "    <{name} \
+     type=\"html\">{}</{name}>\n",
+    escape(html)
+  ));
+}
+
+fn escape(value: &str) -> String {
+  let mut out =
+    String::with_capacity(value.len());
+
+  for c in value.chars() {
+    match c {
+      | '&' => out.push_str("&amp;"),
+      | '<' => out.push_str("&lt;"),
+      | '>' => out.push_str("&gt;"),
+      | '"' => out.push_str("&quot;"),
+      | '\'' => out.push_str("&apos;"),
+      | c => out.push(c)
+    }
+  }
+
+  out
+}
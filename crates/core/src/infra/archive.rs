@@ -0,0 +1,427 @@
+//! Content-addressed raw payload
+//! archive. Each blob is a
+//! zstd-compressed `ArchivedResponse`
+//! keyed by the payload's sha256
+//! `content_hash`, kept either under a
+//! directory tree or in the
+//! `payload_archive` table. Blobs no
+//! payload references are pruned.
+
+use std::path::PathBuf;
+
+use crate::domain::archive::{
+  ArchiveConfig,
+  ArchiveStore,
+  ArchivedResponse
+};
+use crate::ports::repo::Repo;
+
+const BLOB_EXTENSION: &str = "zst";
+
+#[derive(Debug, Clone)]
+pub struct PayloadArchive {
+  store:      ArchiveStore,
+  directory:  PathBuf,
+  zstd_level: i32
+}
+
+impl PayloadArchive {
+  /// `None` when the archive is
+  /// disabled.
+  pub fn from_config(
+    cfg: &ArchiveConfig
+  ) -> Option<PayloadArchive> {
+    cfg.enabled.then(|| {
+      PayloadArchive {
+        store:      cfg.store,
+        directory:  cfg
+          .directory
+          .clone(),
+        zstd_level: cfg.zstd_level
+      }
+    })
+  }
+
+  /// Stores `response` unless a blob
+  /// with this hash already exists.
+  /// Returns whether a new blob was
+  /// written.
+  pub async fn put<R>(
+    &self,
+    repo: &R,
+    content_hash: &str,
+    response: &ArchivedResponse,
+    now_ms: i64
+  ) -> Result<bool, String>
+  where
+    R: Repo + ?Sized
+  {
+    check_hash(content_hash)?;
+
+    match self.store {
+      | ArchiveStore::Database => {
+        if repo
+          .archived_blob_exists(
+            content_hash
+          )
+          .await?
+        {
+          return Ok(false);
+        }
+
+        let data =
+          self.compress(response)?;
+
+        repo
+          .insert_archived_blob(
+            content_hash,
+            now_ms,
+            &data
+          )
+          .await?;
+
+        Ok(true)
+      }
+      | ArchiveStore::Filesystem => {
+        let path =
+          self.blob_path(content_hash);
+
+        if tokio::fs::try_exists(&path)
+          .await
+          .unwrap_or(false)
+        {
+          return Ok(false);
+        }
+
+        let data =
+          self.compress(response)?;
+
+        write_atomically(&path, &data)
+          .await?;
+
+        Ok(true)
+      }
+    }
+  }
+
+  pub async fn get<R>(
+    &self,
+    repo: &R,
+    content_hash: &str
+  ) -> Result<
+    Option<ArchivedResponse>,
+    String
+  >
+  where
+    R: Repo + ?Sized
+  {
+    check_hash(content_hash)?;
+
+    let data = match self.store {
+      | ArchiveStore::Database => {
+        repo
+          .archived_blob(content_hash)
+          .await?
+      }
+      | ArchiveStore::Filesystem => {
+        let path =
+          self.blob_path(content_hash);
+
+        match tokio::fs::read(&path).await
+        {
+          | Ok(data) => Some(data),
+          | Err(e)
+            if e.kind()
+              == std::io::ErrorKind::NotFound =>
+          {
+            None
+          }
+          | Err(e) => {
+            return Err(format!(
+              "read archive blob {}: {e}",
+              path.display()
+            ));
+          }
+        }
+      }
+    };
+
+    let Some(data) = data else {
+      return Ok(None);
+    };
+
+    let raw =
+      zstd::decode_all(data.as_slice())
+        .map_err(|e| {
+          format!(
+            "decompress archive blob \
+             {content_hash}: {e}"
+          )
+        })?;
+
+    ArchivedResponse::decode(&raw)
+      .map(Some)
+  }
+
+  /// Deletes (or, for a dry run,
+  /// counts) blobs whose hash no
+  /// payload row references, in
+  /// batches of `batch_size`.
+  pub async fn prune_orphans<R>(
+    &self,
+    repo: &R,
+    batch_size: u64,
+    dry_run: bool
+  ) -> Result<u64, String>
+  where
+    R: Repo + ?Sized
+  {
+    match self.store {
+      | ArchiveStore::Database => {
+        if dry_run {
+          return repo
+            .orphan_archived_blobs(
+              u64::MAX
+            )
+            .await
+            .map(|h| h.len() as u64);
+        }
+
+        let mut deleted = 0;
+
+        loop {
+          let orphans = repo
+            .orphan_archived_blobs(
+              batch_size
+            )
+            .await?;
+
+          deleted += repo
+            .delete_archived_blobs(
+              &orphans
+            )
+            .await?;
+
+          if (orphans.len() as u64)
+            < batch_size
+          {
+            return Ok(deleted);
+          }
+
+          tokio::task::yield_now()
+            .await;
+        }
+      }
+      | ArchiveStore::Filesystem => {
+        let hashes =
+          self.stored_hashes().await?;
+
+        let mut pruned = 0;
+
+        for chunk in
+          hashes
+            .chunks(batch_size.max(1)
+              as usize)
+        {
+          let referenced = repo
+            .referenced_content_hashes(
+              chunk
+            )
+            .await?;
+
+          for hash in
+            chunk.iter().filter(|h| {
+              !referenced.contains(*h)
+            })
+          {
+            if !dry_run {
+              let path =
+                self.blob_path(hash);
+
+              tokio::fs::remove_file(
+                &path
+              )
+              .await
+              .map_err(
+                |e| {
+                  format!(
+                    "remove archive \
+                     blob {}: {e}",
+                    path.display()
+                  )
+                }
+              )?;
+            }
+
+            pruned += 1;
+          }
+        }
+
+        Ok(pruned)
+      }
+    }
+  }
+
+  fn compress(
+    &self,
+    response: &ArchivedResponse
+  ) -> Result<Vec<u8>, String> {
+    zstd::encode_all(
+      response.encode().as_slice(),
+      self.zstd_level
+    )
+    .map_err(|e| {
+      format!(
+        "compress archive blob: {e}"
+      )
+    })
+  }
+
+  /// `<dir>/ab/cd/abcd….zst`
+  fn blob_path(
+    &self,
+    content_hash: &str
+  ) -> PathBuf {
+    self
+      .directory
+      .join(&content_hash[..2])
+      .join(&content_hash[2..4])
+      .join(format!(
+        "{content_hash}.\
+         {BLOB_EXTENSION}"
+      ))
+  }
+
+  /// Hashes of every blob in the
+  /// directory tree.
+  async fn stored_hashes(
+    &self
+  ) -> Result<Vec<String>, String> {
+    let mut out = Vec::new();
+    let mut dirs =
+      vec![self.directory.clone()];
+
+    while let Some(dir) = dirs.pop() {
+      let mut entries =
+        match tokio::fs::read_dir(&dir)
+          .await
+        {
+          | Ok(entries) => entries,
+          | Err(e)
+            if e.kind()
+              == std::io::ErrorKind::NotFound =>
+          {
+            continue;
+          }
+          | Err(e) => {
+            return Err(format!(
+              "read archive dir {}: {e}",
+              dir.display()
+            ));
+          }
+        };
+
+      while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| {
+          format!(
+            "read archive dir {}: {e}",
+            dir.display()
+          )
+        })?
+      {
+        let path = entry.path();
+
+        if path.is_dir() {
+          dirs.push(path);
+
+          continue;
+        }
+
+        if path
+          .extension()
+          .and_then(|e| e.to_str())
+          != Some(BLOB_EXTENSION)
+        {
+          continue;
+        }
+
+        if let Some(hash) = path
+          .file_stem()
+          .and_then(|s| s.to_str())
+          .filter(|s| {
+            check_hash(s).is_ok()
+          })
+        {
+          out.push(hash.to_string());
+        }
+      }
+    }
+
+    out.sort();
+
+    Ok(out)
+  }
+}
+
+/// Content hashes are lowercase sha256
+/// hex; anything else would escape the
+/// blob tree.
+fn check_hash(
+  content_hash: &str
+) -> Result<(), String> {
+  if content_hash.len() == 64
+    && content_hash.bytes().all(|b| {
+      b.is_ascii_digit()
+        || (b'a'..=b'f').contains(&b)
+    })
+  {
+    Ok(())
+  } else {
+    Err(format!(
+      "invalid content hash \
+       '{content_hash}'"
+    ))
+  }
+}
+
+async fn write_atomically(
+  path: &std::path::Path,
+  data: &[u8]
+) -> Result<(), String> {
+  if let Some(parent) = path.parent() {
+    tokio::fs::create_dir_all(parent)
+      .await
+      .map_err(|e| {
+        format!(
+          "create archive dir {}: {e}",
+          parent.display()
+        )
+      })?;
+  }
+
+  let tmp =
+    path.with_extension(format!(
+      "{BLOB_EXTENSION}.tmp-{}",
+      std::process::id()
+    ));
+
+  tokio::fs::write(&tmp, data)
+    .await
+    .map_err(|e| {
+      format!(
+        "write archive blob {}: {e}",
+        tmp.display()
+      )
+    })?;
+
+  tokio::fs::rename(&tmp, path)
+    .await
+    .map_err(|e| {
+      format!(
+        "rename archive blob {}: {e}",
+        path.display()
+      )
+    })
+}
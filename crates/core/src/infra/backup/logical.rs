@@ -0,0 +1,574 @@
+//! Logical dumps: each table's rows in
+//! key order as canonical
+//! dialect-neutral values,
+//! zstd-compressed, one file per
+//! table. A dump reads every table in
+//! one snapshot transaction; a restore
+//! inserts everything in one
+//! transaction.
+
+use std::fs::File;
+use std::io::{
+  BufReader,
+  BufWriter,
+  ErrorKind,
+  Read,
+  Write
+};
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use sqlx::{
+  Postgres,
+  Sqlite,
+  Transaction
+};
+
+use super::{
+  BATCH_ROWS,
+  BackupManifest,
+  TableEntry,
+  catalog_table,
+  compare,
+  ensure_empty
+};
+use crate::domain::model::AppConfig;
+use crate::infra::database::create_repo;
+use crate::infra::db_copy::tables::{
+  TABLES,
+  Table
+};
+use crate::infra::db_copy::{
+  Checksum,
+  Endpoint,
+  MAX_BINDS,
+  Row,
+  TableCheck,
+  Value,
+  postgres,
+  sqlite
+};
+use crate::infra::migrations;
+
+const TABLES_DIR: &str = "tables";
+
+/// Writes every table below
+/// `dest/tables`.
+pub(super) async fn dump(
+  source: &Endpoint,
+  tables: &[&Table],
+  dest: &Path,
+  zstd_level: i32
+) -> Result<Vec<TableEntry>, String> {
+  let dir = dest.join(TABLES_DIR);
+
+  std::fs::create_dir_all(&dir)
+    .map_err(|e| {
+      format!(
+        "create {}: {e}",
+        dir.display()
+      )
+    })?;
+
+  let mut tx =
+    Tx::begin(source, true).await?;
+
+  let mut entries = Vec::new();
+
+  for table in tables {
+    entries.push(
+      dump_table(
+        &mut tx,
+        source,
+        table,
+        &table_file(dest, table),
+        zstd_level
+      )
+      .await?
+    );
+  }
+
+  tx.commit().await?;
+
+  Ok(entries)
+}
+
+async fn dump_table(
+  tx: &mut Tx,
+  source: &Endpoint,
+  table: &Table,
+  path: &Path,
+  zstd_level: i32
+) -> Result<TableEntry, String> {
+  let io_err = |e: std::io::Error| {
+    format!(
+      "write {}: {e}",
+      path.display()
+    )
+  };
+
+  let file = File::create(path)
+    .map_err(io_err)?;
+
+  let mut encoder = zstd::Encoder::new(
+    BufWriter::new(file),
+    zstd_level
+  )
+  .map_err(io_err)?;
+
+  let mut sum = Checksum::default();
+  let mut last: Option<Row> = None;
+  let mut buf = Vec::new();
+
+  loop {
+    let batch = tx
+      .read_batch(
+        source,
+        table,
+        last.as_ref(),
+        BATCH_ROWS
+      )
+      .await?;
+
+    for row in &batch {
+      sum.add(row);
+      buf.clear();
+
+      for value in row {
+        value.write_canonical(&mut buf);
+      }
+
+      encoder
+        .write_all(&buf)
+        .map_err(io_err)?;
+    }
+
+    if (batch.len() as u64) < BATCH_ROWS
+    {
+      break;
+    }
+
+    last = batch.into_iter().last();
+  }
+
+  encoder
+    .finish()
+    .and_then(|mut w| w.flush())
+    .map_err(io_err)?;
+
+  let (rows, checksum) = sum.finish();
+
+  Ok(TableEntry {
+    name: table.name.to_string(),
+    rows,
+    checksum
+  })
+}
+
+/// Migrates the target to the dump's
+/// schema, loads every table and
+/// checks it against the manifest.
+pub(super) async fn restore(
+  cfg: &AppConfig,
+  src: &Path,
+  server_schema: &str,
+  force: bool,
+  manifest: &BackupManifest
+) -> Result<Vec<TableCheck>, String> {
+  create_repo(cfg.db_dialect, cfg)
+    .await?
+    .migrate(
+      &cfg.timezone,
+      cfg.default_poll_seconds
+    )
+    .await?;
+
+  if manifest.server_version.is_some() {
+    migrations::migrate_server(
+      cfg,
+      server_schema
+    )
+    .await?;
+  }
+
+  let target =
+    Endpoint::open(cfg, server_schema)
+      .await?;
+
+  let checks = match load(
+    &target, src, force, manifest
+  )
+  .await
+  {
+    | Ok(()) => {
+      compare(&target, manifest).await
+    }
+    | Err(e) => Err(e)
+  };
+
+  target.close().await;
+
+  checks
+}
+
+async fn load(
+  target: &Endpoint,
+  src: &Path,
+  force: bool,
+  manifest: &BackupManifest
+) -> Result<(), String> {
+  let mut entries = manifest
+    .tables
+    .iter()
+    .map(|e| {
+      catalog_table(&e.name)
+        .map(|t| (t, e))
+    })
+    .collect::<Result<Vec<_>, String>>(
+    )?;
+
+  // Foreign key order.
+  entries.sort_by_key(|(t, _)| {
+    TABLES
+      .iter()
+      .position(|c| c.name == t.name)
+  });
+
+  let mut existing = Vec::new();
+
+  if force {
+    for table in TABLES.iter().rev() {
+      if target.has_table(table).await?
+      {
+        existing.push(table);
+      }
+    }
+  } else {
+    ensure_empty(target).await?;
+  }
+
+  let mut tx =
+    Tx::begin(target, false).await?;
+
+  for table in existing {
+    tx.clear(target, table).await?;
+  }
+
+  for (table, entry) in entries {
+    let rows = load_table(
+      &mut tx,
+      target,
+      table,
+      &table_file(src, table)
+    )
+    .await?;
+
+    if rows != entry.rows {
+      return Err(format!(
+        "backup table {} holds {rows} \
+         row(s) but its manifest \
+         lists {}",
+        table.name, entry.rows
+      ));
+    }
+  }
+
+  tx.commit().await
+}
+
+async fn load_table(
+  tx: &mut Tx,
+  target: &Endpoint,
+  table: &Table,
+  path: &Path
+) -> Result<u64, String> {
+  let io_err = |e: std::io::Error| {
+    format!(
+      "read {}: {e}",
+      path.display()
+    )
+  };
+
+  let file =
+    File::open(path).map_err(io_err)?;
+
+  let mut reader = BufReader::new(
+    zstd::Decoder::new(file)
+      .map_err(io_err)?
+  );
+
+  // Stay under the bind parameter
+  // limits of both dialects.
+  let batch_size = BATCH_ROWS.min(
+    MAX_BINDS
+      / table.columns.len() as u64
+  ) as usize;
+
+  let mut batch =
+    Vec::with_capacity(batch_size);
+  let mut loaded = 0u64;
+
+  while let Some(row) =
+    read_row(&mut reader, table)
+      .map_err(io_err)?
+  {
+    batch.push(row);
+
+    if batch.len() == batch_size {
+      tx.insert_batch(
+        target, table, &batch
+      )
+      .await?;
+
+      loaded += batch.len() as u64;
+      batch.clear();
+    }
+  }
+
+  if !batch.is_empty() {
+    tx.insert_batch(
+      target, table, &batch
+    )
+    .await?;
+
+    loaded += batch.len() as u64;
+  }
+
+  if table.serial {
+    tx.reset_sequence(target, table)
+      .await?;
+  }
+
+  Ok(loaded)
+}
+
+/// Next row, or `None` at the end of
+/// the file.
+fn read_row<R>(
+  input: &mut R,
+  table: &Table
+) -> std::io::Result<Option<Row>>
+where
+  R: Read
+{
+  let mut row = Vec::with_capacity(
+    table.columns.len()
+  );
+
+  for (i, column) in
+    table.columns.iter().enumerate()
+  {
+    match Value::read_canonical(
+      input,
+      column.kind
+    ) {
+      | Ok(value) => row.push(value),
+      | Err(e)
+        if i == 0
+          && e.kind()
+            == ErrorKind::UnexpectedEof =>
+      {
+        return Ok(None);
+      }
+      | Err(e) => return Err(e)
+    }
+  }
+
+  Ok(Some(row))
+}
+
+fn table_file(
+  dir: &Path,
+  table: &Table
+) -> PathBuf {
+  dir.join(TABLES_DIR).join(format!(
+    "{}.rows.zst",
+    table.name
+  ))
+}
+
+/// A transaction on either side.
+enum Tx {
+  Sqlite(Transaction<'static, Sqlite>),
+  Postgres(
+    Transaction<'static, Postgres>
+  )
+}
+
+impl Tx {
+  /// A read-only transaction sees one
+  /// snapshot: Postgres is asked for
+  /// repeatable read, and a deferred
+  /// SQLite transaction in WAL mode
+  /// keeps the snapshot of its first
+  /// read.
+  async fn begin(
+    endpoint: &Endpoint,
+    read_only: bool
+  ) -> Result<Tx, String> {
+    let begin_err = |e: sqlx::Error| {
+      format!(
+        "backup begin transaction \
+         error: {e}"
+      )
+    };
+
+    match endpoint {
+      | Endpoint::Sqlite(pool) => {
+        Ok(Tx::Sqlite(
+          pool
+            .begin()
+            .await
+            .map_err(begin_err)?
+        ))
+      }
+      | Endpoint::Postgres {
+        pool,
+        ..
+      } => {
+        let mut tx = pool
+          .begin()
+          .await
+          .map_err(begin_err)?;
+
+        if read_only {
+          sqlx::query(
+            "SET TRANSACTION \
+             ISOLATION LEVEL \
+             REPEATABLE READ, READ \
+             ONLY"
+          )
+          .execute(&mut *tx)
+          .await
+          .map_err(begin_err)?;
+        }
+
+        Ok(Tx::Postgres(tx))
+      }
+    }
+  }
+
+  async fn read_batch(
+    &mut self,
+    endpoint: &Endpoint,
+    table: &Table,
+    after: Option<&Row>,
+    limit: u64
+  ) -> Result<Vec<Row>, String> {
+    match self {
+      | Tx::Sqlite(tx) => {
+        sqlite::read_batch(
+          &mut **tx, table, after,
+          limit
+        )
+        .await
+      }
+      | Tx::Postgres(tx) => {
+        postgres::read_batch(
+          &mut **tx,
+          &endpoint.qualified(table),
+          table,
+          after,
+          limit
+        )
+        .await
+      }
+    }
+  }
+
+  async fn insert_batch(
+    &mut self,
+    endpoint: &Endpoint,
+    table: &Table,
+    rows: &[Row]
+  ) -> Result<(), String> {
+    match self {
+      | Tx::Sqlite(tx) => {
+        sqlite::insert_batch(
+          &mut **tx, table, rows
+        )
+        .await
+      }
+      | Tx::Postgres(tx) => {
+        postgres::insert_batch(
+          &mut **tx,
+          &endpoint.qualified(table),
+          table,
+          rows
+        )
+        .await
+      }
+    }
+  }
+
+  async fn clear(
+    &mut self,
+    endpoint: &Endpoint,
+    table: &Table
+  ) -> Result<(), String> {
+    let sql = format!(
+      "DELETE FROM {}",
+      endpoint.qualified(table)
+    );
+
+    let result = match self {
+      | Tx::Sqlite(tx) => {
+        sqlx::query(&sql)
+          .execute(&mut **tx)
+          .await
+          .map(|_| ())
+      }
+      | Tx::Postgres(tx) => {
+        sqlx::query(&sql)
+          .execute(&mut **tx)
+          .await
+          .map(|_| ())
+      }
+    };
+
+    result.map_err(|e| {
+      format!(
+        "restore clear {} error: {e}",
+        table.name
+      )
+    })
+  }
+
+  /// See `Endpoint::reset_sequence`.
+  async fn reset_sequence(
+    &mut self,
+    endpoint: &Endpoint,
+    table: &Table
+  ) -> Result<(), String> {
+    match self {
+      | Tx::Sqlite(_) => Ok(()),
+      | Tx::Postgres(tx) => {
+        postgres::reset_sequence(
+          &mut **tx,
+          &endpoint.qualified(table)
+        )
+        .await
+      }
+    }
+  }
+
+  async fn commit(
+    self
+  ) -> Result<(), String> {
+    match self {
+      | Tx::Sqlite(tx) => {
+        tx.commit().await
+      }
+      | Tx::Postgres(tx) => {
+        tx.commit().await
+      }
+    }
+    .map_err(|e| {
+      format!(
+        "backup commit error: {e}"
+      )
+    })
+  }
+}
@@ -0,0 +1,850 @@
+//! Database backups and restores. A
+//! backup is a directory holding a
+//! `manifest.json`, stamped with the
+//! schema versions it was taken at and
+//! a row count and checksum per table,
+//! next to either a `VACUUM INTO`
+//! snapshot of a SQLite database or a
+//! logical dump: one zstd-compressed
+//! file of dialect-neutral rows per
+//! table, all read in one snapshot
+//! transaction. Postgres is always
+//! dumped logically; logical dumps
+//! restore into either dialect.
+//!
+//! Restores refuse backups written by
+//! a newer build. A SQLite snapshot
+//! from an older schema is migrated
+//! after it is put in place; a logical
+//! dump needs the exact schema version
+//! it was written at.
+
+mod logical;
+mod rotation;
+
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use serde::{
+  Deserialize,
+  Serialize
+};
+use sqlx::SqlitePool;
+use sqlx::sqlite::{
+  SqliteConnectOptions,
+  SqlitePoolOptions
+};
+
+pub use self::rotation::{
+  backup_name,
+  list_backups,
+  rotate_backups
+};
+use crate::domain::model::{
+  AppConfig,
+  SqlDialect
+};
+use crate::infra::database::create_repo;
+use crate::infra::db_copy::tables::{
+  TABLES,
+  Table
+};
+use crate::infra::db_copy::{
+  Endpoint,
+  TableCheck,
+  checksum,
+  ensure_current,
+  server_marker
+};
+use crate::infra::migrations::{
+  self,
+  Component,
+  known_migrations
+};
+
+pub const MANIFEST_FILE: &str =
+  "manifest.json";
+
+const SQLITE_FILE: &str =
+  "database.sqlite";
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Rows per read and insert batch.
+const BATCH_ROWS: u64 = 500;
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  PartialEq,
+  Eq,
+  Serialize,
+  Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupKind {
+  /// `database.sqlite`, restorable
+  /// into SQLite only.
+  SqliteFile,
+  /// `tables/<name>.rows.zst`.
+  Logical
+}
+
+#[derive(
+  Debug, Clone, Serialize, Deserialize,
+)]
+pub struct TableEntry {
+  pub name:     String,
+  pub rows:     u64,
+  pub checksum: String
+}
+
+#[derive(
+  Debug, Clone, Serialize, Deserialize,
+)]
+pub struct BackupManifest {
+  pub format:          u32,
+  pub kind:            BackupKind,
+  /// Dialect the backup was taken
+  /// from.
+  pub source:          String,
+  pub created_at_ms:   i64,
+  pub fetcher_version: i64,
+  /// `None` when the source had no
+  /// server tables.
+  pub server_version:  Option<i64>,
+  pub tables:          Vec<TableEntry>
+}
+
+impl BackupManifest {
+  pub fn total_rows(&self) -> u64 {
+    self
+      .tables
+      .iter()
+      .map(|t| t.rows)
+      .sum()
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RestoreReport {
+  pub manifest: BackupManifest,
+  /// Restored tables checked against
+  /// the manifest; empty when an older
+  /// snapshot was migrated after the
+  /// restore.
+  pub checks:   Vec<TableCheck>
+}
+
+/// Writes a backup of the configured
+/// database into `dest`, which must
+/// not exist or be empty. SQLite is
+/// snapshotted with `VACUUM INTO`
+/// unless `logical` is set. A failed
+/// backup leaves no directory behind.
+pub async fn backup_database(
+  cfg: &AppConfig,
+  dest: &Path,
+  server_schema: &str,
+  logical: bool,
+  zstd_level: i32,
+  now_ms: i64
+) -> Result<BackupManifest, String> {
+  prepare_dest(dest)?;
+
+  let written = write_backup(
+    cfg,
+    dest,
+    server_schema,
+    logical,
+    zstd_level,
+    now_ms
+  )
+  .await;
+
+  if written.is_err() {
+    let _ =
+      std::fs::remove_dir_all(dest);
+  }
+
+  written
+}
+
+async fn write_backup(
+  cfg: &AppConfig,
+  dest: &Path,
+  server_schema: &str,
+  logical: bool,
+  zstd_level: i32,
+  now_ms: i64
+) -> Result<BackupManifest, String> {
+  let source =
+    Endpoint::open(cfg, server_schema)
+      .await?;
+
+  let with_server = source
+    .has_table(server_marker())
+    .await?;
+
+  let fetcher_version = ensure_current(
+    cfg,
+    Component::Fetcher,
+    server_schema
+  )
+  .await?;
+
+  let server_version = if with_server {
+    Some(
+      ensure_current(
+        cfg,
+        Component::Server,
+        server_schema
+      )
+      .await?
+    )
+  } else {
+    None
+  };
+
+  let tables: Vec<&Table> = TABLES
+    .iter()
+    .filter(|t| {
+      with_server
+        || t.component
+          == Component::Fetcher
+    })
+    .collect();
+
+  let (kind, entries) = match &source {
+    | Endpoint::Sqlite(pool)
+      if !logical =>
+    {
+      let file = dest.join(SQLITE_FILE);
+
+      sqlx::query("VACUUM INTO ?1")
+        .bind(
+          file
+            .to_string_lossy()
+            .to_string()
+        )
+        .execute(pool)
+        .await
+        .map_err(|e| {
+          format!(
+            "backup vacuum into {} \
+             error: {e}",
+            file.display()
+          )
+        })?;
+
+      let snapshot = Endpoint::Sqlite(
+        open_snapshot(&file).await?
+      );
+
+      let entries = table_entries(
+        &snapshot, &tables
+      )
+      .await;
+
+      snapshot.close().await;
+
+      (BackupKind::SqliteFile, entries?)
+    }
+    | _ => {
+      (
+        BackupKind::Logical,
+        logical::dump(
+          &source, &tables, dest,
+          zstd_level
+        )
+        .await?
+      )
+    }
+  };
+
+  source.close().await;
+
+  let manifest = BackupManifest {
+    format: FORMAT_VERSION,
+    kind,
+    source: dialect_name(
+      cfg.db_dialect
+    )
+    .to_string(),
+    created_at_ms: now_ms,
+    fetcher_version,
+    server_version,
+    tables: entries
+  };
+
+  write_manifest(dest, &manifest)?;
+
+  Ok(manifest)
+}
+
+/// Restores a backup into the
+/// configured database. The target
+/// must hold no rows unless `force` is
+/// set, in which case its data is
+/// replaced.
+pub async fn restore_database(
+  cfg: &AppConfig,
+  src: &Path,
+  server_schema: &str,
+  force: bool
+) -> Result<RestoreReport, String> {
+  let manifest = read_manifest(src)?;
+
+  check_compatible(
+    &manifest,
+    cfg.db_dialect
+  )?;
+
+  let checks = match manifest.kind {
+    | BackupKind::SqliteFile => {
+      restore_file(
+        cfg,
+        src,
+        server_schema,
+        force,
+        &manifest
+      )
+      .await?
+    }
+    | BackupKind::Logical => {
+      logical::restore(
+        cfg,
+        src,
+        server_schema,
+        force,
+        &manifest
+      )
+      .await?
+    }
+  };
+
+  Ok(RestoreReport {
+    manifest,
+    checks
+  })
+}
+
+pub fn read_manifest(
+  dir: &Path
+) -> Result<BackupManifest, String> {
+  let path = dir.join(MANIFEST_FILE);
+
+  let text =
+    std::fs::read_to_string(&path)
+      .map_err(|e| {
+        format!(
+          "read {}: {e}",
+          path.display()
+        )
+      })?;
+
+  serde_json::from_str(&text).map_err(
+    |e| {
+      format!(
+        "parse {}: {e}",
+        path.display()
+      )
+    }
+  )
+}
+
+fn write_manifest(
+  dir: &Path,
+  manifest: &BackupManifest
+) -> Result<(), String> {
+  let json =
+    serde_json::to_string_pretty(
+      manifest
+    )
+    .map_err(|e| {
+      format!(
+        "encode backup manifest: {e}"
+      )
+    })?;
+
+  // Written last and renamed into
+  // place: a directory without a
+  // manifest is an unfinished backup.
+  let tmp = dir.join(format!(
+    "{MANIFEST_FILE}.tmp"
+  ));
+
+  std::fs::write(&tmp, json)
+    .and_then(|_| {
+      std::fs::rename(
+        &tmp,
+        dir.join(MANIFEST_FILE)
+      )
+    })
+    .map_err(|e| {
+      format!(
+        "write backup manifest in {}: \
+         {e}",
+        dir.display()
+      )
+    })
+}
+
+fn prepare_dest(
+  dest: &Path
+) -> Result<(), String> {
+  if dest.exists() {
+    let mut entries =
+      std::fs::read_dir(dest).map_err(
+        |e| {
+          format!(
+            "read {}: {e}",
+            dest.display()
+          )
+        }
+      )?;
+
+    if entries.next().is_some() {
+      return Err(format!(
+        "backup destination {} is not \
+         empty",
+        dest.display()
+      ));
+    }
+  }
+
+  std::fs::create_dir_all(dest).map_err(
+    |e| {
+      format!(
+        "create {}: {e}",
+        dest.display()
+      )
+    }
+  )
+}
+
+fn check_compatible(
+  manifest: &BackupManifest,
+  dialect: SqlDialect
+) -> Result<(), String> {
+  if manifest.format != FORMAT_VERSION {
+    return Err(format!(
+      "unsupported backup format {} \
+       (this build writes \
+       {FORMAT_VERSION})",
+      manifest.format
+    ));
+  }
+
+  for (component, version) in [
+    (
+      Component::Fetcher,
+      Some(manifest.fetcher_version)
+    ),
+    (
+      Component::Server,
+      manifest.server_version
+    )
+  ] {
+    let Some(version) = version else {
+      continue;
+    };
+
+    let latest = known_migrations(
+      component, dialect
+    )
+    .last()
+    .map(|m| m.version)
+    .unwrap_or(0);
+
+    if version > latest {
+      return Err(format!(
+        "backup {} schema is at \
+         version {version}, newer \
+         than this build understands \
+         ({latest}); upgrade before \
+         restoring",
+        component.as_str()
+      ));
+    }
+
+    if manifest.kind
+      == BackupKind::Logical
+      && version < latest
+    {
+      return Err(format!(
+        "logical backup {} schema is \
+         at version {version} but \
+         this build expects {latest}; \
+         restore it with the build \
+         that wrote it, then upgrade",
+        component.as_str()
+      ));
+    }
+  }
+
+  Ok(())
+}
+
+/// Whether the manifest was written at
+/// the schema this build's table
+/// catalog describes.
+fn is_current(
+  manifest: &BackupManifest,
+  dialect: SqlDialect
+) -> bool {
+  let latest = |component| {
+    known_migrations(component, dialect)
+      .last()
+      .map(|m| m.version)
+      .unwrap_or(0)
+  };
+
+  manifest.fetcher_version
+    == latest(Component::Fetcher)
+    && manifest
+      .server_version
+      .is_none_or(|v| {
+        v == latest(Component::Server)
+      })
+}
+
+async fn restore_file(
+  cfg: &AppConfig,
+  src: &Path,
+  server_schema: &str,
+  force: bool,
+  manifest: &BackupManifest
+) -> Result<Vec<TableCheck>, String> {
+  if cfg.db_dialect
+    != SqlDialect::Sqlite
+  {
+    return Err(
+      "a SQLite file backup restores \
+       into SQLite only; restore it \
+       there, then use `db copy`"
+        .to_string()
+    );
+  }
+
+  let file = src.join(SQLITE_FILE);
+
+  let current = is_current(
+    manifest,
+    cfg.db_dialect
+  );
+
+  let snapshot = Endpoint::Sqlite(
+    open_snapshot(&file).await?
+  );
+
+  let verified = verify_snapshot(
+    &snapshot, manifest, current
+  )
+  .await;
+
+  snapshot.close().await;
+  verified?;
+
+  let target = &cfg.sqlite_path;
+
+  if target.exists() && !force {
+    let endpoint = Endpoint::open(
+      cfg,
+      server_schema
+    )
+    .await?;
+
+    let empty =
+      ensure_empty(&endpoint).await;
+
+    endpoint.close().await;
+    empty?;
+  }
+
+  replace_file(&file, target)?;
+
+  create_repo(cfg.db_dialect, cfg)
+    .await?
+    .migrate(
+      &cfg.timezone,
+      cfg.default_poll_seconds
+    )
+    .await?;
+
+  if manifest.server_version.is_some() {
+    migrations::migrate_server(
+      cfg,
+      server_schema
+    )
+    .await?;
+  }
+
+  if !current {
+    return Ok(Vec::new());
+  }
+
+  let endpoint =
+    Endpoint::open(cfg, server_schema)
+      .await?;
+
+  let checks =
+    compare(&endpoint, manifest).await;
+
+  endpoint.close().await;
+
+  checks
+}
+
+async fn verify_snapshot(
+  snapshot: &Endpoint,
+  manifest: &BackupManifest,
+  current: bool
+) -> Result<(), String> {
+  let Endpoint::Sqlite(pool) = snapshot
+  else {
+    unreachable!(
+      "snapshots are SQLite"
+    );
+  };
+
+  let integrity: String =
+    sqlx::query_scalar(
+      "PRAGMA integrity_check"
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "backup integrity check \
+         error: {e}"
+      )
+    })?;
+
+  if integrity != "ok" {
+    return Err(format!(
+      "backup file is damaged: \
+       {integrity}"
+    ));
+  }
+
+  if !current {
+    return Ok(());
+  }
+
+  for check in
+    compare(snapshot, manifest).await?
+  {
+    if !check.matches() {
+      return Err(format!(
+        "backup file does not match \
+         its manifest: table {}",
+        check.table
+      ));
+    }
+  }
+
+  Ok(())
+}
+
+/// Fails when any known table in the
+/// target holds rows.
+async fn ensure_empty(
+  target: &Endpoint
+) -> Result<(), String> {
+  for table in TABLES.iter() {
+    if !target.has_table(table).await? {
+      continue;
+    }
+
+    let rows =
+      target.count(table).await?;
+
+    if rows > 0 {
+      return Err(format!(
+        "target table {} already has \
+         {rows} row(s); pass --force \
+         to replace its data",
+        table.name
+      ));
+    }
+  }
+
+  Ok(())
+}
+
+/// Copies the snapshot next to the
+/// target and renames it into place,
+/// dropping the old WAL files.
+fn replace_file(
+  snapshot: &Path,
+  target: &Path
+) -> Result<(), String> {
+  if let Some(parent) =
+    target.parent().filter(|p| {
+      !p.as_os_str().is_empty()
+    })
+  {
+    std::fs::create_dir_all(parent)
+      .map_err(|e| {
+        format!(
+          "create {}: {e}",
+          parent.display()
+        )
+      })?;
+  }
+
+  let with_suffix = |suffix: &str| {
+    let mut name =
+      target.as_os_str().to_owned();
+
+    name.push(suffix);
+
+    PathBuf::from(name)
+  };
+
+  let tmp = with_suffix(".restore");
+
+  std::fs::copy(snapshot, &tmp)
+    .map_err(|e| {
+      format!(
+        "copy {} to {}: {e}",
+        snapshot.display(),
+        tmp.display()
+      )
+    })?;
+
+  for suffix in ["-wal", "-shm"] {
+    let path = with_suffix(suffix);
+
+    if path.exists() {
+      std::fs::remove_file(&path)
+        .map_err(|e| {
+          format!(
+            "remove {}: {e}",
+            path.display()
+          )
+        })?;
+    }
+  }
+
+  std::fs::rename(&tmp, target).map_err(
+    |e| {
+      format!(
+        "move {} to {}: {e}",
+        tmp.display(),
+        target.display()
+      )
+    }
+  )
+}
+
+/// Read-only pool over a snapshot
+/// file, which must not be modified.
+async fn open_snapshot(
+  file: &Path
+) -> Result<SqlitePool, String> {
+  if !file.is_file() {
+    return Err(format!(
+      "backup file {} not found",
+      file.display()
+    ));
+  }
+
+  let opts =
+    SqliteConnectOptions::new()
+      .filename(file)
+      .read_only(true)
+      .immutable(true);
+
+  SqlitePoolOptions::new()
+    .max_connections(1)
+    .connect_with(opts)
+    .await
+    .map_err(|e| {
+      format!(
+        "open backup {}: {e}",
+        file.display()
+      )
+    })
+}
+
+async fn table_entries(
+  endpoint: &Endpoint,
+  tables: &[&Table]
+) -> Result<Vec<TableEntry>, String> {
+  let mut entries = Vec::new();
+
+  for table in tables {
+    let (rows, checksum) = checksum(
+      endpoint, table, BATCH_ROWS
+    )
+    .await?;
+
+    entries.push(TableEntry {
+      name: table.name.to_string(),
+      rows,
+      checksum
+    });
+  }
+
+  Ok(entries)
+}
+
+/// Row count and checksum of every
+/// manifest table in `endpoint`.
+async fn compare(
+  endpoint: &Endpoint,
+  manifest: &BackupManifest
+) -> Result<Vec<TableCheck>, String> {
+  let mut checks = Vec::new();
+
+  for entry in &manifest.tables {
+    let table =
+      catalog_table(&entry.name)?;
+
+    let (target_rows, target_checksum) =
+      checksum(
+        endpoint, table, BATCH_ROWS
+      )
+      .await?;
+
+    checks.push(TableCheck {
+      table: table.name,
+      source_rows: entry.rows,
+      target_rows,
+      source_checksum: entry
+        .checksum
+        .clone(),
+      target_checksum
+    });
+  }
+
+  Ok(checks)
+}
+
+fn catalog_table(
+  name: &str
+) -> Result<&'static Table, String> {
+  TABLES
+    .iter()
+    .find(|t| t.name == name)
+    .ok_or_else(|| {
+      format!(
+        "backup lists unknown table \
+         '{name}'"
+      )
+    })
+}
+
+fn dialect_name(
+  dialect: SqlDialect
+) -> &'static str {
+  match dialect {
+    | SqlDialect::Sqlite => "sqlite",
+    | SqlDialect::Postgres => "postgres"
+  }
+}
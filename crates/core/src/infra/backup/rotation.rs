@@ -0,0 +1,140 @@
+//! Timestamped backup directories and
+//! their rotation.
+
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use super::MANIFEST_FILE;
+
+const PREFIX: &str = "pulsewire-";
+
+/// Directory name for a backup taken
+/// at `now_ms`, such as
+/// `pulsewire-20250101T000000Z`; names
+/// sort by time.
+pub fn backup_name(
+  now_ms: i64
+) -> String {
+  let at =
+    chrono::DateTime::from_timestamp_millis(
+      now_ms
+    )
+    .unwrap_or_default();
+
+  format!(
+    "{PREFIX}{}",
+    at.format("%Y%m%dT%H%M%SZ")
+  )
+}
+
+/// Finished backups (those with a
+/// manifest) below `root`, oldest
+/// first.
+pub fn list_backups(
+  root: &Path
+) -> Result<Vec<PathBuf>, String> {
+  Ok(
+    named_dirs(root)?
+      .into_iter()
+      .filter(|dir| {
+        dir
+          .join(MANIFEST_FILE)
+          .is_file()
+      })
+      .collect()
+  )
+}
+
+/// Removes all but the newest `keep`
+/// finished backups, plus unfinished
+/// ones older than the newest finished
+/// backup. Returns the removed
+/// directories.
+pub fn rotate_backups(
+  root: &Path,
+  keep: usize
+) -> Result<Vec<PathBuf>, String> {
+  let finished = list_backups(root)?;
+
+  let Some(newest) = finished.last()
+  else {
+    return Ok(Vec::new());
+  };
+
+  let stale =
+    finished.len().saturating_sub(keep);
+
+  let mut removed: Vec<PathBuf> =
+    finished[..stale].to_vec();
+
+  removed.extend(
+    named_dirs(root)?
+      .into_iter()
+      .filter(|dir| {
+        dir < newest
+          && !dir
+            .join(MANIFEST_FILE)
+            .is_file()
+      })
+  );
+
+  for dir in &removed {
+    std::fs::remove_dir_all(dir)
+      .map_err(|e| {
+        format!(
+          "remove backup {}: {e}",
+          dir.display()
+        )
+      })?;
+  }
+
+  removed.sort();
+
+  Ok(removed)
+}
+
+/// Backup-named directories below
+/// `root`, sorted by name.
+fn named_dirs(
+  root: &Path
+) -> Result<Vec<PathBuf>, String> {
+  if !root.exists() {
+    return Ok(Vec::new());
+  }
+
+  let entries = std::fs::read_dir(root)
+    .map_err(|e| {
+      format!(
+        "read {}: {e}",
+        root.display()
+      )
+    })?;
+
+  let mut dirs = Vec::new();
+
+  for entry in entries {
+    let entry = entry.map_err(|e| {
+      format!(
+        "read {}: {e}",
+        root.display()
+      )
+    })?;
+
+    let path = entry.path();
+
+    let named = entry
+      .file_name()
+      .to_string_lossy()
+      .starts_with(PREFIX);
+
+    if named && path.is_dir() {
+      dirs.push(path);
+    }
+  }
+
+  dirs.sort();
+
+  Ok(dirs)
+}
@@ -0,0 +1,104 @@
+//! `[archive]` section: where raw
+//! payloads are kept and how hard they
+//! are compressed.
+
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use super::ConfigError;
+use super::raw::RawArchive;
+use crate::domain::archive::{
+  ArchiveConfig,
+  ArchiveStore
+};
+
+const DEFAULT_DIRECTORY: &str =
+  "archive";
+
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// Builds the archive config; a
+/// missing section disables it. A
+/// relative `directory` resolves
+/// against the database base dir.
+pub(crate) fn parse_archive(
+  raw: Option<RawArchive>,
+  db_base: &Path
+) -> Result<ArchiveConfig, ConfigError>
+{
+  let Some(raw) = raw else {
+    return Ok(ArchiveConfig {
+      enabled:    false,
+      store:
+        ArchiveStore::Filesystem,
+      directory:  db_base
+        .join(DEFAULT_DIRECTORY),
+      zstd_level: DEFAULT_ZSTD_LEVEL
+    });
+  };
+
+  let store = match raw
+    .store
+    .as_deref()
+    .map(|s| {
+      s.trim().to_ascii_lowercase()
+    })
+    .as_deref()
+  {
+    | None | Some("filesystem") => {
+      ArchiveStore::Filesystem
+    }
+    | Some("database") => {
+      ArchiveStore::Database
+    }
+    | Some(other) => {
+      return Err(ConfigError::Invalid(
+        format!(
+          "archive.store must be \
+           'filesystem' or \
+           'database', got '{other}'"
+        )
+      ));
+    }
+  };
+
+  let zstd_level = raw
+    .zstd_level
+    .unwrap_or(DEFAULT_ZSTD_LEVEL);
+
+  if !(1..=19).contains(&zstd_level) {
+    return Err(ConfigError::Invalid(
+      format!(
+        "archive.zstd_level must be \
+         between 1 and 19, got \
+         {zstd_level}"
+      )
+    ));
+  }
+
+  let directory = PathBuf::from(
+    raw
+      .directory
+      .as_deref()
+      .map(str::trim)
+      .filter(|d| !d.is_empty())
+      .unwrap_or(DEFAULT_DIRECTORY)
+  );
+
+  Ok(ArchiveConfig {
+    enabled: raw
+      .enabled
+      .unwrap_or(true),
+    store,
+    directory: if directory
+      .is_absolute()
+    {
+      directory
+    } else {
+      db_base.join(directory)
+    },
+    zstd_level
+  })
+}
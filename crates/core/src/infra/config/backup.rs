@@ -0,0 +1,121 @@
+//! `[backup]` section: scheduled
+//! backups taken by the fetcher.
+
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use super::ConfigError;
+use super::raw::RawBackup;
+use crate::domain::backup::BackupConfig;
+
+const DEFAULT_INTERVAL_SECONDS: u64 =
+  86_400;
+
+const DEFAULT_DIRECTORY: &str =
+  "backups";
+
+const DEFAULT_KEEP: usize = 7;
+
+const DEFAULT_SERVER_SCHEMA: &str =
+  "server";
+
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// Builds the backup config; a missing
+/// section disables scheduled backups.
+/// A relative `directory` resolves
+/// against the database base dir.
+pub(crate) fn parse_backup(
+  raw: Option<RawBackup>,
+  db_base: &Path
+) -> Result<BackupConfig, ConfigError> {
+  let Some(raw) = raw else {
+    return Ok(BackupConfig {
+      enabled:          false,
+      interval_seconds:
+        DEFAULT_INTERVAL_SECONDS,
+      directory:        db_base
+        .join(DEFAULT_DIRECTORY),
+      keep:             DEFAULT_KEEP,
+      server_schema:
+        DEFAULT_SERVER_SCHEMA
+          .to_string(),
+      zstd_level:
+        DEFAULT_ZSTD_LEVEL
+    });
+  };
+
+  let interval_seconds =
+    raw.interval_seconds.unwrap_or(
+      DEFAULT_INTERVAL_SECONDS
+    );
+
+  if interval_seconds < 60 {
+    return Err(ConfigError::Invalid(
+      format!(
+        "backup.interval_seconds must \
+         be at least 60, got \
+         {interval_seconds}"
+      )
+    ));
+  }
+
+  let keep =
+    raw.keep.unwrap_or(DEFAULT_KEEP);
+
+  if keep == 0 {
+    return Err(ConfigError::Invalid(
+      "backup.keep must be at least 1"
+        .to_string()
+    ));
+  }
+
+  let zstd_level = raw
+    .zstd_level
+    .unwrap_or(DEFAULT_ZSTD_LEVEL);
+
+  if !(1..=19).contains(&zstd_level) {
+    return Err(ConfigError::Invalid(
+      format!(
+        "backup.zstd_level must be \
+         between 1 and 19, got \
+         {zstd_level}"
+      )
+    ));
+  }
+
+  let directory = PathBuf::from(
+    raw
+      .directory
+      .as_deref()
+      .map(str::trim)
+      .filter(|d| !d.is_empty())
+      .unwrap_or(DEFAULT_DIRECTORY)
+  );
+
+  Ok(BackupConfig {
+    enabled: raw
+      .enabled
+      .unwrap_or(true),
+    interval_seconds,
+    directory: if directory
+      .is_absolute()
+    {
+      directory
+    } else {
+      db_base.join(directory)
+    },
+    keep,
+    server_schema: raw
+      .server_schema
+      .map(|s| s.trim().to_string())
+      .filter(|s| !s.is_empty())
+      .unwrap_or_else(|| {
+        DEFAULT_SERVER_SCHEMA
+          .to_string()
+      }),
+    zstd_level
+  })
+}
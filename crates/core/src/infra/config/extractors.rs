@@ -0,0 +1,275 @@
+use regex::Regex;
+use scraper::Selector;
+use sxd_xpath::Factory;
+
+use super::ConfigError;
+use crate::domain::model::{
+  WatchConfig,
+  WatchExtractorKind,
+  WatchItemIdentity
+};
+
+const REGEX_ITEM_GROUPS: [&str; 3] =
+  ["title", "link", "id"];
+
+pub(crate) fn parse_extractor_kind(
+  raw: Option<&str>,
+  key: &str,
+  watch_id: &str
+) -> Result<
+  Option<WatchExtractorKind>,
+  ConfigError
+> {
+  match raw
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+    .map(|s| s.to_ascii_lowercase())
+  {
+    | None => Ok(None),
+    | Some(s) if s == "css" => {
+      Ok(Some(WatchExtractorKind::Css))
+    }
+    | Some(s) if s == "xpath" => {
+      Ok(Some(
+        WatchExtractorKind::Xpath
+      ))
+    }
+    | Some(s) if s == "regex" => {
+      Ok(Some(
+        WatchExtractorKind::Regex
+      ))
+    }
+    | Some(other) => {
+      Err(ConfigError::Invalid(
+        format!(
+          "watch '{}' has invalid {} \
+           '{}', expected 'css', \
+           'xpath', or 'regex'",
+          watch_id, key, other
+        )
+      ))
+    }
+  }
+}
+
+/// Checks that every selector on a
+/// watch compiles for its extractor
+/// kind and that field kinds can be
+/// evaluated against the item kind.
+pub(crate) fn validate_watch_extractors(
+  watch: &WatchConfig
+) -> Result<(), ConfigError> {
+  let item_selector = watch
+    .item_selector
+    .as_deref()
+    .unwrap_or_default();
+
+  validate_selector(
+    watch,
+    "item_selector",
+    item_selector,
+    watch.extractor
+  )?;
+
+  match watch
+    .next_page_selector
+    .as_deref()
+  {
+    | Some(selector) => {
+      validate_selector(
+        watch,
+        "next_page_selector",
+        selector,
+        watch.extractor
+      )?;
+    }
+    | None if watch.max_pages > 1 => {
+      return Err(ConfigError::Invalid(
+        format!(
+          "watch '{}' requires \
+           next_page_selector when \
+           max_pages > 1",
+          watch.id
+        )
+      ));
+    }
+    | None => {}
+  }
+
+  if watch.extractor
+    == WatchExtractorKind::Regex
+  {
+    validate_regex_item(
+      watch,
+      item_selector
+    )?;
+  }
+
+  let fields = [
+    (
+      "title_selector",
+      watch.title_selector.as_deref(),
+      watch.title_extractor
+    ),
+    (
+      "link_selector",
+      watch.link_selector.as_deref(),
+      watch.link_extractor
+    ),
+    (
+      "summary_selector",
+      watch.summary_selector.as_deref(),
+      watch.summary_extractor
+    ),
+    (
+      "published_selector",
+      watch
+        .published_selector
+        .as_deref(),
+      watch.published_extractor
+    )
+  ];
+
+  for (key, selector, kind) in fields {
+    let kind =
+      kind.unwrap_or(watch.extractor);
+
+    if kind != watch.extractor
+      && kind
+        != WatchExtractorKind::Regex
+    {
+      return Err(ConfigError::Invalid(
+        format!(
+          "watch '{}' {} uses {} but \
+           items are extracted with \
+           {}; fields may only use \
+           the item extractor or regex",
+          watch.id,
+          key,
+          kind_name(kind),
+          kind_name(watch.extractor)
+        )
+      ));
+    }
+
+    if let Some(selector) = selector {
+      validate_selector(
+        watch, key, selector, kind
+      )?;
+    }
+  }
+
+  Ok(())
+}
+
+fn validate_regex_item(
+  watch: &WatchConfig,
+  item_selector: &str
+) -> Result<(), ConfigError> {
+  if matches!(
+    watch.item_identity,
+    Some(WatchItemIdentity::Attr)
+  ) {
+    return Err(ConfigError::Invalid(
+      format!(
+        "watch '{}' cannot use \
+         item_identity='attr' with \
+         the regex extractor; use a \
+         named 'id' group instead",
+        watch.id
+      )
+    ));
+  }
+
+  let Ok(regex) =
+    Regex::new(item_selector)
+  else {
+    return Ok(());
+  };
+
+  let has_group = regex
+    .capture_names()
+    .flatten()
+    .any(|name| {
+      REGEX_ITEM_GROUPS.contains(&name)
+    });
+
+  if !has_group {
+    return Err(ConfigError::Invalid(
+      format!(
+        "watch '{}' regex \
+         item_selector needs at least \
+         one named group of \
+         title,link,id",
+        watch.id
+      )
+    ));
+  }
+
+  Ok(())
+}
+
+fn validate_selector(
+  watch: &WatchConfig,
+  key: &str,
+  selector: &str,
+  kind: WatchExtractorKind
+) -> Result<(), ConfigError> {
+  let error = match kind {
+    | WatchExtractorKind::Css => {
+      Selector::parse(selector)
+        .err()
+        .map(|e| e.to_string())
+    }
+    | WatchExtractorKind::Xpath => {
+      match Factory::new()
+        .build(selector)
+      {
+        | Ok(Some(_)) => None,
+        | Ok(None) => {
+          Some(
+            "empty expression"
+              .to_string()
+          )
+        }
+        | Err(e) => Some(e.to_string())
+      }
+    }
+    | WatchExtractorKind::Regex => {
+      Regex::new(selector)
+        .err()
+        .map(|e| e.to_string())
+    }
+  };
+
+  match error {
+    | Some(e) => {
+      Err(ConfigError::Invalid(
+        format!(
+          "watch '{}' has invalid {} \
+           {} '{}': {}",
+          watch.id,
+          kind_name(kind),
+          key,
+          selector,
+          e
+        )
+      ))
+    }
+    | None => Ok(())
+  }
+}
+
+fn kind_name(
+  kind: WatchExtractorKind
+) -> &'static str {
+  match kind {
+    | WatchExtractorKind::Css => "css",
+    | WatchExtractorKind::Xpath => {
+      "xpath"
+    }
+    | WatchExtractorKind::Regex => {
+      "regex"
+    }
+  }
+}
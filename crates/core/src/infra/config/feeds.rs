@@ -194,7 +194,7 @@ pub(crate) async fn load_all_feeds(
   })
 }
 
-async fn collect_feed_files(
+pub(crate) async fn collect_feed_files(
   feeds_dir: &Path
 ) -> Result<Vec<PathBuf>, ConfigError> {
   let mut entries =
@@ -524,9 +524,17 @@ struct WatchDefaults {
   fetch_body_on_change:  Option<bool>,
   max_body_bytes:        Option<u64>,
   max_items_per_fetch:   Option<u64>,
+  next_page_selector:    Option<String>,
+  max_pages:             Option<u64>,
+  max_diff_bytes:        Option<u64>,
   item_selector:         Option<String>,
   item_identity:         Option<String>,
   item_identity_attr:    Option<String>,
+  extractor:             Option<String>,
+  title_extractor:       Option<String>,
+  link_extractor:        Option<String>,
+  summary_extractor:     Option<String>,
+  published_extractor:   Option<String>,
   title_selector:        Option<String>,
   link_selector:         Option<String>,
   summary_selector:      Option<String>,
@@ -562,9 +570,17 @@ impl WatchDefaults {
       fetch_body_on_change:  None,
       max_body_bytes:        None,
       max_items_per_fetch:   None,
+      next_page_selector:    None,
+      max_pages:             None,
+      max_diff_bytes:        None,
       item_selector:         None,
       item_identity:         None,
       item_identity_attr:    None,
+      extractor:             None,
+      title_extractor:       None,
+      link_extractor:        None,
+      summary_extractor:     None,
+      published_extractor:   None,
       title_selector:        None,
       link_selector:         None,
       summary_selector:      None,
@@ -635,6 +651,12 @@ impl WatchDefaults {
         .max_body_bytes,
       max_items_per_fetch: raw
         .max_items_per_fetch,
+      next_page_selector: raw
+        .next_page_selector
+        .clone(),
+      max_pages: raw.max_pages,
+      max_diff_bytes: raw
+        .max_diff_bytes,
       item_selector: raw
         .item_selector
         .clone(),
@@ -644,6 +666,19 @@ impl WatchDefaults {
       item_identity_attr: raw
         .item_identity_attr
         .clone(),
+      extractor: raw.extractor.clone(),
+      title_extractor: raw
+        .title_extractor
+        .clone(),
+      link_extractor: raw
+        .link_extractor
+        .clone(),
+      summary_extractor: raw
+        .summary_extractor
+        .clone(),
+      published_extractor: raw
+        .published_extractor
+        .clone(),
       title_selector: raw
         .title_selector
         .clone(),
@@ -778,6 +813,23 @@ impl WatchDefaults {
         override_with
           .max_items_per_fetch
           .or(base.max_items_per_fetch),
+      next_page_selector:
+        override_with
+          .next_page_selector
+          .clone()
+          .or_else(|| {
+            base
+              .next_page_selector
+              .clone()
+          }),
+      max_pages:
+        override_with
+          .max_pages
+          .or(base.max_pages),
+      max_diff_bytes:
+        override_with
+          .max_diff_bytes
+          .or(base.max_diff_bytes),
       item_selector:
         override_with
           .item_selector
@@ -801,6 +853,45 @@ impl WatchDefaults {
               .item_identity_attr
               .clone()
           }),
+      extractor:
+        override_with
+          .extractor
+          .clone()
+          .or_else(|| {
+            base.extractor.clone()
+          }),
+      title_extractor:
+        override_with
+          .title_extractor
+          .clone()
+          .or_else(|| {
+            base.title_extractor.clone()
+          }),
+      link_extractor:
+        override_with
+          .link_extractor
+          .clone()
+          .or_else(|| {
+            base.link_extractor.clone()
+          }),
+      summary_extractor:
+        override_with
+          .summary_extractor
+          .clone()
+          .or_else(|| {
+            base
+              .summary_extractor
+              .clone()
+          }),
+      published_extractor:
+        override_with
+          .published_extractor
+          .clone()
+          .or_else(|| {
+            base
+              .published_extractor
+              .clone()
+          }),
       title_selector:
         override_with
           .title_selector
@@ -1084,6 +1175,21 @@ fn apply_watch_defaults(
       base.max_items_per_fetch;
   }
 
+  if watch.next_page_selector.is_none()
+  {
+    watch.next_page_selector =
+      base.next_page_selector.clone();
+  }
+
+  if watch.max_pages.is_none() {
+    watch.max_pages = base.max_pages;
+  }
+
+  if watch.max_diff_bytes.is_none() {
+    watch.max_diff_bytes =
+      base.max_diff_bytes;
+  }
+
   if watch.item_selector.is_none() {
     watch.item_selector =
       base.item_selector.clone();
@@ -1100,6 +1206,32 @@ fn apply_watch_defaults(
       base.item_identity_attr.clone();
   }
 
+  if watch.extractor.is_none() {
+    watch.extractor =
+      base.extractor.clone();
+  }
+
+  if watch.title_extractor.is_none() {
+    watch.title_extractor =
+      base.title_extractor.clone();
+  }
+
+  if watch.link_extractor.is_none() {
+    watch.link_extractor =
+      base.link_extractor.clone();
+  }
+
+  if watch.summary_extractor.is_none() {
+    watch.summary_extractor =
+      base.summary_extractor.clone();
+  }
+
+  if watch.published_extractor.is_none()
+  {
+    watch.published_extractor =
+      base.published_extractor.clone();
+  }
+
   if watch.title_selector.is_none() {
     watch.title_selector =
       base.title_selector.clone();
@@ -0,0 +1,142 @@
+//! Per-domain HTTP client profiles
+//! read from `domains.toml`.
+
+use std::path::Path;
+
+use super::ConfigError;
+use super::raw::RawDomainEntry;
+use crate::domain::model::{
+  HttpProfile,
+  HttpVersion
+};
+
+const PROXY_SCHEMES: [&str; 4] = [
+  "http", "https", "socks5", "socks5h"
+];
+
+/// Builds the domain's profile, or
+/// `None` when it overrides nothing.
+/// A relative `ca_bundle` resolves
+/// against the config directory.
+pub(crate) fn parse_http_profile(
+  entry: &RawDomainEntry,
+  base_dir: &Path
+) -> Result<
+  Option<HttpProfile>,
+  ConfigError
+> {
+  let proxy =
+    non_empty(entry.proxy.as_deref())
+      .map(|raw| {
+        parse_proxy(&entry.name, raw)
+      })
+      .transpose()?;
+
+  let ca_bundle = non_empty(
+    entry.ca_bundle.as_deref()
+  )
+  .map(|raw| {
+    let path = base_dir.join(raw);
+
+    if path.is_file() {
+      Ok(path)
+    } else {
+      Err(ConfigError::Invalid(
+        format!(
+          "domain '{}' ca_bundle '{}' \
+           not found",
+          entry.name,
+          path.display()
+        )
+      ))
+    }
+  })
+  .transpose()?;
+
+  let http_version = non_empty(
+    entry.http_version.as_deref()
+  )
+  .map(|raw| {
+    parse_http_version(&entry.name, raw)
+  })
+  .transpose()?;
+
+  let profile = HttpProfile {
+    connect_timeout_seconds: entry
+      .connect_timeout_seconds,
+    read_timeout_seconds: entry
+      .read_timeout_seconds,
+    proxy,
+    ca_bundle,
+    http_version,
+    user_agent: non_empty(
+      entry.user_agent.as_deref()
+    )
+    .map(str::to_string)
+  };
+
+  if profile == HttpProfile::default() {
+    Ok(None)
+  } else {
+    Ok(Some(profile))
+  }
+}
+
+fn parse_proxy(
+  domain: &str,
+  raw: &str
+) -> Result<String, ConfigError> {
+  let url = reqwest::Url::parse(raw)
+    .map_err(|e| {
+      ConfigError::Invalid(format!(
+        "domain '{domain}' has \
+         invalid proxy '{raw}': {e}"
+      ))
+    })?;
+
+  if !PROXY_SCHEMES
+    .contains(&url.scheme())
+  {
+    return Err(ConfigError::Invalid(
+      format!(
+        "domain '{domain}' proxy \
+         '{raw}' must use http, \
+         https, socks5 or socks5h"
+      )
+    ));
+  }
+
+  Ok(raw.to_string())
+}
+
+fn parse_http_version(
+  domain: &str,
+  raw: &str
+) -> Result<HttpVersion, ConfigError> {
+  match raw
+    .to_ascii_lowercase()
+    .as_str()
+  {
+    | "http1" => Ok(HttpVersion::Http1),
+    | "http2" => Ok(HttpVersion::Http2),
+    | "h2c" => Ok(HttpVersion::H2c),
+    | other => {
+      Err(ConfigError::Invalid(
+        format!(
+          "domain '{domain}' has \
+           invalid http_version \
+           '{other}', expected \
+           'http1', 'http2' or 'h2c'"
+        )
+      ))
+    }
+  }
+}
+
+fn non_empty(
+  raw: Option<&str>
+) -> Option<&str> {
+  raw
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+}
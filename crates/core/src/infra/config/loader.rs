@@ -12,6 +12,8 @@ use chrono_tz::Tz;
 use tokio::fs;
 
 use super::ConfigError;
+use super::archive::parse_archive;
+use super::backup::parse_backup;
 use super::defaults::{
   default_metrics_bind,
   default_metrics_enabled,
@@ -20,7 +22,12 @@ use super::defaults::{
   normalize_log_rotation,
   normalize_status_codes
 };
+use super::extractors::{
+  parse_extractor_kind,
+  validate_watch_extractors
+};
 use super::feeds::load_all_feeds;
+use super::http_profiles::parse_http_profile;
 use super::parse::{
   parse_dialect,
   parse_mode,
@@ -38,10 +45,13 @@ use super::raw::{
   RawMetrics,
   RawWatch
 };
+use super::registry::parse_registry;
+use super::retention::parse_retention;
 use super::schema::{
   load_schema,
   validate_toml
 };
+use super::write_behind::parse_write_behind;
 use crate::domain::model::{
   AppConfig,
   CategoryConfig,
@@ -53,8 +63,10 @@ use crate::domain::model::{
   WatchConfig,
   WatchDetector,
   WatchEmitMode,
+  WatchExtractorKind,
   WatchItemIdentity
 };
+use crate::domain::text_diff::DEFAULT_MAX_DIFF_BYTES;
 
 pub struct ConfigLoader;
 
@@ -62,7 +74,8 @@ pub struct LoadedConfig {
   pub app:        AppConfig,
   pub feeds:      Vec<FeedConfig>,
   pub watches:    Vec<WatchConfig>,
-  pub categories: Vec<CategoryConfig>
+  pub categories: Vec<CategoryConfig>,
+  pub feeds_dir:  PathBuf
 }
 
 impl ConfigLoader {
@@ -264,11 +277,16 @@ impl ConfigLoader {
     let mut domains = HashMap::new();
 
     for d in raw_domains.domains {
+      let http = parse_http_profile(
+        &d, base_dir
+      )?;
+
       domains.insert(
         d.name,
         DomainConfig {
           max_concurrent_requests: d
-            .max_concurrent_requests
+            .max_concurrent_requests,
+          http
         }
       );
     }
@@ -470,6 +488,30 @@ impl ConfigLoader {
       watches.push(watch);
     }
 
+    let retention = parse_retention(
+      raw_cfg.retention,
+      &category_names,
+      &source_ids
+    )?;
+
+    let archive = parse_archive(
+      raw_cfg.archive,
+      &db_base
+    )?;
+
+    let backup = parse_backup(
+      raw_cfg.backup,
+      &db_base
+    )?;
+
+    let write_behind =
+      parse_write_behind(
+        raw_cfg.write_behind
+      )?;
+
+    let registry =
+      parse_registry(raw_cfg.registry)?;
+
     let metrics_cfg = raw_cfg
       .metrics
       .unwrap_or(RawMetrics {
@@ -539,10 +581,16 @@ impl ConfigLoader {
         timezone,
         domains,
         state_history_sample_rate: history_sample_rate,
+        retention,
+        archive,
+        backup,
+        write_behind,
+        registry,
       },
       feeds,
       watches,
       categories,
+      feeds_dir,
     })
   }
 }
@@ -636,6 +684,41 @@ fn parse_watch(
       &w.id
     )?;
 
+  let extractor = parse_extractor_kind(
+    w.extractor.as_deref(),
+    "extractor",
+    &w.id
+  )?
+  .unwrap_or(WatchExtractorKind::Css);
+
+  let title_extractor =
+    parse_extractor_kind(
+      w.title_extractor.as_deref(),
+      "title_extractor",
+      &w.id
+    )?;
+
+  let link_extractor =
+    parse_extractor_kind(
+      w.link_extractor.as_deref(),
+      "link_extractor",
+      &w.id
+    )?;
+
+  let summary_extractor =
+    parse_extractor_kind(
+      w.summary_extractor.as_deref(),
+      "summary_extractor",
+      &w.id
+    )?;
+
+  let published_extractor =
+    parse_extractor_kind(
+      w.published_extractor.as_deref(),
+      "published_extractor",
+      &w.id
+    )?;
+
   if w
     .item_selector
     .as_deref()
@@ -670,7 +753,7 @@ fn parse_watch(
     ));
   }
 
-  Ok(WatchConfig {
+  let watch = WatchConfig {
     id: w.id,
     url: w.url,
     domain,
@@ -704,10 +787,25 @@ fn parse_watch(
     max_body_bytes: w.max_body_bytes,
     max_items_per_fetch: w
       .max_items_per_fetch,
+    next_page_selector:
+      normalize_optional_string(
+        w.next_page_selector
+      ),
+    max_pages: w.max_pages.unwrap_or(1),
+    max_diff_bytes: w
+      .max_diff_bytes
+      .unwrap_or(
+        DEFAULT_MAX_DIFF_BYTES
+      ),
     item_selector: w.item_selector,
     item_identity,
     item_identity_attr: w
       .item_identity_attr,
+    extractor,
+    title_extractor,
+    link_extractor,
+    summary_extractor,
+    published_extractor,
     title_selector: w.title_selector,
     link_selector: w.link_selector,
     summary_selector: w
@@ -730,7 +828,11 @@ fn parse_watch(
     emit_title: w.emit_title,
     min_item_count_change: w
       .min_item_count_change
-  })
+  };
+
+  validate_watch_extractors(&watch)?;
+
+  Ok(watch)
 }
 
 fn normalize_headers(
@@ -2,19 +2,33 @@
 //! (app/domains/feeds) and normalizes
 //! it into `AppConfig` + feed list.
 
-mod defaults;
+mod archive;
+mod backup;
+pub(crate) mod defaults;
 mod error;
+mod extractors;
 mod feeds;
+mod http_profiles;
 mod loader;
+mod opml;
 mod parse;
 mod paths;
 mod raw;
+mod registry;
+mod retention;
+mod rewrite;
 mod schema;
 mod semantic;
+mod write_behind;
 
 pub use error::ConfigError;
 pub use loader::{
   ConfigLoader,
   LoadedConfig
 };
+pub use opml::{
+  FeedsFile,
+  opml_feeds_file
+};
+pub use rewrite::rewrite_feed_urls;
 pub use semantic::validate_semantic;
@@ -0,0 +1,135 @@
+//! Turns OPML feed outlines into a
+//! feed TOML file for the bundle's
+//! `feeds/` directory.
+
+use std::collections::HashSet;
+
+use crate::feed::opml::OpmlFeed;
+
+/// A rendered feed file.
+#[derive(Debug, Clone)]
+pub struct FeedsFile {
+  pub content: String,
+  pub feeds:   usize,
+  /// Outlines left out, with why.
+  pub skipped: Vec<String>
+}
+
+/// Renders `feeds` as one `[[feeds]]`
+/// table each under `category`. Ids
+/// come from the outline title (or
+/// the host), made unique within the
+/// file; folders become tags.
+/// Non-http(s) and repeated URLs are
+/// skipped.
+pub fn opml_feeds_file(
+  feeds: &[OpmlFeed],
+  category: &str,
+  id_prefix: Option<&str>
+) -> FeedsFile {
+  let mut content = format!(
+    "category = {}\n",
+    quote(category)
+  );
+
+  if let Some(prefix) = id_prefix {
+    content.push_str(&format!(
+      "id_prefix = {}\n",
+      quote(prefix)
+    ));
+  }
+
+  let mut ids = HashSet::new();
+  let mut urls = HashSet::new();
+  let mut skipped = Vec::new();
+  let mut count = 0;
+
+  for feed in feeds {
+    let Some(host) =
+      reqwest::Url::parse(
+        &feed.xml_url
+      )
+      .ok()
+      .filter(|u| {
+        matches!(
+          u.scheme(),
+          "http" | "https"
+        )
+      })
+      .and_then(|u| {
+        u.host_str().map(String::from)
+      })
+    else {
+      skipped.push(format!(
+        "{}: not an http(s) URL",
+        feed.xml_url
+      ));
+      continue;
+    };
+
+    if !urls
+      .insert(feed.xml_url.clone())
+    {
+      skipped.push(format!(
+        "{}: listed twice",
+        feed.xml_url
+      ));
+      continue;
+    }
+
+    let base = feed
+      .title
+      .as_deref()
+      .map(slug)
+      .filter(|s| !s.is_empty())
+      .unwrap_or_else(|| slug(&host));
+
+    let mut id = base.clone();
+    let mut n = 2;
+
+    while !ids.insert(id.clone()) {
+      id = format!("{base}-{n}");
+      n += 1;
+    }
+
+    content.push_str(&format!(
+      "\n[[feeds]]\nid  = {}\nurl = \
+       {}\n",
+      quote(&id),
+      quote(&feed.xml_url)
+    ));
+
+    if let Some(folder) = &feed.folder {
+      content.push_str(&format!(
+        "tags = [{}]\n",
+        quote(folder)
+      ));
+    }
+
+    count += 1;
+  }
+
+  FeedsFile {
+    content,
+    feeds: count,
+    skipped
+  }
+}
+
+/// Lowercase ASCII letters and digits
+/// joined by single dashes.
+fn slug(value: &str) -> String {
+  value
+    .to_ascii_lowercase()
+    .split(|c: char| {
+      !c.is_ascii_alphanumeric()
+    })
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<_>>()
+    .join("-")
+}
+
+fn quote(value: &str) -> String {
+  toml::Value::String(value.to_string())
+    .to_string()
+}
@@ -39,7 +39,18 @@ pub(crate) struct RawAppFile {
   pub metrics:       Option<RawMetrics>,
   #[serde(default)]
   pub state_history:
-    Option<RawStateHistory>
+    Option<RawStateHistory>,
+  #[serde(default)]
+  pub retention: Option<RawRetention>,
+  #[serde(default)]
+  pub archive:       Option<RawArchive>,
+  #[serde(default)]
+  pub backup:        Option<RawBackup>,
+  #[serde(default)]
+  pub write_behind:
+    Option<RawWriteBehind>,
+  #[serde(default)]
+  pub registry: Option<RawRegistry>
 }
 
 #[derive(Debug, Deserialize)]
@@ -173,6 +184,73 @@ pub(crate) struct RawStateHistory {
   pub sample_rate: Option<f64>
 }
 
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawRetention {
+  pub enabled:          Option<bool>,
+  pub interval_seconds: Option<u64>,
+  pub batch_size:       Option<u64>,
+  pub server_schema:    Option<String>,
+  #[serde(flatten)]
+  pub policy: RawRetentionPolicy,
+  #[serde(default)]
+  pub categories:
+    HashMap<String, RawRetentionPolicy>,
+  #[serde(default)]
+  pub feeds:
+    HashMap<String, RawRetentionPolicy>
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawRetentionPolicy {
+  pub fetch_events:
+    Option<RawRetentionLimit>,
+  pub state_history:
+    Option<RawRetentionLimit>,
+  pub items: Option<RawRetentionLimit>,
+  pub payloads:
+    Option<RawRetentionLimit>
+}
+
+#[derive(
+  Debug, Deserialize, Default,
+)]
+pub(crate) struct RawRetentionLimit {
+  pub max_age_days: Option<u64>,
+  pub max_rows:     Option<u64>
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawArchive {
+  pub enabled:    Option<bool>,
+  pub store:      Option<String>,
+  pub directory:  Option<String>,
+  pub zstd_level: Option<i32>
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawBackup {
+  pub enabled:          Option<bool>,
+  pub interval_seconds: Option<u64>,
+  pub directory:        Option<String>,
+  pub keep:             Option<usize>,
+  pub server_schema:    Option<String>,
+  pub zstd_level:       Option<i32>
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawWriteBehind {
+  pub enabled:   Option<bool>,
+  pub max_batch: Option<usize>,
+  pub flush_ms:  Option<u64>
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RawRegistry {
+  pub enabled:          Option<bool>,
+  pub category:         Option<String>,
+  pub interval_seconds: Option<u64>
+}
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct RawDomainsFile {
   pub domains: Vec<RawDomainEntry>
@@ -181,7 +259,14 @@ pub(crate) struct RawDomainsFile {
 #[derive(Debug, Deserialize)]
 pub(crate) struct RawDomainEntry {
   pub name:                    String,
-  pub max_concurrent_requests: usize
+  pub max_concurrent_requests: usize,
+  pub connect_timeout_seconds:
+    Option<u64>,
+  pub read_timeout_seconds: Option<u64>,
+  pub proxy: Option<String>,
+  pub ca_bundle: Option<String>,
+  pub http_version: Option<String>,
+  pub user_agent: Option<String>
 }
 
 #[derive(Debug, Deserialize)]
@@ -274,10 +359,20 @@ pub(crate) struct RawWatchDefaults {
     Option<bool>,
   pub max_body_bytes: Option<u64>,
   pub max_items_per_fetch: Option<u64>,
+  pub next_page_selector:
+    Option<String>,
+  pub max_pages: Option<u64>,
+  pub max_diff_bytes: Option<u64>,
   pub item_selector: Option<String>,
   pub item_identity: Option<String>,
   pub item_identity_attr:
     Option<String>,
+  pub extractor: Option<String>,
+  pub title_extractor: Option<String>,
+  pub link_extractor: Option<String>,
+  pub summary_extractor: Option<String>,
+  pub published_extractor:
+    Option<String>,
   pub title_selector: Option<String>,
   pub link_selector: Option<String>,
   pub summary_selector: Option<String>,
@@ -327,10 +422,20 @@ pub(crate) struct RawWatch {
     Option<bool>,
   pub max_body_bytes: Option<u64>,
   pub max_items_per_fetch: Option<u64>,
+  pub next_page_selector:
+    Option<String>,
+  pub max_pages: Option<u64>,
+  pub max_diff_bytes: Option<u64>,
   pub item_selector: Option<String>,
   pub item_identity: Option<String>,
   pub item_identity_attr:
     Option<String>,
+  pub extractor: Option<String>,
+  pub title_extractor: Option<String>,
+  pub link_extractor: Option<String>,
+  pub summary_extractor: Option<String>,
+  pub published_extractor:
+    Option<String>,
   pub title_selector: Option<String>,
   pub link_selector: Option<String>,
   pub summary_selector: Option<String>,
@@ -0,0 +1,68 @@
+//! `[registry]` section: polling of
+//! feeds users added through the
+//! server.
+
+use super::ConfigError;
+use super::raw::RawRegistry;
+use crate::domain::registry::RegistryConfig;
+
+const DEFAULT_CATEGORY: &str = "user";
+
+const DEFAULT_INTERVAL_SECONDS: u64 =
+  60;
+
+/// Builds the registry config; a
+/// missing section leaves registry
+/// feeds unpolled.
+pub(crate) fn parse_registry(
+  raw: Option<RawRegistry>
+) -> Result<RegistryConfig, ConfigError>
+{
+  let Some(raw) = raw else {
+    return Ok(RegistryConfig {
+      enabled:          false,
+      category:
+        DEFAULT_CATEGORY.to_string(),
+      interval_seconds:
+        DEFAULT_INTERVAL_SECONDS
+    });
+  };
+
+  let category =
+    raw.category.unwrap_or_else(|| {
+      DEFAULT_CATEGORY.to_string()
+    });
+
+  if category.trim().is_empty() {
+    return Err(ConfigError::Invalid(
+      "registry.category must not be \
+       empty"
+        .to_string()
+    ));
+  }
+
+  let interval_seconds =
+    raw.interval_seconds.unwrap_or(
+      DEFAULT_INTERVAL_SECONDS
+    );
+
+  if !(5..=86_400)
+    .contains(&interval_seconds)
+  {
+    return Err(ConfigError::Invalid(
+      format!(
+        "registry.interval_seconds \
+         must be between 5 and 86400, \
+         got {interval_seconds}"
+      )
+    ));
+  }
+
+  Ok(RegistryConfig {
+    enabled: raw
+      .enabled
+      .unwrap_or(true),
+    category,
+    interval_seconds
+  })
+}
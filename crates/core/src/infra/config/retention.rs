@@ -0,0 +1,216 @@
+//! `[retention]` section: table limits
+//! with category and feed overrides.
+
+use std::collections::{
+  HashMap,
+  HashSet
+};
+
+use super::ConfigError;
+use super::raw::{
+  RawRetention,
+  RawRetentionLimit,
+  RawRetentionPolicy
+};
+use crate::domain::retention::{
+  RetentionConfig,
+  RetentionLimit,
+  RetentionPolicy
+};
+
+const DEFAULT_INTERVAL_SECONDS: u64 =
+  3600;
+
+const DEFAULT_BATCH_SIZE: u64 = 500;
+
+const DEFAULT_SERVER_SCHEMA: &str =
+  "server";
+
+/// Builds the retention config; a
+/// missing section disables pruning.
+/// Overrides must name a known
+/// category or source id.
+pub(crate) fn parse_retention(
+  raw: Option<RawRetention>,
+  category_names: &HashSet<String>,
+  source_ids: &HashSet<String>
+) -> Result<RetentionConfig, ConfigError>
+{
+  let Some(raw) = raw else {
+    return Ok(RetentionConfig {
+      enabled:          false,
+      interval_seconds:
+        DEFAULT_INTERVAL_SECONDS,
+      batch_size:
+        DEFAULT_BATCH_SIZE,
+      server_schema:
+        DEFAULT_SERVER_SCHEMA
+          .to_string(),
+      defaults:
+        RetentionPolicy::default(),
+      categories:       HashMap::new(),
+      feeds:            HashMap::new()
+    });
+  };
+
+  let interval_seconds = positive(
+    "retention.interval_seconds",
+    raw.interval_seconds.unwrap_or(
+      DEFAULT_INTERVAL_SECONDS
+    )
+  )?;
+
+  let batch_size = positive(
+    "retention.batch_size",
+    raw
+      .batch_size
+      .unwrap_or(DEFAULT_BATCH_SIZE)
+  )?;
+
+  let server_schema = raw
+    .server_schema
+    .map(|s| s.trim().to_string())
+    .filter(|s| !s.is_empty())
+    .unwrap_or_else(|| {
+      DEFAULT_SERVER_SCHEMA.to_string()
+    });
+
+  let defaults = parse_policy(
+    "retention",
+    raw.policy
+  )?;
+
+  let mut categories = HashMap::new();
+
+  for (name, policy) in raw.categories {
+    if !category_names.contains(&name) {
+      return Err(ConfigError::Invalid(
+        format!(
+          "retention.categories \
+           references unknown \
+           category '{name}'"
+        )
+      ));
+    }
+
+    let scope = format!(
+      "retention.categories.{name}"
+    );
+
+    categories.insert(
+      name,
+      parse_policy(&scope, policy)?
+    );
+  }
+
+  let mut feeds = HashMap::new();
+
+  for (id, policy) in raw.feeds {
+    if !source_ids.contains(&id) {
+      return Err(ConfigError::Invalid(
+        format!(
+          "retention.feeds references \
+           unknown feed '{id}'"
+        )
+      ));
+    }
+
+    let scope =
+      format!("retention.feeds.{id}");
+
+    feeds.insert(
+      id,
+      parse_policy(&scope, policy)?
+    );
+  }
+
+  Ok(RetentionConfig {
+    enabled: raw
+      .enabled
+      .unwrap_or(true),
+    interval_seconds,
+    batch_size,
+    server_schema,
+    defaults,
+    categories,
+    feeds
+  })
+}
+
+fn parse_policy(
+  scope: &str,
+  raw: RawRetentionPolicy
+) -> Result<RetentionPolicy, ConfigError>
+{
+  let limit = |table: &str,
+               raw: Option<
+    RawRetentionLimit
+  >| {
+    parse_limit(
+      &format!("{scope}.{table}"),
+      raw.unwrap_or_default()
+    )
+  };
+
+  Ok(RetentionPolicy {
+    fetch_events:  limit(
+      "fetch_events",
+      raw.fetch_events
+    )?,
+    state_history: limit(
+      "state_history",
+      raw.state_history
+    )?,
+    items:         limit(
+      "items", raw.items
+    )?,
+    payloads:      limit(
+      "payloads",
+      raw.payloads
+    )?
+  })
+}
+
+fn parse_limit(
+  scope: &str,
+  raw: RawRetentionLimit
+) -> Result<RetentionLimit, ConfigError>
+{
+  Ok(RetentionLimit {
+    max_age_days: raw
+      .max_age_days
+      .map(|v| {
+        positive(
+          &format!(
+            "{scope}.max_age_days"
+          ),
+          v
+        )
+      })
+      .transpose()?,
+    max_rows:     raw
+      .max_rows
+      .map(|v| {
+        positive(
+          &format!("{scope}.max_rows"),
+          v
+        )
+      })
+      .transpose()?
+  })
+}
+
+fn positive(
+  key: &str,
+  value: u64
+) -> Result<u64, ConfigError> {
+  if value == 0 {
+    return Err(ConfigError::Invalid(
+      format!(
+        "{key} must be at least 1"
+      )
+    ));
+  }
+
+  Ok(value)
+}
@@ -0,0 +1,133 @@
+//! Rewrites feed URLs in place inside
+//! the feed TOML files, keeping the
+//! rest of each file untouched.
+
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use tokio::fs;
+use toml_edit::{
+  DocumentMut,
+  Item,
+  Value
+};
+
+use super::ConfigError;
+use super::feeds::collect_feed_files;
+
+/// Tables whose entries carry a `url`.
+const URL_TABLES: [&str; 2] =
+  ["feeds", "watches"];
+
+/// Replaces the `url` of every feed or
+/// watch whose URL is an `old` of the
+/// `(old, new)` pairs and returns the
+/// files that changed. Formatting and
+/// comments are kept.
+pub async fn rewrite_feed_urls(
+  feeds_dir: &Path,
+  changes: &[(String, String)]
+) -> Result<Vec<PathBuf>, ConfigError> {
+  let mut changed_files = Vec::new();
+
+  for path in
+    collect_feed_files(feeds_dir)
+      .await?
+  {
+    let content =
+      fs::read_to_string(&path).await?;
+
+    let mut doc = content
+      .parse::<DocumentMut>()
+      .map_err(|e| {
+        ConfigError::Invalid(format!(
+          "{}: {e}",
+          path.display()
+        ))
+      })?;
+
+    let mut changed = false;
+
+    for key in URL_TABLES {
+      if let Some(item) =
+        doc.get_mut(key)
+      {
+        changed |= rewrite_entries(
+          item, changes
+        );
+      }
+    }
+
+    if changed {
+      fs::write(&path, doc.to_string())
+        .await?;
+
+      changed_files.push(path);
+    }
+  }
+
+  Ok(changed_files)
+}
+
+/// Rewrites the entries of a
+/// `[[feeds]]` array of tables or an
+/// inline `feeds = [{ .. }]` array.
+fn rewrite_entries(
+  item: &mut Item,
+  changes: &[(String, String)]
+) -> bool {
+  let mut changed = false;
+
+  if let Some(tables) =
+    item.as_array_of_tables_mut()
+  {
+    for table in tables.iter_mut() {
+      if let Some(url) = table
+        .get_mut("url")
+        .and_then(Item::as_value_mut)
+      {
+        changed |=
+          rewrite_url(url, changes);
+      }
+    }
+  } else if let Some(array) =
+    item.as_array_mut()
+  {
+    for entry in array.iter_mut() {
+      if let Some(url) = entry
+        .as_inline_table_mut()
+        .and_then(|t| t.get_mut("url"))
+      {
+        changed |=
+          rewrite_url(url, changes);
+      }
+    }
+  }
+
+  changed
+}
+
+fn rewrite_url(
+  url: &mut Value,
+  changes: &[(String, String)]
+) -> bool {
+  let Some(new) =
+    url.as_str().and_then(|current| {
+      changes
+        .iter()
+        .find(|(old, _)| old == current)
+        .map(|(_, new)| new)
+    })
+  else {
+    return false;
+  };
+
+  let decor = url.decor().clone();
+
+  *url = Value::from(new.as_str());
+  *url.decor_mut() = decor;
+
+  true
+}
@@ -0,0 +1,66 @@
+//! `[write_behind]` section: batching
+//! of fetch events and state
+//! snapshots.
+
+use super::ConfigError;
+use super::raw::RawWriteBehind;
+use crate::domain::write_behind::WriteBehindConfig;
+
+const DEFAULT_MAX_BATCH: usize = 256;
+
+const DEFAULT_FLUSH_MS: u64 = 20;
+
+/// Builds the write-behind config; a
+/// missing section writes every event
+/// and snapshot on its own.
+pub(crate) fn parse_write_behind(
+  raw: Option<RawWriteBehind>
+) -> Result<
+  WriteBehindConfig,
+  ConfigError
+> {
+  let Some(raw) = raw else {
+    return Ok(WriteBehindConfig {
+      enabled:   false,
+      max_batch: DEFAULT_MAX_BATCH,
+      flush_ms:  DEFAULT_FLUSH_MS
+    });
+  };
+
+  let max_batch = raw
+    .max_batch
+    .unwrap_or(DEFAULT_MAX_BATCH);
+
+  if !(1..=10_000).contains(&max_batch)
+  {
+    return Err(ConfigError::Invalid(
+      format!(
+        "write_behind.max_batch must \
+         be between 1 and 10000, got \
+         {max_batch}"
+      )
+    ));
+  }
+
+  let flush_ms = raw
+    .flush_ms
+    .unwrap_or(DEFAULT_FLUSH_MS);
+
+  if !(1..=1_000).contains(&flush_ms) {
+    return Err(ConfigError::Invalid(
+      format!(
+        "write_behind.flush_ms must \
+         be between 1 and 1000, got \
+         {flush_ms}"
+      )
+    ));
+  }
+
+  Ok(WriteBehindConfig {
+    enabled: raw
+      .enabled
+      .unwrap_or(true),
+    max_batch,
+    flush_ms
+  })
+}
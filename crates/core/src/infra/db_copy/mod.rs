@@ -0,0 +1,739 @@
+//! Copies every fetcher and server
+//! table from one database to another,
+//! across dialects. Rows travel in
+//! primary key order and in batches,
+//! keeping their ids so foreign keys
+//! (`feed_items.payload_id`,
+//! `entry_states.item_id`,
+//! `folder_feeds.folder_id`, …) stay
+//! valid. Each table is then verified
+//! by row count and an
+//! order-independent checksum over the
+//! dialect-neutral values.
+
+pub(crate) mod postgres;
+pub(crate) mod sqlite;
+pub mod tables;
+
+use sha2::{
+  Digest,
+  Sha256
+};
+use sqlx::{
+  PgPool,
+  SqlitePool
+};
+use tracing::info;
+
+use self::tables::{
+  ColumnKind,
+  TABLES,
+  Table
+};
+use crate::domain::model::{
+  AppConfig,
+  SqlDialect
+};
+use crate::infra::database::create_repo;
+use crate::infra::migrations::{
+  self,
+  Component
+};
+use crate::infra::{
+  postgres_repo,
+  sqlite_repo
+};
+
+pub(crate) const MAX_BINDS: u64 =
+  30_000;
+
+/// A column value in dialect-neutral
+/// form: timestamps are epoch
+/// milliseconds, tags are JSON text.
+#[derive(
+  Debug, Clone, PartialEq, Eq,
+)]
+pub enum Value {
+  Int(Option<i64>),
+  Text(Option<String>),
+  Bytes(Option<Vec<u8>>)
+}
+
+impl Value {
+  pub(crate) fn write_canonical(
+    &self,
+    out: &mut Vec<u8>
+  ) {
+    let (tag, bytes): (u8, &[u8]) =
+      match self {
+        | Value::Int(None)
+        | Value::Text(None)
+        | Value::Bytes(None) => {
+          (0, &[])
+        }
+        | Value::Int(Some(n)) => {
+          out.push(1);
+          out.extend_from_slice(
+            &n.to_be_bytes()
+          );
+
+          return;
+        }
+        | Value::Text(Some(s)) => {
+          (2, s.as_bytes())
+        }
+        | Value::Bytes(Some(b)) => {
+          (3, b.as_slice())
+        }
+      };
+
+    out.push(tag);
+    out.extend_from_slice(
+      &(bytes.len() as u64)
+        .to_be_bytes()
+    );
+    out.extend_from_slice(bytes);
+  }
+
+  /// Reads back one value written by
+  /// `write_canonical`; NULLs take the
+  /// column's kind.
+  pub(crate) fn read_canonical<R>(
+    input: &mut R,
+    kind: ColumnKind
+  ) -> std::io::Result<Value>
+  where
+    R: std::io::Read
+  {
+    let mut tag = [0u8; 1];
+
+    input.read_exact(&mut tag)?;
+
+    if tag[0] == 1 {
+      let mut n = [0u8; 8];
+
+      input.read_exact(&mut n)?;
+
+      return Ok(Value::Int(Some(
+        i64::from_be_bytes(n)
+      )));
+    }
+
+    let mut len = [0u8; 8];
+
+    input.read_exact(&mut len)?;
+
+    let mut bytes = vec![
+      0u8;
+      u64::from_be_bytes(len)
+        as usize
+    ];
+
+    input.read_exact(&mut bytes)?;
+
+    match tag[0] {
+      | 0 if is_int(kind) => {
+        Ok(Value::Int(None))
+      }
+      | 0 if kind
+        == ColumnKind::Bytes =>
+      {
+        Ok(Value::Bytes(None))
+      }
+      | 0 => Ok(Value::Text(None)),
+      | 2 => {
+        String::from_utf8(bytes)
+          .map(|s| Value::Text(Some(s)))
+          .map_err(|e| {
+            std::io::Error::new(
+              std::io::ErrorKind::InvalidData,
+              e
+            )
+          })
+      }
+      | 3 => Ok(Value::Bytes(Some(bytes))),
+      | other => {
+        Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidData,
+          format!(
+            "unknown value tag {other}"
+          )
+        ))
+      }
+    }
+  }
+}
+
+pub type Row = Vec<Value>;
+
+/// One side of a copy.
+pub enum Endpoint {
+  Sqlite(SqlitePool),
+  Postgres {
+    pool:           PgPool,
+    fetcher_schema: String,
+    server_schema:  String
+  }
+}
+
+impl Endpoint {
+  pub async fn open(
+    cfg: &AppConfig,
+    server_schema: &str
+  ) -> Result<Endpoint, String> {
+    match cfg.db_dialect {
+      | SqlDialect::Sqlite => {
+        Ok(Endpoint::Sqlite(
+          sqlite_repo::create_pool(
+            &cfg.sqlite_path
+          )
+          .await?
+        ))
+      }
+      | SqlDialect::Postgres => {
+        Ok(Endpoint::Postgres {
+          pool:
+            postgres_repo::create_pool(
+              &cfg.postgres,
+              &cfg.timezone
+            )
+            .await?,
+          fetcher_schema: cfg
+            .postgres
+            .schema
+            .clone(),
+          server_schema:  server_schema
+            .to_string()
+        })
+      }
+    }
+  }
+
+  pub(crate) async fn has_table(
+    &self,
+    table: &Table
+  ) -> Result<bool, String> {
+    match self {
+      | Endpoint::Sqlite(pool) => {
+        sqlite::has_table(pool, table)
+          .await
+      }
+      | Endpoint::Postgres {
+        pool,
+        ..
+      } => {
+        postgres::has_table(
+          pool,
+          &self.qualified(table)
+        )
+        .await
+      }
+    }
+  }
+
+  pub(crate) async fn count(
+    &self,
+    table: &Table
+  ) -> Result<u64, String> {
+    match self {
+      | Endpoint::Sqlite(pool) => {
+        sqlite::count(pool, table).await
+      }
+      | Endpoint::Postgres {
+        pool,
+        ..
+      } => {
+        postgres::count(
+          pool,
+          &self.qualified(table)
+        )
+        .await
+      }
+    }
+  }
+
+  /// Up to `limit` rows whose key
+  /// sorts after `after`.
+  pub(crate) async fn read_batch(
+    &self,
+    table: &Table,
+    after: Option<&Row>,
+    limit: u64
+  ) -> Result<Vec<Row>, String> {
+    match self {
+      | Endpoint::Sqlite(pool) => {
+        sqlite::read_batch(
+          pool, table, after, limit
+        )
+        .await
+      }
+      | Endpoint::Postgres {
+        pool,
+        ..
+      } => {
+        postgres::read_batch(
+          pool,
+          &self.qualified(table),
+          table,
+          after,
+          limit
+        )
+        .await
+      }
+    }
+  }
+
+  pub(crate) async fn insert_batch(
+    &self,
+    table: &Table,
+    rows: &[Row]
+  ) -> Result<(), String> {
+    if rows.is_empty() {
+      return Ok(());
+    }
+
+    match self {
+      | Endpoint::Sqlite(pool) => {
+        sqlite::insert_batch(
+          pool, table, rows
+        )
+        .await
+      }
+      | Endpoint::Postgres {
+        pool,
+        ..
+      } => {
+        postgres::insert_batch(
+          pool,
+          &self.qualified(table),
+          table,
+          rows
+        )
+        .await
+      }
+    }
+  }
+
+  /// Moves the Postgres id sequence
+  /// past the copied ids; SQLite's
+  /// AUTOINCREMENT counter follows
+  /// explicit inserts on its own.
+  pub(crate) async fn reset_sequence(
+    &self,
+    table: &Table
+  ) -> Result<(), String> {
+    match self {
+      | Endpoint::Sqlite(_) => Ok(()),
+      | Endpoint::Postgres {
+        pool,
+        ..
+      } => {
+        postgres::reset_sequence(
+          pool,
+          &self.qualified(table)
+        )
+        .await
+      }
+    }
+  }
+
+  pub(crate) async fn close(&self) {
+    match self {
+      | Endpoint::Sqlite(pool) => {
+        pool.close().await
+      }
+      | Endpoint::Postgres {
+        pool,
+        ..
+      } => pool.close().await
+    }
+  }
+
+  pub(crate) fn qualified(
+    &self,
+    table: &Table
+  ) -> String {
+    match self {
+      | Endpoint::Sqlite(_) => {
+        table.name.to_string()
+      }
+      | Endpoint::Postgres {
+        fetcher_schema,
+        server_schema,
+        ..
+      } => {
+        let schema =
+          match table.component {
+            | Component::Fetcher => {
+              fetcher_schema
+            }
+            | Component::Server => {
+              server_schema
+            }
+          };
+
+        format!(
+          "{}.{}",
+          postgres::quote_ident(schema),
+          postgres::quote_ident(
+            table.name
+          )
+        )
+      }
+    }
+  }
+}
+
+/// Rows copied into one table.
+#[derive(Debug, Clone)]
+pub struct TableCopy {
+  pub table: &'static str,
+  pub rows:  u64
+}
+
+/// Row count and checksum of one table
+/// on both sides.
+#[derive(Debug, Clone)]
+pub struct TableCheck {
+  pub table:           &'static str,
+  pub source_rows:     u64,
+  pub target_rows:     u64,
+  pub source_checksum: String,
+  pub target_checksum: String
+}
+
+impl TableCheck {
+  pub fn matches(&self) -> bool {
+    self.source_rows == self.target_rows
+      && self.source_checksum
+        == self.target_checksum
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct CopyReport {
+  pub copied:  Vec<TableCopy>,
+  pub checks:  Vec<TableCheck>,
+  /// The source has no server tables
+  /// (the server never ran on it).
+  pub skipped: Vec<&'static str>
+}
+
+/// Migrates the target, refuses a
+/// target that already holds rows or a
+/// source behind this build, then
+/// copies and verifies every table.
+pub async fn copy_database(
+  from: &AppConfig,
+  to: &AppConfig,
+  server_schema: &str,
+  batch_size: u64
+) -> Result<CopyReport, String> {
+  let batch_size = batch_size.max(1);
+
+  let source =
+    Endpoint::open(from, server_schema)
+      .await?;
+
+  let with_server = source
+    .has_table(server_marker())
+    .await?;
+
+  ensure_current(
+    from,
+    Component::Fetcher,
+    server_schema
+  )
+  .await?;
+
+  if with_server {
+    ensure_current(
+      from,
+      Component::Server,
+      server_schema
+    )
+    .await?;
+  }
+
+  create_repo(to.db_dialect, to)
+    .await?
+    .migrate(
+      &to.timezone,
+      to.default_poll_seconds
+    )
+    .await?;
+
+  migrations::migrate_server(
+    to,
+    server_schema
+  )
+  .await?;
+
+  let target =
+    Endpoint::open(to, server_schema)
+      .await?;
+
+  let (tables, skipped): (
+    Vec<&Table>,
+    Vec<&Table>
+  ) = TABLES.iter().partition(|t| {
+    with_server
+      || t.component
+        == Component::Fetcher
+  });
+
+  for table in &tables {
+    let rows =
+      target.count(table).await?;
+
+    if rows > 0 {
+      return Err(format!(
+        "target table {} already has \
+         {rows} row(s); copy needs an \
+         empty target",
+        table.name
+      ));
+    }
+  }
+
+  let mut copied = Vec::new();
+
+  for table in &tables {
+    let rows = copy_table(
+      &source, &target, table,
+      batch_size
+    )
+    .await?;
+
+    info!(
+      table = table.name,
+      rows = rows,
+      "Copied table"
+    );
+
+    copied.push(TableCopy {
+      table: table.name,
+      rows
+    });
+  }
+
+  let mut checks = Vec::new();
+
+  for table in &tables {
+    let (source_rows, source_checksum) =
+      checksum(
+        &source, table, batch_size
+      )
+      .await?;
+
+    let (target_rows, target_checksum) =
+      checksum(
+        &target, table, batch_size
+      )
+      .await?;
+
+    checks.push(TableCheck {
+      table: table.name,
+      source_rows,
+      target_rows,
+      source_checksum,
+      target_checksum
+    });
+  }
+
+  Ok(CopyReport {
+    copied,
+    checks,
+    skipped: skipped
+      .iter()
+      .map(|t| t.name)
+      .collect()
+  })
+}
+
+pub(crate) fn server_marker()
+-> &'static Table {
+  TABLES
+    .iter()
+    .find(|t| t.name == "users")
+    .expect("users table listed")
+}
+
+/// The catalog matches the latest
+/// schema, so the source must not have
+/// pending migrations. Returns its
+/// schema version.
+pub(crate) async fn ensure_current(
+  cfg: &AppConfig,
+  component: Component,
+  server_schema: &str
+) -> Result<i64, String> {
+  let status =
+    migrations::database_status(
+      cfg,
+      component,
+      server_schema
+    )
+    .await?;
+
+  status.ensure_supported()?;
+
+  if status.current_version()
+    < status.latest_version()
+  {
+    return Err(format!(
+      "source {} schema is at version \
+       {} but this build expects {}; \
+       run `db migrate` on the source \
+       first",
+      component.as_str(),
+      status.current_version(),
+      status.latest_version()
+    ));
+  }
+
+  Ok(status.current_version())
+}
+
+async fn copy_table(
+  source: &Endpoint,
+  target: &Endpoint,
+  table: &Table,
+  batch_size: u64
+) -> Result<u64, String> {
+  // Stay under the bind parameter
+  // limits of both dialects.
+  let batch_size = batch_size.min(
+    MAX_BINDS
+      / table.columns.len() as u64
+  );
+
+  let mut copied = 0;
+  let mut last: Option<Row> = None;
+
+  loop {
+    let rows = source
+      .read_batch(
+        table,
+        last.as_ref(),
+        batch_size
+      )
+      .await?;
+
+    target
+      .insert_batch(table, &rows)
+      .await?;
+
+    copied += rows.len() as u64;
+
+    if (rows.len() as u64) < batch_size
+    {
+      break;
+    }
+
+    last = rows.into_iter().last();
+  }
+
+  if table.serial {
+    target
+      .reset_sequence(table)
+      .await?;
+  }
+
+  Ok(copied)
+}
+
+/// Row count and a checksum that does
+/// not depend on row order, since text
+/// keys may collate differently per
+/// dialect.
+pub(crate) async fn checksum(
+  endpoint: &Endpoint,
+  table: &Table,
+  batch_size: u64
+) -> Result<(u64, String), String> {
+  let mut sum = Checksum::default();
+  let mut last: Option<Row> = None;
+
+  loop {
+    let batch = endpoint
+      .read_batch(
+        table,
+        last.as_ref(),
+        batch_size
+      )
+      .await?;
+
+    for row in &batch {
+      sum.add(row);
+    }
+
+    if (batch.len() as u64) < batch_size
+    {
+      break;
+    }
+
+    last = batch.into_iter().last();
+  }
+
+  Ok(sum.finish())
+}
+
+/// Order-independent row checksum: the
+/// wrapping sum of each row's
+/// truncated SHA-256.
+#[derive(Default)]
+pub(crate) struct Checksum {
+  rows: u64,
+  sum:  u128,
+  buf:  Vec<u8>
+}
+
+impl Checksum {
+  pub(crate) fn add(
+    &mut self,
+    row: &Row
+  ) {
+    self.buf.clear();
+
+    for value in row {
+      value
+        .write_canonical(&mut self.buf);
+    }
+
+    let digest =
+      Sha256::digest(&self.buf);
+
+    let mut head = [0u8; 16];
+
+    head.copy_from_slice(&digest[..16]);
+
+    self.sum = self.sum.wrapping_add(
+      u128::from_be_bytes(head)
+    );
+    self.rows += 1;
+  }
+
+  /// Row count and hex checksum.
+  pub(crate) fn finish(
+    &self
+  ) -> (u64, String) {
+    (
+      self.rows,
+      format!("{:032x}", self.sum)
+    )
+  }
+}
+
+/// Neutral value kind read for a
+/// column.
+pub(crate) fn is_int(
+  kind: ColumnKind
+) -> bool {
+  matches!(
+    kind,
+    ColumnKind::Int
+      | ColumnKind::EpochMs
+      | ColumnKind::ServerTime
+  )
+}
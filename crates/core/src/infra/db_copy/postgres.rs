@@ -0,0 +1,327 @@
+//! Postgres side of `db copy` and
+//! logical backups. Tables are
+//! schema-qualified by the caller.
+
+use sqlx::postgres::PgRow;
+use sqlx::{
+  Executor,
+  PgPool,
+  Postgres,
+  QueryBuilder,
+  Row as _
+};
+
+use super::tables::{
+  ColumnKind,
+  Table
+};
+use super::{
+  Row,
+  Value,
+  is_int
+};
+
+pub async fn has_table(
+  pool: &PgPool,
+  qualified: &str
+) -> Result<bool, String> {
+  sqlx::query_scalar(
+    "SELECT to_regclass($1) IS NOT \
+     NULL"
+  )
+  .bind(qualified)
+  .fetch_one(pool)
+  .await
+  .map_err(|e| {
+    format!("copy has_table error: {e}")
+  })
+}
+
+pub async fn count(
+  pool: &PgPool,
+  qualified: &str
+) -> Result<u64, String> {
+  let sql = format!(
+    "SELECT COUNT(*) FROM {qualified}"
+  );
+
+  let n: i64 = sqlx::query_scalar(&sql)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "copy count {qualified} \
+         error: {e}"
+      )
+    })?;
+
+  Ok(n as u64)
+}
+
+pub async fn read_batch<'e, E>(
+  executor: E,
+  qualified: &str,
+  table: &Table,
+  after: Option<&Row>,
+  limit: u64
+) -> Result<Vec<Row>, String>
+where
+  E: Executor<'e, Database = Postgres>
+{
+  let columns: Vec<String> = table
+    .columns
+    .iter()
+    .map(|c| {
+      match c.kind {
+        | ColumnKind::EpochMs => {
+          format!(
+            "CAST(EXTRACT(EPOCH FROM \
+             {}) * 1000 AS BIGINT)",
+            c.postgres
+          )
+        }
+        | ColumnKind::ServerTime => {
+          format!(
+            "CAST(FLOOR(EXTRACT(EPOCH \
+             FROM {})) AS BIGINT) * \
+             1000",
+            c.postgres
+          )
+        }
+        | ColumnKind::Tags => {
+          format!(
+            "array_to_json({})::text",
+            c.postgres
+          )
+        }
+        | _ => c.postgres.to_string()
+      }
+    })
+    .collect();
+
+  let key = table.key.join(", ");
+
+  let mut builder = QueryBuilder::<
+    Postgres
+  >::new(format!(
+    "SELECT {} FROM {qualified}",
+    columns.join(", ")
+  ));
+
+  if let Some(after) = after {
+    builder.push(format!(
+      " WHERE ({key}) > ("
+    ));
+
+    let mut separated =
+      builder.separated(", ");
+
+    for i in table.key_indexes() {
+      match &after[i] {
+        | Value::Int(v) => {
+          separated.push_bind(*v);
+        }
+        | Value::Text(v) => {
+          separated
+            .push_bind(v.clone());
+        }
+        | Value::Bytes(v) => {
+          separated
+            .push_bind(v.clone());
+        }
+      }
+    }
+
+    separated.push_unseparated(")");
+  }
+
+  builder.push(format!(
+    " ORDER BY {key} LIMIT "
+  ));
+  builder.push_bind(
+    limit.min(i64::MAX as u64) as i64
+  );
+
+  let rows = builder
+    .build()
+    .fetch_all(executor)
+    .await
+    .map_err(|e| {
+      format!(
+        "copy read {qualified} error: \
+         {e}"
+      )
+    })?;
+
+  rows
+    .iter()
+    .map(|row| decode(row, table))
+    .collect()
+}
+
+fn decode(
+  row: &PgRow,
+  table: &Table
+) -> Result<Row, String> {
+  table
+    .columns
+    .iter()
+    .enumerate()
+    .map(|(i, c)| {
+      let value = if is_int(c.kind) {
+        row.try_get(i).map(Value::Int)
+      } else if c.kind
+        == ColumnKind::Bytes
+      {
+        row.try_get(i).map(Value::Bytes)
+      } else {
+        row.try_get(i).map(Value::Text)
+      };
+
+      value.map_err(|e| {
+        format!(
+          "copy decode {}.{} error: \
+           {e}",
+          table.name, c.postgres
+        )
+      })
+    })
+    .collect()
+}
+
+pub async fn insert_batch<'e, E>(
+  executor: E,
+  qualified: &str,
+  table: &Table,
+  rows: &[Row]
+) -> Result<(), String>
+where
+  E: Executor<'e, Database = Postgres>
+{
+  let columns: Vec<&str> = table
+    .columns
+    .iter()
+    .map(|c| c.postgres)
+    .collect();
+
+  let mut builder = QueryBuilder::<
+    Postgres
+  >::new(format!(
+    "INSERT INTO {qualified} ({}) \
+     VALUES ",
+    columns.join(", ")
+  ));
+
+  for (r, row) in
+    rows.iter().enumerate()
+  {
+    if r > 0 {
+      builder.push(", ");
+    }
+
+    builder.push("(");
+
+    for (i, (column, value)) in table
+      .columns
+      .iter()
+      .zip(row)
+      .enumerate()
+    {
+      if i > 0 {
+        builder.push(", ");
+      }
+
+      match (column.kind, value) {
+        | (
+          ColumnKind::EpochMs
+          | ColumnKind::ServerTime,
+          Value::Int(v)
+        ) => {
+          builder
+            .push("to_timestamp(CAST(");
+          builder.push_bind(*v);
+          builder.push(
+            " AS BIGINT) / 1000.0)"
+          );
+        }
+        | (
+          ColumnKind::Tags,
+          Value::Text(v)
+        ) => {
+          builder.push("CASE WHEN ");
+          builder.push_bind(v.clone());
+          builder.push(
+            "::text IS NULL THEN NULL \
+             ELSE ARRAY(SELECT \
+             json_array_elements_text(\
+             CAST("
+          );
+          builder.push_bind(v.clone());
+          builder
+            .push(" AS json))) END");
+        }
+        | (_, Value::Int(v)) => {
+          builder.push_bind(*v);
+        }
+        | (_, Value::Text(v)) => {
+          builder.push_bind(v.clone());
+        }
+        | (_, Value::Bytes(v)) => {
+          builder.push_bind(v.clone());
+        }
+      }
+    }
+
+    builder.push(")");
+  }
+
+  builder
+    .build()
+    .execute(executor)
+    .await
+    .map_err(|e| {
+      format!(
+        "copy insert {qualified} \
+         error: {e}"
+      )
+    })?;
+
+  Ok(())
+}
+
+pub async fn reset_sequence<'e, E>(
+  executor: E,
+  qualified: &str
+) -> Result<(), String>
+where
+  E: Executor<'e, Database = Postgres>
+{
+  let sql = format!(
+    "SELECT setval(\
+     pg_get_serial_sequence($1, 'id'), \
+     COALESCE(MAX(id), 1), MAX(id) IS \
+     NOT NULL) FROM {qualified}"
+  );
+
+  sqlx::query(&sql)
+    .bind(qualified)
+    .execute(executor)
+    .await
+    .map_err(|e| {
+      format!(
+        "copy reset sequence \
+         {qualified} error: {e}"
+      )
+    })?;
+
+  Ok(())
+}
+
+pub fn quote_ident(
+  name: &str
+) -> String {
+  format!(
+    "\"{}\"",
+    name.replace('"', "\"\"")
+  )
+}
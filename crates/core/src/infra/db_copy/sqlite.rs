@@ -0,0 +1,263 @@
+//! SQLite side of `db copy` and
+//! logical backups.
+
+use sqlx::sqlite::SqliteRow;
+use sqlx::{
+  Executor,
+  QueryBuilder,
+  Row as _,
+  Sqlite,
+  SqlitePool
+};
+
+use super::tables::{
+  ColumnKind,
+  Table
+};
+use super::{
+  Row,
+  Value,
+  is_int
+};
+
+pub async fn has_table(
+  pool: &SqlitePool,
+  table: &Table
+) -> Result<bool, String> {
+  let found: Option<i64> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM sqlite_master \
+       WHERE type = 'table' AND name \
+       = ?1"
+    )
+    .bind(table.name)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "copy has_table error: {e}"
+      )
+    })?;
+
+  Ok(found.is_some())
+}
+
+pub async fn count(
+  pool: &SqlitePool,
+  table: &Table
+) -> Result<u64, String> {
+  let sql = format!(
+    "SELECT COUNT(*) FROM {}",
+    table.name
+  );
+
+  let n: i64 = sqlx::query_scalar(&sql)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "copy count {} error: {e}",
+        table.name
+      )
+    })?;
+
+  Ok(n as u64)
+}
+
+pub async fn read_batch<'e, E>(
+  executor: E,
+  table: &Table,
+  after: Option<&Row>,
+  limit: u64
+) -> Result<Vec<Row>, String>
+where
+  E: Executor<'e, Database = Sqlite>
+{
+  let columns: Vec<String> = table
+    .columns
+    .iter()
+    .map(|c| {
+      match c.kind {
+        | ColumnKind::ServerTime => {
+          format!(
+            "CAST(strftime('%s', {}) \
+             AS INTEGER) * 1000",
+            c.sqlite
+          )
+        }
+        | _ => c.sqlite.to_string()
+      }
+    })
+    .collect();
+
+  let key = table.key.join(", ");
+
+  let mut builder = QueryBuilder::<
+    Sqlite
+  >::new(format!(
+    "SELECT {} FROM {}",
+    columns.join(", "),
+    table.name
+  ));
+
+  if let Some(after) = after {
+    builder.push(format!(
+      " WHERE ({key}) > ("
+    ));
+
+    let mut separated =
+      builder.separated(", ");
+
+    for i in table.key_indexes() {
+      match &after[i] {
+        | Value::Int(v) => {
+          separated.push_bind(*v);
+        }
+        | Value::Text(v) => {
+          separated
+            .push_bind(v.clone());
+        }
+        | Value::Bytes(v) => {
+          separated
+            .push_bind(v.clone());
+        }
+      }
+    }
+
+    separated.push_unseparated(")");
+  }
+
+  builder.push(format!(
+    " ORDER BY {key} LIMIT "
+  ));
+  builder.push_bind(
+    limit.min(i64::MAX as u64) as i64
+  );
+
+  let rows = builder
+    .build()
+    .fetch_all(executor)
+    .await
+    .map_err(|e| {
+      format!(
+        "copy read {} error: {e}",
+        table.name
+      )
+    })?;
+
+  rows
+    .iter()
+    .map(|row| decode(row, table))
+    .collect()
+}
+
+fn decode(
+  row: &SqliteRow,
+  table: &Table
+) -> Result<Row, String> {
+  table
+    .columns
+    .iter()
+    .enumerate()
+    .map(|(i, c)| {
+      let value = if is_int(c.kind) {
+        row.try_get(i).map(Value::Int)
+      } else if c.kind
+        == ColumnKind::Bytes
+      {
+        row.try_get(i).map(Value::Bytes)
+      } else {
+        row.try_get(i).map(Value::Text)
+      };
+
+      value.map_err(|e| {
+        format!(
+          "copy decode {}.{} error: \
+           {e}",
+          table.name, c.sqlite
+        )
+      })
+    })
+    .collect()
+}
+
+pub async fn insert_batch<'e, E>(
+  executor: E,
+  table: &Table,
+  rows: &[Row]
+) -> Result<(), String>
+where
+  E: Executor<'e, Database = Sqlite>
+{
+  let columns: Vec<&str> = table
+    .columns
+    .iter()
+    .map(|c| c.sqlite)
+    .collect();
+
+  let mut builder = QueryBuilder::<
+    Sqlite
+  >::new(format!(
+    "INSERT INTO {} ({}) VALUES ",
+    table.name,
+    columns.join(", ")
+  ));
+
+  for (r, row) in
+    rows.iter().enumerate()
+  {
+    if r > 0 {
+      builder.push(", ");
+    }
+
+    builder.push("(");
+
+    for (i, (column, value)) in table
+      .columns
+      .iter()
+      .zip(row)
+      .enumerate()
+    {
+      if i > 0 {
+        builder.push(", ");
+      }
+
+      match value {
+        | Value::Int(v)
+          if column.kind
+            == ColumnKind::ServerTime =>
+        {
+          builder.push("datetime(");
+          builder.push_bind(*v);
+          builder.push(
+            " / 1000, 'unixepoch')"
+          );
+        }
+        | Value::Int(v) => {
+          builder.push_bind(*v);
+        }
+        | Value::Text(v) => {
+          builder.push_bind(v.clone());
+        }
+        | Value::Bytes(v) => {
+          builder.push_bind(v.clone());
+        }
+      }
+    }
+
+    builder.push(")");
+  }
+
+  builder
+    .build()
+    .execute(executor)
+    .await
+    .map_err(|e| {
+      format!(
+        "copy insert {} error: {e}",
+        table.name
+      )
+    })?;
+
+  Ok(())
+}
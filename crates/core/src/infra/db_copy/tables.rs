@@ -0,0 +1,626 @@
+//! Column catalog of every fetcher and
+//! server table, with the name each
+//! column has per dialect and how its
+//! value converts between them. Tables
+//! are listed in foreign key order.
+
+use crate::infra::migrations::Component;
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum ColumnKind {
+  Int,
+  Text,
+  Bytes,
+  /// `*_ms` INTEGER in SQLite,
+  /// TIMESTAMPTZ in Postgres.
+  EpochMs,
+  /// Server timestamps: UTC
+  /// `YYYY-MM-DD HH:MM:SS` text in
+  /// SQLite, TIMESTAMPTZ in Postgres.
+  /// Compared at second precision.
+  ServerTime,
+  /// JSON array text in SQLite,
+  /// `TEXT[]` in Postgres.
+  Tags
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+  pub sqlite:   &'static str,
+  pub postgres: &'static str,
+  pub kind:     ColumnKind
+}
+
+#[derive(Debug)]
+pub struct Table {
+  pub name:      &'static str,
+  pub component: Component,
+  /// Primary key columns; rows are
+  /// read in this order.
+  pub key: &'static [&'static str],
+  pub columns:   &'static [Column],
+  /// Has a `BIGSERIAL id` whose
+  /// Postgres sequence must follow the
+  /// copied ids.
+  pub serial:    bool
+}
+
+impl Table {
+  pub fn key_indexes(
+    &self
+  ) -> Vec<usize> {
+    self
+      .key
+      .iter()
+      .map(|k| {
+        self
+          .columns
+          .iter()
+          .position(|c| c.sqlite == *k)
+          .expect("key column listed")
+      })
+      .collect()
+  }
+}
+
+const fn int(
+  name: &'static str
+) -> Column {
+  Column {
+    sqlite:   name,
+    postgres: name,
+    kind:     ColumnKind::Int
+  }
+}
+
+const fn text(
+  name: &'static str
+) -> Column {
+  Column {
+    sqlite:   name,
+    postgres: name,
+    kind:     ColumnKind::Text
+  }
+}
+
+const fn ms(
+  sqlite: &'static str,
+  postgres: &'static str
+) -> Column {
+  Column {
+    sqlite,
+    postgres,
+    kind: ColumnKind::EpochMs
+  }
+}
+
+const fn server_time(
+  name: &'static str
+) -> Column {
+  Column {
+    sqlite:   name,
+    postgres: name,
+    kind:     ColumnKind::ServerTime
+  }
+}
+
+/// Columns shared by
+/// `feed_state_current` and
+/// `feed_state_history` after their
+/// key.
+macro_rules! feed_state_columns {
+  ($($key:expr),*) => {
+    &[
+      $($key,)*
+      text("phase"),
+      ms("last_head_at_ms", "last_head_at"),
+      int("last_head_status"),
+      text("last_head_error"),
+      ms("last_get_at_ms", "last_get_at"),
+      int("last_get_status"),
+      text("last_get_error"),
+      text("etag"),
+      ms("last_modified_ms", "last_modified_at"),
+      int("backoff_index"),
+      int("base_poll_seconds"),
+      ms("next_action_at_ms", "next_action_at"),
+      int("jitter_seconds"),
+      text("note"),
+      int("consecutive_error_count"),
+    ]
+  };
+}
+
+pub const TABLES: &[Table] = &[
+  Table {
+    name:      "categories",
+    component: Component::Fetcher,
+    key:       &["name"],
+    columns:   &[
+      text("name"),
+      ms("created_at_ms", "created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "feeds",
+    component: Component::Fetcher,
+    key:       &["id"],
+    columns:   &[
+      text("id"),
+      text("url"),
+      text("domain"),
+      text("category"),
+      int("base_poll_seconds"),
+      Column {
+        sqlite:   "tags",
+        postgres: "tags",
+        kind:     ColumnKind::Tags
+      },
+      ms("created_at_ms", "created_at"),
+      text("language")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "feed_state_current",
+    component: Component::Fetcher,
+    key:       &["feed_id"],
+    columns:   feed_state_columns!(
+      text("feed_id")
+    ),
+    serial:    false
+  },
+  Table {
+    name:      "feed_state_history",
+    component: Component::Fetcher,
+    key:       &["id"],
+    columns:   feed_state_columns!(
+      int("id"),
+      text("feed_id"),
+      ms(
+        "recorded_at_ms",
+        "recorded_at"
+      )
+    ),
+    serial:    true
+  },
+  Table {
+    name:      "fetch_events",
+    component: Component::Fetcher,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      text("feed_id"),
+      ms("event_time_ms", "event_time"),
+      text("method"),
+      int("status"),
+      text("error_kind"),
+      int("latency_ms"),
+      int("backoff_index"),
+      ms(
+        "scheduled_next_action_at_ms",
+        "scheduled_next_action_at"
+      ),
+      text("debug")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "feed_payloads",
+    component: Component::Fetcher,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      text("feed_id"),
+      ms("fetched_at_ms", "fetched_at"),
+      text("etag"),
+      ms(
+        "last_modified_ms",
+        "last_modified_at"
+      ),
+      text("content_hash"),
+      text("title"),
+      text("link"),
+      text("description"),
+      text("language"),
+      ms("updated_at_ms", "updated_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "feed_items",
+    component: Component::Fetcher,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("payload_id"),
+      text("feed_id"),
+      text("title"),
+      text("link"),
+      text("guid"),
+      ms(
+        "published_at_ms",
+        "published_at"
+      ),
+      text("category"),
+      text("description"),
+      text("summary"),
+      text("diff"),
+      text("language"),
+      text("author")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "error_feeds",
+    component: Component::Fetcher,
+    key:       &["feed_id"],
+    columns:   &[
+      text("feed_id"),
+      int("error_count"),
+      text("last_error_kind"),
+      int("last_error_status"),
+      ms(
+        "last_error_at_ms",
+        "last_error_at"
+      ),
+      text("note")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "source_cookies",
+    component: Component::Fetcher,
+    key:       &["feed_id"],
+    columns:   &[
+      text("feed_id"),
+      text("cookie_header"),
+      ms("updated_at_ms", "updated_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "watch_snapshots",
+    component: Component::Fetcher,
+    key:       &["feed_id"],
+    columns:   &[
+      text("feed_id"),
+      text("content_text"),
+      ms("updated_at_ms", "updated_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "feed_redirects",
+    component: Component::Fetcher,
+    key:       &["feed_id"],
+    columns:   &[
+      text("feed_id"),
+      text("from_url"),
+      text("suggested_url"),
+      int("status"),
+      int("hit_count"),
+      ms(
+        "first_seen_at_ms",
+        "first_seen_at"
+      ),
+      ms(
+        "last_seen_at_ms",
+        "last_seen_at"
+      )
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "payload_archive",
+    component: Component::Fetcher,
+    key:       &["content_hash"],
+    columns:   &[
+      text("content_hash"),
+      ms("stored_at_ms", "stored_at"),
+      int("size_bytes"),
+      Column {
+        sqlite:   "data",
+        postgres: "data",
+        kind:     ColumnKind::Bytes
+      }
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "payload_archive_index",
+    component: Component::Fetcher,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      text("feed_id"),
+      ms("fetched_at_ms", "fetched_at"),
+      text("content_hash")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "feed_registry",
+    component: Component::Fetcher,
+    key:       &["id"],
+    columns:   &[
+      text("id"),
+      text("url"),
+      text("title"),
+      int("added_by"),
+      text("status"),
+      ms("created_at_ms", "created_at"),
+      ms(
+        "reviewed_at_ms",
+        "reviewed_at"
+      )
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "users",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      text("username"),
+      text("password_hash"),
+      server_time("created_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "user_tokens",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("user_id"),
+      text("token_hash"),
+      server_time("expires_at"),
+      server_time("created_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "user_password_resets",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("user_id"),
+      text("token_hash"),
+      server_time("expires_at"),
+      server_time("created_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "subscriptions",
+    component: Component::Server,
+    key:       &["user_id", "feed_id"],
+    columns:   &[
+      int("user_id"),
+      text("feed_id"),
+      server_time("created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "entry_states",
+    component: Component::Server,
+    key:       &["user_id", "item_id"],
+    columns:   &[
+      int("user_id"),
+      int("item_id"),
+      server_time("read_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "favorites",
+    component: Component::Server,
+    key:       &["user_id", "feed_id"],
+    columns:   &[
+      int("user_id"),
+      text("feed_id"),
+      server_time("created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "folders",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("user_id"),
+      text("name"),
+      server_time("created_at"),
+      text("filter")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "folder_feeds",
+    component: Component::Server,
+    key:       &[
+      "folder_id",
+      "feed_id"
+    ],
+    columns:   &[
+      int("folder_id"),
+      text("feed_id"),
+      server_time("created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "entry_stars",
+    component: Component::Server,
+    key:       &["user_id", "item_id"],
+    columns:   &[
+      int("user_id"),
+      int("item_id"),
+      server_time("created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "entry_queue",
+    component: Component::Server,
+    key:       &["user_id", "item_id"],
+    columns:   &[
+      int("user_id"),
+      int("item_id"),
+      server_time("created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "labels",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("user_id"),
+      text("name"),
+      server_time("created_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "entry_labels",
+    component: Component::Server,
+    key:       &["label_id", "item_id"],
+    columns:   &[
+      int("label_id"),
+      int("item_id"),
+      server_time("created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "saved_searches",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("user_id"),
+      text("name"),
+      text("query"),
+      server_time("created_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "filter_rules",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("user_id"),
+      text("name"),
+      text("filter"),
+      text("action"),
+      int("label_id"),
+      int("checked_item_id"),
+      server_time("created_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "rule_hits",
+    component: Component::Server,
+    key:       &["rule_id", "item_id"],
+    columns:   &[
+      int("rule_id"),
+      int("item_id"),
+      int("user_id"),
+      text("action"),
+      server_time("created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "feed_quotas",
+    component: Component::Server,
+    key:       &["user_id"],
+    columns:   &[
+      int("user_id"),
+      int("max_feeds"),
+      server_time("updated_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "fever_keys",
+    component: Component::Server,
+    key:       &["user_id"],
+    columns:   &[
+      int("user_id"),
+      text("key_hash"),
+      server_time("created_at")
+    ],
+    serial:    false
+  },
+  Table {
+    name:      "webhooks",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("user_id"),
+      text("url"),
+      text("secret"),
+      text("feed_id"),
+      int("folder_id"),
+      int("label_id"),
+      int("rule_id"),
+      int("checked_item_id"),
+      server_time("paused_at"),
+      server_time("created_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "webhook_deliveries",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("webhook_id"),
+      text("event"),
+      text("payload"),
+      int("entry_count"),
+      text("status"),
+      int("attempts"),
+      server_time("next_attempt_at"),
+      int("last_status"),
+      text("last_error"),
+      server_time("created_at"),
+      server_time("delivered_at")
+    ],
+    serial:    true
+  },
+  Table {
+    name:      "published_feeds",
+    component: Component::Server,
+    key:       &["id"],
+    columns:   &[
+      int("id"),
+      int("user_id"),
+      text("token_hash"),
+      text("title"),
+      text("source"),
+      int("folder_id"),
+      int("label_id"),
+      int("search_id"),
+      server_time("created_at")
+    ],
+    serial:    true
+  }
+];
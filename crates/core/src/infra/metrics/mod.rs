@@ -57,7 +57,9 @@ pub struct Metrics {
   http_latency:
     Mutex<HashMap<String, Histogram>>,
   db_timings:
-    Mutex<HashMap<String, Histogram>>
+    Mutex<HashMap<String, Histogram>>,
+  pruned_rows:
+    Mutex<HashMap<String, u64>>
 }
 
 static METRICS: OnceLock<Arc<Metrics>> =
@@ -110,6 +112,9 @@ impl Metrics {
       ),
       db_timings: Mutex::new(
         HashMap::new()
+      ),
+      pruned_rows: Mutex::new(
+        HashMap::new()
       )
     }
   }
@@ -283,6 +288,24 @@ pub fn record_db_time(
   }
 }
 
+pub fn record_pruned(
+  table: &str,
+  rows: u64
+) {
+  let Some(metrics) = METRICS.get()
+  else {
+    return;
+  };
+
+  if let Ok(mut pruned) =
+    metrics.pruned_rows.lock()
+  {
+    *pruned
+      .entry(table.to_string())
+      .or_insert(0) += rows;
+  }
+}
+
 pub fn record_inflight_start()
 -> InFlightGuard {
   let Some(metrics) = METRICS.get()
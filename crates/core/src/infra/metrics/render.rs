@@ -243,6 +243,29 @@ pub(super) fn render(
     );
   }
 
+  let pruned = metrics
+    .pruned_rows
+    .lock()
+    .unwrap_or_else(|e| e.into_inner());
+
+  out.push_str(
+    "# HELP pulsewire_pruned_rows_total Rows deleted by retention per table.\n",
+  );
+
+  out.push_str(
+    "# TYPE pulsewire_pruned_rows_total counter\n",
+  );
+
+  for (table, count) in
+    sorted_map(&pruned)
+  {
+    out.push_str(&format!(
+      "pulsewire_pruned_rows_total{{table=\"{}\"}} {}\n",
+      escape_label(&table),
+      count
+    ));
+  }
+
   out
 }
 
@@ -0,0 +1,803 @@
+//! Versioned, forward-only schema
+//! migrations. Each component (fetcher
+//! schema, server schema) ships
+//! numbered SQL files per dialect under
+//! `res/sql/<dialect>/<component>/`;
+//! applied versions are recorded with
+//! their checksums in
+//! `schema_migrations`.
+
+pub mod postgres;
+pub mod sqlite;
+
+use crate::domain::hashing::sha256_hex;
+use crate::domain::model::{
+  AppConfig,
+  SqlDialect
+};
+use crate::infra::{
+  postgres_repo,
+  sqlite_repo
+};
+
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq,
+)]
+pub enum Component {
+  Fetcher,
+  Server
+}
+
+impl Component {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      | Component::Fetcher => "fetcher",
+      | Component::Server => "server"
+    }
+  }
+}
+
+/// One numbered migration file,
+/// embedded at compile time.
+#[derive(Debug)]
+pub struct Migration {
+  pub version: i64,
+  pub name:    &'static str,
+  pub sql:     &'static str
+}
+
+impl Migration {
+  pub fn checksum(&self) -> String {
+    sha256_hex(self.sql.as_bytes())
+  }
+}
+
+macro_rules! migration {
+  (
+    $version:expr,
+    $name:literal,
+    $path:literal
+  ) => {
+    Migration {
+      version: $version,
+      name:    $name,
+      sql:     include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/res/sql/",
+        $path
+      ))
+    }
+  };
+}
+
+const SQLITE_FETCHER: &[Migration] = &[
+  migration!(
+    1,
+    "baseline",
+    "sqlite/fetcher/0001_baseline.sql"
+  ),
+  migration!(
+    2,
+    "payload_archive",
+    "sqlite/fetcher/\
+     0002_payload_archive.sql"
+  ),
+  migration!(
+    3,
+    "feed_history_indexes",
+    "sqlite/fetcher/\
+     0003_feed_history_indexes.sql"
+  ),
+  migration!(
+    4,
+    "item_search",
+    "sqlite/fetcher/0004_item_search.\
+     sql"
+  ),
+  migration!(
+    5,
+    "item_keyset_indexes",
+    "sqlite/fetcher/\
+     0005_item_keyset_indexes.sql"
+  ),
+  migration!(
+    6,
+    "item_author",
+    "sqlite/fetcher/0006_item_author.\
+     sql"
+  ),
+  migration!(
+    7,
+    "feed_registry",
+    "sqlite/fetcher/\
+     0007_feed_registry.sql"
+  ),
+  migration!(
+    8,
+    "payload_archive_index",
+    "sqlite/fetcher/\
+     0008_payload_archive_index.sql"
+  )
+];
+
+const SQLITE_SERVER: &[Migration] = &[
+  migration!(
+    1,
+    "baseline",
+    "sqlite/server/0001_baseline.sql"
+  ),
+  migration!(
+    2,
+    "entry_marks",
+    "sqlite/server/0002_entry_marks.\
+     sql"
+  ),
+  migration!(
+    3,
+    "smart_folders",
+    "sqlite/server/0003_smart_folders.\
+     sql"
+  ),
+  migration!(
+    4,
+    "filter_rules",
+    "sqlite/server/0004_filter_rules.\
+     sql"
+  ),
+  migration!(
+    5,
+    "feed_quotas",
+    "sqlite/server/0005_feed_quotas.\
+     sql"
+  ),
+  migration!(
+    6,
+    "fever_keys",
+    "sqlite/server/0006_fever_keys.sql"
+  ),
+  migration!(
+    7,
+    "webhooks",
+    "sqlite/server/0007_webhooks.sql"
+  ),
+  migration!(
+    8,
+    "published_feeds",
+    "sqlite/server/\
+     0008_published_feeds.sql"
+  ),
+  migration!(
+    9,
+    "entry_queue_item",
+    "sqlite/server/\
+     0009_entry_queue_item.sql"
+  )
+];
+
+const POSTGRES_FETCHER: &[Migration] =
+  &[
+    migration!(
+      1,
+      "baseline",
+      "postgres/fetcher/0001_baseline.\
+       sql"
+    ),
+    migration!(
+      2,
+      "payload_archive",
+      "postgres/fetcher/\
+       0002_payload_archive.sql"
+    ),
+    migration!(
+      3,
+      "feed_history_indexes",
+      "postgres/fetcher/\
+       0003_feed_history_indexes.sql"
+    ),
+    migration!(
+      4,
+      "item_search",
+      "postgres/fetcher/\
+       0004_item_search.sql"
+    ),
+    migration!(
+      5,
+      "item_keyset_indexes",
+      "postgres/fetcher/\
+       0005_item_keyset_indexes.sql"
+    ),
+    migration!(
+      6,
+      "item_author",
+      "postgres/fetcher/\
+       0006_item_author.sql"
+    ),
+    migration!(
+      7,
+      "feed_registry",
+      "postgres/fetcher/\
+       0007_feed_registry.sql"
+    ),
+    migration!(
+      8,
+      "payload_archive_index",
+      "postgres/fetcher/\
+       0008_payload_archive_index.sql"
+    )
+  ];
+
+const POSTGRES_SERVER: &[Migration] = &[
+  migration!(
+    1,
+    "baseline",
+    "postgres/server/0001_baseline.sql"
+  ),
+  migration!(
+    2,
+    "entry_marks",
+    "postgres/server/0002_entry_marks.\
+     sql"
+  ),
+  migration!(
+    3,
+    "smart_folders",
+    "postgres/server/\
+     0003_smart_folders.sql"
+  ),
+  migration!(
+    4,
+    "filter_rules",
+    "postgres/server/\
+     0004_filter_rules.sql"
+  ),
+  migration!(
+    5,
+    "feed_quotas",
+    "postgres/server/0005_feed_quotas.\
+     sql"
+  ),
+  migration!(
+    6,
+    "fever_keys",
+    "postgres/server/0006_fever_keys.\
+     sql"
+  ),
+  migration!(
+    7,
+    "webhooks",
+    "postgres/server/0007_webhooks.sql"
+  ),
+  migration!(
+    8,
+    "published_feeds",
+    "postgres/server/\
+     0008_published_feeds.sql"
+  ),
+  migration!(
+    9,
+    "entry_queue_item",
+    "postgres/server/\
+     0009_entry_queue_item.sql"
+  )
+];
+
+/// Migrations this build knows for a
+/// component, in version order.
+pub fn known_migrations(
+  component: Component,
+  dialect: SqlDialect
+) -> &'static [Migration] {
+  match (dialect, component) {
+    | (
+      SqlDialect::Sqlite,
+      Component::Fetcher
+    ) => SQLITE_FETCHER,
+    | (
+      SqlDialect::Sqlite,
+      Component::Server
+    ) => SQLITE_SERVER,
+    | (
+      SqlDialect::Postgres,
+      Component::Fetcher
+    ) => POSTGRES_FETCHER,
+    | (
+      SqlDialect::Postgres,
+      Component::Server
+    ) => POSTGRES_SERVER
+  }
+}
+
+/// A row of `schema_migrations`.
+#[derive(Debug, Clone)]
+pub struct AppliedMigration {
+  pub version:       i64,
+  pub name:          String,
+  pub checksum:      String,
+  pub applied_at_ms: i64
+}
+
+/// Ledger contents for one component
+/// compared with the migrations this
+/// build knows.
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+  pub component: Component,
+  pub known:     &'static [Migration],
+  pub applied:   Vec<AppliedMigration>
+}
+
+impl MigrationStatus {
+  /// Highest applied version, 0 for an
+  /// empty ledger.
+  pub fn current_version(&self) -> i64 {
+    self
+      .applied
+      .iter()
+      .map(|m| m.version)
+      .max()
+      .unwrap_or(0)
+  }
+
+  /// Highest version this build knows.
+  pub fn latest_version(&self) -> i64 {
+    self
+      .known
+      .last()
+      .map(|m| m.version)
+      .unwrap_or(0)
+  }
+
+  pub fn pending(
+    &self
+  ) -> Vec<&'static Migration> {
+    self
+      .known
+      .iter()
+      .filter(|m| {
+        !self.applied.iter().any(|a| {
+          a.version == m.version
+        })
+      })
+      .collect()
+  }
+
+  /// Ledger entries that disagree with
+  /// this build: unknown versions,
+  /// edited files, or gaps below the
+  /// current version.
+  pub fn problems(
+    &self
+  ) -> Vec<String> {
+    let mut out = Vec::new();
+
+    for applied in &self.applied {
+      match self.known.iter().find(
+        |m| {
+          m.version == applied.version
+        }
+      ) {
+        | None => {
+          out.push(format!(
+            "{} {:04}_{} is applied \
+             but unknown to this build",
+            self.component.as_str(),
+            applied.version,
+            applied.name
+          ));
+        }
+        | Some(known)
+          if known.checksum()
+            != applied.checksum =>
+        {
+          out.push(format!(
+            "{} {:04}_{} checksum \
+             differs (db {}, build {})",
+            self.component.as_str(),
+            applied.version,
+            applied.name,
+            short(&applied.checksum),
+            short(&known.checksum())
+          ));
+        }
+        | Some(_) => {}
+      }
+    }
+
+    let current =
+      self.current_version();
+
+    for missing in self
+      .pending()
+      .into_iter()
+      .filter(|m| m.version < current)
+    {
+      out.push(format!(
+        "{} {:04}_{} is pending below \
+         applied version {current}",
+        self.component.as_str(),
+        missing.version,
+        missing.name
+      ));
+    }
+
+    out
+  }
+
+  /// Fails when the database was
+  /// migrated by a newer build.
+  pub fn ensure_supported(
+    &self
+  ) -> Result<(), String> {
+    let current =
+      self.current_version();
+
+    let latest = self.latest_version();
+
+    if current > latest {
+      return Err(format!(
+        "{} schema is at version \
+         {current}, newer than this \
+         build understands \
+         ({latest}); upgrade pulsewire",
+        self.component.as_str()
+      ));
+    }
+
+    Ok(())
+  }
+
+  /// Checks run before applying
+  /// pending migrations.
+  fn ensure_appliable(
+    &self
+  ) -> Result<(), String> {
+    self.ensure_supported()?;
+
+    let problems = self.problems();
+
+    if !problems.is_empty() {
+      return Err(format!(
+        "{} migrations failed \
+         verification: {}; run \
+         `pulsewire-cli db verify`",
+        self.component.as_str(),
+        problems.join("; ")
+      ));
+    }
+
+    Ok(())
+  }
+}
+
+fn short(checksum: &str) -> &str {
+  checksum.get(..12).unwrap_or(checksum)
+}
+
+/// Splits a migration into statements
+/// on `;`, skipping those inside
+/// quoted strings and identifiers,
+/// `--` and `/* */` comments and
+/// Postgres `$tag$` bodies. A `CREATE
+/// TRIGGER` runs on to the `END`
+/// closing its body. Comment-only
+/// pieces are dropped.
+pub fn statements(
+  sql: &str
+) -> Vec<&str> {
+  let bytes = sql.as_bytes();
+  let mut out = Vec::new();
+  let mut start = 0;
+  let mut i = 0;
+
+  while i < bytes.len() {
+    i = match bytes[i] {
+      | quote @ (b'\'' | b'"'
+      | b'`') => {
+        skip_quoted(bytes, i, quote)
+      }
+      | b'-'
+        if bytes.get(i + 1)
+          == Some(&b'-') =>
+      {
+        find_from(sql, i, "\n")
+          .map_or(bytes.len(), |end| {
+            end + 1
+          })
+      }
+      | b'/'
+        if bytes.get(i + 1)
+          == Some(&b'*') =>
+      {
+        find_from(sql, i + 2, "*/")
+          .map_or(bytes.len(), |end| {
+            end + 2
+          })
+      }
+      | b'$' => {
+        skip_dollar_quoted(sql, i)
+      }
+      | b';' => {
+        let stmt = sql[start..i].trim();
+
+        if !is_open_trigger(stmt) {
+          if !strip_comments(stmt)
+            .is_empty()
+          {
+            out.push(stmt);
+          }
+
+          start = i + 1;
+        }
+
+        i + 1
+      }
+      | _ => i + 1
+    };
+  }
+
+  let rest = sql[start..].trim();
+
+  if !strip_comments(rest).is_empty() {
+    out.push(rest);
+  }
+
+  out
+}
+
+fn find_from(
+  sql: &str,
+  from: usize,
+  needle: &str
+) -> Option<usize> {
+  sql
+    .get(from..)?
+    .find(needle)
+    .map(|at| from + at)
+}
+
+/// Index past the quote closing the
+/// one at `open`; a doubled quote is
+/// an escaped one.
+fn skip_quoted(
+  bytes: &[u8],
+  open: usize,
+  quote: u8
+) -> usize {
+  let mut i = open + 1;
+
+  while i < bytes.len() {
+    if bytes[i] == quote {
+      if bytes.get(i + 1)
+        == Some(&quote)
+      {
+        i += 2;
+        continue;
+      }
+
+      return i + 1;
+    }
+
+    i += 1;
+  }
+
+  bytes.len()
+}
+
+/// Index past a `$tag$ .. $tag$` body
+/// starting at `open`, or just past
+/// the `$` when it opens none (e.g. a
+/// `$1` parameter or a `$` inside an
+/// identifier).
+fn skip_dollar_quoted(
+  sql: &str,
+  open: usize
+) -> usize {
+  let in_identifier = sql[..open]
+    .chars()
+    .next_back()
+    .is_some_and(|c| {
+      c.is_alphanumeric() || c == '_'
+    });
+
+  if in_identifier {
+    return open + 1;
+  }
+
+  let Some(len) =
+    sql[open + 1..].find('$')
+  else {
+    return open + 1;
+  };
+
+  let tag =
+    &sql[open + 1..open + 1 + len];
+
+  let valid =
+    tag.chars().next().is_none_or(
+      |c| !c.is_ascii_digit()
+    ) && tag.chars().all(|c| {
+      c.is_ascii_alphanumeric()
+        || c == '_'
+    });
+
+  if !valid {
+    return open + 1;
+  }
+
+  let delimiter =
+    &sql[open..open + len + 2];
+
+  find_from(
+    sql,
+    open + delimiter.len(),
+    delimiter
+  )
+  .map_or(sql.len(), |end| {
+    end + delimiter.len()
+  })
+}
+
+/// Whether `stmt` is a trigger whose
+/// body has not reached its `END`.
+fn is_open_trigger(stmt: &str) -> bool {
+  let head = strip_comments(stmt)
+    .split_whitespace()
+    .take(3)
+    .collect::<Vec<_>>()
+    .join(" ")
+    .to_ascii_uppercase();
+
+  let trigger = [
+    "CREATE TRIGGER",
+    "CREATE TEMP TRIGGER",
+    "CREATE TEMPORARY TRIGGER"
+  ]
+  .iter()
+  .any(|p| head.starts_with(p));
+
+  trigger
+    && !stmt
+      .to_ascii_uppercase()
+      .ends_with("END")
+}
+
+/// `stmt` without leading whitespace
+/// and comments.
+fn strip_comments(stmt: &str) -> &str {
+  let mut rest = stmt.trim_start();
+
+  loop {
+    if let Some(line) =
+      rest.strip_prefix("--")
+    {
+      rest = line
+        .split_once('\n')
+        .map_or("", |(_, next)| next)
+        .trim_start();
+    } else if let Some(block) =
+      rest.strip_prefix("/*")
+    {
+      rest = block
+        .split_once("*/")
+        .map_or("", |(_, next)| next)
+        .trim_start();
+    } else {
+      return rest;
+    }
+  }
+}
+
+/// Reads the ledger of `component` in
+/// the database named by the fetcher
+/// config. On Postgres the server
+/// component lives in `server_schema`.
+pub async fn database_status(
+  cfg: &AppConfig,
+  component: Component,
+  server_schema: &str
+) -> Result<MigrationStatus, String> {
+  match cfg.db_dialect {
+    | SqlDialect::Sqlite => {
+      let pool =
+        sqlite_repo::create_pool(
+          &cfg.sqlite_path
+        )
+        .await?;
+
+      sqlite::status(&pool, component)
+        .await
+    }
+    | SqlDialect::Postgres => {
+      let mut conn = postgres_conn(
+        cfg,
+        component,
+        server_schema
+      )
+      .await?;
+
+      postgres::status(
+        &mut conn, component
+      )
+      .await
+    }
+  }
+}
+
+/// Applies pending server migrations;
+/// fetcher migrations run through
+/// `Repo::migrate` so pre-ledger
+/// databases are adopted the same way
+/// the fetcher does at startup.
+pub async fn migrate_server(
+  cfg: &AppConfig,
+  server_schema: &str
+) -> Result<
+  Vec<&'static Migration>,
+  String
+> {
+  match cfg.db_dialect {
+    | SqlDialect::Sqlite => {
+      let pool =
+        sqlite_repo::create_pool(
+          &cfg.sqlite_path
+        )
+        .await?;
+
+      sqlite::apply_pending(
+        &pool,
+        Component::Server
+      )
+      .await
+    }
+    | SqlDialect::Postgres => {
+      let mut conn = postgres_conn(
+        cfg,
+        Component::Server,
+        server_schema
+      )
+      .await?;
+
+      postgres::apply_pending(
+        &mut conn,
+        Component::Server
+      )
+      .await
+    }
+  }
+}
+
+async fn postgres_conn(
+  cfg: &AppConfig,
+  component: Component,
+  server_schema: &str
+) -> Result<
+  sqlx::pool::PoolConnection<
+    sqlx::Postgres
+  >,
+  String
+> {
+  let pool =
+    postgres_repo::create_pool(
+      &cfg.postgres,
+      &cfg.timezone
+    )
+    .await?;
+
+  let mut conn = pool
+    .acquire()
+    .await
+    .map_err(|e| {
+    format!("postgres acquire: {e}")
+  })?;
+
+  if component == Component::Server {
+    postgres::set_server_search_path(
+      &mut conn,
+      server_schema,
+      &cfg.postgres.schema
+    )
+    .await?;
+  }
+
+  Ok(conn)
+}
@@ -0,0 +1,261 @@
+//! Postgres ledger: each component
+//! keeps `schema_migrations` in its
+//! own schema. Functions run on a
+//! single connection whose
+//! `search_path` the caller has set.
+
+use sqlx::{
+  Connection,
+  PgConnection
+};
+use tracing::info;
+
+use super::{
+  AppliedMigration,
+  Component,
+  Migration,
+  MigrationStatus,
+  known_migrations,
+  statements
+};
+use crate::domain::model::SqlDialect;
+
+const LEDGER_DDL: &str =
+  "CREATE TABLE IF NOT EXISTS \
+   schema_migrations(component TEXT \
+   NOT NULL, version BIGINT NOT NULL, \
+   name TEXT NOT NULL, checksum TEXT \
+   NOT NULL, applied_at TIMESTAMPTZ \
+   NOT NULL DEFAULT NOW(), PRIMARY \
+   KEY (component, version))";
+
+/// Points the connection at the server
+/// schema, with the fetcher schema
+/// second so server tables can
+/// reference `feeds` and `feed_items`.
+pub async fn set_server_search_path(
+  conn: &mut PgConnection,
+  server_schema: &str,
+  fetcher_schema: &str
+) -> Result<(), String> {
+  let server =
+    quote_ident(server_schema);
+
+  let create = format!(
+    "CREATE SCHEMA IF NOT EXISTS \
+     {server}"
+  );
+
+  sqlx::query(&create)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+      format!(
+        "postgres create schema \
+         error: {e}"
+      )
+    })?;
+
+  let search = format!(
+    "SET search_path TO {server}, {}",
+    quote_ident(fetcher_schema)
+  );
+
+  sqlx::query(&search)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+      format!(
+        "postgres search_path error: \
+         {e}"
+      )
+    })?;
+
+  Ok(())
+}
+
+/// Reads the ledger without creating
+/// it; a schema without one reports
+/// every migration as pending.
+pub async fn status(
+  conn: &mut PgConnection,
+  component: Component
+) -> Result<MigrationStatus, String> {
+  let has_ledger: Option<String> =
+    sqlx::query_scalar(
+      "SELECT to_regclass('\
+       schema_migrations')::text"
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| {
+      format!(
+        "introspect \
+         schema_migrations: {e}"
+      )
+    })?;
+
+  let applied = if has_ledger.is_some()
+  {
+    read_ledger(conn, component).await?
+  } else {
+    Vec::new()
+  };
+
+  Ok(MigrationStatus {
+    component,
+    known: known_migrations(
+      component,
+      SqlDialect::Postgres
+    ),
+    applied
+  })
+}
+
+/// Applies pending migrations in
+/// order, each in its own transaction
+/// together with its ledger row.
+/// Refuses to run against a schema
+/// that is newer than this build or
+/// whose ledger fails verification.
+pub async fn apply_pending(
+  conn: &mut PgConnection,
+  component: Component
+) -> Result<
+  Vec<&'static Migration>,
+  String
+> {
+  sqlx::query(LEDGER_DDL)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+      format!(
+        "create schema_migrations: {e}"
+      )
+    })?;
+
+  let status =
+    status(conn, component).await?;
+
+  status.ensure_appliable()?;
+
+  let pending = status.pending();
+
+  for migration in &pending {
+    apply_one(
+      conn, component, migration
+    )
+    .await?;
+  }
+
+  Ok(pending)
+}
+
+async fn apply_one(
+  conn: &mut PgConnection,
+  component: Component,
+  migration: &Migration
+) -> Result<(), String> {
+  let mut tx =
+    conn.begin().await.map_err(
+      |e| format!("migrate begin: {e}")
+    )?;
+
+  for stmt in statements(migration.sql)
+  {
+    sqlx::query(stmt)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "migrate {} {:04}_{}: {e}",
+          component.as_str(),
+          migration.version,
+          migration.name
+        )
+      })?;
+  }
+
+  sqlx::query(
+    "INSERT INTO schema_migrations \
+     (component, version, name, \
+     checksum) VALUES ($1, $2, $3, $4)"
+  )
+  .bind(component.as_str())
+  .bind(migration.version)
+  .bind(migration.name)
+  .bind(migration.checksum())
+  .execute(&mut *tx)
+  .await
+  .map_err(|e| {
+    format!("record migration: {e}")
+  })?;
+
+  tx.commit().await.map_err(|e| {
+    format!("migrate commit: {e}")
+  })?;
+
+  info!(
+    component = component.as_str(),
+    version = migration.version,
+    name = migration.name,
+    "Applied migration"
+  );
+
+  Ok(())
+}
+
+async fn read_ledger(
+  conn: &mut PgConnection,
+  component: Component
+) -> Result<Vec<AppliedMigration>, String>
+{
+  let rows: Vec<(
+    i64,
+    String,
+    String,
+    i64
+  )> = sqlx::query_as(
+    "SELECT version, name, checksum, \
+     CAST(EXTRACT(EPOCH FROM \
+     applied_at) * 1000 AS BIGINT) \
+     FROM schema_migrations WHERE \
+     component = $1 ORDER BY version"
+  )
+  .bind(component.as_str())
+  .fetch_all(&mut *conn)
+  .await
+  .map_err(|e| {
+    format!(
+      "read schema_migrations: {e}"
+    )
+  })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(
+        |(
+          version,
+          name,
+          checksum,
+          applied_at_ms
+        )| {
+          AppliedMigration {
+            version,
+            name,
+            checksum,
+            applied_at_ms
+          }
+        }
+      )
+      .collect()
+  )
+}
+
+fn quote_ident(name: &str) -> String {
+  format!(
+    "\"{}\"",
+    name.replace('"', "\"\"")
+  )
+}
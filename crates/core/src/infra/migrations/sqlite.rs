@@ -0,0 +1,202 @@
+//! SQLite ledger: one
+//! `schema_migrations` table shared by
+//! both components, keyed by component
+//! and version.
+
+use sqlx::SqlitePool;
+use tracing::info;
+
+use super::{
+  AppliedMigration,
+  Component,
+  Migration,
+  MigrationStatus,
+  known_migrations,
+  statements
+};
+use crate::domain::model::SqlDialect;
+
+const LEDGER_DDL: &str =
+  "CREATE TABLE IF NOT EXISTS \
+   schema_migrations(component TEXT \
+   NOT NULL, version INTEGER NOT \
+   NULL, name TEXT NOT NULL, checksum \
+   TEXT NOT NULL, applied_at_ms \
+   INTEGER NOT NULL, PRIMARY KEY \
+   (component, version))";
+
+/// Reads the ledger without creating
+/// it; a database without one reports
+/// every migration as pending.
+pub async fn status(
+  pool: &SqlitePool,
+  component: Component
+) -> Result<MigrationStatus, String> {
+  let has_ledger: Option<i64> = sqlx::query_scalar(
+        r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='schema_migrations' LIMIT 1"#,
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("introspect sqlite_master: {e}"))?;
+
+  let applied = if has_ledger.is_some()
+  {
+    read_ledger(pool, component).await?
+  } else {
+    Vec::new()
+  };
+
+  Ok(MigrationStatus {
+    component,
+    known: known_migrations(
+      component,
+      SqlDialect::Sqlite
+    ),
+    applied
+  })
+}
+
+/// Applies pending migrations in
+/// order, each in its own transaction
+/// together with its ledger row.
+/// Refuses to run against a database
+/// that is newer than this build or
+/// whose ledger fails verification.
+pub async fn apply_pending(
+  pool: &SqlitePool,
+  component: Component
+) -> Result<
+  Vec<&'static Migration>,
+  String
+> {
+  sqlx::query(LEDGER_DDL)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "create schema_migrations: {e}"
+      )
+    })?;
+
+  let status =
+    status(pool, component).await?;
+
+  status.ensure_appliable()?;
+
+  let pending = status.pending();
+
+  for migration in &pending {
+    apply_one(
+      pool, component, migration
+    )
+    .await?;
+  }
+
+  Ok(pending)
+}
+
+async fn apply_one(
+  pool: &SqlitePool,
+  component: Component,
+  migration: &Migration
+) -> Result<(), String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("migrate begin: {e}")
+    )?;
+
+  for stmt in statements(migration.sql)
+  {
+    sqlx::query(stmt)
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "migrate {} {:04}_{}: {e}",
+          component.as_str(),
+          migration.version,
+          migration.name
+        )
+      })?;
+  }
+
+  sqlx::query(
+    "INSERT INTO schema_migrations \
+     (component, version, name, \
+     checksum, applied_at_ms) VALUES \
+     (?1, ?2, ?3, ?4, ?5)"
+  )
+  .bind(component.as_str())
+  .bind(migration.version)
+  .bind(migration.name)
+  .bind(migration.checksum())
+  .bind(
+    chrono::Utc::now()
+      .timestamp_millis()
+  )
+  .execute(&mut *tx)
+  .await
+  .map_err(|e| {
+    format!("record migration: {e}")
+  })?;
+
+  tx.commit().await.map_err(|e| {
+    format!("migrate commit: {e}")
+  })?;
+
+  info!(
+    component = component.as_str(),
+    version = migration.version,
+    name = migration.name,
+    "Applied migration"
+  );
+
+  Ok(())
+}
+
+async fn read_ledger(
+  pool: &SqlitePool,
+  component: Component
+) -> Result<Vec<AppliedMigration>, String>
+{
+  let rows: Vec<(
+    i64,
+    String,
+    String,
+    i64
+  )> = sqlx::query_as(
+    "SELECT version, name, checksum, \
+     applied_at_ms FROM \
+     schema_migrations WHERE \
+     component = ?1 ORDER BY version"
+  )
+  .bind(component.as_str())
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "read schema_migrations: {e}"
+    )
+  })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(
+        |(
+          version,
+          name,
+          checksum,
+          applied_at_ms
+        )| {
+          AppliedMigration {
+            version,
+            name,
+            checksum,
+            applied_at_ms
+          }
+        }
+      )
+      .collect()
+  )
+}
@@ -1,14 +1,22 @@
 //! Infrastructure adapters: config
 //! loading, logging setup, HTTP client,
-//! SQLite repo, time, randomness.
+//! SQLite repo, payload archive,
+//! cross-dialect copy, backups,
+//! write-behind batching, time,
+//! randomness.
 
+pub mod archive;
+pub mod backup;
 pub mod config;
 pub mod database;
+pub mod db_copy;
 pub mod logging;
 pub mod metrics;
+pub mod migrations;
 pub mod postgres_repo;
 pub mod random;
 pub mod reqwest_http;
 pub mod sqlite_repo;
 pub mod system_clock;
 pub mod time;
+pub mod write_behind;
@@ -0,0 +1,244 @@
+//! Payload archive blobs and the
+//! archive index used for reparsing
+//! (Postgres).
+
+use std::collections::HashSet;
+
+use chrono_tz::Tz;
+use sqlx::PgPool;
+
+use super::util::ts_from_ms;
+use crate::ports::repo::ArchiveRefRow;
+
+pub async fn insert_archive_ref(
+  pool: &PgPool,
+  feed_id: &str,
+  fetched_at_ms: i64,
+  content_hash: &str,
+  zone: &Tz
+) -> Result<(), String> {
+  sqlx::query(
+    "INSERT INTO \
+     payload_archive_index(feed_id, \
+     fetched_at, content_hash) VALUES \
+     ($1, $2, $3)"
+  )
+  .bind(feed_id)
+  .bind(ts_from_ms(fetched_at_ms, zone))
+  .bind(content_hash)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "insert_archive_ref error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
+
+/// Index rows of a feed with the
+/// payload stored for the same fetch
+/// and body, if any.
+pub async fn archive_refs_since(
+  pool: &PgPool,
+  feed_id: &str,
+  since_ms: i64,
+  zone: &Tz
+) -> Result<Vec<ArchiveRefRow>, String>
+{
+  let rows: Vec<(
+    i64,
+    String,
+    Option<i64>
+  )> = sqlx::query_as(
+    "SELECT CAST(EXTRACT(EPOCH FROM \
+     a.fetched_at) * 1000 AS BIGINT), \
+     a.content_hash, (SELECT \
+     MIN(p.id) FROM feed_payloads p \
+     WHERE p.feed_id = a.feed_id AND \
+     p.fetched_at = a.fetched_at AND \
+     p.content_hash = a.content_hash) \
+     FROM payload_archive_index a \
+     WHERE a.feed_id = $1 AND \
+     a.fetched_at >= $2 ORDER BY \
+     a.fetched_at, a.id"
+  )
+  .bind(feed_id)
+  .bind(ts_from_ms(since_ms, zone))
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "archive_refs_since error: {e}"
+    )
+  })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(
+        |(
+          fetched_at_ms,
+          content_hash,
+          payload_id
+        )| {
+          ArchiveRefRow {
+            fetched_at_ms,
+            content_hash,
+            payload_id
+          }
+        }
+      )
+      .collect()
+  )
+}
+
+pub async fn archived_blob_exists(
+  pool: &PgPool,
+  content_hash: &str
+) -> Result<bool, String> {
+  let found: Option<i64> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM payload_archive \
+       WHERE content_hash = $1"
+    )
+    .bind(content_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "archived_blob_exists error: \
+         {e}"
+      )
+    })?;
+
+  Ok(found.is_some())
+}
+
+pub async fn insert_archived_blob(
+  pool: &PgPool,
+  content_hash: &str,
+  stored_at_ms: i64,
+  data: &[u8],
+  zone: &Tz
+) -> Result<(), String> {
+  sqlx::query(
+    "INSERT INTO payload_archive \
+     (content_hash, stored_at, \
+     size_bytes, data) VALUES ($1, \
+     $2, $3, $4) ON \
+     CONFLICT(content_hash) DO NOTHING"
+  )
+  .bind(content_hash)
+  .bind(ts_from_ms(stored_at_ms, zone))
+  .bind(data.len() as i64)
+  .bind(data)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "insert_archived_blob error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
+
+pub async fn archived_blob(
+  pool: &PgPool,
+  content_hash: &str
+) -> Result<Option<Vec<u8>>, String> {
+  sqlx::query_scalar(
+    "SELECT data FROM payload_archive \
+     WHERE content_hash = $1"
+  )
+  .bind(content_hash)
+  .fetch_optional(pool)
+  .await
+  .map_err(|e| {
+    format!("archived_blob error: {e}")
+  })
+}
+
+pub async fn orphan_archived_blobs(
+  pool: &PgPool,
+  limit: u64
+) -> Result<Vec<String>, String> {
+  sqlx::query_scalar(
+    "SELECT a.content_hash FROM \
+     payload_archive a WHERE NOT \
+     EXISTS (SELECT 1 FROM \
+     feed_payloads p WHERE \
+     p.content_hash = a.content_hash) \
+     AND NOT EXISTS (SELECT 1 FROM \
+     payload_archive_index i WHERE \
+     i.content_hash = a.content_hash) \
+     LIMIT $1"
+  )
+  .bind(
+    limit.min(i64::MAX as u64) as i64
+  )
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "orphan_archived_blobs error: \
+       {e}"
+    )
+  })
+}
+
+pub async fn delete_archived_blobs(
+  pool: &PgPool,
+  content_hashes: &[String]
+) -> Result<u64, String> {
+  if content_hashes.is_empty() {
+    return Ok(0);
+  }
+
+  sqlx::query(
+    "DELETE FROM payload_archive \
+     WHERE content_hash = ANY($1)"
+  )
+  .bind(content_hashes)
+  .execute(pool)
+  .await
+  .map(|res| res.rows_affected())
+  .map_err(|e| {
+    format!(
+      "delete_archived_blobs error: \
+       {e}"
+    )
+  })
+}
+
+pub async fn referenced_content_hashes(
+  pool: &PgPool,
+  content_hashes: &[String]
+) -> Result<HashSet<String>, String> {
+  if content_hashes.is_empty() {
+    return Ok(HashSet::new());
+  }
+
+  let rows: Vec<String> =
+    sqlx::query_scalar(
+      "SELECT content_hash FROM \
+       feed_payloads WHERE \
+       content_hash = ANY($1) UNION \
+       SELECT content_hash FROM \
+       payload_archive_index WHERE \
+       content_hash = ANY($1)"
+    )
+    .bind(content_hashes)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "referenced_content_hashes \
+         error: {e}"
+      )
+    })?;
+
+  Ok(rows.into_iter().collect())
+}
@@ -3,13 +3,23 @@
 //! Postgres.
 
 use chrono_tz::Tz;
-use sqlx::PgPool;
+use sqlx::{
+  PgPool,
+  Postgres,
+  QueryBuilder
+};
 
 use super::util::{
   now_epoch_ms,
   ts_from_ms
 };
 use crate::domain::model::ErrorKind;
+use crate::ports::repo::NewEvent;
+
+/// Rows per multi-row statement; nine
+/// binds a row stays far below the
+/// 65535 parameter limit.
+const CHUNK_ROWS: usize = 1_000;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn insert_event(
@@ -24,42 +34,88 @@ pub async fn insert_event(
   debug: Option<&str>,
   zone: &Tz
 ) -> Result<(), String> {
-  let now_ms = now_epoch_ms();
+  insert_events(
+    pool,
+    &[NewEvent {
+      feed_id: feed_id.to_string(),
+      event_time_ms: now_epoch_ms(),
+      method: method.to_string(),
+      status,
+      error_kind,
+      latency_ms,
+      backoff_index,
+      scheduled_next_action_at_ms,
+      debug: debug.map(str::to_string)
+    }],
+    zone
+  )
+  .await
+}
+
+pub async fn insert_events(
+  pool: &PgPool,
+  events: &[NewEvent],
+  zone: &Tz
+) -> Result<(), String> {
+  if events.is_empty() {
+    return Ok(());
+  }
 
-  let event_time =
-    ts_from_ms(now_ms, zone);
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
 
-  let scheduled_next_action_at =
-    ts_from_ms(
-      scheduled_next_action_at_ms,
-      zone
+  for chunk in events.chunks(CHUNK_ROWS)
+  {
+    let mut builder =
+      QueryBuilder::<Postgres>::new(
+        "INSERT INTO \
+         fetch_events(feed_id, \
+         event_time, method, status, \
+         error_kind, latency_ms, \
+         backoff_index, \
+         scheduled_next_action_at, \
+         debug) "
+      );
+
+    builder.push_values(
+      chunk,
+      |mut row, e| {
+        row
+          .push_bind(&e.feed_id)
+          .push_bind(ts_from_ms(
+            e.event_time_ms,
+            zone
+          ))
+          .push_bind(&e.method)
+          .push_bind(e.status)
+          .push_bind(
+            e.error_kind
+              .map(|k| format!("{k:?}"))
+          )
+          .push_bind(e.latency_ms)
+          .push_bind(e.backoff_index)
+          .push_bind(ts_from_ms(
+            e.scheduled_next_action_at_ms,
+            zone
+          ))
+          .push_bind(&e.debug);
+      }
     );
 
-  sqlx::query(
-        r#"
-      INSERT INTO fetch_events(
-        feed_id, event_time, method,
-        status, error_kind, latency_ms, backoff_index,
-        scheduled_next_action_at, debug
-      ) VALUES (
-        $1, $2, $3,
-        $4, $5, $6, $7,
-        $8, $9
-      )
-      "#,
-    )
-    .bind(feed_id)
-    .bind(event_time)
-    .bind(method)
-    .bind(status)
-    .bind(error_kind.map(|e| format!("{:?}", e)))
-    .bind(latency_ms)
-    .bind(backoff_index)
-    .bind(scheduled_next_action_at)
-    .bind(debug.map(|s| s.to_string()))
-    .execute(pool)
-    .await
-    .map_err(|e| format!("insert_event error: {e}"))?;
+    builder
+      .build()
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "insert_events error: {e}"
+        )
+      })?;
+  }
 
-  Ok(())
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })
 }
@@ -0,0 +1,176 @@
+//! Read queries over fetch events,
+//! state history, error feeds and
+//! stored payloads, with timestamps
+//! read back as epoch milliseconds.
+
+use sqlx::PgPool;
+
+use super::models::{
+  ErrorFeedRecord,
+  FetchEventRecord,
+  PayloadSummaryRecord,
+  StateHistoryRecord,
+  StoredItemRecord
+};
+use crate::ports::repo::{
+  ErrorFeedRow,
+  FetchEventRow,
+  PayloadSummaryRow,
+  StateHistoryRow,
+  StoredItemRow
+};
+
+pub async fn recent_events(
+  pool: &PgPool,
+  feed_id: &str,
+  limit: u64
+) -> Result<Vec<FetchEventRow>, String>
+{
+  let rows = sqlx::query_as::<_, FetchEventRecord>(
+        r#"
+      SELECT id, feed_id,
+        CAST(EXTRACT(EPOCH FROM event_time) * 1000 AS BIGINT) AS event_time_ms,
+        method, status, error_kind, latency_ms, backoff_index,
+        CAST(EXTRACT(EPOCH FROM scheduled_next_action_at) * 1000 AS BIGINT) AS scheduled_next_action_at_ms,
+        debug
+      FROM fetch_events
+      WHERE feed_id = $1
+      ORDER BY event_time DESC, id DESC
+      LIMIT $2
+      "#,
+    )
+    .bind(feed_id)
+    .bind(limit.min(i64::MAX as u64) as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("recent_events error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(FetchEventRow::from)
+      .collect()
+  )
+}
+
+pub async fn state_history(
+  pool: &PgPool,
+  feed_id: &str,
+  limit: u64
+) -> Result<Vec<StateHistoryRow>, String>
+{
+  let rows = sqlx::query_as::<_, StateHistoryRecord>(
+        r#"
+      SELECT CAST(EXTRACT(EPOCH FROM recorded_at) * 1000 AS BIGINT) AS recorded_at_ms,
+        feed_id, phase, last_head_at, last_head_status, last_head_error,
+        last_get_at, last_get_status, last_get_error, etag,
+        last_modified_at, backoff_index, base_poll_seconds, next_action_at,
+        jitter_seconds, note, consecutive_error_count
+      FROM feed_state_history
+      WHERE feed_id = $1
+      ORDER BY recorded_at DESC, id DESC
+      LIMIT $2
+      "#,
+    )
+    .bind(feed_id)
+    .bind(limit.min(i64::MAX as u64) as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("state_history error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(StateHistoryRow::from)
+      .collect()
+  )
+}
+
+pub async fn error_feeds(
+  pool: &PgPool
+) -> Result<Vec<ErrorFeedRow>, String> {
+  let rows = sqlx::query_as::<_, ErrorFeedRecord>(
+        r#"
+      SELECT feed_id, error_count, last_error_kind, last_error_status,
+        CAST(EXTRACT(EPOCH FROM last_error_at) * 1000 AS BIGINT) AS last_error_at_ms,
+        note
+      FROM error_feeds
+      ORDER BY feed_id
+      "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("error_feeds error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(ErrorFeedRow::from)
+      .collect()
+  )
+}
+
+pub async fn payload_timeline(
+  pool: &PgPool,
+  feed_id: &str,
+  limit: u64
+) -> Result<
+  Vec<PayloadSummaryRow>,
+  String
+> {
+  let rows = sqlx::query_as::<_, PayloadSummaryRecord>(
+        r#"
+      SELECT p.id AS payload_id, p.feed_id,
+        CAST(EXTRACT(EPOCH FROM p.fetched_at) * 1000 AS BIGINT) AS fetched_at_ms,
+        p.etag,
+        CAST(EXTRACT(EPOCH FROM p.last_modified_at) * 1000 AS BIGINT) AS last_modified_ms,
+        p.content_hash, p.title,
+        (SELECT COUNT(*) FROM feed_items i WHERE i.payload_id = p.id)
+          AS item_count
+      FROM feed_payloads p
+      WHERE p.feed_id = $1
+      ORDER BY p.fetched_at DESC, p.id DESC
+      LIMIT $2
+      "#,
+    )
+    .bind(feed_id)
+    .bind(limit.min(i64::MAX as u64) as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("payload_timeline error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(PayloadSummaryRow::from)
+      .collect()
+  )
+}
+
+pub async fn payload_items(
+  pool: &PgPool,
+  payload_id: i64
+) -> Result<Vec<StoredItemRow>, String>
+{
+  let rows = sqlx::query_as::<_, StoredItemRecord>(
+        r#"
+      SELECT id, payload_id, feed_id, title, link, guid,
+        CAST(EXTRACT(EPOCH FROM published_at) * 1000 AS BIGINT) AS published_at_ms,
+        category, summary
+      FROM feed_items
+      WHERE payload_id = $1
+      ORDER BY id
+      "#,
+    )
+    .bind(payload_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("payload_items error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(StoredItemRow::from)
+      .collect()
+  )
+}
@@ -2,15 +2,24 @@
 //! associated feed items in a single
 //! transaction (Postgres).
 
+use std::collections::HashSet;
+
 use chrono_tz::Tz;
-use sqlx::PgPool;
+use sqlx::{
+  PgConnection,
+  PgPool
+};
 use tracing::debug;
 
 use super::util::{
   ts_from_ms,
   ts_from_ms_opt
 };
-use crate::feed::parser::ParsedFeed;
+use crate::domain::realtime::ITEMS_CHANNEL;
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
 
 #[allow(clippy::too_many_arguments)]
 pub async fn insert_payload_with_items(
@@ -71,33 +80,14 @@ pub async fn insert_payload_with_items(
     .await
     .map_err(|e| format!("insert payload: {e}"))?;
 
-  for it in &parsed.items {
-    sqlx::query(
-            r#"
-        INSERT INTO feed_items(
-          payload_id, feed_id, title, link, guid,
-          published_at,
-          category, description, summary
-        ) VALUES (
-          $1, $2, $3, $4, $5,
-          $6,
-          $7, $8, $9
-        )
-        "#,
-        )
-        .bind(payload_id)
-        .bind(feed_id)
-        .bind(it.title.clone())
-        .bind(it.link.clone())
-        .bind(it.guid.clone())
-        .bind(ts_from_ms_opt(it.published_at_ms, zone))
-        .bind(it.category.clone())
-        .bind(it.description.clone())
-        .bind(it.summary.clone())
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("insert item: {e}"))?;
-  }
+  copy_items(
+    &mut tx,
+    payload_id,
+    feed_id,
+    &parsed.items,
+    zone
+  )
+  .await?;
 
   tx.commit().await.map_err(|e| {
     format!("tx commit: {e}")
@@ -111,3 +101,197 @@ pub async fn insert_payload_with_items(
 
   Ok(())
 }
+
+/// Appends reparsed items to an
+/// existing payload.
+pub async fn insert_items(
+  pool: &PgPool,
+  payload_id: i64,
+  feed_id: &str,
+  items: &[FeedItem],
+  zone: &Tz
+) -> Result<u64, String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  copy_items(
+    &mut tx, payload_id, feed_id,
+    items, zone
+  )
+  .await?;
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  Ok(items.len() as u64)
+}
+
+/// Streams items through `COPY ... FROM
+/// STDIN` in text format, one round
+/// trip for the whole payload. Items
+/// carry the feed's language, which
+/// picks their search configuration.
+/// Notifies `ITEMS_CHANNEL` with the
+/// feed id.
+async fn copy_items(
+  conn: &mut PgConnection,
+  payload_id: i64,
+  feed_id: &str,
+  items: &[FeedItem],
+  zone: &Tz
+) -> Result<(), String> {
+  if items.is_empty() {
+    return Ok(());
+  }
+
+  let language: Option<String> =
+    sqlx::query_scalar(
+      "SELECT language FROM feeds \
+       WHERE id = $1"
+    )
+    .bind(feed_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| {
+      format!("feed language: {e}")
+    })?
+    .flatten();
+
+  let payload_id =
+    payload_id.to_string();
+
+  let mut data = String::new();
+
+  for it in items {
+    let published_at =
+      it.published_at_ms.map(|ms| {
+        ts_from_ms(ms, zone)
+          .to_rfc3339()
+      });
+
+    let fields = [
+      Some(payload_id.as_str()),
+      Some(feed_id),
+      it.title.as_deref(),
+      it.link.as_deref(),
+      it.guid.as_deref(),
+      published_at.as_deref(),
+      it.category.as_deref(),
+      it.description.as_deref(),
+      it.summary.as_deref(),
+      it.diff.as_deref(),
+      language.as_deref(),
+      it.author.as_deref()
+    ];
+
+    for (i, field) in
+      fields.into_iter().enumerate()
+    {
+      if i > 0 {
+        data.push('\t');
+      }
+
+      push_copy_field(&mut data, field);
+    }
+
+    data.push('\n');
+  }
+
+  let mut copy = conn
+    .copy_in_raw(
+      "COPY feed_items(payload_id, \
+       feed_id, title, link, guid, \
+       published_at, category, \
+       description, summary, diff, \
+       language, author) FROM STDIN"
+    )
+    .await
+    .map_err(|e| {
+      format!("copy items: {e}")
+    })?;
+
+  if let Err(e) =
+    copy.send(data.into_bytes()).await
+  {
+    let _ =
+      copy.abort(e.to_string()).await;
+
+    return Err(format!(
+      "copy items: {e}"
+    ));
+  }
+
+  copy.finish().await.map_err(|e| {
+    format!("copy items: {e}")
+  })?;
+
+  // Delivered on commit; the server
+  // pushes the items to open streams.
+  sqlx::query(
+    "SELECT pg_notify($1, $2)"
+  )
+  .bind(ITEMS_CHANNEL)
+  .bind(feed_id)
+  .execute(&mut *conn)
+  .await
+  .map_err(|e| {
+    format!("notify items: {e}")
+  })?;
+
+  Ok(())
+}
+
+/// Appends one COPY text-format field:
+/// `\N` for NULL, with backslash and
+/// the row/column delimiters escaped.
+fn push_copy_field(
+  out: &mut String,
+  field: Option<&str>
+) {
+  let Some(value) = field else {
+    out.push_str("\\N");
+    return;
+  };
+
+  for c in value.chars() {
+    match c {
+      | '\\' => out.push_str("\\\\"),
+      | '\n' => out.push_str("\\n"),
+      | '\r' => out.push_str("\\r"),
+      | '\t' => out.push_str("\\t"),
+      | c => out.push(c)
+    }
+  }
+}
+
+pub async fn known_item_guids(
+  pool: &PgPool,
+  feed_id: &str,
+  guids: &[String]
+) -> Result<HashSet<String>, String> {
+  if guids.is_empty() {
+    return Ok(HashSet::new());
+  }
+
+  let rows: Vec<String> =
+    sqlx::query_scalar(
+      r#"
+      SELECT DISTINCT guid
+      FROM feed_items
+      WHERE feed_id = $1
+        AND guid = ANY($2)
+      "#
+    )
+    .bind(feed_id)
+    .bind(guids)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!("known item guids: {e}")
+    })?;
+
+  Ok(rows.into_iter().collect())
+}
@@ -0,0 +1,322 @@
+//! Retention pruning for history
+//! tables (Postgres). Read state and
+//! stars are looked up in the
+//! configured server schema.
+
+use sqlx::PgPool;
+
+use crate::domain::retention::{
+  RetentionLimit,
+  RetentionTable
+};
+
+pub async fn feed_categories(
+  pool: &PgPool
+) -> Result<Vec<(String, String)>, String>
+{
+  sqlx::query_as::<_, (String, String)>(
+    "SELECT id, category FROM feeds \
+     ORDER BY id"
+  )
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "feed_categories error: {e}"
+    )
+  })
+}
+
+pub async fn prune_batch(
+  pool: &PgPool,
+  table: RetentionTable,
+  feed_id: &str,
+  limit: RetentionLimit,
+  now_ms: i64,
+  batch_size: u64,
+  server_schema: &str
+) -> Result<u64, String> {
+  let guard =
+    item_guard(pool, server_schema)
+      .await?;
+
+  let Some((candidates, binds)) =
+    candidate_sql(
+      table, limit, now_ms, &guard
+    )
+  else {
+    return Ok(0);
+  };
+
+  let sql = format!(
+    "DELETE FROM {} WHERE id IN \
+     ({candidates} LIMIT ${})",
+    table.as_str(),
+    binds.len() + 2
+  );
+
+  let mut query =
+    sqlx::query(&sql).bind(feed_id);
+
+  for value in binds {
+    query = query.bind(value);
+  }
+
+  query
+    .bind(batch_size as i64)
+    .execute(pool)
+    .await
+    .map(|res| res.rows_affected())
+    .map_err(|e| {
+      format!(
+        "prune {} error: {e}",
+        table.as_str()
+      )
+    })
+}
+
+pub async fn count_prunable(
+  pool: &PgPool,
+  table: RetentionTable,
+  feed_id: &str,
+  limit: RetentionLimit,
+  now_ms: i64,
+  server_schema: &str
+) -> Result<u64, String> {
+  let guard =
+    item_guard(pool, server_schema)
+      .await?;
+
+  let Some((candidates, binds)) =
+    candidate_sql(
+      table, limit, now_ms, &guard
+    )
+  else {
+    return Ok(0);
+  };
+
+  let sql = format!(
+    "SELECT COUNT(*) FROM \
+     ({candidates}) c"
+  );
+
+  let mut query =
+    sqlx::query_scalar::<_, i64>(&sql)
+      .bind(feed_id);
+
+  for value in binds {
+    query = query.bind(value);
+  }
+
+  query
+    .fetch_one(pool)
+    .await
+    .map(|n| n.max(0) as u64)
+    .map_err(|e| {
+      format!(
+        "count prunable {} error: {e}",
+        table.as_str()
+      )
+    })
+}
+
+/// `SELECT t.id` of the rows outside
+/// `limit`, with `$1` bound to the feed
+/// id and the returned values bound
+/// from `$2`. Items also have to pass
+/// `guard`. `None` when the limit is
+/// unbounded.
+fn candidate_sql(
+  table: RetentionTable,
+  limit: RetentionLimit,
+  now_ms: i64,
+  guard: &str
+) -> Option<(String, Vec<i64>)> {
+  if limit.is_unbounded() {
+    return None;
+  }
+
+  let name = table.as_str();
+  let mut binds = Vec::new();
+  let mut outside = Vec::new();
+
+  if let Some(cutoff) =
+    limit.cutoff_ms(now_ms)
+  {
+    binds.push(cutoff);
+
+    let n = binds.len() + 1;
+
+    outside.push(match table {
+      | RetentionTable::Items => {
+        format!(
+          "t.payload_id IN (SELECT \
+           p.id FROM feed_payloads p \
+           WHERE p.feed_id = $1 AND \
+           p.fetched_at < \
+           to_timestamp(${n}::bigint \
+           / 1000.0))"
+        )
+      }
+      | _ => {
+        format!(
+          "t.{} < \
+           to_timestamp(${n}::bigint / \
+           1000.0)",
+          age_column(table)
+        )
+      }
+    });
+  }
+
+  if let Some(max_rows) = limit.max_rows
+  {
+    binds.push(max_rows as i64);
+
+    let n = binds.len() + 1;
+
+    outside.push(format!(
+      "t.id NOT IN (SELECT k.id FROM \
+       {name} k WHERE k.feed_id = $1 \
+       ORDER BY k.id DESC LIMIT ${n})"
+    ));
+  }
+
+  let mut sql = format!(
+    "SELECT t.id FROM {name} t WHERE \
+     t.feed_id = $1 AND ({})",
+    outside.join(" OR ")
+  );
+
+  match table {
+    | RetentionTable::Payloads => {
+      sql.push_str(
+        " AND NOT EXISTS (SELECT 1 \
+         FROM feed_items i WHERE \
+         i.payload_id = t.id)"
+      );
+    }
+    | RetentionTable::Items => {
+      sql.push_str(guard);
+    }
+    | _ => {}
+  }
+
+  Some((sql, binds))
+}
+
+fn age_column(
+  table: RetentionTable
+) -> &'static str {
+  match table {
+    | RetentionTable::FetchEvents => {
+      "event_time"
+    }
+    | RetentionTable::StateHistory => {
+      "recorded_at"
+    }
+    | RetentionTable::Items
+    | RetentionTable::Payloads
+    | RetentionTable::ArchiveIndex => {
+      "fetched_at"
+    }
+  }
+}
+
+/// Keeps items any subscriber (direct
+/// or through a folder) has not read.
+const UNREAD_GUARD: &str = r#"
+  AND NOT EXISTS (
+    SELECT 1 FROM {server}.subscriptions s
+    WHERE s.feed_id = t.feed_id
+      AND NOT EXISTS (
+        SELECT 1 FROM {server}.entry_states es
+        WHERE es.user_id = s.user_id
+          AND es.item_id = t.id
+          AND es.read_at IS NOT NULL
+      )
+  )
+  AND NOT EXISTS (
+    SELECT 1 FROM {server}.folder_feeds ff
+    JOIN {server}.folders f ON f.id = ff.folder_id
+    WHERE ff.feed_id = t.feed_id
+      AND NOT EXISTS (
+        SELECT 1 FROM {server}.entry_states es
+        WHERE es.user_id = f.user_id
+          AND es.item_id = t.id
+          AND es.read_at IS NOT NULL
+      )
+  )
+"#;
+
+/// Keeps items any user starred.
+const STAR_GUARD: &str = r#"
+  AND NOT EXISTS (
+    SELECT 1 FROM {server}.entry_stars st
+    WHERE st.item_id = t.id
+  )
+"#;
+
+/// Keeps items any user queued.
+const QUEUE_GUARD: &str = r#"
+  AND NOT EXISTS (
+    SELECT 1 FROM {server}.entry_queue eq
+    WHERE eq.item_id = t.id
+  )
+"#;
+
+/// Keeps items any user labeled.
+const LABEL_GUARD: &str = r#"
+  AND NOT EXISTS (
+    SELECT 1 FROM {server}.entry_labels el
+    WHERE el.item_id = t.id
+  )
+"#;
+
+/// Item guards for the server tables
+/// present in `server_schema`.
+async fn item_guard(
+  pool: &PgPool,
+  server_schema: &str
+) -> Result<String, String> {
+  let schema =
+    quote_ident(server_schema);
+
+  let mut guard = String::new();
+
+  for (table, clause) in [
+    ("entry_states", UNREAD_GUARD),
+    ("entry_stars", STAR_GUARD),
+    ("entry_queue", QUEUE_GUARD),
+    ("entry_labels", LABEL_GUARD)
+  ] {
+    let found: Option<String> =
+      sqlx::query_scalar(
+        "SELECT to_regclass($1)::text"
+      )
+      .bind(format!("{schema}.{table}"))
+      .fetch_one(pool)
+      .await
+      .map_err(|e| {
+        format!(
+          "introspect {table}: {e}"
+        )
+      })?;
+
+    if found.is_some() {
+      guard
+        .push_str(&clause.replace(
+          "{server}", &schema
+        ));
+    }
+  }
+
+  Ok(guard)
+}
+
+fn quote_ident(name: &str) -> String {
+  format!(
+    "\"{}\"",
+    name.replace('"', "\"\"")
+  )
+}
@@ -0,0 +1,68 @@
+//! Last normalized text snapshot per
+//! watch, used to diff `any_change`
+//! watches (Postgres).
+
+use chrono_tz::Tz;
+use sqlx::PgPool;
+
+use super::util::ts_from_ms;
+
+pub async fn latest_watch_snapshot(
+  pool: &PgPool,
+  feed_id: &str
+) -> Result<Option<String>, String> {
+  sqlx::query_scalar::<_, String>(
+    r#"
+      SELECT content_text
+      FROM watch_snapshots
+      WHERE feed_id = $1
+      LIMIT 1
+      "#
+  )
+  .bind(feed_id)
+  .fetch_optional(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "latest_watch_snapshot error: \
+       {e}"
+    )
+  })
+}
+
+pub async fn upsert_watch_snapshot(
+  pool: &PgPool,
+  feed_id: &str,
+  content_text: &str,
+  observed_at_ms: i64,
+  zone: &Tz
+) -> Result<(), String> {
+  let updated_at =
+    ts_from_ms(observed_at_ms, zone);
+
+  sqlx::query(
+    r#"
+      INSERT INTO watch_snapshots(
+        feed_id,
+        content_text,
+        updated_at
+      ) VALUES ($1, $2, $3)
+      ON CONFLICT(feed_id)
+      DO UPDATE SET
+        content_text = excluded.content_text,
+        updated_at = excluded.updated_at
+      "#,
+  )
+  .bind(feed_id)
+  .bind(content_text)
+  .bind(updated_at)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "upsert_watch_snapshot error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
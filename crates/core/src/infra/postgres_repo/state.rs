@@ -2,11 +2,28 @@
 //! link state snapshots for Postgres.
 
 use chrono_tz::Tz;
-use sqlx::PgPool;
+use sqlx::query_builder::Separated;
+use sqlx::{
+  PgPool,
+  Postgres,
+  QueryBuilder
+};
 
 use super::models::StateRowRecord;
+use super::util::{
+  ts_from_ms,
+  ts_from_ms_opt
+};
 use crate::domain::link_state::LinkState;
-use crate::ports::repo::StateRow;
+use crate::ports::repo::{
+  NewState,
+  StateRow
+};
+
+/// Rows per multi-row statement; 17
+/// binds a row stays below the 65535
+/// parameter limit.
+const CHUNK_ROWS: usize = 1_000;
 
 pub async fn latest_state(
   pool: &PgPool,
@@ -55,95 +72,128 @@ pub async fn insert_state(
   zone: &Tz,
   record_history: bool
 ) -> Result<(), String> {
-  let recorded_at =
-    super::util::ts_from_ms(
+  insert_states(
+    pool,
+    &[NewState {
+      state: state.clone(),
       recorded_at_ms,
-      zone
-    );
+      record_history
+    }],
+    zone
+  )
+  .await
+}
 
-  let last_head_at =
-    super::util::ts_from_ms_opt(
-      state.last_head_at_ms,
-      zone
-    );
+/// Postgres refuses to upsert one row
+/// twice in a statement, so current
+/// rows are first reduced to the last
+/// snapshot per feed.
+pub async fn insert_states(
+  pool: &PgPool,
+  states: &[NewState],
+  zone: &Tz
+) -> Result<(), String> {
+  if states.is_empty() {
+    return Ok(());
+  }
 
-  let last_get_at =
-    super::util::ts_from_ms_opt(
-      state.last_get_at_ms,
-      zone
-    );
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
 
-  let last_modified_at =
-    super::util::ts_from_ms_opt(
-      state.last_modified_ms,
-      zone
+  let history: Vec<&NewState> = states
+    .iter()
+    .filter(|s| s.record_history)
+    .collect();
+
+  for chunk in
+    history.chunks(CHUNK_ROWS)
+  {
+    let mut builder = QueryBuilder::<
+      Postgres
+    >::new(
+      "INSERT INTO \
+       feed_state_history(feed_id, \
+       recorded_at, phase, \
+       last_head_at, \
+       last_head_status, \
+       last_head_error, last_get_at, \
+       last_get_status, \
+       last_get_error, etag, \
+       last_modified_at, \
+       backoff_index, \
+       base_poll_seconds, \
+       next_action_at, \
+       jitter_seconds, note, \
+       consecutive_error_count) "
     );
 
-  let next_action_at =
-    super::util::ts_from_ms(
-      state.next_action_at_ms,
-      zone
+    builder.push_values(
+      chunk,
+      |mut row, s| {
+        row
+          .push_bind(&s.state.feed_id)
+          .push_bind(ts_from_ms(
+            s.recorded_at_ms,
+            zone
+          ));
+
+        push_state(
+          &mut row, &s.state, zone
+        );
+      }
     );
 
-  if record_history {
-    sqlx::query(
-            r#"
-        INSERT INTO feed_state_history(
-          feed_id, recorded_at, phase,
-          last_head_at, last_head_status, last_head_error,
-          last_get_at, last_get_status, last_get_error,
-          etag, last_modified_at,
-          backoff_index, base_poll_seconds, next_action_at,
-          jitter_seconds, note, consecutive_error_count
-        ) VALUES (
-          $1, $2, $3,
-          $4, $5, $6,
-          $7, $8, $9,
-          $10, $11,
-          $12, $13, $14,
-          $15, $16, $17
-        )
-        "#,
+    builder
+      .build()
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "insert_state history \
+           error: {e}"
         )
-        .bind(&state.feed_id)
-        .bind(recorded_at)
-        .bind(format!("{:?}", state.phase))
-        .bind(last_head_at)
-        .bind(state.last_head_status.map(|x| x as i64))
-        .bind(state.last_head_error.map(|e| format!("{:?}", e)))
-        .bind(last_get_at)
-        .bind(state.last_get_status.map(|x| x as i64))
-        .bind(state.last_get_error.map(|e| format!("{:?}", e)))
-        .bind(&state.etag)
-        .bind(last_modified_at)
-        .bind(state.backoff_index as i64)
-        .bind(state.base_poll_seconds as i64)
-        .bind(next_action_at)
-        .bind(state.jitter_seconds)
-        .bind(&state.note)
-        .bind(state.consecutive_error_count as i64)
-        .execute(pool)
-        .await
-        .map_err(|e| format!("insert_state history error: {e}"))?;
+      })?;
   }
 
-  sqlx::query(
-        r#"
-      INSERT INTO feed_state_current(
-        feed_id, phase,
-        last_head_at, last_head_status, last_head_error,
-        last_get_at, last_get_status, last_get_error,
-        etag, last_modified_at,
-        backoff_index, base_poll_seconds, next_action_at,
-        jitter_seconds, note, consecutive_error_count
-      ) VALUES (
-        $1, $2,
-        $3, $4, $5,
-        $6, $7, $8,
-        $9, $10,
-        $11, $12, $13,
-        $14, $15, $16
-      )
+  let current =
+    NewState::latest_per_feed(states);
+
+  for chunk in
+    current.chunks(CHUNK_ROWS)
+  {
+    let mut builder = QueryBuilder::<
+      Postgres
+    >::new(
+      "INSERT INTO \
+       feed_state_current(feed_id, \
+       phase, last_head_at, \
+       last_head_status, \
+       last_head_error, last_get_at, \
+       last_get_status, \
+       last_get_error, etag, \
+       last_modified_at, \
+       backoff_index, \
+       base_poll_seconds, \
+       next_action_at, \
+       jitter_seconds, note, \
+       consecutive_error_count) "
+    );
+
+    builder.push_values(
+      chunk,
+      |mut row, s| {
+        row.push_bind(&s.state.feed_id);
+
+        push_state(
+          &mut row, &s.state, zone
+        );
+      }
+    );
+
+    builder.push(
+      r#"
       ON CONFLICT(feed_id) DO UPDATE SET
         phase = excluded.phase,
         last_head_at = excluded.last_head_at,
@@ -160,27 +210,90 @@ pub async fn insert_state(
         jitter_seconds = excluded.jitter_seconds,
         note = excluded.note,
         consecutive_error_count = excluded.consecutive_error_count
-      "#,
+      "#
+    );
+
+    builder
+      .build()
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "insert_state current \
+           error: {e}"
+        )
+      })?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })
+}
+
+/// Binds the columns from `phase` on,
+/// shared by history and current rows.
+fn push_state<'q>(
+  row: &mut Separated<
+    '_,
+    'q,
+    Postgres,
+    &'static str
+  >,
+  state: &'q LinkState,
+  zone: &Tz
+) {
+  row
+    .push_bind(format!(
+      "{:?}",
+      state.phase
+    ))
+    .push_bind(ts_from_ms_opt(
+      state.last_head_at_ms,
+      zone
+    ))
+    .push_bind(
+      state
+        .last_head_status
+        .map(|x| x as i64)
     )
-    .bind(&state.feed_id)
-    .bind(format!("{:?}", state.phase))
-    .bind(last_head_at)
-    .bind(state.last_head_status.map(|x| x as i64))
-    .bind(state.last_head_error.map(|e| format!("{:?}", e)))
-    .bind(last_get_at)
-    .bind(state.last_get_status.map(|x| x as i64))
-    .bind(state.last_get_error.map(|e| format!("{:?}", e)))
-    .bind(&state.etag)
-    .bind(last_modified_at)
-    .bind(state.backoff_index as i64)
-    .bind(state.base_poll_seconds as i64)
-    .bind(next_action_at)
-    .bind(state.jitter_seconds)
-    .bind(&state.note)
-    .bind(state.consecutive_error_count as i64)
-    .execute(pool)
-    .await
-    .map_err(|e| format!("insert_state current error: {e}"))?;
-
-  Ok(())
+    .push_bind(
+      state
+        .last_head_error
+        .map(|e| format!("{e:?}"))
+    )
+    .push_bind(ts_from_ms_opt(
+      state.last_get_at_ms,
+      zone
+    ))
+    .push_bind(
+      state
+        .last_get_status
+        .map(|x| x as i64)
+    )
+    .push_bind(
+      state
+        .last_get_error
+        .map(|e| format!("{e:?}"))
+    )
+    .push_bind(&state.etag)
+    .push_bind(ts_from_ms_opt(
+      state.last_modified_ms,
+      zone
+    ))
+    .push_bind(
+      state.backoff_index as i64
+    )
+    .push_bind(
+      state.base_poll_seconds as i64
+    )
+    .push_bind(ts_from_ms(
+      state.next_action_at_ms,
+      zone
+    ))
+    .push_bind(state.jitter_seconds)
+    .push_bind(&state.note)
+    .push_bind(
+      state.consecutive_error_count
+        as i64
+    );
 }
@@ -54,12 +54,3 @@ pub fn ms_from_ts(
 ) -> Option<i64> {
   ts.map(|dt| dt.timestamp_millis())
 }
-
-pub fn chunk_statements(
-  schema: &str
-) -> impl Iterator<Item = &str> {
-  schema
-    .split(';')
-    .map(str::trim)
-    .filter(|s| !s.is_empty())
-}
@@ -2,55 +2,377 @@
 //! implementing the `Http` port; maps
 //! reqwest errors/statuses into domain
 //! `HeadResult`/`GetResult` with coarse
-//! error kinds.
+//! error kinds. Requests use the
+//! client built for the feed's domain
+//! profile, if any. Public-only
+//! clients, for URLs users give the
+//! server, refuse internal addresses
+//! on every hop and cap body sizes.
 
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::{
   DateTime,
   Utc
 };
+use reqwest::redirect::Policy;
 use reqwest::{
+  Certificate,
+  Method,
   StatusCode,
   header
 };
 use tracing::{
   debug,
+  info,
   warn
 };
 
+use crate::domain::destination::{
+  is_public_ip,
+  literal_ip
+};
 use crate::domain::model::{
+  DomainConfig,
   ErrorKind,
   GetResult,
-  HeadResult
+  HeadResult,
+  HttpProfile,
+  HttpVersion,
+  RedirectHop
 };
 use crate::ports::http::Http;
 
+/// Redirect hops followed before the
+/// last 3xx response is returned as is.
+const MAX_REDIRECTS: usize = 10;
+
+/// Total request timeout for clients
+/// without a `read_timeout_seconds`
+/// override.
+const DEFAULT_TIMEOUT_SECONDS: u64 = 30;
+
+/// Largest body a public-only client
+/// reads.
+const PUBLIC_BODY_LIMIT: usize =
+  5 * 1024 * 1024;
+
 pub struct ReqwestHttp {
-  client:      reqwest::Client,
-  _user_agent: String
+  default_clients: Clients,
+  clients: HashMap<String, Clients>,
+  /// Refuse non-public destinations.
+  public_only:     bool,
+  body_limit:      Option<usize>
+}
+
+/// One profile's clients. Webhook POSTs
+/// go out without a cookie jar, so a
+/// receiver's cookies never reach
+/// another user's webhook.
+#[derive(Clone)]
+struct Clients {
+  fetch: reqwest::Client,
+  post:  reqwest::Client
+}
+
+impl Clients {
+  fn build(
+    user_agent: &str,
+    profile: &HttpProfile,
+    public_only: bool
+  ) -> Result<Self, String> {
+    Ok(Self {
+      // A public-only client serves
+      // every user, so one user's
+      // cookies must not reach
+      // another's requests.
+      fetch: build_client(
+        user_agent,
+        profile,
+        public_only,
+        !public_only
+      )?,
+      post:  build_client(
+        user_agent,
+        profile,
+        public_only,
+        false
+      )?
+    })
+  }
+}
+
+/// Why a request got no response.
+enum SendError {
+  /// The URL points at an address
+  /// this client may not reach.
+  Blocked,
+  Request(reqwest::Error)
+}
+
+impl std::fmt::Display for SendError {
+  fn fmt(
+    &self,
+    f: &mut std::fmt::Formatter<'_>
+  ) -> std::fmt::Result {
+    match self {
+      | Self::Blocked => {
+        f.write_str(
+          "destination is not public"
+        )
+      }
+      | Self::Request(e) => e.fmt(f)
+    }
+  }
 }
 
 impl ReqwestHttp {
+  /// Builds the default client plus one
+  /// client per distinct domain
+  /// profile.
   pub fn new(
-    user_agent: String
-  ) -> Result<Self, reqwest::Error> {
-    let client = reqwest::Client::builder()
-            .user_agent(user_agent.clone())
-            .pool_idle_timeout(std::time::Duration::from_secs(120))
-            .timeout(std::time::Duration::from_secs(30))
-            .cookie_store(true)
-            .build()?;
+    user_agent: String,
+    domains: &HashMap<
+      String,
+      DomainConfig
+    >
+  ) -> Result<Self, String> {
+    let default_clients =
+      Clients::build(
+        &user_agent,
+        &HttpProfile::default(),
+        false
+      )?;
+
+    let mut by_profile: HashMap<
+      &HttpProfile,
+      Clients
+    > = HashMap::new();
+
+    let mut clients = HashMap::new();
+
+    for (domain, cfg) in domains {
+      let Some(profile) =
+        cfg.http.as_ref()
+      else {
+        continue;
+      };
+
+      let client = match by_profile
+        .get(profile)
+      {
+        | Some(client) => {
+          client.clone()
+        }
+        | None => {
+          let client = Clients::build(
+            &user_agent,
+            profile,
+            false
+          )
+          .map_err(|e| {
+            format!(
+              "domain '{domain}' http \
+               client: {e}"
+            )
+          })?;
+
+          by_profile.insert(
+            profile,
+            client.clone()
+          );
 
+          client
+        }
+      };
+
+      clients
+        .insert(domain.clone(), client);
+    }
+
+    info!(
+      profiles = by_profile.len(),
+      domains = clients.len(),
+      "HTTP client profiles ready"
+    );
+
+    Ok(Self {
+      default_clients,
+      clients,
+      public_only: false,
+      body_limit: None
+    })
+  }
+
+  /// A client for URLs users give the
+  /// server: names must resolve only
+  /// to public addresses, IP literals
+  /// must be public, each redirect hop
+  /// is checked again, bodies are
+  /// capped and no cookies are kept.
+  pub fn public_only(
+    user_agent: String
+  ) -> Result<Self, String> {
     Ok(Self {
-      client,
-      _user_agent: user_agent
+      default_clients: Clients::build(
+        &user_agent,
+        &HttpProfile::default(),
+        true
+      )?,
+      clients:         HashMap::new(),
+      public_only:     true,
+      body_limit:      Some(
+        PUBLIC_BODY_LIMIT
+      )
     })
   }
 
+  /// Whether this client must refuse
+  /// `url`. Names are checked when
+  /// they resolve; IP literals never
+  /// reach the resolver so are checked
+  /// here.
+  fn blocked(
+    &self,
+    url: &str
+  ) -> bool {
+    self.public_only
+      && reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| literal_ip(&u))
+        .is_some_and(|ip| {
+          !is_public_ip(ip)
+        })
+  }
+
+  fn clients_for(
+    &self,
+    domain: &str
+  ) -> &Clients {
+    self
+      .clients
+      .get(domain)
+      .unwrap_or(&self.default_clients)
+  }
+
+  /// Sends the request and follows up
+  /// to [`MAX_REDIRECTS`] redirects by
+  /// hand so each hop can be recorded.
+  /// Cookie and extra headers are only
+  /// sent to the first URL's origin.
+  async fn send_following(
+    &self,
+    method: Method,
+    domain: &str,
+    url: &str,
+    cookie_header: Option<&str>,
+    extra_headers: Option<
+      &HashMap<String, String>
+    >
+  ) -> Result<
+    (
+      reqwest::Response,
+      Vec<RedirectHop>
+    ),
+    SendError
+  > {
+    let client =
+      &self.clients_for(domain).fetch;
+
+    let origin =
+      reqwest::Url::parse(url)
+        .ok()
+        .map(|u| u.origin());
+
+    let mut target = url.to_string();
+    let mut hops = Vec::new();
+
+    loop {
+      if self.blocked(&target) {
+        return Err(SendError::Blocked);
+      }
+
+      let mut req = client.request(
+        method.clone(),
+        &target
+      );
+
+      // Scheme, host and port must all
+      // match, so a downgrade to http
+      // or a move to another port
+      // drops the credentials.
+      let same_origin = origin
+        .as_ref()
+        .is_some_and(|origin| {
+          reqwest::Url::parse(&target)
+            .is_ok_and(|u| {
+              u.origin() == *origin
+            })
+        });
+
+      if same_origin {
+        if let Some(cookie) =
+          cookie_header
+          && !cookie.trim().is_empty()
+        {
+          req = req.header(
+            header::COOKIE,
+            cookie
+          );
+        }
+
+        req = Self::apply_extra_headers(
+          req,
+          extra_headers
+        );
+      }
+
+      let resp = req
+        .send()
+        .await
+        .map_err(SendError::Request)?;
+      let status = resp.status();
+
+      if !status.is_redirection()
+        || hops.len() >= MAX_REDIRECTS
+      {
+        return Ok((resp, hops));
+      }
+
+      let Some(next) = resp
+        .headers()
+        .get(header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|location| {
+          resp.url().join(location).ok()
+        })
+      else {
+        return Ok((resp, hops));
+      };
+
+      debug!(from = %resp.url(), to = %next, status = status.as_u16(), "HTTP redirect");
+
+      hops.push(RedirectHop {
+        status: status.as_u16(),
+        from:   resp.url().to_string(),
+        to:     next.to_string()
+      });
+
+      target = next.to_string();
+    }
+  }
+
   fn classify_error(
-    e: &reqwest::Error
+    e: &SendError
   ) -> ErrorKind {
+    let SendError::Request(e) = e
+    else {
+      return ErrorKind::ConnectionFailure;
+    };
+
     if e.is_timeout() {
       ErrorKind::Timeout
     } else if e.is_connect() {
@@ -110,6 +432,23 @@ impl ReqwestHttp {
       .collect()
   }
 
+  fn collect_headers(
+    headers: &header::HeaderMap
+  ) -> Vec<(String, String)> {
+    headers
+      .iter()
+      .map(|(name, value)| {
+        (
+          name.as_str().to_string(),
+          String::from_utf8_lossy(
+            value.as_bytes()
+          )
+          .into_owned()
+        )
+      })
+      .collect()
+  }
+
   fn apply_extra_headers(
     mut req: reqwest::RequestBuilder,
     extra_headers: Option<
@@ -144,12 +483,152 @@ impl ReqwestHttp {
 
     req
   }
+
+  /// Reads a body of at most `limit`
+  /// bytes.
+  async fn read_body(
+    mut resp: reqwest::Response,
+    limit: Option<usize>
+  ) -> Result<Vec<u8>, String> {
+    let over = |len: usize| {
+      limit.is_some_and(|l| len > l)
+    };
+
+    if resp
+      .content_length()
+      .is_some_and(|n| {
+        over(
+          n.try_into()
+            .unwrap_or(usize::MAX)
+        )
+      })
+    {
+      return Err(
+        "body too large".to_string()
+      );
+    }
+
+    let mut body = Vec::new();
+
+    while let Some(chunk) = resp
+      .chunk()
+      .await
+      .map_err(|e| e.to_string())?
+    {
+      body.extend_from_slice(&chunk);
+
+      if over(body.len()) {
+        return Err(
+          "body too large".to_string()
+        );
+      }
+    }
+
+    Ok(body)
+  }
+
+  /// Reads a response and its body
+  /// into a `GetResult`.
+  async fn read_response(
+    &self,
+    url: &str,
+    resp: reqwest::Response,
+    redirects: Vec<RedirectHop>,
+    start: tokio::time::Instant
+  ) -> GetResult {
+    let final_url =
+      Some(resp.url().to_string());
+
+    let status =
+      Some(resp.status().as_u16());
+
+    let etag =
+      Self::parse_etag(resp.headers());
+
+    let last_modified =
+      Self::parse_last_modified(
+        resp.headers()
+      );
+
+    let set_cookie_headers =
+      Self::parse_set_cookie_headers(
+        resp.headers()
+      );
+
+    let headers = Self::collect_headers(
+      resp.headers()
+    );
+
+    let body = match Self::read_body(
+      resp,
+      self.body_limit
+    )
+    .await
+    {
+      | Ok(b) => Some(b),
+      | Err(e) => {
+        warn!(url, error = %e, "Failed reading body");
+
+        None
+      }
+    };
+
+    let latency_ms =
+      start.elapsed().as_millis()
+        as u64;
+
+    let error = status.and_then(|s| {
+      Self::status_error_kind(
+        StatusCode::from_u16(s).unwrap_or(
+          StatusCode::INTERNAL_SERVER_ERROR
+        )
+      )
+    });
+
+    GetResult {
+      status,
+      body,
+      etag,
+      last_modified,
+      error,
+      latency_ms,
+      set_cookie_headers,
+      headers,
+      final_url,
+      redirects
+    }
+  }
+
+  /// A request that got no response.
+  fn failed_response(
+    e: &SendError,
+    start: tokio::time::Instant
+  ) -> GetResult {
+    GetResult {
+      status:             None,
+      body:               None,
+      etag:               None,
+      last_modified:      None,
+      error:              Some(
+        Self::classify_error(e)
+      ),
+      latency_ms:         start
+        .elapsed()
+        .as_millis()
+        as u64,
+      set_cookie_headers: Vec::new(),
+      headers:            Vec::new(),
+      final_url:          None,
+      redirects:          Vec::new()
+    }
+  }
 }
 
 #[async_trait::async_trait]
 impl Http for ReqwestHttp {
   async fn head(
     &self,
+    domain: &str,
     url: &str,
     cookie_header: Option<&str>,
     extra_headers: Option<
@@ -161,22 +640,17 @@ impl Http for ReqwestHttp {
 
     debug!(url, "HTTP HEAD start");
 
-    let mut req = self.client.head(url);
-
-    if let Some(cookie) = cookie_header
-      && !cookie.trim().is_empty()
+    match self
+      .send_following(
+        Method::HEAD,
+        domain,
+        url,
+        cookie_header,
+        extra_headers
+      )
+      .await
     {
-      req = req
-        .header(header::COOKIE, cookie);
-    }
-
-    req = Self::apply_extra_headers(
-      req,
-      extra_headers
-    );
-
-    match req.send().await {
-      | Ok(resp) => {
+      | Ok((resp, _)) => {
         let latency_ms =
           start.elapsed().as_millis()
             as u64;
@@ -237,6 +711,7 @@ impl Http for ReqwestHttp {
 
   async fn get(
     &self,
+    domain: &str,
     url: &str,
     cookie_header: Option<&str>,
     extra_headers: Option<
@@ -248,91 +723,305 @@ impl Http for ReqwestHttp {
 
     debug!(url, "HTTP GET start");
 
-    let mut req = self.client.get(url);
-
-    if let Some(cookie) = cookie_header
-      && !cookie.trim().is_empty()
+    match self
+      .send_following(
+        Method::GET,
+        domain,
+        url,
+        cookie_header,
+        extra_headers
+      )
+      .await
     {
-      req = req
-        .header(header::COOKIE, cookie);
+      | Ok((resp, redirects)) => {
+        self
+          .read_response(
+            url, resp, redirects, start
+          )
+          .await
+      }
+      | Err(e) => {
+        warn!(url, error = %e, "HTTP GET failed");
+
+        Self::failed_response(&e, start)
+      }
     }
+  }
 
-    req = Self::apply_extra_headers(
-      req,
-      extra_headers
-    );
+  async fn post(
+    &self,
+    domain: &str,
+    url: &str,
+    headers: &HashMap<String, String>,
+    body: Vec<u8>
+  ) -> GetResult {
+    let start =
+      tokio::time::Instant::now();
+
+    debug!(url, "HTTP POST start");
+
+    if self.blocked(url) {
+      warn!(
+        url,
+        "HTTP POST refused: \
+         destination is not public"
+      );
+
+      return Self::failed_response(
+        &SendError::Blocked,
+        start
+      );
+    }
+
+    let mut req = self
+      .clients_for(domain)
+      .post
+      .post(url)
+      .body(body);
+
+    for (name, value) in headers {
+      req = req.header(name, value);
+    }
 
     match req.send().await {
       | Ok(resp) => {
-        let status =
-          Some(resp.status().as_u16());
+        self
+          .read_response(
+            url,
+            resp,
+            Vec::new(),
+            start
+          )
+          .await
+      }
+      | Err(e) => {
+        warn!(url, error = %e, "HTTP POST failed");
 
-        let etag = Self::parse_etag(
-          resp.headers()
-        );
+        Self::failed_response(
+          &SendError::Request(e),
+          start
+        )
+      }
+    }
+  }
+}
 
-        let last_modified =
-          Self::parse_last_modified(
-            resp.headers()
-          );
+/// Resolves names to their addresses,
+/// failing unless all are public.
+struct PublicResolver;
 
-        let set_cookie_headers =
-          Self::parse_set_cookie_headers(
-            resp.headers()
-          );
+impl reqwest::dns::Resolve
+  for PublicResolver
+{
+  fn resolve(
+    &self,
+    name: reqwest::dns::Name
+  ) -> reqwest::dns::Resolving {
+    Box::pin(async move {
+      let addrs =
+        public_addrs(name.as_str(), 0)
+          .await?;
 
-        let body = match resp
-          .bytes()
-          .await
-        {
-          | Ok(b) => Some(b.to_vec()),
-          | Err(e) => {
-            warn!(url, error = %e, "Failed reading body");
+      let addrs: reqwest::dns::Addrs =
+        Box::new(addrs.into_iter());
 
-            None
-          }
-        };
+      Ok(addrs)
+    })
+  }
+}
 
-        let latency_ms =
-          start.elapsed().as_millis()
-            as u64;
+/// The addresses `host` resolves to,
+/// or an error if any of them is not
+/// public.
+async fn public_addrs(
+  host: &str,
+  port: u16
+) -> Result<Vec<SocketAddr>, String> {
+  let addrs: Vec<SocketAddr> =
+    tokio::net::lookup_host((
+      host, port
+    ))
+    .await
+    .map_err(|e| {
+      format!("resolve '{host}': {e}")
+    })?
+    .collect();
 
-        let error = status.and_then(|s| {
-                    Self::status_error_kind(
-                        StatusCode::from_u16(s).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    )
-                });
+  if addrs.is_empty()
+    || addrs
+      .iter()
+      .any(|a| !is_public_ip(a.ip()))
+  {
+    return Err(format!(
+      "'{host}' is not a public \
+       address"
+    ));
+  }
 
-        GetResult {
-          status,
-          body,
-          etag,
-          last_modified,
-          error,
-          latency_ms,
-          set_cookie_headers
-        }
-      }
-      | Err(e) => {
-        warn!(url, error = %e, "HTTP GET failed");
+  Ok(addrs)
+}
 
-        let latency_ms =
-          start.elapsed().as_millis()
-            as u64;
+/// Checks that `url` is an http(s)
+/// URL whose host is, or resolves
+/// only to, public addresses.
+pub async fn check_public_url(
+  url: &str
+) -> Result<(), String> {
+  let parsed = reqwest::Url::parse(url)
+    .map_err(|_| {
+      format!("invalid url '{url}'")
+    })?;
 
-        GetResult {
-          status: None,
-          body: None,
-          etag: None,
-          last_modified: None,
-          error: Some(
-            Self::classify_error(&e)
-          ),
-          latency_ms,
-          set_cookie_headers: Vec::new(
+  if !matches!(
+    parsed.scheme(),
+    "http" | "https"
+  ) {
+    return Err(format!(
+      "'{url}' is not an http(s) url"
+    ));
+  }
+
+  if let Some(ip) = literal_ip(&parsed)
+  {
+    return if is_public_ip(ip) {
+      Ok(())
+    } else {
+      Err(format!(
+        "'{ip}' is not a public \
+         address"
+      ))
+    };
+  }
+
+  let host =
+    parsed.host_str().ok_or_else(
+      || format!("'{url}' has no host")
+    )?;
+
+  public_addrs(
+    host,
+    parsed
+      .port_or_known_default()
+      .unwrap_or(80)
+  )
+  .await
+  .map(drop)
+}
+
+fn build_client(
+  user_agent: &str,
+  profile: &HttpProfile,
+  public_only: bool,
+  cookies: bool
+) -> Result<reqwest::Client, String> {
+  let mut builder =
+    reqwest::Client::builder()
+      .user_agent(
+        profile
+          .user_agent
+          .as_deref()
+          .unwrap_or(user_agent)
+      )
+      .pool_idle_timeout(
+        Duration::from_secs(120)
+      )
+      .cookie_store(cookies)
+      .redirect(Policy::none());
+
+  if public_only {
+    // A proxy would resolve names
+    // itself, past the resolver.
+    builder =
+      builder.no_proxy().dns_resolver(
+        Arc::new(PublicResolver)
+      );
+  }
+
+  // The total timeout bounds a
+  // response that trickles in under
+  // the read timeout; it never cuts
+  // a longer read timeout short.
+  let read_timeout =
+    profile.read_timeout_seconds;
+
+  builder = builder.timeout(
+    Duration::from_secs(
+      read_timeout.map_or(
+        DEFAULT_TIMEOUT_SECONDS,
+        |secs| {
+          secs.max(
+            DEFAULT_TIMEOUT_SECONDS
           )
         }
-      }
-    }
+      )
+    )
+  );
+
+  if let Some(secs) = read_timeout {
+    builder = builder.read_timeout(
+      Duration::from_secs(secs)
+    );
   }
+
+  if let Some(secs) =
+    profile.connect_timeout_seconds
+  {
+    builder = builder.connect_timeout(
+      Duration::from_secs(secs)
+    );
+  }
+
+  if let Some(proxy) = &profile.proxy {
+    let proxy = reqwest::Proxy::all(
+      proxy.as_str()
+    )
+    .map_err(|e| {
+      format!("proxy '{proxy}': {e}")
+    })?;
+
+    builder = builder.proxy(proxy);
+  }
+
+  if let Some(path) = &profile.ca_bundle
+  {
+    let pem = std::fs::read(path)
+      .map_err(|e| {
+        format!(
+          "read ca_bundle '{}': {e}",
+          path.display()
+        )
+      })?;
+
+    let certs =
+      Certificate::from_pem_bundle(
+        &pem
+      )
+      .map_err(|e| {
+        format!(
+          "parse ca_bundle '{}': {e}",
+          path.display()
+        )
+      })?;
+
+    builder =
+      builder.tls_certs_merge(certs);
+  }
+
+  builder = match profile.http_version {
+    | Some(HttpVersion::Http1) => {
+      builder.http1_only()
+    }
+    // ALPN already offers h2 on TLS;
+    // prior knowledge would break
+    // origins that do not pick it.
+    | Some(HttpVersion::Http2)
+    | None => builder,
+    | Some(HttpVersion::H2c) => {
+      builder.http2_prior_knowledge()
+    }
+  };
+
+  builder.build().map_err(|e| {
+    format!("build http client: {e}")
+  })
 }
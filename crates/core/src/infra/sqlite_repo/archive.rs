@@ -0,0 +1,289 @@
+//! Payload archive blobs and the
+//! archive index used for reparsing
+//! (SQLite).
+
+use std::collections::HashSet;
+
+use sqlx::{
+  QueryBuilder,
+  Sqlite,
+  SqlitePool
+};
+
+use crate::ports::repo::ArchiveRefRow;
+
+pub async fn insert_archive_ref(
+  pool: &SqlitePool,
+  feed_id: &str,
+  fetched_at_ms: i64,
+  content_hash: &str
+) -> Result<(), String> {
+  sqlx::query(
+    "INSERT INTO \
+     payload_archive_index(feed_id, \
+     fetched_at_ms, content_hash) \
+     VALUES (?1, ?2, ?3)"
+  )
+  .bind(feed_id)
+  .bind(fetched_at_ms)
+  .bind(content_hash)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "insert_archive_ref error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
+
+/// Index rows of a feed with the
+/// payload stored for the same fetch
+/// and body, if any.
+pub async fn archive_refs_since(
+  pool: &SqlitePool,
+  feed_id: &str,
+  since_ms: i64
+) -> Result<Vec<ArchiveRefRow>, String>
+{
+  let rows: Vec<(
+    i64,
+    String,
+    Option<i64>
+  )> = sqlx::query_as(
+    "SELECT a.fetched_at_ms, \
+     a.content_hash, (SELECT \
+     MIN(p.id) FROM feed_payloads p \
+     WHERE p.feed_id = a.feed_id AND \
+     p.fetched_at_ms = \
+     a.fetched_at_ms AND \
+     p.content_hash = a.content_hash) \
+     FROM payload_archive_index a \
+     WHERE a.feed_id = ?1 AND \
+     a.fetched_at_ms >= ?2 ORDER BY \
+     a.fetched_at_ms, a.id"
+  )
+  .bind(feed_id)
+  .bind(since_ms)
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "archive_refs_since error: {e}"
+    )
+  })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(
+        |(
+          fetched_at_ms,
+          content_hash,
+          payload_id
+        )| {
+          ArchiveRefRow {
+            fetched_at_ms,
+            content_hash,
+            payload_id
+          }
+        }
+      )
+      .collect()
+  )
+}
+
+pub async fn archived_blob_exists(
+  pool: &SqlitePool,
+  content_hash: &str
+) -> Result<bool, String> {
+  let found: Option<i64> =
+    sqlx::query_scalar(
+      "SELECT 1 FROM payload_archive \
+       WHERE content_hash = ?1"
+    )
+    .bind(content_hash)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "archived_blob_exists error: \
+         {e}"
+      )
+    })?;
+
+  Ok(found.is_some())
+}
+
+pub async fn insert_archived_blob(
+  pool: &SqlitePool,
+  content_hash: &str,
+  stored_at_ms: i64,
+  data: &[u8]
+) -> Result<(), String> {
+  sqlx::query(
+    "INSERT INTO payload_archive \
+     (content_hash, stored_at_ms, \
+     size_bytes, data) VALUES (?1, \
+     ?2, ?3, ?4) ON \
+     CONFLICT(content_hash) DO NOTHING"
+  )
+  .bind(content_hash)
+  .bind(stored_at_ms)
+  .bind(data.len() as i64)
+  .bind(data)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "insert_archived_blob error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
+
+pub async fn archived_blob(
+  pool: &SqlitePool,
+  content_hash: &str
+) -> Result<Option<Vec<u8>>, String> {
+  sqlx::query_scalar(
+    "SELECT data FROM payload_archive \
+     WHERE content_hash = ?1"
+  )
+  .bind(content_hash)
+  .fetch_optional(pool)
+  .await
+  .map_err(|e| {
+    format!("archived_blob error: {e}")
+  })
+}
+
+pub async fn orphan_archived_blobs(
+  pool: &SqlitePool,
+  limit: u64
+) -> Result<Vec<String>, String> {
+  sqlx::query_scalar(
+    "SELECT a.content_hash FROM \
+     payload_archive a WHERE NOT \
+     EXISTS (SELECT 1 FROM \
+     feed_payloads p WHERE \
+     p.content_hash = a.content_hash) \
+     AND NOT EXISTS (SELECT 1 FROM \
+     payload_archive_index i WHERE \
+     i.content_hash = a.content_hash) \
+     LIMIT ?1"
+  )
+  .bind(
+    limit.min(i64::MAX as u64) as i64
+  )
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "orphan_archived_blobs error: \
+       {e}"
+    )
+  })
+}
+
+pub async fn delete_archived_blobs(
+  pool: &SqlitePool,
+  content_hashes: &[String]
+) -> Result<u64, String> {
+  if content_hashes.is_empty() {
+    return Ok(0);
+  }
+
+  let mut builder =
+    QueryBuilder::<Sqlite>::new(
+      "DELETE FROM payload_archive \
+       WHERE content_hash IN ("
+    );
+
+  let mut separated =
+    builder.separated(", ");
+
+  for hash in content_hashes {
+    separated.push_bind(hash);
+  }
+
+  separated.push_unseparated(")");
+
+  builder
+    .build()
+    .execute(pool)
+    .await
+    .map(|res| res.rows_affected())
+    .map_err(|e| {
+      format!(
+        "delete_archived_blobs error: \
+         {e}"
+      )
+    })
+}
+
+pub async fn referenced_content_hashes(
+  pool: &SqlitePool,
+  content_hashes: &[String]
+) -> Result<HashSet<String>, String> {
+  if content_hashes.is_empty() {
+    return Ok(HashSet::new());
+  }
+
+  let mut builder =
+    QueryBuilder::<Sqlite>::new(
+      "SELECT content_hash FROM \
+       feed_payloads WHERE \
+       content_hash IN ("
+    );
+
+  push_hashes(
+    &mut builder,
+    content_hashes
+  );
+
+  builder.push(
+    " UNION SELECT content_hash FROM \
+     payload_archive_index WHERE \
+     content_hash IN ("
+  );
+
+  push_hashes(
+    &mut builder,
+    content_hashes
+  );
+
+  let rows: Vec<String> = builder
+    .build_query_scalar()
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!(
+        "referenced_content_hashes \
+         error: {e}"
+      )
+    })?;
+
+  Ok(rows.into_iter().collect())
+}
+
+/// Binds `content_hashes` as an `IN`
+/// list and closes it.
+fn push_hashes<'a>(
+  builder: &mut QueryBuilder<
+    'a,
+    Sqlite
+  >,
+  content_hashes: &'a [String]
+) {
+  let mut separated =
+    builder.separated(", ");
+
+  for hash in content_hashes {
+    separated.push_bind(hash);
+  }
+
+  separated.push_unseparated(")");
+}
@@ -239,6 +239,37 @@ pub async fn ensure_feed_tags_column(
   Ok(())
 }
 
+pub async fn ensure_feed_item_diff_column(
+  pool: &SqlitePool
+) -> Result<(), String> {
+  let has_column: Option<i64> = sqlx::query_scalar(
+        r#"SELECT 1 FROM pragma_table_info('feed_items') WHERE name = 'diff' LIMIT 1"#,
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("introspect feed_items table: {e}"))?;
+
+  if has_column.is_some() {
+    return Ok(());
+  }
+
+  sqlx::query(
+    "ALTER TABLE feed_items ADD \
+     COLUMN diff TEXT NULL"
+  )
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!("add diff column: {e}")
+  })?;
+
+  info!(
+    "Added diff column to feed_items"
+  );
+
+  Ok(())
+}
+
 pub async fn ensure_feed_category_column(
   pool: &SqlitePool
 ) -> Result<(), String> {
@@ -2,10 +2,20 @@
 //! timing/status/error info.
 
 use chrono_tz::Tz;
-use sqlx::SqlitePool;
+use sqlx::{
+  QueryBuilder,
+  Sqlite,
+  SqlitePool
+};
 
 use super::util::now_epoch_ms;
 use crate::domain::model::ErrorKind;
+use crate::ports::repo::NewEvent;
+
+/// Rows per multi-row statement; nine
+/// binds a row stays far below
+/// SQLite's variable limit.
+const CHUNK_ROWS: usize = 500;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn insert_event(
@@ -18,35 +28,86 @@ pub async fn insert_event(
   backoff_index: i64,
   scheduled_next_action_at_ms: i64,
   debug: Option<&str>,
+  zone: &Tz
+) -> Result<(), String> {
+  insert_events(
+    pool,
+    &[NewEvent {
+      feed_id: feed_id.to_string(),
+      event_time_ms: now_epoch_ms(),
+      method: method.to_string(),
+      status,
+      error_kind,
+      latency_ms,
+      backoff_index,
+      scheduled_next_action_at_ms,
+      debug: debug.map(str::to_string)
+    }],
+    zone
+  )
+  .await
+}
+
+pub async fn insert_events(
+  pool: &SqlitePool,
+  events: &[NewEvent],
   _zone: &Tz
 ) -> Result<(), String> {
-  let now_ms = now_epoch_ms();
-
-  sqlx::query(
-        r#"
-      INSERT INTO fetch_events(
-        feed_id, event_time_ms, method,
-        status, error_kind, latency_ms, backoff_index,
-        scheduled_next_action_at_ms, debug
-      ) VALUES (
-        ?1, ?2, ?3,
-        ?4, ?5, ?6, ?7,
-        ?8, ?9
-      )
-      "#,
-    )
-    .bind(feed_id)
-    .bind(now_ms)
-    .bind(method)
-    .bind(status)
-    .bind(error_kind.map(|e| format!("{:?}", e)))
-    .bind(latency_ms)
-    .bind(backoff_index)
-    .bind(scheduled_next_action_at_ms)
-    .bind(debug.map(|s| s.to_string()))
-    .execute(pool)
-    .await
-    .map_err(|e| format!("insert_event error: {e}"))?;
-
-  Ok(())
+  if events.is_empty() {
+    return Ok(());
+  }
+
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  for chunk in events.chunks(CHUNK_ROWS)
+  {
+    let mut builder =
+      QueryBuilder::<Sqlite>::new(
+        "INSERT INTO \
+         fetch_events(feed_id, \
+         event_time_ms, method, \
+         status, error_kind, \
+         latency_ms, backoff_index, \
+         scheduled_next_action_at_ms, \
+         debug) "
+      );
+
+    builder.push_values(
+      chunk,
+      |mut row, e| {
+        row
+          .push_bind(&e.feed_id)
+          .push_bind(e.event_time_ms)
+          .push_bind(&e.method)
+          .push_bind(e.status)
+          .push_bind(
+            e.error_kind
+              .map(|k| format!("{k:?}"))
+          )
+          .push_bind(e.latency_ms)
+          .push_bind(e.backoff_index)
+          .push_bind(
+            e.scheduled_next_action_at_ms
+          )
+          .push_bind(&e.debug);
+      }
+    );
+
+    builder
+      .build()
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "insert_events error: {e}"
+        )
+      })?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })
 }
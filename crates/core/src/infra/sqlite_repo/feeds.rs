@@ -101,16 +101,37 @@ async fn upsert_chunk(
         serde_json::to_string(tags).ok()
       });
 
+    // A feed archived after 410 Gone is
+    // rescheduled once its URL changes.
+    sqlx::query(
+      r#"
+        UPDATE feed_state_current
+        SET phase = 'NeedsInitialGet', next_action_at_ms = ?3
+        WHERE feed_id = ?1
+          AND phase = 'Archived'
+          AND EXISTS (SELECT 1 FROM feeds WHERE id = ?1 AND url <> ?2)
+        "#
+    )
+    .bind(&f.id)
+    .bind(&f.url)
+    .bind(now_ms)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+      format!("unarchive feed error: {e}")
+    })?;
+
     sqlx::query(
             r#"
-        INSERT INTO feeds(id, url, domain, category, base_poll_seconds, tags, created_at_ms)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        INSERT INTO feeds(id, url, domain, category, base_poll_seconds, tags, created_at_ms, language)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
         ON CONFLICT(id) DO UPDATE SET
           url = excluded.url,
           domain = excluded.domain,
           category = excluded.category,
           base_poll_seconds = excluded.base_poll_seconds,
-          tags = excluded.tags
+          tags = excluded.tags,
+          language = excluded.language
         "#,
         )
         .bind(&f.id)
@@ -120,6 +141,7 @@ async fn upsert_chunk(
         .bind(f.base_poll_seconds as i64)
         .bind(tags_json)
         .bind(now_ms)
+        .bind(&f.language)
         .execute(&mut *tx)
         .await
         .map_err(|e| format!("upsert feed error: {e}"))?;
@@ -155,6 +177,7 @@ pub async fn due_feeds(
       LEFT JOIN error_feeds e ON e.feed_id = f.id
       WHERE f.category = ?1
         AND e.feed_id IS NULL
+        AND (s.feed_id IS NULL OR s.phase <> 'Archived')
         AND (s.feed_id IS NULL OR s.next_action_at_ms <= ?2)
       ORDER BY COALESCE(s.next_action_at_ms, ?2)
       LIMIT ?3
@@ -0,0 +1,167 @@
+//! Read queries over fetch events,
+//! state history, error feeds and
+//! stored payloads.
+
+use sqlx::SqlitePool;
+
+use super::models::{
+  ErrorFeedRecord,
+  FetchEventRecord,
+  PayloadSummaryRecord,
+  StateHistoryRecord,
+  StoredItemRecord
+};
+use crate::ports::repo::{
+  ErrorFeedRow,
+  FetchEventRow,
+  PayloadSummaryRow,
+  StateHistoryRow,
+  StoredItemRow
+};
+
+pub async fn recent_events(
+  pool: &SqlitePool,
+  feed_id: &str,
+  limit: u64
+) -> Result<Vec<FetchEventRow>, String>
+{
+  let rows = sqlx::query_as::<_, FetchEventRecord>(
+        r#"
+      SELECT id, feed_id, event_time_ms, method, status, error_kind,
+        latency_ms, backoff_index, scheduled_next_action_at_ms, debug
+      FROM fetch_events
+      WHERE feed_id = ?1
+      ORDER BY event_time_ms DESC, id DESC
+      LIMIT ?2
+      "#,
+    )
+    .bind(feed_id)
+    .bind(limit.min(i64::MAX as u64) as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("recent_events error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(FetchEventRow::from)
+      .collect()
+  )
+}
+
+pub async fn state_history(
+  pool: &SqlitePool,
+  feed_id: &str,
+  limit: u64
+) -> Result<Vec<StateHistoryRow>, String>
+{
+  let rows = sqlx::query_as::<_, StateHistoryRecord>(
+        r#"
+      SELECT recorded_at_ms, feed_id, phase, last_head_at_ms,
+        last_head_status, last_head_error, last_get_at_ms,
+        last_get_status, last_get_error, etag, last_modified_ms,
+        backoff_index, base_poll_seconds, next_action_at_ms,
+        jitter_seconds, note, consecutive_error_count
+      FROM feed_state_history
+      WHERE feed_id = ?1
+      ORDER BY recorded_at_ms DESC, id DESC
+      LIMIT ?2
+      "#,
+    )
+    .bind(feed_id)
+    .bind(limit.min(i64::MAX as u64) as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("state_history error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(StateHistoryRow::from)
+      .collect()
+  )
+}
+
+pub async fn error_feeds(
+  pool: &SqlitePool
+) -> Result<Vec<ErrorFeedRow>, String> {
+  let rows = sqlx::query_as::<_, ErrorFeedRecord>(
+        r#"
+      SELECT feed_id, error_count, last_error_kind, last_error_status,
+        last_error_at_ms, note
+      FROM error_feeds
+      ORDER BY feed_id
+      "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("error_feeds error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(ErrorFeedRow::from)
+      .collect()
+  )
+}
+
+pub async fn payload_timeline(
+  pool: &SqlitePool,
+  feed_id: &str,
+  limit: u64
+) -> Result<
+  Vec<PayloadSummaryRow>,
+  String
+> {
+  let rows = sqlx::query_as::<_, PayloadSummaryRecord>(
+        r#"
+      SELECT p.id AS payload_id, p.feed_id, p.fetched_at_ms, p.etag,
+        p.last_modified_ms, p.content_hash, p.title,
+        (SELECT COUNT(*) FROM feed_items i WHERE i.payload_id = p.id)
+          AS item_count
+      FROM feed_payloads p
+      WHERE p.feed_id = ?1
+      ORDER BY p.fetched_at_ms DESC, p.id DESC
+      LIMIT ?2
+      "#,
+    )
+    .bind(feed_id)
+    .bind(limit.min(i64::MAX as u64) as i64)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("payload_timeline error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(PayloadSummaryRow::from)
+      .collect()
+  )
+}
+
+pub async fn payload_items(
+  pool: &SqlitePool,
+  payload_id: i64
+) -> Result<Vec<StoredItemRow>, String>
+{
+  let rows = sqlx::query_as::<_, StoredItemRecord>(
+        r#"
+      SELECT id, payload_id, feed_id, title, link, guid, published_at_ms,
+        category, summary
+      FROM feed_items
+      WHERE payload_id = ?1
+      ORDER BY id
+      "#,
+    )
+    .bind(payload_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("payload_items error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(StoredItemRow::from)
+      .collect()
+  )
+}
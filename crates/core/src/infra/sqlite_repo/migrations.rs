@@ -1,6 +1,7 @@
-//! Database migrations: create
-//! tables/indexes and ensure new
-//! columns exist.
+//! Database migrations: applies the
+//! fetcher's numbered migrations and
+//! adopts databases created before the
+//! migrations ledger existed.
 
 use chrono_tz::Tz;
 use sqlx::SqlitePool;
@@ -9,16 +10,15 @@ use tracing::info;
 use super::connection::{
   ensure_feed_base_poll_column,
   ensure_feed_category_column,
+  ensure_feed_item_diff_column,
   ensure_feed_state_error_count_column,
   ensure_feed_state_note_column,
   ensure_feed_tags_column
 };
-
-const SQLITE_SCHEMA: &str =
-  include_str!(concat!(
-    env!("CARGO_MANIFEST_DIR"),
-    "/res/sql/sqlite/schema.sql"
-  ));
+use crate::infra::migrations::{
+  self,
+  Component
+};
 
 pub async fn migrate(
   pool: &SqlitePool,
@@ -38,25 +38,64 @@ pub async fn migrate(
     )
   })?;
 
-  // Ensure category exists before
-  // schema indexes use it on existing
-  // DBs.
-  ensure_feed_category_column(pool)
+  let status =
+    migrations::sqlite::status(
+      pool,
+      Component::Fetcher
+    )
     .await?;
 
-  ensure_feed_tags_column(pool).await?;
-
-  for ddl in schema_statements() {
-    sqlx::query(ddl)
-      .execute(pool)
-      .await
-      .map_err(|e| {
-        format!(
-          "migrate error (ddl): {e}"
-        )
-      })?;
+  status.ensure_supported()?;
+
+  let adopting =
+    status.applied.is_empty()
+      && has_feeds_table(pool).await?;
+
+  // Pre-ledger databases may predate
+  // columns the baseline indexes use.
+  if adopting {
+    info!(
+      "Adopting pre-ledger database"
+    );
+
+    ensure_feed_category_column(pool)
+      .await?;
+
+    ensure_feed_tags_column(pool)
+      .await?;
   }
 
+  let applied =
+    migrations::sqlite::apply_pending(
+      pool,
+      Component::Fetcher
+    )
+    .await?;
+
+  if adopting {
+    backfill_legacy_columns(
+      pool,
+      default_poll_seconds
+    )
+    .await?;
+  }
+
+  info!(
+    applied = applied.len(),
+    "DB migrate done"
+  );
+
+  Ok(())
+}
+
+/// Columns added by hand before the
+/// ledger; the baseline's `IF NOT
+/// EXISTS` tables skip them on old
+/// databases.
+async fn backfill_legacy_columns(
+  pool: &SqlitePool,
+  default_poll_seconds: u64
+) -> Result<(), String> {
   ensure_feed_base_poll_column(
     pool,
     default_poll_seconds
@@ -66,6 +105,9 @@ pub async fn migrate(
   ensure_feed_state_note_column(pool)
     .await?;
 
+  ensure_feed_item_diff_column(pool)
+    .await?;
+
   ensure_feed_state_error_count_column(
     pool,
     "feed_state_current"
@@ -76,17 +118,18 @@ pub async fn migrate(
     pool,
     "feed_state_history"
   )
-  .await?;
-
-  info!("DB migrate done");
-
-  Ok(())
+  .await
 }
 
-fn schema_statements()
--> impl Iterator<Item = &'static str> {
-  SQLITE_SCHEMA
-    .split(';')
-    .map(str::trim)
-    .filter(|s| !s.is_empty())
+async fn has_feeds_table(
+  pool: &SqlitePool
+) -> Result<bool, String> {
+  let has_table: Option<i64> = sqlx::query_scalar(
+        r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='feeds' LIMIT 1"#,
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("introspect sqlite_master: {e}"))?;
+
+  Ok(has_table.is_some())
 }
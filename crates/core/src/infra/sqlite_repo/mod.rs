@@ -2,17 +2,24 @@
 //! implementing persistence for feeds,
 //! state, events, and payloads.
 
+mod archive;
 mod connection;
 mod cookies;
 mod error_feeds;
 mod events;
 mod feeds;
+mod history;
 mod migrations;
 mod models;
 mod payloads;
+mod redirects;
+mod registry;
+mod retention;
+mod snapshots;
 mod state;
 mod util;
 
+use std::collections::HashSet;
 use std::path::Path;
 
 use chrono_tz::Tz;
@@ -23,10 +30,31 @@ use crate::domain::model::{
   ErrorKind,
   FeedConfig
 };
-use crate::feed::parser::ParsedFeed;
+use crate::domain::registry::{
+  RegistryFeed,
+  RegistryStatus
+};
+use crate::domain::retention::{
+  RetentionLimit,
+  RetentionTable
+};
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
 use crate::ports::repo::{
+  ArchiveRefRow,
+  ArchivedFeedRow,
+  ErrorFeedRow,
+  FeedRedirectRow,
+  FetchEventRow,
+  NewEvent,
+  NewState,
+  PayloadSummaryRow,
   Repo,
-  StateRow
+  StateHistoryRow,
+  StateRow,
+  StoredItemRow
 };
 
 pub struct SqliteRepo {
@@ -45,6 +73,23 @@ impl SqliteRepo {
       pool
     })
   }
+
+  /// Wraps a pool opened elsewhere,
+  /// e.g. by the server on the same
+  /// database file.
+  pub fn from_pool(
+    pool: SqlitePool
+  ) -> Self {
+    Self {
+      pool
+    }
+  }
+}
+
+pub async fn create_pool(
+  db_path: &Path
+) -> Result<SqlitePool, String> {
+  connection::create_pool(db_path).await
 }
 
 #[async_trait::async_trait]
@@ -157,6 +202,28 @@ impl Repo for SqliteRepo {
     .await
   }
 
+  async fn insert_events(
+    &self,
+    events: &[NewEvent],
+    zone: &Tz
+  ) -> Result<(), String> {
+    events::insert_events(
+      &self.pool, events, zone
+    )
+    .await
+  }
+
+  async fn insert_states(
+    &self,
+    states: &[NewState],
+    zone: &Tz
+  ) -> Result<(), String> {
+    state::insert_states(
+      &self.pool, states, zone
+    )
+    .await
+  }
+
   async fn insert_payload_with_items(
     &self,
     feed_id: &str,
@@ -180,6 +247,18 @@ impl Repo for SqliteRepo {
     .await
   }
 
+  async fn known_item_guids(
+    &self,
+    feed_id: &str,
+    guids: &[String]
+  ) -> Result<HashSet<String>, String>
+  {
+    payloads::known_item_guids(
+      &self.pool, feed_id, guids
+    )
+    .await
+  }
+
   async fn mark_feed_error(
     &self,
     feed_id: &str,
@@ -228,4 +307,363 @@ impl Repo for SqliteRepo {
     )
     .await
   }
+
+  async fn latest_watch_snapshot(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>
+  {
+    snapshots::latest_watch_snapshot(
+      &self.pool, feed_id
+    )
+    .await
+  }
+
+  async fn upsert_watch_snapshot(
+    &self,
+    feed_id: &str,
+    content_text: &str,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    snapshots::upsert_watch_snapshot(
+      &self.pool,
+      feed_id,
+      content_text,
+      observed_at_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn record_feed_redirect(
+    &self,
+    feed_id: &str,
+    from_url: &str,
+    suggested_url: &str,
+    status: i64,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    redirects::record_feed_redirect(
+      &self.pool,
+      feed_id,
+      from_url,
+      suggested_url,
+      status,
+      observed_at_ms,
+      zone
+    )
+    .await
+  }
+
+  async fn clear_feed_redirect(
+    &self,
+    feed_id: &str
+  ) -> Result<(), String> {
+    redirects::clear_feed_redirect(
+      &self.pool, feed_id
+    )
+    .await
+  }
+
+  async fn feed_redirects(
+    &self,
+    min_hits: i64
+  ) -> Result<
+    Vec<FeedRedirectRow>,
+    String
+  > {
+    redirects::feed_redirects(
+      &self.pool, min_hits
+    )
+    .await
+  }
+
+  async fn archived_feeds(
+    &self
+  ) -> Result<
+    Vec<ArchivedFeedRow>,
+    String
+  > {
+    redirects::archived_feeds(
+      &self.pool
+    )
+    .await
+  }
+
+  async fn feed_categories(
+    &self
+  ) -> Result<
+    Vec<(String, String)>,
+    String
+  > {
+    retention::feed_categories(
+      &self.pool
+    )
+    .await
+  }
+
+  async fn prune_batch(
+    &self,
+    table: RetentionTable,
+    feed_id: &str,
+    limit: RetentionLimit,
+    now_ms: i64,
+    batch_size: u64,
+    _server_schema: &str
+  ) -> Result<u64, String> {
+    retention::prune_batch(
+      &self.pool, table, feed_id,
+      limit, now_ms, batch_size
+    )
+    .await
+  }
+
+  async fn count_prunable(
+    &self,
+    table: RetentionTable,
+    feed_id: &str,
+    limit: RetentionLimit,
+    now_ms: i64,
+    _server_schema: &str
+  ) -> Result<u64, String> {
+    retention::count_prunable(
+      &self.pool, table, feed_id,
+      limit, now_ms
+    )
+    .await
+  }
+
+  async fn insert_archive_ref(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    content_hash: &str
+  ) -> Result<(), String> {
+    archive::insert_archive_ref(
+      &self.pool,
+      feed_id,
+      fetched_at_ms,
+      content_hash
+    )
+    .await
+  }
+
+  async fn archive_refs_since(
+    &self,
+    feed_id: &str,
+    since_ms: i64
+  ) -> Result<Vec<ArchiveRefRow>, String>
+  {
+    archive::archive_refs_since(
+      &self.pool, feed_id, since_ms
+    )
+    .await
+  }
+
+  async fn insert_items(
+    &self,
+    payload_id: i64,
+    feed_id: &str,
+    items: &[FeedItem],
+    _zone: &Tz
+  ) -> Result<u64, String> {
+    payloads::insert_items(
+      &self.pool, payload_id, feed_id,
+      items
+    )
+    .await
+  }
+
+  async fn archived_blob_exists(
+    &self,
+    content_hash: &str
+  ) -> Result<bool, String> {
+    archive::archived_blob_exists(
+      &self.pool,
+      content_hash
+    )
+    .await
+  }
+
+  async fn insert_archived_blob(
+    &self,
+    content_hash: &str,
+    stored_at_ms: i64,
+    data: &[u8]
+  ) -> Result<(), String> {
+    archive::insert_archived_blob(
+      &self.pool,
+      content_hash,
+      stored_at_ms,
+      data
+    )
+    .await
+  }
+
+  async fn archived_blob(
+    &self,
+    content_hash: &str
+  ) -> Result<Option<Vec<u8>>, String>
+  {
+    archive::archived_blob(
+      &self.pool,
+      content_hash
+    )
+    .await
+  }
+
+  async fn orphan_archived_blobs(
+    &self,
+    limit: u64
+  ) -> Result<Vec<String>, String> {
+    archive::orphan_archived_blobs(
+      &self.pool, limit
+    )
+    .await
+  }
+
+  async fn delete_archived_blobs(
+    &self,
+    content_hashes: &[String]
+  ) -> Result<u64, String> {
+    archive::delete_archived_blobs(
+      &self.pool,
+      content_hashes
+    )
+    .await
+  }
+
+  async fn referenced_content_hashes(
+    &self,
+    content_hashes: &[String]
+  ) -> Result<HashSet<String>, String>
+  {
+    archive::referenced_content_hashes(
+      &self.pool,
+      content_hashes
+    )
+    .await
+  }
+
+  async fn recent_events(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<Vec<FetchEventRow>, String>
+  {
+    history::recent_events(
+      &self.pool, feed_id, limit
+    )
+    .await
+  }
+
+  async fn state_history(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<
+    Vec<StateHistoryRow>,
+    String
+  > {
+    history::state_history(
+      &self.pool, feed_id, limit
+    )
+    .await
+  }
+
+  async fn error_feeds(
+    &self
+  ) -> Result<Vec<ErrorFeedRow>, String>
+  {
+    history::error_feeds(&self.pool)
+      .await
+  }
+
+  async fn payload_timeline(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<
+    Vec<PayloadSummaryRow>,
+    String
+  > {
+    history::payload_timeline(
+      &self.pool, feed_id, limit
+    )
+    .await
+  }
+
+  async fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Result<Vec<StoredItemRow>, String>
+  {
+    history::payload_items(
+      &self.pool, payload_id
+    )
+    .await
+  }
+
+  async fn registry_feeds(
+    &self,
+    status: Option<RegistryStatus>
+  ) -> Result<Vec<RegistryFeed>, String>
+  {
+    registry::registry_feeds(
+      &self.pool, status
+    )
+    .await
+  }
+
+  async fn registry_feed(
+    &self,
+    id_or_url: &str
+  ) -> Result<
+    Option<RegistryFeed>,
+    String
+  > {
+    registry::registry_feed(
+      &self.pool, id_or_url
+    )
+    .await
+  }
+
+  async fn count_registry_feeds(
+    &self,
+    added_by: i64
+  ) -> Result<i64, String> {
+    registry::count_registry_feeds(
+      &self.pool, added_by
+    )
+    .await
+  }
+
+  async fn insert_registry_feed(
+    &self,
+    feed: &RegistryFeed,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    registry::insert_registry_feed(
+      &self.pool, feed
+    )
+    .await
+  }
+
+  async fn review_registry_feed(
+    &self,
+    id: &str,
+    status: RegistryStatus,
+    reviewed_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<bool, String> {
+    registry::review_registry_feed(
+      &self.pool,
+      id,
+      status,
+      reviewed_at_ms
+    )
+    .await
+  }
 }
@@ -2,7 +2,20 @@
 //! SQLx records and domain types.
 
 use crate::domain::model::FeedConfig;
-use crate::ports::repo::StateRow;
+use crate::domain::registry::{
+  RegistryFeed,
+  RegistryStatus
+};
+use crate::ports::repo::{
+  ArchivedFeedRow,
+  ErrorFeedRow,
+  FeedRedirectRow,
+  FetchEventRow,
+  PayloadSummaryRow,
+  StateHistoryRow,
+  StateRow,
+  StoredItemRow
+};
 
 #[derive(Debug, sqlx::FromRow)]
 
@@ -97,3 +110,264 @@ impl From<DueFeedRow> for FeedConfig {
     }
   }
 }
+
+#[derive(Debug, sqlx::FromRow)]
+
+pub struct FeedRedirectRecord {
+  pub feed_id:          String,
+  pub url:              String,
+  pub suggested_url:    String,
+  pub status:           i64,
+  pub hit_count:        i64,
+  pub first_seen_at_ms: i64,
+  pub last_seen_at_ms:  i64
+}
+
+impl From<FeedRedirectRecord>
+  for FeedRedirectRow
+{
+  fn from(
+    value: FeedRedirectRecord
+  ) -> Self {
+    Self {
+      feed_id:          value.feed_id,
+      url:              value.url,
+      suggested_url:    value
+        .suggested_url,
+      status:           value.status,
+      hit_count:        value.hit_count,
+      first_seen_at_ms: value
+        .first_seen_at_ms,
+      last_seen_at_ms:  value
+        .last_seen_at_ms
+    }
+  }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+pub struct ArchivedFeedRecord {
+  pub feed_id:        String,
+  pub url:            String,
+  pub archived_at_ms: Option<i64>
+}
+
+impl From<ArchivedFeedRecord>
+  for ArchivedFeedRow
+{
+  fn from(
+    value: ArchivedFeedRecord
+  ) -> Self {
+    Self {
+      feed_id:        value.feed_id,
+      url:            value.url,
+      archived_at_ms: value
+        .archived_at_ms
+    }
+  }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+pub struct FetchEventRecord {
+  pub id: i64,
+  pub feed_id: String,
+  pub event_time_ms: i64,
+  pub method: String,
+  pub status: Option<i64>,
+  pub error_kind: Option<String>,
+  pub latency_ms: Option<i64>,
+  pub backoff_index: i64,
+  pub scheduled_next_action_at_ms: i64,
+  pub debug: Option<String>
+}
+
+impl From<FetchEventRecord>
+  for FetchEventRow
+{
+  fn from(
+    value: FetchEventRecord
+  ) -> Self {
+    Self {
+      id: value.id,
+      feed_id: value.feed_id,
+      event_time_ms: value
+        .event_time_ms,
+      method: value.method,
+      status: value.status,
+      error_kind: value.error_kind,
+      latency_ms: value.latency_ms,
+      backoff_index: value
+        .backoff_index,
+      scheduled_next_action_at_ms:
+        value
+          .scheduled_next_action_at_ms,
+      debug: value.debug
+    }
+  }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+pub struct StateHistoryRecord {
+  pub recorded_at_ms: i64,
+  #[sqlx(flatten)]
+  pub state:          StateRowRecord
+}
+
+impl From<StateHistoryRecord>
+  for StateHistoryRow
+{
+  fn from(
+    value: StateHistoryRecord
+  ) -> Self {
+    Self {
+      recorded_at_ms: value
+        .recorded_at_ms,
+      state:          StateRow::from(
+        value.state
+      )
+    }
+  }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+pub struct ErrorFeedRecord {
+  pub feed_id:           String,
+  pub error_count:       i64,
+  pub last_error_kind:   Option<String>,
+  pub last_error_status: Option<i64>,
+  pub last_error_at_ms:  i64,
+  pub note:              Option<String>
+}
+
+impl From<ErrorFeedRecord>
+  for ErrorFeedRow
+{
+  fn from(
+    value: ErrorFeedRecord
+  ) -> Self {
+    Self {
+      feed_id:           value.feed_id,
+      error_count:       value
+        .error_count,
+      last_error_kind:   value
+        .last_error_kind,
+      last_error_status: value
+        .last_error_status,
+      last_error_at_ms:  value
+        .last_error_at_ms,
+      note:              value.note
+    }
+  }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+pub struct PayloadSummaryRecord {
+  pub payload_id:       i64,
+  pub feed_id:          String,
+  pub fetched_at_ms:    i64,
+  pub etag:             Option<String>,
+  pub last_modified_ms: Option<i64>,
+  pub content_hash:     Option<String>,
+  pub title:            Option<String>,
+  pub item_count:       i64
+}
+
+impl From<PayloadSummaryRecord>
+  for PayloadSummaryRow
+{
+  fn from(
+    value: PayloadSummaryRecord
+  ) -> Self {
+    Self {
+      payload_id:       value
+        .payload_id,
+      feed_id:          value.feed_id,
+      fetched_at_ms:    value
+        .fetched_at_ms,
+      etag:             value.etag,
+      last_modified_ms: value
+        .last_modified_ms,
+      content_hash:     value
+        .content_hash,
+      title:            value.title,
+      item_count:       value
+        .item_count
+    }
+  }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+pub struct StoredItemRecord {
+  pub id:              i64,
+  pub payload_id:      i64,
+  pub feed_id:         String,
+  pub title:           Option<String>,
+  pub link:            Option<String>,
+  pub guid:            Option<String>,
+  pub published_at_ms: Option<i64>,
+  pub category:        Option<String>,
+  pub summary:         Option<String>
+}
+
+impl From<StoredItemRecord>
+  for StoredItemRow
+{
+  fn from(
+    value: StoredItemRecord
+  ) -> Self {
+    Self {
+      id:              value.id,
+      payload_id:      value.payload_id,
+      feed_id:         value.feed_id,
+      title:           value.title,
+      link:            value.link,
+      guid:            value.guid,
+      published_at_ms: value
+        .published_at_ms,
+      category:        value.category,
+      summary:         value.summary
+    }
+  }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+
+pub struct RegistryFeedRecord {
+  pub id:             String,
+  pub url:            String,
+  pub title:          Option<String>,
+  pub added_by:       i64,
+  pub status:         String,
+  pub created_at_ms:  i64,
+  pub reviewed_at_ms: Option<i64>
+}
+
+impl TryFrom<RegistryFeedRecord>
+  for RegistryFeed
+{
+  type Error = String;
+
+  fn try_from(
+    value: RegistryFeedRecord
+  ) -> Result<Self, String> {
+    Ok(Self {
+      id:             value.id,
+      url:            value.url,
+      title:          value.title,
+      added_by:       value.added_by,
+      status:
+        RegistryStatus::parse(
+          &value.status
+        )?,
+      created_at_ms:  value
+        .created_at_ms,
+      reviewed_at_ms: value
+        .reviewed_at_ms
+    })
+  }
+}
@@ -2,11 +2,26 @@
 //! associated feed items in a single
 //! transaction.
 
+use std::collections::HashSet;
+
 use chrono_tz::Tz;
-use sqlx::SqlitePool;
+use sqlx::{
+  QueryBuilder,
+  Sqlite,
+  SqliteConnection,
+  SqlitePool
+};
 use tracing::debug;
 
-use crate::feed::parser::ParsedFeed;
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
+
+/// Items per multi-row insert; ten
+/// binds a row stays far below
+/// SQLite's variable limit.
+const CHUNK_ROWS: usize = 500;
 
 #[allow(clippy::too_many_arguments)]
 pub async fn insert_payload_with_items(
@@ -54,33 +69,13 @@ pub async fn insert_payload_with_items(
     .await
     .map_err(|e| format!("insert payload: {e}"))?;
 
-  for it in &parsed.items {
-    sqlx::query(
-            r#"
-        INSERT INTO feed_items(
-          payload_id, feed_id, title, link, guid,
-          published_at_ms,
-          category, description, summary
-        ) VALUES (
-          ?1, ?2, ?3, ?4, ?5,
-          ?6,
-          ?7, ?8, ?9
-        )
-        "#,
-        )
-        .bind(payload_id)
-        .bind(feed_id)
-        .bind(it.title.clone())
-        .bind(it.link.clone())
-        .bind(it.guid.clone())
-        .bind(it.published_at_ms)
-        .bind(it.category.clone())
-        .bind(it.description.clone())
-        .bind(it.summary.clone())
-        .execute(&mut *tx)
-        .await
-        .map_err(|e| format!("insert item: {e}"))?;
-  }
+  insert_item_rows(
+    &mut tx,
+    payload_id,
+    feed_id,
+    &parsed.items
+  )
+  .await?;
 
   tx.commit().await.map_err(|e| {
     format!("tx commit: {e}")
@@ -94,3 +89,135 @@ pub async fn insert_payload_with_items(
 
   Ok(())
 }
+
+/// Appends reparsed items to an
+/// existing payload.
+pub async fn insert_items(
+  pool: &SqlitePool,
+  payload_id: i64,
+  feed_id: &str,
+  items: &[FeedItem]
+) -> Result<u64, String> {
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  insert_item_rows(
+    &mut tx, payload_id, feed_id, items
+  )
+  .await?;
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })?;
+
+  Ok(items.len() as u64)
+}
+
+/// Inserts items with multi-row
+/// statements of `CHUNK_ROWS` rows,
+/// tagged with the feed's language.
+async fn insert_item_rows(
+  conn: &mut SqliteConnection,
+  payload_id: i64,
+  feed_id: &str,
+  items: &[FeedItem]
+) -> Result<(), String> {
+  if items.is_empty() {
+    return Ok(());
+  }
+
+  let language: Option<String> =
+    sqlx::query_scalar(
+      "SELECT language FROM feeds \
+       WHERE id = ?1"
+    )
+    .bind(feed_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| {
+      format!("feed language: {e}")
+    })?
+    .flatten();
+
+  for chunk in items.chunks(CHUNK_ROWS)
+  {
+    let mut builder =
+      QueryBuilder::<Sqlite>::new(
+        "INSERT INTO \
+         feed_items(payload_id, \
+         feed_id, title, link, guid, \
+         published_at_ms, category, \
+         description, summary, diff, \
+         language, author) "
+      );
+
+    builder.push_values(
+      chunk,
+      |mut row, it| {
+        row
+          .push_bind(payload_id)
+          .push_bind(feed_id)
+          .push_bind(&it.title)
+          .push_bind(&it.link)
+          .push_bind(&it.guid)
+          .push_bind(it.published_at_ms)
+          .push_bind(&it.category)
+          .push_bind(&it.description)
+          .push_bind(&it.summary)
+          .push_bind(&it.diff)
+          .push_bind(&language)
+          .push_bind(&it.author);
+      }
+    );
+
+    builder
+      .build()
+      .execute(&mut *conn)
+      .await
+      .map_err(|e| {
+        format!("insert item: {e}")
+      })?;
+  }
+
+  Ok(())
+}
+
+pub async fn known_item_guids(
+  pool: &SqlitePool,
+  feed_id: &str,
+  guids: &[String]
+) -> Result<HashSet<String>, String> {
+  if guids.is_empty() {
+    return Ok(HashSet::new());
+  }
+
+  let mut builder =
+    QueryBuilder::<Sqlite>::new(
+      "SELECT DISTINCT guid FROM \
+       feed_items WHERE feed_id = "
+    );
+
+  builder.push_bind(feed_id);
+  builder.push(" AND guid IN (");
+
+  let mut separated =
+    builder.separated(", ");
+
+  for guid in guids {
+    separated.push_bind(guid);
+  }
+
+  separated.push_unseparated(")");
+
+  let rows: Vec<String> = builder
+    .build_query_scalar()
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      format!("known item guids: {e}")
+    })?;
+
+  Ok(rows.into_iter().collect())
+}
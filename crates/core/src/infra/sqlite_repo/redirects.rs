@@ -0,0 +1,138 @@
+//! Permanent redirect tracking and
+//! archived feed listing (SQLite).
+
+use chrono_tz::Tz;
+use sqlx::SqlitePool;
+
+use super::models::{
+  ArchivedFeedRecord,
+  FeedRedirectRecord
+};
+use crate::ports::repo::{
+  ArchivedFeedRow,
+  FeedRedirectRow
+};
+
+pub async fn record_feed_redirect(
+  pool: &SqlitePool,
+  feed_id: &str,
+  from_url: &str,
+  suggested_url: &str,
+  status: i64,
+  observed_at_ms: i64,
+  _zone: &Tz
+) -> Result<(), String> {
+  sqlx::query(
+    r#"
+      INSERT INTO feed_redirects(
+        feed_id, from_url, suggested_url, status,
+        hit_count, first_seen_at_ms, last_seen_at_ms
+      ) VALUES (?1, ?2, ?3, ?4, 1, ?5, ?5)
+      ON CONFLICT(feed_id) DO UPDATE SET
+        hit_count = CASE
+          WHEN feed_redirects.from_url = excluded.from_url
+            AND feed_redirects.suggested_url = excluded.suggested_url
+          THEN feed_redirects.hit_count + 1
+          ELSE 1
+        END,
+        first_seen_at_ms = CASE
+          WHEN feed_redirects.from_url = excluded.from_url
+            AND feed_redirects.suggested_url = excluded.suggested_url
+          THEN feed_redirects.first_seen_at_ms
+          ELSE excluded.first_seen_at_ms
+        END,
+        from_url = excluded.from_url,
+        suggested_url = excluded.suggested_url,
+        status = excluded.status,
+        last_seen_at_ms = excluded.last_seen_at_ms
+      "#
+  )
+  .bind(feed_id)
+  .bind(from_url)
+  .bind(suggested_url)
+  .bind(status)
+  .bind(observed_at_ms)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "record_feed_redirect error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
+
+pub async fn clear_feed_redirect(
+  pool: &SqlitePool,
+  feed_id: &str
+) -> Result<(), String> {
+  sqlx::query(
+    "DELETE FROM feed_redirects WHERE \
+     feed_id = ?1"
+  )
+  .bind(feed_id)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "clear_feed_redirect error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
+
+pub async fn feed_redirects(
+  pool: &SqlitePool,
+  min_hits: i64
+) -> Result<Vec<FeedRedirectRow>, String>
+{
+  let rows = sqlx::query_as::<_, FeedRedirectRecord>(
+        r#"
+      SELECT r.feed_id, f.url, r.suggested_url, r.status, r.hit_count,
+        r.first_seen_at_ms, r.last_seen_at_ms
+      FROM feed_redirects r
+      JOIN feeds f ON f.id = r.feed_id
+      WHERE r.from_url = f.url AND r.hit_count >= ?1
+      ORDER BY r.feed_id
+      "#,
+    )
+    .bind(min_hits)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("feed_redirects error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(FeedRedirectRow::from)
+      .collect()
+  )
+}
+
+pub async fn archived_feeds(
+  pool: &SqlitePool
+) -> Result<Vec<ArchivedFeedRow>, String>
+{
+  let rows = sqlx::query_as::<_, ArchivedFeedRecord>(
+        r#"
+      SELECT s.feed_id, f.url,
+        COALESCE(s.last_get_at_ms, s.last_head_at_ms) AS archived_at_ms
+      FROM feed_state_current s
+      JOIN feeds f ON f.id = s.feed_id
+      WHERE s.phase = 'Archived'
+      ORDER BY s.feed_id
+      "#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("archived_feeds error: {e}"))?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(ArchivedFeedRow::from)
+      .collect()
+  )
+}
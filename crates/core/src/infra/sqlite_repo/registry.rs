@@ -0,0 +1,128 @@
+//! Feeds users added through the
+//! server (SQLite).
+
+use sqlx::SqlitePool;
+
+use super::models::RegistryFeedRecord;
+use crate::domain::registry::{
+  RegistryFeed,
+  RegistryStatus
+};
+
+pub async fn registry_feeds(
+  pool: &SqlitePool,
+  status: Option<RegistryStatus>
+) -> Result<Vec<RegistryFeed>, String> {
+  let rows = sqlx::query_as::<_, RegistryFeedRecord>(
+        r#"
+      SELECT id, url, title, added_by, status, created_at_ms, reviewed_at_ms
+      FROM feed_registry
+      WHERE ?1 IS NULL OR status = ?1
+      ORDER BY created_at_ms, id
+      "#,
+    )
+    .bind(status.map(RegistryStatus::as_str))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("registry_feeds error: {e}"))?;
+
+  rows
+    .into_iter()
+    .map(RegistryFeed::try_from)
+    .collect()
+}
+
+pub async fn registry_feed(
+  pool: &SqlitePool,
+  id_or_url: &str
+) -> Result<Option<RegistryFeed>, String>
+{
+  sqlx::query_as::<_, RegistryFeedRecord>(
+        r#"
+      SELECT id, url, title, added_by, status, created_at_ms, reviewed_at_ms
+      FROM feed_registry
+      WHERE id = ?1 OR url = ?1
+      ORDER BY id
+      LIMIT 1
+      "#,
+    )
+    .bind(id_or_url)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("registry_feed error: {e}"))?
+    .map(RegistryFeed::try_from)
+    .transpose()
+}
+
+pub async fn count_registry_feeds(
+  pool: &SqlitePool,
+  added_by: i64
+) -> Result<i64, String> {
+  sqlx::query_scalar::<_, i64>(
+    "SELECT COUNT(*) FROM \
+     feed_registry WHERE added_by = \
+     ?1 AND status <> 'rejected'"
+  )
+  .bind(added_by)
+  .fetch_one(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "count_registry_feeds error: {e}"
+    )
+  })
+}
+
+pub async fn insert_registry_feed(
+  pool: &SqlitePool,
+  feed: &RegistryFeed
+) -> Result<(), String> {
+  sqlx::query(
+    r#"
+      INSERT INTO feed_registry(
+        id, url, title, added_by, status, created_at_ms, reviewed_at_ms
+      ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+      "#
+  )
+  .bind(&feed.id)
+  .bind(&feed.url)
+  .bind(&feed.title)
+  .bind(feed.added_by)
+  .bind(feed.status.as_str())
+  .bind(feed.created_at_ms)
+  .bind(feed.reviewed_at_ms)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "insert_registry_feed error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
+
+pub async fn review_registry_feed(
+  pool: &SqlitePool,
+  id: &str,
+  status: RegistryStatus,
+  reviewed_at_ms: i64
+) -> Result<bool, String> {
+  let res = sqlx::query(
+    "UPDATE feed_registry SET status \
+     = ?1, reviewed_at_ms = ?2 WHERE \
+     id = ?3 AND status = 'pending'"
+  )
+  .bind(status.as_str())
+  .bind(reviewed_at_ms)
+  .bind(id)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "review_registry_feed error: {e}"
+    )
+  })?;
+
+  Ok(res.rows_affected() > 0)
+}
@@ -0,0 +1,300 @@
+//! Retention pruning for history
+//! tables (SQLite). Server tables live
+//! in the same database when the
+//! server shares it.
+
+use sqlx::SqlitePool;
+
+use crate::domain::retention::{
+  RetentionLimit,
+  RetentionTable
+};
+
+pub async fn feed_categories(
+  pool: &SqlitePool
+) -> Result<Vec<(String, String)>, String>
+{
+  sqlx::query_as::<_, (String, String)>(
+    "SELECT id, category FROM feeds \
+     ORDER BY id"
+  )
+  .fetch_all(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "feed_categories error: {e}"
+    )
+  })
+}
+
+pub async fn prune_batch(
+  pool: &SqlitePool,
+  table: RetentionTable,
+  feed_id: &str,
+  limit: RetentionLimit,
+  now_ms: i64,
+  batch_size: u64
+) -> Result<u64, String> {
+  let guard = item_guard(pool).await?;
+
+  let Some((candidates, binds)) =
+    candidate_sql(
+      table, limit, now_ms, &guard
+    )
+  else {
+    return Ok(0);
+  };
+
+  let sql = format!(
+    "DELETE FROM {} WHERE id IN \
+     ({candidates} LIMIT ?{})",
+    table.as_str(),
+    binds.len() + 2
+  );
+
+  let mut query =
+    sqlx::query(&sql).bind(feed_id);
+
+  for value in binds {
+    query = query.bind(value);
+  }
+
+  query
+    .bind(batch_size as i64)
+    .execute(pool)
+    .await
+    .map(|res| res.rows_affected())
+    .map_err(|e| {
+      format!(
+        "prune {} error: {e}",
+        table.as_str()
+      )
+    })
+}
+
+pub async fn count_prunable(
+  pool: &SqlitePool,
+  table: RetentionTable,
+  feed_id: &str,
+  limit: RetentionLimit,
+  now_ms: i64
+) -> Result<u64, String> {
+  let guard = item_guard(pool).await?;
+
+  let Some((candidates, binds)) =
+    candidate_sql(
+      table, limit, now_ms, &guard
+    )
+  else {
+    return Ok(0);
+  };
+
+  let sql = format!(
+    "SELECT COUNT(*) FROM \
+     ({candidates})"
+  );
+
+  let mut query =
+    sqlx::query_scalar::<_, i64>(&sql)
+      .bind(feed_id);
+
+  for value in binds {
+    query = query.bind(value);
+  }
+
+  query
+    .fetch_one(pool)
+    .await
+    .map(|n| n.max(0) as u64)
+    .map_err(|e| {
+      format!(
+        "count prunable {} error: {e}",
+        table.as_str()
+      )
+    })
+}
+
+/// `SELECT t.id` of the rows outside
+/// `limit`, with `?1` bound to the feed
+/// id and the returned values bound
+/// from `?2`. Items also have to pass
+/// `guard`. `None` when the limit is
+/// unbounded.
+fn candidate_sql(
+  table: RetentionTable,
+  limit: RetentionLimit,
+  now_ms: i64,
+  guard: &str
+) -> Option<(String, Vec<i64>)> {
+  if limit.is_unbounded() {
+    return None;
+  }
+
+  let name = table.as_str();
+  let mut binds = Vec::new();
+  let mut outside = Vec::new();
+
+  if let Some(cutoff) =
+    limit.cutoff_ms(now_ms)
+  {
+    binds.push(cutoff);
+
+    let n = binds.len() + 1;
+
+    outside.push(match table {
+      | RetentionTable::Items => {
+        format!(
+          "t.payload_id IN (SELECT \
+           p.id FROM feed_payloads p \
+           WHERE p.feed_id = ?1 AND \
+           p.fetched_at_ms < ?{n})"
+        )
+      }
+      | _ => {
+        format!(
+          "t.{} < ?{n}",
+          age_column(table)
+        )
+      }
+    });
+  }
+
+  if let Some(max_rows) = limit.max_rows
+  {
+    binds.push(max_rows as i64);
+
+    let n = binds.len() + 1;
+
+    outside.push(format!(
+      "t.id NOT IN (SELECT k.id FROM \
+       {name} k WHERE k.feed_id = ?1 \
+       ORDER BY k.id DESC LIMIT ?{n})"
+    ));
+  }
+
+  let mut sql = format!(
+    "SELECT t.id FROM {name} t WHERE \
+     t.feed_id = ?1 AND ({})",
+    outside.join(" OR ")
+  );
+
+  match table {
+    | RetentionTable::Payloads => {
+      sql.push_str(
+        " AND NOT EXISTS (SELECT 1 \
+         FROM feed_items i WHERE \
+         i.payload_id = t.id)"
+      );
+    }
+    | RetentionTable::Items => {
+      sql.push_str(guard);
+    }
+    | _ => {}
+  }
+
+  Some((sql, binds))
+}
+
+fn age_column(
+  table: RetentionTable
+) -> &'static str {
+  match table {
+    | RetentionTable::FetchEvents => {
+      "event_time_ms"
+    }
+    | RetentionTable::StateHistory => {
+      "recorded_at_ms"
+    }
+    | RetentionTable::Items
+    | RetentionTable::Payloads
+    | RetentionTable::ArchiveIndex => {
+      "fetched_at_ms"
+    }
+  }
+}
+
+/// Keeps items any subscriber (direct
+/// or through a folder) has not read.
+const UNREAD_GUARD: &str = r#"
+  AND NOT EXISTS (
+    SELECT 1 FROM subscriptions s
+    WHERE s.feed_id = t.feed_id
+      AND NOT EXISTS (
+        SELECT 1 FROM entry_states es
+        WHERE es.user_id = s.user_id
+          AND es.item_id = t.id
+          AND es.read_at IS NOT NULL
+      )
+  )
+  AND NOT EXISTS (
+    SELECT 1 FROM folder_feeds ff
+    JOIN folders f ON f.id = ff.folder_id
+    WHERE ff.feed_id = t.feed_id
+      AND NOT EXISTS (
+        SELECT 1 FROM entry_states es
+        WHERE es.user_id = f.user_id
+          AND es.item_id = t.id
+          AND es.read_at IS NOT NULL
+      )
+  )
+"#;
+
+/// Keeps items any user starred.
+const STAR_GUARD: &str = r#"
+  AND NOT EXISTS (
+    SELECT 1 FROM entry_stars st
+    WHERE st.item_id = t.id
+  )
+"#;
+
+/// Keeps items any user queued.
+const QUEUE_GUARD: &str = r#"
+  AND NOT EXISTS (
+    SELECT 1 FROM entry_queue eq
+    WHERE eq.item_id = t.id
+  )
+"#;
+
+/// Keeps items any user labeled.
+const LABEL_GUARD: &str = r#"
+  AND NOT EXISTS (
+    SELECT 1 FROM entry_labels el
+    WHERE el.item_id = t.id
+  )
+"#;
+
+/// Item guards for the server tables
+/// present in this database.
+async fn item_guard(
+  pool: &SqlitePool
+) -> Result<String, String> {
+  let mut guard = String::new();
+
+  for (table, clause) in [
+    ("entry_states", UNREAD_GUARD),
+    ("entry_stars", STAR_GUARD),
+    ("entry_queue", QUEUE_GUARD),
+    ("entry_labels", LABEL_GUARD)
+  ] {
+    if has_table(pool, table).await? {
+      guard.push_str(clause);
+    }
+  }
+
+  Ok(guard)
+}
+
+async fn has_table(
+  pool: &SqlitePool,
+  name: &str
+) -> Result<bool, String> {
+  let has_table: Option<i64> = sqlx::query_scalar(
+        r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name=?1 LIMIT 1"#,
+    )
+    .bind(name)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("introspect sqlite_master: {e}"))?;
+
+  Ok(has_table.is_some())
+}
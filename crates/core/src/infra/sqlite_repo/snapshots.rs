@@ -0,0 +1,63 @@
+//! Last normalized text snapshot per
+//! watch, used to diff `any_change`
+//! watches (SQLite).
+
+use chrono_tz::Tz;
+use sqlx::SqlitePool;
+
+pub async fn latest_watch_snapshot(
+  pool: &SqlitePool,
+  feed_id: &str
+) -> Result<Option<String>, String> {
+  sqlx::query_scalar::<_, String>(
+    r#"
+      SELECT content_text
+      FROM watch_snapshots
+      WHERE feed_id = ?1
+      LIMIT 1
+      "#
+  )
+  .bind(feed_id)
+  .fetch_optional(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "latest_watch_snapshot error: \
+       {e}"
+    )
+  })
+}
+
+pub async fn upsert_watch_snapshot(
+  pool: &SqlitePool,
+  feed_id: &str,
+  content_text: &str,
+  observed_at_ms: i64,
+  _zone: &Tz
+) -> Result<(), String> {
+  sqlx::query(
+    r#"
+      INSERT INTO watch_snapshots(
+        feed_id,
+        content_text,
+        updated_at_ms
+      ) VALUES (?1, ?2, ?3)
+      ON CONFLICT(feed_id)
+      DO UPDATE SET
+        content_text = excluded.content_text,
+        updated_at_ms = excluded.updated_at_ms
+      "#,
+  )
+  .bind(feed_id)
+  .bind(content_text)
+  .bind(observed_at_ms)
+  .execute(pool)
+  .await
+  .map_err(|e| {
+    format!(
+      "upsert_watch_snapshot error: {e}"
+    )
+  })?;
+
+  Ok(())
+}
@@ -2,11 +2,24 @@
 //! link state snapshots.
 
 use chrono_tz::Tz;
-use sqlx::SqlitePool;
+use sqlx::query_builder::Separated;
+use sqlx::{
+  QueryBuilder,
+  Sqlite,
+  SqlitePool
+};
 
 use super::models::StateRowRecord;
 use crate::domain::link_state::LinkState;
-use crate::ports::repo::StateRow;
+use crate::ports::repo::{
+  NewState,
+  StateRow
+};
+
+/// Rows per multi-row statement; 17
+/// binds a row stays far below
+/// SQLite's variable limit.
+const CHUNK_ROWS: usize = 500;
 
 pub async fn latest_state(
   pool: &SqlitePool,
@@ -52,68 +65,120 @@ pub async fn insert_state(
   pool: &SqlitePool,
   state: &LinkState,
   recorded_at_ms: i64,
-  _zone: &Tz,
+  zone: &Tz,
   record_history: bool
 ) -> Result<(), String> {
-  if record_history {
-    sqlx::query(
-            r#"
-        INSERT INTO feed_state_history(
-          feed_id, recorded_at_ms, phase,
-          last_head_at_ms, last_head_status, last_head_error,
-          last_get_at_ms, last_get_status, last_get_error,
-          etag, last_modified_ms,
-          backoff_index, base_poll_seconds, next_action_at_ms,
-          jitter_seconds, note, consecutive_error_count
-        ) VALUES (
-          ?1, ?2, ?3,
-          ?4, ?5, ?6,
-          ?7, ?8, ?9,
-          ?10, ?11,
-          ?12, ?13, ?14,
-          ?15, ?16, ?17
-        )
-        "#,
+  insert_states(
+    pool,
+    &[NewState {
+      state: state.clone(),
+      recorded_at_ms,
+      record_history
+    }],
+    zone
+  )
+  .await
+}
+
+pub async fn insert_states(
+  pool: &SqlitePool,
+  states: &[NewState],
+  _zone: &Tz
+) -> Result<(), String> {
+  if states.is_empty() {
+    return Ok(());
+  }
+
+  let mut tx =
+    pool.begin().await.map_err(
+      |e| format!("tx begin: {e}")
+    )?;
+
+  let history: Vec<&NewState> = states
+    .iter()
+    .filter(|s| s.record_history)
+    .collect();
+
+  for chunk in
+    history.chunks(CHUNK_ROWS)
+  {
+    let mut builder =
+      QueryBuilder::<Sqlite>::new(
+        "INSERT INTO \
+         feed_state_history(feed_id, \
+         recorded_at_ms, phase, \
+         last_head_at_ms, \
+         last_head_status, \
+         last_head_error, \
+         last_get_at_ms, \
+         last_get_status, \
+         last_get_error, etag, \
+         last_modified_ms, \
+         backoff_index, \
+         base_poll_seconds, \
+         next_action_at_ms, \
+         jitter_seconds, note, \
+         consecutive_error_count) "
+      );
+
+    builder.push_values(
+      chunk,
+      |mut row, s| {
+        row
+          .push_bind(&s.state.feed_id)
+          .push_bind(s.recorded_at_ms);
+
+        push_state(&mut row, &s.state);
+      }
+    );
+
+    builder
+      .build()
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "insert_state history \
+           error: {e}"
         )
-        .bind(&state.feed_id)
-        .bind(recorded_at_ms)
-        .bind(format!("{:?}", state.phase))
-        .bind(state.last_head_at_ms)
-        .bind(state.last_head_status.map(|x| x as i64))
-        .bind(state.last_head_error.map(|e| format!("{:?}", e)))
-        .bind(state.last_get_at_ms)
-        .bind(state.last_get_status.map(|x| x as i64))
-        .bind(state.last_get_error.map(|e| format!("{:?}", e)))
-        .bind(&state.etag)
-        .bind(state.last_modified_ms)
-        .bind(state.backoff_index as i64)
-        .bind(state.base_poll_seconds as i64)
-        .bind(state.next_action_at_ms)
-        .bind(state.jitter_seconds)
-        .bind(&state.note)
-        .bind(state.consecutive_error_count as i64)
-        .execute(pool)
-        .await
-        .map_err(|e| format!("insert_state history error: {e}"))?;
+      })?;
   }
 
-  sqlx::query(
-        r#"
-      INSERT INTO feed_state_current(
-        feed_id, phase,
-        last_head_at_ms, last_head_status, last_head_error,
-        last_get_at_ms, last_get_status, last_get_error,
-        etag, last_modified_ms,
-        backoff_index, base_poll_seconds, next_action_at_ms,
-        jitter_seconds, note, consecutive_error_count
-      ) VALUES (
-        ?1, ?2,
-        ?3, ?4, ?5,
-        ?6, ?7, ?8,
-        ?9, ?10,
-        ?11, ?12, ?13,
-        ?14, ?15, ?16
-      )
+  let current =
+    NewState::latest_per_feed(states);
+
+  for chunk in
+    current.chunks(CHUNK_ROWS)
+  {
+    let mut builder =
+      QueryBuilder::<Sqlite>::new(
+        "INSERT INTO \
+         feed_state_current(feed_id, \
+         phase, last_head_at_ms, \
+         last_head_status, \
+         last_head_error, \
+         last_get_at_ms, \
+         last_get_status, \
+         last_get_error, etag, \
+         last_modified_ms, \
+         backoff_index, \
+         base_poll_seconds, \
+         next_action_at_ms, \
+         jitter_seconds, note, \
+         consecutive_error_count) "
+      );
+
+    builder.push_values(
+      chunk,
+      |mut row, s| {
+        row.push_bind(&s.state.feed_id);
+
+        push_state(&mut row, &s.state);
+      }
+    );
+
+    builder.push(
+      r#"
       ON CONFLICT(feed_id) DO UPDATE SET
         phase = excluded.phase,
         last_head_at_ms = excluded.last_head_at_ms,
@@ -130,27 +195,77 @@ pub async fn insert_state(
         jitter_seconds = excluded.jitter_seconds,
         note = excluded.note,
         consecutive_error_count = excluded.consecutive_error_count
-      "#,
+      "#
+    );
+
+    builder
+      .build()
+      .execute(&mut *tx)
+      .await
+      .map_err(|e| {
+        format!(
+          "insert_state current \
+           error: {e}"
+        )
+      })?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    format!("tx commit: {e}")
+  })
+}
+
+/// Binds the columns from `phase` on,
+/// shared by history and current rows.
+fn push_state<'q>(
+  row: &mut Separated<
+    '_,
+    'q,
+    Sqlite,
+    &'static str
+  >,
+  state: &'q LinkState
+) {
+  row
+    .push_bind(format!(
+      "{:?}",
+      state.phase
+    ))
+    .push_bind(state.last_head_at_ms)
+    .push_bind(
+      state
+        .last_head_status
+        .map(|x| x as i64)
+    )
+    .push_bind(
+      state
+        .last_head_error
+        .map(|e| format!("{e:?}"))
+    )
+    .push_bind(state.last_get_at_ms)
+    .push_bind(
+      state
+        .last_get_status
+        .map(|x| x as i64)
+    )
+    .push_bind(
+      state
+        .last_get_error
+        .map(|e| format!("{e:?}"))
+    )
+    .push_bind(&state.etag)
+    .push_bind(state.last_modified_ms)
+    .push_bind(
+      state.backoff_index as i64
+    )
+    .push_bind(
+      state.base_poll_seconds as i64
     )
-    .bind(&state.feed_id)
-    .bind(format!("{:?}", state.phase))
-    .bind(state.last_head_at_ms)
-    .bind(state.last_head_status.map(|x| x as i64))
-    .bind(state.last_head_error.map(|e| format!("{:?}", e)))
-    .bind(state.last_get_at_ms)
-    .bind(state.last_get_status.map(|x| x as i64))
-    .bind(state.last_get_error.map(|e| format!("{:?}", e)))
-    .bind(&state.etag)
-    .bind(state.last_modified_ms)
-    .bind(state.backoff_index as i64)
-    .bind(state.base_poll_seconds as i64)
-    .bind(state.next_action_at_ms)
-    .bind(state.jitter_seconds)
-    .bind(&state.note)
-    .bind(state.consecutive_error_count as i64)
-    .execute(pool)
-    .await
-    .map_err(|e| format!("insert_state current error: {e}"))?;
-
-  Ok(())
+    .push_bind(state.next_action_at_ms)
+    .push_bind(state.jitter_seconds)
+    .push_bind(&state.note)
+    .push_bind(
+      state.consecutive_error_count
+        as i64
+    );
 }
@@ -1,6 +1,7 @@
 //! Helpers for formatting epoch
 //! milliseconds into human-readable
-//! strings in a given timezone.
+//! strings in a given timezone, and
+//! parsing dates back.
 
 use chrono::{
   DateTime,
@@ -49,3 +50,46 @@ pub fn epoch_ms_to_iso(
     .with_timezone(zone)
     .to_rfc3339()
 }
+
+/// Parses an RFC 3339 timestamp or a
+/// `YYYY-MM-DD` date (midnight in
+/// `zone`) into epoch millis.
+pub fn parse_date_ms(
+  value: &str,
+  zone: &Tz
+) -> Result<i64, String> {
+  let value = value.trim();
+
+  if let Ok(dt) =
+    DateTime::parse_from_rfc3339(value)
+  {
+    return Ok(dt.timestamp_millis());
+  }
+
+  let date =
+    chrono::NaiveDate::parse_from_str(
+      value, "%Y-%m-%d"
+    )
+    .map_err(|_| {
+      format!(
+        "invalid date '{value}'; \
+         expected YYYY-MM-DD or RFC \
+         3339"
+      )
+    })?;
+
+  zone
+    .from_local_datetime(
+      &date.and_time(
+        chrono::NaiveTime::MIN
+      )
+    )
+    .earliest()
+    .map(|dt| dt.timestamp_millis())
+    .ok_or_else(|| {
+      format!(
+        "date '{value}' does not \
+         exist in {zone}"
+      )
+    })
+}
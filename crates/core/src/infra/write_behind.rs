@@ -0,0 +1,977 @@
+//! Write-behind batching for the
+//! fetcher's hottest writes. Fetch
+//! events and state snapshots from
+//! concurrent feed actions are queued
+//! and written together with the
+//! repo's multi-row inserts, one
+//! transaction per batch. A batch is
+//! flushed once it holds `max_batch`
+//! writes or `flush_ms` after its first
+//! write arrived. Callers still wait
+//! for their batch to commit, so
+//! errors reach them and a snapshot is
+//! readable once `insert_state`
+//! returns.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{
+  AtomicU64,
+  Ordering
+};
+use std::time::{
+  Duration,
+  Instant
+};
+
+use chrono_tz::Tz;
+use tokio::sync::{
+  mpsc,
+  oneshot
+};
+
+use crate::domain::link_state::LinkState;
+use crate::domain::model::{
+  ErrorKind,
+  FeedConfig
+};
+use crate::domain::registry::{
+  RegistryFeed,
+  RegistryStatus
+};
+use crate::domain::retention::{
+  RetentionLimit,
+  RetentionTable
+};
+use crate::domain::write_behind::WriteBehindConfig;
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
+use crate::infra::metrics;
+use crate::ports::clock::Clock;
+use crate::ports::repo::{
+  ArchiveRefRow,
+  ArchivedFeedRow,
+  ErrorFeedRow,
+  FeedRedirectRow,
+  FetchEventRow,
+  NewEvent,
+  NewState,
+  PayloadSummaryRow,
+  Repo,
+  StateHistoryRow,
+  StateRow,
+  StoredItemRow
+};
+
+type Ack =
+  oneshot::Sender<Result<(), String>>;
+
+enum Write {
+  Event(NewEvent),
+  State(NewState)
+}
+
+struct Queued {
+  write: Write,
+  zone:  Tz,
+  done:  Ack
+}
+
+/// Batches flushed so far and the
+/// writes they held.
+#[derive(
+  Debug, Clone, Copy, Default,
+)]
+pub struct BatchStats {
+  pub batches: u64,
+  pub writes:  u64,
+  pub largest: u64
+}
+
+impl BatchStats {
+  pub fn mean(&self) -> f64 {
+    self.writes as f64
+      / self.batches.max(1) as f64
+  }
+}
+
+#[derive(Default)]
+struct BatchCounters {
+  batches: AtomicU64,
+  writes:  AtomicU64,
+  largest: AtomicU64
+}
+
+impl BatchCounters {
+  fn record(
+    &self,
+    size: usize
+  ) {
+    let size = size as u64;
+
+    self
+      .batches
+      .fetch_add(1, Ordering::Relaxed);
+    self.writes.fetch_add(
+      size,
+      Ordering::Relaxed
+    );
+    self.largest.fetch_max(
+      size,
+      Ordering::Relaxed
+    );
+  }
+}
+
+/// Wraps a repo and batches its
+/// `insert_event` and `insert_state`
+/// calls; everything else goes
+/// straight to the inner repo.
+pub struct WriteBehindRepo {
+  inner:    Arc<dyn Repo>,
+  clock:    Arc<dyn Clock>,
+  queue:    mpsc::Sender<Queued>,
+  counters: Arc<BatchCounters>
+}
+
+impl WriteBehindRepo {
+  /// Spawns the flush task; it stops
+  /// once the repo is dropped and the
+  /// queue is drained. The clock stamps
+  /// event times, as the SQL backends
+  /// do with the wall clock.
+  pub fn new(
+    inner: Arc<dyn Repo>,
+    cfg: &WriteBehindConfig,
+    clock: Arc<dyn Clock>
+  ) -> Self {
+    let (queue, rx) =
+      mpsc::channel(cfg.max_batch * 2);
+
+    let counters = Arc::new(
+      BatchCounters::default()
+    );
+
+    tokio::spawn(run(
+      inner.clone(),
+      rx,
+      cfg.max_batch,
+      Duration::from_millis(
+        cfg.flush_ms
+      ),
+      counters.clone()
+    ));
+
+    Self {
+      inner,
+      clock,
+      queue,
+      counters
+    }
+  }
+
+  pub fn stats(&self) -> BatchStats {
+    BatchStats {
+      batches: self
+        .counters
+        .batches
+        .load(Ordering::Relaxed),
+      writes:  self
+        .counters
+        .writes
+        .load(Ordering::Relaxed),
+      largest: self
+        .counters
+        .largest
+        .load(Ordering::Relaxed)
+    }
+  }
+
+  async fn enqueue(
+    &self,
+    write: Write,
+    zone: &Tz
+  ) -> Result<(), String> {
+    let (done, ack) =
+      oneshot::channel();
+
+    self
+      .queue
+      .send(Queued {
+        write,
+        zone: *zone,
+        done
+      })
+      .await
+      .map_err(|_| {
+        "write-behind queue closed"
+          .to_string()
+      })?;
+
+    ack.await.map_err(|_| {
+      "write-behind flush dropped"
+        .to_string()
+    })?
+  }
+}
+
+/// Collects writes into batches until
+/// the queue closes. A batch closes at
+/// `max_batch` writes or `flush_after`
+/// past its first write. Writes queued
+/// during a flush form the next batch.
+async fn run(
+  inner: Arc<dyn Repo>,
+  mut rx: mpsc::Receiver<Queued>,
+  max_batch: usize,
+  flush_after: Duration,
+  counters: Arc<BatchCounters>
+) {
+  while let Some(first) =
+    rx.recv().await
+  {
+    let deadline =
+      tokio::time::Instant::now()
+        + flush_after;
+
+    let mut batch = vec![first];
+
+    while batch.len() < max_batch {
+      match tokio::time::timeout_at(
+        deadline,
+        rx.recv()
+      )
+      .await
+      {
+        | Ok(Some(queued)) => {
+          batch.push(queued)
+        }
+        | Ok(None) | Err(_) => break
+      }
+    }
+
+    counters.record(batch.len());
+
+    flush(inner.as_ref(), batch).await;
+  }
+}
+
+/// Writes events before snapshots; a
+/// feed's snapshot is only queued once
+/// its event was acknowledged, so the
+/// order per feed holds.
+async fn flush(
+  inner: &dyn Repo,
+  batch: Vec<Queued>
+) {
+  let mut events = Vec::new();
+  let mut states = Vec::new();
+
+  for queued in batch {
+    match queued.write {
+      | Write::Event(e) => {
+        events.push((
+          queued.zone,
+          e,
+          queued.done
+        ))
+      }
+      | Write::State(s) => {
+        states.push((
+          queued.zone,
+          s,
+          queued.done
+        ))
+      }
+    }
+  }
+
+  for (zone, rows, acks) in
+    by_zone(events)
+  {
+    let started = Instant::now();
+
+    let res = inner
+      .insert_events(&rows, &zone)
+      .await;
+
+    metrics::record_db_time(
+      "insert_events_batch",
+      started.elapsed().as_millis()
+        as u64
+    );
+
+    acknowledge(acks, &res);
+  }
+
+  for (zone, rows, acks) in
+    by_zone(states)
+  {
+    let started = Instant::now();
+
+    let res = inner
+      .insert_states(&rows, &zone)
+      .await;
+
+    metrics::record_db_time(
+      "insert_states_batch",
+      started.elapsed().as_millis()
+        as u64
+    );
+
+    acknowledge(acks, &res);
+  }
+}
+
+/// Splits writes by time zone; callers
+/// pass the configured zone, so there
+/// is normally one group.
+fn by_zone<T>(
+  writes: Vec<(Tz, T, Ack)>
+) -> Vec<(Tz, Vec<T>, Vec<Ack>)> {
+  let mut groups: Vec<(
+    Tz,
+    Vec<T>,
+    Vec<Ack>
+  )> = Vec::new();
+
+  for (zone, row, ack) in writes {
+    match groups
+      .iter_mut()
+      .find(|g| g.0 == zone)
+    {
+      | Some(group) => {
+        group.1.push(row);
+        group.2.push(ack);
+      }
+      | None => {
+        groups.push((
+          zone,
+          vec![row],
+          vec![ack]
+        ))
+      }
+    }
+  }
+
+  groups
+}
+
+fn acknowledge(
+  acks: Vec<Ack>,
+  res: &Result<(), String>
+) {
+  for ack in acks {
+    // The caller may have given up.
+    let _ = ack.send(res.clone());
+  }
+}
+
+#[async_trait::async_trait]
+impl Repo for WriteBehindRepo {
+  async fn insert_state(
+    &self,
+    state: &LinkState,
+    recorded_at_ms: i64,
+    zone: &Tz,
+    record_history: bool
+  ) -> Result<(), String> {
+    self
+      .enqueue(
+        Write::State(NewState {
+          state: state.clone(),
+          recorded_at_ms,
+          record_history
+        }),
+        zone
+      )
+      .await
+  }
+
+  async fn insert_event(
+    &self,
+    feed_id: &str,
+    method: &str,
+    status: Option<i64>,
+    error_kind: Option<ErrorKind>,
+    latency_ms: Option<i64>,
+    backoff_index: i64,
+    scheduled_next_action_at_ms: i64,
+    debug: Option<&str>,
+    zone: &Tz
+  ) -> Result<(), String> {
+    let event_time_ms =
+      self.clock.now_epoch_ms().await;
+
+    self
+      .enqueue(
+        Write::Event(NewEvent {
+          feed_id: feed_id.to_string(),
+          event_time_ms,
+          method: method.to_string(),
+          status,
+          error_kind,
+          latency_ms,
+          backoff_index,
+          scheduled_next_action_at_ms,
+          debug: debug
+            .map(str::to_string)
+        }),
+        zone
+      )
+      .await
+  }
+
+  async fn migrate(
+    &self,
+    zone: &Tz,
+    default_poll_seconds: u64
+  ) -> Result<(), String> {
+    self
+      .inner
+      .migrate(
+        zone,
+        default_poll_seconds
+      )
+      .await
+  }
+
+  async fn upsert_feeds_bulk(
+    &self,
+    feeds: Vec<FeedConfig>,
+    chunk_size: usize,
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .upsert_feeds_bulk(
+        feeds, chunk_size, zone
+      )
+      .await
+  }
+
+  async fn upsert_categories(
+    &self,
+    categories: Vec<String>,
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .upsert_categories(
+        categories, zone
+      )
+      .await
+  }
+
+  async fn latest_state(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<StateRow>, String>
+  {
+    self
+      .inner
+      .latest_state(feed_id)
+      .await
+  }
+
+  async fn due_feeds_for_category(
+    &self,
+    category: &str,
+    now_ms: i64,
+    limit: i64
+  ) -> Result<Vec<FeedConfig>, String>
+  {
+    self
+      .inner
+      .due_feeds_for_category(
+        category, now_ms, limit
+      )
+      .await
+  }
+
+  async fn insert_events(
+    &self,
+    events: &[NewEvent],
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .insert_events(events, zone)
+      .await
+  }
+
+  async fn insert_states(
+    &self,
+    states: &[NewState],
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .insert_states(states, zone)
+      .await
+  }
+
+  async fn insert_payload_with_items(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>,
+    content_hash: Option<&str>,
+    parsed: &ParsedFeed,
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .insert_payload_with_items(
+        feed_id,
+        fetched_at_ms,
+        etag,
+        last_modified_ms,
+        content_hash,
+        parsed,
+        zone
+      )
+      .await
+  }
+
+  async fn known_item_guids(
+    &self,
+    feed_id: &str,
+    guids: &[String]
+  ) -> Result<HashSet<String>, String>
+  {
+    self
+      .inner
+      .known_item_guids(feed_id, guids)
+      .await
+  }
+
+  async fn mark_feed_error(
+    &self,
+    feed_id: &str,
+    error_kind: Option<ErrorKind>,
+    status: Option<i64>,
+    error_count: i64,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .mark_feed_error(
+        feed_id,
+        error_kind,
+        status,
+        error_count,
+        observed_at_ms,
+        zone
+      )
+      .await
+  }
+
+  async fn latest_cookie_header(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>
+  {
+    self
+      .inner
+      .latest_cookie_header(feed_id)
+      .await
+  }
+
+  async fn upsert_cookie_header(
+    &self,
+    feed_id: &str,
+    cookie_header: &str,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .upsert_cookie_header(
+        feed_id,
+        cookie_header,
+        observed_at_ms,
+        zone
+      )
+      .await
+  }
+
+  async fn latest_watch_snapshot(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>
+  {
+    self
+      .inner
+      .latest_watch_snapshot(feed_id)
+      .await
+  }
+
+  async fn upsert_watch_snapshot(
+    &self,
+    feed_id: &str,
+    content_text: &str,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .upsert_watch_snapshot(
+        feed_id,
+        content_text,
+        observed_at_ms,
+        zone
+      )
+      .await
+  }
+
+  async fn record_feed_redirect(
+    &self,
+    feed_id: &str,
+    from_url: &str,
+    suggested_url: &str,
+    status: i64,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .record_feed_redirect(
+        feed_id,
+        from_url,
+        suggested_url,
+        status,
+        observed_at_ms,
+        zone
+      )
+      .await
+  }
+
+  async fn clear_feed_redirect(
+    &self,
+    feed_id: &str
+  ) -> Result<(), String> {
+    self
+      .inner
+      .clear_feed_redirect(feed_id)
+      .await
+  }
+
+  async fn feed_redirects(
+    &self,
+    min_hits: i64
+  ) -> Result<
+    Vec<FeedRedirectRow>,
+    String
+  > {
+    self
+      .inner
+      .feed_redirects(min_hits)
+      .await
+  }
+
+  async fn archived_feeds(
+    &self
+  ) -> Result<
+    Vec<ArchivedFeedRow>,
+    String
+  > {
+    self.inner.archived_feeds().await
+  }
+
+  async fn feed_categories(
+    &self
+  ) -> Result<
+    Vec<(String, String)>,
+    String
+  > {
+    self.inner.feed_categories().await
+  }
+
+  async fn prune_batch(
+    &self,
+    table: RetentionTable,
+    feed_id: &str,
+    limit: RetentionLimit,
+    now_ms: i64,
+    batch_size: u64,
+    server_schema: &str
+  ) -> Result<u64, String> {
+    self
+      .inner
+      .prune_batch(
+        table,
+        feed_id,
+        limit,
+        now_ms,
+        batch_size,
+        server_schema
+      )
+      .await
+  }
+
+  async fn count_prunable(
+    &self,
+    table: RetentionTable,
+    feed_id: &str,
+    limit: RetentionLimit,
+    now_ms: i64,
+    server_schema: &str
+  ) -> Result<u64, String> {
+    self
+      .inner
+      .count_prunable(
+        table,
+        feed_id,
+        limit,
+        now_ms,
+        server_schema
+      )
+      .await
+  }
+
+  async fn insert_archive_ref(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    content_hash: &str
+  ) -> Result<(), String> {
+    self
+      .inner
+      .insert_archive_ref(
+        feed_id,
+        fetched_at_ms,
+        content_hash
+      )
+      .await
+  }
+
+  async fn archive_refs_since(
+    &self,
+    feed_id: &str,
+    since_ms: i64
+  ) -> Result<Vec<ArchiveRefRow>, String>
+  {
+    self
+      .inner
+      .archive_refs_since(
+        feed_id, since_ms
+      )
+      .await
+  }
+
+  async fn insert_items(
+    &self,
+    payload_id: i64,
+    feed_id: &str,
+    items: &[FeedItem],
+    zone: &Tz
+  ) -> Result<u64, String> {
+    self
+      .inner
+      .insert_items(
+        payload_id, feed_id, items,
+        zone
+      )
+      .await
+  }
+
+  async fn archived_blob_exists(
+    &self,
+    content_hash: &str
+  ) -> Result<bool, String> {
+    self
+      .inner
+      .archived_blob_exists(
+        content_hash
+      )
+      .await
+  }
+
+  async fn insert_archived_blob(
+    &self,
+    content_hash: &str,
+    stored_at_ms: i64,
+    data: &[u8]
+  ) -> Result<(), String> {
+    self
+      .inner
+      .insert_archived_blob(
+        content_hash,
+        stored_at_ms,
+        data
+      )
+      .await
+  }
+
+  async fn archived_blob(
+    &self,
+    content_hash: &str
+  ) -> Result<Option<Vec<u8>>, String>
+  {
+    self
+      .inner
+      .archived_blob(content_hash)
+      .await
+  }
+
+  async fn orphan_archived_blobs(
+    &self,
+    limit: u64
+  ) -> Result<Vec<String>, String> {
+    self
+      .inner
+      .orphan_archived_blobs(limit)
+      .await
+  }
+
+  async fn delete_archived_blobs(
+    &self,
+    content_hashes: &[String]
+  ) -> Result<u64, String> {
+    self
+      .inner
+      .delete_archived_blobs(
+        content_hashes
+      )
+      .await
+  }
+
+  async fn referenced_content_hashes(
+    &self,
+    content_hashes: &[String]
+  ) -> Result<HashSet<String>, String>
+  {
+    self
+      .inner
+      .referenced_content_hashes(
+        content_hashes
+      )
+      .await
+  }
+
+  async fn recent_events(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<Vec<FetchEventRow>, String>
+  {
+    self
+      .inner
+      .recent_events(feed_id, limit)
+      .await
+  }
+
+  async fn state_history(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<
+    Vec<StateHistoryRow>,
+    String
+  > {
+    self
+      .inner
+      .state_history(feed_id, limit)
+      .await
+  }
+
+  async fn error_feeds(
+    &self
+  ) -> Result<Vec<ErrorFeedRow>, String>
+  {
+    self.inner.error_feeds().await
+  }
+
+  async fn payload_timeline(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<
+    Vec<PayloadSummaryRow>,
+    String
+  > {
+    self
+      .inner
+      .payload_timeline(feed_id, limit)
+      .await
+  }
+
+  async fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Result<Vec<StoredItemRow>, String>
+  {
+    self
+      .inner
+      .payload_items(payload_id)
+      .await
+  }
+
+  async fn registry_feeds(
+    &self,
+    status: Option<RegistryStatus>
+  ) -> Result<Vec<RegistryFeed>, String>
+  {
+    self
+      .inner
+      .registry_feeds(status)
+      .await
+  }
+
+  async fn registry_feed(
+    &self,
+    id_or_url: &str
+  ) -> Result<
+    Option<RegistryFeed>,
+    String
+  > {
+    self
+      .inner
+      .registry_feed(id_or_url)
+      .await
+  }
+
+  async fn count_registry_feeds(
+    &self,
+    added_by: i64
+  ) -> Result<i64, String> {
+    self
+      .inner
+      .count_registry_feeds(added_by)
+      .await
+  }
+
+  async fn insert_registry_feed(
+    &self,
+    feed: &RegistryFeed,
+    zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .inner
+      .insert_registry_feed(feed, zone)
+      .await
+  }
+
+  async fn review_registry_feed(
+    &self,
+    id: &str,
+    status: RegistryStatus,
+    reviewed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<bool, String> {
+    self
+      .inner
+      .review_registry_feed(
+        id,
+        status,
+        reviewed_at_ms,
+        zone
+      )
+      .await
+  }
+}
@@ -3,3 +3,5 @@ pub mod domain;
 pub mod feed;
 pub mod infra;
 pub mod ports;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
@@ -1,5 +1,8 @@
 //! HTTP abstraction returning
-//! lightweight HEAD/GET results.
+//! lightweight HEAD/GET results, plus
+//! POST for outgoing webhooks.
+//! `domain` selects the per-domain
+//! client profile.
 
 use std::collections::HashMap;
 
@@ -12,6 +15,7 @@ use crate::domain::model::{
 pub trait Http: Send + Sync {
   async fn head(
     &self,
+    domain: &str,
     url: &str,
     cookie_header: Option<&str>,
     extra_headers: Option<
@@ -21,10 +25,22 @@ pub trait Http: Send + Sync {
 
   async fn get(
     &self,
+    domain: &str,
     url: &str,
     cookie_header: Option<&str>,
     extra_headers: Option<
       &HashMap<String, String>
     >
   ) -> GetResult;
+
+  /// POSTs `body` with `headers`. The
+  /// response reads as for a GET;
+  /// redirects are not followed.
+  async fn post(
+    &self,
+    domain: &str,
+    url: &str,
+    headers: &HashMap<String, String>,
+    body: Vec<u8>
+  ) -> GetResult;
 }
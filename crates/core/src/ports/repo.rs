@@ -2,6 +2,11 @@
 //! definitions, state snapshots, fetch
 //! events, and parsed payloads.
 
+use std::collections::{
+  HashMap,
+  HashSet
+};
+
 use chrono_tz::Tz;
 
 use crate::domain::link_state::LinkState;
@@ -9,7 +14,18 @@ use crate::domain::model::{
   ErrorKind,
   FeedConfig
 };
-use crate::feed::parser::ParsedFeed;
+use crate::domain::registry::{
+  RegistryFeed,
+  RegistryStatus
+};
+use crate::domain::retention::{
+  RetentionLimit,
+  RetentionTable
+};
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
 
 #[derive(Debug, Clone)]
 
@@ -32,6 +48,162 @@ pub struct StateRow {
   pub consecutive_error_count: i64
 }
 
+/// A permanent redirect seen on the
+/// feed's current URL.
+#[derive(Debug, Clone)]
+
+pub struct FeedRedirectRow {
+  pub feed_id:          String,
+  pub url:              String,
+  pub suggested_url:    String,
+  pub status:           i64,
+  pub hit_count:        i64,
+  pub first_seen_at_ms: i64,
+  pub last_seen_at_ms:  i64
+}
+
+/// A feed parked after 410 Gone.
+#[derive(Debug, Clone)]
+
+pub struct ArchivedFeedRow {
+  pub feed_id:        String,
+  pub url:            String,
+  pub archived_at_ms: Option<i64>
+}
+
+/// An archived body of a feed, with
+/// the payload parsed from it if any.
+#[derive(Debug, Clone)]
+
+pub struct ArchiveRefRow {
+  pub fetched_at_ms: i64,
+  pub content_hash:  String,
+  pub payload_id:    Option<i64>
+}
+
+/// One HEAD or GET attempt.
+#[derive(Debug, Clone)]
+
+pub struct FetchEventRow {
+  pub id: i64,
+  pub feed_id: String,
+  pub event_time_ms: i64,
+  pub method: String,
+  pub status: Option<i64>,
+  pub error_kind: Option<String>,
+  pub latency_ms: Option<i64>,
+  pub backoff_index: i64,
+  pub scheduled_next_action_at_ms: i64,
+  pub debug: Option<String>
+}
+
+/// A state snapshot from
+/// `feed_state_history`.
+#[derive(Debug, Clone)]
+
+pub struct StateHistoryRow {
+  pub recorded_at_ms: i64,
+  pub state:          StateRow
+}
+
+/// A feed parked after too many
+/// consecutive errors.
+#[derive(Debug, Clone)]
+
+pub struct ErrorFeedRow {
+  pub feed_id:           String,
+  pub error_count:       i64,
+  pub last_error_kind:   Option<String>,
+  pub last_error_status: Option<i64>,
+  pub last_error_at_ms:  i64,
+  pub note:              Option<String>
+}
+
+/// A stored payload with the number of
+/// items attached to it.
+#[derive(Debug, Clone)]
+
+pub struct PayloadSummaryRow {
+  pub payload_id:       i64,
+  pub feed_id:          String,
+  pub fetched_at_ms:    i64,
+  pub etag:             Option<String>,
+  pub last_modified_ms: Option<i64>,
+  pub content_hash:     Option<String>,
+  pub title:            Option<String>,
+  pub item_count:       i64
+}
+
+/// An item as stored under a payload.
+#[derive(Debug, Clone)]
+
+pub struct StoredItemRow {
+  pub id:              i64,
+  pub payload_id:      i64,
+  pub feed_id:         String,
+  pub title:           Option<String>,
+  pub link:            Option<String>,
+  pub guid:            Option<String>,
+  pub published_at_ms: Option<i64>,
+  pub category:        Option<String>,
+  pub summary:         Option<String>
+}
+
+/// A fetch event for `insert_events`.
+#[derive(Debug, Clone)]
+
+pub struct NewEvent {
+  pub feed_id: String,
+  pub event_time_ms: i64,
+  pub method: String,
+  pub status: Option<i64>,
+  pub error_kind: Option<ErrorKind>,
+  pub latency_ms: Option<i64>,
+  pub backoff_index: i64,
+  pub scheduled_next_action_at_ms: i64,
+  pub debug: Option<String>
+}
+
+/// A state snapshot for
+/// `insert_states`.
+#[derive(Debug, Clone)]
+
+pub struct NewState {
+  pub state:          LinkState,
+  pub recorded_at_ms: i64,
+  pub record_history: bool
+}
+
+impl NewState {
+  /// The last snapshot of each feed in
+  /// `states`, in order of each feed's
+  /// first appearance.
+  pub fn latest_per_feed(
+    states: &[NewState]
+  ) -> Vec<&NewState> {
+    let mut index =
+      HashMap::<&str, usize>::new();
+    let mut latest = Vec::new();
+
+    for s in states {
+      match index
+        .get(s.state.feed_id.as_str())
+      {
+        | Some(&i) => latest[i] = s,
+        | None => {
+          index.insert(
+            &s.state.feed_id,
+            latest.len()
+          );
+          latest.push(s);
+        }
+      }
+    }
+
+    latest
+  }
+}
+
 #[async_trait::async_trait]
 #[allow(clippy::too_many_arguments)]
 pub trait Repo: Send + Sync {
@@ -87,6 +259,24 @@ pub trait Repo: Send + Sync {
     zone: &Tz
   ) -> Result<(), String>;
 
+  /// Inserts events with multi-row
+  /// statements in one transaction.
+  async fn insert_events(
+    &self,
+    events: &[NewEvent],
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  /// Writes snapshots in one
+  /// transaction; when a feed appears
+  /// more than once the last snapshot
+  /// becomes its current state.
+  async fn insert_states(
+    &self,
+    states: &[NewState],
+    zone: &Tz
+  ) -> Result<(), String>;
+
   async fn insert_payload_with_items(
     &self,
     feed_id: &str,
@@ -98,6 +288,12 @@ pub trait Repo: Send + Sync {
     zone: &Tz
   ) -> Result<(), String>;
 
+  async fn known_item_guids(
+    &self,
+    feed_id: &str,
+    guids: &[String]
+  ) -> Result<HashSet<String>, String>;
+
   async fn mark_feed_error(
     &self,
     feed_id: &str,
@@ -120,4 +316,240 @@ pub trait Repo: Send + Sync {
     observed_at_ms: i64,
     zone: &Tz
   ) -> Result<(), String>;
+
+  async fn latest_watch_snapshot(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>;
+
+  async fn upsert_watch_snapshot(
+    &self,
+    feed_id: &str,
+    content_text: &str,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  /// Counts a permanent redirect from
+  /// `from_url`; the count restarts
+  /// when the suggested URL changes.
+  async fn record_feed_redirect(
+    &self,
+    feed_id: &str,
+    from_url: &str,
+    suggested_url: &str,
+    status: i64,
+    observed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  async fn clear_feed_redirect(
+    &self,
+    feed_id: &str
+  ) -> Result<(), String>;
+
+  async fn feed_redirects(
+    &self,
+    min_hits: i64
+  ) -> Result<
+    Vec<FeedRedirectRow>,
+    String
+  >;
+
+  async fn archived_feeds(
+    &self
+  ) -> Result<
+    Vec<ArchivedFeedRow>,
+    String
+  >;
+
+  /// Stored feed ids with their
+  /// category, for the retention job
+  /// and the registry sync.
+  async fn feed_categories(
+    &self
+  ) -> Result<
+    Vec<(String, String)>,
+    String
+  >;
+
+  /// Deletes up to `batch_size` rows of
+  /// `table` for one feed that fall
+  /// outside `limit`. Items unread by
+  /// any subscriber in the server
+  /// schema, items any user starred and
+  /// payloads that still have items are
+  /// kept.
+  async fn prune_batch(
+    &self,
+    table: RetentionTable,
+    feed_id: &str,
+    limit: RetentionLimit,
+    now_ms: i64,
+    batch_size: u64,
+    server_schema: &str
+  ) -> Result<u64, String>;
+
+  /// Rows `prune_batch` would delete,
+  /// ignoring the batch size.
+  async fn count_prunable(
+    &self,
+    table: RetentionTable,
+    feed_id: &str,
+    limit: RetentionLimit,
+    now_ms: i64,
+    server_schema: &str
+  ) -> Result<u64, String>;
+
+  /// Indexes a body archived under
+  /// `content_hash` for one fetch.
+  async fn insert_archive_ref(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    content_hash: &str
+  ) -> Result<(), String>;
+
+  /// Archived bodies of a feed fetched
+  /// at or after `since_ms`, oldest
+  /// first.
+  async fn archive_refs_since(
+    &self,
+    feed_id: &str,
+    since_ms: i64
+  ) -> Result<Vec<ArchiveRefRow>, String>;
+
+  /// Adds items to an existing
+  /// payload; used when reparsing
+  /// archived bodies.
+  async fn insert_items(
+    &self,
+    payload_id: i64,
+    feed_id: &str,
+    items: &[FeedItem],
+    zone: &Tz
+  ) -> Result<u64, String>;
+
+  async fn archived_blob_exists(
+    &self,
+    content_hash: &str
+  ) -> Result<bool, String>;
+
+  /// Stores a compressed archive blob;
+  /// an existing hash is left as is.
+  async fn insert_archived_blob(
+    &self,
+    content_hash: &str,
+    stored_at_ms: i64,
+    data: &[u8]
+  ) -> Result<(), String>;
+
+  async fn archived_blob(
+    &self,
+    content_hash: &str
+  ) -> Result<Option<Vec<u8>>, String>;
+
+  /// Archive blobs no payload or
+  /// archive index row references any
+  /// more.
+  async fn orphan_archived_blobs(
+    &self,
+    limit: u64
+  ) -> Result<Vec<String>, String>;
+
+  async fn delete_archived_blobs(
+    &self,
+    content_hashes: &[String]
+  ) -> Result<u64, String>;
+
+  /// The subset of `content_hashes`
+  /// still referenced by a payload or
+  /// an archive index row.
+  async fn referenced_content_hashes(
+    &self,
+    content_hashes: &[String]
+  ) -> Result<HashSet<String>, String>;
+
+  /// Newest fetch events of a feed,
+  /// newest first.
+  async fn recent_events(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<Vec<FetchEventRow>, String>;
+
+  /// Newest state snapshots of a feed,
+  /// newest first.
+  async fn state_history(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<
+    Vec<StateHistoryRow>,
+    String
+  >;
+
+  async fn error_feeds(
+    &self
+  ) -> Result<Vec<ErrorFeedRow>, String>;
+
+  /// Newest payloads of a feed with
+  /// their item counts, newest first.
+  async fn payload_timeline(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<
+    Vec<PayloadSummaryRow>,
+    String
+  >;
+
+  async fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Result<Vec<StoredItemRow>, String>;
+
+  /// Registry feeds with the given
+  /// status (all without one), oldest
+  /// first.
+  async fn registry_feeds(
+    &self,
+    status: Option<RegistryStatus>
+  ) -> Result<Vec<RegistryFeed>, String>;
+
+  /// The registry feed with this id or
+  /// URL, if any.
+  async fn registry_feed(
+    &self,
+    id_or_url: &str
+  ) -> Result<
+    Option<RegistryFeed>,
+    String
+  >;
+
+  /// How many registry feeds the user
+  /// added that were not rejected.
+  async fn count_registry_feeds(
+    &self,
+    added_by: i64
+  ) -> Result<i64, String>;
+
+  /// Adds a feed to the registry; fails
+  /// if its id or URL is taken.
+  async fn insert_registry_feed(
+    &self,
+    feed: &RegistryFeed,
+    zone: &Tz
+  ) -> Result<(), String>;
+
+  /// Approves or rejects a pending
+  /// registry feed; false if there is
+  /// no such pending feed.
+  async fn review_registry_feed(
+    &self,
+    id: &str,
+    status: RegistryStatus,
+    reviewed_at_ms: i64,
+    zone: &Tz
+  ) -> Result<bool, String>;
 }
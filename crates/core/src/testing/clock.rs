@@ -0,0 +1,95 @@
+//! Deterministic `Clock` and
+//! `RandomSource` implementations.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::ports::clock::Clock;
+use crate::ports::random::RandomSource;
+
+/// Epoch clock that starts at
+/// `start_ms` and advances with tokio's
+/// clock; under paused time it moves
+/// only when the runtime sleeps.
+#[derive(Debug)]
+pub struct VirtualClock {
+  start_ms: i64,
+  origin:   Instant
+}
+
+impl VirtualClock {
+  pub fn new(start_ms: i64) -> Self {
+    Self {
+      start_ms,
+      origin: Instant::now()
+    }
+  }
+
+  pub fn start_ms(&self) -> i64 {
+    self.start_ms
+  }
+
+  /// Time since the clock was created.
+  pub fn elapsed(&self) -> Duration {
+    self.origin.elapsed()
+  }
+
+  pub fn now_ms(&self) -> i64 {
+    self.start_ms
+      + self.elapsed().as_millis()
+        as i64
+  }
+}
+
+#[async_trait::async_trait]
+
+impl Clock for VirtualClock {
+  async fn now_epoch_ms(&self) -> i64 {
+    self.now_ms()
+  }
+}
+
+/// Reproducible `[0, 1)` sequence
+/// (SplitMix64).
+#[derive(Debug)]
+pub struct SeededRandom {
+  state: Mutex<u64>
+}
+
+impl SeededRandom {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      state: Mutex::new(seed)
+    }
+  }
+}
+
+#[async_trait::async_trait]
+
+impl RandomSource for SeededRandom {
+  async fn next_f64(&self) -> f64 {
+    let mut state = self
+      .state
+      .lock()
+      .expect("rng lock");
+
+    *state = state.wrapping_add(
+      0x9e37_79b9_7f4a_7c15
+    );
+
+    let mut z = *state;
+
+    z = (z ^ (z >> 30)).wrapping_mul(
+      0xbf58_476d_1ce4_e5b9
+    );
+    z = (z ^ (z >> 27)).wrapping_mul(
+      0x94d0_49bb_1331_11eb
+    );
+    z ^= z >> 31;
+
+    (z >> 11) as f64
+      / (1u64 << 53) as f64
+  }
+}
@@ -0,0 +1,147 @@
+//! Configuration for simulations:
+//! config-file defaults, no history
+//! sampling, no archive, retention,
+//! backups, write-behind batching or
+//! registry polling, UTC.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::domain::archive::{
+  ArchiveConfig,
+  ArchiveStore
+};
+use crate::domain::backup::BackupConfig;
+use crate::domain::model::{
+  AppConfig,
+  AppMode,
+  DomainConfig,
+  MetricsConfig,
+  PostgresConfig,
+  SqlDialect
+};
+use crate::domain::registry::RegistryConfig;
+use crate::domain::retention::{
+  RetentionConfig,
+  RetentionPolicy
+};
+use crate::domain::write_behind::WriteBehindConfig;
+use crate::infra::config::defaults::*;
+
+/// Editable defaults; add per-domain
+/// limits with
+/// [`with_domain_limit`].
+pub fn sim_config() -> AppConfig {
+  AppConfig {
+    db_dialect: SqlDialect::Sqlite,
+    sqlite_path: PathBuf::from(
+      default_sqlite_path()
+    ),
+    postgres: PostgresConfig {
+      user:     default_pg_user(),
+      password: default_pg_password(),
+      host:     default_pg_host(),
+      port:     default_pg_port(),
+      database: default_pg_database(),
+      schema:   default_pg_schema()
+    },
+    default_poll_seconds: 300,
+    max_poll_seconds: 3600,
+    error_backoff_base_seconds: 30,
+    max_error_backoff_seconds: 7200,
+    max_consecutive_errors:
+      default_max_consecutive_errors(),
+    immediate_error_statuses:
+      default_immediate_error_statuses(),
+    jitter_fraction: 0.2,
+    global_max_concurrent_requests:
+      None,
+    user_agent: "pulsewire-sim"
+      .to_string(),
+    log_level: "info".to_string(),
+    log_file_enabled: false,
+    log_file_level:
+      default_log_file_level(),
+    log_file_directory: PathBuf::from(
+      default_log_file_directory()
+    ),
+    log_file_name:
+      default_log_file_name(),
+    log_file_rotation:
+      default_log_file_rotation(),
+    log_tick_warn_seconds: 0,
+    log_feed_timing_enabled: false,
+    log_feed_timing_domains: Vec::new(),
+    log_feed_timing_warn_ms:
+      default_log_feed_timing_warn_ms(),
+    log_feed_timing_log_all: false,
+    metrics: MetricsConfig {
+      enabled: false,
+      bind:    default_metrics_bind()
+    },
+    mode: AppMode::Dev,
+    timezone: chrono_tz::UTC,
+    domains: HashMap::new(),
+    state_history_sample_rate: 1.0,
+    retention: RetentionConfig {
+      enabled:          false,
+      interval_seconds: 3600,
+      batch_size:       1000,
+      server_schema:    "server"
+        .to_string(),
+      defaults:
+        RetentionPolicy::default(),
+      categories:       HashMap::new(),
+      feeds:            HashMap::new()
+    },
+    archive: ArchiveConfig {
+      enabled:    false,
+      store:
+        ArchiveStore::Database,
+      directory:  PathBuf::from(
+        "archive"
+      ),
+      zstd_level: 3
+    },
+    backup: BackupConfig {
+      enabled:          false,
+      interval_seconds: 86_400,
+      directory:        PathBuf::from(
+        "backups"
+      ),
+      keep:             7,
+      server_schema:    "server"
+        .to_string(),
+      zstd_level:       3
+    },
+    write_behind: WriteBehindConfig {
+      enabled:   false,
+      max_batch: 256,
+      flush_ms:  20
+    },
+    registry: RegistryConfig {
+      enabled:          false,
+      category:         "user"
+        .to_string(),
+      interval_seconds: 60
+    }
+  }
+}
+
+/// Caps concurrent requests to
+/// `domain`.
+pub fn with_domain_limit(
+  mut cfg: AppConfig,
+  domain: &str,
+  max_concurrent_requests: usize
+) -> AppConfig {
+  cfg.domains.insert(
+    domain.to_string(),
+    DomainConfig {
+      max_concurrent_requests,
+      http: None
+    }
+  );
+
+  cfg
+}
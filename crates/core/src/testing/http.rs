@@ -0,0 +1,531 @@
+//! Scriptable `Http`: each URL has a
+//! timeline of responses that take
+//! effect at offsets from the clock's
+//! start. Every request is recorded,
+//! with the headers and body of POSTs,
+//! along with per-domain concurrency.
+
+use std::collections::HashMap;
+use std::sync::{
+  Arc,
+  Mutex
+};
+use std::time::Duration;
+
+use super::clock::VirtualClock;
+use crate::domain::model::{
+  ErrorKind,
+  GetResult,
+  HeadResult,
+  RedirectHop
+};
+use crate::ports::http::Http;
+
+/// One scripted response. HEAD
+/// requests get the same status and
+/// validators without the body.
+#[derive(Debug, Clone)]
+pub struct FakeResponse {
+  pub status:           Option<u16>,
+  pub headers: Vec<(String, String)>,
+  pub body:             Vec<u8>,
+  pub etag:             Option<String>,
+  pub last_modified_ms: Option<i64>,
+  pub error: Option<ErrorKind>,
+  pub latency:          Duration,
+  pub redirects: Vec<RedirectHop>
+}
+
+impl FakeResponse {
+  /// `200` with `body`.
+  pub fn ok(
+    body: impl Into<Vec<u8>>
+  ) -> Self {
+    Self {
+      status:           Some(200),
+      headers:          Vec::new(),
+      body:             body.into(),
+      etag:             None,
+      last_modified_ms: None,
+      error:            None,
+      latency:          Duration::ZERO,
+      redirects:        Vec::new()
+    }
+  }
+
+  /// An empty response with `status`;
+  /// 4xx and 5xx carry the matching
+  /// error kind, as the real client
+  /// reports them.
+  pub fn status(status: u16) -> Self {
+    let error = match status {
+      | 400..=499 => {
+        Some(ErrorKind::Http4xx(status))
+      }
+      | 500..=599 => {
+        Some(ErrorKind::Http5xx(status))
+      }
+      | _ => None
+    };
+
+    Self {
+      status: Some(status),
+      error,
+      ..Self::ok(Vec::new())
+    }
+  }
+
+  /// A transport failure without a
+  /// status.
+  pub fn failure(
+    error: ErrorKind
+  ) -> Self {
+    Self {
+      status: None,
+      error: Some(error),
+      ..Self::ok(Vec::new())
+    }
+  }
+
+  pub fn with_etag(
+    mut self,
+    etag: &str
+  ) -> Self {
+    self.etag = Some(etag.to_string());
+    self.headers.push((
+      "etag".to_string(),
+      etag.to_string()
+    ));
+
+    self
+  }
+
+  pub fn with_last_modified(
+    mut self,
+    epoch_ms: i64
+  ) -> Self {
+    self.last_modified_ms =
+      Some(epoch_ms);
+
+    self
+  }
+
+  pub fn with_header(
+    mut self,
+    name: &str,
+    value: &str
+  ) -> Self {
+    self.headers.push((
+      name.to_string(),
+      value.to_string()
+    ));
+
+    self
+  }
+
+  pub fn with_latency(
+    mut self,
+    latency: Duration
+  ) -> Self {
+    self.latency = latency;
+
+    self
+  }
+
+  /// Marks the response as reached
+  /// through a redirect from the
+  /// requested URL.
+  pub fn with_redirect(
+    mut self,
+    status: u16,
+    from: &str,
+    to: &str
+  ) -> Self {
+    self.redirects.push(RedirectHop {
+      status,
+      from: from.to_string(),
+      to: to.to_string()
+    });
+
+    self
+  }
+
+  fn set_cookie_headers(
+    &self
+  ) -> Vec<String> {
+    self
+      .headers
+      .iter()
+      .filter(|(name, _)| {
+        name.eq_ignore_ascii_case(
+          "set-cookie"
+        )
+      })
+      .map(|(_, value)| value.clone())
+      .collect()
+  }
+}
+
+/// A request the fake answered.
+#[derive(Debug, Clone)]
+pub struct RequestRecord {
+  pub method:  &'static str,
+  pub domain:  String,
+  pub url:     String,
+  pub at_ms:   i64,
+  /// Sent headers; for HEAD and GET
+  /// the extra headers plus any cookie
+  /// under `cookie`.
+  pub headers: HashMap<String, String>,
+  pub body:    Vec<u8>
+}
+
+#[derive(Default)]
+struct FakeState {
+  timelines: HashMap<
+    String,
+    Vec<(Duration, FakeResponse)>
+  >,
+  requests:      Vec<RequestRecord>,
+  in_flight:     HashMap<String, usize>,
+  max_in_flight: HashMap<String, usize>
+}
+
+pub struct FakeHttp {
+  clock: Arc<VirtualClock>,
+  state: Mutex<FakeState>
+}
+
+impl FakeHttp {
+  pub fn new(
+    clock: Arc<VirtualClock>
+  ) -> Self {
+    Self {
+      clock,
+      state: Mutex::new(
+        FakeState::default()
+      )
+    }
+  }
+
+  /// Serves `response` for `url` from
+  /// `offset` after the clock's start
+  /// until a later entry takes over.
+  /// URLs without a current entry fail
+  /// with `ConnectionFailure`.
+  pub fn respond_from(
+    &self,
+    url: &str,
+    offset: Duration,
+    response: FakeResponse
+  ) {
+    let mut state = self.lock();
+
+    let timeline = state
+      .timelines
+      .entry(url.to_string())
+      .or_default();
+
+    timeline.push((offset, response));
+    timeline.sort_by_key(|(at, _)| *at);
+  }
+
+  /// Serves `response` from the start.
+  pub fn respond(
+    &self,
+    url: &str,
+    response: FakeResponse
+  ) {
+    self.respond_from(
+      url,
+      Duration::ZERO,
+      response
+    );
+  }
+
+  pub fn requests(
+    &self
+  ) -> Vec<RequestRecord> {
+    self.lock().requests.clone()
+  }
+
+  /// Requests with `method` to `url`.
+  pub fn count(
+    &self,
+    method: &str,
+    url: &str
+  ) -> usize {
+    self
+      .lock()
+      .requests
+      .iter()
+      .filter(|r| {
+        r.method == method
+          && r.url == url
+      })
+      .count()
+  }
+
+  /// Most requests to `domain` ever in
+  /// flight at once.
+  pub fn max_in_flight(
+    &self,
+    domain: &str
+  ) -> usize {
+    self
+      .lock()
+      .max_in_flight
+      .get(domain)
+      .copied()
+      .unwrap_or(0)
+  }
+
+  fn lock(
+    &self
+  ) -> std::sync::MutexGuard<
+    '_,
+    FakeState
+  > {
+    self
+      .state
+      .lock()
+      .expect("http lock")
+  }
+
+  async fn serve(
+    &self,
+    method: &'static str,
+    domain: &str,
+    url: &str,
+    sent: (
+      HashMap<String, String>,
+      Vec<u8>
+    )
+  ) -> FakeResponse {
+    let elapsed = self.clock.elapsed();
+
+    let response = {
+      let mut state = self.lock();
+
+      state.requests.push(
+        RequestRecord {
+          method,
+          domain: domain.to_string(),
+          url: url.to_string(),
+          at_ms: self.clock.now_ms(),
+          headers: sent.0,
+          body: sent.1
+        }
+      );
+
+      let in_flight = {
+        let n = state
+          .in_flight
+          .entry(domain.to_string())
+          .or_default();
+
+        *n += 1;
+        *n
+      };
+
+      let max = state
+        .max_in_flight
+        .entry(domain.to_string())
+        .or_default();
+
+      *max = (*max).max(in_flight);
+
+      state
+        .timelines
+        .get(url)
+        .and_then(|timeline| {
+          timeline.iter().rev().find(
+            |(at, _)| *at <= elapsed
+          )
+        })
+        .map(|(_, response)| {
+          response.clone()
+        })
+        .unwrap_or_else(|| {
+          FakeResponse::failure(
+            ErrorKind::ConnectionFailure
+          )
+        })
+    };
+
+    if !response.latency.is_zero() {
+      tokio::time::sleep(
+        response.latency
+      )
+      .await;
+    }
+
+    if let Some(n) = self
+      .lock()
+      .in_flight
+      .get_mut(domain)
+    {
+      *n -= 1;
+    }
+
+    response
+  }
+}
+
+#[async_trait::async_trait]
+
+impl Http for FakeHttp {
+  async fn head(
+    &self,
+    domain: &str,
+    url: &str,
+    cookie_header: Option<&str>,
+    extra_headers: Option<
+      &HashMap<String, String>
+    >
+  ) -> HeadResult {
+    let response = self
+      .serve(
+        "HEAD",
+        domain,
+        url,
+        (
+          sent_headers(
+            cookie_header,
+            extra_headers
+          ),
+          Vec::new()
+        )
+      )
+      .await;
+
+    HeadResult {
+      status:             response
+        .status,
+      etag:               response
+        .etag
+        .clone(),
+      last_modified:      response
+        .last_modified_ms,
+      error:              response
+        .error,
+      latency_ms:         response
+        .latency
+        .as_millis()
+        as u64,
+      set_cookie_headers: response
+        .set_cookie_headers()
+    }
+  }
+
+  async fn get(
+    &self,
+    domain: &str,
+    url: &str,
+    cookie_header: Option<&str>,
+    extra_headers: Option<
+      &HashMap<String, String>
+    >
+  ) -> GetResult {
+    let response = self
+      .serve(
+        "GET",
+        domain,
+        url,
+        (
+          sent_headers(
+            cookie_header,
+            extra_headers
+          ),
+          Vec::new()
+        )
+      )
+      .await;
+
+    get_result(url, response)
+  }
+
+  async fn post(
+    &self,
+    domain: &str,
+    url: &str,
+    headers: &HashMap<String, String>,
+    body: Vec<u8>
+  ) -> GetResult {
+    let response = self
+      .serve(
+        "POST",
+        domain,
+        url,
+        (headers.clone(), body)
+      )
+      .await;
+
+    get_result(url, response)
+  }
+}
+
+/// Headers a HEAD or GET sends.
+fn sent_headers(
+  cookie_header: Option<&str>,
+  extra_headers: Option<
+    &HashMap<String, String>
+  >
+) -> HashMap<String, String> {
+  let mut headers = extra_headers
+    .cloned()
+    .unwrap_or_default();
+
+  if let Some(cookie) = cookie_header {
+    headers.insert(
+      "cookie".to_string(),
+      cookie.to_string()
+    );
+  }
+
+  headers
+}
+
+/// The `GetResult` the real client
+/// reports for `response`.
+fn get_result(
+  url: &str,
+  response: FakeResponse
+) -> GetResult {
+  let set_cookie_headers =
+    response.set_cookie_headers();
+
+  // The real client reports a body
+  // and final URL for any response
+  // with a status.
+  let answered =
+    response.status.is_some();
+
+  let final_url = answered.then(|| {
+    response
+      .redirects
+      .last()
+      .map(|hop| hop.to.clone())
+      .unwrap_or_else(|| {
+        url.to_string()
+      })
+  });
+
+  GetResult {
+    status: response.status,
+    body: answered
+      .then_some(response.body),
+    etag: response.etag,
+    last_modified: response
+      .last_modified_ms,
+    error: response.error,
+    latency_ms: response
+      .latency
+      .as_millis()
+      as u64,
+    set_cookie_headers,
+    headers: response.headers,
+    final_url,
+    redirects: response.redirects
+  }
+}
@@ -0,0 +1,35 @@
+//! Test support: an in-memory `Repo`,
+//! a scriptable `Http`, a virtual
+//! clock and a harness that drives the
+//! scheduler tick by tick. The clock
+//! follows tokio's clock, so run
+//! simulations under paused time
+//! (`#[tokio::test(start_paused =
+//! true)]`) to cover days in
+//! milliseconds.
+
+pub mod clock;
+pub mod config;
+pub mod http;
+pub mod repo;
+pub mod simulation;
+
+pub use clock::{
+  SeededRandom,
+  VirtualClock
+};
+pub use config::{
+  sim_config,
+  with_domain_limit
+};
+pub use http::{
+  FakeHttp,
+  FakeResponse,
+  RequestRecord
+};
+pub use repo::MemoryRepo;
+pub use simulation::{
+  Simulation,
+  feed,
+  watch
+};
@@ -0,0 +1,1343 @@
+//! In-memory `Repo` with the semantics
+//! of the SQL backends, for tests and
+//! simulations. It has no server
+//! tables, so retention never protects
+//! unread items.
+
+mod retention;
+pub mod tables;
+
+use std::collections::HashSet;
+use std::sync::{
+  Arc,
+  Mutex,
+  MutexGuard
+};
+
+use chrono_tz::Tz;
+
+pub use self::tables::{
+  ArchiveIndexRow,
+  EventRow,
+  HistoryRow,
+  ItemRow,
+  MemoryTables,
+  ParkedFeedRow,
+  PayloadRow,
+  RedirectRow
+};
+use crate::domain::link_state::{
+  LinkPhase,
+  LinkState
+};
+use crate::domain::model::{
+  ErrorKind,
+  FeedConfig
+};
+use crate::domain::registry::{
+  RegistryFeed,
+  RegistryStatus
+};
+use crate::domain::retention::{
+  RetentionLimit,
+  RetentionTable
+};
+use crate::feed::parser::{
+  FeedItem,
+  ParsedFeed
+};
+use crate::infra::system_clock::SystemClock;
+use crate::ports::clock::Clock;
+use crate::ports::repo::{
+  ArchiveRefRow,
+  ArchivedFeedRow,
+  ErrorFeedRow,
+  FeedRedirectRow,
+  FetchEventRow,
+  NewEvent,
+  NewState,
+  PayloadSummaryRow,
+  Repo,
+  StateHistoryRow,
+  StateRow,
+  StoredItemRow
+};
+
+pub struct MemoryRepo {
+  tables: Mutex<MemoryTables>,
+  /// Stands in for the SQL backends'
+  /// wall clock: event times and
+  /// rescheduling an archived feed
+  /// whose URL changed.
+  clock:  Arc<dyn Clock>
+}
+
+impl Default for MemoryRepo {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl MemoryRepo {
+  pub fn new() -> Self {
+    Self::with_clock(Arc::new(
+      SystemClock
+    ))
+  }
+
+  pub fn with_clock(
+    clock: Arc<dyn Clock>
+  ) -> Self {
+    Self {
+      tables: Mutex::new(
+        MemoryTables::default()
+      ),
+      clock
+    }
+  }
+
+  /// Runs `f` over the stored rows.
+  pub fn read<T>(
+    &self,
+    f: impl FnOnce(&MemoryTables) -> T
+  ) -> T {
+    f(&self.lock())
+  }
+
+  fn lock(
+    &self
+  ) -> MutexGuard<'_, MemoryTables> {
+    self
+      .tables
+      .lock()
+      .expect("memory repo lock")
+  }
+}
+
+/// The row the SQL backends store for
+/// `state`.
+fn state_row(
+  state: &LinkState
+) -> StateRow {
+  StateRow {
+    feed_id:                 state
+      .feed_id
+      .clone(),
+    phase:                   format!(
+      "{:?}",
+      state.phase
+    ),
+    last_head_at_ms:         state
+      .last_head_at_ms,
+    last_head_status:        state
+      .last_head_status
+      .map(i64::from),
+    last_head_error:         state
+      .last_head_error
+      .map(|e| format!("{e:?}")),
+    last_get_at_ms:          state
+      .last_get_at_ms,
+    last_get_status:         state
+      .last_get_status
+      .map(i64::from),
+    last_get_error:          state
+      .last_get_error
+      .map(|e| format!("{e:?}")),
+    etag:                    state
+      .etag
+      .clone(),
+    last_modified_ms:        state
+      .last_modified_ms,
+    backoff_index:           state
+      .backoff_index
+      as i64,
+    base_poll_seconds:       state
+      .base_poll_seconds
+      as i64,
+    next_action_at_ms:       state
+      .next_action_at_ms,
+    jitter_seconds:          state
+      .jitter_seconds,
+    note:                    state
+      .note
+      .clone(),
+    consecutive_error_count: state
+      .consecutive_error_count
+      as i64
+  }
+}
+
+fn is_archived(row: &StateRow) -> bool {
+  row.phase
+    == format!(
+      "{:?}",
+      LinkPhase::Archived
+    )
+}
+
+fn push_items(
+  tables: &mut MemoryTables,
+  payload_id: i64,
+  feed_id: &str,
+  items: &[FeedItem]
+) {
+  for item in items {
+    let id = tables.next_id();
+
+    tables.feed_items.push(ItemRow {
+      id,
+      payload_id,
+      feed_id: feed_id.to_string(),
+      item: item.clone()
+    });
+  }
+}
+
+#[async_trait::async_trait]
+
+impl Repo for MemoryRepo {
+  async fn migrate(
+    &self,
+    _zone: &Tz,
+    _default_poll_seconds: u64
+  ) -> Result<(), String> {
+    Ok(())
+  }
+
+  async fn upsert_feeds_bulk(
+    &self,
+    feeds: Vec<FeedConfig>,
+    _chunk_size: usize,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    let now_ms =
+      self.clock.now_epoch_ms().await;
+
+    let mut tables = self.lock();
+
+    for feed in feeds {
+      let moved = tables
+        .feeds
+        .get(&feed.id)
+        .is_some_and(|f| {
+          f.url != feed.url
+        });
+
+      if moved
+        && let Some(state) = tables
+          .state_current
+          .get_mut(&feed.id)
+        && is_archived(state)
+      {
+        state.phase = format!(
+          "{:?}",
+          LinkPhase::NeedsInitialGet
+        );
+        state.next_action_at_ms =
+          now_ms;
+      }
+
+      tables
+        .feeds
+        .insert(feed.id.clone(), feed);
+    }
+
+    Ok(())
+  }
+
+  async fn upsert_categories(
+    &self,
+    categories: Vec<String>,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self
+      .lock()
+      .categories
+      .extend(categories);
+
+    Ok(())
+  }
+
+  async fn latest_state(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<StateRow>, String>
+  {
+    Ok(
+      self
+        .lock()
+        .state_current
+        .get(feed_id)
+        .cloned()
+    )
+  }
+
+  async fn due_feeds_for_category(
+    &self,
+    category: &str,
+    now_ms: i64,
+    limit: i64
+  ) -> Result<Vec<FeedConfig>, String>
+  {
+    let tables = self.lock();
+
+    let mut due: Vec<(
+      i64,
+      &FeedConfig
+    )> = tables
+      .feeds
+      .values()
+      .filter(|f| {
+        f.category == category
+          && !tables
+            .error_feeds
+            .contains_key(&f.id)
+      })
+      .filter_map(|f| {
+        match tables
+          .state_current
+          .get(&f.id)
+        {
+          | None => Some((now_ms, f)),
+          | Some(s)
+            if !is_archived(s)
+              && s
+                .next_action_at_ms
+                <= now_ms =>
+          {
+            Some((
+              s.next_action_at_ms,
+              f
+            ))
+          }
+          | Some(_) => None
+        }
+      })
+      .collect();
+
+    due.sort_by(|a, b| {
+      a.0
+        .cmp(&b.0)
+        .then(a.1.id.cmp(&b.1.id))
+    });
+
+    Ok(
+      due
+        .into_iter()
+        .take(limit.max(0) as usize)
+        .map(|(_, f)| f.clone())
+        .collect()
+    )
+  }
+
+  async fn insert_state(
+    &self,
+    state: &LinkState,
+    recorded_at_ms: i64,
+    _zone: &Tz,
+    record_history: bool
+  ) -> Result<(), String> {
+    let row = state_row(state);
+
+    let mut tables = self.lock();
+
+    if record_history {
+      let id = tables.next_id();
+
+      tables.state_history.push(
+        HistoryRow {
+          id,
+          recorded_at_ms,
+          state: row.clone()
+        }
+      );
+    }
+
+    tables
+      .state_current
+      .insert(row.feed_id.clone(), row);
+
+    Ok(())
+  }
+
+  async fn insert_event(
+    &self,
+    feed_id: &str,
+    method: &str,
+    status: Option<i64>,
+    error_kind: Option<ErrorKind>,
+    latency_ms: Option<i64>,
+    backoff_index: i64,
+    scheduled_next_action_at_ms: i64,
+    debug: Option<&str>,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    let event_time_ms =
+      self.clock.now_epoch_ms().await;
+
+    let mut tables = self.lock();
+
+    let id = tables.next_id();
+
+    tables.fetch_events.push(
+      EventRow {
+        id,
+        feed_id: feed_id.to_string(),
+        event_time_ms,
+        method: method.to_string(),
+        status,
+        error_kind,
+        latency_ms,
+        backoff_index,
+        scheduled_next_action_at_ms,
+        debug: debug
+          .map(str::to_string)
+      }
+    );
+
+    Ok(())
+  }
+
+  async fn insert_events(
+    &self,
+    events: &[NewEvent],
+    _zone: &Tz
+  ) -> Result<(), String> {
+    let mut tables = self.lock();
+
+    for e in events {
+      let id = tables.next_id();
+
+      tables
+        .fetch_events
+        .push(EventRow {
+        id,
+        feed_id: e.feed_id.clone(),
+        event_time_ms: e.event_time_ms,
+        method: e.method.clone(),
+        status: e.status,
+        error_kind: e.error_kind,
+        latency_ms: e.latency_ms,
+        backoff_index: e.backoff_index,
+        scheduled_next_action_at_ms: e
+          .scheduled_next_action_at_ms,
+        debug: e.debug.clone()
+      });
+    }
+
+    Ok(())
+  }
+
+  async fn insert_states(
+    &self,
+    states: &[NewState],
+    zone: &Tz
+  ) -> Result<(), String> {
+    for s in states {
+      self
+        .insert_state(
+          &s.state,
+          s.recorded_at_ms,
+          zone,
+          s.record_history
+        )
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  async fn insert_payload_with_items(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    etag: Option<&str>,
+    last_modified_ms: Option<i64>,
+    content_hash: Option<&str>,
+    parsed: &ParsedFeed,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    let mut tables = self.lock();
+
+    let payload_id = tables.next_id();
+
+    tables.feed_payloads.push(
+      PayloadRow {
+        id: payload_id,
+        feed_id: feed_id.to_string(),
+        fetched_at_ms,
+        etag: etag.map(str::to_string),
+        last_modified_ms,
+        content_hash: content_hash
+          .map(str::to_string),
+        metadata: parsed
+          .metadata
+          .clone()
+      }
+    );
+
+    push_items(
+      &mut tables,
+      payload_id,
+      feed_id,
+      &parsed.items
+    );
+
+    Ok(())
+  }
+
+  async fn known_item_guids(
+    &self,
+    feed_id: &str,
+    guids: &[String]
+  ) -> Result<HashSet<String>, String>
+  {
+    let wanted: HashSet<&String> =
+      guids.iter().collect();
+
+    Ok(
+      self
+        .lock()
+        .items_for(feed_id)
+        .iter()
+        .filter_map(|i| {
+          i.item.guid.as_ref()
+        })
+        .filter(|g| wanted.contains(g))
+        .cloned()
+        .collect()
+    )
+  }
+
+  async fn mark_feed_error(
+    &self,
+    feed_id: &str,
+    error_kind: Option<ErrorKind>,
+    status: Option<i64>,
+    error_count: i64,
+    observed_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.lock().error_feeds.insert(
+      feed_id.to_string(),
+      ParkedFeedRow {
+        error_count,
+        last_error_kind: error_kind,
+        last_error_status: status,
+        last_error_at_ms:
+          observed_at_ms
+      }
+    );
+
+    Ok(())
+  }
+
+  async fn latest_cookie_header(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>
+  {
+    Ok(
+      self
+        .lock()
+        .cookies
+        .get(feed_id)
+        .cloned()
+    )
+  }
+
+  async fn upsert_cookie_header(
+    &self,
+    feed_id: &str,
+    cookie_header: &str,
+    _observed_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.lock().cookies.insert(
+      feed_id.to_string(),
+      cookie_header.to_string()
+    );
+
+    Ok(())
+  }
+
+  async fn latest_watch_snapshot(
+    &self,
+    feed_id: &str
+  ) -> Result<Option<String>, String>
+  {
+    Ok(
+      self
+        .lock()
+        .snapshots
+        .get(feed_id)
+        .cloned()
+    )
+  }
+
+  async fn upsert_watch_snapshot(
+    &self,
+    feed_id: &str,
+    content_text: &str,
+    _observed_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    self.lock().snapshots.insert(
+      feed_id.to_string(),
+      content_text.to_string()
+    );
+
+    Ok(())
+  }
+
+  async fn record_feed_redirect(
+    &self,
+    feed_id: &str,
+    from_url: &str,
+    suggested_url: &str,
+    status: i64,
+    observed_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    let mut tables = self.lock();
+
+    let row = tables
+      .redirects
+      .entry(feed_id.to_string())
+      .or_insert_with(|| {
+        RedirectRow {
+          from_url: from_url
+            .to_string(),
+          suggested_url: suggested_url
+            .to_string(),
+          status,
+          hit_count: 0,
+          first_seen_at_ms:
+            observed_at_ms,
+          last_seen_at_ms:
+            observed_at_ms
+        }
+      });
+
+    if row.from_url == from_url
+      && row.suggested_url
+        == suggested_url
+    {
+      row.hit_count += 1;
+    } else {
+      row.from_url =
+        from_url.to_string();
+      row.suggested_url =
+        suggested_url.to_string();
+      row.hit_count = 1;
+      row.first_seen_at_ms =
+        observed_at_ms;
+    }
+
+    row.status = status;
+    row.last_seen_at_ms =
+      observed_at_ms;
+
+    Ok(())
+  }
+
+  async fn clear_feed_redirect(
+    &self,
+    feed_id: &str
+  ) -> Result<(), String> {
+    self
+      .lock()
+      .redirects
+      .remove(feed_id);
+
+    Ok(())
+  }
+
+  async fn feed_redirects(
+    &self,
+    min_hits: i64
+  ) -> Result<
+    Vec<FeedRedirectRow>,
+    String
+  > {
+    let tables = self.lock();
+
+    Ok(
+      tables
+        .redirects
+        .iter()
+        .filter_map(|(feed_id, r)| {
+          let feed = tables
+            .feeds
+            .get(feed_id)?;
+
+          (r.from_url == feed.url
+            && r.hit_count >= min_hits)
+            .then(|| {
+              FeedRedirectRow {
+                feed_id:
+                  feed_id.clone(),
+                url:              feed
+                  .url
+                  .clone(),
+                suggested_url:    r
+                  .suggested_url
+                  .clone(),
+                status:           r
+                  .status,
+                hit_count:        r
+                  .hit_count,
+                first_seen_at_ms: r
+                  .first_seen_at_ms,
+                last_seen_at_ms:  r
+                  .last_seen_at_ms
+              }
+            })
+        })
+        .collect()
+    )
+  }
+
+  async fn archived_feeds(
+    &self
+  ) -> Result<
+    Vec<ArchivedFeedRow>,
+    String
+  > {
+    let tables = self.lock();
+
+    Ok(
+      tables
+        .state_current
+        .values()
+        .filter(|s| is_archived(s))
+        .filter_map(|s| {
+          let feed = tables
+            .feeds
+            .get(&s.feed_id)?;
+
+          Some(ArchivedFeedRow {
+            feed_id:        s
+              .feed_id
+              .clone(),
+            url:            feed
+              .url
+              .clone(),
+            archived_at_ms: s
+              .last_get_at_ms
+              .or(s.last_head_at_ms)
+          })
+        })
+        .collect()
+    )
+  }
+
+  async fn feed_categories(
+    &self
+  ) -> Result<
+    Vec<(String, String)>,
+    String
+  > {
+    Ok(
+      self
+        .lock()
+        .feeds
+        .values()
+        .map(|f| {
+          (
+            f.id.clone(),
+            f.category.clone()
+          )
+        })
+        .collect()
+    )
+  }
+
+  async fn prune_batch(
+    &self,
+    table: RetentionTable,
+    feed_id: &str,
+    limit: RetentionLimit,
+    now_ms: i64,
+    batch_size: u64,
+    _server_schema: &str
+  ) -> Result<u64, String> {
+    let mut tables = self.lock();
+
+    let mut ids = retention::candidates(
+      &tables, table, feed_id, limit,
+      now_ms
+    );
+
+    ids.truncate(batch_size as usize);
+
+    retention::delete(
+      &mut tables,
+      table,
+      &ids
+    );
+
+    Ok(ids.len() as u64)
+  }
+
+  async fn count_prunable(
+    &self,
+    table: RetentionTable,
+    feed_id: &str,
+    limit: RetentionLimit,
+    now_ms: i64,
+    _server_schema: &str
+  ) -> Result<u64, String> {
+    Ok(
+      retention::candidates(
+        &self.lock(),
+        table,
+        feed_id,
+        limit,
+        now_ms
+      )
+      .len() as u64
+    )
+  }
+
+  async fn insert_archive_ref(
+    &self,
+    feed_id: &str,
+    fetched_at_ms: i64,
+    content_hash: &str
+  ) -> Result<(), String> {
+    let mut tables = self.lock();
+    let id = tables.next_id();
+
+    tables.archive_index.push(
+      ArchiveIndexRow {
+        id,
+        feed_id: feed_id.to_string(),
+        fetched_at_ms,
+        content_hash: content_hash
+          .to_string()
+      }
+    );
+
+    Ok(())
+  }
+
+  async fn archive_refs_since(
+    &self,
+    feed_id: &str,
+    since_ms: i64
+  ) -> Result<Vec<ArchiveRefRow>, String>
+  {
+    let tables = self.lock();
+
+    let mut rows: Vec<(
+      i64,
+      ArchiveRefRow
+    )> = tables
+      .archive_index
+      .iter()
+      .filter(|a| {
+        a.feed_id == feed_id
+          && a.fetched_at_ms >= since_ms
+      })
+      .map(|a| {
+        let payload_id = tables
+          .payloads_for(feed_id)
+          .iter()
+          .find(|p| {
+            p.fetched_at_ms
+              == a.fetched_at_ms
+              && p.content_hash.as_ref()
+                == Some(&a.content_hash)
+          })
+          .map(|p| p.id);
+
+        (a.id, ArchiveRefRow {
+          fetched_at_ms: a
+            .fetched_at_ms,
+          content_hash: a
+            .content_hash
+            .clone(),
+          payload_id
+        })
+      })
+      .collect();
+
+    rows.sort_by_key(|(id, r)| {
+      (r.fetched_at_ms, *id)
+    });
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|(_, r)| r)
+        .collect()
+    )
+  }
+
+  async fn insert_items(
+    &self,
+    payload_id: i64,
+    feed_id: &str,
+    items: &[FeedItem],
+    _zone: &Tz
+  ) -> Result<u64, String> {
+    push_items(
+      &mut self.lock(),
+      payload_id,
+      feed_id,
+      items
+    );
+
+    Ok(items.len() as u64)
+  }
+
+  async fn archived_blob_exists(
+    &self,
+    content_hash: &str
+  ) -> Result<bool, String> {
+    Ok(
+      self
+        .lock()
+        .archive
+        .contains_key(content_hash)
+    )
+  }
+
+  async fn insert_archived_blob(
+    &self,
+    content_hash: &str,
+    stored_at_ms: i64,
+    data: &[u8]
+  ) -> Result<(), String> {
+    self
+      .lock()
+      .archive
+      .entry(content_hash.to_string())
+      .or_insert_with(|| {
+        (stored_at_ms, data.to_vec())
+      });
+
+    Ok(())
+  }
+
+  async fn archived_blob(
+    &self,
+    content_hash: &str
+  ) -> Result<Option<Vec<u8>>, String>
+  {
+    Ok(
+      self
+        .lock()
+        .archive
+        .get(content_hash)
+        .map(|(_, data)| data.clone())
+    )
+  }
+
+  async fn orphan_archived_blobs(
+    &self,
+    limit: u64
+  ) -> Result<Vec<String>, String> {
+    let tables = self.lock();
+    let referenced =
+      tables.referenced_hashes();
+
+    Ok(
+      tables
+        .archive
+        .keys()
+        .filter(|h| {
+          !referenced.contains(h)
+        })
+        .take(
+          limit.min(usize::MAX as u64)
+            as usize
+        )
+        .cloned()
+        .collect()
+    )
+  }
+
+  async fn delete_archived_blobs(
+    &self,
+    content_hashes: &[String]
+  ) -> Result<u64, String> {
+    let mut tables = self.lock();
+
+    Ok(
+      content_hashes
+        .iter()
+        .filter(|h| {
+          tables
+            .archive
+            .remove(h.as_str())
+            .is_some()
+        })
+        .count() as u64
+    )
+  }
+
+  async fn referenced_content_hashes(
+    &self,
+    content_hashes: &[String]
+  ) -> Result<HashSet<String>, String>
+  {
+    let tables = self.lock();
+    let referenced =
+      tables.referenced_hashes();
+
+    Ok(
+      content_hashes
+        .iter()
+        .filter(|h| {
+          referenced.contains(h)
+        })
+        .cloned()
+        .collect()
+    )
+  }
+
+  async fn recent_events(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<Vec<FetchEventRow>, String>
+  {
+    let tables = self.lock();
+
+    let mut events =
+      tables.events_for(feed_id);
+
+    events.sort_by_key(|e| {
+      std::cmp::Reverse((
+        e.event_time_ms,
+        e.id
+      ))
+    });
+
+    Ok(
+      events
+        .into_iter()
+        .take(limit as usize)
+        .map(|e| {
+          FetchEventRow {
+            id: e.id,
+            feed_id: e.feed_id.clone(),
+            event_time_ms: e.event_time_ms,
+            method: e.method.clone(),
+            status: e.status,
+            error_kind: e
+              .error_kind
+              .map(|k| format!("{k:?}")),
+            latency_ms: e.latency_ms,
+            backoff_index: e.backoff_index,
+            scheduled_next_action_at_ms: e
+              .scheduled_next_action_at_ms,
+            debug: e.debug.clone()
+          }
+        })
+        .collect()
+    )
+  }
+
+  async fn state_history(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<
+    Vec<StateHistoryRow>,
+    String
+  > {
+    let tables = self.lock();
+
+    let mut rows: Vec<&HistoryRow> =
+      tables
+        .state_history
+        .iter()
+        .filter(|h| {
+          h.state.feed_id == feed_id
+        })
+        .collect();
+
+    rows.sort_by_key(|h| {
+      std::cmp::Reverse((
+        h.recorded_at_ms,
+        h.id
+      ))
+    });
+
+    Ok(
+      rows
+        .into_iter()
+        .take(limit as usize)
+        .map(|h| {
+          StateHistoryRow {
+            recorded_at_ms: h
+              .recorded_at_ms,
+            state:          h
+              .state
+              .clone()
+          }
+        })
+        .collect()
+    )
+  }
+
+  async fn error_feeds(
+    &self
+  ) -> Result<Vec<ErrorFeedRow>, String>
+  {
+    Ok(
+      self
+        .lock()
+        .error_feeds
+        .iter()
+        .map(|(feed_id, e)| {
+          ErrorFeedRow {
+            feed_id:           feed_id
+              .clone(),
+            error_count:       e
+              .error_count,
+            last_error_kind:   e
+              .last_error_kind
+              .map(|k| {
+                format!("{k:?}")
+              }),
+            last_error_status: e
+              .last_error_status,
+            last_error_at_ms:  e
+              .last_error_at_ms,
+            note:              Some(
+              "max-consecutive-errors"
+                .to_string()
+            )
+          }
+        })
+        .collect()
+    )
+  }
+
+  async fn payload_timeline(
+    &self,
+    feed_id: &str,
+    limit: u64
+  ) -> Result<
+    Vec<PayloadSummaryRow>,
+    String
+  > {
+    let tables = self.lock();
+
+    let mut payloads =
+      tables.payloads_for(feed_id);
+
+    payloads.sort_by_key(|p| {
+      std::cmp::Reverse((
+        p.fetched_at_ms,
+        p.id
+      ))
+    });
+
+    Ok(
+      payloads
+        .into_iter()
+        .take(limit as usize)
+        .map(|p| {
+          PayloadSummaryRow {
+            payload_id:       p.id,
+            feed_id:          p
+              .feed_id
+              .clone(),
+            fetched_at_ms:    p
+              .fetched_at_ms,
+            etag:             p
+              .etag
+              .clone(),
+            last_modified_ms: p
+              .last_modified_ms,
+            content_hash:     p
+              .content_hash
+              .clone(),
+            title:            p
+              .metadata
+              .title
+              .clone(),
+            item_count:       tables
+              .feed_items
+              .iter()
+              .filter(|i| {
+                i.payload_id == p.id
+              })
+              .count()
+              as i64
+          }
+        })
+        .collect()
+    )
+  }
+
+  async fn payload_items(
+    &self,
+    payload_id: i64
+  ) -> Result<Vec<StoredItemRow>, String>
+  {
+    Ok(
+      self
+        .lock()
+        .feed_items
+        .iter()
+        .filter(|i| {
+          i.payload_id == payload_id
+        })
+        .map(|i| {
+          StoredItemRow {
+            id:              i.id,
+            payload_id:      i
+              .payload_id,
+            feed_id:         i
+              .feed_id
+              .clone(),
+            title:           i
+              .item
+              .title
+              .clone(),
+            link:            i
+              .item
+              .link
+              .clone(),
+            guid:            i
+              .item
+              .guid
+              .clone(),
+            published_at_ms: i
+              .item
+              .published_at_ms,
+            category:        i
+              .item
+              .category
+              .clone(),
+            summary:         i
+              .item
+              .summary
+              .clone()
+          }
+        })
+        .collect()
+    )
+  }
+
+  async fn registry_feeds(
+    &self,
+    status: Option<RegistryStatus>
+  ) -> Result<Vec<RegistryFeed>, String>
+  {
+    let mut feeds: Vec<RegistryFeed> =
+      self
+        .lock()
+        .registry
+        .iter()
+        .filter(|f| {
+          status.is_none_or(|s| {
+            f.status == s
+          })
+        })
+        .cloned()
+        .collect();
+
+    feeds.sort_by(|a, b| {
+      (a.created_at_ms, &a.id)
+        .cmp(&(b.created_at_ms, &b.id))
+    });
+
+    Ok(feeds)
+  }
+
+  async fn registry_feed(
+    &self,
+    id_or_url: &str
+  ) -> Result<
+    Option<RegistryFeed>,
+    String
+  > {
+    Ok(
+      self
+        .lock()
+        .registry
+        .iter()
+        .find(|f| {
+          f.id == id_or_url
+            || f.url == id_or_url
+        })
+        .cloned()
+    )
+  }
+
+  async fn count_registry_feeds(
+    &self,
+    added_by: i64
+  ) -> Result<i64, String> {
+    Ok(
+      self
+        .lock()
+        .registry
+        .iter()
+        .filter(|f| {
+          f.added_by == added_by
+            && f.status
+              != RegistryStatus::Rejected
+        })
+        .count() as i64
+    )
+  }
+
+  async fn insert_registry_feed(
+    &self,
+    feed: &RegistryFeed,
+    _zone: &Tz
+  ) -> Result<(), String> {
+    let mut tables = self.lock();
+
+    if tables.registry.iter().any(|f| {
+      f.id == feed.id
+        || f.url == feed.url
+    }) {
+      return Err(format!(
+        "insert_registry_feed error: \
+         '{}' already registered",
+        feed.url
+      ));
+    }
+
+    tables.registry.push(feed.clone());
+
+    Ok(())
+  }
+
+  async fn review_registry_feed(
+    &self,
+    id: &str,
+    status: RegistryStatus,
+    reviewed_at_ms: i64,
+    _zone: &Tz
+  ) -> Result<bool, String> {
+    let mut tables = self.lock();
+
+    let Some(feed) = tables
+      .registry
+      .iter_mut()
+      .find(|f| {
+        f.id == id
+          && f.status
+            == RegistryStatus::Pending
+      })
+    else {
+      return Ok(false);
+    };
+
+    feed.status = status;
+    feed.reviewed_at_ms =
+      Some(reviewed_at_ms);
+
+    Ok(true)
+  }
+}
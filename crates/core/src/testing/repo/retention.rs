@@ -0,0 +1,177 @@
+//! Retention candidates, matching the
+//! SQL backends on a database without
+//! server tables: nothing protects
+//! unread items.
+
+use std::collections::HashSet;
+
+use super::tables::MemoryTables;
+use crate::domain::retention::{
+  RetentionLimit,
+  RetentionTable
+};
+
+/// Ids of `feed_id`'s rows in `table`
+/// outside `limit`, oldest first.
+pub fn candidates(
+  tables: &MemoryTables,
+  table: RetentionTable,
+  feed_id: &str,
+  limit: RetentionLimit,
+  now_ms: i64
+) -> Vec<i64> {
+  if limit.is_unbounded() {
+    return Vec::new();
+  }
+
+  let fetched_at = |payload_id: i64| {
+    tables
+      .feed_payloads
+      .iter()
+      .find(|p| p.id == payload_id)
+      .map(|p| p.fetched_at_ms)
+  };
+
+  // (id, age_ms) of the feed's rows.
+  let rows: Vec<(i64, Option<i64>)> =
+    match table {
+      | RetentionTable::FetchEvents => {
+        tables
+          .events_for(feed_id)
+          .iter()
+          .map(|e| {
+            (e.id, Some(e.event_time_ms))
+          })
+          .collect()
+      }
+      | RetentionTable::StateHistory => {
+        tables
+          .state_history
+          .iter()
+          .filter(|h| {
+            h.state.feed_id == feed_id
+          })
+          .map(|h| {
+            (h.id, Some(h.recorded_at_ms))
+          })
+          .collect()
+      }
+      | RetentionTable::Items => {
+        tables
+          .items_for(feed_id)
+          .iter()
+          .map(|i| {
+            (i.id, fetched_at(i.payload_id))
+          })
+          .collect()
+      }
+      | RetentionTable::Payloads => {
+        tables
+          .payloads_for(feed_id)
+          .iter()
+          .map(|p| {
+            (p.id, Some(p.fetched_at_ms))
+          })
+          .collect()
+      }
+      | RetentionTable::ArchiveIndex => {
+        tables
+          .archive_index
+          .iter()
+          .filter(|a| a.feed_id == feed_id)
+          .map(|a| {
+            (a.id, Some(a.fetched_at_ms))
+          })
+          .collect()
+      }
+    };
+
+  let cutoff = limit.cutoff_ms(now_ms);
+
+  let newest: HashSet<i64> =
+    match limit.max_rows {
+      | Some(max_rows) => {
+        let mut ids: Vec<i64> = rows
+          .iter()
+          .map(|(id, _)| *id)
+          .collect();
+
+        ids.sort_unstable_by(|a, b| {
+          b.cmp(a)
+        });
+        ids.truncate(max_rows as usize);
+
+        ids.into_iter().collect()
+      }
+      | None => {
+        rows
+          .iter()
+          .map(|(id, _)| *id)
+          .collect()
+      }
+    };
+
+  let has_items: HashSet<i64> = tables
+    .feed_items
+    .iter()
+    .map(|i| i.payload_id)
+    .collect();
+
+  let mut out: Vec<i64> = rows
+    .into_iter()
+    .filter(|(id, age)| {
+      let too_old = matches!(
+        (cutoff, age),
+        (Some(c), Some(a)) if a < &c
+      );
+
+      too_old || !newest.contains(id)
+    })
+    .map(|(id, _)| id)
+    .filter(|id| {
+      table != RetentionTable::Payloads
+        || !has_items.contains(id)
+    })
+    .collect();
+
+  out.sort_unstable();
+
+  out
+}
+
+pub fn delete(
+  tables: &mut MemoryTables,
+  table: RetentionTable,
+  ids: &[i64]
+) {
+  let ids: HashSet<i64> =
+    ids.iter().copied().collect();
+
+  match table {
+    | RetentionTable::FetchEvents => {
+      tables.fetch_events.retain(|e| {
+        !ids.contains(&e.id)
+      });
+    }
+    | RetentionTable::StateHistory => {
+      tables.state_history.retain(
+        |h| !ids.contains(&h.id)
+      );
+    }
+    | RetentionTable::Items => {
+      tables.feed_items.retain(|i| {
+        !ids.contains(&i.id)
+      });
+    }
+    | RetentionTable::Payloads => {
+      tables.feed_payloads.retain(
+        |p| !ids.contains(&p.id)
+      );
+    }
+    | RetentionTable::ArchiveIndex => {
+      tables.archive_index.retain(
+        |a| !ids.contains(&a.id)
+      );
+    }
+  }
+}
@@ -0,0 +1,180 @@
+//! Rows kept by `MemoryRepo`, one
+//! collection per fetcher table.
+
+use std::collections::{
+  BTreeMap,
+  BTreeSet,
+  HashSet
+};
+
+use crate::domain::model::{
+  ErrorKind,
+  FeedConfig
+};
+use crate::domain::registry::RegistryFeed;
+use crate::feed::parser::{
+  FeedItem,
+  FeedMetadata
+};
+use crate::ports::repo::StateRow;
+
+#[derive(Debug, Clone)]
+pub struct HistoryRow {
+  pub id:             i64,
+  pub recorded_at_ms: i64,
+  pub state:          StateRow
+}
+
+#[derive(Debug, Clone)]
+pub struct EventRow {
+  pub id: i64,
+  pub feed_id: String,
+  pub event_time_ms: i64,
+  pub method: String,
+  pub status: Option<i64>,
+  pub error_kind: Option<ErrorKind>,
+  pub latency_ms: Option<i64>,
+  pub backoff_index: i64,
+  pub scheduled_next_action_at_ms: i64,
+  pub debug: Option<String>
+}
+
+#[derive(Debug, Clone)]
+pub struct PayloadRow {
+  pub id:               i64,
+  pub feed_id:          String,
+  pub fetched_at_ms:    i64,
+  pub etag:             Option<String>,
+  pub last_modified_ms: Option<i64>,
+  pub content_hash:     Option<String>,
+  pub metadata:         FeedMetadata
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemRow {
+  pub id:         i64,
+  pub payload_id: i64,
+  pub feed_id:    String,
+  pub item:       FeedItem
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveIndexRow {
+  pub id:            i64,
+  pub feed_id:       String,
+  pub fetched_at_ms: i64,
+  pub content_hash:  String
+}
+
+#[derive(Debug, Clone)]
+pub struct ParkedFeedRow {
+  pub error_count:       i64,
+  pub last_error_kind:
+    Option<ErrorKind>,
+  pub last_error_status: Option<i64>,
+  pub last_error_at_ms:  i64
+}
+
+#[derive(Debug, Clone)]
+pub struct RedirectRow {
+  pub from_url:         String,
+  pub suggested_url:    String,
+  pub status:           i64,
+  pub hit_count:        i64,
+  pub first_seen_at_ms: i64,
+  pub last_seen_at_ms:  i64
+}
+
+/// Everything `MemoryRepo` stores;
+/// tests read it through
+/// `MemoryRepo::read`.
+#[derive(Debug, Default)]
+pub struct MemoryTables {
+  pub categories:    BTreeSet<String>,
+  pub feeds:
+    BTreeMap<String, FeedConfig>,
+  pub state_current:
+    BTreeMap<String, StateRow>,
+  pub state_history: Vec<HistoryRow>,
+  pub fetch_events:  Vec<EventRow>,
+  pub feed_payloads: Vec<PayloadRow>,
+  pub feed_items:    Vec<ItemRow>,
+  pub error_feeds:
+    BTreeMap<String, ParkedFeedRow>,
+  pub cookies: BTreeMap<String, String>,
+  pub snapshots:
+    BTreeMap<String, String>,
+  pub redirects:
+    BTreeMap<String, RedirectRow>,
+  pub archive:
+    BTreeMap<String, (i64, Vec<u8>)>,
+  pub archive_index:
+    Vec<ArchiveIndexRow>,
+  pub registry:      Vec<RegistryFeed>,
+  next_id:           i64
+}
+
+impl MemoryTables {
+  /// Ids are unique across tables,
+  /// which keeps `id DESC` ordering
+  /// per table intact.
+  pub(super) fn next_id(
+    &mut self
+  ) -> i64 {
+    self.next_id += 1;
+    self.next_id
+  }
+
+  pub fn events_for(
+    &self,
+    feed_id: &str
+  ) -> Vec<&EventRow> {
+    self
+      .fetch_events
+      .iter()
+      .filter(|e| e.feed_id == feed_id)
+      .collect()
+  }
+
+  pub fn payloads_for(
+    &self,
+    feed_id: &str
+  ) -> Vec<&PayloadRow> {
+    self
+      .feed_payloads
+      .iter()
+      .filter(|p| p.feed_id == feed_id)
+      .collect()
+  }
+
+  /// Content hashes a payload or an
+  /// archive index row refers to.
+  pub fn referenced_hashes(
+    &self
+  ) -> HashSet<&String> {
+    self
+      .feed_payloads
+      .iter()
+      .filter_map(|p| {
+        p.content_hash.as_ref()
+      })
+      .chain(
+        self
+          .archive_index
+          .iter()
+          .map(|a| &a.content_hash)
+      )
+      .collect()
+  }
+
+  pub fn items_for(
+    &self,
+    feed_id: &str
+  ) -> Vec<&ItemRow> {
+    self
+      .feed_items
+      .iter()
+      .filter(|i| i.feed_id == feed_id)
+      .collect()
+  }
+}
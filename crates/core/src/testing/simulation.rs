@@ -0,0 +1,368 @@
+//! Drives `run_tick` for every category
+//! on a virtual clock, the way the
+//! fetcher's scheduler loop does.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{
+  Duration,
+  Instant
+};
+
+use super::clock::{
+  SeededRandom,
+  VirtualClock
+};
+use super::http::FakeHttp;
+use super::repo::MemoryRepo;
+use crate::app::context::AppContext;
+use crate::app::scheduler::{
+  ConcurrencyGuards,
+  TICK_INTERVAL_SECS,
+  run_tick
+};
+use crate::domain::model::{
+  AppConfig,
+  FeedConfig,
+  WatchCheckMethod,
+  WatchConfig,
+  WatchDetector,
+  WatchEmitMode,
+  WatchExtractorKind
+};
+use crate::domain::text_diff::DEFAULT_MAX_DIFF_BYTES;
+use crate::ports::repo::Repo;
+
+/// 2024-01-01T00:00:00Z.
+pub const DEFAULT_START_MS: i64 =
+  1_704_067_200_000;
+
+pub struct Simulation {
+  pub cfg:         Arc<AppConfig>,
+  pub repo:        Arc<MemoryRepo>,
+  pub http:        Arc<FakeHttp>,
+  /// Answers registry feeds, as the
+  /// fetcher's public-only client.
+  pub public_http: Arc<FakeHttp>,
+  pub clock:       Arc<VirtualClock>,
+  ctx: AppContext<
+    MemoryRepo,
+    FakeHttp,
+    VirtualClock,
+    SeededRandom
+  >,
+  concurrency:     ConcurrencyGuards,
+  categories:      Vec<String>,
+  ticks:           u64
+}
+
+impl Simulation {
+  /// Stores `feeds` and their
+  /// categories; script responses on
+  /// `http` before running.
+  pub async fn new(
+    cfg: AppConfig,
+    feeds: Vec<FeedConfig>,
+    seed: u64
+  ) -> Result<Simulation, String> {
+    Self::with_watches(
+      cfg,
+      feeds,
+      Vec::new(),
+      seed
+    )
+    .await
+  }
+
+  /// Like `new`, with `watches` stored
+  /// as feeds the way the fetcher does.
+  pub async fn with_watches(
+    cfg: AppConfig,
+    mut feeds: Vec<FeedConfig>,
+    watches: Vec<WatchConfig>,
+    seed: u64
+  ) -> Result<Simulation, String> {
+    let cfg = Arc::new(cfg);
+
+    feeds.extend(
+      watches.iter().map(watch_feed)
+    );
+
+    let watches_by_id: HashMap<
+      String,
+      WatchConfig
+    > = watches
+      .into_iter()
+      .map(|w| (w.id.clone(), w))
+      .collect();
+
+    let clock =
+      Arc::new(VirtualClock::new(
+        DEFAULT_START_MS
+      ));
+
+    let repo =
+      Arc::new(MemoryRepo::with_clock(
+        clock.clone()
+      ));
+
+    let http = Arc::new(FakeHttp::new(
+      clock.clone()
+    ));
+
+    let public_http = Arc::new(
+      FakeHttp::new(clock.clone())
+    );
+
+    let mut categories: Vec<String> =
+      feeds
+        .iter()
+        .map(|f| f.category.clone())
+        .collect();
+
+    categories.sort();
+    categories.dedup();
+
+    repo
+      .upsert_categories(
+        categories.clone(),
+        &cfg.timezone
+      )
+      .await?;
+
+    repo
+      .upsert_feeds_bulk(
+        feeds,
+        1000,
+        &cfg.timezone
+      )
+      .await?;
+
+    let ctx = AppContext {
+      cfg:                 cfg.clone(),
+      repo:                repo.clone(),
+      http:                http.clone(),
+      public_http:         public_http
+        .clone(),
+      clock:               clock
+        .clone(),
+      rng:                 Arc::new(
+        SeededRandom::new(seed)
+      ),
+      watches_by_id:       Arc::new(
+        watches_by_id
+      ),
+      cookie_header_by_id: Arc::new(
+        HashMap::new()
+      ),
+      extra_headers_by_id: Arc::new(
+        HashMap::new()
+      )
+    };
+
+    Ok(Simulation {
+      concurrency:
+        ConcurrencyGuards::new(
+          cfg.clone()
+        ),
+      cfg,
+      repo,
+      http,
+      public_http,
+      clock,
+      ctx,
+      categories,
+      ticks: 0
+    })
+  }
+
+  /// One scheduler tick over every
+  /// category.
+  pub async fn tick(
+    &mut self
+  ) -> Result<(), String> {
+    for category in &self.categories {
+      run_tick(
+        &self.ctx,
+        &self.concurrency,
+        Instant::now(),
+        category
+      )
+      .await?;
+    }
+
+    self.ticks += 1;
+
+    Ok(())
+  }
+
+  /// Ticks every `TICK_INTERVAL_SECS`
+  /// of virtual time for `duration`.
+  pub async fn run_for(
+    &mut self,
+    duration: Duration
+  ) -> Result<(), String> {
+    let interval = Duration::from_secs(
+      TICK_INTERVAL_SECS
+    );
+
+    let end =
+      self.clock.elapsed() + duration;
+
+    while self.clock.elapsed() < end {
+      self.tick().await?;
+
+      tokio::time::sleep(interval)
+        .await;
+    }
+
+    Ok(())
+  }
+
+  /// Sends `cookie` with every request
+  /// for `feed_id`, as a configured
+  /// cookie file does.
+  pub fn set_cookie(
+    &mut self,
+    feed_id: &str,
+    cookie: &str
+  ) {
+    Arc::make_mut(
+      &mut self.ctx.cookie_header_by_id
+    )
+    .insert(
+      feed_id.to_string(),
+      cookie.to_string()
+    );
+  }
+
+  pub fn ticks(&self) -> u64 {
+    self.ticks
+  }
+
+  pub fn now_ms(&self) -> i64 {
+    self.clock.now_ms()
+  }
+}
+
+/// A feed polled every
+/// `base_poll_seconds`.
+pub fn feed(
+  id: &str,
+  url: &str,
+  domain: &str,
+  category: &str,
+  base_poll_seconds: u64
+) -> FeedConfig {
+  FeedConfig {
+    id: id.to_string(),
+    url: url.to_string(),
+    domain: domain.to_string(),
+    category: category.to_string(),
+    base_poll_seconds,
+    provenance: None,
+    tags: None,
+    language: None,
+    content_type: None,
+    cookie_path: None,
+    headers_path: None,
+    headers: None
+  }
+}
+
+/// A watch with the loader's defaults
+/// that GETs the page every poll and
+/// extracts `item_selector` with CSS.
+pub fn watch(
+  id: &str,
+  url: &str,
+  domain: &str,
+  category: &str,
+  base_poll_seconds: u64,
+  item_selector: &str
+) -> WatchConfig {
+  WatchConfig {
+    id: id.to_string(),
+    url: url.to_string(),
+    domain: domain.to_string(),
+    category: category.to_string(),
+    base_poll_seconds,
+    provenance: None,
+    tags: None,
+    language: None,
+    content_type: None,
+    cookie_path: None,
+    headers_path: None,
+    headers: None,
+    check_method: WatchCheckMethod::Get,
+    fallback_to_get: true,
+    detectors: vec![
+      WatchDetector::ContentHash,
+    ],
+    fetch_body_on_change: true,
+    max_body_bytes: None,
+    max_items_per_fetch: None,
+    next_page_selector: None,
+    max_pages: 1,
+    max_diff_bytes:
+      DEFAULT_MAX_DIFF_BYTES,
+    item_selector: Some(
+      item_selector.to_string()
+    ),
+    item_identity: None,
+    item_identity_attr: None,
+    extractor: WatchExtractorKind::Css,
+    title_extractor: None,
+    link_extractor: None,
+    summary_extractor: None,
+    published_extractor: None,
+    title_selector: None,
+    link_selector: None,
+    summary_selector: None,
+    published_selector: None,
+    published_format: None,
+    include_selectors: None,
+    exclude_selectors: None,
+    normalize_whitespace: true,
+    strip_query_params: false,
+    emit_mode:
+      WatchEmitMode::NewItemsOnly,
+    emit_title: None,
+    min_item_count_change: None
+  }
+}
+
+/// The feed row the fetcher stores for
+/// a watch.
+fn watch_feed(
+  w: &WatchConfig
+) -> FeedConfig {
+  FeedConfig {
+    provenance: w
+      .provenance
+      .clone()
+      .or_else(|| {
+        Some("ad-hoc-watch".to_string())
+      }),
+    tags: w.tags.clone(),
+    language: w.language.clone(),
+    content_type: w
+      .content_type
+      .clone()
+      .or_else(|| {
+        Some("text/html".to_string())
+      }),
+    cookie_path: w.cookie_path.clone(),
+    headers_path: w
+      .headers_path
+      .clone(),
+    headers: w.headers.clone(),
+    ..feed(
+      &w.id,
+      &w.url,
+      &w.domain,
+      &w.category,
+      w.base_poll_seconds
+    )
+  }
+}
@@ -0,0 +1,285 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use pulsewire_core::app::reparse::reparse_feed;
+use pulsewire_core::domain::archive::{
+  ArchiveConfig,
+  ArchiveStore,
+  ArchivedResponse
+};
+use pulsewire_core::domain::hashing::sha256_hex;
+use pulsewire_core::infra::archive::PayloadArchive;
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::Repo;
+use pulsewire_core::testing::{
+  FakeResponse,
+  Simulation,
+  feed,
+  sim_config
+};
+
+fn scratch_dir(name: &str) -> PathBuf {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-{name}-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  dir
+}
+
+#[test]
+
+fn record_roundtrips_headers_and_body()
+{
+  let response = ArchivedResponse {
+    headers: vec![
+      (
+        "content-type".to_string(),
+        "application/rss+xml"
+          .to_string()
+      ),
+      (
+        "etag".to_string(),
+        String::new()
+      ),
+    ],
+    body:    b"<rss>\n\n</rss>"
+      .to_vec()
+  };
+
+  assert_eq!(
+    ArchivedResponse::decode(
+      &response.encode()
+    )
+    .unwrap(),
+    response
+  );
+}
+
+#[tokio::test]
+
+async fn blobs_dedupe_and_orphans_prune()
+ {
+  let dir = scratch_dir("archive");
+
+  let repo =
+    SqliteRepo::new(&dir.join("t.db"))
+      .await
+      .unwrap();
+
+  repo
+    .migrate(&chrono_tz::UTC, 60)
+    .await
+    .unwrap();
+
+  let response = ArchivedResponse {
+    headers: vec![(
+      "etag".to_string(),
+      "\"v1\"".to_string()
+    )],
+    body:    b"<rss/>".to_vec()
+  };
+
+  let hash = sha256_hex(&response.body);
+
+  for store in [
+    ArchiveStore::Filesystem,
+    ArchiveStore::Database
+  ] {
+    let archive =
+      PayloadArchive::from_config(
+        &ArchiveConfig {
+          enabled: true,
+          store,
+          directory: dir.join("blobs"),
+          zstd_level: 3
+        }
+      )
+      .unwrap();
+
+    assert!(
+      archive
+        .put(&repo, &hash, &response, 1)
+        .await
+        .unwrap()
+    );
+    assert!(
+      !archive
+        .put(&repo, &hash, &response, 2)
+        .await
+        .unwrap()
+    );
+    assert_eq!(
+      archive
+        .get(&repo, &hash)
+        .await
+        .unwrap(),
+      Some(response.clone())
+    );
+
+    // No payload references the hash.
+    assert_eq!(
+      archive
+        .prune_orphans(&repo, 10, false)
+        .await
+        .unwrap(),
+      1
+    );
+    assert_eq!(
+      archive
+        .get(&repo, &hash)
+        .await
+        .unwrap(),
+      None
+    );
+  }
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn unparsable_bodies_are_archived_and_reparsed()
+ {
+  let url = "https://a.example/rss";
+
+  let mut cfg = sim_config();
+
+  cfg.archive = ArchiveConfig {
+    enabled:    true,
+    store:      ArchiveStore::Database,
+    directory:  PathBuf::new(),
+    zstd_level: 3
+  };
+
+  let mut sim = Simulation::new(
+    cfg.clone(),
+    vec![feed(
+      "f1",
+      url,
+      "a.example",
+      "news",
+      300
+    )],
+    1
+  )
+  .await
+  .unwrap();
+
+  sim.http.respond(
+    url,
+    FakeResponse::ok(
+      b"not a feed".to_vec()
+    )
+  );
+
+  sim
+    .run_for(Duration::from_secs(60))
+    .await
+    .unwrap();
+
+  assert_eq!(
+    sim.http.count("GET", url),
+    1
+  );
+  sim.repo.read(|t| {
+    assert!(
+      t.payloads_for("f1").is_empty()
+    );
+  });
+
+  let refs = sim
+    .repo
+    .archive_refs_since("f1", 0)
+    .await
+    .unwrap();
+
+  assert_eq!(refs.len(), 1);
+  assert_eq!(refs[0].payload_id, None);
+
+  let summary = reparse_feed(
+    sim.repo.as_ref(),
+    &cfg,
+    "f1",
+    0
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(
+    (
+      summary.bodies,
+      summary.missing,
+      summary.failed
+    ),
+    (1, 0, 1)
+  );
+
+  // A body indexed without a payload,
+  // as one an older parser rejected,
+  // gets a payload of its own.
+  let response = ArchivedResponse {
+    headers: Vec::new(),
+    body:    b"<?xml version=\"1.0\"?><rss \
+               version=\"2.0\"><channel><title>t</\
+               title><item><guid>g1</guid></\
+               item></channel></rss>"
+      .to_vec()
+  };
+  let hash = sha256_hex(&response.body);
+
+  PayloadArchive::from_config(
+    &cfg.archive
+  )
+  .unwrap()
+  .put(
+    sim.repo.as_ref(),
+    &hash,
+    &response,
+    1
+  )
+  .await
+  .unwrap();
+  sim
+    .repo
+    .insert_archive_ref("f1", 1, &hash)
+    .await
+    .unwrap();
+
+  for inserted in [1, 0] {
+    let summary = reparse_feed(
+      sim.repo.as_ref(),
+      &cfg,
+      "f1",
+      0
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(summary.bodies, 2);
+    assert_eq!(summary.failed, 1);
+    assert_eq!(
+      summary.inserted,
+      inserted
+    );
+  }
+
+  sim.repo.read(|t| {
+    let payloads = t.payloads_for("f1");
+
+    assert_eq!(payloads.len(), 1);
+    assert_eq!(
+      payloads[0].content_hash,
+      Some(hash.clone())
+    );
+    assert_eq!(
+      t.items_for("f1").len(),
+      1
+    );
+  });
+}
@@ -0,0 +1,308 @@
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use pulsewire_core::domain::model::AppConfig;
+use pulsewire_core::feed::parser;
+use pulsewire_core::infra::backup::{
+  BackupKind,
+  MANIFEST_FILE,
+  backup_database,
+  backup_name,
+  list_backups,
+  restore_database,
+  rotate_backups
+};
+use pulsewire_core::infra::migrations;
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::Repo;
+use pulsewire_core::testing::{
+  feed,
+  sim_config
+};
+
+const UTC: chrono_tz::Tz =
+  chrono_tz::UTC;
+
+fn scratch(name: &str) -> PathBuf {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-backup-{name}-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  dir
+}
+
+fn config(db: &Path) -> AppConfig {
+  let mut cfg = sim_config();
+
+  cfg.sqlite_path = db.to_path_buf();
+
+  cfg
+}
+
+/// A fetcher and server database with
+/// one feed and one stored payload.
+async fn seeded(
+  db: &Path
+) -> AppConfig {
+  let cfg = config(db);
+
+  let repo =
+    SqliteRepo::new(db).await.unwrap();
+
+  repo.migrate(&UTC, 60).await.unwrap();
+
+  migrations::migrate_server(
+    &cfg, "server"
+  )
+  .await
+  .unwrap();
+
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  repo
+    .upsert_feeds_bulk(
+      vec![feed(
+        "a",
+        "https://a.example/rss",
+        "a.example",
+        "news",
+        300
+      )],
+      100,
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  repo
+    .insert_payload_with_items(
+      "a",
+      1_000,
+      Some("v1"),
+      None,
+      None,
+      &parser::parse(
+        b"<rss version=\"2.0\"><channel>\
+          <title>t</title><item><guid>\
+          x</guid></item></channel></rss>"
+      )
+      .unwrap(),
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  cfg
+}
+
+#[tokio::test]
+
+async fn snapshot_and_logical_backups_restore_verified()
+ {
+  let dir = scratch("roundtrip");
+
+  let source =
+    seeded(&dir.join("source.db"))
+      .await;
+
+  for (name, logical, kind) in [
+    (
+      "snap",
+      false,
+      BackupKind::SqliteFile
+    ),
+    ("dump", true, BackupKind::Logical)
+  ] {
+    let dest = dir.join(name);
+
+    let manifest = backup_database(
+      &source, &dest, "server",
+      logical, 3, 5_000
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(manifest.kind, kind);
+    assert!(
+      manifest.server_version.is_some()
+    );
+    assert!(
+      manifest.tables.iter().any(|t| {
+        t.name == "feed_items"
+          && t.rows == 1
+      })
+    );
+
+    let target = config(
+      &dir.join(format!("{name}.db"))
+    );
+
+    let report = restore_database(
+      &target, &dest, "server", false
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+      report.checks.len(),
+      manifest.tables.len()
+    );
+    assert!(
+      report
+        .checks
+        .iter()
+        .all(|c| c.matches())
+    );
+
+    // The target now holds rows.
+    let refused = restore_database(
+      &target, &dest, "server", false
+    )
+    .await
+    .unwrap_err();
+
+    assert!(
+      refused.contains("--force")
+    );
+
+    restore_database(
+      &target, &dest, "server", true
+    )
+    .await
+    .unwrap();
+  }
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+
+async fn restore_refuses_incompatible_versions()
+ {
+  let dir = scratch("versions");
+
+  let source =
+    seeded(&dir.join("source.db"))
+      .await;
+
+  let dest = dir.join("dump");
+
+  backup_database(
+    &source, &dest, "server", true, 3,
+    5_000
+  )
+  .await
+  .unwrap();
+
+  let manifest_path =
+    dest.join(MANIFEST_FILE);
+
+  let original =
+    std::fs::read_to_string(
+      &manifest_path
+    )
+    .unwrap();
+
+  let target =
+    config(&dir.join("target.db"));
+
+  for (version, expected) in [
+    (99, "newer than this build"),
+    (1, "restore it with the build")
+  ] {
+    let mut manifest: serde_json::Value =
+      serde_json::from_str(&original)
+        .unwrap();
+
+    manifest["fetcher_version"] =
+      version.into();
+
+    std::fs::write(
+      &manifest_path,
+      manifest.to_string()
+    )
+    .unwrap();
+
+    let error = restore_database(
+      &target, &dest, "server", false
+    )
+    .await
+    .unwrap_err();
+
+    assert!(
+      error.contains(expected),
+      "{error}"
+    );
+  }
+
+  assert!(
+    !dir.join("target.db").exists()
+  );
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn rotation_keeps_newest_finished_backups()
+ {
+  let dir = scratch("rotation");
+
+  let names: Vec<String> = (0..4)
+    .map(|day| {
+      backup_name(day * 86_400_000)
+    })
+    .collect();
+
+  for name in &names {
+    std::fs::create_dir_all(
+      dir.join(name)
+    )
+    .unwrap();
+    std::fs::write(
+      dir
+        .join(name)
+        .join(MANIFEST_FILE),
+      "{}"
+    )
+    .unwrap();
+  }
+
+  // An unfinished run from day 1.
+  std::fs::remove_file(
+    dir
+      .join(&names[1])
+      .join(MANIFEST_FILE)
+  )
+  .unwrap();
+
+  let removed =
+    rotate_backups(&dir, 2).unwrap();
+
+  assert_eq!(removed, [
+    dir.join(&names[0]),
+    dir.join(&names[1])
+  ]);
+  assert_eq!(
+    list_backups(&dir).unwrap(),
+    [
+      dir.join(&names[2]),
+      dir.join(&names[3])
+    ]
+  );
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
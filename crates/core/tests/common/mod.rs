@@ -0,0 +1,136 @@
+//! Config bundles for tests that go
+//! through `ConfigLoader`. `a.example`,
+//! `b.example` and `c.example` sit in
+//! one `news` category.
+
+use std::path::{
+  Path,
+  PathBuf
+};
+
+use pulsewire_core::infra::config::{
+  ConfigError,
+  ConfigLoader,
+  LoadedConfig
+};
+
+/// A `[[domains]]` entry for `name`
+/// with extra `fields`.
+pub fn domain(
+  name: &str,
+  fields: &[&str]
+) -> String {
+  [
+    "[[domains]]",
+    &format!("name = \"{name}\""),
+    "max_concurrent_requests = 1"
+  ]
+  .iter()
+  .chain(fields)
+  .map(|line| format!("{line}\n"))
+  .collect()
+}
+
+/// A config directory holding the
+/// fetcher's sample `config.toml`, the
+/// schemas, `domains` and one feeds
+/// file of `feeds` lines.
+fn config_dir(
+  name: &str,
+  domains: &[String],
+  feeds: &[&str]
+) -> PathBuf {
+  let manifest = Path::new(env!(
+    "CARGO_MANIFEST_DIR"
+  ));
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-config-{name}-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(
+    dir.join("feeds")
+  )
+  .unwrap();
+  std::fs::create_dir_all(
+    dir.join("schemas")
+  )
+  .unwrap();
+
+  std::fs::copy(
+    manifest.join(
+      "../fetcher/res/config.toml"
+    ),
+    dir.join("config.toml")
+  )
+  .unwrap();
+
+  for schema in std::fs::read_dir(
+    manifest.join("../schemas/fetcher")
+  )
+  .unwrap()
+  {
+    let schema = schema.unwrap().path();
+
+    std::fs::copy(
+      &schema,
+      dir.join("schemas").join(
+        schema.file_name().unwrap()
+      )
+    )
+    .unwrap();
+  }
+
+  let files = [
+    (
+      "feeds/news.toml",
+      feeds.join("\n")
+    ),
+    (
+      "categories.toml",
+      [
+        "[[categories]]",
+        "name = \"news\"",
+        "domains = [\"a.example\", \
+         \"b.example\", \"c.example\"]"
+      ]
+      .join("\n")
+    ),
+    (
+      "domains.toml",
+      domains.join("\n")
+    )
+  ];
+
+  for (file, content) in files {
+    std::fs::write(
+      dir.join(file),
+      content
+    )
+    .unwrap();
+  }
+
+  dir
+}
+
+/// Loads the config directory the way
+/// the fetcher does at startup.
+pub async fn load(
+  name: &str,
+  domains: &[String],
+  feeds: &[&str]
+) -> Result<LoadedConfig, ConfigError> {
+  let dir =
+    config_dir(name, domains, feeds);
+
+  let loaded = ConfigLoader::load(
+    &dir.join("config.toml")
+  )
+  .await;
+
+  let _ = std::fs::remove_dir_all(&dir);
+
+  loaded
+}
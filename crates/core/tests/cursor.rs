@@ -0,0 +1,102 @@
+use pulsewire_core::domain::cursor::{
+  Cursor,
+  Direction,
+  SortKey,
+  SortOrder
+};
+
+const SECRET: &[u8] = b"cursor-test";
+
+#[test]
+fn cursors_round_trip_signed() {
+  for (sort, key, id) in [
+    (
+      SortOrder::Newest,
+      SortKey::Millis(
+        1_700_000_000_123
+      ),
+      "42"
+    ),
+    (
+      SortOrder::Relevance,
+      SortKey::Rank(0.1 + 0.2),
+      "7"
+    ),
+    (
+      SortOrder::Oldest,
+      SortKey::Millis(-5),
+      "feed/with spaces"
+    )
+  ] {
+    let cursor = Cursor {
+      sort,
+      direction: Direction::Before,
+      key,
+      id: id.to_string()
+    };
+
+    let token = cursor.encode(SECRET);
+
+    assert!(token.chars().all(|c| {
+      c.is_ascii_alphanumeric()
+        || "-_.".contains(c)
+    }));
+    assert_eq!(
+      Cursor::decode(&token, SECRET),
+      Ok(cursor)
+    );
+  }
+}
+
+#[test]
+fn tampered_or_foreign_cursors_are_rejected()
+ {
+  let token = Cursor {
+    sort:      SortOrder::Newest,
+    direction: Direction::After,
+    key:       SortKey::Millis(1_000),
+    id:        "1".to_string()
+  }
+  .encode(SECRET);
+
+  let (body, signature) =
+    token.split_once('.').unwrap();
+
+  let forged = Cursor {
+    sort:      SortOrder::Newest,
+    direction: Direction::After,
+    key:       SortKey::Millis(1_000),
+    id:        "2".to_string()
+  }
+  .encode(b"other secret");
+
+  let forged_body =
+    forged.split_once('.').unwrap().0;
+
+  for bad in [
+    format!(
+      "{forged_body}.{signature}"
+    ),
+    format!(
+      "{body}.{}",
+      &signature[..8]
+    ),
+    format!("{body}."),
+    body.to_string(),
+    forged.clone(),
+    "not a cursor".to_string()
+  ] {
+    assert_eq!(
+      Cursor::decode(&bad, SECRET),
+      Err("invalid cursor".to_string()),
+      "{bad}"
+    );
+  }
+
+  assert_eq!(
+    Cursor::decode(&token, SECRET)
+      .unwrap()
+      .int_id(),
+    Ok(1)
+  );
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+
+use pulsewire_core::infra::db_copy::tables::TABLES;
+use pulsewire_core::infra::migrations::{
+  self,
+  Component
+};
+use pulsewire_core::infra::sqlite_repo;
+
+#[tokio::test]
+
+async fn catalog_matches_migrated_sqlite_schema()
+ {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-db-copy-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  let pool = sqlite_repo::create_pool(
+    &dir.join("copy.db")
+  )
+  .await
+  .unwrap();
+
+  for component in [
+    Component::Fetcher,
+    Component::Server
+  ] {
+    migrations::sqlite::apply_pending(
+      &pool, component
+    )
+    .await
+    .unwrap();
+  }
+
+  // The search index and its shadow
+  // tables are rebuilt by triggers.
+  let tables: BTreeSet<String> =
+    sqlx::query_scalar(
+      "SELECT name FROM sqlite_master \
+       WHERE type = 'table' AND name \
+       NOT IN ('sqlite_sequence', \
+       'schema_migrations') AND name \
+       NOT LIKE 'feed_items_fts%'"
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .collect();
+
+  let listed: BTreeSet<String> = TABLES
+    .iter()
+    .map(|t| t.name.to_string())
+    .collect();
+
+  assert_eq!(tables, listed);
+
+  for table in TABLES {
+    let columns: BTreeSet<String> =
+      sqlx::query_scalar(
+        "SELECT name FROM \
+         pragma_table_info(?1)"
+      )
+      .bind(table.name)
+      .fetch_all(&pool)
+      .await
+      .unwrap()
+      .into_iter()
+      .collect();
+
+    let listed: BTreeSet<String> =
+      table
+        .columns
+        .iter()
+        .map(|c| c.sqlite.to_string())
+        .collect();
+
+    assert_eq!(
+      columns, listed,
+      "{}",
+      table.name
+    );
+  }
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use pulsewire_core::domain::destination::is_public_ip;
+use pulsewire_core::domain::model::ErrorKind;
+use pulsewire_core::infra::reqwest_http::{
+  ReqwestHttp,
+  check_public_url
+};
+use pulsewire_core::ports::http::Http;
+use tokio::io::{
+  AsyncReadExt,
+  AsyncWriteExt
+};
+use tokio::net::TcpListener;
+
+fn ip(s: &str) -> IpAddr {
+  s.parse().unwrap()
+}
+
+#[test]
+fn only_global_unicast_is_public() {
+  for public in [
+    "93.184.216.34",
+    "1.1.1.1",
+    "2606:4700:4700::1111"
+  ] {
+    assert!(
+      is_public_ip(ip(public)),
+      "{public}"
+    );
+  }
+
+  for internal in [
+    "127.0.0.1",
+    "10.1.2.3",
+    "172.16.0.1",
+    "192.168.1.1",
+    "169.254.169.254",
+    "0.0.0.0",
+    "100.64.0.1",
+    "255.255.255.255",
+    "224.0.0.1",
+    "::",
+    "::1",
+    "fe80::1",
+    "fd00::1",
+    "::ffff:127.0.0.1",
+    "::ffff:169.254.169.254",
+    "64:ff9b::a00:1"
+  ] {
+    assert!(
+      !is_public_ip(ip(internal)),
+      "{internal}"
+    );
+  }
+}
+
+#[tokio::test]
+async fn urls_must_reach_public_hosts()
+{
+  for url in [
+    "http://127.0.0.1/hook",
+    "http://[::1]:8080/",
+    "http://169.254.169.254/latest/meta-data/",
+    "http://localhost:9999/",
+    "ftp://93.184.216.34/feed",
+    "not a url"
+  ] {
+    assert!(
+      check_public_url(url)
+        .await
+        .is_err(),
+      "{url}"
+    );
+  }
+
+  assert!(
+    check_public_url(
+      "https://93.184.216.34/feed"
+    )
+    .await
+    .is_ok()
+  );
+}
+
+/// Answers every connection on a
+/// loopback port with `response`.
+async fn serve(
+  response: &'static [u8]
+) -> u16 {
+  let listener =
+    TcpListener::bind("127.0.0.1:0")
+      .await
+      .unwrap();
+  let port = listener
+    .local_addr()
+    .unwrap()
+    .port();
+
+  tokio::spawn(async move {
+    while let Ok((mut socket, _)) =
+      listener.accept().await
+    {
+      let mut buf = [0u8; 1024];
+      let _ =
+        socket.read(&mut buf).await;
+      let _ = socket
+        .write_all(response)
+        .await;
+    }
+  });
+
+  port
+}
+
+#[tokio::test]
+async fn public_only_client_refuses_internal_hosts()
+ {
+  let port = serve(
+    b"HTTP/1.1 200 OK\r\n\
+      Content-Length: 2\r\n\
+      Connection: close\r\n\r\nok"
+  )
+  .await;
+
+  let open = ReqwestHttp::new(
+    "test".into(),
+    &HashMap::new()
+  )
+  .unwrap();
+  let public =
+    ReqwestHttp::public_only(
+      "test".into()
+    )
+    .unwrap();
+
+  let by_ip =
+    format!("http://127.0.0.1:{port}/");
+
+  assert_eq!(
+    open
+      .get(
+        "127.0.0.1",
+        &by_ip,
+        None,
+        None
+      )
+      .await
+      .status,
+    Some(200)
+  );
+
+  for url in [
+    by_ip.clone(),
+    format!("http://localhost:{port}/")
+  ] {
+    let res = public
+      .get(
+        "localhost",
+        &url,
+        None,
+        None
+      )
+      .await;
+
+    assert_eq!(
+      res.status, None,
+      "{url}"
+    );
+    assert_eq!(
+      res.error,
+      Some(
+        ErrorKind::ConnectionFailure
+      ),
+      "{url}"
+    );
+
+    let res = public
+      .post(
+        "localhost",
+        &url,
+        &HashMap::new(),
+        Vec::new()
+      )
+      .await;
+
+    assert_eq!(
+      res.status, None,
+      "{url}"
+    );
+  }
+}
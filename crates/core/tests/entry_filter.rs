@@ -0,0 +1,131 @@
+use pulsewire_core::domain::entry_filter::{
+  Condition,
+  FilterTerm,
+  RuleAction,
+  parse_filter
+};
+
+fn term(
+  condition: Condition,
+  negated: bool
+) -> FilterTerm {
+  FilterTerm {
+    condition,
+    negated
+  }
+}
+
+#[test]
+fn filter_expression_parses_every_condition()
+ {
+  let filter = parse_filter(
+    "feed:arxiv-cs category:News \
+     -tag:ml author:\"Jane Doe\" \
+     title:rust \"async runtime\" \
+     is:unread age:<2w -age:>12h"
+  )
+  .unwrap();
+
+  assert_eq!(filter.terms, vec![
+    term(
+      Condition::Feed(
+        "arxiv-cs".to_string()
+      ),
+      false
+    ),
+    term(
+      Condition::Category(
+        "News".to_string()
+      ),
+      false
+    ),
+    term(
+      Condition::Tag("ml".to_string()),
+      true
+    ),
+    term(
+      Condition::Author(
+        "Jane Doe".to_string()
+      ),
+      false
+    ),
+    term(
+      Condition::Title(
+        "rust".to_string()
+      ),
+      false
+    ),
+    term(
+      Condition::Text(
+        "async runtime".to_string()
+      ),
+      false
+    ),
+    term(Condition::Read(false), false),
+    term(
+      Condition::NewerThan(
+        14 * 86_400_000
+      ),
+      false
+    ),
+    term(
+      Condition::OlderThan(
+        12 * 3_600_000
+      ),
+      true
+    ),
+  ]);
+}
+
+#[test]
+fn filter_expression_rejects_bad_values()
+ {
+  for expr in [
+    "",
+    "  ",
+    "is:starred",
+    "age:7",
+    "age:<0d",
+    "age:>xd",
+    "age:<99999999999999999w"
+  ] {
+    assert!(
+      parse_filter(expr).is_err(),
+      "{expr:?} should not parse"
+    );
+  }
+
+  assert_eq!(
+    parse_filter("age:3D")
+      .unwrap()
+      .terms,
+    vec![term(
+      Condition::NewerThan(
+        3 * 86_400_000
+      ),
+      false
+    )]
+  );
+}
+
+#[test]
+fn rule_actions_round_trip() {
+  for action in [
+    RuleAction::Read,
+    RuleAction::Star,
+    RuleAction::Label,
+    RuleAction::Hide
+  ] {
+    assert_eq!(
+      RuleAction::parse(
+        action.as_str()
+      ),
+      Ok(action)
+    );
+  }
+
+  assert!(
+    RuleAction::parse("delete")
+      .is_err()
+  );
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use common::domain;
+use pulsewire_core::domain::model::{
+  WatchConfig,
+  WatchExtractorKind
+};
+use pulsewire_core::infra::config::ConfigError;
+use pulsewire_core::testing::{
+  FakeResponse,
+  Simulation,
+  sim_config,
+  watch
+};
+
+const URL: &str =
+  "https://a.example/posts";
+
+const POSTS: &[u8] = include_bytes!(
+  "fixtures/watch/posts.html"
+);
+
+/// (title, link, summary, published)
+type Fields = (
+  Option<String>,
+  Option<String>,
+  Option<String>,
+  Option<i64>
+);
+
+fn posts_watch(
+  extractor: WatchExtractorKind,
+  item_selector: &str
+) -> WatchConfig {
+  WatchConfig {
+    extractor,
+    ..watch(
+      "w1",
+      URL,
+      "a.example",
+      "news",
+      300,
+      item_selector
+    )
+  }
+}
+
+/// The items one poll of the fixture
+/// page stores for `w`.
+async fn extract(
+  w: WatchConfig
+) -> Vec<Fields> {
+  let mut sim =
+    Simulation::with_watches(
+      sim_config(),
+      Vec::new(),
+      vec![w],
+      7
+    )
+    .await
+    .unwrap();
+
+  sim.http.respond(
+    URL,
+    FakeResponse::ok(POSTS.to_vec())
+  );
+
+  sim.tick().await.unwrap();
+
+  sim.repo.read(|t| {
+    t.feed_items
+      .iter()
+      .map(|row| {
+        (
+          row.item.title.clone(),
+          row.item.link.clone(),
+          row.item.summary.clone(),
+          row.item.published_at_ms
+        )
+      })
+      .collect()
+  })
+}
+
+fn expected() -> Vec<Fields> {
+  vec![
+    (
+      Some("First post".into()),
+      Some(
+        "https://a.example/posts/1"
+          .into()
+      ),
+      Some("Alpha summary".into()),
+      Some(1_704_164_645_000)
+    ),
+    (
+      Some("Second post".into()),
+      Some(
+        "https://a.example/posts/2"
+          .into()
+      ),
+      Some("Beta summary".into()),
+      Some(1_704_240_000_000)
+    ),
+  ]
+}
+
+#[tokio::test(start_paused = true)]
+async fn css_extracts_fixture_items() {
+  let w = WatchConfig {
+    title_selector: Some("h2".into()),
+    link_selector: Some("a".into()),
+    summary_selector: Some(
+      "p.summary".into()
+    ),
+    // Regex fields run on the item's
+    // HTML.
+    published_extractor: Some(
+      WatchExtractorKind::Regex
+    ),
+    published_selector: Some(
+      "datetime=\"([^\"]+)\"".into()
+    ),
+    ..posts_watch(
+      WatchExtractorKind::Css,
+      "li.post"
+    )
+  };
+
+  assert_eq!(
+    extract(w).await,
+    expected()
+  );
+}
+
+#[tokio::test(start_paused = true)]
+async fn xpath_extracts_fixture_items()
+{
+  let w = WatchConfig {
+    title_selector: Some(
+      "string(.//h2)".into()
+    ),
+    link_selector: Some(".//a".into()),
+    summary_selector: Some(
+      "string(.//p[@class='summary'])"
+        .into()
+    ),
+    published_selector: Some(
+      "string(.//time/@datetime)"
+        .into()
+    ),
+    ..posts_watch(
+      WatchExtractorKind::Xpath,
+      "//li[@class='post']"
+    )
+  };
+
+  assert_eq!(
+    extract(w).await,
+    expected()
+  );
+}
+
+#[tokio::test(start_paused = true)]
+async fn regex_extracts_fixture_items()
+{
+  let w = WatchConfig {
+    title_selector: Some(
+      "<h2>([^<]+)</h2>".into()
+    ),
+    link_selector: Some(
+      "href=\"([^\"]+)\"".into()
+    ),
+    summary_selector: Some(
+      "class=\"summary\">([^<]+)"
+        .into()
+    ),
+    published_selector: Some(
+      "datetime=\"([^\"]+)\"".into()
+    ),
+    ..posts_watch(
+      WatchExtractorKind::Regex,
+      "(?s)<li class=\"post\" \
+       data-id=\"(?P<id>[^\"]+)\">.*?\
+       </li>"
+    )
+  };
+
+  assert_eq!(
+    extract(w).await,
+    expected()
+  );
+}
+
+#[tokio::test]
+async fn invalid_regexes_are_config_errors()
+ {
+  for (name, fields, expected) in [
+    (
+      "item",
+      [
+        "extractor = \"regex\"",
+        "item_selector = \"(?P<id>\""
+      ],
+      "invalid regex item_selector"
+    ),
+    (
+      "field",
+      [
+        "item_selector = \"li.post\"",
+        "link_extractor = \"regex\""
+      ],
+      "invalid regex link_selector"
+    )
+  ] {
+    let mut feeds = vec![
+      "[[watches]]",
+      "id = \"w1\"",
+      "url = \"https://a.example/posts\"",
+      "link_selector = \"(\""
+    ];
+    feeds.extend(fields);
+
+    let err = common::load(
+      &format!("regex-{name}"),
+      &[domain("a.example", &[])],
+      &feeds
+    )
+    .await
+    .err()
+    .unwrap_or_else(|| {
+      panic!("{name} loaded")
+    });
+
+    let ConfigError::Invalid(msg) = err
+    else {
+      panic!("{name}: {err}");
+    };
+
+    assert!(
+      msg.contains(expected),
+      "{name}: {msg}"
+    );
+  }
+}
@@ -0,0 +1,236 @@
+use pulsewire_core::domain::compat::fever::{
+  Feed,
+  FeverRequest,
+  FeverResponse,
+  Item,
+  ItemMark,
+  Mark,
+  api_key,
+  feed_id,
+  groups
+};
+use pulsewire_core::domain::compat::{
+  CompatEntry,
+  CompatFeed,
+  CompatFolder,
+  form_pairs,
+  site_url
+};
+
+fn fixture(
+  json: &str
+) -> serde_json::Value {
+  serde_json::from_str(json).unwrap()
+}
+
+fn folder(
+  id: i64,
+  name: &str
+) -> CompatFolder {
+  CompatFolder {
+    id,
+    name: name.into()
+  }
+}
+
+fn feeds() -> Vec<CompatFeed> {
+  vec![
+    CompatFeed {
+      id:       "rust-blog".into(),
+      url:      "https://blog.rust-lang.org/feed.xml".into(),
+      title:    "Rust Blog".into(),
+      site_url: site_url(
+        "https://blog.rust-lang.org/feed.xml"
+      ),
+      folders:  vec![
+        folder(5, "Weekly"),
+        folder(3, "Tech"),
+      ]
+    },
+    CompatFeed {
+      id:       "news".into(),
+      url:      "https://news.example/rss"
+        .into(),
+      title:    "news".into(),
+      site_url: site_url(
+        "https://news.example/rss"
+      ),
+      folders:  vec![folder(3, "Tech")]
+    },
+  ]
+}
+
+#[test]
+fn requests_parse_flags_and_marks() {
+  let key = api_key("alice", "s3cret!");
+
+  assert_eq!(
+    key,
+    "8aa47d3407009974db0366d89e696749"
+  );
+
+  let read = |raw: &str| {
+    FeverRequest::parse(&form_pairs(
+      raw
+    ))
+  };
+
+  let items = read(include_str!(
+    "fixtures/fever/items.query"
+  ))
+  .unwrap();
+
+  assert_eq!(items, FeverRequest {
+    items: true,
+    unread_item_ids: true,
+    since_id: Some(41),
+    with_ids: Some(vec![42, 7]),
+    ..FeverRequest::default()
+  });
+
+  let saved = read(include_str!(
+    "fixtures/fever/mark_item.form"
+  ))
+  .unwrap();
+
+  // Keys compare in lower case.
+  assert_eq!(
+    saved.api_key.as_deref(),
+    Some(key.as_str())
+  );
+  assert_eq!(
+    saved.mark,
+    Some(Mark::Item {
+      id:   42,
+      mark: ItemMark::Saved
+    })
+  );
+
+  assert_eq!(
+    read(include_str!(
+      "fixtures/fever/mark_group.form"
+    ))
+    .unwrap()
+    .mark,
+    Some(Mark::Group {
+      id:     0,
+      before: Some(1_700_000_000)
+    })
+  );
+
+  assert!(
+    read("mark=feed&as=unread&id=1")
+      .is_err()
+  );
+  assert!(
+    read("mark=item&as=read").is_err()
+  );
+}
+
+#[test]
+fn groups_and_feeds_match_fixture() {
+  let feeds = feeds();
+
+  let (groups, feeds_groups) =
+    groups(&feeds);
+
+  let mut response = FeverResponse::new(
+    true,
+    1_700_000_100
+  );
+  response.groups = Some(groups);
+  response.feeds = Some(
+    feeds
+      .iter()
+      .map(Feed::new)
+      .collect()
+  );
+  response.feeds_groups =
+    Some(feeds_groups);
+
+  assert_eq!(
+    serde_json::to_value(&response)
+      .unwrap(),
+    fixture(include_str!(
+      "fixtures/fever/groups_feeds.\
+       json"
+    ))
+  );
+
+  // Feed ids stay exact as JavaScript
+  // numbers.
+  assert!(
+    feed_id("rust-blog") < 1 << 53
+  );
+}
+
+#[test]
+fn items_and_unauthorized_match_fixtures()
+ {
+  let entries = [
+    CompatEntry {
+      id:           42,
+      feed_id:      "rust-blog".into(),
+      title:        Some(
+        "Announcing Rust 1.99".into()
+      ),
+      link:         Some(
+        "https://blog.rust-lang.org/1.99"
+          .into()
+      ),
+      author:       Some(
+        "The Rust Team".into()
+      ),
+      content:      Some(
+        "<p>New release</p>".into()
+      ),
+      published_ms: Some(1_700_000_000_500),
+      read:         false,
+      starred:      true
+    },
+    CompatEntry {
+      id:           7,
+      feed_id:      "gone".into(),
+      title:        None,
+      link:         None,
+      author:       None,
+      content:      None,
+      published_ms: None,
+      read:         true,
+      starred:      false
+    },
+  ];
+
+  let mut response = FeverResponse::new(
+    true,
+    1_700_000_100
+  );
+  response.items = Some(
+    entries
+      .iter()
+      .map(Item::new)
+      .collect()
+  );
+  response.total_items = Some(2);
+  response.unread_item_ids =
+    Some("42".into());
+
+  assert_eq!(
+    serde_json::to_value(&response)
+      .unwrap(),
+    fixture(include_str!(
+      "fixtures/fever/items.json"
+    ))
+  );
+
+  assert_eq!(
+    serde_json::to_value(
+      FeverResponse::new(false, 1)
+    )
+    .unwrap(),
+    fixture(include_str!(
+      "fixtures/fever/unauthorized.\
+       json"
+    ))
+  );
+}
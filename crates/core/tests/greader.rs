@@ -0,0 +1,306 @@
+use pulsewire_core::domain::compat::greader::{
+  EditTag,
+  Item,
+  ItemRef,
+  ItemRefs,
+  MarkAllRead,
+  StreamContents,
+  StreamId,
+  StreamQuery,
+  SubscriptionList,
+  TagList,
+  auth_token,
+  client_login_body,
+  item_id,
+  parse_item_id
+};
+use pulsewire_core::domain::compat::{
+  CompatEntry,
+  CompatFeed,
+  CompatFolder,
+  form_pairs,
+  form_value
+};
+
+fn fixture(
+  json: &str
+) -> serde_json::Value {
+  serde_json::from_str(json).unwrap()
+}
+
+fn feeds() -> Vec<CompatFeed> {
+  vec![
+    CompatFeed {
+      id:       "rust-blog".into(),
+      url:      "https://blog.rust-lang.org/feed.xml".into(),
+      title:    "Rust Blog".into(),
+      site_url: "https://blog.rust-lang.org/".into(),
+      folders:  vec![CompatFolder {
+        id:   3,
+        name: "Tech".into()
+      }]
+    },
+    CompatFeed {
+      id:       "news".into(),
+      url:      "https://news.example/rss"
+        .into(),
+      title:    "news".into(),
+      site_url: "https://news.example/"
+        .into(),
+      folders:  Vec::new()
+    },
+  ]
+}
+
+fn entries() -> Vec<CompatEntry> {
+  vec![
+    CompatEntry {
+      id:           42,
+      feed_id:      "rust-blog".into(),
+      title:        Some(
+        "Announcing Rust 1.99".into()
+      ),
+      link:         Some(
+        "https://blog.rust-lang.org/1.99"
+          .into()
+      ),
+      author:       Some(
+        "The Rust Team".into()
+      ),
+      content:      Some(
+        "<p>New release</p>".into()
+      ),
+      published_ms: Some(1_700_000_000_500),
+      read:         false,
+      starred:      true
+    },
+    CompatEntry {
+      id:           7,
+      feed_id:      "gone".into(),
+      title:        None,
+      link:         None,
+      author:       None,
+      content:      None,
+      published_ms: None,
+      read:         true,
+      starred:      false
+    },
+  ]
+}
+
+#[test]
+fn client_login_reads_the_form_and_answers_with_the_token()
+ {
+  let pairs = form_pairs(include_str!(
+    "fixtures/greader/client_login.\
+     form"
+  ));
+
+  assert_eq!(
+    form_value(&pairs, "Email"),
+    Some("alice")
+  );
+  assert_eq!(
+    form_value(&pairs, "Passwd"),
+    Some("s3cret!")
+  );
+  assert_eq!(
+    client_login_body("abc"),
+    include_str!(
+      "fixtures/greader/client_login.\
+       txt"
+    )
+  );
+  assert_eq!(
+    auth_token("GoogleLogin auth=abc"),
+    Some("abc")
+  );
+  assert_eq!(
+    auth_token("Bearer abc"),
+    None
+  );
+}
+
+#[test]
+fn ids_normalize_user_and_item_forms() {
+  assert_eq!(
+    StreamId::parse(
+      "user/1001/state/com.google/\
+       reading-list"
+    ),
+    Ok(StreamId::ReadingList)
+  );
+  assert_eq!(
+    StreamId::parse(
+      "user/-/label/Tech / Deep"
+    ),
+    Ok(StreamId::Label(
+      "Tech / Deep".into()
+    ))
+  );
+  assert_eq!(
+    StreamId::parse("feed/rust-blog")
+      .map(|s| s.to_id()),
+    Ok("feed/rust-blog".to_string())
+  );
+  assert!(
+    StreamId::parse(
+      "user/-/state/com.google/like"
+    )
+    .is_err()
+  );
+
+  assert_eq!(
+    item_id(42),
+    "tag:google.com,2005:reader/item/\
+     000000000000002a"
+  );
+  assert_eq!(
+    parse_item_id(&item_id(42)),
+    Ok(42)
+  );
+  assert_eq!(
+    parse_item_id("42"),
+    Ok(42)
+  );
+  assert!(parse_item_id("x").is_err());
+}
+
+#[test]
+fn write_requests_parse_from_form_bodies()
+ {
+  let edit = EditTag::parse(
+    &form_pairs(include_str!(
+      "fixtures/greader/edit_tag.form"
+    ))
+  )
+  .unwrap();
+
+  assert_eq!(edit, EditTag {
+    item_ids: vec![42, 7],
+    read:     Some(false),
+    starred:  Some(true)
+  });
+
+  let mark = MarkAllRead::parse(
+    &form_pairs(include_str!(
+      "fixtures/greader/\
+       mark_all_as_read.form"
+    ))
+  )
+  .unwrap();
+
+  assert_eq!(mark, MarkAllRead {
+    stream:        StreamId::Label(
+      "Tech".into()
+    ),
+    older_than_ms: Some(
+      1_700_000_000_123
+    )
+  });
+
+  assert!(
+    EditTag::parse(&form_pairs(
+      "a=user/-/state/com.google/read"
+    ))
+    .is_err()
+  );
+}
+
+#[test]
+fn stream_contents_match_fixture() {
+  let query = StreamQuery::parse(
+    "user/-/label/Tech",
+    &form_pairs(include_str!(
+      "fixtures/greader/\
+       stream_contents.query"
+    ))
+  )
+  .unwrap();
+
+  assert_eq!(query, StreamQuery {
+    stream:        StreamId::Label(
+      "Tech".into()
+    ),
+    count:         50,
+    oldest_first:  true,
+    exclude_read:  true,
+    only_starred:  false,
+    newer_than_ms: Some(
+      1_700_000_000_000
+    ),
+    older_than_ms: None,
+    continuation:  Some(
+      "next-page".into()
+    )
+  });
+
+  let feeds = feeds();
+
+  let contents = StreamContents {
+    id:           query.stream.to_id(),
+    updated:      1_700_000_100,
+    items:        entries()
+      .iter()
+      .map(|entry| {
+        Item::new(
+          entry,
+          feeds.iter().find(|f| {
+            f.id == entry.feed_id
+          })
+        )
+      })
+      .collect(),
+    continuation: query.continuation
+  };
+
+  assert_eq!(
+    serde_json::to_value(&contents)
+      .unwrap(),
+    fixture(include_str!(
+      "fixtures/greader/\
+       stream_contents.json"
+    ))
+  );
+
+  let refs = ItemRefs {
+    item_refs:    entries()
+      .iter()
+      .map(ItemRef::new)
+      .collect(),
+    continuation: None
+  };
+
+  assert_eq!(
+    serde_json::to_value(&refs)
+      .unwrap(),
+    fixture(include_str!(
+      "fixtures/greader/item_ids.json"
+    ))
+  );
+}
+
+#[test]
+fn subscription_and_tag_lists_match_fixtures()
+ {
+  assert_eq!(
+    serde_json::to_value(
+      SubscriptionList::new(&feeds())
+    )
+    .unwrap(),
+    fixture(include_str!(
+      "fixtures/greader/\
+       subscription_list.json"
+    ))
+  );
+
+  assert_eq!(
+    serde_json::to_value(TagList::new(
+      &["Tech".to_string()]
+    ))
+    .unwrap(),
+    fixture(include_str!(
+      "fixtures/greader/tag_list.json"
+    ))
+  );
+}
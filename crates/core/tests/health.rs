@@ -0,0 +1,345 @@
+use pulsewire_core::app::health::{
+  HealthWindow,
+  health_report
+};
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::domain::model::ErrorKind;
+use pulsewire_core::feed::parser;
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::repo::Repo;
+use pulsewire_core::testing::{
+  MemoryRepo,
+  feed
+};
+
+const UTC: chrono_tz::Tz =
+  chrono_tz::UTC;
+
+fn rss(guids: &[&str]) -> Vec<u8> {
+  let items: String = guids
+    .iter()
+    .map(|guid| {
+      format!(
+        "<item><title>{guid}</\
+         title><guid>{guid}</guid></\
+         item>"
+      )
+    })
+    .collect();
+
+  format!(
+    "<?xml version=\"1.0\"?><rss \
+     version=\"2.0\"\
+     ><channel><title>t</\
+     title>{items}</channel></rss>"
+  )
+  .into_bytes()
+}
+
+/// Two feeds: `ok` fetches with one
+/// timeout, `down` is parked after
+/// 503s.
+async fn scenario<R>(repo: &R)
+where
+  R: Repo + ?Sized
+{
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  repo
+    .upsert_feeds_bulk(
+      vec![
+        feed(
+          "ok",
+          "https://a.example/rss",
+          "a.example",
+          "news",
+          300
+        ),
+        feed(
+          "down",
+          "https://b.example/rss",
+          "b.example",
+          "news",
+          300
+        ),
+      ],
+      100,
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  for (
+    method,
+    status,
+    error,
+    latency
+  ) in [
+    ("GET", Some(200), None, 100),
+    ("HEAD", Some(304), None, 50),
+    (
+      "HEAD",
+      None,
+      Some(ErrorKind::Timeout),
+      300
+    )
+  ] {
+    repo
+      .insert_event(
+        "ok",
+        method,
+        status,
+        error,
+        Some(latency),
+        0,
+        0,
+        None,
+        &UTC
+      )
+      .await
+      .unwrap();
+  }
+
+  for (at, guids) in [
+    (1_000, vec!["a", "b"]),
+    (2_000, vec![])
+  ] {
+    repo
+      .insert_payload_with_items(
+        "ok",
+        at,
+        None,
+        None,
+        None,
+        &parser::parse(&rss(&guids))
+          .unwrap(),
+        &UTC
+      )
+      .await
+      .unwrap();
+  }
+
+  let mut state = LinkState::initial(
+    "ok".to_string(),
+    300,
+    3600,
+    0.0,
+    0
+  );
+
+  for (at, backoff) in
+    [(10, 1), (20, 2)]
+  {
+    state.backoff_index = backoff;
+
+    repo
+      .insert_state(
+        &state, at, &UTC, true
+      )
+      .await
+      .unwrap();
+  }
+
+  repo
+    .insert_event(
+      "down",
+      "GET",
+      Some(503),
+      Some(ErrorKind::Http5xx(503)),
+      Some(40),
+      5,
+      0,
+      None,
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  repo
+    .mark_feed_error(
+      "down",
+      Some(ErrorKind::Http5xx(503)),
+      Some(503),
+      5,
+      3_000,
+      &UTC
+    )
+    .await
+    .unwrap();
+}
+
+async fn check<R>(repo: &R)
+where
+  R: Repo + ?Sized
+{
+  let report = health_report(
+    repo,
+    HealthWindow::default()
+  )
+  .await
+  .unwrap();
+
+  let ids: Vec<&str> = report
+    .iter()
+    .map(|h| h.feed_id.as_str())
+    .collect();
+
+  assert_eq!(ids, ["down", "ok"]);
+
+  let down = &report[0];
+
+  assert!(down.parked);
+  assert_eq!(
+    down.success_rate,
+    Some(0.0)
+  );
+  assert_eq!(
+    down.last_error_status,
+    Some(503)
+  );
+  assert_eq!(
+    down.last_new_item_at_ms,
+    None
+  );
+
+  let ok = &report[1];
+
+  assert!(!ok.parked);
+  assert_eq!(ok.events, 3);
+  assert_eq!(
+    ok.success_rate,
+    Some(2.0 / 3.0)
+  );
+  assert_eq!(
+    ok.median_latency_ms,
+    Some(100)
+  );
+  assert_eq!(
+    ok.last_new_item_at_ms,
+    Some(1_000)
+  );
+  assert_eq!(
+    ok.avg_items_per_fetch,
+    Some(1.0)
+  );
+  assert_eq!(ok.backoff_index, Some(2));
+
+  let events = repo
+    .recent_events("ok", 2)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    events
+      .iter()
+      .map(|e| e.latency_ms)
+      .collect::<Vec<_>>(),
+    [Some(300), Some(50)]
+  );
+  assert_eq!(
+    events[0].error_kind.as_deref(),
+    Some("Timeout")
+  );
+
+  let history = repo
+    .state_history("ok", 10)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    history
+      .iter()
+      .map(|h| {
+        (
+          h.recorded_at_ms,
+          h.state.backoff_index
+        )
+      })
+      .collect::<Vec<_>>(),
+    [(20, 2), (10, 1)]
+  );
+
+  let payloads = repo
+    .payload_timeline("ok", 10)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    payloads
+      .iter()
+      .map(|p| {
+        (p.fetched_at_ms, p.item_count)
+      })
+      .collect::<Vec<_>>(),
+    [(2_000, 0), (1_000, 2)]
+  );
+
+  let items = repo
+    .payload_items(
+      payloads[1].payload_id
+    )
+    .await
+    .unwrap();
+
+  assert_eq!(
+    items
+      .iter()
+      .map(|i| i.guid.as_deref())
+      .collect::<Vec<_>>(),
+    [Some("a"), Some("b")]
+  );
+
+  let errors =
+    repo.error_feeds().await.unwrap();
+
+  assert_eq!(errors.len(), 1);
+  assert_eq!(
+    errors[0]
+      .last_error_kind
+      .as_deref(),
+    Some("Http5xx(503)")
+  );
+}
+
+#[tokio::test]
+
+async fn sqlite_history_backs_health_report()
+ {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-health-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  let repo =
+    SqliteRepo::new(&dir.join("t.db"))
+      .await
+      .unwrap();
+
+  repo.migrate(&UTC, 60).await.unwrap();
+
+  scenario(&repo).await;
+  check(&repo).await;
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+
+async fn memory_repo_matches_sqlite_history()
+ {
+  let repo = MemoryRepo::new();
+
+  scenario(&repo).await;
+  check(&repo).await;
+}
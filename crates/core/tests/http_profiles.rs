@@ -0,0 +1,295 @@
+mod common;
+
+use common::domain;
+use pulsewire_core::domain::model::{
+  HttpProfile,
+  HttpVersion
+};
+use pulsewire_core::infra::config::{
+  ConfigError,
+  LoadedConfig
+};
+use pulsewire_core::infra::reqwest_http::ReqwestHttp;
+use pulsewire_core::ports::http::Http;
+use tokio::io::{
+  AsyncReadExt,
+  AsyncWriteExt
+};
+use tokio::net::TcpListener;
+
+/// One feed on `a.example`.
+const FEED: [&str; 3] = [
+  "[[feeds]]",
+  "id = \"a\"",
+  "url = \"https://a.example/rss\""
+];
+
+async fn load(
+  name: &str,
+  domains: &[String]
+) -> Result<LoadedConfig, ConfigError> {
+  common::load(
+    &format!("profiles-{name}"),
+    domains,
+    &FEED
+  )
+  .await
+}
+
+fn profile(
+  loaded: &LoadedConfig,
+  domain: &str
+) -> Option<HttpProfile> {
+  loaded.app.domains[domain]
+    .http
+    .clone()
+}
+
+#[tokio::test]
+async fn domains_without_overrides_have_no_profile()
+ {
+  let loaded =
+    load("plain", &[domain(
+      "a.example",
+      &[]
+    )])
+    .await
+    .unwrap();
+
+  assert_eq!(
+    profile(&loaded, "a.example"),
+    None
+  );
+}
+
+#[tokio::test]
+async fn profile_fields_are_parsed() {
+  let loaded =
+    load("fields", &[domain(
+      "a.example",
+      &[
+        "connect_timeout_seconds = 5",
+        "read_timeout_seconds = 90",
+        "proxy = \"socks5h://127.0.0.\
+         1:1080\"",
+        "http_version = \"http1\"",
+        "user_agent = \" agent-a \""
+      ]
+    )])
+    .await
+    .unwrap();
+
+  assert_eq!(
+    profile(&loaded, "a.example"),
+    Some(HttpProfile {
+      connect_timeout_seconds: Some(5),
+      read_timeout_seconds:    Some(90),
+      proxy:                   Some(
+        "socks5h://127.0.0.1:1080"
+          .into()
+      ),
+      ca_bundle:               None,
+      http_version:            Some(
+        HttpVersion::Http1
+      ),
+      user_agent:              Some(
+        "agent-a".into()
+      )
+    })
+  );
+}
+
+#[tokio::test]
+async fn invalid_profiles_are_config_errors()
+ {
+  for (name, field, expected) in [
+    (
+      "proxy-scheme",
+      "proxy = \"ftp://proxy.example\"",
+      "must use http, https, socks5 \
+       or socks5h"
+    ),
+    (
+      "proxy-url",
+      "proxy = \"not a url\"",
+      "invalid proxy"
+    ),
+    (
+      "ca-bundle",
+      "ca_bundle = \"missing.pem\"",
+      "ca_bundle"
+    )
+  ] {
+    let err = load(name, &[domain(
+      "a.example",
+      &[field]
+    )])
+    .await
+    .err()
+    .unwrap_or_else(|| {
+      panic!("{name} loaded")
+    });
+
+    let ConfigError::Invalid(msg) = err
+    else {
+      panic!("{name}: {err}");
+    };
+
+    assert!(
+      msg.contains("'a.example'")
+        && msg.contains(expected),
+      "{name}: {msg}"
+    );
+  }
+}
+
+/// Answers every request on a loopback
+/// port with its `User-Agent`.
+async fn echo_user_agent() -> String {
+  let listener =
+    TcpListener::bind("127.0.0.1:0")
+      .await
+      .unwrap();
+  let port = listener
+    .local_addr()
+    .unwrap()
+    .port();
+
+  tokio::spawn(async move {
+    while let Ok((mut socket, _)) =
+      listener.accept().await
+    {
+      let mut buf = [0u8; 4096];
+      let n = socket
+        .read(&mut buf)
+        .await
+        .unwrap_or(0);
+      let request =
+        String::from_utf8_lossy(
+          &buf[..n]
+        );
+
+      let agent = request
+        .lines()
+        .find_map(|line| {
+          let (name, value) =
+            line.split_once(':')?;
+
+          name
+            .eq_ignore_ascii_case(
+              "user-agent"
+            )
+            .then(|| value.trim())
+        })
+        .unwrap_or_default()
+        .to_string();
+
+      let _ = socket
+        .write_all(
+          format!(
+            "HTTP/1.1 200 \
+             OK\r\nContent-Length: \
+             {}\r\nConnection: \
+             close\r\n\r\n{agent}",
+            agent.len()
+          )
+          .as_bytes()
+        )
+        .await;
+    }
+  });
+
+  format!("http://127.0.0.1:{port}/")
+}
+
+#[tokio::test]
+async fn each_domain_uses_its_profile()
+{
+  let loaded = load("per-host", &[
+    domain("a.example", &[
+      "user_agent = \"agent-a\""
+    ]),
+    domain("b.example", &[
+      "user_agent = \"agent-b\"",
+      "read_timeout_seconds = 10"
+    ]),
+    domain("c.example", &[])
+  ])
+  .await
+  .unwrap();
+
+  let http = ReqwestHttp::new(
+    "default-agent".into(),
+    &loaded.app.domains
+  )
+  .unwrap();
+
+  let url = echo_user_agent().await;
+
+  for (domain, agent) in [
+    ("a.example", "agent-a"),
+    ("b.example", "agent-b"),
+    ("c.example", "default-agent"),
+    (
+      "unlisted.example",
+      "default-agent"
+    )
+  ] {
+    let res = http
+      .get(domain, &url, None, None)
+      .await;
+
+    assert_eq!(
+      res.status,
+      Some(200),
+      "{domain}"
+    );
+    assert_eq!(
+      res.body.as_deref(),
+      Some(agent.as_bytes()),
+      "{domain}"
+    );
+  }
+}
+
+#[tokio::test]
+async fn http2_profiles_still_reach_http1_origins()
+ {
+  let loaded = load("versions", &[
+    domain("a.example", &[
+      "http_version = \"http2\"",
+      "user_agent = \"agent-a\""
+    ]),
+    domain("b.example", &[
+      "http_version = \"h2c\""
+    ])
+  ])
+  .await
+  .unwrap();
+
+  assert_eq!(
+    profile(&loaded, "b.example")
+      .and_then(|p| p.http_version),
+    Some(HttpVersion::H2c)
+  );
+
+  let http = ReqwestHttp::new(
+    "default-agent".into(),
+    &loaded.app.domains
+  )
+  .unwrap();
+
+  let url = echo_user_agent().await;
+
+  // Without TLS there is nothing to
+  // negotiate, so `http2` falls back
+  // to HTTP/1.1.
+  let res = http
+    .get("a.example", &url, None, None)
+    .await;
+
+  assert_eq!(
+    res.body.as_deref(),
+    Some(b"agent-a".as_slice())
+  );
+}
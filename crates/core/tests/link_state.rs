@@ -1,4 +1,8 @@
 use pulsewire_core::domain::link_state::{compute_delay_seconds, LinkPhase, LinkState, NextAction};
+use pulsewire_core::domain::model::{
+  ErrorKind,
+  GetResult
+};
 
 #[test]
 
@@ -36,3 +40,46 @@ fn decide_action_respects_next_action_at()
     | _ => panic!("expected sleep")
   }
 }
+
+#[test]
+
+fn gone_get_archives_feed() {
+  let s = LinkState::initial(
+    "f1".to_string(),
+    10,
+    60,
+    0.1,
+    1_000
+  );
+
+  let res = GetResult {
+    status:             Some(410),
+    body:               None,
+    etag:               None,
+    last_modified:      None,
+    error:              Some(
+      ErrorKind::Http4xx(410)
+    ),
+    latency_ms:         5,
+    set_cookie_headers: Vec::new(),
+    headers:            Vec::new(),
+    final_url:          None,
+    redirects:          Vec::new()
+  };
+
+  let s = LinkState::apply_get_result(
+    s, res, 2_000, false, 0.5
+  );
+
+  assert_eq!(
+    s.phase,
+    LinkPhase::Archived
+  );
+
+  assert!(matches!(
+    LinkState::decide_next_action(
+      &s, 10_000_000
+    ),
+    NextAction::SleepUntil { .. }
+  ));
+}
@@ -0,0 +1,186 @@
+use pulsewire_core::domain::model::SqlDialect;
+use pulsewire_core::infra::migrations::{
+  AppliedMigration,
+  Component,
+  MigrationStatus,
+  known_migrations,
+  statements
+};
+
+#[test]
+
+fn known_migrations_are_numbered_from_one()
+ {
+  for dialect in [
+    SqlDialect::Sqlite,
+    SqlDialect::Postgres
+  ] {
+    for component in [
+      Component::Fetcher,
+      Component::Server
+    ] {
+      let versions: Vec<i64> =
+        known_migrations(
+          component, dialect
+        )
+        .iter()
+        .map(|m| m.version)
+        .collect();
+
+      let expected: Vec<i64> = (1
+        ..=versions.len() as i64)
+        .collect();
+
+      assert_eq!(versions, expected);
+    }
+  }
+}
+
+#[test]
+
+fn newer_or_edited_ledger_is_rejected()
+{
+  let known = known_migrations(
+    Component::Fetcher,
+    SqlDialect::Sqlite
+  );
+
+  let applied = |version, checksum| {
+    AppliedMigration {
+      version,
+      name: "baseline".to_string(),
+      checksum,
+      applied_at_ms: 0
+    }
+  };
+
+  let clean = MigrationStatus {
+    component: Component::Fetcher,
+    known,
+    applied: known
+      .iter()
+      .map(|m| {
+        applied(m.version, m.checksum())
+      })
+      .collect()
+  };
+
+  assert!(clean.problems().is_empty());
+  assert!(clean.pending().is_empty());
+  assert!(
+    clean.ensure_supported().is_ok()
+  );
+
+  let newer = MigrationStatus {
+    applied: vec![
+      applied(1, known[0].checksum()),
+      applied(
+        known.len() as i64 + 1,
+        "x".to_string()
+      ),
+    ],
+    ..clean.clone()
+  };
+
+  assert!(
+    newer.ensure_supported().is_err()
+  );
+
+  let edited = MigrationStatus {
+    applied: vec![applied(
+      1,
+      "edited".to_string()
+    )],
+    ..clean
+  };
+
+  assert_eq!(
+    edited.problems().len(),
+    1
+  );
+}
+
+#[test]
+
+fn statements_split_on_semicolons() {
+  assert_eq!(
+    statements(
+      "CREATE TABLE a(x);\n\nCREATE \
+       TABLE b(y);\n"
+    ),
+    [
+      "CREATE TABLE a(x)",
+      "CREATE TABLE b(y)"
+    ]
+  );
+}
+
+#[test]
+
+fn statements_keep_quoted_semicolons() {
+  let sql = "INSERT INTO t VALUES \
+             ('a;b', 'it''s; \
+             ok');SELECT \"odd;name\" \
+             FROM t;";
+
+  assert_eq!(statements(sql), [
+    "INSERT INTO t VALUES ('a;b', \
+     'it''s; ok')",
+    "SELECT \"odd;name\" FROM t"
+  ]);
+}
+
+#[test]
+
+fn statements_skip_comments() {
+  let sql =
+    "-- first; table\nCREATE TABLE \
+     a(x);\n/* second;\n table */ \
+     CREATE TABLE b(y);\n-- done;\n";
+
+  assert_eq!(statements(sql), [
+    "-- first; table\nCREATE TABLE \
+     a(x)",
+    "/* second;\n table */ CREATE \
+     TABLE b(y)"
+  ]);
+}
+
+#[test]
+
+fn statements_keep_dollar_quoted_bodies()
+ {
+  let sql =
+    "CREATE FUNCTION f() AS $$ SELECT \
+     1; $$ LANGUAGE sql;\nCREATE \
+     FUNCTION g() AS $body$ SELECT \
+     $$;$$; $body$ LANGUAGE \
+     sql;\nSELECT $1, a$b FROM t;";
+
+  assert_eq!(statements(sql), [
+    "CREATE FUNCTION f() AS $$ SELECT \
+     1; $$ LANGUAGE sql",
+    "CREATE FUNCTION g() AS $body$ \
+     SELECT $$;$$; $body$ LANGUAGE sql",
+    "SELECT $1, a$b FROM t"
+  ]);
+}
+
+#[test]
+
+fn statements_keep_trigger_bodies() {
+  let sql = "CREATE TRIGGER IF NOT \
+             EXISTS t AFTER INSERT ON \
+             a BEGIN\n  INSERT INTO b \
+             VALUES (1);\n  DELETE \
+             FROM c;\nEND;\nCREATE \
+             INDEX i ON a(x);";
+
+  assert_eq!(statements(sql), [
+    "CREATE TRIGGER IF NOT EXISTS t \
+     AFTER INSERT ON a BEGIN\n  \
+     INSERT INTO b VALUES (1);\n  \
+     DELETE FROM c;\nEND",
+    "CREATE INDEX i ON a(x)"
+  ]);
+}
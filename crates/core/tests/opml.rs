@@ -0,0 +1,163 @@
+use pulsewire_core::feed::opml::{
+  OpmlFeed,
+  parse_opml,
+  write_opml
+};
+use pulsewire_core::infra::config::opml_feeds_file;
+
+const OPML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <head><title>Export</title></head>
+  <body>
+    <outline type="rss" text="Top" xmlUrl="https://top.example/feed"/>
+    <outline text="Tech &amp; Code">
+      <outline type="rss" title="Ars" xmlURL="https://ars.example/rss" htmlUrl="https://ars.example/"/>
+      <outline text="Deep">
+        <outline type="rss" text="Ars" xmlUrl="https://ars.example/features"/>
+      </outline>
+      <outline text="Empty"/>
+    </outline>
+    <outline type="rss" xmlUrl="ftp://files.example/feed"/>
+  </body>
+</opml>"#;
+
+fn feed(
+  title: Option<&str>,
+  url: &str,
+  folder: Option<&str>
+) -> OpmlFeed {
+  OpmlFeed {
+    title:    title.map(String::from),
+    xml_url:  url.to_string(),
+    html_url: None,
+    folder:   folder.map(String::from)
+  }
+}
+
+#[test]
+fn parse_opml_maps_nested_outlines_to_folders()
+ {
+  let feeds = parse_opml(OPML).unwrap();
+
+  assert_eq!(feeds.len(), 4);
+  assert_eq!(
+    feeds[0],
+    feed(
+      Some("Top"),
+      "https://top.example/feed",
+      None
+    )
+  );
+  assert_eq!(
+    feeds[1].html_url.as_deref(),
+    Some("https://ars.example/")
+  );
+  assert_eq!(
+    feeds[1].folder.as_deref(),
+    Some("Tech & Code")
+  );
+  assert_eq!(
+    feeds[2].folder.as_deref(),
+    Some("Tech & Code / Deep")
+  );
+  assert_eq!(feeds[3].title, None);
+
+  assert!(
+    parse_opml("<rss/>").is_err()
+  );
+}
+
+#[test]
+fn write_opml_round_trips_folders() {
+  let feeds = vec![
+    feed(
+      Some("Deep <one>"),
+      "https://a.example/?x=1&y=2",
+      Some("Tech / Deep")
+    ),
+    feed(
+      Some("Top"),
+      "https://top.example/feed",
+      None
+    ),
+    feed(
+      None,
+      "https://b.example/feed",
+      Some("Tech")
+    ),
+  ];
+
+  let xml = write_opml("Mine", &feeds);
+
+  let parsed =
+    parse_opml(&xml).unwrap();
+
+  // Top-level feeds come first, then
+  // folders by path.
+  assert_eq!(parsed[0], feeds[1]);
+  assert_eq!(
+    parsed[1].folder.as_deref(),
+    Some("Tech")
+  );
+  assert_eq!(
+    parsed[1].title.as_deref(),
+    Some("https://b.example/feed")
+  );
+  assert_eq!(parsed[2], feeds[0]);
+  assert_eq!(
+    xml
+      .matches("text=\"Tech\"")
+      .count(),
+    1
+  );
+}
+
+#[test]
+fn feeds_file_gives_unique_ids_and_skips_bad_urls()
+ {
+  let mut feeds =
+    parse_opml(OPML).unwrap();
+  feeds.push(feed(
+    Some("Dup"),
+    "https://top.example/feed",
+    None
+  ));
+
+  let file = opml_feeds_file(
+    &feeds,
+    "tech",
+    Some("imp")
+  );
+
+  assert_eq!(file.feeds, 3);
+  assert_eq!(file.skipped.len(), 2);
+
+  let value: toml::Value =
+    toml::from_str(&file.content)
+      .unwrap();
+
+  assert_eq!(
+    value["category"].as_str(),
+    Some("tech")
+  );
+  assert_eq!(
+    value["id_prefix"].as_str(),
+    Some("imp")
+  );
+
+  let ids: Vec<&str> = value["feeds"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|f| f["id"].as_str().unwrap())
+    .collect();
+
+  assert_eq!(ids, [
+    "top", "ars", "ars-2"
+  ]);
+  assert_eq!(
+    value["feeds"][2]["tags"][0]
+      .as_str(),
+    Some("Tech & Code / Deep")
+  );
+}
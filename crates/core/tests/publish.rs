@@ -0,0 +1,211 @@
+use pulsewire_core::feed::parser::{
+  FeedItem,
+  parse
+};
+use pulsewire_core::feed::publish::{
+  Channel,
+  FeedFormat,
+  etag,
+  last_modified_ms,
+  not_modified,
+  render
+};
+
+const FEED_URL: &str =
+  "https://reader.example/p/abc/feed";
+
+fn item(
+  title: &str,
+  link: Option<&str>,
+  published_at_ms: Option<i64>
+) -> FeedItem {
+  FeedItem {
+    title: Some(title.to_string()),
+    link: link.map(String::from),
+    guid: None,
+    published_at_ms,
+    category: Some("gov".to_string()),
+    author: None,
+    description: Some(
+      "<p>Full <b>text</b></p>"
+        .to_string()
+    ),
+    summary: Some(
+      "Short & sweet".to_string()
+    ),
+    diff: None
+  }
+}
+
+fn items() -> Vec<FeedItem> {
+  vec![
+    FeedItem {
+      guid: Some("tag:gov,1".into()),
+      ..item(
+        "Budget <2025> & you",
+        Some(
+          "https://gov.example/a?x=1&y=2"
+        ),
+        Some(1_700_000_060_000)
+      )
+    },
+    item(
+      "Undated",
+      Some("https://gov.example/b"),
+      None
+    ),
+  ]
+}
+
+fn channel() -> Channel {
+  Channel {
+    title:         "Gov & Co"
+      .to_string(),
+    url:           FEED_URL.to_string(),
+    updated_at_ms: None
+  }
+}
+
+#[test]
+fn every_format_parses_back() {
+  for format in [
+    FeedFormat::Atom,
+    FeedFormat::Rss,
+    FeedFormat::Json
+  ] {
+    let body = render(
+      format,
+      &channel(),
+      &items()
+    );
+
+    let parsed = parse(body.as_bytes())
+      .unwrap_or_else(|e| {
+        panic!(
+          "{format:?}: {e}\n{body}"
+        )
+      });
+
+    assert_eq!(
+      parsed.metadata.title.as_deref(),
+      Some("Gov & Co"),
+      "{format:?}"
+    );
+    assert_eq!(
+      parsed.items.len(),
+      2,
+      "{format:?}"
+    );
+
+    let first = &parsed.items[0];
+
+    assert_eq!(
+      first.title.as_deref(),
+      Some("Budget <2025> & you"),
+      "{format:?}"
+    );
+    assert_eq!(
+      first.link.as_deref(),
+      Some(
+        "https://gov.example/a?x=1&y=2"
+      ),
+      "{format:?}"
+    );
+    assert_eq!(
+      first.guid.as_deref(),
+      Some("tag:gov,1"),
+      "{format:?}"
+    );
+    assert_eq!(
+      first.published_at_ms,
+      Some(1_700_000_060_000),
+      "{format:?}"
+    );
+    assert_eq!(
+      first.category.as_deref(),
+      Some("gov"),
+      "{format:?}"
+    );
+    assert!(
+      first
+        .summary
+        .as_deref()
+        .is_some_and(|s| {
+          s.contains("Short & sweet")
+        }),
+      "{format:?}"
+    );
+
+    // Without a guid the link is the
+    // id.
+    assert_eq!(
+      parsed.items[1].guid.as_deref(),
+      Some("https://gov.example/b"),
+      "{format:?}"
+    );
+  }
+}
+
+#[test]
+fn file_names_pick_the_format() {
+  assert_eq!(
+    FeedFormat::from_file("feed.atom"),
+    Some(FeedFormat::Atom)
+  );
+  assert_eq!(
+    FeedFormat::from_file("feed.json"),
+    Some(FeedFormat::Json)
+  );
+  assert_eq!(
+    FeedFormat::from_file("feed.xml"),
+    None
+  );
+  assert_eq!(
+    FeedFormat::Rss.file_name(),
+    "feed.rss"
+  );
+}
+
+#[test]
+fn conditional_gets_compare_entity_tags()
+ {
+  let body = render(
+    FeedFormat::Atom,
+    &channel(),
+    &items()
+  );
+  let tag = etag(&body);
+
+  assert_eq!(
+    last_modified_ms(&items()),
+    Some(1_700_000_060_000)
+  );
+  assert_ne!(
+    tag,
+    etag(&render(
+      FeedFormat::Atom,
+      &channel(),
+      &items()[1..]
+    ))
+  );
+
+  let weak = format!("W/{tag}");
+
+  assert!(not_modified(
+    Some(&tag),
+    &tag
+  ));
+  assert!(not_modified(
+    Some(&format!("\"x\", {weak}")),
+    &tag
+  ));
+  assert!(not_modified(
+    Some("*"),
+    &tag
+  ));
+  assert!(!not_modified(
+    Some("\"stale\""),
+    &tag
+  ));
+  assert!(!not_modified(None, &tag));
+}
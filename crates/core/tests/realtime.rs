@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use pulsewire_core::domain::realtime::{
+  EventId,
+  EventLog,
+  UnreadChange,
+  unread_changes
+};
+
+#[test]
+fn event_ids_round_trip() {
+  let id = EventId {
+    item_id: 42,
+    seq:     1_700_000_000_000_007
+  };
+
+  assert_eq!(
+    id.to_string(),
+    "42-1700000000000007"
+  );
+  assert_eq!(
+    id.to_string().parse::<EventId>(),
+    Ok(id)
+  );
+
+  assert!(
+    "42".parse::<EventId>().is_err()
+  );
+  assert!(
+    "x-1".parse::<EventId>().is_err()
+  );
+  assert!(
+    "42--1".parse::<EventId>().is_err()
+  );
+}
+
+#[test]
+fn event_log_replays_until_events_are_dropped()
+ {
+  let mut log = EventLog::new(100, 3);
+
+  assert_eq!(log.last(), 99);
+  assert_eq!(
+    log.since(99),
+    Some(vec![])
+  );
+
+  for name in ["a", "b", "c", "d"] {
+    log.push(name);
+  }
+
+  assert_eq!(log.last(), 103);
+  assert_eq!(
+    log.since(101),
+    Some(vec![(102, "c"), (103, "d")])
+  );
+  assert_eq!(
+    log.since(100),
+    Some(vec![
+      (101, "b"),
+      (102, "c"),
+      (103, "d")
+    ])
+  );
+
+  // "a" is gone, so a client that saw
+  // only the start missed it.
+  assert_eq!(log.since(99), None);
+
+  // Positions from a later log.
+  assert_eq!(log.since(104), None);
+}
+
+#[test]
+fn a_restarted_log_continues_above_the_old_one()
+ {
+  let mut before =
+    EventLog::new(100, 8);
+  let seen = before.push("a");
+
+  let after =
+    EventLog::<&str>::new(seen + 50, 8);
+
+  // Events between the two logs are
+  // lost; the client must reset.
+  assert_eq!(after.since(seen), None);
+}
+
+#[test]
+fn unread_changes_report_counts_and_deltas()
+ {
+  let counts =
+    |pairs: &[(&str, i64)]| {
+      pairs
+        .iter()
+        .map(|(feed, count)| {
+          (feed.to_string(), *count)
+        })
+        .collect::<BTreeMap<_, _>>()
+    };
+
+  let before = counts(&[
+    ("a", 3),
+    ("b", 1),
+    ("c", 2)
+  ]);
+  let after = counts(&[
+    ("a", 5),
+    ("c", 2),
+    ("d", 1)
+  ]);
+
+  assert_eq!(
+    unread_changes(&before, &after),
+    vec![
+      UnreadChange {
+        feed_id:      "a".into(),
+        unread_count: 5,
+        delta:        2
+      },
+      UnreadChange {
+        feed_id:      "b".into(),
+        unread_count: 0,
+        delta:        -1
+      },
+      UnreadChange {
+        feed_id:      "d".into(),
+        unread_count: 1,
+        delta:        1
+      },
+    ]
+  );
+
+  assert!(
+    unread_changes(&after, &after)
+      .is_empty()
+  );
+}
@@ -0,0 +1,236 @@
+use std::sync::Arc;
+
+use pulsewire_core::app::registry::{
+  discover,
+  sync_once
+};
+use pulsewire_core::domain::registry::{
+  RegistryFeed,
+  RegistryStatus,
+  registry_feed_id
+};
+use pulsewire_core::feed::discovery::feed_links;
+use pulsewire_core::ports::repo::Repo;
+use pulsewire_core::testing::{
+  FakeHttp,
+  FakeResponse,
+  MemoryRepo,
+  VirtualClock,
+  sim_config
+};
+
+const RSS: &str = "<rss \
+                   version=\"2.0\"><channel>\
+                   <title>Blog</title>\
+                   <link>https://example.org/</link>\
+                   <description>d</description>\
+                   </channel></rss>";
+
+fn entry(
+  url: &str,
+  status: RegistryStatus
+) -> RegistryFeed {
+  RegistryFeed {
+    id: registry_feed_id(url),
+    url: url.to_string(),
+    title: None,
+    added_by: 1,
+    status,
+    created_at_ms: 1_000,
+    reviewed_at_ms: None
+  }
+}
+
+#[test]
+fn feed_links_resolve_alternate_feeds()
+{
+  let html = r#"<html><head>
+    <link rel="stylesheet" href="/site.css">
+    <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+    <link rel="alternate nofollow" type="application/atom+xml" href="atom.xml">
+    <link rel="alternate" type="text/html" href="/fr/">
+    <link rel="alternate" type="application/rss+xml" href="https://example.org/feed.xml">
+    <link rel="alternate" type="application/feed+json" href="https://cdn.example.net/feed.json">
+  </head></html>"#;
+
+  assert_eq!(
+    feed_links(
+      html,
+      "https://example.org/blog/"
+    ),
+    vec![
+      "https://example.org/feed.xml",
+      "https://example.org/blog/atom.xml",
+      "https://cdn.example.net/feed.json"
+    ]
+  );
+}
+
+#[tokio::test]
+async fn discover_hides_upstream_failures()
+ {
+  let http = FakeHttp::new(Arc::new(
+    VirtualClock::new(0)
+  ));
+
+  http.respond(
+    "https://example.org/secret",
+    FakeResponse::status(500)
+  );
+
+  assert_eq!(
+    discover(
+      &http,
+      "https://example.org/secret"
+    )
+    .await
+    .unwrap_err(),
+    "could not fetch feed"
+  );
+}
+
+#[tokio::test]
+async fn discover_follows_page_to_its_feed()
+ {
+  let http = FakeHttp::new(Arc::new(
+    VirtualClock::new(0)
+  ));
+
+  http.respond(
+    "https://example.org/",
+    FakeResponse::ok(
+      "<html><head><link \
+       rel=\"alternate\" \
+       type=\"application/rss+xml\" \
+       href=\"/missing.xml\"><link \
+       rel=\"alternate\" \
+       type=\"application/rss+xml\" \
+       href=\"/rss\"></head></html>"
+    )
+  );
+  http.respond(
+    "https://example.org/missing.xml",
+    FakeResponse::status(404)
+  );
+  http.respond(
+    "https://example.org/rss",
+    FakeResponse::ok(RSS)
+  );
+
+  let feed = discover(
+    &http,
+    "https://example.org/"
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(
+    feed.url,
+    "https://example.org/rss"
+  );
+  assert_eq!(
+    feed.title.as_deref(),
+    Some("Blog")
+  );
+
+  http.respond(
+    "https://example.org/plain",
+    FakeResponse::ok("<html></html>")
+  );
+
+  assert!(
+    discover(
+      &http,
+      "https://example.org/plain"
+    )
+    .await
+    .is_err()
+  );
+}
+
+#[tokio::test]
+async fn sync_adds_new_approved_feeds_only()
+ {
+  let repo = MemoryRepo::new();
+  let cfg = sim_config();
+  let zone = cfg.timezone;
+
+  for (url, status) in [
+    (
+      "https://a.example/feed",
+      RegistryStatus::Approved
+    ),
+    (
+      "https://b.example/feed",
+      RegistryStatus::Pending
+    ),
+    (
+      "https://c.example/feed",
+      RegistryStatus::Rejected
+    )
+  ] {
+    repo
+      .insert_registry_feed(
+        &entry(url, status),
+        &zone
+      )
+      .await
+      .unwrap();
+  }
+
+  assert_eq!(
+    sync_once(&repo, &cfg).await,
+    Ok(1)
+  );
+
+  let pending = registry_feed_id(
+    "https://b.example/feed"
+  );
+
+  assert!(
+    repo
+      .review_registry_feed(
+        &pending,
+        RegistryStatus::Approved,
+        2_000,
+        &zone
+      )
+      .await
+      .unwrap()
+  );
+  assert!(
+    !repo
+      .review_registry_feed(
+        &pending,
+        RegistryStatus::Rejected,
+        3_000,
+        &zone
+      )
+      .await
+      .unwrap()
+  );
+
+  // The feed added before is kept.
+  assert_eq!(
+    sync_once(&repo, &cfg).await,
+    Ok(1)
+  );
+  assert_eq!(
+    sync_once(&repo, &cfg).await,
+    Ok(0)
+  );
+
+  repo.read(|t| {
+    let ids: Vec<&String> =
+      t.feeds.keys().collect();
+
+    assert_eq!(ids.len(), 2);
+
+    for feed in t.feeds.values() {
+      assert_eq!(feed.category, "user");
+      assert!(
+        feed.id.starts_with("u-")
+      );
+    }
+  });
+}
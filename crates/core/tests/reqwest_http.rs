@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use pulsewire_core::infra::reqwest_http::ReqwestHttp;
+use pulsewire_core::ports::http::Http;
+use tokio::io::{
+  AsyncReadExt,
+  AsyncWriteExt
+};
+use tokio::net::TcpListener;
+
+/// Answers every request on a loopback
+/// port with `respond(request)`,
+/// returning the port.
+async fn serve<F>(respond: F) -> u16
+where
+  F:
+    Fn(&str) -> String + Send + 'static
+{
+  let listener =
+    TcpListener::bind("127.0.0.1:0")
+      .await
+      .unwrap();
+  let port = listener
+    .local_addr()
+    .unwrap()
+    .port();
+
+  tokio::spawn(async move {
+    while let Ok((mut socket, _)) =
+      listener.accept().await
+    {
+      let mut buf = [0u8; 4096];
+      let n = socket
+        .read(&mut buf)
+        .await
+        .unwrap_or(0);
+      let request =
+        String::from_utf8_lossy(
+          &buf[..n]
+        );
+
+      let _ = socket
+        .write_all(
+          respond(&request).as_bytes()
+        )
+        .await;
+    }
+  });
+
+  port
+}
+
+/// A response with `headers` and
+/// `body`.
+fn response(
+  status: &str,
+  headers: &[String],
+  body: &str
+) -> String {
+  let mut lines =
+    vec![format!("HTTP/1.1 {status}")];
+
+  lines.extend_from_slice(headers);
+  lines.push(format!(
+    "Content-Length: {}",
+    body.len()
+  ));
+  lines.push(
+    "Connection: close".to_string()
+  );
+  lines.push(String::new());
+  lines.push(body.to_string());
+
+  lines.join("\r\n")
+}
+
+/// Answers with the request's `Cookie`
+/// header, empty without one.
+fn echo_cookie(
+  request: &str
+) -> String {
+  let cookie = request
+    .lines()
+    .find_map(|line| {
+      let (name, value) =
+        line.split_once(':')?;
+
+      name
+        .eq_ignore_ascii_case("cookie")
+        .then(|| value.trim())
+    })
+    .unwrap_or_default()
+    .to_string();
+
+  response("200 OK", &[], &cookie)
+}
+
+fn client() -> ReqwestHttp {
+  ReqwestHttp::new(
+    "test-agent".into(),
+    &HashMap::new()
+  )
+  .unwrap()
+}
+
+#[tokio::test]
+async fn redirects_keep_credentials_only_on_the_same_origin()
+ {
+  let echo = serve(echo_cookie).await;
+
+  let redirect =
+    serve(move |request| {
+      let to = if request
+        .starts_with("GET /other")
+      {
+        format!(
+          "http://127.0.0.1:{echo}/"
+        )
+      } else {
+        "/echo".to_string()
+      };
+
+      if request
+        .starts_with("GET /echo")
+      {
+        return echo_cookie(request);
+      }
+
+      response(
+        "302 Found",
+        &[format!("Location: {to}")],
+        ""
+      )
+    })
+    .await;
+
+  let http = client();
+
+  for (path, cookie) in
+    [("same", "sid=1"), ("other", "")]
+  {
+    let res = http
+      .get(
+        "127.0.0.1",
+        &format!(
+          "http://127.0.0.1:{redirect}/\
+           {path}"
+        ),
+        Some("sid=1"),
+        None
+      )
+      .await;
+
+    assert_eq!(
+      res.redirects.len(),
+      1,
+      "{path}"
+    );
+    assert_eq!(
+      res.body.as_deref(),
+      Some(cookie.as_bytes()),
+      "{path}"
+    );
+  }
+}
+
+#[tokio::test]
+async fn posts_never_replay_a_receivers_cookie()
+ {
+  // Sets a cookie on every answer and
+  // echoes any cookie sent back.
+  let port = serve(|request| {
+    let echoed = echo_cookie(request);
+    let cookie = echoed
+      .rsplit("\r\n")
+      .next()
+      .unwrap_or_default();
+
+    response(
+      "200 OK",
+      &["Set-Cookie: seen=1".into()],
+      cookie
+    )
+  })
+  .await;
+
+  let http = client();
+  let url =
+    format!("http://127.0.0.1:{port}/");
+
+  for _ in 0..2 {
+    let res = http
+      .post(
+        "127.0.0.1",
+        &url,
+        &HashMap::new(),
+        b"{}".to_vec()
+      )
+      .await;
+
+    assert_eq!(
+      res.body.as_deref(),
+      Some(&b""[..])
+    );
+  }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use pulsewire_core::domain::retention::{
+  RetentionConfig,
+  RetentionLimit,
+  RetentionPolicy,
+  RetentionTable
+};
+use pulsewire_core::infra::migrations::{
+  self,
+  Component
+};
+use pulsewire_core::infra::sqlite_repo::{
+  self,
+  SqliteRepo
+};
+use pulsewire_core::ports::repo::Repo;
+
+fn limit(
+  max_age_days: Option<u64>,
+  max_rows: Option<u64>
+) -> RetentionLimit {
+  RetentionLimit {
+    max_age_days,
+    max_rows
+  }
+}
+
+#[test]
+
+fn feed_then_category_then_defaults() {
+  let mut categories = HashMap::new();
+  let mut feeds = HashMap::new();
+
+  categories.insert(
+    "news".to_string(),
+    RetentionPolicy {
+      items: limit(Some(7), None),
+      ..Default::default()
+    }
+  );
+
+  feeds.insert(
+    "f1".to_string(),
+    RetentionPolicy {
+      items: limit(None, Some(10)),
+      ..Default::default()
+    }
+  );
+
+  let cfg = RetentionConfig {
+    enabled: true,
+    interval_seconds: 3600,
+    batch_size: 500,
+    server_schema: "server".to_string(),
+    defaults: RetentionPolicy {
+      items: limit(Some(30), Some(100)),
+      ..Default::default()
+    },
+    categories,
+    feeds
+  };
+
+  assert_eq!(
+    cfg.limit_for(
+      "f1",
+      "news",
+      RetentionTable::Items
+    ),
+    limit(Some(7), Some(10))
+  );
+  assert_eq!(
+    cfg.limit_for(
+      "f2",
+      "tech",
+      RetentionTable::Items
+    ),
+    limit(Some(30), Some(100))
+  );
+  assert!(
+    cfg
+      .limit_for(
+        "f1",
+        "news",
+        RetentionTable::Payloads
+      )
+      .is_unbounded()
+  );
+}
+
+/// Prunes every item of a feed with
+/// items 1 to 3 after running
+/// `marks`, returning the ids left.
+async fn survivors(
+  name: &str,
+  marks: &[&str]
+) -> Vec<i64> {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-retention-{name}-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  let path = dir.join("t.db");
+
+  let pool =
+    sqlite_repo::create_pool(&path)
+      .await
+      .unwrap();
+
+  for component in [
+    Component::Fetcher,
+    Component::Server
+  ] {
+    migrations::sqlite::apply_pending(
+      &pool, component
+    )
+    .await
+    .unwrap();
+  }
+
+  for sql in [
+    "INSERT INTO categories(name, \
+     created_at_ms) VALUES ('news', 0)",
+    "INSERT INTO feeds(id, url, \
+     domain, category, \
+     base_poll_seconds, \
+     created_at_ms) VALUES ('f1', \
+     'https://a/rss', 'a', 'news', \
+     60, 0)",
+    "INSERT INTO feed_payloads(id, \
+     feed_id, fetched_at_ms) VALUES \
+     (1, 'f1', 0)",
+    "INSERT INTO feed_items(id, \
+     payload_id, feed_id) VALUES (1, \
+     1, 'f1'), (2, 1, 'f1'), (3, 1, \
+     'f1')",
+    "INSERT INTO users(id, username, \
+     password_hash, created_at) \
+     VALUES (1, 'u', 'x', \
+     datetime('now'))",
+  ]
+  .iter()
+  .chain(marks)
+  {
+    sqlx::query(sql)
+      .execute(&pool)
+      .await
+      .unwrap();
+  }
+
+  let repo = SqliteRepo::new(&path)
+    .await
+    .unwrap();
+
+  let pruned = repo
+    .prune_batch(
+      RetentionTable::Items,
+      "f1",
+      limit(None, Some(0)),
+      0,
+      100,
+      "server"
+    )
+    .await
+    .unwrap();
+
+  let left: Vec<i64> =
+    sqlx::query_scalar(
+      "SELECT id FROM feed_items"
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    pruned as usize + left.len(),
+    3
+  );
+
+  left
+}
+
+#[tokio::test]
+async fn starred_items_survive_pruning()
+{
+  assert_eq!(
+    survivors("star", &[
+      "INSERT INTO \
+       entry_stars(user_id, item_id, \
+       created_at) VALUES (1, 2, \
+       datetime('now'))"
+    ])
+    .await,
+    vec![2]
+  );
+}
+
+#[tokio::test]
+async fn queued_items_survive_pruning()
+{
+  assert_eq!(
+    survivors("queue", &[
+      "INSERT INTO \
+       entry_queue(user_id, item_id, \
+       created_at) VALUES (1, 3, \
+       datetime('now'))"
+    ])
+    .await,
+    vec![3]
+  );
+}
+
+#[tokio::test]
+async fn labeled_items_survive_pruning()
+{
+  assert_eq!(
+    survivors("label", &[
+      "INSERT INTO labels(id, \
+       user_id, name, created_at) \
+       VALUES (1, 1, 'keep', \
+       datetime('now'))",
+      "INSERT INTO \
+       entry_labels(label_id, \
+       item_id, created_at) VALUES \
+       (1, 1, datetime('now'))"
+    ])
+    .await,
+    vec![1]
+  );
+}
@@ -0,0 +1,117 @@
+use pulsewire_core::infra::config::rewrite_feed_urls;
+
+const BEFORE: &str = r#"# News feeds.
+[[feeds]]
+id  = "a"
+url = "https://old.example/rss"   # moved in 2024
+
+[[feeds]]
+id = "b"
+url = 'https://old.example/rss2'
+
+[[watches]]
+id  = "w"
+url = "https://old.example/page"
+
+[links]
+url = "https://old.example/rss"
+"#;
+
+const AFTER: &str = r#"# News feeds.
+[[feeds]]
+id  = "a"
+url = "https://new.example/rss"   # moved in 2024
+
+[[feeds]]
+id = "b"
+url = 'https://old.example/rss2'
+
+[[watches]]
+id  = "w"
+url = "https://new.example/page"
+
+[links]
+url = "https://old.example/rss"
+"#;
+
+#[tokio::test]
+async fn rewrites_feed_and_watch_urls_only()
+ {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-rewrite-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  let news = dir.join("news.toml");
+  let other = dir.join("other.toml");
+
+  std::fs::write(&news, BEFORE)
+    .unwrap();
+  std::fs::write(
+    &other,
+    "feeds = [{ id = \"c\", url = \
+     \"https://old.example/c\" }]\n"
+  )
+  .unwrap();
+
+  let changes = [
+    (
+      "https://old.example/rss",
+      "https://new.example/rss"
+    ),
+    (
+      "https://old.example/page",
+      "https://new.example/page"
+    ),
+    (
+      "https://old.example/c",
+      "https://new.example/c\"?"
+    )
+  ]
+  .map(|(old, new)| {
+    (old.to_string(), new.to_string())
+  });
+
+  let mut files =
+    rewrite_feed_urls(&dir, &changes)
+      .await
+      .unwrap();
+  files.sort();
+
+  assert_eq!(files, vec![
+    news.clone(),
+    other.clone()
+  ]);
+  assert_eq!(
+    std::fs::read_to_string(&news)
+      .unwrap(),
+    AFTER
+  );
+
+  let other: toml::Value =
+    toml::from_str(
+      &std::fs::read_to_string(&other)
+        .unwrap()
+    )
+    .unwrap();
+
+  assert_eq!(
+    other["feeds"][0]["url"].as_str(),
+    Some("https://new.example/c\"?")
+  );
+
+  // Nothing left to change.
+  assert!(
+    rewrite_feed_urls(&dir, &changes)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,211 @@
+use pulsewire_core::domain::search::parse_search;
+use pulsewire_core::feed::parser;
+use pulsewire_core::infra::sqlite_repo::{
+  self,
+  SqliteRepo
+};
+use pulsewire_core::ports::repo::Repo;
+use pulsewire_core::testing::feed;
+
+const UTC: chrono_tz::Tz =
+  chrono_tz::UTC;
+
+#[test]
+fn query_syntax_renders_for_both_dialects()
+ {
+  let parsed = parse_search(
+    "\"rust async\" title:tokio \
+     -nightly OR feed:a -feed:b \
+     pulse-wire"
+  )
+  .unwrap();
+
+  assert_eq!(parsed.feed_ids, ["a"]);
+  assert_eq!(
+    parsed.excluded_feed_ids,
+    ["b"]
+  );
+  assert_eq!(
+    parsed.fts5_match(),
+    "((\"rust async\" AND title : \
+     \"tokio\") NOT (\"nightly\")) OR \
+     (\"pulse wire\")"
+  );
+  assert_eq!(
+    parsed.tsquery(),
+    "(('rust' <-> 'async') & \
+     'tokio':A & !('nightly')) | \
+     (('pulse' <-> 'wire'))"
+  );
+
+  for (q, expected) in [
+    ("feed:a", "at least one word"),
+    ("\"'\"", "at least one word"),
+    (
+      "rust OR -nightly",
+      "every OR alternative"
+    )
+  ] {
+    let error =
+      parse_search(q).unwrap_err();
+
+    assert!(
+      error.contains(expected),
+      "{q}: {error}"
+    );
+  }
+}
+
+#[tokio::test]
+
+async fn sqlite_index_follows_items() {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-search-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  let db = dir.join("search.db");
+
+  let repo =
+    SqliteRepo::new(&db).await.unwrap();
+
+  repo.migrate(&UTC, 60).await.unwrap();
+
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  let mut config = feed(
+    "a",
+    "https://a.example/rss",
+    "a.example",
+    "news",
+    300
+  );
+
+  config.language =
+    Some("en".to_string());
+
+  repo
+    .upsert_feeds_bulk(
+      vec![config],
+      100,
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  repo
+    .insert_payload_with_items(
+      "a",
+      1_000,
+      Some("v1"),
+      None,
+      None,
+      &parser::parse(
+        b"<rss version=\"2.0\"><channel>\
+          <title>t</title>\
+          <item><guid>1</guid>\
+          <title>Tokio release</title>\
+          <description>Async runtime \
+          for Rust</description></item>\
+          <item><guid>2</guid>\
+          <title>Weekly notes</title>\
+          <description>Mentions tokio \
+          once</description></item>\
+          <item><guid>3</guid>\
+          <title>Garden</title>\
+          <description>Tomatoes\
+          </description></item>\
+          </channel></rss>"
+      )
+      .unwrap(),
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  let pool =
+    sqlite_repo::create_pool(&db)
+      .await
+      .unwrap();
+
+  let search = async |q: &str| {
+    sqlx::query_scalar::<_, String>(
+      "SELECT fi.guid FROM \
+       feed_items_fts JOIN feed_items \
+       fi ON fi.id = \
+       feed_items_fts.rowid WHERE \
+       feed_items_fts MATCH ?1 ORDER \
+       BY bm25(feed_items_fts, 5.0, \
+       2.0, 1.0)"
+    )
+    .bind(
+      parse_search(q)
+        .unwrap()
+        .fts5_match()
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap()
+  };
+
+  // Title hits outrank body hits.
+  assert_eq!(search("tokio").await, [
+    "1", "2"
+  ]);
+  assert_eq!(
+    search("title:tokio").await,
+    ["1"]
+  );
+  assert_eq!(
+    search("tokio -runtime").await,
+    ["2"]
+  );
+  assert_eq!(
+    search("\"rust async\"").await,
+    Vec::<String>::new()
+  );
+  assert_eq!(
+    search("garden OR release")
+      .await
+      .len(),
+    2
+  );
+
+  let languages: Vec<Option<String>> =
+    sqlx::query_scalar(
+      "SELECT DISTINCT language FROM \
+       feed_items"
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+
+  assert_eq!(languages, [Some(
+    "en".to_string()
+  )]);
+
+  sqlx::query(
+    "DELETE FROM feed_items WHERE \
+     guid = '1'"
+  )
+  .execute(&pool)
+  .await
+  .unwrap();
+
+  assert_eq!(search("tokio").await, [
+    "2"
+  ]);
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,410 @@
+use std::time::Duration;
+
+use pulsewire_core::domain::registry::registry_feed_id;
+use pulsewire_core::ports::repo::Repo;
+use pulsewire_core::testing::{
+  FakeResponse,
+  Simulation,
+  feed,
+  sim_config,
+  with_domain_limit
+};
+
+const DAY: Duration =
+  Duration::from_secs(86_400);
+
+fn rss(guids: &[&str]) -> Vec<u8> {
+  let items: String = guids
+    .iter()
+    .map(|guid| {
+      format!(
+        "<item><title>{guid}</\
+         title><guid>{guid}</guid></\
+         item>"
+      )
+    })
+    .collect();
+
+  format!(
+    "<?xml version=\"1.0\"?><rss \
+     version=\"2.0\"\
+     ><channel><title>t</\
+     title>{items}</channel></rss>"
+  )
+  .into_bytes()
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn unchanged_feed_is_polled_within_bounds()
+ {
+  let url = "https://a.example/rss";
+
+  let cfg = sim_config();
+
+  let mut sim = Simulation::new(
+    cfg.clone(),
+    vec![feed(
+      "f1",
+      url,
+      "a.example",
+      "news",
+      300
+    )],
+    7
+  )
+  .await
+  .unwrap();
+
+  sim.http.respond(
+    url,
+    FakeResponse::ok(rss(&["a"]))
+      .with_etag("v1")
+  );
+  sim.http.respond_from(
+    url,
+    Duration::from_secs(6 * 3600),
+    FakeResponse::ok(rss(&["a", "b"]))
+      .with_etag("v2")
+  );
+
+  sim.run_for(DAY).await.unwrap();
+
+  // One GET for the first fetch and
+  // one after the change; HEADs in
+  // between.
+  assert_eq!(
+    sim.http.count("GET", url),
+    2
+  );
+
+  let times: Vec<i64> = sim
+    .http
+    .requests()
+    .iter()
+    .map(|r| r.at_ms)
+    .collect();
+
+  let min_gap = (300.0
+    * (1.0 - cfg.jitter_fraction)
+    * 1000.0) as i64;
+  let max_gap = (cfg.max_poll_seconds
+    as f64
+    * (1.0 + cfg.jitter_fraction)
+    * 1000.0) as i64
+    + 5_000;
+
+  for pair in times.windows(2) {
+    let gap = pair[1] - pair[0];
+
+    assert!(
+      (min_gap..=max_gap)
+        .contains(&gap),
+      "gap {gap} ms"
+    );
+  }
+
+  sim.repo.read(|t| {
+    let payloads = t.payloads_for("f1");
+
+    assert_eq!(payloads.len(), 2);
+    assert_eq!(
+      payloads[1].etag.as_deref(),
+      Some("v2")
+    );
+    assert_eq!(
+      t.events_for("f1").len(),
+      times.len()
+    );
+  });
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn server_errors_back_off_then_park_the_feed()
+ {
+  let url = "https://down.example/rss";
+
+  let cfg = sim_config();
+
+  let mut sim = Simulation::new(
+    cfg.clone(),
+    vec![feed(
+      "f1",
+      url,
+      "down.example",
+      "news",
+      300
+    )],
+    7
+  )
+  .await
+  .unwrap();
+
+  sim.http.respond(
+    url,
+    FakeResponse::status(503)
+  );
+
+  sim.run_for(DAY).await.unwrap();
+
+  let times: Vec<i64> = sim
+    .http
+    .requests()
+    .iter()
+    .map(|r| r.at_ms)
+    .collect();
+
+  assert_eq!(
+    times.len(),
+    cfg.max_consecutive_errors as usize
+  );
+
+  let gaps: Vec<i64> = times
+    .windows(2)
+    .map(|p| p[1] - p[0])
+    .collect();
+
+  assert!(
+    gaps
+      .windows(2)
+      .all(|g| g[0] < g[1]),
+    "gaps {gaps:?}"
+  );
+
+  sim.repo.read(|t| {
+    let parked = t
+      .error_feeds
+      .get("f1")
+      .expect("feed parked");
+
+    assert_eq!(
+      parked.last_error_status,
+      Some(503)
+    );
+    assert!(t.feed_payloads.is_empty());
+  });
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn domain_limit_caps_requests_in_flight()
+ {
+  let feeds = (0..6)
+    .map(|i| {
+      feed(
+        &format!("f{i}"),
+        &format!(
+          "https://slow.example/{i}"
+        ),
+        "slow.example",
+        "news",
+        300
+      )
+    })
+    .chain([feed(
+      "other",
+      "https://other.example/rss",
+      "other.example",
+      "news",
+      300
+    )])
+    .collect();
+
+  let mut sim = Simulation::new(
+    with_domain_limit(
+      sim_config(),
+      "slow.example",
+      2
+    ),
+    feeds,
+    7
+  )
+  .await
+  .unwrap();
+
+  for i in 0..6 {
+    sim.http.respond(
+      &format!(
+        "https://slow.example/{i}"
+      ),
+      FakeResponse::ok(rss(&["a"]))
+        .with_latency(
+          Duration::from_secs(3)
+        )
+    );
+  }
+
+  sim.http.respond(
+    "https://other.example/rss",
+    FakeResponse::ok(rss(&["a"]))
+  );
+
+  sim.tick().await.unwrap();
+
+  assert_eq!(
+    sim
+      .http
+      .max_in_flight("slow.example"),
+    2
+  );
+  assert_eq!(
+    sim.http.requests().len(),
+    7
+  );
+
+  // Three rounds of two 3 s requests.
+  assert_eq!(
+    sim.now_ms() - sim.clock.start_ms(),
+    9_000
+  );
+
+  sim.repo.read(|t| {
+    assert_eq!(
+      t.feed_payloads.len(),
+      7
+    );
+  });
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn gone_feed_waits_for_a_new_url()
+{
+  let old = "https://gone.example/rss";
+  let new = "https://gone.example/feed";
+
+  let f1 = |url: &str| {
+    feed(
+      "f1",
+      url,
+      "gone.example",
+      "news",
+      300
+    )
+  };
+
+  let mut sim = Simulation::new(
+    sim_config(),
+    vec![f1(old)],
+    7
+  )
+  .await
+  .unwrap();
+
+  sim.http.respond(
+    old,
+    FakeResponse::status(410)
+  );
+  sim.http.respond(
+    new,
+    FakeResponse::ok(rss(&["a"]))
+  );
+
+  sim.run_for(DAY).await.unwrap();
+
+  // Restating the same URL keeps the
+  // feed archived.
+  sim
+    .repo
+    .upsert_feeds_bulk(
+      vec![f1(old)],
+      1000,
+      &sim.cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  sim.run_for(DAY).await.unwrap();
+
+  assert_eq!(
+    sim.http.requests().len(),
+    1
+  );
+
+  sim
+    .repo
+    .upsert_feeds_bulk(
+      vec![f1(new)],
+      1000,
+      &sim.cfg.timezone
+    )
+    .await
+    .unwrap();
+
+  sim.run_for(DAY).await.unwrap();
+
+  assert!(
+    sim.http.count("GET", new) > 0
+  );
+
+  sim.repo.read(|t| {
+    assert_eq!(
+      t.payloads_for("f1").len(),
+      1
+    );
+    assert_ne!(
+      t.state_current["f1"].phase,
+      "Archived"
+    );
+  });
+}
+
+#[tokio::test(start_paused = true)]
+
+async fn registry_feeds_use_the_public_client()
+ {
+  let config_url =
+    "https://a.example/rss";
+  let registry_url =
+    "https://b.example/rss";
+
+  let mut sim = Simulation::new(
+    sim_config(),
+    vec![
+      feed(
+        "f1",
+        config_url,
+        "a.example",
+        "news",
+        300
+      ),
+      feed(
+        &registry_feed_id(registry_url),
+        registry_url,
+        "b.example",
+        "news",
+        300
+      ),
+    ],
+    7
+  )
+  .await
+  .unwrap();
+
+  sim.http.respond(
+    config_url,
+    FakeResponse::ok(rss(&["a"]))
+  );
+  sim.public_http.respond(
+    registry_url,
+    FakeResponse::ok(rss(&["b"]))
+  );
+
+  sim.tick().await.unwrap();
+
+  assert_eq!(
+    sim.http.count("GET", config_url),
+    1
+  );
+  assert_eq!(
+    sim
+      .public_http
+      .count("GET", registry_url),
+    1
+  );
+  assert_eq!(
+    sim.http.count("GET", registry_url),
+    0
+  );
+}
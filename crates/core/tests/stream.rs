@@ -0,0 +1,65 @@
+use pulsewire_core::domain::stream::{
+  ReadFilter,
+  StreamScope
+};
+
+#[test]
+fn scopes_parse_with_subscriptions_default()
+ {
+  for (raw, scope) in [
+    (None, StreamScope::Subscriptions),
+    (
+      Some(""),
+      StreamScope::Subscriptions
+    ),
+    (
+      Some("subscriptions"),
+      StreamScope::Subscriptions
+    ),
+    (Some("all"), StreamScope::All),
+    (
+      Some("favorites"),
+      StreamScope::Favorites
+    ),
+    (
+      Some("folder:12"),
+      StreamScope::Folder(12)
+    )
+  ] {
+    assert_eq!(
+      StreamScope::parse(raw),
+      Ok(scope)
+    );
+  }
+
+  for raw in [
+    "folder:", "folder:x", "folders",
+    "All"
+  ] {
+    assert_eq!(
+      StreamScope::parse(Some(raw)),
+      Err(format!(
+        "invalid scope: {raw}"
+      ))
+    );
+  }
+}
+
+#[test]
+fn read_filters_parse() {
+  assert_eq!(
+    ReadFilter::parse(None),
+    Ok(ReadFilter::All)
+  );
+  assert_eq!(
+    ReadFilter::parse(Some("unread")),
+    Ok(ReadFilter::Unread)
+  );
+  assert_eq!(
+    ReadFilter::parse(Some("seen")),
+    Err(
+      "invalid read filter: seen"
+        .to_string()
+    )
+  );
+}
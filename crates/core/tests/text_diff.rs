@@ -0,0 +1,40 @@
+use pulsewire_core::domain::text_diff::{TRUNCATED_MARKER, line_diff};
+
+#[test]
+
+fn diff_lists_removed_and_added_lines()
+{
+  let diff = line_diff(
+    "a\nb\nc", "a\nc\nd", 1024
+  )
+  .unwrap();
+
+  assert_eq!(diff, "- b\n+ d");
+  assert!(
+    line_diff("a\nb", "a\nb", 1024)
+      .is_none()
+  );
+}
+
+#[test]
+
+fn diff_is_capped_at_line_boundary() {
+  let new = (0..100)
+    .map(|i| format!("line {i}"))
+    .collect::<Vec<_>>()
+    .join("\n");
+
+  let diff =
+    line_diff("", &new, 64).unwrap();
+
+  assert!(
+    diff.len()
+      <= 64 + TRUNCATED_MARKER.len()
+  );
+  assert!(
+    diff.ends_with(TRUNCATED_MARKER)
+  );
+  assert!(
+    diff.starts_with("+ line 0\n")
+  );
+}
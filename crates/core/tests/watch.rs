@@ -0,0 +1,425 @@
+use std::time::Duration;
+
+use pulsewire_core::domain::model::WatchEmitMode;
+use pulsewire_core::domain::text_diff::TRUNCATED_MARKER;
+use pulsewire_core::testing::{
+  FakeResponse,
+  Simulation,
+  sim_config,
+  watch
+};
+
+const URL: &str =
+  "https://a.example/news";
+
+/// A list page linking each title,
+/// with an optional next page link.
+fn page(
+  titles: &[&str],
+  next: Option<&str>
+) -> Vec<u8> {
+  let items: String = titles
+    .iter()
+    .map(|title| {
+      format!(
+        "<li><a href=\"https://\
+         a.example/{title}\">{title}\
+         </a></li>"
+      )
+    })
+    .collect();
+
+  let next = next
+    .map(|href| {
+      format!(
+        "<a class=\"next\" \
+         href=\"{href}\">Older</a>"
+      )
+    })
+    .unwrap_or_default();
+
+  format!(
+    "<html><body><ul>{items}</\
+     ul>{next}</body></html>"
+  )
+  .into_bytes()
+}
+
+/// Guids stored for the watch, in
+/// insertion order.
+fn guids(
+  sim: &Simulation
+) -> Vec<String> {
+  sim.repo.read(|t| {
+    t.feed_items
+      .iter()
+      .filter_map(|row| {
+        row.item.guid.clone()
+      })
+      .collect()
+  })
+}
+
+#[tokio::test(start_paused = true)]
+async fn any_change_emits_items_and_the_diff()
+ {
+  let mut w = watch(
+    "w1",
+    URL,
+    "a.example",
+    "news",
+    300,
+    "li a"
+  );
+  w.emit_mode =
+    WatchEmitMode::AnyChange;
+
+  let mut sim =
+    Simulation::with_watches(
+      sim_config(),
+      Vec::new(),
+      vec![w],
+      7
+    )
+    .await
+    .unwrap();
+
+  sim.http.respond(
+    URL,
+    FakeResponse::ok(page(
+      &["one"],
+      None
+    ))
+  );
+  sim.http.respond_from(
+    URL,
+    Duration::from_secs(3600),
+    FakeResponse::ok(page(
+      &["one", "two"],
+      None
+    ))
+  );
+
+  sim
+    .run_for(Duration::from_secs(
+      2 * 3600
+    ))
+    .await
+    .unwrap();
+
+  sim.repo.read(|t| {
+    let items: Vec<_> = t
+      .feed_items
+      .iter()
+      .filter(|row| row.feed_id == "w1")
+      .map(|row| &row.item)
+      .collect();
+
+    let guids: Vec<&str> = items
+      .iter()
+      .filter_map(|item| {
+        item.guid.as_deref()
+      })
+      .collect();
+
+    assert!(
+      guids.contains(
+        &"w1:https://a.example/one"
+      ),
+      "{guids:?}"
+    );
+    assert!(
+      guids.contains(
+        &"w1:https://a.example/two"
+      ),
+      "{guids:?}"
+    );
+
+    let diff = items
+      .iter()
+      .find_map(|item| {
+        item.diff.as_deref()
+      })
+      .expect("change item with diff");
+
+    assert!(
+      diff.contains("two"),
+      "{diff}"
+    );
+  });
+}
+
+#[tokio::test(start_paused = true)]
+async fn any_change_diffs_are_capped_per_watch()
+ {
+  let mut w = watch(
+    "w1",
+    URL,
+    "a.example",
+    "news",
+    300,
+    "li a"
+  );
+  w.emit_mode =
+    WatchEmitMode::AnyChange;
+  w.max_diff_bytes = 8;
+
+  let mut sim =
+    Simulation::with_watches(
+      sim_config(),
+      Vec::new(),
+      vec![w],
+      7
+    )
+    .await
+    .unwrap();
+
+  sim.http.respond(
+    URL,
+    FakeResponse::ok(page(
+      &["one"],
+      None
+    ))
+  );
+  sim.http.respond_from(
+    URL,
+    Duration::from_secs(3600),
+    FakeResponse::ok(page(
+      &["one", "two", "three"],
+      None
+    ))
+  );
+
+  sim
+    .run_for(Duration::from_secs(
+      2 * 3600
+    ))
+    .await
+    .unwrap();
+
+  sim.repo.read(|t| {
+    let diff = t
+      .feed_items
+      .iter()
+      .find_map(|row| {
+        row.item.diff.as_deref()
+      })
+      .expect("change item with diff");
+
+    assert_eq!(
+      diff,
+      format!(
+        "+ two\n{TRUNCATED_MARKER}"
+      )
+    );
+  });
+}
+
+const PAGE_2: &str =
+  "https://a.example/news?page=2";
+
+const PAGE_3: &str =
+  "https://a.example/news?page=3";
+
+/// A watch over three linked pages;
+/// page 2 repeats an item of page 1.
+async fn paged(
+  max_pages: u64
+) -> Simulation {
+  let mut w = watch(
+    "w1",
+    URL,
+    "a.example",
+    "news",
+    300,
+    "li a"
+  );
+  w.next_page_selector =
+    Some("a.next".into());
+  w.max_pages = max_pages;
+
+  let sim = Simulation::with_watches(
+    sim_config(),
+    Vec::new(),
+    vec![w],
+    7
+  )
+  .await
+  .unwrap();
+
+  for (url, body) in [
+    (
+      URL,
+      page(
+        &["one", "two"],
+        Some("/news?page=2")
+      )
+    ),
+    (
+      PAGE_2,
+      page(
+        &["two", "three"],
+        Some("?page=3")
+      )
+    ),
+    (PAGE_3, page(&["four"], None))
+  ] {
+    sim.http.respond(
+      url,
+      FakeResponse::ok(body)
+    );
+  }
+
+  sim
+}
+
+fn guid(title: &str) -> String {
+  format!(
+    "w1:https://a.example/{title}"
+  )
+}
+
+#[tokio::test(start_paused = true)]
+async fn follows_next_page_links_once_per_item()
+ {
+  let mut sim = paged(5).await;
+
+  sim.tick().await.unwrap();
+
+  assert_eq!(
+    guids(&sim),
+    ["one", "two", "three", "four"]
+      .map(guid)
+  );
+
+  for url in [URL, PAGE_2, PAGE_3] {
+    assert_eq!(
+      sim.http.count("GET", url),
+      1,
+      "{url}"
+    );
+  }
+}
+
+#[tokio::test(start_paused = true)]
+async fn cross_origin_next_pages_get_no_cookie()
+ {
+  const PLAIN: &str =
+    "http://a.example/news?page=2";
+  const OTHER: &str =
+    "https://b.example/news?page=3";
+
+  let mut w = watch(
+    "w1",
+    URL,
+    "a.example",
+    "news",
+    300,
+    "li a"
+  );
+  w.next_page_selector =
+    Some("a.next".into());
+  w.max_pages = 3;
+
+  let mut sim =
+    Simulation::with_watches(
+      sim_config(),
+      Vec::new(),
+      vec![w],
+      7
+    )
+    .await
+    .unwrap();
+
+  sim.set_cookie("w1", "sid=secret");
+
+  sim.http.respond(
+    URL,
+    FakeResponse::ok(page(
+      &["one"],
+      Some(PLAIN)
+    ))
+  );
+  sim.http.respond(
+    PLAIN,
+    FakeResponse::ok(page(
+      &["two"],
+      Some(OTHER)
+    ))
+  );
+  sim.http.respond(
+    OTHER,
+    FakeResponse::ok(page(
+      &["three"],
+      None
+    ))
+  );
+
+  sim.tick().await.unwrap();
+
+  let sent = |url: &str| {
+    sim
+      .http
+      .requests()
+      .into_iter()
+      .find(|r| {
+        r.method == "GET"
+          && r.url == url
+      })
+      .unwrap()
+      .headers
+  };
+
+  assert_eq!(
+    sent(URL)["cookie"],
+    "sid=secret"
+  );
+  for url in [PLAIN, OTHER] {
+    assert!(
+      !sent(url).contains_key("cookie"),
+      "{url}"
+    );
+  }
+}
+
+#[tokio::test(start_paused = true)]
+async fn stops_at_max_pages() {
+  let mut sim = paged(2).await;
+
+  sim.tick().await.unwrap();
+
+  assert_eq!(
+    guids(&sim),
+    ["one", "two", "three"].map(guid)
+  );
+  assert_eq!(
+    sim.http.count("GET", PAGE_3),
+    0
+  );
+}
+
+#[tokio::test(start_paused = true)]
+async fn stops_once_a_page_is_already_stored()
+ {
+  let mut sim = paged(5).await;
+
+  sim
+    .run_for(Duration::from_secs(3600))
+    .await
+    .unwrap();
+
+  assert!(
+    sim.http.count("GET", URL) > 1
+  );
+
+  // Later polls find every item on
+  // the first page stored and go no
+  // further.
+  for url in [PAGE_2, PAGE_3] {
+    assert_eq!(
+      sim.http.count("GET", url),
+      1,
+      "{url}"
+    );
+  }
+}
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use pulsewire_core::app::webhook::{
+  Attempt,
+  Delivery,
+  deliver
+};
+use pulsewire_core::domain::model::ErrorKind;
+use pulsewire_core::domain::webhook::{
+  DELIVERY_HEADER,
+  DeliveryStatus,
+  EVENT_HEADER,
+  NEW_ENTRIES_EVENT,
+  RetryPolicy,
+  SIGNATURE_HEADER,
+  TIMESTAMP_HEADER,
+  sign,
+  verify
+};
+use pulsewire_core::testing::{
+  FakeHttp,
+  FakeResponse,
+  VirtualClock
+};
+
+const HOOK: &str =
+  "http://127.0.0.1:9999/hook";
+
+const BODY: &[u8] =
+  br#"{"event":"entries.new"}"#;
+
+fn delivery() -> Delivery<'static> {
+  Delivery {
+    id:     7,
+    url:    HOOK,
+    secret: "shh",
+    event:  NEW_ENTRIES_EVENT,
+    body:   BODY
+  }
+}
+
+#[test]
+fn signatures_cover_timestamp_and_body()
+{
+  let signature =
+    sign("shh", 1_700_000_000, BODY);
+
+  assert_eq!(
+    signature,
+    "sha256=4a61cb4df3314269c5d98bc0eb2\
+     ea434b97ecba0800ff7eb1321d9fcf9fd9c67"
+  );
+  assert!(verify(
+    "shh",
+    1_700_000_000,
+    BODY,
+    &signature
+  ));
+
+  assert!(!verify(
+    "shh",
+    1_700_000_001,
+    BODY,
+    &signature
+  ));
+  assert!(!verify(
+    "other",
+    1_700_000_000,
+    BODY,
+    &signature
+  ));
+  assert!(!verify(
+    "shh",
+    1_700_000_000,
+    b"{}",
+    &signature
+  ));
+  assert!(!verify(
+    "shh",
+    1_700_000_000,
+    BODY,
+    "sha1=00"
+  ));
+}
+
+#[test]
+fn retries_back_off_until_attempts_run_out()
+ {
+  let policy = RetryPolicy {
+    max_attempts: 5,
+    backoff:      Duration::from_secs(
+      30
+    ),
+    max_backoff:  Duration::from_secs(
+      100
+    )
+  };
+
+  let delays: Vec<_> = (1..=5)
+    .map(|n| policy.next_delay(n))
+    .collect();
+
+  assert_eq!(delays, vec![
+    Some(Duration::from_secs(30)),
+    Some(Duration::from_secs(60)),
+    Some(Duration::from_secs(100)),
+    Some(Duration::from_secs(100)),
+    None
+  ]);
+
+  assert_eq!(
+    DeliveryStatus::parse("dead"),
+    Ok(DeliveryStatus::Dead)
+  );
+  assert!(
+    DeliveryStatus::parse("gone")
+      .is_err()
+  );
+}
+
+#[tokio::test]
+async fn deliveries_post_signed_json() {
+  let http = FakeHttp::new(Arc::new(
+    VirtualClock::new(0)
+  ));
+
+  http.respond(
+    HOOK,
+    FakeResponse::status(204)
+  );
+
+  let attempt = deliver(
+    &http,
+    &delivery(),
+    1_700_000_000
+  )
+  .await;
+
+  assert!(attempt.delivered());
+  assert_eq!(attempt.status, Some(204));
+
+  let requests = http.requests();
+  assert_eq!(requests.len(), 1);
+
+  let sent = &requests[0];
+  assert_eq!(sent.method, "POST");
+  assert_eq!(sent.domain, "127.0.0.1");
+  assert_eq!(sent.body, BODY);
+
+  let header = |name: &str| {
+    sent.headers[name].as_str()
+  };
+
+  assert_eq!(
+    header("Content-Type"),
+    "application/json"
+  );
+  assert_eq!(
+    header(EVENT_HEADER),
+    "entries.new"
+  );
+  assert_eq!(
+    header(DELIVERY_HEADER),
+    "7"
+  );
+  assert!(verify(
+    "shh",
+    header(TIMESTAMP_HEADER)
+      .parse()
+      .unwrap(),
+    &sent.body,
+    header(SIGNATURE_HEADER)
+  ));
+}
+
+#[tokio::test]
+async fn failed_attempts_say_why() {
+  let http = FakeHttp::new(Arc::new(
+    VirtualClock::new(0)
+  ));
+
+  http.respond(HOOK, FakeResponse {
+    body: b"  try later  ".to_vec(),
+    ..FakeResponse::status(503)
+  });
+
+  // The receiver's body is not kept.
+  assert_eq!(
+    deliver(&http, &delivery(), 1)
+      .await,
+    Attempt {
+      status: Some(503),
+      error:  Some("status 503".into())
+    }
+  );
+
+  http.respond(
+    HOOK,
+    FakeResponse::failure(
+      ErrorKind::Timeout
+    )
+  );
+
+  let attempt =
+    deliver(&http, &delivery(), 2)
+      .await;
+
+  assert!(!attempt.delivered());
+  assert_eq!(
+    attempt.error.as_deref(),
+    Some("no response: Timeout")
+  );
+
+  let bad = Delivery {
+    url: "ftp://example.org/hook",
+    ..delivery()
+  };
+
+  assert!(
+    !deliver(&http, &bad, 3)
+      .await
+      .delivered()
+  );
+  assert_eq!(http.requests().len(), 2);
+}
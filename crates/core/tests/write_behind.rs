@@ -0,0 +1,339 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use pulsewire_core::domain::link_state::LinkState;
+use pulsewire_core::domain::write_behind::WriteBehindConfig;
+use pulsewire_core::feed::parser::{
+  FeedItem,
+  FeedMetadata,
+  ParsedFeed
+};
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::infra::system_clock::SystemClock;
+use pulsewire_core::infra::write_behind::WriteBehindRepo;
+use pulsewire_core::ports::repo::{
+  NewState,
+  Repo
+};
+use pulsewire_core::testing::feed;
+
+const UTC: chrono_tz::Tz =
+  chrono_tz::UTC;
+
+fn scratch(name: &str) -> PathBuf {
+  let dir =
+    std::env::temp_dir().join(format!(
+    "pulsewire-write-behind-{name}-{}",
+    std::process::id()
+  ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  dir
+}
+
+/// A migrated database with feeds
+/// `f0` .. `f{count - 1}`.
+async fn seeded(
+  dir: &std::path::Path,
+  count: usize
+) -> Arc<SqliteRepo> {
+  let repo = SqliteRepo::new(
+    &dir.join("fetcher.db")
+  )
+  .await
+  .unwrap();
+
+  repo.migrate(&UTC, 60).await.unwrap();
+
+  repo
+    .upsert_categories(
+      vec!["news".to_string()],
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  repo
+    .upsert_feeds_bulk(
+      (0..count)
+        .map(|i| {
+          feed(
+            &format!("f{i}"),
+            &format!(
+              "https://{i}.example/rss"
+            ),
+            "example",
+            "news",
+            300
+          )
+        })
+        .collect(),
+      100,
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  Arc::new(repo)
+}
+
+fn state(
+  feed_id: &str,
+  next_action_at_ms: i64
+) -> LinkState {
+  let mut state = LinkState::initial(
+    feed_id.to_string(),
+    300,
+    3_600,
+    0.0,
+    0
+  );
+
+  state.next_action_at_ms =
+    next_action_at_ms;
+
+  state
+}
+
+#[tokio::test]
+
+async fn batched_writes_are_visible_once_acknowledged()
+ {
+  let dir = scratch("visible");
+
+  let inner = seeded(&dir, 40).await;
+
+  let batched =
+    Arc::new(WriteBehindRepo::new(
+      inner.clone(),
+      &WriteBehindConfig {
+        enabled:   true,
+        max_batch: 16,
+        flush_ms:  5
+      },
+      Arc::new(SystemClock)
+    ));
+  let repo: Arc<dyn Repo> =
+    batched.clone();
+
+  let tasks = (0..40)
+    .map(|i| {
+      let repo = repo.clone();
+      let inner = inner.clone();
+
+      tokio::spawn(async move {
+        let id = format!("f{i}");
+
+        repo
+          .insert_event(
+            &id,
+            "GET",
+            Some(200),
+            None,
+            Some(10),
+            0,
+            i,
+            None,
+            &UTC
+          )
+          .await
+          .unwrap();
+
+        repo
+          .insert_state(
+            &state(&id, i),
+            1_000,
+            &UTC,
+            true
+          )
+          .await
+          .unwrap();
+
+        // Committed before the ack.
+        inner
+          .latest_state(&id)
+          .await
+          .unwrap()
+          .unwrap()
+          .next_action_at_ms
+      })
+    })
+    .collect::<Vec<_>>();
+
+  for (i, task) in
+    tasks.into_iter().enumerate()
+  {
+    assert_eq!(
+      task.await.unwrap(),
+      i as i64
+    );
+  }
+
+  for i in 0..40 {
+    let id = format!("f{i}");
+
+    let events = repo
+      .recent_events(&id, 10)
+      .await
+      .unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+      events[0]
+        .scheduled_next_action_at_ms,
+      i
+    );
+    assert_eq!(
+      repo
+        .state_history(&id, 10)
+        .await
+        .unwrap()
+        .len(),
+      1
+    );
+  }
+
+  // Concurrent writes share batches
+  // of at most `max_batch`.
+  let stats = batched.stats();
+
+  assert_eq!(stats.writes, 80);
+  assert!(
+    (2..=16).contains(&stats.largest),
+    "{stats:?}"
+  );
+  assert!(
+    stats.batches < 80,
+    "{stats:?}"
+  );
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+
+async fn insert_states_keeps_last_snapshot_per_feed()
+ {
+  let dir = scratch("states");
+
+  let repo = seeded(&dir, 2).await;
+
+  let snapshot =
+    |id: &str, at: i64, history| {
+      NewState {
+        state:          state(id, at),
+        recorded_at_ms: at,
+        record_history: history
+      }
+    };
+
+  repo
+    .insert_states(
+      &[
+        snapshot("f0", 1, true),
+        snapshot("f1", 2, false),
+        snapshot("f0", 3, true)
+      ],
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  for (id, next, history) in
+    [("f0", 3, 2), ("f1", 2, 0)]
+  {
+    assert_eq!(
+      repo
+        .latest_state(id)
+        .await
+        .unwrap()
+        .unwrap()
+        .next_action_at_ms,
+      next
+    );
+    assert_eq!(
+      repo
+        .state_history(id, 10)
+        .await
+        .unwrap()
+        .len(),
+      history
+    );
+  }
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+
+async fn payload_items_span_several_statements()
+ {
+  let dir = scratch("items");
+
+  let repo = seeded(&dir, 1).await;
+
+  let items: Vec<FeedItem> = (0..1_203)
+    .map(|i| {
+      FeedItem {
+        title:           Some(format!(
+          "item\t{i}\nline"
+        )),
+        link:            None,
+        guid:            Some(format!(
+          "g{i}"
+        )),
+        published_at_ms: Some(i),
+        category:        None,
+        author:          None,
+        description:     None,
+        summary:         None,
+        diff:            None
+      }
+    })
+    .collect();
+
+  repo
+    .insert_payload_with_items(
+      "f0",
+      1_000,
+      None,
+      None,
+      None,
+      &ParsedFeed {
+        metadata: FeedMetadata {
+          title:         None,
+          link:          None,
+          description:   None,
+          language:      None,
+          updated_at_ms: None
+        },
+        items
+      },
+      &UTC
+    )
+    .await
+    .unwrap();
+
+  let payload = &repo
+    .payload_timeline("f0", 1)
+    .await
+    .unwrap()[0];
+
+  assert_eq!(payload.item_count, 1_203);
+
+  let stored = repo
+    .payload_items(payload.payload_id)
+    .await
+    .unwrap();
+
+  assert!(stored.iter().any(|it| {
+    it.guid.as_deref() == Some("g1202")
+      && it.title.as_deref()
+        == Some("item\t1202\nline")
+  }));
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
@@ -3,6 +3,12 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use pulsewire_core::app::context::AppContext;
+use pulsewire_core::app::{
+  backup,
+  ingest_benchmark,
+  registry,
+  retention
+};
 use pulsewire_core::app::scheduler::Scheduler;
 use pulsewire_core::domain::model::{
   AppConfig,
@@ -22,6 +28,7 @@ use pulsewire_core::infra::logging::{
 use pulsewire_core::infra::random::MutexRng;
 use pulsewire_core::infra::reqwest_http::ReqwestHttp;
 use pulsewire_core::infra::system_clock::SystemClock;
+use pulsewire_core::infra::write_behind::WriteBehindRepo;
 use pulsewire_core::infra::{
   database,
   metrics,
@@ -44,9 +51,13 @@ use tracing::{
 ///   opens SQLite + runs migrations
 /// - bulk upserts feeds, then either
 ///   runs the ingest benchmark
-///   (HEAD/GET skipped) or starts the
-///   scheduler loop with
-///   HTTP/clock/rng/repo adapters
+///   (HEAD/GET skipped; also times
+///   item, event and state writes) or
+///   adds approved registry feeds,
+///   wraps the repo for write-behind
+///   batching and starts the scheduler
+///   loop with HTTP/clock/rng/repo
+///   adapters
 /// - exits with `BootError` on fatal
 ///   startup/ingest errors
 #[tokio::main]
@@ -68,7 +79,8 @@ async fn main() -> Result<(), BootError>
     app: app_cfg,
     feeds,
     watches,
-    categories
+    categories,
+    ..
   } = ConfigLoader::load(&cfg_path)
     .await
     .map_err(|e| {
@@ -197,12 +209,21 @@ async fn main() -> Result<(), BootError>
 
   let cfg = Arc::new(app_cfg);
 
-  let category_names: Vec<String> =
+  let mut category_names: Vec<String> =
     categories
       .iter()
       .map(|c| c.name.clone())
       .collect();
 
+  if cfg.registry.enabled
+    && !category_names
+      .contains(&cfg.registry.category)
+  {
+    category_names.push(
+      cfg.registry.category.clone()
+    );
+  }
+
   match args.mode {
     | RunMode::IngestBenchmark {
       feeds_to_insert
@@ -240,6 +261,66 @@ async fn main() -> Result<(), BootError>
       )
       .await?;
 
+      let feed_ids: Vec<String> =
+        benchmark_feed_stream(
+          feeds_to_insert,
+          cfg.default_poll_seconds,
+          "benchmark".to_string()
+        )
+        .map(|f| f.id)
+        .collect();
+
+      let report =
+        ingest_benchmark::run(
+          repo.clone(),
+          Arc::new(SystemClock),
+          &cfg,
+          &feed_ids
+        )
+        .await
+        .map_err(BootError::Fatal)?;
+
+      info!(
+        items = report.items,
+        elapsed_ms = report
+          .items_elapsed
+          .as_millis(),
+        items_per_sec =
+          report.items_per_sec() as u64,
+        "Benchmark payload items \
+         written"
+      );
+
+      info!(
+        writes = report.writes,
+        direct_ms = report
+          .direct_elapsed
+          .as_millis(),
+        direct_per_sec = report
+          .direct_per_sec()
+          as u64,
+        batched_ms = report
+          .batched_elapsed
+          .as_millis(),
+        batched_per_sec = report
+          .batched_per_sec()
+          as u64,
+        speedup = format!(
+          "{:.2}x",
+          report.speedup()
+        ),
+        batches =
+          report.batches.batches,
+        mean_batch = format!(
+          "{:.1}",
+          report.batches.mean()
+        ),
+        largest_batch =
+          report.batches.largest,
+        "Benchmark events and states \
+         written"
+      );
+
       info!(
         feeds = feeds_to_insert,
         "Ingest benchmark finished"
@@ -265,23 +346,111 @@ async fn main() -> Result<(), BootError>
   )
   .await?;
 
+  if cfg.registry.enabled {
+    let count = registry::sync_once(
+      repo.as_ref(),
+      &cfg
+    )
+    .await
+    .map_err(BootError::Fatal)?;
+
+    info!(
+      added = count,
+      category = %cfg.registry.category,
+      interval_seconds =
+        cfg.registry.interval_seconds,
+      "Feed registry enabled"
+    );
+  }
+
   let http = Arc::new(
     ReqwestHttp::new(
+      cfg.user_agent.clone(),
+      &cfg.domains
+    )
+    .map_err(BootError::Fatal)?
+  );
+
+  // Registry feed URLs come from users,
+  // so they must not reach internal
+  // addresses on any hop.
+  let public_http = Arc::new(
+    ReqwestHttp::public_only(
       cfg.user_agent.clone()
     )
-    .map_err(|e| {
-      BootError::Fatal(e.to_string())
-    })?
+    .map_err(BootError::Fatal)?
   );
 
   let clock = Arc::new(SystemClock);
 
+  let repo: Arc<dyn Repo> =
+    if cfg.write_behind.enabled {
+      info!(
+        max_batch =
+          cfg.write_behind.max_batch,
+        flush_ms =
+          cfg.write_behind.flush_ms,
+        "Write-behind batching enabled"
+      );
+
+      Arc::new(WriteBehindRepo::new(
+        repo,
+        &cfg.write_behind,
+        clock.clone()
+      ))
+    } else {
+      repo
+    };
+
   let rng = Arc::new(MutexRng::new());
 
+  if cfg.retention.enabled {
+    info!(
+      interval_seconds =
+        cfg.retention.interval_seconds,
+      "Retention job enabled"
+    );
+
+    tokio::spawn(
+      retention::run_forever(
+        repo.clone(),
+        clock.clone(),
+        cfg.clone()
+      )
+    );
+  }
+
+  if cfg.registry.enabled {
+    tokio::spawn(
+      registry::run_forever(
+        repo.clone(),
+        cfg.clone()
+      )
+    );
+  }
+
+  if cfg.backup.enabled {
+    info!(
+      interval_seconds =
+        cfg.backup.interval_seconds,
+      directory = %cfg
+        .backup
+        .directory
+        .display(),
+      "Backup job enabled"
+    );
+
+    tokio::spawn(backup::run_forever(
+      clock.clone(),
+      cfg.clone()
+    ));
+  }
+
   let ctx = AppContext {
     cfg: cfg.clone(),
     repo: repo.clone(),
     http: http.clone(),
+    public_http,
     clock: clock.clone(),
     rng: rng.clone(),
     watches_by_id,
@@ -407,8 +576,9 @@ fn print_help_and_exit() -> ! {
   println!("Options:");
   println!(
     "  --ingest-benchmark <count>  \
-     ingest that many feeds for \
-     benchmarking"
+     ingest that many feeds, then \
+     time item, event and state \
+     writes with and without batching"
   );
   println!(
     "  config_path                 \
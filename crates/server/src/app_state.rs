@@ -1,14 +1,36 @@
+use std::sync::Arc;
+
+use pulsewire_core::ports::http::Http;
+use pulsewire_core::ports::repo::Repo;
 use sqlx::{
   Pool,
   Postgres,
   Sqlite
 };
 
+use crate::config::{
+  PublishedConfig,
+  RegistryConfig
+};
+use crate::realtime::Realtime;
+
 #[derive(Clone)]
 
 pub struct AppState {
   pub sqlite: Option<Pool<Sqlite>>,
   pub postgres: Option<Pool<Postgres>>,
   pub fetcher_schema:    Option<String>,
-  pub token_ttl_seconds: u64
+  pub token_ttl_seconds: u64,
+  /// Read access to the fetcher's
+  /// tables for admin reports.
+  pub fetcher:           Arc<dyn Repo>,
+  pub admin_usernames:   Vec<String>,
+  /// Key signing list cursors.
+  pub cursor_secret:     Arc<[u8]>,
+  /// Fetches feeds users add by URL.
+  pub http:              Arc<dyn Http>,
+  pub registry:          RegistryConfig,
+  /// Signals for realtime streams.
+  pub realtime:          Arc<Realtime>,
+  pub published: PublishedConfig
 }
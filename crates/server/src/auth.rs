@@ -18,7 +18,16 @@ pub async fn auth_user_id(
 ) -> Result<i64, ServerError> {
   let token = bearer_token(headers)?;
 
-  let token_hash = hash_token(&token);
+  token_user_id(state, &token).await
+}
+
+/// The user an unexpired token was
+/// issued to.
+pub async fn token_user_id(
+  state: &AppState,
+  token: &str
+) -> Result<i64, ServerError> {
+  let token_hash = hash_token(token);
 
   if let Some(pool) = &state.postgres {
     let id = sqlx::query_scalar::<_, i64>(
@@ -50,6 +59,167 @@ pub async fn auth_user_id(
   Ok(id)
 }
 
+/// The id of the user `username` if
+/// `password` is theirs.
+pub async fn check_password(
+  state: &AppState,
+  username: &str,
+  password: &str
+) -> Result<i64, ServerError> {
+  let (user_id, password_hash) =
+    if let Some(pool) = &state.postgres
+    {
+      sqlx::query_as::<_, (i64, String)>(
+            "SELECT id, password_hash FROM users WHERE username = $1",
+        )
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ServerError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| ServerError::new(axum::http::StatusCode::UNAUTHORIZED, "invalid credentials"))?
+    } else {
+      let pool = state
+            .sqlite
+            .as_ref()
+            .ok_or_else(|| ServerError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+      sqlx::query_as::<_, (i64, String)>(
+            "SELECT id, password_hash FROM users WHERE username = ?1",
+        )
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| ServerError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or_else(|| ServerError::new(axum::http::StatusCode::UNAUTHORIZED, "invalid credentials"))?
+    };
+
+  verify_password(
+    &password_hash,
+    password
+  )
+  .map_err(|_| {
+    ServerError::new(
+      axum::http::StatusCode::UNAUTHORIZED,
+      "invalid credentials"
+    )
+  })?;
+
+  Ok(user_id)
+}
+
+/// Issues a new token to the user,
+/// valid for `auth.token_ttl_seconds`.
+pub async fn issue_token(
+  state: &AppState,
+  user_id: i64
+) -> Result<String, ServerError> {
+  let token = generate_token();
+
+  let token_hash = hash_token(&token);
+
+  let ttl =
+    state.token_ttl_seconds as i64;
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query(
+            "INSERT INTO user_tokens (user_id, token_hash, expires_at, created_at) VALUES ($1, $2, NOW() + ($3 || ' seconds')::interval, NOW())",
+        )
+        .bind(user_id)
+        .bind(&token_hash)
+        .bind(ttl)
+        .execute(pool)
+        .await
+        .map_err(|e| ServerError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+  } else {
+    let pool = state
+            .sqlite
+            .as_ref()
+            .ok_or_else(|| ServerError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+            "INSERT INTO user_tokens (user_id, token_hash, expires_at, created_at) VALUES (?1, ?2, datetime('now', '+' || ?3 || ' seconds'), datetime('now'))",
+        )
+        .bind(user_id)
+        .bind(&token_hash)
+        .bind(ttl)
+        .execute(pool)
+        .await
+        .map_err(|e| ServerError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+  }
+
+  Ok(token)
+}
+
+/// Resolves the bearer token and
+/// requires its user to be listed in
+/// `auth.admin_usernames`.
+pub async fn auth_admin_id(
+  state: &AppState,
+  headers: &HeaderMap
+) -> Result<i64, ServerError> {
+  let user_id =
+    auth_user_id(state, headers)
+      .await?;
+
+  if !is_admin(state, user_id).await? {
+    return Err(ServerError::new(
+      axum::http::StatusCode::FORBIDDEN,
+      "admin access required"
+    ));
+  }
+
+  Ok(user_id)
+}
+
+/// Whether the user is listed in
+/// `auth.admin_usernames`.
+pub async fn is_admin(
+  state: &AppState,
+  user_id: i64
+) -> Result<bool, ServerError> {
+  let username = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, String>(
+      "SELECT username FROM users \
+       WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, String>(
+      "SELECT username FROM users \
+       WHERE id = ?1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(|e| ServerError::new(axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+  Ok(username.is_some_and(|name| {
+    state
+      .admin_usernames
+      .contains(&name)
+  }))
+}
+
+/// Tells a request's session apart
+/// from the user's others: the hash
+/// of its bearer token.
+pub fn session_id(
+  headers: &HeaderMap
+) -> Result<String, ServerError> {
+  bearer_token(headers)
+    .map(|token| hash_token(&token))
+}
+
 pub fn bearer_token(
   headers: &HeaderMap
 ) -> Result<String, ServerError> {
@@ -0,0 +1,983 @@
+//! Storage behind the Google Reader
+//! and Fever APIs. Both read the same
+//! subscriptions, plain folders, entry
+//! states and stars as `/v1`; the wire
+//! formats live in core's
+//! `domain::compat`.
+
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+use pulsewire_core::domain::compat::{
+  CompatEntry,
+  CompatFeed,
+  CompatFolder,
+  form_pairs,
+  site_url
+};
+use pulsewire_core::domain::registry::RegistryStatus;
+use sqlx::{
+  Database,
+  Encode,
+  Postgres,
+  QueryBuilder,
+  Sqlite,
+  Type
+};
+
+use crate::app_state::AppState;
+use crate::auth::hash_token;
+use crate::entry_stream::{
+  EntryStream,
+  feeds_table,
+  items_table
+};
+use crate::errors::ServerError;
+use crate::pagination::{
+  Keyset,
+  POSTGRES_ITEMS,
+  Page,
+  Paged,
+  SQLITE_ITEMS,
+  item_cut
+};
+
+/// How a list of entries is cut.
+enum EntryOrder<'p> {
+  /// One keyset page by publish time.
+  Page(&'p Page),
+  /// By id, up to `limit` items.
+  Id {
+    newest_first: bool,
+    limit:        i64
+  }
+}
+
+#[derive(sqlx::FromRow)]
+struct EntryRow {
+  id:              i64,
+  feed_id:         String,
+  title:           Option<String>,
+  link:            Option<String>,
+  author:          Option<String>,
+  content:         Option<String>,
+  published_at_ms: Option<i64>,
+  is_read:         bool,
+  is_starred:      bool
+}
+
+impl From<EntryRow> for CompatEntry {
+  fn from(row: EntryRow) -> Self {
+    Self {
+      id:           row.id,
+      feed_id:      row.feed_id,
+      title:        row.title,
+      link:         row.link,
+      author:       row.author,
+      content:      row.content,
+      published_ms: row.published_at_ms,
+      read:         row.is_read,
+      starred:      row.is_starred
+    }
+  }
+}
+
+/// The user's subscriptions by feed
+/// id, with their plain folders and
+/// the registry title when known.
+pub async fn subscribed_feeds(
+  state: &AppState,
+  user_id: i64
+) -> Result<Vec<CompatFeed>, ServerError>
+{
+  let (feeds_sql, folders_sql) =
+    if state.postgres.is_some() {
+      (
+        format!(
+          "SELECT s.feed_id, f.url \
+           FROM subscriptions s JOIN \
+           {} f ON f.id = s.feed_id \
+           WHERE s.user_id = $1 ORDER \
+           BY s.feed_id",
+          feeds_table(state)
+        ),
+        "SELECT ff.feed_id, f.id, \
+         f.name FROM folder_feeds ff \
+         JOIN folders f ON f.id = \
+         ff.folder_id WHERE f.user_id \
+         = $1 AND f.filter IS NULL \
+         ORDER BY f.name"
+      )
+    } else {
+      (
+        "SELECT s.feed_id, f.url FROM \
+         subscriptions s JOIN feeds f \
+         ON f.id = s.feed_id WHERE \
+         s.user_id = ?1 ORDER BY \
+         s.feed_id"
+          .to_string(),
+        "SELECT ff.feed_id, f.id, \
+         f.name FROM folder_feeds ff \
+         JOIN folders f ON f.id = \
+         ff.folder_id WHERE f.user_id \
+         = ?1 AND f.filter IS NULL \
+         ORDER BY f.name"
+      )
+    };
+
+  let (subs, folder_rows) =
+    if let Some(pool) = &state.postgres
+    {
+      (
+        sqlx::query_as::<
+          _,
+          (String, String)
+        >(&feeds_sql)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(query_error)?,
+        sqlx::query_as::<
+          _,
+          (String, i64, String)
+        >(folders_sql)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(query_error)?
+      )
+    } else {
+      let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+      (
+        sqlx::query_as::<
+          _,
+          (String, String)
+        >(&feeds_sql)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(query_error)?,
+        sqlx::query_as::<
+          _,
+          (String, i64, String)
+        >(folders_sql)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(query_error)?
+      )
+    };
+
+  let mut feed_folders: HashMap<
+    String,
+    Vec<CompatFolder>
+  > = HashMap::new();
+
+  for (feed_id, id, name) in folder_rows
+  {
+    feed_folders
+      .entry(feed_id)
+      .or_default()
+      .push(CompatFolder {
+        id,
+        name
+      });
+  }
+
+  // Feeds have no title of their own;
+  // the registry keeps the one seen
+  // when a user added it.
+  let titles: HashMap<String, String> =
+    state
+      .fetcher
+      .registry_feeds(Some(
+        RegistryStatus::Approved
+      ))
+      .await
+      .map_err(internal)?
+      .into_iter()
+      .filter_map(|f| {
+        f.title.map(|t| (f.url, t))
+      })
+      .collect();
+
+  Ok(
+    subs
+      .into_iter()
+      .map(|(id, url)| {
+        CompatFeed {
+          title: titles
+            .get(&url)
+            .cloned()
+            .unwrap_or_else(|| {
+              id.clone()
+            }),
+          site_url: site_url(&url),
+          folders: feed_folders
+            .remove(&id)
+            .unwrap_or_default(),
+          id,
+          url
+        }
+      })
+      .collect()
+  )
+}
+
+/// The user's name.
+pub async fn username(
+  state: &AppState,
+  user_id: i64
+) -> Result<String, ServerError> {
+  if let Some(pool) = &state.postgres {
+    sqlx::query_scalar::<_, String>(
+      "SELECT username FROM users \
+       WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, String>(
+      "SELECT username FROM users \
+       WHERE id = ?1"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+  }
+  .map_err(query_error)
+}
+
+/// Every folder of the user, smart
+/// folders included, by name.
+pub async fn folders(
+  state: &AppState,
+  user_id: i64
+) -> Result<
+  Vec<CompatFolder>,
+  ServerError
+> {
+  if let Some(pool) = &state.postgres {
+    sqlx::query_as::<_, (i64, String)>(
+      "SELECT id, name FROM folders \
+       WHERE user_id = $1 ORDER BY name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, (i64, String)>(
+      "SELECT id, name FROM folders \
+       WHERE user_id = ?1 ORDER BY name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map(|rows| {
+    rows
+      .into_iter()
+      .map(|(id, name)| {
+        CompatFolder {
+          id,
+          name
+        }
+      })
+      .collect()
+  })
+  .map_err(query_error)
+}
+
+/// The user's folder called `name`.
+pub async fn folder_by_name(
+  state: &AppState,
+  user_id: i64,
+  name: &str
+) -> Result<i64, ServerError> {
+  if let Some(pool) = &state.postgres {
+    sqlx::query_scalar::<_, i64>(
+      "SELECT id FROM folders WHERE \
+       user_id = $1 AND name = $2"
+    )
+    .bind(user_id)
+    .bind(name)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, i64>(
+      "SELECT id FROM folders WHERE \
+       user_id = ?1 AND name = ?2"
+    )
+    .bind(user_id)
+    .bind(name)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)?
+  .ok_or_else(|| {
+    ServerError::new(
+      StatusCode::NOT_FOUND,
+      format!("folder '{name}' not found")
+    )
+  })
+}
+
+/// One page of a stream's entries by
+/// publish time.
+pub async fn entry_page(
+  state: &AppState,
+  stream: &EntryStream,
+  page: &Page
+) -> Result<
+  Paged<CompatEntry>,
+  ServerError
+> {
+  let rows = fetch_entries(
+    state,
+    stream,
+    &EntryOrder::Page(page)
+  )
+  .await?;
+
+  let paged =
+    page.finish(rows, |row| {
+      item_cut(
+        row.published_at_ms,
+        row.id
+      )
+    });
+
+  Ok(Paged {
+    items:       paged
+      .items
+      .into_iter()
+      .map(CompatEntry::from)
+      .collect(),
+    next_cursor: paged.next_cursor,
+    prev_cursor: paged.prev_cursor
+  })
+}
+
+/// A stream's entries by id.
+pub async fn entries_by_id(
+  state: &AppState,
+  stream: &EntryStream,
+  newest_first: bool,
+  limit: i64
+) -> Result<Vec<CompatEntry>, ServerError>
+{
+  Ok(
+    fetch_entries(
+      state,
+      stream,
+      &EntryOrder::Id {
+        newest_first,
+        limit
+      }
+    )
+    .await?
+    .into_iter()
+    .map(CompatEntry::from)
+    .collect()
+  )
+}
+
+async fn fetch_entries(
+  state: &AppState,
+  stream: &EntryStream,
+  order: &EntryOrder<'_>
+) -> Result<Vec<EntryRow>, ServerError>
+{
+  let items = items_table(state);
+
+  if let Some(pool) = &state.postgres {
+    let mut builder = QueryBuilder::<
+      Postgres
+    >::new(
+      "SELECT fi.id, fi.feed_id, \
+       fi.title, fi.link, fi.author, \
+       COALESCE(fi.description, \
+       fi.summary) AS content, \
+       CAST(EXTRACT(EPOCH FROM \
+       fi.published_at) * 1000 AS \
+       BIGINT) AS published_at_ms, \
+       (es.read_at IS NOT NULL) AS \
+       is_read"
+    );
+
+    stream.push_mark_columns(&mut builder);
+    stream.push_from(&mut builder, &items);
+    push_order(
+      &mut builder,
+      order,
+      &POSTGRES_ITEMS
+    )?;
+
+    builder
+      .build_query_as::<EntryRow>()
+      .fetch_all(pool)
+      .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let mut builder = QueryBuilder::<
+      Sqlite
+    >::new(
+      "SELECT fi.id, fi.feed_id, \
+       fi.title, fi.link, fi.author, \
+       COALESCE(fi.description, \
+       fi.summary) AS content, \
+       fi.published_at_ms, \
+       (es.read_at IS NOT NULL) AS \
+       is_read"
+    );
+
+    stream.push_mark_columns(&mut builder);
+    stream.push_from(&mut builder, &items);
+    push_order(
+      &mut builder,
+      order,
+      &SQLITE_ITEMS
+    )?;
+
+    builder
+      .build_query_as::<EntryRow>()
+      .fetch_all(pool)
+      .await
+  }
+  .map_err(query_error)
+}
+
+/// Pushes the keyset condition, if
+/// any, then ` ORDER BY .. LIMIT ..`.
+fn push_order<'a, DB>(
+  builder: &mut QueryBuilder<'a, DB>,
+  order: &EntryOrder<'_>,
+  keyset: &Keyset
+) -> Result<(), ServerError>
+where
+  DB: Database,
+  i64: Encode<'a, DB> + Type<DB>,
+  f64: Encode<'a, DB> + Type<DB>,
+  String: Encode<'a, DB> + Type<DB>
+{
+  match order {
+    | EntryOrder::Page(page) => {
+      page.push_keyset(
+        builder, keyset, true
+      )?;
+
+      builder.push(format!(
+        " ORDER BY {key} {order}, \
+         fi.id {order} LIMIT ",
+        key = keyset.key,
+        order = page.order()
+      ));
+      builder
+        .push_bind(page.fetch_limit());
+    }
+    | EntryOrder::Id {
+      newest_first,
+      limit
+    } => {
+      builder.push(
+        if *newest_first {
+          " ORDER BY fi.id DESC LIMIT "
+        } else {
+          " ORDER BY fi.id ASC LIMIT "
+        }
+      );
+      builder.push_bind(*limit);
+    }
+  }
+
+  Ok(())
+}
+
+/// Ids of every item in a stream,
+/// newest first.
+pub async fn stream_item_ids(
+  state: &AppState,
+  stream: &EntryStream
+) -> Result<Vec<i64>, ServerError> {
+  let items = items_table(state);
+
+  if let Some(pool) = &state.postgres {
+    let mut builder = QueryBuilder::<
+      Postgres
+    >::new("SELECT fi.id");
+
+    stream.push_from(&mut builder, &items);
+    builder
+      .push(" ORDER BY fi.id DESC");
+
+    builder
+      .build_query_scalar::<i64>()
+      .fetch_all(pool)
+      .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let mut builder = QueryBuilder::<
+      Sqlite
+    >::new("SELECT fi.id");
+
+    stream.push_from(&mut builder, &items);
+    builder
+      .push(" ORDER BY fi.id DESC");
+
+    builder
+      .build_query_scalar::<i64>()
+      .fetch_all(pool)
+      .await
+  }
+  .map_err(query_error)
+}
+
+/// How many items a stream holds.
+pub async fn count_items(
+  state: &AppState,
+  stream: &EntryStream
+) -> Result<i64, ServerError> {
+  let items = items_table(state);
+
+  if let Some(pool) = &state.postgres {
+    let mut builder = QueryBuilder::<
+      Postgres
+    >::new("SELECT COUNT(*)");
+
+    stream.push_from(&mut builder, &items);
+
+    builder
+      .build_query_scalar::<i64>()
+      .fetch_one(pool)
+      .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let mut builder = QueryBuilder::<
+      Sqlite
+    >::new("SELECT COUNT(*)");
+
+    stream.push_from(&mut builder, &items);
+
+    builder
+      .build_query_scalar::<i64>()
+      .fetch_one(pool)
+      .await
+  }
+  .map_err(query_error)
+}
+
+/// Marks items read or unread; items
+/// outside the user's subscriptions
+/// are not marked read.
+pub async fn set_read(
+  state: &AppState,
+  user_id: i64,
+  item_ids: &[i64],
+  read: bool
+) -> Result<(), ServerError> {
+  if read {
+    insert_for_items(
+      state,
+      user_id,
+      item_ids,
+      "entry_states (user_id, \
+       item_id, read_at)",
+      "ON CONFLICT (user_id, item_id) \
+       DO UPDATE SET read_at = \
+       COALESCE(entry_states.read_at, \
+       excluded.read_at)"
+    )
+    .await
+  } else {
+    delete_for_items(
+      state,
+      user_id,
+      item_ids,
+      "entry_states"
+    )
+    .await
+  }
+}
+
+/// Stars or unstars items; items
+/// outside the user's subscriptions
+/// are not starred.
+pub async fn set_starred(
+  state: &AppState,
+  user_id: i64,
+  item_ids: &[i64],
+  starred: bool
+) -> Result<(), ServerError> {
+  if starred {
+    insert_for_items(
+      state,
+      user_id,
+      item_ids,
+      "entry_stars (user_id, item_id, \
+       created_at)",
+      "ON CONFLICT DO NOTHING"
+    )
+    .await
+  } else {
+    delete_for_items(
+      state,
+      user_id,
+      item_ids,
+      "entry_stars"
+    )
+    .await
+  }
+}
+
+/// Inserts `(user, item, now)` into
+/// `target` for each item of the
+/// user's subscriptions.
+/// Ids are bound as an array on
+/// Postgres and JSON text on SQLite.
+async fn insert_for_items(
+  state: &AppState,
+  user_id: i64,
+  item_ids: &[i64],
+  target: &str,
+  conflict: &str
+) -> Result<(), ServerError> {
+  if item_ids.is_empty() {
+    return Ok(());
+  }
+
+  let items = items_table(state);
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query(&format!(
+      "INSERT INTO {target} SELECT $1, \
+       fi.id, NOW() FROM {items} fi \
+       WHERE fi.id = ANY($2) AND \
+       fi.feed_id IN (SELECT feed_id \
+       FROM subscriptions WHERE \
+       user_id = $1) {conflict}"
+    ))
+    .bind(user_id)
+    .bind(item_ids)
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(&format!(
+      "INSERT INTO {target} SELECT ?1, \
+       fi.id, datetime('now') FROM \
+       {items} fi WHERE fi.id IN \
+       (SELECT value FROM \
+       json_each(?2)) AND fi.feed_id \
+       IN (SELECT feed_id FROM \
+       subscriptions WHERE user_id = \
+       ?1) {conflict}"
+    ))
+    .bind(user_id)
+    .bind(id_json(item_ids))
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  }
+  .map_err(query_error)?;
+
+  Ok(())
+}
+
+async fn delete_for_items(
+  state: &AppState,
+  user_id: i64,
+  item_ids: &[i64],
+  table: &str
+) -> Result<(), ServerError> {
+  if item_ids.is_empty() {
+    return Ok(());
+  }
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query(&format!(
+      "DELETE FROM {table} WHERE \
+       user_id = $1 AND item_id = \
+       ANY($2)"
+    ))
+    .bind(user_id)
+    .bind(item_ids)
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(&format!(
+      "DELETE FROM {table} WHERE \
+       user_id = ?1 AND item_id IN \
+       (SELECT value FROM \
+       json_each(?2))"
+    ))
+    .bind(user_id)
+    .bind(id_json(item_ids))
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  }
+  .map_err(query_error)?;
+
+  Ok(())
+}
+
+/// Marks every unread item of a stream
+/// read, returning their ids.
+pub async fn mark_stream_read(
+  state: &AppState,
+  stream: &EntryStream
+) -> Result<Vec<i64>, ServerError> {
+  let items = items_table(state);
+
+  if let Some(pool) =
+    &state.postgres
+  {
+    let mut builder = QueryBuilder::<
+      Postgres
+    >::new(
+      "INSERT INTO entry_states \
+       (user_id, item_id, read_at) \
+       SELECT "
+    );
+
+    builder.push_bind(stream.user_id);
+    builder.push(", fi.id, NOW()");
+    stream.push_from(&mut builder, &items);
+    builder.push(
+      " AND es.read_at IS NULL ON \
+       CONFLICT (user_id, item_id) DO \
+       UPDATE SET read_at = \
+       EXCLUDED.read_at RETURNING \
+       item_id"
+    );
+
+    builder
+      .build_query_scalar::<i64>()
+      .fetch_all(pool)
+      .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let mut builder = QueryBuilder::<
+      Sqlite
+    >::new(
+      "INSERT INTO entry_states \
+       (user_id, item_id, read_at) \
+       SELECT "
+    );
+
+    builder.push_bind(stream.user_id);
+    builder
+      .push(", fi.id, datetime('now')");
+    stream.push_from(&mut builder, &items);
+    builder.push(
+      " AND es.read_at IS NULL ON \
+       CONFLICT(user_id, item_id) DO \
+       UPDATE SET read_at = \
+       excluded.read_at RETURNING \
+       item_id"
+    );
+
+    builder
+      .build_query_scalar::<i64>()
+      .fetch_all(pool)
+      .await
+  }
+  .map_err(query_error)
+}
+
+/// The user a Fever API key belongs
+/// to, if any.
+pub async fn fever_user_id(
+  state: &AppState,
+  api_key: &str
+) -> Result<Option<i64>, ServerError> {
+  let key_hash = hash_token(api_key);
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query_scalar::<_, i64>(
+      "SELECT user_id FROM fever_keys \
+       WHERE key_hash = $1"
+    )
+    .bind(&key_hash)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, i64>(
+      "SELECT user_id FROM fever_keys \
+       WHERE key_hash = ?1"
+    )
+    .bind(&key_hash)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)
+}
+
+/// Stores the user's Fever API key,
+/// replacing any earlier one.
+pub async fn set_fever_key(
+  state: &AppState,
+  user_id: i64,
+  api_key: &str
+) -> Result<(), ServerError> {
+  let key_hash = hash_token(api_key);
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query(
+      "INSERT INTO fever_keys (user_id, \
+       key_hash, created_at) VALUES \
+       ($1, $2, NOW()) ON CONFLICT \
+       (user_id) DO UPDATE SET \
+       key_hash = EXCLUDED.key_hash, \
+       created_at = EXCLUDED.created_at"
+    )
+    .bind(user_id)
+    .bind(&key_hash)
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "INSERT INTO fever_keys (user_id, \
+       key_hash, created_at) VALUES \
+       (?1, ?2, datetime('now')) ON \
+       CONFLICT(user_id) DO UPDATE SET \
+       key_hash = excluded.key_hash, \
+       created_at = excluded.created_at"
+    )
+    .bind(user_id)
+    .bind(&key_hash)
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  }
+  .map_err(query_error)?;
+
+  Ok(())
+}
+
+/// Removes the user's Fever API key;
+/// whether there was one.
+pub async fn delete_fever_key(
+  state: &AppState,
+  user_id: i64
+) -> Result<bool, ServerError> {
+  if let Some(pool) = &state.postgres {
+    sqlx::query(
+      "DELETE FROM fever_keys WHERE \
+       user_id = $1"
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "DELETE FROM fever_keys WHERE \
+       user_id = ?1"
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  }
+  .map(|affected| affected > 0)
+  .map_err(query_error)
+}
+
+/// Query and form body pairs of one
+/// request; both APIs take either.
+pub fn params(
+  query: Option<String>,
+  body: &str
+) -> Vec<(String, String)> {
+  let mut pairs = form_pairs(
+    query
+      .as_deref()
+      .unwrap_or_default()
+  );
+
+  pairs.extend(form_pairs(body));
+
+  pairs
+}
+
+fn id_json(ids: &[i64]) -> String {
+  serde_json::to_string(ids)
+    .unwrap_or_else(|_| "[]".into())
+}
+
+fn internal(e: String) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e
+  )
+}
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  internal(e.to_string())
+}
@@ -4,6 +4,7 @@ use std::path::{
   PathBuf
 };
 
+use chrono_tz::Tz;
 use serde::Deserialize;
 use thiserror::Error;
 
@@ -41,15 +42,23 @@ pub enum SqlDialect {
 
 #[derive(Debug, Deserialize)]
 pub struct ServerConfig {
-  pub app:      AppConfig,
-  pub http:     HttpConfig,
-  pub database: DatabaseConfig,
-  pub sqlite:   SqliteConfig,
-  pub postgres: Option<PostgresConfig>,
-  pub logging:  LoggingConfig,
-  pub auth:     AuthConfig,
-  pub dev:      DevConfig,
-  pub seed:     SeedConfig
+  pub app:       AppConfig,
+  pub http:      HttpConfig,
+  pub database:  DatabaseConfig,
+  pub sqlite:    SqliteConfig,
+  pub postgres:  Option<PostgresConfig>,
+  pub logging:   LoggingConfig,
+  pub auth:      AuthConfig,
+  pub dev:       DevConfig,
+  pub seed:      SeedConfig,
+  #[serde(default)]
+  pub registry:  RegistryConfig,
+  #[serde(default)]
+  pub realtime:  RealtimeConfig,
+  #[serde(default)]
+  pub webhooks:  WebhooksConfig,
+  #[serde(default)]
+  pub published: PublishedConfig
 }
 
 #[derive(Debug, Deserialize)]
@@ -93,7 +102,228 @@ pub struct LoggingConfig {
 
 #[derive(Debug, Deserialize)]
 pub struct AuthConfig {
-  pub token_ttl_seconds: u64
+  pub token_ttl_seconds: u64,
+  /// Users allowed on `/v1/admin`.
+  #[serde(default)]
+  pub admin_usernames:   Vec<String>,
+  /// Signs list cursors; without it a
+  /// random key is used and cursors
+  /// expire on restart.
+  pub cursor_secret:     Option<String>
+}
+
+/// Feeds users add by URL.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistryConfig {
+  /// New feeds wait for an admin
+  /// before the fetcher polls them.
+  #[serde(default)]
+  pub require_approval:   bool,
+  /// Feeds a user may add unless an
+  /// admin sets their own quota.
+  #[serde(
+    default = "default_feed_quota"
+  )]
+  pub default_quota:      i64,
+  /// Fetcher category of added feeds;
+  /// the fetcher's `[registry]` must
+  /// name the same one.
+  #[serde(
+    default = "default_category"
+  )]
+  pub category:           String,
+  #[serde(
+    default = "default_poll_seconds"
+  )]
+  pub poll_seconds:       u64,
+  /// Feed outlines one OPML import
+  /// may hold.
+  #[serde(
+    default = "default_import_limit"
+  )]
+  pub import_limit:       usize,
+  /// Outlines an OPML import looks up
+  /// at once.
+  #[serde(
+    default = "default_import_concurrency"
+  )]
+  pub import_concurrency: usize
+}
+
+impl Default for RegistryConfig {
+  fn default() -> Self {
+    Self {
+      require_approval:   false,
+      default_quota:
+        default_feed_quota(),
+      category:
+        default_category(),
+      poll_seconds:
+        default_poll_seconds(),
+      import_limit:
+        default_import_limit(),
+      import_concurrency:
+        default_import_concurrency()
+    }
+  }
+}
+
+fn default_feed_quota() -> i64 {
+  20
+}
+
+fn default_category() -> String {
+  "user".to_string()
+}
+
+fn default_poll_seconds() -> u64 {
+  900
+}
+
+fn default_import_limit() -> usize {
+  500
+}
+
+fn default_import_concurrency() -> usize
+{
+  4
+}
+
+/// Realtime update streams.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RealtimeConfig {
+  /// How often SQLite is checked for
+  /// new items; Postgres is notified
+  /// by the fetcher instead.
+  #[serde(
+    default = "default_realtime_poll_seconds"
+  )]
+  pub poll_seconds: u64,
+  /// Read-state changes kept for
+  /// clients resuming a stream.
+  #[serde(default = "default_backlog")]
+  pub backlog:      usize
+}
+
+impl Default for RealtimeConfig {
+  fn default() -> Self {
+    Self {
+      poll_seconds:
+        default_realtime_poll_seconds(),
+      backlog:      default_backlog()
+    }
+  }
+}
+
+fn default_realtime_poll_seconds() -> u64
+{
+  5
+}
+
+fn default_backlog() -> usize {
+  1024
+}
+
+/// Outgoing webhook deliveries.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhooksConfig {
+  /// How often new items are matched
+  /// and due deliveries sent.
+  #[serde(
+    default = "default_webhook_poll_seconds"
+  )]
+  pub poll_seconds:        u64,
+  /// Entries sent in one delivery.
+  #[serde(
+    default = "default_batch_size"
+  )]
+  pub batch_size:          u32,
+  /// Attempts before a delivery is
+  /// dead.
+  #[serde(
+    default = "default_max_attempts"
+  )]
+  pub max_attempts:        u32,
+  /// Wait after the first failed
+  /// attempt, doubled after each
+  /// further one up to the maximum.
+  #[serde(
+    default = "default_backoff_seconds"
+  )]
+  pub backoff_seconds:     u64,
+  #[serde(
+    default = "default_max_backoff_seconds"
+  )]
+  pub max_backoff_seconds: u64
+}
+
+impl Default for WebhooksConfig {
+  fn default() -> Self {
+    Self {
+      poll_seconds:
+        default_webhook_poll_seconds(),
+      batch_size:
+        default_batch_size(),
+      max_attempts:
+        default_max_attempts(),
+      backoff_seconds:
+        default_backoff_seconds(),
+      max_backoff_seconds:
+        default_max_backoff_seconds()
+    }
+  }
+}
+
+fn default_webhook_poll_seconds() -> u64
+{
+  10
+}
+
+fn default_batch_size() -> u32 {
+  50
+}
+
+fn default_max_attempts() -> u32 {
+  8
+}
+
+fn default_backoff_seconds() -> u64 {
+  30
+}
+
+fn default_max_backoff_seconds() -> u64
+{
+  3600
+}
+
+/// Entries published as feeds.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublishedConfig {
+  /// The server's public URL, which
+  /// published feed links start with;
+  /// without it they follow the
+  /// request's `Host`.
+  #[serde(default)]
+  pub base_url:    Option<String>,
+  /// Newest entries in a document.
+  #[serde(
+    default = "default_entry_limit"
+  )]
+  pub entry_limit: u32
+}
+
+impl Default for PublishedConfig {
+  fn default() -> Self {
+    Self {
+      base_url:    None,
+      entry_limit: default_entry_limit(
+      )
+    }
+  }
+}
+
+fn default_entry_limit() -> u32 {
+  50
 }
 
 #[derive(Debug, Deserialize)]
@@ -190,6 +420,23 @@ impl ServerConfig {
 
     base_dir.join(raw)
   }
+
+  pub fn timezone(
+    &self
+  ) -> Result<Tz, ConfigError> {
+    let raw = self
+      .app
+      .timezone
+      .as_deref()
+      .filter(|s| !s.trim().is_empty())
+      .unwrap_or("UTC");
+
+    raw.parse().map_err(|_| {
+      ConfigError::Invalid(format!(
+        "invalid timezone '{raw}'"
+      ))
+    })
+  }
 }
 
 async fn load_schema(
@@ -1,10 +1,19 @@
 use std::path::Path;
+use std::sync::Arc;
 
-use sqlx::PgPool;
+use pulsewire_core::domain::model::PostgresConfig as FetcherPostgresConfig;
+use pulsewire_core::infra::postgres_repo::PostgresRepo;
+use pulsewire_core::infra::reqwest_http::ReqwestHttp;
+use pulsewire_core::infra::sqlite_repo::SqliteRepo;
+use pulsewire_core::ports::http::Http;
 use sqlx::postgres::PgPoolOptions;
 
 use crate::app_state::AppState;
-use crate::auth::hash_password;
+use crate::auth::{
+  generate_token,
+  hash_password
+};
+use crate::realtime::Realtime;
 use crate::config::{
   ConfigError,
   ServerConfig,
@@ -47,15 +56,36 @@ pub async fn connect_db(
             )
           })?;
 
-      ensure_fetcher_tags_column_sqlite(&pool).await?;
-
       Ok(AppState {
+        fetcher:           Arc::new(
+          SqliteRepo::from_pool(
+            pool.clone()
+          )
+        ),
         sqlite:            Some(pool),
         postgres:          None,
         fetcher_schema:    None,
         token_ttl_seconds: config
           .auth
-          .token_ttl_seconds
+          .token_ttl_seconds,
+        admin_usernames:   config
+          .auth
+          .admin_usernames
+          .clone(),
+        cursor_secret:
+          cursor_secret(config),
+        http:              feed_http()?,
+        registry:          config
+          .registry
+          .clone(),
+        realtime:          Arc::new(
+          Realtime::new(
+            config.realtime.backlog
+          )
+        ),
+        published:         config
+          .published
+          .clone()
       })
     }
     | SqlDialect::Postgres => {
@@ -104,13 +134,32 @@ pub async fn connect_db(
           ))
         })?;
 
-      ensure_fetcher_tags_column_postgres(
-        &pool,
-        &fetcher_schema
+      // The fetcher repo keeps its own
+      // pool, whose search_path is the
+      // fetcher schema.
+      let fetcher = PostgresRepo::new(
+        &FetcherPostgresConfig {
+          user:     pg.user.clone(),
+          password: pg.password.clone(),
+          host:     pg.host.clone(),
+          port:     pg.port,
+          database: pg.database.clone(),
+          schema:   fetcher_schema
+            .clone()
+        },
+        &config.timezone()?
       )
-      .await?;
+      .await
+      .map_err(|e| {
+        ConfigError::Invalid(format!(
+          "fetcher repo: {e}"
+        ))
+      })?;
 
       Ok(AppState {
+        fetcher:           Arc::new(
+          fetcher
+        ),
         sqlite:            None,
         postgres:          Some(pool),
         fetcher_schema:    Some(
@@ -118,12 +167,75 @@ pub async fn connect_db(
         ),
         token_ttl_seconds: config
           .auth
-          .token_ttl_seconds
+          .token_ttl_seconds,
+        admin_usernames:   config
+          .auth
+          .admin_usernames
+          .clone(),
+        cursor_secret:
+          cursor_secret(config),
+        http:              feed_http()?,
+        registry:          config
+          .registry
+          .clone(),
+        realtime:          Arc::new(
+          Realtime::new(
+            config.realtime.backlog
+          )
+        ),
+        published:         config
+          .published
+          .clone()
       })
     }
   }
 }
 
+/// HTTP client for the URLs users
+/// give the server: feeds they add and
+/// webhook endpoints. It only reaches
+/// public addresses.
+fn feed_http()
+-> Result<Arc<dyn Http>, ConfigError> {
+  let http =
+    ReqwestHttp::public_only(format!(
+      "pulsewire-server/{}",
+      env!("CARGO_PKG_VERSION")
+    ))
+    .map_err(ConfigError::Invalid)?;
+
+  Ok(Arc::new(http))
+}
+
+/// The configured cursor key, or a
+/// random one for this process.
+fn cursor_secret(
+  config: &ServerConfig
+) -> Arc<[u8]> {
+  match config
+    .auth
+    .cursor_secret
+    .as_deref()
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+  {
+    | Some(secret) => {
+      Arc::from(secret.as_bytes())
+    }
+    | None => {
+      tracing::warn!(
+        "auth.cursor_secret not set; \
+         list cursors will not \
+         survive a restart"
+      );
+
+      Arc::from(
+        generate_token().as_bytes()
+      )
+    }
+  }
+}
+
 pub async fn reset_server_data(
   config: &ServerConfig,
   state: &AppState
@@ -136,6 +248,11 @@ pub async fn reset_server_data(
     "folder_feeds",
     "folders",
     "subscriptions",
+    "feed_quotas",
+    "fever_keys",
+    "webhook_deliveries",
+    "webhooks",
+    "published_feeds",
     "users"
   ];
 
@@ -394,97 +511,3 @@ pub async fn ensure_default_user(
 
   Ok(())
 }
-
-async fn ensure_fetcher_tags_column_postgres(
-  pool: &PgPool,
-  schema: &str
-) -> Result<(), ConfigError> {
-  let has_column: Option<i32> =
-    sqlx::query_scalar(
-      "SELECT 1 FROM \
-       information_schema.columns \
-       WHERE table_schema = $1 AND \
-       table_name = 'feeds' AND \
-       column_name = 'tags'"
-    )
-    .bind(schema)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "fetcher tags column check \
-         failed: {e}"
-      ))
-    })?;
-
-  if has_column.is_some() {
-    return Ok(());
-  }
-
-  let ddl = format!(
-    "ALTER TABLE {}.feeds ADD COLUMN \
-     IF NOT EXISTS tags TEXT[]",
-    quote_ident(schema)
-  );
-
-  sqlx::query(&ddl)
-    .execute(pool)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "fetcher tags column add \
-         failed: {e}"
-      ))
-    })?;
-
-  Ok(())
-}
-
-async fn ensure_fetcher_tags_column_sqlite(
-  pool: &sqlx::SqlitePool
-) -> Result<(), ConfigError> {
-  let has_table: Option<i32> = sqlx::query_scalar(
-        r#"SELECT 1 FROM sqlite_master WHERE type='table' AND name='feeds' LIMIT 1"#
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "fetcher tags table check failed: {e}"
-      ))
-    })?;
-
-  if has_table.is_none() {
-    return Ok(());
-  }
-
-  let has_column: Option<i32> = sqlx::query_scalar(
-        r#"SELECT 1 FROM pragma_table_info('feeds') WHERE name = 'tags' LIMIT 1"#
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "fetcher tags column check failed: {e}"
-      ))
-    })?;
-
-  if has_column.is_some() {
-    return Ok(());
-  }
-
-  sqlx::query(
-    "ALTER TABLE feeds ADD COLUMN \
-     tags TEXT NULL"
-  )
-  .execute(pool)
-  .await
-  .map_err(|e| {
-    ConfigError::Invalid(format!(
-      "fetcher tags column add \
-       failed: {e}"
-    ))
-  })?;
-
-  Ok(())
-}
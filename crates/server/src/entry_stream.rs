@@ -0,0 +1,822 @@
+//! The one place entry lists, search
+//! and unread counts pick their feed
+//! items, so they agree on what a
+//! user's stream holds.
+
+use axum::http::StatusCode;
+use pulsewire_core::domain::entry_filter::{
+  Condition,
+  EntryFilter,
+  parse_filter
+};
+use pulsewire_core::domain::stream::{
+  ReadFilter,
+  StreamScope
+};
+use sqlx::{
+  Database,
+  Encode,
+  QueryBuilder,
+  Type
+};
+
+use crate::app_state::AppState;
+use crate::auth::is_admin;
+use crate::db::quote_ident;
+use crate::errors::ServerError;
+
+/// Per-entry marks a stream may be
+/// limited to; all given must hold.
+#[derive(Debug, Default)]
+pub struct Marks {
+  pub starred: bool,
+  pub queued:  bool,
+  pub label:   Option<i64>,
+  /// Entries one of the user's rules
+  /// hit.
+  pub rule:    Option<i64>
+}
+
+impl Marks {
+  pub fn from_params(
+    starred: Option<bool>,
+    queued: Option<bool>,
+    label: Option<i64>
+  ) -> Self {
+    Self {
+      starred: starred.unwrap_or(false),
+      queued: queued.unwrap_or(false),
+      label,
+      rule: None
+    }
+  }
+
+  fn any(&self) -> bool {
+    self.starred
+      || self.queued
+      || self.label.is_some()
+      || self.rule.is_some()
+  }
+}
+
+/// A smart folder's filter with what
+/// it needs to render for the pool.
+struct StreamFilter {
+  filter:   EntryFilter,
+  /// The feeds table as seen from the
+  /// server's connection.
+  feeds:    String,
+  postgres: bool,
+  /// Reference time for age terms.
+  now_ms:   i64
+}
+
+/// Bounds on when items were
+/// published, in epoch milliseconds,
+/// with the column to read for the
+/// pool.
+struct Published {
+  from:     Option<i64>,
+  until:    Option<i64>,
+  postgres: bool
+}
+
+/// Which items of which feeds a
+/// request reads.
+pub struct EntryStream {
+  pub user_id: i64,
+  pub scope:   StreamScope,
+  pub read:    ReadFilter,
+  pub marks:   Marks,
+  /// Narrows the scope to one feed.
+  pub feed_id: Option<String>,
+  /// Only items with a larger id.
+  pub since:   Option<i64>,
+  /// Only items with a smaller id.
+  pub before:  Option<i64>,
+  /// Only these items.
+  pub ids:     Option<Vec<i64>>,
+  filter:      Option<StreamFilter>,
+  published:   Option<Published>
+}
+
+impl EntryStream {
+  /// A stream over `scope` with every
+  /// read state.
+  pub fn new(
+    user_id: i64,
+    scope: StreamScope
+  ) -> Self {
+    Self {
+      user_id,
+      scope,
+      read: ReadFilter::All,
+      marks: Marks::default(),
+      feed_id: None,
+      since: None,
+      before: None,
+      ids: None,
+      filter: None,
+      published: None
+    }
+  }
+
+  /// The stream of one of the user's
+  /// folders. A smart folder draws
+  /// from the user's subscriptions
+  /// through its filter; any other
+  /// folder from its feeds.
+  pub async fn folder(
+    state: &AppState,
+    user_id: i64,
+    folder_id: i64
+  ) -> Result<Self, ServerError> {
+    let filter = if let Some(pool) =
+      &state.postgres
+    {
+      sqlx::query_scalar::<
+        _,
+        Option<String>
+      >(
+        "SELECT filter FROM folders \
+         WHERE id = $1 AND user_id = $2"
+      )
+      .bind(folder_id)
+      .bind(user_id)
+      .fetch_optional(pool)
+      .await
+    } else {
+      let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| {
+          ServerError::new(
+          StatusCode::INTERNAL_SERVER_ERROR,
+          "database pool missing"
+        )
+        })?;
+
+      sqlx::query_scalar::<
+        _,
+        Option<String>
+      >(
+        "SELECT filter FROM folders \
+         WHERE id = ?1 AND user_id = ?2"
+      )
+      .bind(folder_id)
+      .bind(user_id)
+      .fetch_optional(pool)
+      .await
+    }
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        e.to_string()
+      )
+    })?
+    .flatten();
+
+    let Some(filter) = filter else {
+      return Ok(Self::new(
+        user_id,
+        StreamScope::Folder(folder_id)
+      ));
+    };
+
+    Self::new(
+      user_id,
+      StreamScope::Subscriptions
+    )
+    .with_filter(state, &filter)
+  }
+
+  /// Adds an entry filter expression
+  /// to the stream.
+  pub fn with_filter(
+    mut self,
+    state: &AppState,
+    expr: &str
+  ) -> Result<Self, ServerError> {
+    let filter = parse_filter(expr)
+      .map_err(|e| {
+        ServerError::new(
+          StatusCode::BAD_REQUEST,
+          e
+        )
+      })?;
+
+    let now_ms = chrono::Utc::now()
+      .timestamp_millis();
+
+    let ages_fit =
+      filter.terms.iter().all(|term| {
+        match term.condition {
+          | Condition::NewerThan(
+            ms
+          )
+          | Condition::OlderThan(
+            ms
+          ) => {
+            now_ms
+              .checked_sub(ms)
+              .is_some()
+          }
+          | _ => true
+        }
+      });
+
+    if !ages_fit {
+      return Err(ServerError::new(
+        StatusCode::BAD_REQUEST,
+        "invalid age"
+      ));
+    }
+
+    self.filter = Some(StreamFilter {
+      filter,
+      feeds: feeds_table(state),
+      postgres: state
+        .postgres
+        .is_some(),
+      now_ms
+    });
+
+    Ok(self)
+  }
+
+  /// Limits the stream to items
+  /// published at or after `from` and
+  /// before `until`, in epoch
+  /// milliseconds. Undated items count
+  /// as the oldest.
+  pub fn published(
+    mut self,
+    state: &AppState,
+    from: Option<i64>,
+    until: Option<i64>
+  ) -> Self {
+    self.published = Some(Published {
+      from,
+      until,
+      postgres: state
+        .postgres
+        .is_some()
+    });
+
+    self
+  }
+
+  /// Reads `scope` and `read`
+  /// parameters; `scope=all` is
+  /// limited to admins. Without a
+  /// scope, marks alone pick the
+  /// items: they are the user's own,
+  /// and starred entries outlive
+  /// subscriptions.
+  pub async fn from_params(
+    state: &AppState,
+    user_id: i64,
+    scope: Option<&str>,
+    read: Option<&str>,
+    marks: Marks
+  ) -> Result<Self, ServerError> {
+    let bad_request = |message| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        message
+      )
+    };
+
+    let scope = if scope.is_none()
+      && marks.any()
+    {
+      StreamScope::All
+    } else {
+      let scope =
+        StreamScope::parse(scope)
+          .map_err(bad_request)?;
+
+      if scope == StreamScope::All
+        && !is_admin(state, user_id)
+          .await?
+      {
+        return Err(ServerError::new(
+          StatusCode::FORBIDDEN,
+          "scope all requires admin \
+           access"
+        ));
+      }
+
+      scope
+    };
+
+    let mut stream = match scope {
+      | StreamScope::Folder(
+        folder_id
+      ) => {
+        Self::folder(
+          state, user_id, folder_id
+        )
+        .await?
+      }
+      | scope => {
+        Self::new(user_id, scope)
+      }
+    };
+
+    stream.read =
+      ReadFilter::parse(read)
+        .map_err(bad_request)?;
+    stream.marks = marks;
+
+    Ok(stream)
+  }
+
+  /// Pushes `, .. AS is_starred, ..
+  /// AS is_queued` select columns.
+  pub fn push_mark_columns<'a, DB>(
+    &self,
+    builder: &mut QueryBuilder<'a, DB>
+  ) where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>
+  {
+    for (table, column) in [
+      ("entry_stars", "is_starred"),
+      ("entry_queue", "is_queued")
+    ] {
+      builder.push(format!(
+        ", EXISTS (SELECT 1 FROM \
+         {table} m WHERE m.item_id = \
+         fi.id AND m.user_id = "
+      ));
+      builder.push_bind(self.user_id);
+      builder
+        .push(format!(") AS {column}"));
+    }
+  }
+
+  /// Pushes ` FROM <items> fi` joined
+  /// to the user's entry states, then
+  /// the stream's filters. `items` is
+  /// the feed items table.
+  pub fn push_from<'a, DB>(
+    &self,
+    builder: &mut QueryBuilder<'a, DB>,
+    items: &str
+  ) where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>
+  {
+    builder.push(format!(
+      " FROM {items} fi LEFT JOIN \
+       entry_states es ON es.item_id \
+       = fi.id AND es.user_id = "
+    ));
+
+    builder.push_bind(self.user_id);
+
+    builder.push(" WHERE 1=1");
+
+    self.push_filters(builder);
+  }
+
+  /// Pushes ` AND ..` conditions on
+  /// `fi` and `es` for the scope, read
+  /// state, hidden entries, marks,
+  /// feed, item ids and publish time.
+  pub fn push_filters<'a, DB>(
+    &self,
+    builder: &mut QueryBuilder<'a, DB>
+  ) where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>
+  {
+    match self.scope {
+      | StreamScope::All => {}
+      | StreamScope::Subscriptions => {
+        builder.push(
+          " AND fi.feed_id IN (SELECT \
+           feed_id FROM subscriptions \
+           WHERE user_id = "
+        );
+        builder.push_bind(self.user_id);
+        builder.push(")");
+      }
+      | StreamScope::SubscriptionsOrStarred => {
+        builder.push(
+          " AND (fi.feed_id IN (SELECT \
+           feed_id FROM subscriptions \
+           WHERE user_id = "
+        );
+        builder.push_bind(self.user_id);
+        builder.push(
+          ") OR fi.id IN (SELECT \
+           item_id FROM entry_stars \
+           WHERE user_id = "
+        );
+        builder.push_bind(self.user_id);
+        builder.push("))");
+      }
+      | StreamScope::Favorites => {
+        builder.push(
+          " AND fi.feed_id IN (SELECT \
+           feed_id FROM favorites \
+           WHERE user_id = "
+        );
+        builder.push_bind(self.user_id);
+        builder.push(")");
+      }
+      | StreamScope::Folder(
+        folder_id
+      ) => {
+        builder.push(
+          " AND fi.feed_id IN (SELECT \
+           ff.feed_id FROM \
+           folder_feeds ff JOIN \
+           folders f ON f.id = \
+           ff.folder_id WHERE \
+           ff.folder_id = "
+        );
+        builder.push_bind(folder_id);
+        builder
+          .push(" AND f.user_id = ");
+        builder.push_bind(self.user_id);
+        builder.push(")");
+      }
+    }
+
+    match self.read {
+      | ReadFilter::All => {}
+      | ReadFilter::Read => {
+        builder.push(
+          " AND es.read_at IS NOT NULL"
+        );
+      }
+      | ReadFilter::Unread => {
+        builder.push(
+          " AND es.read_at IS NULL"
+        );
+      }
+    }
+
+    // Entries a hide rule hit are gone
+    // from every stream.
+    builder.push(
+      " AND NOT EXISTS (SELECT 1 FROM \
+       rule_hits rh WHERE rh.item_id \
+       = fi.id AND rh.action = 'hide' \
+       AND rh.user_id = "
+    );
+    builder.push_bind(self.user_id);
+    builder.push(")");
+
+    for (on, table) in [
+      (
+        self.marks.starred,
+        "entry_stars"
+      ),
+      (
+        self.marks.queued,
+        "entry_queue"
+      )
+    ] {
+      if on {
+        builder.push(format!(
+          " AND fi.id IN (SELECT \
+           item_id FROM {table} WHERE \
+           user_id = "
+        ));
+        builder.push_bind(self.user_id);
+        builder.push(")");
+      }
+    }
+
+    if let Some(label) =
+      self.marks.label
+    {
+      builder.push(
+        " AND fi.id IN (SELECT \
+         el.item_id FROM entry_labels \
+         el JOIN labels l ON l.id = \
+         el.label_id WHERE l.id = "
+      );
+      builder.push_bind(label);
+      builder.push(" AND l.user_id = ");
+      builder.push_bind(self.user_id);
+      builder.push(")");
+    }
+
+    if let Some(rule) = self.marks.rule
+    {
+      builder.push(
+        " AND fi.id IN (SELECT \
+         item_id FROM rule_hits WHERE \
+         rule_id = "
+      );
+      builder.push_bind(rule);
+      builder.push(" AND user_id = ");
+      builder.push_bind(self.user_id);
+      builder.push(")");
+    }
+
+    if let Some(feed_id) = &self.feed_id
+    {
+      builder
+        .push(" AND fi.feed_id = ");
+      builder
+        .push_bind(feed_id.clone());
+    }
+
+    if let Some(since) = self.since {
+      builder.push(" AND fi.id > ");
+      builder.push_bind(since);
+    }
+
+    if let Some(before) = self.before {
+      builder.push(" AND fi.id < ");
+      builder.push_bind(before);
+    }
+
+    if let Some(ids) = &self.ids {
+      if ids.is_empty() {
+        builder.push(" AND 1=0");
+      } else {
+        builder.push(" AND fi.id IN (");
+
+        let mut list =
+          builder.separated(", ");
+
+        for id in ids {
+          list.push_bind(*id);
+        }
+
+        builder.push(")");
+      }
+    }
+
+    if let Some(published) =
+      &self.published
+    {
+      let column = published_ms(
+        published.postgres
+      );
+
+      if let Some(from) = published.from
+      {
+        builder.push(format!(
+          " AND {column} >= "
+        ));
+        builder.push_bind(from);
+      }
+
+      if let Some(until) =
+        published.until
+      {
+        builder.push(format!(
+          " AND {column} < "
+        ));
+        builder.push_bind(until);
+      }
+    }
+
+    if let Some(filter) = &self.filter {
+      filter.push_terms(builder);
+    }
+  }
+}
+
+impl StreamFilter {
+  /// Pushes one ` AND ..` per term.
+  /// Missing text counts as empty and
+  /// undated items as the oldest, so
+  /// negated terms keep them.
+  fn push_terms<'a, DB>(
+    &self,
+    builder: &mut QueryBuilder<'a, DB>
+  ) where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>
+  {
+    let like = if self.postgres {
+      "ILIKE"
+    } else {
+      "LIKE"
+    };
+
+    let published =
+      published_ms(self.postgres);
+
+    // `with_filter` checked that every
+    // cutoff fits.
+    let now_ms = self.now_ms;
+
+    for term in &self.filter.terms {
+      builder.push(
+        if term.negated {
+          " AND NOT ("
+        } else {
+          " AND ("
+        }
+      );
+
+      match &term.condition {
+        | Condition::Feed(feed_id) => {
+          builder.push("fi.feed_id = ");
+          builder
+            .push_bind(feed_id.clone());
+        }
+        | Condition::Category(name) => {
+          builder.push(
+            "lower(COALESCE(fi.\
+             category, '')) = lower("
+          );
+          builder
+            .push_bind(name.clone());
+          builder.push(format!(
+            ") OR fi.feed_id IN \
+             (SELECT id FROM {} WHERE \
+             lower(category) = lower(",
+            self.feeds
+          ));
+          builder
+            .push_bind(name.clone());
+          builder.push("))");
+        }
+        | Condition::Tag(tag) => {
+          if self.postgres {
+            builder.push(format!(
+              "fi.feed_id IN (SELECT \
+               id FROM {} WHERE ",
+              self.feeds
+            ));
+            builder
+              .push_bind(tag.clone());
+            builder
+              .push(" = ANY(tags))");
+          } else {
+            builder.push(
+              "fi.feed_id IN (SELECT \
+               f.id FROM feeds f, \
+               json_each(f.tags) t \
+               WHERE t.value = "
+            );
+            builder
+              .push_bind(tag.clone());
+            builder.push(")");
+          }
+        }
+        | Condition::Author(text) => {
+          push_like(
+            builder,
+            &["fi.author"],
+            like,
+            text
+          );
+        }
+        | Condition::Title(text) => {
+          push_like(
+            builder,
+            &["fi.title"],
+            like,
+            text
+          );
+        }
+        | Condition::Text(text) => {
+          push_like(
+            builder,
+            &[
+              "fi.title",
+              "fi.summary",
+              "fi.description"
+            ],
+            like,
+            text
+          );
+        }
+        | Condition::Read(true) => {
+          builder.push(
+            "es.read_at IS NOT NULL"
+          );
+        }
+        | Condition::Read(false) => {
+          builder
+            .push("es.read_at IS NULL");
+        }
+        | Condition::NewerThan(ms) => {
+          builder.push(format!(
+            "{published} >= "
+          ));
+          builder
+            .push_bind(now_ms - ms);
+        }
+        | Condition::OlderThan(ms) => {
+          builder.push(format!(
+            "{published} < "
+          ));
+          builder
+            .push_bind(now_ms - ms);
+        }
+      }
+
+      builder.push(")");
+    }
+  }
+}
+
+/// When an item was published, in
+/// epoch milliseconds, with undated
+/// items at zero.
+fn published_ms(
+  postgres: bool
+) -> &'static str {
+  if postgres {
+    "COALESCE(CAST(EXTRACT(EPOCH FROM \
+     fi.published_at) * 1000 AS \
+     BIGINT), 0)"
+  } else {
+    "COALESCE(fi.published_at_ms, 0)"
+  }
+}
+
+/// `col LIKE '%text%'` for any of
+/// `columns`, with `%`, `_` and `\`
+/// in `text` matched literally.
+fn push_like<'a, DB>(
+  builder: &mut QueryBuilder<'a, DB>,
+  columns: &[&str],
+  like: &str,
+  text: &str
+) where
+  DB: Database,
+  String: Encode<'a, DB> + Type<DB>
+{
+  let mut pattern = String::from("%");
+
+  for c in text.chars() {
+    if matches!(c, '%' | '_' | '\\') {
+      pattern.push('\\');
+    }
+
+    pattern.push(c);
+  }
+
+  pattern.push('%');
+
+  for (i, column) in
+    columns.iter().enumerate()
+  {
+    if i > 0 {
+      builder.push(" OR ");
+    }
+
+    builder.push(format!(
+      "COALESCE({column}, '') {like} "
+    ));
+    builder.push_bind(pattern.clone());
+    builder.push(" ESCAPE '\\'");
+  }
+}
+
+/// The feeds table as seen from the
+/// server's connection.
+pub fn feeds_table(
+  state: &AppState
+) -> String {
+  if state.postgres.is_some() {
+    format!(
+      "{}.feeds",
+      quote_ident(
+        state
+          .fetcher_schema
+          .as_deref()
+          .unwrap_or("fetcher")
+      )
+    )
+  } else {
+    "feeds".to_string()
+  }
+}
+
+/// The feed items table as seen from
+/// the server's connection.
+pub fn items_table(
+  state: &AppState
+) -> String {
+  if state.postgres.is_some() {
+    format!(
+      "{}.feed_items",
+      quote_ident(
+        state
+          .fetcher_schema
+          .as_deref()
+          .unwrap_or("fetcher")
+      )
+    )
+  } else {
+    "feed_items".to_string()
+  }
+}
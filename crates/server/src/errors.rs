@@ -37,6 +37,14 @@ impl ServerError {
       message: message.into()
     }
   }
+
+  pub fn status(&self) -> StatusCode {
+    self.status
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
 }
 
 impl IntoResponse for ServerError {
@@ -80,6 +88,7 @@ pub fn is_unique_violation(
       sqlx::Error::Database(db_err)
           if db_err.code().as_deref() == Some("23505")
               || db_err.code().as_deref() == Some("2067")
+              || db_err.code().as_deref() == Some("1555")
   )
 }
 
@@ -92,6 +101,7 @@ fn status_code_to_string(
         StatusCode::FORBIDDEN => "forbidden",
         StatusCode::NOT_FOUND => "not_found",
         StatusCode::CONFLICT => "conflict",
+        StatusCode::UNPROCESSABLE_ENTITY => "unprocessable_entity",
         StatusCode::INTERNAL_SERVER_ERROR => "internal_error",
         _ => status.canonical_reason().unwrap_or("error"),
     }
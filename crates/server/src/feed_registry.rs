@@ -0,0 +1,219 @@
+//! Feeds users add by URL. A new feed
+//! is discovered and validated once,
+//! then recorded in the fetcher's feed
+//! registry. Approved feeds also get a
+//! `feeds` row right away so they can
+//! be subscribed to before the fetcher
+//! next syncs the registry.
+
+use axum::http::StatusCode;
+use pulsewire_core::app::registry::{
+  DiscoveredFeed,
+  feed_config
+};
+use pulsewire_core::domain::registry::{
+  RegistryFeed,
+  RegistryStatus,
+  registry_feed_id
+};
+
+use crate::app_state::AppState;
+use crate::entry_stream::feeds_table;
+use crate::errors::ServerError;
+use crate::models::FeedQuotaRow;
+
+/// The polled feed at `url`, if any.
+pub async fn feed_by_url(
+  state: &AppState,
+  url: &str
+) -> Result<Option<String>, ServerError>
+{
+  let sql = format!(
+    "SELECT id FROM {} WHERE url = {} \
+     ORDER BY id LIMIT 1",
+    feeds_table(state),
+    if state.postgres.is_some() {
+      "$1"
+    } else {
+      "?1"
+    }
+  );
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query_scalar::<_, String>(&sql)
+      .bind(url)
+      .fetch_optional(pool)
+      .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, String>(&sql)
+      .bind(url)
+      .fetch_optional(pool)
+      .await
+  }
+  .map_err(|e| internal(e.to_string()))
+}
+
+/// The registry feed with this id or
+/// URL, if any.
+pub async fn registry_feed(
+  state: &AppState,
+  id_or_url: &str
+) -> Result<
+  Option<RegistryFeed>,
+  ServerError
+> {
+  state
+    .fetcher
+    .registry_feed(id_or_url)
+    .await
+    .map_err(internal)
+}
+
+/// The user's feed quota and how much
+/// of it is used.
+pub async fn quota(
+  state: &AppState,
+  user_id: i64
+) -> Result<FeedQuotaRow, ServerError> {
+  let custom = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, i64>(
+      "SELECT max_feeds FROM \
+       feed_quotas WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, i64>(
+      "SELECT max_feeds FROM \
+       feed_quotas WHERE user_id = ?1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(|e| internal(e.to_string()))?;
+
+  let used = state
+    .fetcher
+    .count_registry_feeds(user_id)
+    .await
+    .map_err(internal)?;
+
+  Ok(FeedQuotaRow {
+    user_id,
+    max_feeds: custom.unwrap_or(
+      state.registry.default_quota
+    ),
+    used,
+    custom: custom.is_some()
+  })
+}
+
+/// Records a discovered feed, approved
+/// at once for admins or when approval
+/// is not required.
+pub async fn register(
+  state: &AppState,
+  user_id: i64,
+  feed: DiscoveredFeed,
+  admin: bool
+) -> Result<RegistryFeed, ServerError> {
+  let now_ms = chrono::Utc::now()
+    .timestamp_millis();
+
+  let approved = admin
+    || !state.registry.require_approval;
+
+  let entry = RegistryFeed {
+    id:             registry_feed_id(
+      &feed.url
+    ),
+    url:            feed.url,
+    title:          feed.title,
+    added_by:       user_id,
+    status:         if approved {
+      RegistryStatus::Approved
+    } else {
+      RegistryStatus::Pending
+    },
+    created_at_ms:  now_ms,
+    reviewed_at_ms: approved
+      .then_some(now_ms)
+  };
+
+  state
+    .fetcher
+    .insert_registry_feed(
+      &entry,
+      &chrono_tz::UTC
+    )
+    .await
+    .map_err(internal)?;
+
+  if approved {
+    activate(state, &entry).await?;
+  }
+
+  Ok(entry)
+}
+
+/// Adds an approved feed to the
+/// fetcher's feeds under the registry
+/// category.
+pub async fn activate(
+  state: &AppState,
+  feed: &RegistryFeed
+) -> Result<(), ServerError> {
+  let config = feed_config(
+    feed,
+    &state.registry.category,
+    state.registry.poll_seconds
+  )
+  .ok_or_else(|| {
+    internal(format!(
+      "invalid feed url '{}'",
+      feed.url
+    ))
+  })?;
+
+  state
+    .fetcher
+    .upsert_categories(
+      vec![
+        state.registry.category.clone(),
+      ],
+      &chrono_tz::UTC
+    )
+    .await
+    .map_err(internal)?;
+
+  state
+    .fetcher
+    .upsert_feeds_bulk(
+      vec![config],
+      1,
+      &chrono_tz::UTC
+    )
+    .await
+    .map_err(internal)
+}
+
+fn internal(e: String) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e
+  )
+}
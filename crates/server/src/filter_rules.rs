@@ -0,0 +1,458 @@
+//! Runs users' filter rules. A rule
+//! checks the items of the user's
+//! subscriptions its filter matches,
+//! records a hit per item and applies
+//! its action once per hit. New items
+//! are checked when the user next
+//! reads their entries; older ones
+//! only when a rule is applied on
+//! demand.
+
+use axum::http::StatusCode;
+use pulsewire_core::domain::entry_filter::RuleAction;
+use pulsewire_core::domain::stream::StreamScope;
+use sqlx::{
+  Database,
+  Encode,
+  Postgres,
+  QueryBuilder,
+  Sqlite,
+  Type
+};
+
+use crate::app_state::AppState;
+use crate::entry_stream::{
+  EntryStream,
+  items_table
+};
+use crate::errors::ServerError;
+
+/// What running a rule needs.
+#[derive(sqlx::FromRow)]
+struct RuleState {
+  id:              i64,
+  filter:          String,
+  action:          String,
+  label_id:        Option<i64>,
+  checked_item_id: i64
+}
+
+/// The largest feed item id so far;
+/// new rules start checking after it.
+pub async fn latest_item_id(
+  state: &AppState
+) -> Result<i64, ServerError> {
+  let sql = format!(
+    "SELECT CAST(COALESCE(MAX(id), 0) \
+     AS BIGINT) FROM {}",
+    items_table(state)
+  );
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query_scalar::<_, i64>(&sql)
+      .fetch_one(pool)
+      .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, i64>(&sql)
+      .fetch_one(pool)
+      .await
+  }
+  .map_err(query_error)
+}
+
+/// Runs the user's rules over items
+/// that arrived since each last ran.
+/// Called before entry lists, search
+/// and counts so they see the results.
+pub async fn run_new_items(
+  state: &AppState,
+  user_id: i64
+) -> Result<(), ServerError> {
+  let rules =
+    load_rules(state, user_id, None)
+      .await?;
+
+  if rules.is_empty() {
+    return Ok(());
+  }
+
+  let upto =
+    latest_item_id(state).await?;
+
+  for rule in rules {
+    if rule.checked_item_id < upto {
+      run_rule(
+        state,
+        user_id,
+        &rule,
+        Some(rule.checked_item_id),
+        upto
+      )
+      .await?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Runs one rule over every item up
+/// to now, returning how many entries
+/// it newly matched. `None` if the
+/// user has no such rule.
+pub async fn apply_rule(
+  state: &AppState,
+  user_id: i64,
+  rule_id: i64
+) -> Result<Option<i64>, ServerError> {
+  let Some(rule) = load_rules(
+    state,
+    user_id,
+    Some(rule_id)
+  )
+  .await?
+  .into_iter()
+  .next() else {
+    return Ok(None);
+  };
+
+  let upto =
+    latest_item_id(state).await?;
+
+  let hits = run_rule(
+    state, user_id, &rule, None, upto
+  )
+  .await?;
+
+  Ok(Some(hits.len() as i64))
+}
+
+async fn load_rules(
+  state: &AppState,
+  user_id: i64,
+  rule_id: Option<i64>
+) -> Result<Vec<RuleState>, ServerError>
+{
+  if let Some(pool) = &state.postgres {
+    sqlx::query_as::<_, RuleState>(
+      "SELECT id, filter, action, \
+       label_id, checked_item_id FROM \
+       filter_rules WHERE user_id = $1 \
+       AND ($2::BIGINT IS NULL OR id = \
+       $2) ORDER BY id"
+    )
+    .bind(user_id)
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, RuleState>(
+      "SELECT id, filter, action, \
+       label_id, checked_item_id FROM \
+       filter_rules WHERE user_id = ?1 \
+       AND (?2 IS NULL OR id = ?2) \
+       ORDER BY id"
+    )
+    .bind(user_id)
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)
+}
+
+/// Records hits for the items after
+/// `since` up to `upto` the rule
+/// matches and has not hit before,
+/// applies its action to them and
+/// moves its checked mark to `upto`.
+/// Returns the newly hit item ids.
+async fn run_rule(
+  state: &AppState,
+  user_id: i64,
+  rule: &RuleState,
+  since: Option<i64>,
+  upto: i64
+) -> Result<Vec<i64>, ServerError> {
+  let action =
+    RuleAction::parse(&rule.action)
+      .map_err(|e| {
+        ServerError::new(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      e
+    )
+      })?;
+
+  let mut stream = EntryStream::new(
+    user_id,
+    StreamScope::Subscriptions
+  )
+  .with_filter(state, &rule.filter)?;
+
+  stream.since = since;
+
+  let items = items_table(state);
+
+  // The rule's own label, or the user
+  // for marks kept per user.
+  let owner = match action {
+    | RuleAction::Label => {
+      rule.label_id.unwrap_or_default()
+    }
+    | _ => user_id
+  };
+
+  let hits = if let Some(pool) =
+    &state.postgres
+  {
+    let mut tx = pool
+      .begin()
+      .await
+      .map_err(query_error)?;
+
+    let mut builder =
+      QueryBuilder::<Postgres>::new("");
+
+    push_hits(
+      &mut builder,
+      &stream,
+      &items,
+      rule,
+      action,
+      upto,
+      "NOW()"
+    );
+
+    let hits = builder
+      .build_query_scalar::<i64>()
+      .fetch_all(&mut *tx)
+      .await
+      .map_err(query_error)?;
+
+    if let Some(sql) =
+      action_sql(action, true)
+      && !hits.is_empty()
+    {
+      sqlx::query(sql)
+        .bind(owner)
+        .bind(&hits)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+    }
+
+    sqlx::query(
+      "UPDATE filter_rules SET \
+       checked_item_id = $1 WHERE id \
+       = $2 AND checked_item_id < $1"
+    )
+    .bind(upto)
+    .bind(rule.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(query_error)?;
+
+    tx.commit()
+      .await
+      .map_err(query_error)?;
+
+    hits
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let mut tx = pool
+      .begin()
+      .await
+      .map_err(query_error)?;
+
+    let mut builder =
+      QueryBuilder::<Sqlite>::new("");
+
+    push_hits(
+      &mut builder,
+      &stream,
+      &items,
+      rule,
+      action,
+      upto,
+      "datetime('now')"
+    );
+
+    let hits = builder
+      .build_query_scalar::<i64>()
+      .fetch_all(&mut *tx)
+      .await
+      .map_err(query_error)?;
+
+    if let Some(sql) =
+      action_sql(action, false)
+      && !hits.is_empty()
+    {
+      let ids =
+        serde_json::to_string(&hits)
+          .unwrap_or_default();
+
+      sqlx::query(sql)
+        .bind(owner)
+        .bind(ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+    }
+
+    sqlx::query(
+      "UPDATE filter_rules SET \
+       checked_item_id = ?1 WHERE id \
+       = ?2 AND checked_item_id < ?1"
+    )
+    .bind(upto)
+    .bind(rule.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(query_error)?;
+
+    tx.commit()
+      .await
+      .map_err(query_error)?;
+
+    hits
+  };
+
+  Ok(hits)
+}
+
+/// Pushes the insert of the rule's new
+/// hits, returning their item ids.
+fn push_hits<'a, DB>(
+  builder: &mut QueryBuilder<'a, DB>,
+  stream: &EntryStream,
+  items: &str,
+  rule: &RuleState,
+  action: RuleAction,
+  upto: i64,
+  now: &str
+) where
+  DB: Database,
+  i64: Encode<'a, DB> + Type<DB>,
+  String: Encode<'a, DB> + Type<DB>
+{
+  builder.push(
+    "INSERT INTO rule_hits (rule_id, \
+     item_id, user_id, action, \
+     created_at) SELECT "
+  );
+  builder.push_bind(rule.id);
+  builder.push(", fi.id, ");
+  builder.push_bind(stream.user_id);
+  builder.push(", ");
+  builder.push_bind(
+    action.as_str().to_string()
+  );
+  builder.push(format!(", {now}"));
+
+  stream.push_from(builder, items);
+
+  builder.push(" AND fi.id <= ");
+  builder.push_bind(upto);
+  builder.push(
+    " AND NOT EXISTS (SELECT 1 FROM \
+     rule_hits h WHERE h.item_id = \
+     fi.id AND h.rule_id = "
+  );
+  builder.push_bind(rule.id);
+  builder.push(
+    ") ON CONFLICT DO NOTHING \
+     RETURNING item_id"
+  );
+}
+
+/// The statement applying an action to
+/// a batch of items: bound to the user
+/// (or the label) and the item ids, an
+/// array on Postgres and JSON text on
+/// SQLite. Hiding needs no more than
+/// the hit.
+fn action_sql(
+  action: RuleAction,
+  postgres: bool
+) -> Option<&'static str> {
+  let sql = match (action, postgres) {
+    | (RuleAction::Read, true) => {
+      "INSERT INTO entry_states \
+       (user_id, item_id, read_at) \
+       SELECT $1, \
+       UNNEST($2::BIGINT[]), NOW() ON \
+       CONFLICT (user_id, item_id) DO \
+       UPDATE SET read_at = \
+       COALESCE(entry_states.read_at, \
+       EXCLUDED.read_at)"
+    }
+    | (RuleAction::Read, false) => {
+      "INSERT INTO entry_states \
+       (user_id, item_id, read_at) \
+       SELECT ?1, value, \
+       datetime('now') FROM \
+       json_each(?2) WHERE true ON \
+       CONFLICT(user_id, item_id) DO \
+       UPDATE SET read_at = \
+       COALESCE(entry_states.read_at, \
+       excluded.read_at)"
+    }
+    | (RuleAction::Star, true) => {
+      "INSERT INTO entry_stars \
+       (user_id, item_id, created_at) \
+       SELECT $1, \
+       UNNEST($2::BIGINT[]), NOW() ON \
+       CONFLICT DO NOTHING"
+    }
+    | (RuleAction::Star, false) => {
+      "INSERT INTO entry_stars \
+       (user_id, item_id, created_at) \
+       SELECT ?1, value, \
+       datetime('now') FROM \
+       json_each(?2) WHERE true ON \
+       CONFLICT DO NOTHING"
+    }
+    | (RuleAction::Label, true) => {
+      "INSERT INTO entry_labels \
+       (label_id, item_id, created_at) \
+       SELECT $1, \
+       UNNEST($2::BIGINT[]), NOW() ON \
+       CONFLICT DO NOTHING"
+    }
+    | (RuleAction::Label, false) => {
+      "INSERT INTO entry_labels \
+       (label_id, item_id, created_at) \
+       SELECT ?1, value, \
+       datetime('now') FROM \
+       json_each(?2) WHERE true ON \
+       CONFLICT DO NOTHING"
+    }
+    | (RuleAction::Hide, _) => {
+      return None;
+    }
+  };
+
+  Some(sql)
+}
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e.to_string()
+  )
+}
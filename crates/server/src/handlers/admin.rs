@@ -0,0 +1,103 @@
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  Query,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use pulsewire_core::app::health::{
+  FeedHealth,
+  HealthWindow,
+  feed_health,
+  health_report
+};
+
+use crate::app_state::AppState;
+use crate::auth::auth_admin_id;
+use crate::errors::ServerError;
+use crate::models::HealthQuery;
+
+fn window(
+  query: &HealthQuery
+) -> HealthWindow {
+  let defaults =
+    HealthWindow::default();
+
+  HealthWindow {
+    events:   query
+      .events
+      .unwrap_or(defaults.events)
+      .clamp(1, 1000),
+    payloads: query
+      .payloads
+      .unwrap_or(defaults.payloads)
+      .clamp(1, 200)
+  }
+}
+
+fn internal(e: String) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e
+  )
+}
+
+pub async fn feeds_health(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(query): Query<HealthQuery>
+) -> Result<
+  Json<Vec<FeedHealth>>,
+  ServerError
+> {
+  auth_admin_id(&state, &headers)
+    .await?;
+
+  let report = health_report(
+    state.fetcher.as_ref(),
+    window(&query)
+  )
+  .await
+  .map_err(internal)?;
+
+  Ok(Json(report))
+}
+
+pub async fn feed_health_detail(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(feed_id): AxumPath<String>,
+  Query(query): Query<HealthQuery>
+) -> Result<Json<FeedHealth>, ServerError>
+{
+  auth_admin_id(&state, &headers)
+    .await?;
+
+  let known = state
+    .fetcher
+    .feed_categories()
+    .await
+    .map_err(internal)?
+    .into_iter()
+    .any(|(id, _)| id == feed_id);
+
+  if !known {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "feed not found"
+    ));
+  }
+
+  let health = feed_health(
+    state.fetcher.as_ref(),
+    &feed_id,
+    window(&query)
+  )
+  .await
+  .map_err(internal)?;
+
+  Ok(Json(health))
+}
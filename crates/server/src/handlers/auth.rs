@@ -11,9 +11,10 @@ use axum::http::{
 use crate::app_state::AppState;
 use crate::auth::{
   bearer_token,
+  check_password,
   generate_token,
   hash_token,
-  verify_password
+  issue_token
 };
 use crate::errors::ServerError;
 use crate::models::{
@@ -44,77 +45,14 @@ pub async fn login(
     ));
   }
 
-  let (user_id, password_hash) =
-    if let Some(pool) = &state.postgres
-    {
-      sqlx::query_as::<_, (i64, String)>(
-            "SELECT id, password_hash FROM users WHERE username = $1",
-        )
-        .bind(username)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| ServerError::new(StatusCode::UNAUTHORIZED, "invalid credentials"))?
-    } else {
-      let pool = state
-            .sqlite
-            .as_ref()
-            .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
-
-      sqlx::query_as::<_, (i64, String)>(
-            "SELECT id, password_hash FROM users WHERE username = ?1",
-        )
-        .bind(username)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or_else(|| ServerError::new(StatusCode::UNAUTHORIZED, "invalid credentials"))?
-    };
-
-  verify_password(
-    &password_hash,
-    password
+  let user_id = check_password(
+    &state, username, password
   )
-  .map_err(|_| {
-    ServerError::new(
-      StatusCode::UNAUTHORIZED,
-      "invalid credentials"
-    )
-  })?;
-
-  let token = generate_token();
-
-  let token_hash = hash_token(&token);
-
-  let ttl =
-    state.token_ttl_seconds as i64;
-
-  if let Some(pool) = &state.postgres {
-    sqlx::query(
-            "INSERT INTO user_tokens (user_id, token_hash, expires_at, created_at) VALUES ($1, $2, NOW() + ($3 || ' seconds')::interval, NOW())",
-        )
-        .bind(user_id)
-        .bind(&token_hash)
-        .bind(ttl)
-        .execute(pool)
-        .await
-        .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-  } else {
-    let pool = state
-            .sqlite
-            .as_ref()
-            .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+  .await?;
 
-    sqlx::query(
-            "INSERT INTO user_tokens (user_id, token_hash, expires_at, created_at) VALUES (?1, ?2, datetime('now', '+' || ?3 || ' seconds'), datetime('now'))",
-        )
-        .bind(user_id)
-        .bind(&token_hash)
-        .bind(ttl)
-        .execute(pool)
-        .await
-        .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-  }
+  let token =
+    issue_token(&state, user_id)
+      .await?;
 
   Ok(Json(TokenResponse {
     token,
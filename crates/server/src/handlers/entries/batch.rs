@@ -6,9 +6,13 @@ use axum::http::{
 };
 
 use crate::app_state::AppState;
-use crate::auth::auth_user_id;
+use crate::auth::{
+  auth_user_id,
+  session_id
+};
 use crate::errors::ServerError;
 use crate::models::EntryBatchRequest;
+use crate::realtime::ReadChange;
 
 pub async fn mark_entries_read(
   State(state): State<AppState>,
@@ -40,7 +44,10 @@ pub async fn mark_entries_read(
         .await
         .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    return Ok(StatusCode::NO_CONTENT);
+    return Ok(read_changed(
+      &state, user_id, &headers,
+      payload, true
+    ));
   }
 
   let pool = state
@@ -53,7 +60,7 @@ pub async fn mark_entries_read(
         .await
         .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-  for item_id in payload.item_ids {
+  for item_id in &payload.item_ids {
     sqlx::query(
             "INSERT INTO entry_states (user_id, item_id, read_at) VALUES (?1, ?2, datetime('now')) \
             ON CONFLICT(user_id, item_id) DO UPDATE SET read_at = excluded.read_at",
@@ -72,7 +79,10 @@ pub async fn mark_entries_read(
     )
   })?;
 
-  Ok(StatusCode::NO_CONTENT)
+  Ok(read_changed(
+    &state, user_id, &headers, payload,
+    true
+  ))
 }
 
 pub async fn mark_entries_unread(
@@ -101,7 +111,10 @@ pub async fn mark_entries_unread(
             .await
             .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    return Ok(StatusCode::NO_CONTENT);
+    return Ok(read_changed(
+      &state, user_id, &headers,
+      payload, false
+    ));
   }
 
   let pool = state
@@ -114,7 +127,7 @@ pub async fn mark_entries_unread(
         .await
         .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-  for item_id in payload.item_ids {
+  for item_id in &payload.item_ids {
     sqlx::query("DELETE FROM entry_states WHERE user_id = ?1 AND item_id = ?2")
             .bind(user_id)
             .bind(item_id)
@@ -130,5 +143,29 @@ pub async fn mark_entries_unread(
     )
   })?;
 
-  Ok(StatusCode::NO_CONTENT)
+  Ok(read_changed(
+    &state, user_id, &headers, payload,
+    false
+  ))
+}
+
+/// Tells the user's other sessions
+/// about the change.
+fn read_changed(
+  state: &AppState,
+  user_id: i64,
+  headers: &HeaderMap,
+  payload: EntryBatchRequest,
+  read: bool
+) -> StatusCode {
+  state.realtime.read_changed(
+    ReadChange {
+      user_id,
+      session: session_id(headers).ok(),
+      item_ids: payload.item_ids,
+      read
+    }
+  );
+
+  StatusCode::NO_CONTENT
 }
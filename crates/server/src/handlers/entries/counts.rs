@@ -1,23 +1,39 @@
 use axum::Json;
-use axum::extract::State;
+use axum::extract::{
+  Query,
+  State
+};
 use axum::http::{
   HeaderMap,
   StatusCode
 };
+use sqlx::{
+  Postgres,
+  QueryBuilder,
+  Sqlite
+};
 
 use crate::app_state::AppState;
 use crate::auth::auth_user_id;
 use crate::db::quote_ident;
+use crate::entry_stream::{
+  EntryStream,
+  Marks,
+  items_table
+};
 use crate::errors::ServerError;
+use crate::filter_rules::run_new_items;
 use crate::models::{
   FeedEntryCounts,
   FeedUnreadCount,
+  ScopeQuery,
   UnreadCountResponse
 };
 
 pub async fn unread_count(
   State(state): State<AppState>,
-  headers: HeaderMap
+  headers: HeaderMap,
+  Query(query): Query<ScopeQuery>
 ) -> Result<
   Json<UnreadCountResponse>,
   ServerError
@@ -26,62 +42,22 @@ pub async fn unread_count(
     auth_user_id(&state, &headers)
       .await?;
 
-  if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
-
-    let query = format!(
-      "SELECT COUNT(*)::BIGINT FROM \
-       {}.feed_items fi JOIN \
-       subscriptions s ON s.feed_id = \
-       fi.feed_id AND s.user_id = $1 \
-       LEFT JOIN entry_states es ON \
-       es.item_id = fi.id AND \
-       es.user_id = $1 WHERE \
-       es.read_at IS NULL",
-      quote_ident(schema)
-    );
-
-    let count = sqlx::query_scalar::<_, i64>(&query)
-            .bind(user_id)
-            .fetch_one(pool)
-            .await
-            .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    return Ok(Json(
-      UnreadCountResponse {
-        count
-      }
-    ));
-  }
+  run_new_items(&state, user_id)
+    .await?;
 
-  let pool = state
-        .sqlite
-        .as_ref()
-        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+  let stream =
+    EntryStream::from_params(
+      &state,
+      user_id,
+      query.scope.as_deref(),
+      Some("unread"),
+      Marks::default()
+    )
+    .await?;
 
   let count =
-    sqlx::query_scalar::<_, i64>(
-      "SELECT COUNT(*) FROM \
-       feed_items fi JOIN \
-       subscriptions s ON s.feed_id = \
-       fi.feed_id AND s.user_id = ?1 \
-       LEFT JOIN entry_states es ON \
-       es.item_id = fi.id AND \
-       es.user_id = ?1 WHERE \
-       es.read_at IS NULL"
-    )
-    .bind(user_id)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
-      ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      e.to_string(),
-    )
-    })?;
+    stream_item_count(&state, &stream)
+      .await?;
 
   Ok(Json(UnreadCountResponse {
     count
@@ -90,7 +66,8 @@ pub async fn unread_count(
 
 pub async fn feed_unread_counts(
   State(state): State<AppState>,
-  headers: HeaderMap
+  headers: HeaderMap,
+  Query(query): Query<ScopeQuery>
 ) -> Result<
   Json<Vec<FeedUnreadCount>>,
   ServerError
@@ -99,66 +76,129 @@ pub async fn feed_unread_counts(
     auth_user_id(&state, &headers)
       .await?;
 
+  run_new_items(&state, user_id)
+    .await?;
+
+  let stream =
+    EntryStream::from_params(
+      &state,
+      user_id,
+      query.scope.as_deref(),
+      Some("unread"),
+      Marks::default()
+    )
+    .await?;
+
+  let rows =
+    stream_feed_counts(&state, &stream)
+      .await?;
+
+  Ok(Json(rows))
+}
+
+/// Items in a stream; unread counts
+/// pass an unread stream.
+pub(crate) async fn stream_item_count(
+  state: &AppState,
+  stream: &EntryStream
+) -> Result<i64, ServerError> {
+  let items = items_table(state);
+
+  let select =
+    "SELECT CAST(COUNT(*) AS BIGINT)";
+
   if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
+    let mut builder =
+      QueryBuilder::<Postgres>::new(
+        select
+      );
 
-    let query = format!(
-      "SELECT fi.feed_id, \
-       COUNT(*)::BIGINT AS \
-       unread_count FROM \
-       {}.feed_items fi JOIN \
-       subscriptions s ON s.feed_id = \
-       fi.feed_id AND s.user_id = $1 \
-       LEFT JOIN entry_states es ON \
-       es.item_id = fi.id AND \
-       es.user_id = $1 WHERE \
-       es.read_at IS NULL GROUP BY \
-       fi.feed_id ORDER BY fi.feed_id",
-      quote_ident(schema)
-    );
+    stream.push_from(&mut builder, &items);
 
-    let rows = sqlx::query_as::<_, FeedUnreadCount>(&query)
-            .bind(user_id)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    builder
+      .build_query_scalar::<i64>()
+      .fetch_one(pool)
+      .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let mut builder =
+      QueryBuilder::<Sqlite>::new(select);
+
+    stream.push_from(&mut builder, &items);
 
-    return Ok(Json(rows));
+    builder
+      .build_query_scalar::<i64>()
+      .fetch_one(pool)
+      .await
   }
+  .map_err(|e| {
+    ServerError::new(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      e.to_string()
+    )
+  })
+}
 
-  let pool = state
+/// Per-feed item counts of a stream;
+/// feeds without items are left out.
+pub(crate) async fn stream_feed_counts(
+  state: &AppState,
+  stream: &EntryStream
+) -> Result<
+  Vec<FeedUnreadCount>,
+  ServerError
+> {
+  let items = items_table(state);
+
+  let select =
+    "SELECT fi.feed_id, CAST(COUNT(*) \
+     AS BIGINT) AS unread_count";
+
+  let group = " GROUP BY fi.feed_id \
+               ORDER BY fi.feed_id";
+
+  if let Some(pool) = &state.postgres {
+    let mut builder =
+      QueryBuilder::<Postgres>::new(
+        select
+      );
+
+    stream.push_from(&mut builder, &items);
+
+    builder.push(group);
+
+    builder
+      .build_query_as::<FeedUnreadCount>()
+      .fetch_all(pool)
+      .await
+  } else {
+    let pool = state
         .sqlite
         .as_ref()
         .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
 
-  let rows = sqlx::query_as::<
-    _,
-    FeedUnreadCount
-  >(
-    "SELECT fi.feed_id, COUNT(*) AS \
-     unread_count FROM feed_items fi \
-     JOIN subscriptions s ON \
-     s.feed_id = fi.feed_id AND \
-     s.user_id = ?1 LEFT JOIN \
-     entry_states es ON es.item_id = \
-     fi.id AND es.user_id = ?1 WHERE \
-     es.read_at IS NULL GROUP BY \
-     fi.feed_id ORDER BY fi.feed_id"
-  )
-  .bind(user_id)
-  .fetch_all(pool)
-  .await
+    let mut builder =
+      QueryBuilder::<Sqlite>::new(select);
+
+    stream.push_from(&mut builder, &items);
+
+    builder.push(group);
+
+    builder
+      .build_query_as::<FeedUnreadCount>()
+      .fetch_all(pool)
+      .await
+  }
   .map_err(|e| {
     ServerError::new(
       StatusCode::INTERNAL_SERVER_ERROR,
       e.to_string()
     )
-  })?;
-
-  Ok(Json(rows))
+  })
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -251,9 +291,9 @@ pub async fn feed_entry_counts(
     "SELECT f.id AS feed_id, \
      COUNT(fi.id) AS total_count, \
      COALESCE(SUM(CASE WHEN \
-     es.read_at_ms IS NULL AND fi.id \
-     IS NOT NULL THEN 1 ELSE 0 END), \
-     0) AS unread_count, \
+     es.read_at IS NULL AND fi.id IS \
+     NOT NULL THEN 1 ELSE 0 END), 0) \
+     AS unread_count, \
      MAX(fi.published_at_ms) AS \
      last_published_at_ms FROM feeds \
      f LEFT JOIN feed_items fi ON \
@@ -7,13 +7,35 @@ use axum::http::{
   HeaderMap,
   StatusCode
 };
+use pulsewire_core::domain::stream::StreamScope;
+use sqlx::{
+  Postgres,
+  QueryBuilder,
+  Sqlite
+};
 
 use crate::app_state::AppState;
-use crate::auth::auth_user_id;
-use crate::db::quote_ident;
+use crate::auth::{
+  auth_user_id,
+  is_admin
+};
+use crate::entry_stream::{
+  EntryStream,
+  items_table
+};
 use crate::errors::ServerError;
-use crate::models::EntryDetail;
+use crate::models::{
+  EntryChanges,
+  EntryDetail
+};
+use crate::pagination::{
+  POSTGRES_ITEMS,
+  SQLITE_ITEMS
+};
 
+/// One entry of the user's stream:
+/// their subscriptions plus what they
+/// starred, or every feed for admins.
 pub async fn entry_detail(
   State(state): State<AppState>,
   headers: HeaderMap,
@@ -26,13 +48,49 @@ pub async fn entry_detail(
     auth_user_id(&state, &headers)
       .await?;
 
-  if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
+  let scope = if is_admin(
+    &state, user_id
+  )
+  .await?
+  {
+    StreamScope::All
+  } else {
+    StreamScope::SubscriptionsOrStarred
+  };
+
+  let mut stream =
+    EntryStream::new(user_id, scope);
+
+  stream.ids = Some(vec![item_id]);
 
-    let query = format!(
+  stream_details(&state, &stream, 1)
+    .await?
+    .pop()
+    .map(Json)
+    .ok_or_else(|| {
+      ServerError::new(
+        StatusCode::NOT_FOUND,
+        "entry not found"
+      )
+    })
+}
+
+/// The newest `limit` entries of a
+/// stream in full.
+pub(crate) async fn stream_details(
+  state: &AppState,
+  stream: &EntryStream,
+  limit: i64
+) -> Result<Vec<EntryDetail>, ServerError>
+{
+  let items = items_table(state);
+
+  let mut rows = if let Some(pool) =
+    &state.postgres
+  {
+    let mut builder = QueryBuilder::<
+      Postgres
+    >::new(
       "SELECT fi.id, fi.feed_id, \
        fi.title, fi.link, fi.guid, \
        CAST(EXTRACT(EPOCH FROM \
@@ -40,59 +98,96 @@ pub async fn entry_detail(
        BIGINT) AS published_at_ms, \
        fi.category, fi.description, \
        fi.summary, (es.read_at IS NOT \
-       NULL) AS is_read FROM \
-       {}.feed_items fi LEFT JOIN \
-       entry_states es ON es.item_id \
-       = fi.id AND es.user_id = $1 \
-       WHERE fi.id = $2",
-      quote_ident(schema)
+       NULL) AS is_read, fi.diff"
     );
 
-    let row = sqlx::query_as::<_, EntryDetail>(&query)
-            .bind(user_id)
-            .bind(item_id)
-            .fetch_optional(pool)
-            .await
-            .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-            .ok_or_else(|| ServerError::new(StatusCode::NOT_FOUND, "entry not found"))?;
+    stream.push_from(&mut builder, &items);
 
-    return Ok(Json(row));
-  }
+    builder.push(format!(
+      " ORDER BY {} DESC, fi.id DESC \
+       LIMIT ",
+      POSTGRES_ITEMS.key
+    ));
+    builder.push_bind(limit);
 
-  let pool = state
+    builder
+      .build_query_as::<EntryDetail>()
+      .fetch_all(pool)
+      .await
+  } else {
+    let pool = state
         .sqlite
         .as_ref()
         .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
 
-  let row =
-    sqlx::query_as::<_, EntryDetail>(
+    let mut builder = QueryBuilder::<
+      Sqlite
+    >::new(
       "SELECT fi.id, fi.feed_id, \
        fi.title, fi.link, fi.guid, \
        fi.published_at_ms, \
        fi.category, fi.description, \
        fi.summary, (es.read_at IS NOT \
-       NULL) AS is_read FROM \
-       feed_items fi LEFT JOIN \
-       entry_states es ON es.item_id \
-       = fi.id AND es.user_id = ?1 \
-       WHERE fi.id = ?2"
-    )
-    .bind(user_id)
-    .bind(item_id)
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-      ServerError::new(
+       NULL) AS is_read, fi.diff"
+    );
+
+    stream.push_from(&mut builder, &items);
+
+    builder.push(format!(
+      " ORDER BY {} DESC, fi.id DESC \
+       LIMIT ",
+      SQLITE_ITEMS.key
+    ));
+    builder.push_bind(limit);
+
+    builder
+      .build_query_as::<EntryDetail>()
+      .fetch_all(pool)
+      .await
+  }
+  .map_err(|e| {
+    ServerError::new(
       StatusCode::INTERNAL_SERVER_ERROR,
-      e.to_string(),
+      e.to_string()
     )
-    })?
-    .ok_or_else(|| {
-      ServerError::new(
-        StatusCode::NOT_FOUND,
-        "entry not found"
-      )
-    })?;
+  })?;
+
+  for row in &mut rows {
+    row.changes = row
+      .diff
+      .as_deref()
+      .map(parse_changes);
+  }
+
+  Ok(rows)
+}
+
+/// Splits a stored watch diff into its
+/// `+ ` added and `- ` removed lines;
+/// a `~ ` line marks a truncated diff.
+fn parse_changes(
+  diff: &str
+) -> EntryChanges {
+  let mut changes =
+    EntryChanges::default();
+
+  for line in diff.lines() {
+    if let Some(text) =
+      line.strip_prefix("+ ")
+    {
+      changes
+        .added
+        .push(text.to_string());
+    } else if let Some(text) =
+      line.strip_prefix("- ")
+    {
+      changes
+        .removed
+        .push(text.to_string());
+    } else if line.starts_with("~ ") {
+      changes.truncated = true;
+    }
+  }
 
-  Ok(Json(row))
+  changes
 }
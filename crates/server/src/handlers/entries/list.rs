@@ -16,13 +16,25 @@ use sqlx::{
 
 use crate::app_state::AppState;
 use crate::auth::auth_user_id;
-use crate::db::quote_ident;
+use crate::entry_stream::{
+  EntryStream,
+  Marks,
+  items_table
+};
 use crate::errors::ServerError;
+use crate::filter_rules::run_new_items;
 use crate::models::{
   EntryListQuery,
   EntryListResponse,
   EntrySummary
 };
+use crate::pagination::{
+  DATED_SORTS,
+  POSTGRES_ITEMS,
+  Page,
+  SQLITE_ITEMS,
+  item_cut
+};
 
 pub async fn list_entries(
   State(state): State<AppState>,
@@ -36,234 +48,167 @@ pub async fn list_entries(
     auth_user_id(&state, &headers)
       .await?;
 
-  let limit =
-    query.limit.unwrap_or(50).min(200)
-      as i64;
+  run_new_items(&state, user_id)
+    .await?;
+
+  let page = Page::new(
+    &state,
+    query.sort.as_deref(),
+    query.cursor.as_deref(),
+    query.limit,
+    DATED_SORTS
+  )?;
+
+  let mut stream =
+    EntryStream::from_params(
+      &state,
+      user_id,
+      query.scope.as_deref(),
+      query.read.as_deref(),
+      Marks::from_params(
+        query.starred,
+        query.queued,
+        query.label
+      )
+    )
+    .await?;
 
-  let offset =
-    query.offset.unwrap_or(0) as i64;
+  stream.feed_id = query.feed_id;
+  stream.since = query.since;
 
-  let read_filter =
-    query.read.as_deref();
+  stream_entries(&state, &stream, &page)
+    .await
+}
 
-  let feed_filter =
-    query.feed_id.as_deref();
+pub async fn list_feed_entries(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(feed_id): AxumPath<String>,
+  Query(query): Query<EntryListQuery>
+) -> Result<
+  Json<EntryListResponse>,
+  ServerError
+> {
+  let mut query = query;
+
+  query.feed_id = Some(feed_id);
 
-  let since = query.since;
+  list_entries(
+    State(state),
+    headers,
+    Query(query)
+  )
+  .await
+}
 
-  if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
+/// One page of a stream's entries.
+pub(crate) async fn stream_entries(
+  state: &AppState,
+  stream: &EntryStream,
+  page: &Page
+) -> Result<
+  Json<EntryListResponse>,
+  ServerError
+> {
+  let items = items_table(state);
 
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
     let mut builder = QueryBuilder::<
       Postgres
     >::new(
-      format!(
       "SELECT fi.id, fi.feed_id, \
        fi.title, fi.link, \
        CAST(EXTRACT(EPOCH FROM \
        fi.published_at) * 1000 AS \
        BIGINT) AS published_at_ms, \
        (es.read_at IS NOT NULL) AS \
-       is_read FROM {}.feed_items fi \
-       LEFT JOIN entry_states es ON \
-       es.item_id = fi.id AND \
-       es.user_id = ",
-      quote_ident(schema)
-    )
+       is_read"
     );
 
-    builder.push_bind(user_id);
-
-    builder.push(" WHERE 1=1");
-
-    if let Some(filter) = read_filter {
-      match filter {
-        | "read" => {
-          builder.push(
-            " AND es.read_at IS NOT \
-             NULL"
-          );
-        }
-        | "unread" => {
-          builder.push(
-            " AND es.read_at IS NULL"
-          );
-        }
-        | "all" => {}
-        | other => {
-          return Err(ServerError::new(
-            StatusCode::BAD_REQUEST,
-            format!(
-              "invalid read filter: \
-               {other}"
-            )
-          ));
-        }
-      }
-    }
-
-    if let Some(feed_id) = feed_filter {
-      builder
-        .push(" AND fi.feed_id = ");
-
-      builder.push_bind(feed_id);
-    }
+    stream.push_mark_columns(&mut builder);
+    stream.push_from(&mut builder, &items);
 
-    if let Some(since_id) = since {
-      builder.push(" AND fi.id > ");
+    page.push_keyset(
+      &mut builder,
+      &POSTGRES_ITEMS,
+      true
+    )?;
 
-      builder.push_bind(since_id);
-    }
-
-    builder.push(
-      " ORDER BY fi.id DESC LIMIT "
-    );
-
-    builder.push_bind(limit);
-
-    builder.push(" OFFSET ");
-
-    builder.push_bind(offset);
-
-    let rows = builder
-            .build_query_as::<EntrySummary>()
-            .fetch_all(pool)
-            .await
-            .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let next_cursor = rows
-      .iter()
-      .map(|row| row.id)
-      .max();
-
-    let next_offset = if rows.is_empty()
-    {
-      None
-    } else {
-      Some(offset + rows.len() as i64)
-    };
-
-    return Ok(Json(
-      EntryListResponse {
-        items: rows,
-        next_cursor,
-        next_offset,
-        since
-      }
+    builder.push(format!(
+      " ORDER BY {key} {order}, fi.id \
+       {order} LIMIT ",
+      key = POSTGRES_ITEMS.key,
+      order = page.order()
     ));
-  }
 
-  let pool = state
+    builder
+      .push_bind(page.fetch_limit());
+
+    builder
+      .build_query_as::<EntrySummary>()
+      .fetch_all(pool)
+      .await
+  } else {
+    let pool = state
         .sqlite
         .as_ref()
         .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
 
-  let mut builder = QueryBuilder::<
-    Sqlite
-  >::new(
-    "SELECT fi.id, fi.feed_id, \
-     fi.title, fi.link, \
-     fi.published_at_ms, (es.read_at \
-     IS NOT NULL) AS is_read FROM \
-     feed_items fi LEFT JOIN \
-     entry_states es ON es.item_id = \
-     fi.id AND es.user_id = "
-  );
-
-  builder.push_bind(user_id);
-
-  builder.push(" WHERE 1=1");
+    let mut builder = QueryBuilder::<
+      Sqlite
+    >::new(
+      "SELECT fi.id, fi.feed_id, \
+       fi.title, fi.link, \
+       fi.published_at_ms, \
+       (es.read_at IS NOT NULL) AS \
+       is_read"
+    );
 
-  if let Some(filter) = read_filter {
-    match filter {
-      | "read" => {
-        builder.push(
-          " AND es.read_at IS NOT NULL"
-        );
-      }
-      | "unread" => {
-        builder.push(
-          " AND es.read_at IS NULL"
-        );
-      }
-      | "all" => {}
-      | other => {
-        return Err(ServerError::new(
-          StatusCode::BAD_REQUEST,
-          format!(
-            "invalid read filter: \
-             {other}"
-          )
-        ));
-      }
-    }
-  }
+    stream.push_mark_columns(&mut builder);
+    stream.push_from(&mut builder, &items);
 
-  if let Some(feed_id) = feed_filter {
-    builder.push(" AND fi.feed_id = ");
+    page.push_keyset(
+      &mut builder,
+      &SQLITE_ITEMS,
+      true
+    )?;
 
-    builder.push_bind(feed_id);
-  }
+    builder.push(format!(
+      " ORDER BY {key} {order}, fi.id \
+       {order} LIMIT ",
+      key = SQLITE_ITEMS.key,
+      order = page.order()
+    ));
 
-  if let Some(since_id) = since {
-    builder.push(" AND fi.id > ");
+    builder
+      .push_bind(page.fetch_limit());
 
-    builder.push_bind(since_id);
+    builder
+      .build_query_as::<EntrySummary>()
+      .fetch_all(pool)
+      .await
   }
+  .map_err(|e| {
+    ServerError::new(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      e.to_string()
+    )
+  })?;
 
-  builder.push(
-    " ORDER BY fi.id DESC LIMIT "
-  );
-
-  builder.push_bind(limit);
-
-  builder.push(" OFFSET ");
-
-  builder.push_bind(offset);
-
-  let rows = builder
-        .build_query_as::<EntrySummary>()
-        .fetch_all(pool)
-        .await
-        .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-  let next_cursor =
-    rows.iter().map(|row| row.id).max();
-
-  let next_offset = if rows.is_empty() {
-    None
-  } else {
-    Some(offset + rows.len() as i64)
-  };
+  let paged =
+    page.finish(rows, |row| {
+      item_cut(
+        row.published_at_ms,
+        row.id
+      )
+    });
 
   Ok(Json(EntryListResponse {
-    items: rows,
-    next_cursor,
-    next_offset,
-    since
+    items:       paged.items,
+    next_cursor: paged.next_cursor,
+    prev_cursor: paged.prev_cursor,
+    since:       stream.since
   }))
 }
-
-pub async fn list_feed_entries(
-  State(state): State<AppState>,
-  headers: HeaderMap,
-  AxumPath(feed_id): AxumPath<String>,
-  Query(query): Query<EntryListQuery>
-) -> Result<
-  Json<EntryListResponse>,
-  ServerError
-> {
-  let mut query = query;
-
-  query.feed_id = Some(feed_id);
-
-  list_entries(
-    State(state),
-    headers,
-    Query(query)
-  )
-  .await
-}
@@ -0,0 +1,417 @@
+//! Per-entry marks: stars, the
+//! read-later queue and labels.
+
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+
+use crate::app_state::AppState;
+use crate::auth::auth_user_id;
+use crate::entry_stream::items_table;
+use crate::errors::ServerError;
+use crate::models::LabelRow;
+
+/// Marks kept as one row per user and
+/// item.
+#[derive(Clone, Copy)]
+enum Mark {
+  Star,
+  Queue
+}
+
+impl Mark {
+  fn table(self) -> &'static str {
+    match self {
+      | Mark::Star => "entry_stars",
+      | Mark::Queue => "entry_queue"
+    }
+  }
+}
+
+pub async fn star_entry(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(item_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  set_mark(
+    &state,
+    &headers,
+    item_id,
+    Mark::Star,
+    true
+  )
+  .await
+}
+
+pub async fn unstar_entry(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(item_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  set_mark(
+    &state,
+    &headers,
+    item_id,
+    Mark::Star,
+    false
+  )
+  .await
+}
+
+pub async fn queue_entry(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(item_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  set_mark(
+    &state,
+    &headers,
+    item_id,
+    Mark::Queue,
+    true
+  )
+  .await
+}
+
+pub async fn unqueue_entry(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(item_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  set_mark(
+    &state,
+    &headers,
+    item_id,
+    Mark::Queue,
+    false
+  )
+  .await
+}
+
+async fn set_mark(
+  state: &AppState,
+  headers: &HeaderMap,
+  item_id: i64,
+  mark: Mark,
+  on: bool
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(state, headers)
+      .await?;
+
+  let table = mark.table();
+
+  if on {
+    ensure_entry(
+      state, user_id, item_id
+    )
+    .await?;
+  }
+
+  if let Some(pool) = &state.postgres {
+    let sql = if on {
+      format!(
+        "INSERT INTO {table} \
+         (user_id, item_id, \
+         created_at) VALUES ($1, $2, \
+         NOW()) ON CONFLICT DO NOTHING"
+      )
+    } else {
+      format!(
+        "DELETE FROM {table} WHERE \
+         user_id = $1 AND item_id = $2"
+      )
+    };
+
+    sqlx::query(&sql)
+      .bind(user_id)
+      .bind(item_id)
+      .execute(pool)
+      .await
+      .map_err(query_error)?;
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let sql = if on {
+      format!(
+        "INSERT INTO {table} \
+         (user_id, item_id, \
+         created_at) VALUES (?1, ?2, \
+         datetime('now')) ON CONFLICT \
+         DO NOTHING"
+      )
+    } else {
+      format!(
+        "DELETE FROM {table} WHERE \
+         user_id = ?1 AND item_id = ?2"
+      )
+    };
+
+    sqlx::query(&sql)
+      .bind(user_id)
+      .bind(item_id)
+      .execute(pool)
+      .await
+      .map_err(query_error)?;
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn entry_labels(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(item_id): AxumPath<i64>
+) -> Result<
+  Json<Vec<LabelRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, LabelRow>(
+      "SELECT l.id, l.name FROM \
+       labels l JOIN entry_labels el \
+       ON el.label_id = l.id WHERE \
+       l.user_id = $1 AND el.item_id \
+       = $2 ORDER BY l.name"
+    )
+    .bind(user_id)
+    .bind(item_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, LabelRow>(
+      "SELECT l.id, l.name FROM \
+       labels l JOIN entry_labels el \
+       ON el.label_id = l.id WHERE \
+       l.user_id = ?1 AND el.item_id \
+       = ?2 ORDER BY l.name"
+    )
+    .bind(user_id)
+    .bind(item_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(Json(rows))
+}
+
+pub async fn add_entry_label(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath((item_id, label_id)): AxumPath<
+    (i64, i64)
+  >
+) -> Result<StatusCode, ServerError> {
+  set_entry_label(
+    &state, &headers, item_id,
+    label_id, true
+  )
+  .await
+}
+
+pub async fn delete_entry_label(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath((item_id, label_id)): AxumPath<
+    (i64, i64)
+  >
+) -> Result<StatusCode, ServerError> {
+  set_entry_label(
+    &state, &headers, item_id,
+    label_id, false
+  )
+  .await
+}
+
+async fn set_entry_label(
+  state: &AppState,
+  headers: &HeaderMap,
+  item_id: i64,
+  label_id: i64,
+  on: bool
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(state, headers)
+      .await?;
+
+  if on {
+    ensure_entry(
+      state, user_id, item_id
+    )
+    .await?;
+  }
+
+  // Only the owner's labels match, so
+  // other users' labels read as
+  // missing.
+  let owned = if let Some(pool) =
+    &state.postgres
+  {
+    let owned =
+      sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM labels WHERE \
+         id = $1 AND user_id = $2"
+      )
+      .bind(label_id)
+      .bind(user_id)
+      .fetch_optional(pool)
+      .await
+      .map_err(query_error)?
+      .is_some();
+
+    let sql = if on {
+      "INSERT INTO entry_labels \
+       (label_id, item_id, created_at) \
+       VALUES ($1, $2, NOW()) ON \
+       CONFLICT DO NOTHING"
+    } else {
+      "DELETE FROM entry_labels WHERE \
+       label_id = $1 AND item_id = $2"
+    };
+
+    if owned {
+      sqlx::query(sql)
+        .bind(label_id)
+        .bind(item_id)
+        .execute(pool)
+        .await
+        .map_err(query_error)?;
+    }
+
+    owned
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let owned =
+      sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM labels WHERE \
+         id = ?1 AND user_id = ?2"
+      )
+      .bind(label_id)
+      .bind(user_id)
+      .fetch_optional(pool)
+      .await
+      .map_err(query_error)?
+      .is_some();
+
+    let sql = if on {
+      "INSERT INTO entry_labels \
+       (label_id, item_id, created_at) \
+       VALUES (?1, ?2, \
+       datetime('now')) ON CONFLICT DO \
+       NOTHING"
+    } else {
+      "DELETE FROM entry_labels WHERE \
+       label_id = ?1 AND item_id = ?2"
+    };
+
+    if owned {
+      sqlx::query(sql)
+        .bind(label_id)
+        .bind(item_id)
+        .execute(pool)
+        .await
+        .map_err(query_error)?;
+    }
+
+    owned
+  };
+
+  if !owned {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "label not found"
+    ));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// 404s unless the feed item belongs
+/// to one of the user's
+/// subscriptions, as in their entry
+/// stream.
+async fn ensure_entry(
+  state: &AppState,
+  user_id: i64,
+  item_id: i64
+) -> Result<(), ServerError> {
+  let (item, user) =
+    if state.postgres.is_some() {
+      ("$1", "$2")
+    } else {
+      ("?1", "?2")
+    };
+
+  let sql = format!(
+    "SELECT id FROM {} WHERE id = \
+     {item} AND feed_id IN (SELECT \
+     feed_id FROM subscriptions WHERE \
+     user_id = {user})",
+    items_table(state)
+  );
+
+  let found = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, i64>(&sql)
+    .bind(item_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, i64>(&sql)
+    .bind(item_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  if found.is_none() {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "entry not found"
+    ));
+  }
+
+  Ok(())
+}
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e.to_string()
+  )
+}
@@ -2,6 +2,7 @@ mod batch;
 mod counts;
 mod detail;
 mod list;
+mod marks;
 mod read_state;
 mod search;
 
@@ -14,14 +15,30 @@ pub use counts::{
   feed_unread_counts,
   unread_count
 };
+pub(crate) use counts::{
+  stream_feed_counts,
+  stream_item_count
+};
 pub use detail::entry_detail;
+pub(crate) use detail::stream_details;
+pub(crate) use list::stream_entries;
 pub use list::{
   list_entries,
   list_feed_entries
 };
+pub use marks::{
+  add_entry_label,
+  delete_entry_label,
+  entry_labels,
+  queue_entry,
+  star_entry,
+  unqueue_entry,
+  unstar_entry
+};
 pub use read_state::{
   mark_read,
   mark_unread,
   read_state
 };
 pub use search::search_entries;
+pub(crate) use search::search_hits;
@@ -8,8 +8,12 @@ use axum::http::{
 };
 
 use crate::app_state::AppState;
-use crate::auth::auth_user_id;
+use crate::auth::{
+  auth_user_id,
+  session_id
+};
 use crate::errors::ServerError;
+use crate::realtime::ReadChange;
 
 pub async fn read_state(
   State(state): State<AppState>,
@@ -95,6 +99,16 @@ pub async fn mark_read(
         .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
   }
 
+  state.realtime.read_changed(
+    ReadChange {
+      user_id,
+      session: session_id(&headers)
+        .ok(),
+      item_ids: vec![item_id],
+      read: true
+    }
+  );
+
   Ok(StatusCode::NO_CONTENT)
 }
 
@@ -128,5 +142,15 @@ pub async fn mark_unread(
             .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
   }
 
+  state.realtime.read_changed(
+    ReadChange {
+      user_id,
+      session: session_id(&headers)
+        .ok(),
+      item_ids: vec![item_id],
+      read: false
+    }
+  );
+
   Ok(StatusCode::NO_CONTENT)
 }
@@ -1,3 +1,10 @@
+//! Ranked full-text search over feed
+//! items: FTS5 with bm25 on SQLite, the
+//! weighted `search_vector` with
+//! `ts_rank_cd` on Postgres. See
+//! `pulsewire_core::domain::search`
+//! for the query syntax.
+
 use axum::Json;
 use axum::extract::{
   Query,
@@ -7,34 +14,89 @@ use axum::http::{
   HeaderMap,
   StatusCode
 };
+use pulsewire_core::domain::cursor::{
+  SortKey,
+  SortOrder
+};
+use pulsewire_core::domain::search::{
+  ParsedSearch,
+  parse_search
+};
 use sqlx::{
+  PgPool,
   Postgres,
   QueryBuilder,
-  Sqlite
+  Sqlite,
+  SqlitePool
 };
 
 use crate::app_state::AppState;
 use crate::auth::auth_user_id;
 use crate::db::quote_ident;
+use crate::entry_stream::{
+  EntryStream,
+  Marks
+};
 use crate::errors::ServerError;
+use crate::filter_rules::run_new_items;
 use crate::models::{
-  EntryListResponse,
-  EntrySummary,
-  SearchQuery
+  SearchEntry,
+  SearchQuery,
+  SearchResponse
+};
+use crate::pagination::{
+  Keyset,
+  Page,
+  SEARCH_SORTS,
+  item_cut
+};
+
+const HEADLINE_OPTIONS: &str =
+  "StartSel=<mark>, StopSel=</mark>, \
+   MaxWords=24, MinWords=8, \
+   MaxFragments=2";
+
+/// Hits carry their sort key as
+/// `sort_key`: the rank, or the
+/// publication time.
+const SQLITE_HITS: Keyset = Keyset {
+  key:  "hit.sort_key",
+  bind: ("", ""),
+  id:   "hit.id"
 };
 
+const POSTGRES_RANKED_HITS: Keyset =
+  Keyset {
+    key:  "hit.sort_key",
+    bind: ("", ""),
+    id:   "hit.id"
+  };
+
+const POSTGRES_DATED_HITS: Keyset =
+  Keyset {
+    key:  "hit.sort_key",
+    bind: (
+      "TIMESTAMPTZ 'epoch' + ",
+      " * INTERVAL '1 millisecond'"
+    ),
+    id:   "hit.id"
+  };
+
 pub async fn search_entries(
   State(state): State<AppState>,
   headers: HeaderMap,
   Query(query): Query<SearchQuery>
 ) -> Result<
-  Json<EntryListResponse>,
+  Json<SearchResponse>,
   ServerError
 > {
   let user_id =
     auth_user_id(&state, &headers)
       .await?;
 
+  run_new_items(&state, user_id)
+    .await?;
+
   let q = query.q.trim();
 
   if q.is_empty() {
@@ -44,240 +106,354 @@ pub async fn search_entries(
     ));
   }
 
-  let limit =
-    query.limit.unwrap_or(50).min(200)
-      as i64;
-
-  let offset =
-    query.offset.unwrap_or(0) as i64;
-
-  let read_filter =
-    query.read.as_deref();
-
-  let feed_filter =
-    query.feed_id.as_deref();
+  let parsed = parse_search(q)
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        e
+      )
+    })?;
+
+  let page = Page::new(
+    &state,
+    query.sort.as_deref(),
+    query.cursor.as_deref(),
+    query.limit,
+    SEARCH_SORTS
+  )?;
+
+  let mut stream =
+    EntryStream::from_params(
+      &state,
+      user_id,
+      query.scope.as_deref(),
+      query.read.as_deref(),
+      Marks::from_params(
+        query.starred,
+        query.queued,
+        query.label
+      )
+    )
+    .await?;
+
+  stream.feed_id = query.feed_id;
+
+  let rows = search_hits(
+    &state, &parsed, &stream, &page
+  )
+  .await?;
+
+  let relevance =
+    page.sort == SortOrder::Relevance;
+
+  let paged =
+    page.finish(rows, |row| {
+      if relevance {
+        (
+          SortKey::Rank(row.rank),
+          row.id.to_string()
+        )
+      } else {
+        item_cut(
+          row.published_at_ms,
+          row.id
+        )
+      }
+    });
 
-  let pattern = format!("%{}%", q);
+  Ok(Json(SearchResponse {
+    items:       paged.items,
+    next_cursor: paged.next_cursor,
+    prev_cursor: paged.prev_cursor
+  }))
+}
 
+/// One page of a search's hits within
+/// a stream.
+pub(crate) async fn search_hits(
+  state: &AppState,
+  parsed: &ParsedSearch,
+  stream: &EntryStream,
+  page: &Page
+) -> Result<Vec<SearchEntry>, ServerError>
+{
   if let Some(pool) = &state.postgres {
     let schema = state
       .fetcher_schema
       .as_deref()
       .unwrap_or("fetcher");
 
-    let mut builder = QueryBuilder::<
-      Postgres
-    >::new(
-      format!(
-      "SELECT fi.id, fi.feed_id, \
-       fi.title, fi.link, \
-       CAST(EXTRACT(EPOCH FROM \
-       fi.published_at) * 1000 AS \
-       BIGINT) AS published_at_ms, \
-       (es.read_at IS NOT NULL) AS \
-       is_read FROM {}.feed_items fi \
-       LEFT JOIN entry_states es ON \
-       es.item_id = fi.id AND \
-       es.user_id = ",
-      quote_ident(schema)
+    return search_postgres(
+      pool, schema, parsed, stream,
+      page
     )
-    );
+    .await;
+  }
 
-    builder.push_bind(user_id);
+  let pool = state
+    .sqlite
+    .as_ref()
+    .ok_or_else(|| {
+      ServerError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "database pool missing"
+      )
+    })?;
+
+  search_sqlite(
+    pool, parsed, stream, page
+  )
+  .await
+}
 
-    builder.push(" WHERE (");
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e.to_string()
+  )
+}
 
-    builder.push("fi.title ILIKE ");
+async fn search_sqlite(
+  pool: &SqlitePool,
+  parsed: &ParsedSearch,
+  stream: &EntryStream,
+  page: &Page
+) -> Result<Vec<SearchEntry>, ServerError>
+{
+  let sort_key = if page.sort
+    == SortOrder::Relevance
+  {
+    "-bm25(feed_items_fts, 5.0, 2.0, \
+     1.0)"
+  } else {
+    "COALESCE(fi.published_at_ms, 0)"
+  };
 
-    builder.push_bind(&pattern);
+  let mut builder = QueryBuilder::<
+    Sqlite
+  >::new(format!(
+    "SELECT hit.id, hit.feed_id, \
+     hit.title, hit.link, \
+     hit.published_at_ms, \
+     hit.is_read, hit.is_starred, \
+     hit.is_queued, hit.rank, \
+     hit.snippet FROM (SELECT fi.id, \
+     fi.feed_id, fi.title, fi.link, \
+     fi.published_at_ms, (es.read_at \
+     IS NOT NULL) AS is_read, \
+     -bm25(feed_items_fts, 5.0, 2.0, \
+     1.0) AS rank, \
+     snippet(feed_items_fts, -1, \
+     '<mark>', '</mark>', '…', 16) AS \
+     snippet, {sort_key} AS sort_key"
+  ));
 
-    builder
-      .push(" OR fi.summary ILIKE ");
+  stream
+    .push_mark_columns(&mut builder);
+
+  builder.push(
+    " FROM feed_items_fts JOIN \
+     feed_items fi ON fi.id = \
+     feed_items_fts.rowid LEFT JOIN \
+     entry_states es ON es.item_id = \
+     fi.id AND es.user_id = "
+  );
 
-    builder.push_bind(&pattern);
+  builder.push_bind(stream.user_id);
 
-    builder.push(
-      " OR fi.description ILIKE "
-    );
+  builder.push(
+    " WHERE feed_items_fts MATCH "
+  );
 
-    builder.push_bind(&pattern);
+  builder
+    .push_bind(parsed.fts5_match());
 
-    builder.push(")");
+  stream.push_filters(&mut builder);
 
-    if let Some(filter) = read_filter {
-      match filter {
-        | "read" => {
-          builder.push(
-            " AND es.read_at IS NOT \
-             NULL"
-          );
-        }
-        | "unread" => {
-          builder.push(
-            " AND es.read_at IS NULL"
-          );
-        }
-        | "all" => {}
-        | other => {
-          return Err(ServerError::new(
-            StatusCode::BAD_REQUEST,
-            format!(
-              "invalid read filter: \
-               {other}"
-            )
-          ));
-        }
-      }
+  for (ids, op) in [
+    (&parsed.feed_ids, "IN"),
+    (
+      &parsed.excluded_feed_ids,
+      "NOT IN"
+    )
+  ] {
+    if ids.is_empty() {
+      continue;
     }
 
-    if let Some(feed_id) = feed_filter {
-      builder
-        .push(" AND fi.feed_id = ");
+    builder.push(format!(
+      " AND fi.feed_id {op} ("
+    ));
 
-      builder.push_bind(feed_id);
-    }
+    let mut separated =
+      builder.separated(", ");
 
-    builder.push(
-      " ORDER BY fi.id DESC LIMIT "
-    );
+    for id in ids {
+      separated.push_bind(id);
+    }
 
-    builder.push_bind(limit);
+    separated.push_unseparated(")");
+  }
 
-    builder.push(" OFFSET ");
+  builder.push(") hit WHERE 1=1");
 
-    builder.push_bind(offset);
+  page.push_keyset(
+    &mut builder,
+    &SQLITE_HITS,
+    true
+  )?;
 
-    let rows = builder
-            .build_query_as::<EntrySummary>()
-            .fetch_all(pool)
-            .await
-            .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+  builder.push(format!(
+    " ORDER BY hit.sort_key {order}, \
+     hit.id {order} LIMIT ",
+    order = page.order()
+  ));
 
-    let next_cursor = rows
-      .iter()
-      .map(|row| row.id)
-      .max();
+  builder.push_bind(page.fetch_limit());
 
-    let next_offset = if rows.is_empty()
-    {
-      None
-    } else {
-      Some(offset + rows.len() as i64)
-    };
+  builder
+    .build_query_as::<SearchEntry>()
+    .fetch_all(pool)
+    .await
+    .map_err(query_error)
+}
 
-    return Ok(Json(
-      EntryListResponse {
-        items: rows,
-        next_cursor,
-        next_offset,
-        since: None
-      }
-    ));
-  }
+/// Items are indexed with the text
+/// search configuration of their
+/// language, so each row's query is
+/// parsed with that same
+/// configuration. Snippets are only
+/// built for the returned page.
+async fn search_postgres(
+  pool: &PgPool,
+  schema: &str,
+  parsed: &ParsedSearch,
+  stream: &EntryStream,
+  page: &Page
+) -> Result<Vec<SearchEntry>, ServerError>
+{
+  let schema = quote_ident(schema);
+
+  let (sort_key, keyset) = if page.sort
+    == SortOrder::Relevance
+  {
+    (
+      "CAST(ts_rank_cd(fi.\
+       search_vector, q.query) AS \
+       FLOAT8)",
+      &POSTGRES_RANKED_HITS
+    )
+  } else {
+    (
+      "COALESCE(fi.published_at, \
+       TIMESTAMPTZ 'epoch')",
+      &POSTGRES_DATED_HITS
+    )
+  };
 
-  let pool = state
-        .sqlite
-        .as_ref()
-        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+  let order = page.order();
 
   let mut builder = QueryBuilder::<
-    Sqlite
-  >::new(
-    "SELECT fi.id, fi.feed_id, \
+    Postgres
+  >::new(format!(
+    "SELECT hit.id, hit.feed_id, \
+     hit.title, hit.link, \
+     hit.published_at_ms, \
+     hit.is_read, hit.is_starred, \
+     hit.is_queued, hit.rank, \
+     ts_headline({schema}.\
+     item_search_config(hit.language), \
+     coalesce(hit.summary, \
+     hit.description, hit.title, ''), \
+     hit.query, '{HEADLINE_OPTIONS}') \
+     AS snippet FROM (SELECT * FROM \
+     (SELECT fi.id, fi.feed_id, \
      fi.title, fi.link, \
-     fi.published_at_ms, (es.read_at \
-     IS NOT NULL) AS is_read FROM \
-     feed_items fi LEFT JOIN \
-     entry_states es ON es.item_id = \
-     fi.id AND es.user_id = "
-  );
-
-  builder.push_bind(user_id);
-
-  builder.push(" WHERE (");
-
-  builder.push(
-    "lower(fi.title) LIKE lower("
-  );
-
-  builder.push_bind(&pattern);
+     CAST(EXTRACT(EPOCH FROM \
+     fi.published_at) * 1000 AS \
+     BIGINT) AS published_at_ms, \
+     (es.read_at IS NOT NULL) AS \
+     is_read, CAST(ts_rank_cd(fi.\
+     search_vector, q.query) AS \
+     FLOAT8) AS rank, {sort_key} AS \
+     sort_key, fi.language, \
+     fi.summary, fi.description, \
+     q.query"
+  ));
+
+  stream
+    .push_mark_columns(&mut builder);
+
+  builder.push(format!(
+    " FROM {schema}.feed_items fi \
+     CROSS JOIN LATERAL (SELECT \
+     to_tsquery({schema}.\
+     item_search_config(fi.language), "
+  ));
+  builder.push_bind(parsed.tsquery());
+  builder.push(")");
 
   builder.push(
-    ") OR lower(fi.summary) LIKE \
-     lower("
+    " AS query) q LEFT JOIN \
+     entry_states es ON es.item_id = \
+     fi.id AND es.user_id = "
   );
 
-  builder.push_bind(&pattern);
+  builder.push_bind(stream.user_id);
 
   builder.push(
-    ") OR lower(fi.description) LIKE \
-     lower("
+    " WHERE fi.search_vector @@ \
+     q.query"
   );
 
-  builder.push_bind(&pattern);
-
-  builder.push(")");
+  stream.push_filters(&mut builder);
 
-  if let Some(filter) = read_filter {
-    match filter {
-      | "read" => {
-        builder.push(
-          " AND es.read_at IS NOT NULL"
-        );
-      }
-      | "unread" => {
-        builder.push(
-          " AND es.read_at IS NULL"
-        );
-      }
-      | "all" => {}
-      | other => {
-        return Err(ServerError::new(
-          StatusCode::BAD_REQUEST,
-          format!(
-            "invalid read filter: \
-             {other}"
-          )
-        ));
-      }
-    }
+  if !parsed.feed_ids.is_empty() {
+    builder
+      .push(" AND fi.feed_id = ANY(");
+    builder.push_bind(
+      parsed.feed_ids.clone()
+    );
+    builder.push(")");
   }
 
-  if let Some(feed_id) = feed_filter {
-    builder.push(" AND fi.feed_id = ");
-
-    builder.push_bind(feed_id);
+  if !parsed
+    .excluded_feed_ids
+    .is_empty()
+  {
+    builder
+      .push(" AND fi.feed_id <> ALL(");
+    builder.push_bind(
+      parsed.excluded_feed_ids.clone()
+    );
+    builder.push(")");
   }
 
-  builder.push(
-    " ORDER BY fi.id DESC LIMIT "
-  );
+  builder.push(") hit WHERE 1=1");
 
-  builder.push_bind(limit);
+  page.push_keyset(
+    &mut builder,
+    keyset,
+    true
+  )?;
 
-  builder.push(" OFFSET ");
+  builder.push(format!(
+    " ORDER BY hit.sort_key {order}, \
+     hit.id {order} LIMIT "
+  ));
 
-  builder.push_bind(offset);
+  builder.push_bind(page.fetch_limit());
 
-  let rows = builder
-        .build_query_as::<EntrySummary>()
-        .fetch_all(pool)
-        .await
-        .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+  builder.push(format!(
+    ") hit ORDER BY hit.sort_key \
+     {order}, hit.id {order}"
+  ));
 
-  let next_cursor =
-    rows.iter().map(|row| row.id).max();
-
-  let next_offset = if rows.is_empty() {
-    None
-  } else {
-    Some(offset + rows.len() as i64)
-  };
-
-  Ok(Json(EntryListResponse {
-    items: rows,
-    next_cursor,
-    next_offset,
-    since: None
-  }))
+  builder
+    .build_query_as::<SearchEntry>()
+    .fetch_all(pool)
+    .await
+    .map_err(query_error)
 }
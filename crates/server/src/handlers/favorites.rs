@@ -8,21 +8,43 @@ use axum::http::{
   HeaderMap,
   StatusCode
 };
+use pulsewire_core::domain::cursor::SortKey;
+use pulsewire_core::domain::stream::{
+  ReadFilter,
+  StreamScope
+};
+use sqlx::{
+  Postgres,
+  QueryBuilder,
+  Sqlite
+};
 
 use crate::app_state::AppState;
 use crate::auth::auth_user_id;
 use crate::db::quote_ident;
+use crate::entry_stream::EntryStream;
 use crate::errors::{
   ServerError,
   map_db_error
 };
+use crate::filter_rules::run_new_items;
+use crate::handlers::entries::{
+  stream_feed_counts,
+  stream_item_count
+};
 use crate::models::{
   FavoriteListQuery,
+  FavoriteListResponse,
   FavoriteRequest,
   FavoriteUnreadCount,
   FeedSummary,
   UnreadCountResponse
 };
+use crate::pagination::{
+  DATED_SORTS,
+  Keyset,
+  Page
+};
 
 #[derive(Debug, sqlx::FromRow)]
 struct FeedSummaryRow {
@@ -31,9 +53,37 @@ struct FeedSummaryRow {
   domain:            String,
   category:          String,
   base_poll_seconds: i64,
-  tags:              Option<String>
+  tags:              Option<String>,
+  created_at_ms:     i64
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct FavoriteRow {
+  #[sqlx(flatten)]
+  feed:          FeedSummary,
+  created_at_ms: i64
 }
 
+/// Favorites page on when they were
+/// added, then on feed id.
+const SQLITE_FAVORITES: Keyset =
+  Keyset {
+    key:  "CAST(strftime('%s', \
+           fav.created_at) AS \
+           INTEGER) * 1000",
+    bind: ("", ""),
+    id:   "fav.feed_id"
+  };
+
+const POSTGRES_FAVORITES: Keyset =
+  Keyset {
+    key:  "CAST(FLOOR(EXTRACT(EPOCH \
+           FROM fav.created_at) * \
+           1000) AS BIGINT)",
+    bind: ("", ""),
+    id:   "fav.feed_id"
+  };
+
 fn parse_tags(
   raw: Option<String>
 ) -> Option<Vec<String>> {
@@ -59,64 +109,14 @@ pub async fn favorites_unread_count(
     auth_user_id(&state, &headers)
       .await?;
 
-  if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
-
-    let query = format!(
-      "SELECT COUNT(*)::BIGINT FROM \
-       favorites f 
-             JOIN {}.feed_items fi ON \
-       fi.feed_id = f.feed_id 
-             LEFT JOIN entry_states es \
-       ON es.item_id = fi.id AND \
-       es.user_id = $1 
-             WHERE f.user_id = $1 AND \
-       es.read_at IS NULL",
-      quote_ident(schema)
-    );
-
-    let count = sqlx::query_scalar::<_, i64>(&query)
-            .bind(user_id)
-            .fetch_one(pool)
-            .await
-            .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+  run_new_items(&state, user_id)
+    .await?;
 
-    return Ok(Json(
-      UnreadCountResponse {
-        count
-      }
-    ));
-  }
-
-  let pool = state
-        .sqlite
-        .as_ref()
-        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
-
-  let count =
-    sqlx::query_scalar::<_, i64>(
-      "SELECT COUNT(*) FROM favorites \
-       f 
-         JOIN feed_items fi ON \
-       fi.feed_id = f.feed_id 
-         LEFT JOIN entry_states es ON \
-       es.item_id = fi.id AND \
-       es.user_id = ?1 
-         WHERE f.user_id = ?1 AND \
-       es.read_at IS NULL"
-    )
-    .bind(user_id)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
-      ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      e.to_string(),
-    )
-    })?;
+  let count = stream_item_count(
+    &state,
+    &unread_favorites(user_id)
+  )
+  .await?;
 
   Ok(Json(UnreadCountResponse {
     count
@@ -134,71 +134,40 @@ pub async fn favorites_unread_counts(
     auth_user_id(&state, &headers)
       .await?;
 
-  if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
-
-    let query = format!(
-      "SELECT f.feed_id, \
-       COUNT(*)::BIGINT AS \
-       unread_count 
-             FROM favorites f 
-             JOIN {}.feed_items fi ON \
-       fi.feed_id = f.feed_id 
-             LEFT JOIN entry_states es \
-       ON es.item_id = fi.id AND \
-       es.user_id = $1 
-             WHERE f.user_id = $1 AND \
-       es.read_at IS NULL 
-             GROUP BY f.feed_id 
-             ORDER BY f.feed_id",
-      quote_ident(schema)
-    );
+  run_new_items(&state, user_id)
+    .await?;
 
-    let rows = sqlx::query_as::<_, FavoriteUnreadCount>(&query)
-        .bind(user_id)
-        .fetch_all(pool)
-        .await
-        .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+  let rows = stream_feed_counts(
+    &state,
+    &unread_favorites(user_id)
+  )
+  .await?;
+
+  Ok(Json(
+    rows
+      .into_iter()
+      .map(|row| {
+        FavoriteUnreadCount {
+          feed_id:      row.feed_id,
+          unread_count: row
+            .unread_count
+        }
+      })
+      .collect()
+  ))
+}
 
-    return Ok(Json(rows));
-  }
+fn unread_favorites(
+  user_id: i64
+) -> EntryStream {
+  let mut stream = EntryStream::new(
+    user_id,
+    StreamScope::Favorites
+  );
 
-  let pool = state
-        .sqlite
-        .as_ref()
-        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+  stream.read = ReadFilter::Unread;
 
-  let rows = sqlx::query_as::<
-    _,
-    FavoriteUnreadCount
-  >(
-    "SELECT f.feed_id, COUNT(*) AS \
-     unread_count 
-         FROM favorites f 
-         JOIN feed_items fi ON \
-     fi.feed_id = f.feed_id 
-         LEFT JOIN entry_states es ON \
-     es.item_id = fi.id AND \
-     es.user_id = ?1 
-         WHERE f.user_id = ?1 AND \
-     es.read_at IS NULL 
-         GROUP BY f.feed_id 
-         ORDER BY f.feed_id"
-  )
-  .bind(user_id)
-  .fetch_all(pool)
-  .await
-  .map_err(|e| {
-    ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      e.to_string()
-    )
-  })?;
-
-  Ok(Json(rows))
+  stream
 }
 
 pub async fn list_favorites(
@@ -208,19 +177,29 @@ pub async fn list_favorites(
     FavoriteListQuery
   >
 ) -> Result<
-  Json<Vec<FeedSummary>>,
+  Json<FavoriteListResponse>,
   ServerError
 > {
   let user_id =
     auth_user_id(&state, &headers)
       .await?;
 
-  let limit =
-    query.limit.unwrap_or(50).min(200)
-      as i64;
-
-  let offset =
-    query.offset.unwrap_or(0) as i64;
+  let page = Page::new(
+    &state,
+    query.sort.as_deref(),
+    query.cursor.as_deref(),
+    query.limit,
+    DATED_SORTS
+  )?;
+
+  let cut =
+    |created_at_ms: i64,
+     feed_id: &str| {
+      (
+        SortKey::Millis(created_at_ms),
+        feed_id.to_string()
+      )
+    };
 
   if let Some(pool) = &state.postgres {
     let schema = state
@@ -228,27 +207,64 @@ pub async fn list_favorites(
       .as_deref()
       .unwrap_or("fetcher");
 
-    let query = format!(
+    let mut builder = QueryBuilder::<
+      Postgres
+    >::new(
+      format!(
       "SELECT f.id, f.url, f.domain, \
        f.category, \
-       f.base_poll_seconds, f.tags \
-       FROM favorites fav JOIN \
-       {}.feeds f ON f.id = \
-       fav.feed_id WHERE fav.user_id \
-       = $1 ORDER BY fav.created_at \
-       DESC LIMIT $2 OFFSET $3",
+       f.base_poll_seconds, f.tags, \
+       {} AS created_at_ms FROM \
+       favorites fav JOIN {}.feeds f \
+       ON f.id = fav.feed_id WHERE \
+       fav.user_id = ",
+      POSTGRES_FAVORITES.key,
       quote_ident(schema)
+    )
     );
 
-    let rows = sqlx::query_as::<_, FeedSummary>(&query)
-            .bind(user_id)
-            .bind(limit)
-            .bind(offset)
+    builder.push_bind(user_id);
+
+    page.push_keyset(
+      &mut builder,
+      &POSTGRES_FAVORITES,
+      false
+    )?;
+
+    builder.push(format!(
+      " ORDER BY created_at_ms \
+       {order}, fav.feed_id {order} \
+       LIMIT ",
+      order = page.order()
+    ));
+
+    builder
+      .push_bind(page.fetch_limit());
+
+    let rows = builder.build_query_as::<FavoriteRow>()
             .fetch_all(pool)
             .await
             .map_err(|e| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    return Ok(Json(rows));
+    let paged =
+      page.finish(rows, |row| {
+        cut(
+          row.created_at_ms,
+          &row.feed.id
+        )
+      });
+
+    return Ok(Json(
+      FavoriteListResponse {
+        items:       paged
+          .items
+          .into_iter()
+          .map(|row| row.feed)
+          .collect(),
+        next_cursor: paged.next_cursor,
+        prev_cursor: paged.prev_cursor
+      }
+    ));
   }
 
   let pool = state
@@ -256,31 +272,52 @@ pub async fn list_favorites(
         .as_ref()
         .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
 
-  let rows = sqlx::query_as::<
-    _,
-    FeedSummaryRow
-  >(
+  let mut builder = QueryBuilder::<
+    Sqlite
+  >::new(format!(
     "SELECT f.id, f.url, f.domain, \
      f.category, f.base_poll_seconds, \
-     f.tags FROM favorites fav JOIN \
-     feeds f ON f.id = fav.feed_id \
-     WHERE fav.user_id = ?1 ORDER BY \
-     fav.created_at DESC LIMIT ?2 \
-     OFFSET ?3"
-  )
-  .bind(user_id)
-  .bind(limit)
-  .bind(offset)
-  .fetch_all(pool)
-  .await
-  .map_err(|e| {
-    ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      e.to_string()
-    )
-  })?;
+     f.tags, {} AS created_at_ms FROM \
+     favorites fav JOIN feeds f ON \
+     f.id = fav.feed_id WHERE \
+     fav.user_id = ",
+    SQLITE_FAVORITES.key
+  ));
+
+  builder.push_bind(user_id);
+
+  page.push_keyset(
+    &mut builder,
+    &SQLITE_FAVORITES,
+    false
+  )?;
+
+  builder.push(format!(
+    " ORDER BY created_at_ms {order}, \
+     fav.feed_id {order} LIMIT ",
+    order = page.order()
+  ));
+
+  builder.push_bind(page.fetch_limit());
+
+  let rows = builder
+    .build_query_as::<FeedSummaryRow>()
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        e.to_string()
+      )
+    })?;
 
-  let feeds = rows
+  let paged =
+    page.finish(rows, |row| {
+      cut(row.created_at_ms, &row.id)
+    });
+
+  let feeds = paged
+    .items
     .into_iter()
     .map(|row| {
       FeedSummary {
@@ -297,7 +334,11 @@ pub async fn list_favorites(
     })
     .collect::<Vec<_>>();
 
-  Ok(Json(feeds))
+  Ok(Json(FavoriteListResponse {
+    items:       feeds,
+    next_cursor: paged.next_cursor,
+    prev_cursor: paged.prev_cursor
+  }))
 }
 
 pub async fn create_favorite(
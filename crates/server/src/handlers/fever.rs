@@ -0,0 +1,422 @@
+//! The Fever API at `/fever/`. A user
+//! opts in by setting their Fever key
+//! with `PUT /v1/auth/fever`; clients
+//! then send it as `api_key` on every
+//! request. An unknown key is answered
+//! with `auth: 0`, not an error.
+
+use axum::Json;
+use axum::extract::{
+  RawQuery,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use pulsewire_core::domain::compat::fever::{
+  Feed,
+  FeverRequest,
+  FeverResponse,
+  ITEM_LIMIT,
+  Item,
+  ItemMark,
+  Mark,
+  api_key,
+  feed_id,
+  groups,
+  id_list
+};
+use pulsewire_core::domain::stream::{
+  ReadFilter,
+  StreamScope
+};
+
+use crate::app_state::AppState;
+use crate::auth::{
+  auth_user_id,
+  check_password
+};
+use crate::compat;
+use crate::entry_stream::EntryStream;
+use crate::errors::ServerError;
+use crate::filter_rules::run_new_items;
+use crate::models::FeverKeyRequest;
+use crate::realtime::ReadChange;
+
+pub async fn fever(
+  State(state): State<AppState>,
+  RawQuery(query): RawQuery,
+  body: String
+) -> Result<
+  Json<FeverResponse>,
+  ServerError
+> {
+  let request = FeverRequest::parse(
+    &compat::params(query, &body)
+  )
+  .map_err(|e| {
+    ServerError::new(
+      StatusCode::BAD_REQUEST,
+      e
+    )
+  })?;
+
+  let now =
+    chrono::Utc::now().timestamp();
+
+  let user_id = match &request.api_key {
+    | Some(key) => {
+      compat::fever_user_id(&state, key)
+        .await?
+    }
+    | None => None
+  };
+
+  let Some(user_id) = user_id else {
+    return Ok(Json(
+      FeverResponse::new(false, now)
+    ));
+  };
+
+  run_new_items(&state, user_id)
+    .await?;
+
+  let mut response =
+    FeverResponse::new(true, now);
+
+  if let Some(mark) = request.mark {
+    apply_mark(&state, user_id, mark)
+      .await?;
+  }
+
+  if request.groups || request.feeds {
+    let feeds =
+      compat::subscribed_feeds(
+        &state, user_id
+      )
+      .await?;
+
+    let (groups, feeds_groups) =
+      groups(&feeds);
+
+    if request.groups {
+      response.groups = Some(groups);
+    }
+
+    if request.feeds {
+      response.feeds = Some(
+        feeds
+          .iter()
+          .map(Feed::new)
+          .collect()
+      );
+    }
+
+    response.feeds_groups =
+      Some(feeds_groups);
+  }
+
+  if request.favicons {
+    response.favicons =
+      Some(Vec::new());
+  }
+
+  if request.links {
+    response.links = Some(Vec::new());
+  }
+
+  if request.items {
+    let subscriptions =
+      EntryStream::new(
+        user_id,
+        StreamScope::Subscriptions
+      );
+
+    response.total_items = Some(
+      compat::count_items(
+        &state,
+        &subscriptions
+      )
+      .await?
+    );
+
+    let (stream, newest_first) =
+      items_stream(user_id, &request);
+
+    response.items = Some(
+      compat::entries_by_id(
+        &state,
+        &stream,
+        newest_first,
+        ITEM_LIMIT
+      )
+      .await?
+      .iter()
+      .map(Item::new)
+      .collect()
+    );
+  }
+
+  if request.unread_item_ids {
+    let mut stream = EntryStream::new(
+      user_id,
+      StreamScope::Subscriptions
+    );
+    stream.read = ReadFilter::Unread;
+
+    response.unread_item_ids =
+      Some(id_list(
+        &compat::stream_item_ids(
+          &state, &stream
+        )
+        .await?
+      ));
+  }
+
+  if request.saved_item_ids {
+    let mut stream = EntryStream::new(
+      user_id,
+      StreamScope::SubscriptionsOrStarred
+    );
+    stream.marks.starred = true;
+
+    response.saved_item_ids =
+      Some(id_list(
+        &compat::stream_item_ids(
+          &state, &stream
+        )
+        .await?
+      ));
+  }
+
+  Ok(Json(response))
+}
+
+/// Sets the user's Fever key from
+/// their username and password.
+pub async fn set_fever_key(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Json(payload): Json<FeverKeyRequest>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let username =
+    compat::username(&state, user_id)
+      .await?;
+
+  let password =
+    payload.password.trim();
+
+  check_password(
+    &state, &username, password
+  )
+  .await?;
+
+  compat::set_fever_key(
+    &state,
+    user_id,
+    &api_key(&username, password)
+  )
+  .await?;
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_fever_key(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  if !compat::delete_fever_key(
+    &state, user_id
+  )
+  .await?
+  {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "fever key not set"
+    ));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// The items a request reads and
+/// whether newest come first: those
+/// in `with_ids`, after `since_id`
+/// (oldest first), before `max_id`,
+/// or the newest.
+fn items_stream(
+  user_id: i64,
+  request: &FeverRequest
+) -> (EntryStream, bool) {
+  if let Some(ids) = &request.with_ids {
+    // Saved items may be of feeds the
+    // user has since left.
+    let mut stream = EntryStream::new(
+      user_id,
+      StreamScope::SubscriptionsOrStarred
+    );
+    stream.ids = Some(
+      ids
+        .iter()
+        .take(ITEM_LIMIT as usize)
+        .copied()
+        .collect()
+    );
+
+    return (stream, true);
+  }
+
+  let mut stream = EntryStream::new(
+    user_id,
+    StreamScope::Subscriptions
+  );
+
+  if let Some(since_id) =
+    request.since_id
+  {
+    stream.since = Some(since_id);
+
+    return (stream, false);
+  }
+
+  stream.before =
+    request.max_id.filter(|id| *id > 0);
+
+  (stream, true)
+}
+
+async fn apply_mark(
+  state: &AppState,
+  user_id: i64,
+  mark: Mark
+) -> Result<(), ServerError> {
+  let (stream, before) = match mark {
+    | Mark::Item {
+      id,
+      mark
+    } => {
+      return match mark {
+        | ItemMark::Read
+        | ItemMark::Unread => {
+          let read =
+            mark == ItemMark::Read;
+
+          compat::set_read(
+            state,
+            user_id,
+            &[id],
+            read
+          )
+          .await?;
+
+          state.realtime.read_changed(
+            ReadChange {
+              user_id,
+              session: None,
+              item_ids: vec![id],
+              read
+            }
+          );
+
+          Ok(())
+        }
+        | ItemMark::Saved
+        | ItemMark::Unsaved => {
+          compat::set_starred(
+            state,
+            user_id,
+            &[id],
+            mark == ItemMark::Saved
+          )
+          .await
+        }
+      };
+    }
+    | Mark::Feed {
+      id,
+      before
+    } => {
+      let feed =
+        compat::subscribed_feeds(
+          state, user_id
+        )
+        .await?
+        .into_iter()
+        .find(|f| feed_id(&f.id) == id)
+        .ok_or_else(|| {
+          ServerError::new(
+            StatusCode::NOT_FOUND,
+            format!(
+              "feed {id} not found"
+            )
+          )
+        })?;
+
+      let mut stream = EntryStream::new(
+        user_id,
+        StreamScope::Subscriptions
+      );
+      stream.feed_id = Some(feed.id);
+
+      (stream, before)
+    }
+    | Mark::Group {
+      id: 0,
+      before
+    } => {
+      (
+        EntryStream::new(
+          user_id,
+          StreamScope::Subscriptions
+        ),
+        before
+      )
+    }
+    | Mark::Group {
+      id,
+      before
+    } => {
+      (
+        EntryStream::folder(
+          state, user_id, id
+        )
+        .await?,
+        before
+      )
+    }
+  };
+
+  let stream = stream.published(
+    state,
+    None,
+    before.map(|secs| secs * 1000)
+  );
+
+  let item_ids =
+    compat::mark_stream_read(
+      state, &stream
+    )
+    .await?;
+
+  // Fever clients share one key, so
+  // the change goes to every session.
+  state.realtime.read_changed(
+    ReadChange {
+      user_id,
+      session: None,
+      item_ids,
+      read: true
+    }
+  );
+
+  Ok(())
+}
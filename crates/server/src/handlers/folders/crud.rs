@@ -8,6 +8,8 @@ use axum::http::{
   StatusCode
 };
 
+use pulsewire_core::domain::entry_filter::parse_filter;
+
 use crate::app_state::AppState;
 use crate::auth::auth_user_id;
 use crate::errors::{
@@ -33,7 +35,7 @@ pub async fn list_folders(
 
   if let Some(pool) = &state.postgres {
     let rows = sqlx::query_as::<_, FolderRow>(
-      "SELECT id, name FROM folders WHERE user_id = $1 ORDER BY name",
+      "SELECT id, name, filter FROM folders WHERE user_id = $1 ORDER BY name",
     )
     .bind(user_id)
     .fetch_all(pool)
@@ -60,9 +62,9 @@ pub async fn list_folders(
 
   let rows =
     sqlx::query_as::<_, FolderRow>(
-      "SELECT id, name FROM folders \
-       WHERE user_id = ?1 ORDER BY \
-       name"
+      "SELECT id, name, filter FROM \
+       folders WHERE user_id = ?1 \
+       ORDER BY name"
     )
     .bind(user_id)
     .fetch_all(pool)
@@ -98,16 +100,22 @@ pub async fn create_folder(
     ));
   }
 
+  let filter = folder_filter(
+    payload.filter.as_deref()
+  )?;
+
   if let Some(pool) = &state.postgres {
     let row =
       sqlx::query_as::<_, FolderRow>(
         "INSERT INTO folders \
-         (user_id, name, created_at) \
-         VALUES ($1, $2, NOW()) \
-         RETURNING id, name"
+         (user_id, name, filter, \
+         created_at) VALUES ($1, $2, \
+         $3, NOW()) RETURNING id, \
+         name, filter"
       )
       .bind(user_id)
       .bind(name)
+      .bind(filter)
       .fetch_one(pool)
       .await
       .map_err(|e| {
@@ -130,34 +138,24 @@ pub async fn create_folder(
     )
     })?;
 
-  sqlx::query(
-    "INSERT INTO folders (user_id, \
-     name, created_at) VALUES (?1, \
-     ?2, datetime('now'))"
-  )
-  .bind(user_id)
-  .bind(name)
-  .execute(pool)
-  .await
-  .map_err(|e| {
-    map_db_error(
-      e,
-      "folder create failed"
-    )
-  })?;
-
   let row =
     sqlx::query_as::<_, FolderRow>(
-      "SELECT id, name FROM folders \
-       WHERE id = last_insert_rowid()"
+      "INSERT INTO folders (user_id, \
+       name, filter, created_at) \
+       VALUES (?1, ?2, ?3, \
+       datetime('now')) RETURNING id, \
+       name, filter"
     )
+    .bind(user_id)
+    .bind(name)
+    .bind(filter)
     .fetch_one(pool)
     .await
     .map_err(|e| {
-      ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      e.to_string(),
-    )
+      map_db_error(
+        e,
+        "folder create failed"
+      )
     })?;
 
   Ok(Json(row))
@@ -184,16 +182,26 @@ pub async fn update_folder(
     ));
   }
 
+  let filter = payload
+    .filter
+    .as_deref()
+    .map(|raw| folder_filter(Some(raw)))
+    .transpose()?;
+
   let rows = if let Some(pool) =
     &state.postgres
   {
     sqlx::query(
-      "UPDATE folders SET name = $1 \
-       WHERE id = $2 AND user_id = $3"
+      "UPDATE folders SET name = $1, \
+       filter = CASE WHEN $4 THEN $5 \
+       ELSE filter END WHERE id = $2 \
+       AND user_id = $3"
     )
     .bind(name)
     .bind(folder_id)
     .bind(user_id)
+    .bind(filter.is_some())
+    .bind(filter.clone().flatten())
     .execute(pool)
     .await
     .map_err(|e| {
@@ -212,12 +220,16 @@ pub async fn update_folder(
     })?;
 
     sqlx::query(
-      "UPDATE folders SET name = ?1 \
-       WHERE id = ?2 AND user_id = ?3"
+      "UPDATE folders SET name = ?1, \
+       filter = CASE WHEN ?4 THEN ?5 \
+       ELSE filter END WHERE id = ?2 \
+       AND user_id = ?3"
     )
     .bind(name)
     .bind(folder_id)
     .bind(user_id)
+    .bind(filter.is_some())
+    .bind(filter.flatten())
     .execute(pool)
     .await
     .map_err(|e| {
@@ -298,3 +310,29 @@ pub async fn delete_folder(
 
   Ok(StatusCode::NO_CONTENT)
 }
+
+/// A folder's filter: blank means a
+/// folder of feeds, anything else must
+/// parse as an entry filter.
+fn folder_filter(
+  raw: Option<&str>
+) -> Result<Option<String>, ServerError>
+{
+  let Some(filter) = raw
+    .map(str::trim)
+    .filter(|f| !f.is_empty())
+  else {
+    return Ok(None);
+  };
+
+  parse_filter(filter).map_err(
+    |e| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        e
+      )
+    }
+  )?;
+
+  Ok(Some(filter.to_string()))
+}
@@ -8,22 +8,27 @@ use axum::http::{
   HeaderMap,
   StatusCode
 };
-use sqlx::{
-  Postgres,
-  QueryBuilder,
-  Sqlite
-};
+use pulsewire_core::domain::stream::ReadFilter;
 
 use crate::app_state::AppState;
 use crate::auth::auth_user_id;
-use crate::db::quote_ident;
+use crate::entry_stream::EntryStream;
 use crate::errors::ServerError;
+use crate::filter_rules::run_new_items;
+use crate::handlers::entries::stream_entries;
 use crate::models::{
   EntryListResponse,
-  EntrySummary,
   FolderEntriesQuery
 };
+use crate::pagination::{
+  DATED_SORTS,
+  Page
+};
 
+/// Same stream as
+/// `/v1/entries?scope=folder:<id>`;
+/// smart folders go through their
+/// filter.
 pub async fn list_folder_entries(
   State(state): State<AppState>,
   headers: HeaderMap,
@@ -39,232 +44,33 @@ pub async fn list_folder_entries(
     auth_user_id(&state, &headers)
       .await?;
 
-  let limit =
-    query.limit.unwrap_or(50).min(200)
-      as i64;
-
-  let offset =
-    query.offset.unwrap_or(0) as i64;
-
-  let read_filter =
-    query.read.as_deref();
-
-  let since = query.since;
-
-  if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
-
-    let mut builder = QueryBuilder::<
-      Postgres
-    >::new(
-      format!(
-      "SELECT fi.id, fi.feed_id, \
-       fi.title, fi.link, \
-       CAST(EXTRACT(EPOCH FROM \
-       fi.published_at) * 1000 AS \
-       BIGINT) AS published_at_ms, \
-       (es.read_at IS NOT NULL) AS \
-       is_read FROM folder_feeds ff \
-       JOIN {}.feed_items fi ON \
-       fi.feed_id = ff.feed_id JOIN \
-       folders f ON f.id = \
-       ff.folder_id LEFT JOIN \
-       entry_states es ON es.item_id \
-       = fi.id AND es.user_id = ",
-      quote_ident(schema)
+  run_new_items(&state, user_id)
+    .await?;
+
+  let page = Page::new(
+    &state,
+    query.sort.as_deref(),
+    query.cursor.as_deref(),
+    query.limit,
+    DATED_SORTS
+  )?;
+
+  let mut stream = EntryStream::folder(
+    &state, user_id, folder_id
+  )
+  .await?;
+
+  stream.read = ReadFilter::parse(
+    query.read.as_deref()
+  )
+  .map_err(|e| {
+    ServerError::new(
+      StatusCode::BAD_REQUEST,
+      e
     )
-    );
-
-    builder.push_bind(user_id);
-
-    builder
-      .push(" WHERE ff.folder_id = ");
-
-    builder.push_bind(folder_id);
-
-    builder.push(" AND f.user_id = ");
-
-    builder.push_bind(user_id);
-
-    if let Some(filter) = read_filter {
-      match filter {
-        | "read" => {
-          builder.push(
-            " AND es.read_at IS NOT \
-             NULL"
-          );
-        }
-        | "unread" => {
-          builder.push(
-            " AND es.read_at IS NULL"
-          );
-        }
-        | "all" => {}
-        | other => {
-          return Err(ServerError::new(
-            StatusCode::BAD_REQUEST,
-            format!(
-              "invalid read filter: \
-               {other}"
-            )
-          ));
-        }
-      }
-    }
-
-    if let Some(since_id) = since {
-      builder.push(" AND fi.id > ");
-
-      builder.push_bind(since_id);
-    }
-
-    builder.push(
-      " ORDER BY fi.id DESC LIMIT "
-    );
-
-    builder.push_bind(limit);
-
-    builder.push(" OFFSET ");
-
-    builder.push_bind(offset);
-
-    let rows = builder
-      .build_query_as::<EntrySummary>()
-      .fetch_all(pool)
-      .await
-      .map_err(|e| {
-        ServerError::new(
-          StatusCode::INTERNAL_SERVER_ERROR,
-          e.to_string(),
-        )
-      })?;
-
-    let next_cursor = rows
-      .iter()
-      .map(|row| row.id)
-      .max();
+  })?;
+  stream.since = query.since;
 
-    let next_offset = if rows.is_empty()
-    {
-      None
-    } else {
-      Some(offset + rows.len() as i64)
-    };
-
-    return Ok(Json(
-      EntryListResponse {
-        items: rows,
-        next_cursor,
-        next_offset,
-        since
-      }
-    ));
-  }
-
-  let pool = state
-    .sqlite
-    .as_ref()
-    .ok_or_else(|| {
-      ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      "database pool missing",
-    )
-    })?;
-
-  let mut builder = QueryBuilder::<
-    Sqlite
-  >::new(
-    "SELECT fi.id, fi.feed_id, \
-     fi.title, fi.link, \
-     fi.published_at_ms, (es.read_at \
-     IS NOT NULL) AS is_read FROM \
-     folder_feeds ff JOIN feed_items \
-     fi ON fi.feed_id = ff.feed_id \
-     JOIN folders f ON f.id = \
-     ff.folder_id LEFT JOIN \
-     entry_states es ON es.item_id = \
-     fi.id AND es.user_id = "
-  );
-
-  builder.push_bind(user_id);
-
-  builder
-    .push(" WHERE ff.folder_id = ");
-
-  builder.push_bind(folder_id);
-
-  builder.push(" AND f.user_id = ");
-
-  builder.push_bind(user_id);
-
-  if let Some(filter) = read_filter {
-    match filter {
-      | "read" => {
-        builder.push(
-          " AND es.read_at IS NOT NULL"
-        );
-      }
-      | "unread" => {
-        builder.push(
-          " AND es.read_at IS NULL"
-        );
-      }
-      | "all" => {}
-      | other => {
-        return Err(ServerError::new(
-          StatusCode::BAD_REQUEST,
-          format!(
-            "invalid read filter: \
-             {other}"
-          )
-        ));
-      }
-    }
-  }
-
-  if let Some(since_id) = since {
-    builder.push(" AND fi.id > ");
-
-    builder.push_bind(since_id);
-  }
-
-  builder.push(
-    " ORDER BY fi.id DESC LIMIT "
-  );
-
-  builder.push_bind(limit);
-
-  builder.push(" OFFSET ");
-
-  builder.push_bind(offset);
-
-  let rows = builder
-    .build_query_as::<EntrySummary>()
-    .fetch_all(pool)
+  stream_entries(&state, &stream, &page)
     .await
-    .map_err(|e| {
-      ServerError::new(
-        StatusCode::INTERNAL_SERVER_ERROR,
-        e.to_string(),
-      )
-    })?;
-
-  let next_cursor =
-    rows.iter().map(|row| row.id).max();
-
-  let next_offset = if rows.is_empty() {
-    None
-  } else {
-    Some(offset + rows.len() as i64)
-  };
-
-  Ok(Json(EntryListResponse {
-    items: rows,
-    next_cursor,
-    next_offset,
-    since
-  }))
 }
@@ -82,6 +82,8 @@ pub async fn list_folder_feeds(
   Ok(Json(rows))
 }
 
+/// Smart folders pick entries by
+/// their filter and take no feeds.
 pub async fn add_folder_feed(
   State(state): State<AppState>,
   headers: HeaderMap,
@@ -112,7 +114,8 @@ pub async fn add_folder_feed(
        created_at) SELECT $1, $2, \
        NOW() WHERE EXISTS (SELECT 1 \
        FROM folders WHERE id = $1 AND \
-       user_id = $3)"
+       user_id = $3 AND filter IS \
+       NULL)"
     )
     .bind(folder_id)
     .bind(feed_id)
@@ -140,7 +143,8 @@ pub async fn add_folder_feed(
        created_at) SELECT ?1, ?2, \
        datetime('now') WHERE EXISTS \
        (SELECT 1 FROM folders WHERE \
-       id = ?1 AND user_id = ?3)"
+       id = ?1 AND user_id = ?3 AND \
+       filter IS NULL)"
     )
     .bind(folder_id)
     .bind(feed_id)
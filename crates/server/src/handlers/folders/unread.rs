@@ -7,11 +7,28 @@ use axum::http::{
   HeaderMap,
   StatusCode
 };
+use pulsewire_core::domain::stream::{
+  ReadFilter,
+  StreamScope
+};
+use sqlx::{
+  Postgres,
+  QueryBuilder,
+  Sqlite
+};
 
 use crate::app_state::AppState;
 use crate::auth::auth_user_id;
-use crate::db::quote_ident;
+use crate::entry_stream::{
+  EntryStream,
+  items_table
+};
 use crate::errors::ServerError;
+use crate::filter_rules::run_new_items;
+use crate::handlers::entries::{
+  stream_feed_counts,
+  stream_item_count
+};
 use crate::models::{
   FolderFeedUnreadCount,
   FolderUnreadCount
@@ -29,85 +46,40 @@ pub async fn folder_feed_unread_counts(
     auth_user_id(&state, &headers)
       .await?;
 
-  if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
-
-    let query = format!(
-      "SELECT fi.feed_id, \
-       COUNT(*)::BIGINT AS \
-       unread_count FROM folder_feeds \
-       ff JOIN folders f ON f.id = \
-       ff.folder_id JOIN \
-       {}.feed_items fi ON fi.feed_id \
-       = ff.feed_id LEFT JOIN \
-       entry_states es ON es.item_id \
-       = fi.id AND es.user_id = $1 \
-       WHERE ff.folder_id = $2 AND \
-       f.user_id = $1 AND es.read_at \
-       IS NULL GROUP BY fi.feed_id \
-       ORDER BY fi.feed_id",
-      quote_ident(schema)
-    );
-
-    let rows = sqlx::query_as::<_, FolderFeedUnreadCount>(&query)
-      .bind(user_id)
-      .bind(folder_id)
-      .fetch_all(pool)
-      .await
-      .map_err(|e| {
-        ServerError::new(
-          StatusCode::INTERNAL_SERVER_ERROR,
-          e.to_string(),
-        )
-      })?;
-
-    return Ok(Json(rows));
-  }
+  run_new_items(&state, user_id)
+    .await?;
 
-  let pool = state
-    .sqlite
-    .as_ref()
-    .ok_or_else(|| {
-      ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      "database pool missing",
-    )
-    })?;
-
-  let rows = sqlx::query_as::<
-    _,
-    FolderFeedUnreadCount
-  >(
-    "SELECT fi.feed_id, COUNT(*) AS \
-     unread_count FROM folder_feeds \
-     ff JOIN folders f ON f.id = \
-     ff.folder_id JOIN feed_items fi \
-     ON fi.feed_id = ff.feed_id LEFT \
-     JOIN entry_states es ON \
-     es.item_id = fi.id AND \
-     es.user_id = ?1 WHERE \
-     ff.folder_id = ?2 AND f.user_id \
-     = ?1 AND es.read_at IS NULL \
-     GROUP BY fi.feed_id ORDER BY \
-     fi.feed_id"
+  let mut stream = EntryStream::folder(
+    &state, user_id, folder_id
   )
-  .bind(user_id)
-  .bind(folder_id)
-  .fetch_all(pool)
-  .await
-  .map_err(|e| {
-    ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      e.to_string()
-    )
-  })?;
+  .await?;
 
-  Ok(Json(rows))
+  stream.read = ReadFilter::Unread;
+
+  let rows =
+    stream_feed_counts(&state, &stream)
+      .await?;
+
+  Ok(Json(
+    rows
+      .into_iter()
+      .map(|row| {
+        FolderFeedUnreadCount {
+          feed_id:      row.feed_id,
+          unread_count: row
+            .unread_count
+        }
+      })
+      .collect()
+  ))
 }
 
+/// Unread items per folder. Each
+/// folder counts the items of its own
+/// feeds, so only the read filter of
+/// the stream applies; smart folders
+/// are counted one by one through
+/// their filter.
 pub async fn folder_unread_counts(
   State(state): State<AppState>,
   headers: HeaderMap
@@ -119,76 +91,148 @@ pub async fn folder_unread_counts(
     auth_user_id(&state, &headers)
       .await?;
 
-  if let Some(pool) = &state.postgres {
-    let schema = state
-      .fetcher_schema
-      .as_deref()
-      .unwrap_or("fetcher");
-
-    let query = format!(
-      "SELECT f.id AS folder_id, \
-       COUNT(*)::BIGINT AS \
-       unread_count FROM folders f \
-       JOIN folder_feeds ff ON \
-       ff.folder_id = f.id JOIN \
-       {}.feed_items fi ON fi.feed_id \
-       = ff.feed_id LEFT JOIN \
-       entry_states es ON es.item_id \
-       = fi.id AND es.user_id = $1 \
-       WHERE f.user_id = $1 AND \
-       es.read_at IS NULL GROUP BY \
-       f.id ORDER BY f.id",
-      quote_ident(schema)
-    );
-
-    let rows = sqlx::query_as::<_, FolderUnreadCount>(&query)
-      .bind(user_id)
+  run_new_items(&state, user_id)
+    .await?;
+
+  let mut stream = EntryStream::new(
+    user_id,
+    StreamScope::All
+  );
+
+  stream.read = ReadFilter::Unread;
+
+  let select = format!(
+    "SELECT f.id AS folder_id, \
+     CAST(COUNT(*) AS BIGINT) AS \
+     unread_count FROM folders f JOIN \
+     folder_feeds ff ON ff.folder_id \
+     = f.id JOIN {} fi ON fi.feed_id \
+     = ff.feed_id LEFT JOIN \
+     entry_states es ON es.item_id = \
+     fi.id AND es.user_id = ",
+    items_table(&state)
+  );
+
+  let group =
+    " GROUP BY f.id ORDER BY f.id";
+
+  let mut rows = if let Some(pool) =
+    &state.postgres
+  {
+    let mut builder =
+      QueryBuilder::<Postgres>::new(
+        select
+      );
+
+    builder.push_bind(user_id);
+    builder.push(" WHERE f.user_id = ");
+    builder.push_bind(user_id);
+    builder.push(" AND f.filter IS NULL");
+
+    stream.push_filters(&mut builder);
+
+    builder.push(group);
+
+    builder
+      .build_query_as::<FolderUnreadCount>()
       .fetch_all(pool)
       .await
-      .map_err(|e| {
+  } else {
+    let pool = state
+      .sqlite
+      .as_ref()
+      .ok_or_else(|| {
         ServerError::new(
-          StatusCode::INTERNAL_SERVER_ERROR,
-          e.to_string(),
-        )
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "database pool missing",
+      )
       })?;
 
-    return Ok(Json(rows));
-  }
+    let mut builder =
+      QueryBuilder::<Sqlite>::new(select);
+
+    builder.push_bind(user_id);
+    builder.push(" WHERE f.user_id = ");
+    builder.push_bind(user_id);
+    builder.push(" AND f.filter IS NULL");
+
+    stream.push_filters(&mut builder);
 
-  let pool = state
-    .sqlite
-    .as_ref()
-    .ok_or_else(|| {
-      ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      "database pool missing",
+    builder.push(group);
+
+    builder
+      .build_query_as::<FolderUnreadCount>()
+      .fetch_all(pool)
+      .await
+  }
+  .map_err(query_error)?;
+
+  let smart = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, i64>(
+      "SELECT id FROM folders WHERE \
+       user_id = $1 AND filter IS NOT \
+       NULL"
     )
-    })?;
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+      .sqlite
+      .as_ref()
+      .ok_or_else(|| {
+        ServerError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "database pool missing",
+      )
+      })?;
 
-  let rows = sqlx::query_as::<
-    _,
-    FolderUnreadCount
-  >(
-    "SELECT f.id AS folder_id, \
-     COUNT(*) AS unread_count FROM \
-     folders f JOIN folder_feeds ff \
-     ON ff.folder_id = f.id JOIN \
-     feed_items fi ON fi.feed_id = \
-     ff.feed_id LEFT JOIN \
-     entry_states es ON es.item_id = \
-     fi.id AND es.user_id = ?1 WHERE \
-     f.user_id = ?1 AND es.read_at IS \
-     NULL GROUP BY f.id ORDER BY f.id"
-  )
-  .bind(user_id)
-  .fetch_all(pool)
-  .await
-  .map_err(|e| {
-    ServerError::new(
-      StatusCode::INTERNAL_SERVER_ERROR,
-      e.to_string()
+    sqlx::query_scalar::<_, i64>(
+      "SELECT id FROM folders WHERE \
+       user_id = ?1 AND filter IS NOT \
+       NULL"
     )
-  })?;
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  for folder_id in smart {
+    let mut stream =
+      EntryStream::folder(
+        &state, user_id, folder_id
+      )
+      .await?;
+
+    stream.read = ReadFilter::Unread;
+
+    let unread_count =
+      stream_item_count(
+        &state, &stream
+      )
+      .await?;
+
+    if unread_count > 0 {
+      rows.push(FolderUnreadCount {
+        folder_id,
+        unread_count
+      });
+    }
+  }
+
+  rows.sort_by_key(|row| row.folder_id);
 
   Ok(Json(rows))
 }
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e.to_string()
+  )
+}
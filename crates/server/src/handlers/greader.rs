@@ -0,0 +1,598 @@
+//! The Google Reader API under
+//! `/accounts` and `/reader/api/0`.
+//! Clients log in with `ClientLogin`
+//! and send the token back as
+//! `Authorization: GoogleLogin
+//! auth=<token>`; it is an ordinary
+//! `/v1` token, so bearer tokens work
+//! too. Parameters may come in the
+//! query or a form body.
+
+use std::collections::HashMap;
+
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  RawQuery,
+  State
+};
+use axum::http::header::AUTHORIZATION;
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use axum::response::{
+  IntoResponse,
+  Response
+};
+use pulsewire_core::domain::compat::greader::{
+  EditTag,
+  Item,
+  ItemRef,
+  ItemRefs,
+  MarkAllRead,
+  READING_LIST,
+  StreamContents,
+  StreamId,
+  StreamQuery,
+  SubscriptionList,
+  TagList,
+  UserInfo,
+  auth_token,
+  client_login_body,
+  parse_item_id
+};
+use pulsewire_core::domain::compat::{
+  CompatEntry,
+  CompatFeed,
+  form_value,
+  form_values
+};
+use pulsewire_core::domain::stream::{
+  ReadFilter,
+  StreamScope
+};
+
+use crate::app_state::AppState;
+use crate::auth::{
+  bearer_token,
+  check_password,
+  hash_token,
+  issue_token,
+  token_user_id
+};
+use crate::compat;
+use crate::entry_stream::EntryStream;
+use crate::errors::ServerError;
+use crate::filter_rules::run_new_items;
+use crate::pagination::{
+  DATED_SORTS,
+  Page
+};
+use crate::realtime::ReadChange;
+
+/// Logs in with `Email` (the username)
+/// and `Passwd`, answering with the
+/// token as `SID`, `LSID` and `Auth`.
+pub async fn client_login(
+  State(state): State<AppState>,
+  RawQuery(query): RawQuery,
+  body: String
+) -> Response {
+  let pairs =
+    compat::params(query, &body);
+
+  let username =
+    form_value(&pairs, "Email")
+      .unwrap_or_default()
+      .trim();
+  let password =
+    form_value(&pairs, "Passwd")
+      .unwrap_or_default()
+      .trim();
+
+  let login = async {
+    let user_id = check_password(
+      &state, username, password
+    )
+    .await?;
+
+    issue_token(&state, user_id).await
+  };
+
+  match login.await {
+    | Ok(token) => {
+      client_login_body(&token)
+        .into_response()
+    }
+    | Err(e)
+      if e.status()
+        == StatusCode::UNAUTHORIZED =>
+    {
+      (
+        StatusCode::UNAUTHORIZED,
+        "Error=BadAuthentication\n"
+      )
+        .into_response()
+    }
+    | Err(e) => e.into_response()
+  }
+}
+
+/// The token for write requests; the
+/// login token serves.
+pub async fn token(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<String, ServerError> {
+  let (_, token) =
+    reader_user(&state, &headers)
+      .await?;
+
+  Ok(token)
+}
+
+pub async fn user_info(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<Json<UserInfo>, ServerError>
+{
+  let (user_id, _) =
+    reader_user(&state, &headers)
+      .await?;
+
+  let username =
+    compat::username(&state, user_id)
+      .await?;
+
+  Ok(Json(UserInfo::new(
+    user_id, &username
+  )))
+}
+
+pub async fn subscription_list(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  Json<SubscriptionList>,
+  ServerError
+> {
+  let (user_id, _) =
+    reader_user(&state, &headers)
+      .await?;
+
+  let feeds = compat::subscribed_feeds(
+    &state, user_id
+  )
+  .await?;
+
+  Ok(Json(SubscriptionList::new(
+    &feeds
+  )))
+}
+
+/// The starred state and one label
+/// per folder, smart folders included.
+pub async fn tag_list(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<Json<TagList>, ServerError>
+{
+  let (user_id, _) =
+    reader_user(&state, &headers)
+      .await?;
+
+  let names: Vec<String> =
+    compat::folders(&state, user_id)
+      .await?
+      .into_iter()
+      .map(|f| f.name)
+      .collect();
+
+  Ok(Json(TagList::new(&names)))
+}
+
+/// Items of the stream in the path,
+/// newest first unless `r=o`.
+pub async fn stream_contents(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(stream): AxumPath<String>,
+  RawQuery(query): RawQuery,
+  body: String
+) -> Result<
+  Json<StreamContents>,
+  ServerError
+> {
+  let (user_id, _) =
+    reader_user(&state, &headers)
+      .await?;
+
+  let pairs =
+    compat::params(query, &body);
+
+  let query =
+    StreamQuery::parse(&stream, &pairs)
+      .map_err(bad_request)?;
+
+  let (entries, continuation) =
+    stream_page(
+      &state, user_id, &query
+    )
+    .await?;
+
+  let feeds =
+    feeds_by_id(&state, user_id)
+      .await?;
+
+  Ok(Json(StreamContents {
+    id: query.stream.to_id(),
+    updated: chrono::Utc::now()
+      .timestamp(),
+    items: entries
+      .iter()
+      .map(|entry| {
+        Item::new(
+          entry,
+          feeds.get(&entry.feed_id)
+        )
+      })
+      .collect(),
+    continuation
+  }))
+}
+
+/// Ids of the stream named by `s`.
+pub async fn stream_item_ids(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  RawQuery(query): RawQuery,
+  body: String
+) -> Result<Json<ItemRefs>, ServerError>
+{
+  let (user_id, _) =
+    reader_user(&state, &headers)
+      .await?;
+
+  let pairs =
+    compat::params(query, &body);
+
+  let stream = form_value(&pairs, "s")
+    .ok_or_else(|| {
+      bad_request(
+        "s required".to_string()
+      )
+    })?;
+
+  let query =
+    StreamQuery::parse(stream, &pairs)
+      .map_err(bad_request)?;
+
+  let (entries, continuation) =
+    stream_page(
+      &state, user_id, &query
+    )
+    .await?;
+
+  Ok(Json(ItemRefs {
+    item_refs: entries
+      .iter()
+      .map(ItemRef::new)
+      .collect(),
+    continuation
+  }))
+}
+
+/// The items named by repeated `i`.
+pub async fn stream_items_contents(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  RawQuery(query): RawQuery,
+  body: String
+) -> Result<
+  Json<StreamContents>,
+  ServerError
+> {
+  let (user_id, _) =
+    reader_user(&state, &headers)
+      .await?;
+
+  let pairs =
+    compat::params(query, &body);
+
+  let ids = form_values(&pairs, "i")
+    .into_iter()
+    .map(parse_item_id)
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(bad_request)?;
+
+  let limit = ids.len() as i64;
+
+  let mut stream = EntryStream::new(
+    user_id,
+    StreamScope::SubscriptionsOrStarred
+  );
+  stream.ids = Some(ids);
+
+  let entries = compat::entries_by_id(
+    &state, &stream, true, limit
+  )
+  .await?;
+
+  let feeds =
+    feeds_by_id(&state, user_id)
+      .await?;
+
+  Ok(Json(StreamContents {
+    id:           READING_LIST
+      .to_string(),
+    updated:      chrono::Utc::now()
+      .timestamp(),
+    items:        entries
+      .iter()
+      .map(|entry| {
+        Item::new(
+          entry,
+          feeds.get(&entry.feed_id)
+        )
+      })
+      .collect(),
+    continuation: None
+  }))
+}
+
+/// Adds or removes the read and
+/// starred states of items.
+pub async fn edit_tag(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  RawQuery(query): RawQuery,
+  body: String
+) -> Result<&'static str, ServerError> {
+  let (user_id, token) =
+    reader_user(&state, &headers)
+      .await?;
+
+  let edit = EditTag::parse(
+    &compat::params(query, &body)
+  )
+  .map_err(bad_request)?;
+
+  if let Some(read) = edit.read {
+    compat::set_read(
+      &state,
+      user_id,
+      &edit.item_ids,
+      read
+    )
+    .await?;
+
+    state.realtime.read_changed(
+      ReadChange {
+        user_id,
+        session: Some(hash_token(
+          &token
+        )),
+        item_ids: edit.item_ids.clone(),
+        read
+      }
+    );
+  }
+
+  if let Some(starred) = edit.starred {
+    compat::set_starred(
+      &state,
+      user_id,
+      &edit.item_ids,
+      starred
+    )
+    .await?;
+  }
+
+  Ok("OK")
+}
+
+pub async fn mark_all_as_read(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  RawQuery(query): RawQuery,
+  body: String
+) -> Result<&'static str, ServerError> {
+  let (user_id, token) =
+    reader_user(&state, &headers)
+      .await?;
+
+  let mark = MarkAllRead::parse(
+    &compat::params(query, &body)
+  )
+  .map_err(bad_request)?;
+
+  let stream = entry_stream(
+    &state,
+    user_id,
+    &mark.stream
+  )
+  .await?
+  .published(
+    &state,
+    None,
+    mark.older_than_ms
+  );
+
+  let item_ids =
+    compat::mark_stream_read(
+      &state, &stream
+    )
+    .await?;
+
+  state.realtime.read_changed(
+    ReadChange {
+      user_id,
+      session: Some(hash_token(&token)),
+      item_ids,
+      read: true
+    }
+  );
+
+  Ok("OK")
+}
+
+/// The user and token of a request.
+async fn reader_user(
+  state: &AppState,
+  headers: &HeaderMap
+) -> Result<(i64, String), ServerError>
+{
+  let token = match headers
+    .get(AUTHORIZATION)
+    .and_then(|v| v.to_str().ok())
+    .and_then(auth_token)
+  {
+    | Some(token) => token.to_string(),
+    | None => bearer_token(headers)?
+  };
+
+  let user_id =
+    token_user_id(state, &token)
+      .await?;
+
+  Ok((user_id, token))
+}
+
+/// One page of a stream query, with
+/// the continuation to the next.
+async fn stream_page(
+  state: &AppState,
+  user_id: i64,
+  query: &StreamQuery
+) -> Result<
+  (Vec<CompatEntry>, Option<String>),
+  ServerError
+> {
+  run_new_items(state, user_id).await?;
+
+  let page = Page::new(
+    state,
+    Some(
+      if query.oldest_first {
+        "oldest"
+      } else {
+        "newest"
+      }
+    ),
+    query.continuation.as_deref(),
+    Some(query.count),
+    DATED_SORTS
+  )?;
+
+  let mut stream = entry_stream(
+    state,
+    user_id,
+    &query.stream
+  )
+  .await?;
+
+  if query.exclude_read {
+    stream.read = ReadFilter::Unread;
+  }
+
+  if query.only_starred {
+    stream.marks.starred = true;
+  }
+
+  let stream = stream.published(
+    state,
+    query.newer_than_ms,
+    query.older_than_ms
+  );
+
+  let paged = compat::entry_page(
+    state, &stream, &page
+  )
+  .await?;
+
+  Ok((paged.items, paged.next_cursor))
+}
+
+/// What a stream id reads: the
+/// subscriptions, starred or read
+/// entries, a folder or one feed.
+async fn entry_stream(
+  state: &AppState,
+  user_id: i64,
+  stream: &StreamId
+) -> Result<EntryStream, ServerError> {
+  let subscriptions = || {
+    EntryStream::new(
+      user_id,
+      StreamScope::Subscriptions
+    )
+  };
+
+  Ok(match stream {
+    | StreamId::ReadingList => {
+      subscriptions()
+    }
+    | StreamId::Starred => {
+      let mut stream = EntryStream::new(
+        user_id,
+        StreamScope::SubscriptionsOrStarred
+      );
+      stream.marks.starred = true;
+
+      stream
+    }
+    | StreamId::Read => {
+      let mut stream = subscriptions();
+      stream.read = ReadFilter::Read;
+
+      stream
+    }
+    | StreamId::Label(name) => {
+      let folder_id =
+        compat::folder_by_name(
+          state, user_id, name
+        )
+        .await?;
+
+      EntryStream::folder(
+        state, user_id, folder_id
+      )
+      .await?
+    }
+    | StreamId::Feed(feed_id) => {
+      let mut stream = subscriptions();
+      stream.feed_id =
+        Some(feed_id.clone());
+
+      stream
+    }
+  })
+}
+
+async fn feeds_by_id(
+  state: &AppState,
+  user_id: i64
+) -> Result<
+  HashMap<String, CompatFeed>,
+  ServerError
+> {
+  Ok(
+    compat::subscribed_feeds(
+      state, user_id
+    )
+    .await?
+    .into_iter()
+    .map(|feed| (feed.id.clone(), feed))
+    .collect()
+  )
+}
+
+fn bad_request(
+  message: String
+) -> ServerError {
+  ServerError::new(
+    StatusCode::BAD_REQUEST,
+    message
+  )
+}
@@ -0,0 +1,360 @@
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use pulsewire_core::domain::stream::{
+  ReadFilter,
+  StreamScope
+};
+use sqlx::{
+  Postgres,
+  QueryBuilder,
+  Sqlite
+};
+
+use crate::app_state::AppState;
+use crate::auth::auth_user_id;
+use crate::entry_stream::{
+  EntryStream,
+  items_table
+};
+use crate::errors::{
+  ServerError,
+  map_db_error
+};
+use crate::filter_rules::run_new_items;
+use crate::models::{
+  LabelRequest,
+  LabelRow,
+  LabelUnreadCount
+};
+
+pub async fn list_labels(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  Json<Vec<LabelRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, LabelRow>(
+      "SELECT id, name FROM labels \
+       WHERE user_id = $1 ORDER BY name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, LabelRow>(
+      "SELECT id, name FROM labels \
+       WHERE user_id = ?1 ORDER BY name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(|e| {
+    ServerError::new(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      e.to_string()
+    )
+  })?;
+
+  Ok(Json(rows))
+}
+
+pub async fn create_label(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Json(payload): Json<LabelRequest>
+) -> Result<Json<LabelRow>, ServerError>
+{
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let name = label_name(&payload)?;
+
+  let row = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, LabelRow>(
+      "INSERT INTO labels (user_id, \
+       name, created_at) VALUES ($1, \
+       $2, NOW()) RETURNING id, name"
+    )
+    .bind(user_id)
+    .bind(name)
+    .fetch_one(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, LabelRow>(
+      "INSERT INTO labels (user_id, \
+       name, created_at) VALUES (?1, \
+       ?2, datetime('now')) RETURNING \
+       id, name"
+    )
+    .bind(user_id)
+    .bind(name)
+    .fetch_one(pool)
+    .await
+  }
+  .map_err(|e| {
+    map_db_error(e, "label create failed")
+  })?;
+
+  Ok(Json(row))
+}
+
+pub async fn update_label(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(label_id): AxumPath<i64>,
+  Json(payload): Json<LabelRequest>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let name = label_name(&payload)?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "UPDATE labels SET name = $1 \
+       WHERE id = $2 AND user_id = $3"
+    )
+    .bind(name)
+    .bind(label_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      map_db_error(
+        e,
+        "label update failed"
+      )
+    })?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "UPDATE labels SET name = ?1 \
+       WHERE id = ?2 AND user_id = ?3"
+    )
+    .bind(name)
+    .bind(label_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      map_db_error(
+        e,
+        "label update failed"
+      )
+    })?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "label not found"
+    ));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_label(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(label_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "DELETE FROM labels WHERE id = \
+       $1 AND user_id = $2"
+    )
+    .bind(label_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        e.to_string()
+      )
+    })?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "DELETE FROM labels WHERE id = \
+       ?1 AND user_id = ?2"
+    )
+    .bind(label_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        e.to_string()
+      )
+    })?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "label not found"
+    ));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// Unread labelled items per label.
+/// Labels count their own entries
+/// whatever the feed, so only the read
+/// filter of the stream applies.
+pub async fn label_unread_counts(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  Json<Vec<LabelUnreadCount>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  run_new_items(&state, user_id)
+    .await?;
+
+  let mut stream = EntryStream::new(
+    user_id,
+    StreamScope::All
+  );
+
+  stream.read = ReadFilter::Unread;
+
+  let select = format!(
+    "SELECT l.id AS label_id, \
+     CAST(COUNT(*) AS BIGINT) AS \
+     unread_count FROM labels l JOIN \
+     entry_labels el ON el.label_id = \
+     l.id JOIN {} fi ON fi.id = \
+     el.item_id LEFT JOIN \
+     entry_states es ON es.item_id = \
+     fi.id AND es.user_id = ",
+    items_table(&state)
+  );
+
+  let group =
+    " GROUP BY l.id ORDER BY l.id";
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    let mut builder =
+      QueryBuilder::<Postgres>::new(
+        select
+      );
+
+    builder.push_bind(user_id);
+    builder.push(" WHERE l.user_id = ");
+    builder.push_bind(user_id);
+
+    stream.push_filters(&mut builder);
+
+    builder.push(group);
+
+    builder
+      .build_query_as::<LabelUnreadCount>()
+      .fetch_all(pool)
+      .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let mut builder =
+      QueryBuilder::<Sqlite>::new(select);
+
+    builder.push_bind(user_id);
+    builder.push(" WHERE l.user_id = ");
+    builder.push_bind(user_id);
+
+    stream.push_filters(&mut builder);
+
+    builder.push(group);
+
+    builder
+      .build_query_as::<LabelUnreadCount>()
+      .fetch_all(pool)
+      .await
+  }
+  .map_err(|e| {
+    ServerError::new(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      e.to_string()
+    )
+  })?;
+
+  Ok(Json(rows))
+}
+
+fn label_name(
+  payload: &LabelRequest
+) -> Result<&str, ServerError> {
+  let name = payload.name.trim();
+
+  if name.is_empty() {
+    return Err(ServerError::new(
+      StatusCode::BAD_REQUEST,
+      "name required"
+    ));
+  }
+
+  Ok(name)
+}
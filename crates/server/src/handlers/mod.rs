@@ -1,20 +1,33 @@
+mod admin;
 mod auth;
 mod docs;
 mod entries;
 mod favorites;
 mod feeds;
+mod fever;
 mod folders;
+mod greader;
 mod health;
+mod labels;
+mod opml;
+mod published;
+mod registry;
+mod rules;
+mod searches;
+mod stream;
 mod subscriptions;
 mod users;
+mod webhooks;
 
 use axum::Router;
 use axum::routing::{
   delete,
   get,
   patch,
-  post
+  post,
+  put
 };
+pub(crate) use entries::stream_entries;
 
 use crate::app_state::AppState;
 
@@ -52,6 +65,9 @@ pub fn router(
         .route("/v1/auth/rotate", post(auth::rotate_token))
         .route("/v1/auth/tokens", get(auth::list_tokens))
         .route("/v1/auth/tokens/:token_id", delete(auth::revoke_token))
+        .route("/v1/auth/fever", put(fever::set_fever_key))
+        .route("/v1/auth/fever", delete(fever::delete_fever_key))
+        .route("/v1/stream", get(stream::stream))
         .route("/v1/entries", get(entries::list_entries))
         .route("/v1/entries/search", get(entries::search_entries))
         .route("/v1/entries/:item_id", get(entries::entry_detail))
@@ -64,8 +80,64 @@ pub fn router(
         .route("/v1/entries/:item_id/read", get(entries::read_state))
         .route("/v1/entries/:item_id/read", post(entries::mark_read))
         .route("/v1/entries/:item_id/read", delete(entries::mark_unread))
+        .route("/v1/entries/:item_id/star", post(entries::star_entry))
+        .route("/v1/entries/:item_id/star", delete(entries::unstar_entry))
+        .route("/v1/entries/:item_id/queue", post(entries::queue_entry))
+        .route("/v1/entries/:item_id/queue", delete(entries::unqueue_entry))
+        .route("/v1/entries/:item_id/labels", get(entries::entry_labels))
+        .route("/v1/entries/:item_id/labels/:label_id", post(entries::add_entry_label))
+        .route("/v1/entries/:item_id/labels/:label_id", delete(entries::delete_entry_label))
+        .route("/v1/labels", get(labels::list_labels))
+        .route("/v1/labels", post(labels::create_label))
+        .route("/v1/labels/:label_id", patch(labels::update_label))
+        .route("/v1/labels/:label_id", delete(labels::delete_label))
+        .route("/v1/labels/unread/counts", get(labels::label_unread_counts))
+        .route("/v1/rules", get(rules::list_rules))
+        .route("/v1/rules", post(rules::create_rule))
+        .route("/v1/rules/dry-run", get(rules::dry_run_rule))
+        .route("/v1/rules/:rule_id", patch(rules::update_rule))
+        .route("/v1/rules/:rule_id", delete(rules::delete_rule))
+        .route("/v1/rules/:rule_id/apply", post(rules::run_rule))
+        .route("/v1/entries/:item_id/rules", get(rules::entry_rule_hits))
+        .route("/v1/searches", get(searches::list_saved_searches))
+        .route("/v1/searches", post(searches::create_saved_search))
+        .route("/v1/searches/:search_id", patch(searches::update_saved_search))
+        .route("/v1/searches/:search_id", delete(searches::delete_saved_search))
+        .route("/v1/webhooks", get(webhooks::list_webhooks))
+        .route("/v1/webhooks", post(webhooks::create_webhook))
+        .route("/v1/webhooks/dead-letters", get(webhooks::list_dead_letters))
+        .route("/v1/webhooks/:webhook_id", patch(webhooks::update_webhook))
+        .route("/v1/webhooks/:webhook_id", delete(webhooks::delete_webhook))
+        .route("/v1/webhooks/:webhook_id/deliveries", get(webhooks::list_webhook_deliveries))
+        .route("/v1/webhooks/deliveries/:delivery_id/redeliver", post(webhooks::redeliver))
+        .route("/v1/published", get(published::list_published_feeds))
+        .route("/v1/published", post(published::create_published_feed))
+        .route("/v1/published/:published_id", delete(published::delete_published_feed))
         .route("/v1/subscriptions", get(subscriptions::list_subscriptions))
         .route("/v1/subscriptions", post(subscriptions::create_subscription))
         .route("/v1/subscriptions/:feed_id", delete(subscriptions::delete_subscription))
+        .route("/v1/opml/import", post(opml::import_opml))
+        .route("/v1/opml/export", get(opml::export_opml))
+        .route("/v1/registry/quota", get(registry::my_feed_quota))
+        .route("/v1/admin/feeds/health", get(admin::feeds_health))
+        .route("/v1/admin/feeds/:feed_id/health", get(admin::feed_health_detail))
+        .route("/v1/admin/registry", get(registry::list_registry_feeds))
+        .route("/v1/admin/registry/:feed_id/approve", post(registry::approve_registry_feed))
+        .route("/v1/admin/registry/:feed_id/reject", post(registry::reject_registry_feed))
+        .route("/v1/admin/users/:user_id/feed-quota", get(registry::user_feed_quota))
+        .route("/v1/admin/users/:user_id/feed-quota", put(registry::set_user_feed_quota))
+        .route("/v1/admin/users/:user_id/feed-quota", delete(registry::reset_user_feed_quota))
+        .route("/accounts/ClientLogin", get(greader::client_login).post(greader::client_login))
+        .route("/reader/api/0/token", get(greader::token))
+        .route("/reader/api/0/user-info", get(greader::user_info))
+        .route("/reader/api/0/subscription/list", get(greader::subscription_list))
+        .route("/reader/api/0/tag/list", get(greader::tag_list))
+        .route("/reader/api/0/stream/contents/*stream", get(greader::stream_contents).post(greader::stream_contents))
+        .route("/reader/api/0/stream/items/ids", get(greader::stream_item_ids).post(greader::stream_item_ids))
+        .route("/reader/api/0/stream/items/contents", get(greader::stream_items_contents).post(greader::stream_items_contents))
+        .route("/reader/api/0/edit-tag", post(greader::edit_tag))
+        .route("/reader/api/0/mark-all-as-read", post(greader::mark_all_as_read))
+        .route("/fever/", get(fever::fever).post(fever::fever))
+        .route("/p/:token/:file", get(published::published_feed))
         .with_state(state)
 }
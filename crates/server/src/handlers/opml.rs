@@ -0,0 +1,639 @@
+use std::collections::{
+  HashMap,
+  HashSet
+};
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::header::{
+  CONTENT_DISPOSITION,
+  CONTENT_TYPE
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use axum::response::IntoResponse;
+use futures_util::{
+  StreamExt,
+  stream
+};
+use pulsewire_core::app::registry::url_domain;
+use pulsewire_core::feed::opml::{
+  OpmlFeed,
+  parse_opml,
+  write_opml
+};
+
+use super::subscriptions::{
+  subscribe_known,
+  subscribe_url
+};
+use crate::app_state::AppState;
+use crate::auth::{
+  auth_user_id,
+  is_admin
+};
+use crate::errors::ServerError;
+use crate::models::{
+  OpmlImportResponse,
+  OpmlOutlineResult
+};
+use crate::{
+  compat,
+  feed_registry
+};
+
+/// Subscribes the user to every feed
+/// outline, adding unknown URLs to the
+/// feed registry, and files them into
+/// folders named after the enclosing
+/// outlines. Uploads over
+/// `import_limit` outlines, or with
+/// more new feeds than the quota has
+/// left, are rejected before any
+/// lookup; otherwise each URL is looked
+/// up once, `import_concurrency` at a
+/// time, and a failed outline does not
+/// stop the rest.
+pub async fn import_opml(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  body: String
+) -> Result<
+  Json<OpmlImportResponse>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let outlines = parse_opml(&body)
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        e
+      )
+    })?;
+
+  let limit =
+    state.registry.import_limit;
+
+  if outlines.len() > limit {
+    return Err(ServerError::new(
+      StatusCode::PAYLOAD_TOO_LARGE,
+      format!(
+        "OPML holds {} feeds; at most \
+         {limit} can be imported at \
+         once",
+        outlines.len()
+      )
+    ));
+  }
+
+  let subscribed: Arc<HashSet<String>> =
+    Arc::new(
+      compat::subscribed_feeds(
+        &state, user_id
+      )
+      .await?
+      .into_iter()
+      .map(|feed| feed.id)
+      .collect()
+    );
+
+  // Resolve folders first so a clash
+  // with a smart folder leaves its
+  // feeds alone.
+  let mut folders: HashMap<
+    &str,
+    Result<i64, String>
+  > = HashMap::new();
+
+  for name in outlines
+    .iter()
+    .filter_map(|o| o.folder.as_deref())
+  {
+    if !folders.contains_key(name) {
+      let id = folder_id(
+        &state, user_id, name
+      )
+      .await
+      .map_err(|e| {
+        e.message().to_string()
+      });
+
+      folders.insert(name, id);
+    }
+  }
+
+  let mut urls = Vec::new();
+  let mut seen = HashSet::new();
+
+  for outline in &outlines {
+    let filed = outline
+      .folder
+      .as_deref()
+      .is_none_or(|name| {
+        folders[name].is_ok()
+      });
+
+    if filed
+      && seen.insert(
+        outline.xml_url.as_str()
+      )
+    {
+      urls
+        .push(outline.xml_url.clone());
+    }
+  }
+
+  check_quota(&state, user_id, &urls)
+    .await?;
+
+  // Owned arguments keep the handler
+  // future `Send` for axum.
+  let imported: HashMap<
+    String,
+    Result<(String, Imported), String>
+  > = stream::iter(
+    urls.into_iter().map(|url| {
+      import_url(
+        state.clone(),
+        user_id,
+        url,
+        subscribed.clone()
+      )
+    })
+  )
+  .buffer_unordered(
+    state.registry.import_concurrency
+  )
+  .collect()
+  .await;
+
+  let mut report = OpmlImportResponse {
+    matched:  0,
+    created:  0,
+    pending:  0,
+    failed:   0,
+    outlines: Vec::new()
+  };
+
+  let mut reported = HashSet::new();
+
+  for outline in &outlines {
+    let url = outline.xml_url.as_str();
+
+    let folder = outline
+      .folder
+      .as_deref()
+      .map(|name| &folders[name]);
+
+    let res = match folder {
+      | Some(Err(e)) => Err(e.clone()),
+      | _ => {
+        file_outline(
+          &state,
+          &imported[url],
+          folder.and_then(|f| {
+            f.as_ref().ok().copied()
+          }),
+          // Repeats of a URL match
+          // the feed the first one
+          // added.
+          !reported.insert(url)
+        )
+        .await
+      }
+    };
+
+    let (status, feed_id, error) =
+      match res {
+        | Ok((
+          feed_id,
+          Imported::Matched
+        )) => {
+          report.matched += 1;
+
+          (
+            "matched",
+            Some(feed_id),
+            None
+          )
+        }
+        | Ok((
+          feed_id,
+          Imported::Created
+        )) => {
+          report.created += 1;
+
+          (
+            "created",
+            Some(feed_id),
+            None
+          )
+        }
+        | Ok((
+          feed_id,
+          Imported::Pending
+        )) => {
+          report.created += 1;
+          report.pending += 1;
+
+          (
+            "pending",
+            Some(feed_id),
+            None
+          )
+        }
+        | Err(e) => {
+          report.failed += 1;
+
+          ("failed", None, Some(e))
+        }
+      };
+
+    report.outlines.push(
+      OpmlOutlineResult {
+        url: outline.xml_url.clone(),
+        title: outline.title.clone(),
+        folder: outline.folder.clone(),
+        status,
+        feed_id,
+        error
+      }
+    );
+  }
+
+  Ok(Json(report))
+}
+
+/// The user's subscriptions as OPML,
+/// nested by folder. A feed in several
+/// folders is listed in each.
+pub async fn export_opml(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  impl IntoResponse,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let mut feeds = Vec::new();
+
+  for feed in compat::subscribed_feeds(
+    &state, user_id
+  )
+  .await?
+  {
+    let folders: Vec<Option<String>> =
+      if feed.folders.is_empty() {
+        vec![None]
+      } else {
+        feed
+          .folders
+          .into_iter()
+          .map(|f| Some(f.name))
+          .collect()
+      };
+
+    for folder in folders {
+      feeds.push(OpmlFeed {
+        title: Some(feed.title.clone()),
+        xml_url: feed.url.clone(),
+        html_url: None,
+        folder
+      });
+    }
+  }
+
+  Ok((
+    [
+      (
+        CONTENT_TYPE,
+        "text/x-opml; charset=utf-8"
+      ),
+      (
+        CONTENT_DISPOSITION,
+        "attachment; \
+         filename=\"pulsewire.opml\""
+      )
+    ],
+    write_opml(
+      "pulsewire subscriptions",
+      &feeds
+    )
+  ))
+}
+
+#[derive(Clone, Copy)]
+enum Imported {
+  Matched,
+  Created,
+  Pending
+}
+
+/// Rejects a non-admin import whose
+/// URLs nobody has added yet would
+/// overrun the user's feed quota.
+async fn check_quota(
+  state: &AppState,
+  user_id: i64,
+  urls: &[String]
+) -> Result<(), ServerError> {
+  if is_admin(state, user_id).await? {
+    return Ok(());
+  }
+
+  let mut new = 0;
+
+  for url in urls {
+    if url_domain(url).is_some()
+      && feed_registry::feed_by_url(
+        state, url
+      )
+      .await?
+      .is_none()
+      && feed_registry::registry_feed(
+        state, url
+      )
+      .await?
+      .is_none()
+    {
+      new += 1;
+    }
+  }
+
+  let quota = feed_registry::quota(
+    state, user_id
+  )
+  .await?;
+
+  let left = (quota.max_feeds
+    - quota.used)
+    .max(0);
+
+  if new > left {
+    return Err(ServerError::new(
+      StatusCode::FORBIDDEN,
+      format!(
+        "OPML adds {new} new feeds; \
+         the feed quota has {left} \
+         left"
+      )
+    ));
+  }
+
+  Ok(())
+}
+
+/// Subscribes to the feed at `url`,
+/// keyed by it for the report.
+async fn import_url(
+  state: AppState,
+  user_id: i64,
+  url: String,
+  subscribed: Arc<HashSet<String>>
+) -> (
+  String,
+  Result<(String, Imported), String>
+) {
+  let res = subscribe_outline(
+    &state,
+    user_id,
+    &url,
+    &subscribed
+  )
+  .await
+  .map_err(|e| e.message().to_string());
+
+  (url, res)
+}
+
+async fn subscribe_outline(
+  state: &AppState,
+  user_id: i64,
+  url: &str,
+  subscribed: &HashSet<String>
+) -> Result<
+  (String, Imported),
+  ServerError
+> {
+  let known =
+    feed_registry::feed_by_url(
+      state, url
+    )
+    .await?
+    .filter(|id| {
+      subscribed.contains(id)
+    });
+
+  if let Some(feed_id) = known {
+    return Ok((
+      feed_id,
+      Imported::Matched
+    ));
+  }
+
+  if let Some(created) =
+    subscribe_known(state, user_id, url)
+      .await?
+  {
+    return Ok((
+      created.feed_id,
+      Imported::Matched
+    ));
+  }
+
+  let created =
+    subscribe_url(state, user_id, url)
+      .await?;
+
+  let imported = if created.pending {
+    Imported::Pending
+  } else {
+    Imported::Created
+  };
+
+  Ok((created.feed_id, imported))
+}
+
+/// Adds an imported feed to the
+/// outline's folder; pending feeds
+/// are left out until approved.
+async fn file_outline(
+  state: &AppState,
+  imported: &Result<
+    (String, Imported),
+    String
+  >,
+  folder_id: Option<i64>,
+  repeat: bool
+) -> Result<(String, Imported), String>
+{
+  let (feed_id, imported) =
+    imported.clone()?;
+
+  if let Imported::Pending = imported {
+    return Ok((feed_id, imported));
+  }
+
+  if let Some(folder_id) = folder_id {
+    add_to_folder(
+      state, folder_id, &feed_id
+    )
+    .await
+    .map_err(|e| {
+      e.message().to_string()
+    })?;
+  }
+
+  if repeat {
+    return Ok((
+      feed_id,
+      Imported::Matched
+    ));
+  }
+
+  Ok((feed_id, imported))
+}
+
+/// The user's folder called `name`,
+/// created if missing.
+async fn folder_id(
+  state: &AppState,
+  user_id: i64,
+  name: &str
+) -> Result<i64, ServerError> {
+  let (id, filter) = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "INSERT INTO folders (user_id, \
+       name, created_at) VALUES ($1, \
+       $2, NOW()) ON CONFLICT \
+       (user_id, name) DO NOTHING"
+    )
+    .bind(user_id)
+    .bind(name)
+    .execute(pool)
+    .await
+    .map_err(query_error)?;
+
+    sqlx::query_as::<
+      _,
+      (i64, Option<String>)
+    >(
+      "SELECT id, filter FROM folders \
+       WHERE user_id = $1 AND name = \
+       $2"
+    )
+    .bind(user_id)
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .map_err(query_error)?
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "INSERT INTO folders (user_id, \
+       name, created_at) VALUES (?1, \
+       ?2, datetime('now')) ON \
+       CONFLICT(user_id, name) DO \
+       NOTHING"
+    )
+    .bind(user_id)
+    .bind(name)
+    .execute(pool)
+    .await
+    .map_err(query_error)?;
+
+    sqlx::query_as::<
+      _,
+      (i64, Option<String>)
+    >(
+      "SELECT id, filter FROM folders \
+       WHERE user_id = ?1 AND name = \
+       ?2"
+    )
+    .bind(user_id)
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .map_err(query_error)?
+  };
+
+  if filter.is_some() {
+    return Err(ServerError::new(
+      StatusCode::CONFLICT,
+      format!(
+        "folder '{name}' is a smart \
+         folder"
+      )
+    ));
+  }
+
+  Ok(id)
+}
+
+async fn add_to_folder(
+  state: &AppState,
+  folder_id: i64,
+  feed_id: &str
+) -> Result<(), ServerError> {
+  if let Some(pool) = &state.postgres {
+    sqlx::query(
+      "INSERT INTO folder_feeds \
+       (folder_id, feed_id, \
+       created_at) VALUES ($1, $2, \
+       NOW()) ON CONFLICT DO NOTHING"
+    )
+    .bind(folder_id)
+    .bind(feed_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?;
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "INSERT INTO folder_feeds \
+       (folder_id, feed_id, \
+       created_at) VALUES (?1, ?2, \
+       datetime('now')) ON CONFLICT \
+       DO NOTHING"
+    )
+    .bind(folder_id)
+    .bind(feed_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?;
+  }
+
+  Ok(())
+}
+
+fn internal(e: String) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e
+  )
+}
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  internal(e.to_string())
+}
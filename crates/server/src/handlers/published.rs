@@ -0,0 +1,756 @@
+//! Published feeds: a folder, label,
+//! saved search or the starred entries
+//! of a user, served as Atom, RSS or
+//! JSON Feed at `/p/{token}/feed.*`
+//! to anyone holding the token.
+
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  State
+};
+use axum::http::header::{
+  CONTENT_TYPE,
+  ETAG,
+  HOST,
+  IF_NONE_MATCH
+};
+use axum::http::{
+  HeaderMap,
+  HeaderValue,
+  StatusCode
+};
+use axum::response::{
+  IntoResponse,
+  Response
+};
+use pulsewire_core::domain::search::parse_search;
+use pulsewire_core::domain::stream::StreamScope;
+use pulsewire_core::feed::parser::FeedItem;
+use pulsewire_core::feed::publish::{
+  Channel,
+  FeedFormat,
+  etag,
+  not_modified,
+  render
+};
+
+use super::entries::{
+  search_hits,
+  stream_details
+};
+use crate::app_state::AppState;
+use crate::auth::{
+  auth_user_id,
+  generate_token,
+  hash_token
+};
+use crate::entry_stream::EntryStream;
+use crate::errors::ServerError;
+use crate::models::{
+  PublishedFeedRequest,
+  PublishedFeedRow,
+  PublishedFeedUrls
+};
+use crate::pagination::{
+  Page,
+  SEARCH_SORTS
+};
+
+/// What a published feed serves.
+#[derive(Debug, Clone, Copy)]
+enum Source {
+  Folder(i64),
+  Label(i64),
+  Search(i64),
+  Starred
+}
+
+impl Source {
+  fn from_request(
+    payload: &PublishedFeedRequest
+  ) -> Result<Self, ServerError> {
+    let starred =
+      payload.starred.unwrap_or(false);
+
+    match (
+      payload.folder_id,
+      payload.label_id,
+      payload.search_id,
+      starred
+    ) {
+      | (
+        Some(id),
+        None,
+        None,
+        false
+      ) => Ok(Self::Folder(id)),
+      | (
+        None,
+        Some(id),
+        None,
+        false
+      ) => Ok(Self::Label(id)),
+      | (
+        None,
+        None,
+        Some(id),
+        false
+      ) => Ok(Self::Search(id)),
+      | (None, None, None, true) => {
+        Ok(Self::Starred)
+      }
+      | _ => {
+        Err(ServerError::new(
+          StatusCode::BAD_REQUEST,
+          "give one of folder_id, \
+           label_id, search_id or \
+           starred"
+        ))
+      }
+    }
+  }
+
+  fn from_row(
+    row: &Published
+  ) -> Option<Self> {
+    match row.source.as_str() {
+      | "folder" => {
+        row.folder_id.map(Self::Folder)
+      }
+      | "label" => {
+        row.label_id.map(Self::Label)
+      }
+      | "search" => {
+        row.search_id.map(Self::Search)
+      }
+      | "starred" => {
+        Some(Self::Starred)
+      }
+      | _ => None
+    }
+  }
+
+  fn as_str(self) -> &'static str {
+    match self {
+      | Self::Folder(_) => "folder",
+      | Self::Label(_) => "label",
+      | Self::Search(_) => "search",
+      | Self::Starred => "starred"
+    }
+  }
+
+  /// The source's table, one of fixed
+  /// names, and id.
+  fn table(
+    self
+  ) -> Option<(&'static str, i64)> {
+    match self {
+      | Self::Folder(id) => {
+        Some(("folders", id))
+      }
+      | Self::Label(id) => {
+        Some(("labels", id))
+      }
+      | Self::Search(id) => {
+        Some(("saved_searches", id))
+      }
+      | Self::Starred => None
+    }
+  }
+
+  fn id_of(
+    self,
+    table: &str
+  ) -> Option<i64> {
+    self
+      .table()
+      .filter(|(t, _)| *t == table)
+      .map(|(_, id)| id)
+  }
+}
+
+/// A published feed found by its
+/// token.
+#[derive(sqlx::FromRow)]
+struct Published {
+  user_id:   i64,
+  title:     String,
+  source:    String,
+  folder_id: Option<i64>,
+  label_id:  Option<i64>,
+  search_id: Option<i64>
+}
+
+pub async fn list_published_feeds(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  Json<Vec<PublishedFeedRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, PublishedFeedRow>(
+      "SELECT id, title, source, \
+       folder_id, label_id, search_id, \
+       created_at::text AS created_at \
+       FROM published_feeds WHERE \
+       user_id = $1 ORDER BY id"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, PublishedFeedRow>(
+      "SELECT id, title, source, \
+       folder_id, label_id, search_id, \
+       created_at FROM published_feeds \
+       WHERE user_id = ?1 ORDER BY id"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(Json(rows))
+}
+
+/// Publishes a source under a new
+/// token. The token and URLs are in
+/// the response only here.
+pub async fn create_published_feed(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Json(payload): Json<
+    PublishedFeedRequest
+  >
+) -> Result<
+  Json<PublishedFeedRow>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let source =
+    Source::from_request(&payload)?;
+
+  let name = source_name(
+    &state, source, user_id
+  )
+  .await?;
+
+  let title = payload
+    .title
+    .as_deref()
+    .map(str::trim)
+    .filter(|t| !t.is_empty())
+    .map(str::to_string)
+    .unwrap_or(name);
+
+  let token = generate_token();
+
+  let mut row = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, PublishedFeedRow>(
+      "INSERT INTO published_feeds \
+       (user_id, token_hash, title, \
+       source, folder_id, label_id, \
+       search_id, created_at) VALUES \
+       ($1, $2, $3, $4, $5, $6, $7, \
+       NOW()) RETURNING id, title, \
+       source, folder_id, label_id, \
+       search_id, created_at::text AS \
+       created_at"
+    )
+    .bind(user_id)
+    .bind(hash_token(&token))
+    .bind(&title)
+    .bind(source.as_str())
+    .bind(source.id_of("folders"))
+    .bind(source.id_of("labels"))
+    .bind(source.id_of("saved_searches"))
+    .fetch_one(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, PublishedFeedRow>(
+      "INSERT INTO published_feeds \
+       (user_id, token_hash, title, \
+       source, folder_id, label_id, \
+       search_id, created_at) VALUES \
+       (?1, ?2, ?3, ?4, ?5, ?6, ?7, \
+       datetime('now')) RETURNING id, \
+       title, source, folder_id, \
+       label_id, search_id, created_at"
+    )
+    .bind(user_id)
+    .bind(hash_token(&token))
+    .bind(&title)
+    .bind(source.as_str())
+    .bind(source.id_of("folders"))
+    .bind(source.id_of("labels"))
+    .bind(source.id_of("saved_searches"))
+    .fetch_one(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  let url = |format: FeedFormat| {
+    feed_url(
+      &state, &headers, &token, format
+    )
+  };
+
+  row.urls = Some(PublishedFeedUrls {
+    atom: url(FeedFormat::Atom),
+    rss:  url(FeedFormat::Rss),
+    json: url(FeedFormat::Json)
+  });
+  row.token = Some(token);
+
+  Ok(Json(row))
+}
+
+/// Revokes a published feed; its URLs
+/// stop working at once.
+pub async fn delete_published_feed(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(published_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "DELETE FROM published_feeds \
+       WHERE id = $1 AND user_id = $2"
+    )
+    .bind(published_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "DELETE FROM published_feeds \
+       WHERE id = ?1 AND user_id = ?2"
+    )
+    .bind(published_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(not_found(
+      "published feed"
+    ));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// The public document of a published
+/// feed: its newest
+/// `[published].entry_limit` entries.
+/// Answers conditional GETs with `304
+/// Not Modified`. Anyone may fetch it,
+/// so it only reads: rule hits are
+/// those the owner's own requests
+/// stored.
+pub async fn published_feed(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath((token, file)): AxumPath<(
+    String,
+    String
+  )>
+) -> Result<Response, ServerError> {
+  let format =
+    FeedFormat::from_file(&file)
+      .ok_or_else(|| {
+        not_found("feed")
+      })?;
+
+  let published =
+    find_published(&state, &token)
+      .await?
+      .ok_or_else(|| {
+        not_found("feed")
+      })?;
+
+  let source =
+    Source::from_row(&published)
+      .ok_or_else(|| {
+        not_found("feed")
+      })?;
+
+  let limit = i64::from(
+    state.published.entry_limit
+  );
+
+  let stream = source_stream(
+    &state,
+    source,
+    published.user_id,
+    limit
+  )
+  .await?;
+
+  let items: Vec<FeedItem> =
+    stream_details(
+      &state, &stream, limit
+    )
+    .await?
+    .into_iter()
+    .map(|entry| {
+      FeedItem {
+        title:           entry.title,
+        link:            entry.link,
+        guid:            entry.guid,
+        published_at_ms: entry
+          .published_at_ms,
+        category:        entry.category,
+        author:          None,
+        description:     entry
+          .description,
+        summary:         entry.summary,
+        diff:            None
+      }
+    })
+    .collect();
+
+  let channel = Channel {
+    title:         published.title,
+    url:           feed_url(
+      &state, &headers, &token, format
+    ),
+    updated_at_ms: None
+  };
+
+  let body =
+    render(format, &channel, &items);
+  let tag = etag(&body);
+
+  let mut validators = HeaderMap::new();
+
+  validators
+    .insert(ETAG, header_value(&tag));
+
+  if not_modified(
+    headers
+      .get(IF_NONE_MATCH)
+      .and_then(|v| v.to_str().ok()),
+    &tag
+  ) {
+    return Ok(
+      (
+        StatusCode::NOT_MODIFIED,
+        validators
+      )
+        .into_response()
+    );
+  }
+
+  validators.insert(
+    CONTENT_TYPE,
+    HeaderValue::from_static(
+      format.content_type()
+    )
+  );
+
+  Ok((validators, body).into_response())
+}
+
+/// The stream a source reads. A saved
+/// search runs over the user's
+/// subscriptions and keeps its newest
+/// hits.
+async fn source_stream(
+  state: &AppState,
+  source: Source,
+  user_id: i64,
+  limit: i64
+) -> Result<EntryStream, ServerError> {
+  match source {
+    | Source::Folder(folder_id) => {
+      EntryStream::folder(
+        state, user_id, folder_id
+      )
+      .await
+    }
+    | Source::Label(label_id) => {
+      let mut stream = EntryStream::new(
+        user_id,
+        StreamScope::All
+      );
+
+      stream.marks.label =
+        Some(label_id);
+
+      Ok(stream)
+    }
+    | Source::Starred => {
+      let mut stream = EntryStream::new(
+        user_id,
+        StreamScope::All
+      );
+
+      stream.marks.starred = true;
+
+      Ok(stream)
+    }
+    | Source::Search(search_id) => {
+      let query = search_query(
+        state, search_id, user_id
+      )
+      .await?;
+
+      let parsed = parse_search(&query)
+        .map_err(|e| {
+          ServerError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e
+          )
+        })?;
+
+      let page = Page::new(
+        state,
+        Some("newest"),
+        None,
+        u32::try_from(limit).ok(),
+        SEARCH_SORTS
+      )?;
+
+      let mut stream = EntryStream::new(
+        user_id,
+        StreamScope::Subscriptions
+      );
+
+      let ids = search_hits(
+        state, &parsed, &stream, &page
+      )
+      .await?
+      .into_iter()
+      .map(|hit| hit.id)
+      .collect();
+
+      stream.ids = Some(ids);
+
+      Ok(stream)
+    }
+  }
+}
+
+/// The name of the user's source,
+/// which also checks they own it.
+async fn source_name(
+  state: &AppState,
+  source: Source,
+  user_id: i64
+) -> Result<String, ServerError> {
+  let Some((table, id)) =
+    source.table()
+  else {
+    return Ok("Starred".to_string());
+  };
+
+  let name = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, String>(
+      &format!(
+        "SELECT name FROM {table} WHERE \
+         id = $1 AND user_id = $2"
+      )
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, String>(
+      &format!(
+        "SELECT name FROM {table} WHERE \
+         id = ?1 AND user_id = ?2"
+      )
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  name.ok_or_else(|| {
+    not_found(source.as_str())
+  })
+}
+
+async fn search_query(
+  state: &AppState,
+  search_id: i64,
+  user_id: i64
+) -> Result<String, ServerError> {
+  let query = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, String>(
+      "SELECT query FROM saved_searches \
+       WHERE id = $1 AND user_id = $2"
+    )
+    .bind(search_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, String>(
+      "SELECT query FROM saved_searches \
+       WHERE id = ?1 AND user_id = ?2"
+    )
+    .bind(search_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  query.ok_or_else(|| not_found("feed"))
+}
+
+async fn find_published(
+  state: &AppState,
+  token: &str
+) -> Result<
+  Option<Published>,
+  ServerError
+> {
+  let token_hash = hash_token(token);
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query_as::<_, Published>(
+      "SELECT user_id, title, source, \
+       folder_id, label_id, search_id \
+       FROM published_feeds WHERE \
+       token_hash = $1"
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, Published>(
+      "SELECT user_id, title, source, \
+       folder_id, label_id, search_id \
+       FROM published_feeds WHERE \
+       token_hash = ?1"
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)
+}
+
+/// A published document's absolute
+/// URL, from `[published].base_url`
+/// or the request's `Host`.
+fn feed_url(
+  state: &AppState,
+  headers: &HeaderMap,
+  token: &str,
+  format: FeedFormat
+) -> String {
+  let base =
+    match &state.published.base_url {
+      | Some(base) => {
+        base
+          .trim_end_matches('/')
+          .to_string()
+      }
+      | None => {
+        let host = headers
+          .get(HOST)
+          .and_then(|v| v.to_str().ok())
+          .unwrap_or("localhost");
+
+        format!("http://{host}")
+      }
+    };
+
+  format!(
+    "{base}/p/{token}/{}",
+    format.file_name()
+  )
+}
+
+fn header_value(
+  value: &str
+) -> HeaderValue {
+  HeaderValue::from_str(value).expect(
+    "entity tags and HTTP dates are \
+     valid header values"
+  )
+}
+
+fn not_found(
+  what: &str
+) -> ServerError {
+  ServerError::new(
+    StatusCode::NOT_FOUND,
+    format!("{what} not found")
+  )
+}
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e.to_string()
+  )
+}
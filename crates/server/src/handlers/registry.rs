@@ -0,0 +1,400 @@
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  Query,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use pulsewire_core::domain::registry::{
+  RegistryFeed,
+  RegistryStatus
+};
+
+use super::subscriptions::insert_subscription;
+use crate::app_state::AppState;
+use crate::auth::{
+  auth_admin_id,
+  auth_user_id
+};
+use crate::errors::ServerError;
+use crate::feed_registry;
+use crate::models::{
+  FeedQuotaRequest,
+  FeedQuotaRow,
+  RegistryFeedRow,
+  RegistryQuery
+};
+
+fn internal(e: String) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e
+  )
+}
+
+fn feed_row(
+  feed: RegistryFeed
+) -> RegistryFeedRow {
+  RegistryFeedRow {
+    id:             feed.id,
+    url:            feed.url,
+    title:          feed.title,
+    added_by:       feed.added_by,
+    status:         feed
+      .status
+      .as_str()
+      .to_string(),
+    created_at_ms:  feed.created_at_ms,
+    reviewed_at_ms: feed.reviewed_at_ms
+  }
+}
+
+pub async fn list_registry_feeds(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(query): Query<RegistryQuery>
+) -> Result<
+  Json<Vec<RegistryFeedRow>>,
+  ServerError
+> {
+  auth_admin_id(&state, &headers)
+    .await?;
+
+  let status = query
+    .status
+    .as_deref()
+    .map(RegistryStatus::parse)
+    .transpose()
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        e
+      )
+    })?;
+
+  let feeds = state
+    .fetcher
+    .registry_feeds(status)
+    .await
+    .map_err(internal)?;
+
+  Ok(Json(
+    feeds
+      .into_iter()
+      .map(feed_row)
+      .collect()
+  ))
+}
+
+pub async fn approve_registry_feed(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(feed_id): AxumPath<String>
+) -> Result<
+  Json<RegistryFeedRow>,
+  ServerError
+> {
+  auth_admin_id(&state, &headers)
+    .await?;
+
+  let feed = review(
+    &state,
+    &feed_id,
+    RegistryStatus::Approved
+  )
+  .await?;
+
+  feed_registry::activate(
+    &state, &feed
+  )
+  .await?;
+
+  // The subscription its adder asked
+  // for starts now.
+  match insert_subscription(
+    &state,
+    feed.added_by,
+    &feed.id
+  )
+  .await
+  {
+    | Ok(()) => {}
+    | Err(e)
+      if e.status()
+        == StatusCode::CONFLICT => {}
+    | Err(e) => return Err(e)
+  }
+
+  Ok(Json(feed_row(feed)))
+}
+
+pub async fn reject_registry_feed(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(feed_id): AxumPath<String>
+) -> Result<
+  Json<RegistryFeedRow>,
+  ServerError
+> {
+  auth_admin_id(&state, &headers)
+    .await?;
+
+  let feed = review(
+    &state,
+    &feed_id,
+    RegistryStatus::Rejected
+  )
+  .await?;
+
+  Ok(Json(feed_row(feed)))
+}
+
+/// Moves a pending feed to `status`,
+/// returning it as reviewed.
+async fn review(
+  state: &AppState,
+  feed_id: &str,
+  status: RegistryStatus
+) -> Result<RegistryFeed, ServerError> {
+  let Some(mut feed) =
+    feed_registry::registry_feed(
+      state, feed_id
+    )
+    .await?
+    .filter(|f| f.id == feed_id)
+  else {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "registry feed not found"
+    ));
+  };
+
+  let now_ms = chrono::Utc::now()
+    .timestamp_millis();
+
+  let reviewed = state
+    .fetcher
+    .review_registry_feed(
+      feed_id,
+      status,
+      now_ms,
+      &chrono_tz::UTC
+    )
+    .await
+    .map_err(internal)?;
+
+  if !reviewed {
+    return Err(ServerError::new(
+      StatusCode::CONFLICT,
+      format!(
+        "feed is already {}",
+        feed.status.as_str()
+      )
+    ));
+  }
+
+  feed.status = status;
+  feed.reviewed_at_ms = Some(now_ms);
+
+  Ok(feed)
+}
+
+pub async fn my_feed_quota(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  Json<FeedQuotaRow>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  Ok(Json(
+    feed_registry::quota(
+      &state, user_id
+    )
+    .await?
+  ))
+}
+
+pub async fn user_feed_quota(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(user_id): AxumPath<i64>
+) -> Result<
+  Json<FeedQuotaRow>,
+  ServerError
+> {
+  auth_admin_id(&state, &headers)
+    .await?;
+
+  require_user(&state, user_id).await?;
+
+  Ok(Json(
+    feed_registry::quota(
+      &state, user_id
+    )
+    .await?
+  ))
+}
+
+pub async fn set_user_feed_quota(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(user_id): AxumPath<i64>,
+  Json(payload): Json<FeedQuotaRequest>
+) -> Result<
+  Json<FeedQuotaRow>,
+  ServerError
+> {
+  auth_admin_id(&state, &headers)
+    .await?;
+
+  if payload.max_feeds < 0 {
+    return Err(ServerError::new(
+      StatusCode::BAD_REQUEST,
+      "max_feeds must not be negative"
+    ));
+  }
+
+  require_user(&state, user_id).await?;
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query(
+      "INSERT INTO feed_quotas \
+       (user_id, max_feeds, \
+       updated_at) VALUES ($1, $2, \
+       NOW()) ON CONFLICT (user_id) \
+       DO UPDATE SET max_feeds = \
+       EXCLUDED.max_feeds, updated_at \
+       = EXCLUDED.updated_at"
+    )
+    .bind(user_id)
+    .bind(payload.max_feeds)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      internal(e.to_string())
+    })?;
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "INSERT INTO feed_quotas \
+       (user_id, max_feeds, \
+       updated_at) VALUES (?1, ?2, \
+       datetime('now')) ON \
+       CONFLICT(user_id) DO UPDATE \
+       SET max_feeds = \
+       excluded.max_feeds, updated_at \
+       = excluded.updated_at"
+    )
+    .bind(user_id)
+    .bind(payload.max_feeds)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      internal(e.to_string())
+    })?;
+  }
+
+  Ok(Json(
+    feed_registry::quota(
+      &state, user_id
+    )
+    .await?
+  ))
+}
+
+pub async fn reset_user_feed_quota(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(user_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  auth_admin_id(&state, &headers)
+    .await?;
+
+  let result = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "DELETE FROM feed_quotas WHERE \
+       user_id = $1"
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "DELETE FROM feed_quotas WHERE \
+       user_id = ?1"
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+  }
+  .map_err(|e| internal(e.to_string()))?;
+
+  if result == 0 {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "no quota set for user"
+    ));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+async fn require_user(
+  state: &AppState,
+  user_id: i64
+) -> Result<(), ServerError> {
+  let found = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, i64>(
+      "SELECT id FROM users WHERE id = \
+       $1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, i64>(
+      "SELECT id FROM users WHERE id = \
+       ?1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(|e| internal(e.to_string()))?;
+
+  if found.is_none() {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "user not found"
+    ));
+  }
+
+  Ok(())
+}
@@ -0,0 +1,531 @@
+//! Filter rules: per-user actions on
+//! entries matching a filter
+//! expression, with a dry run and an
+//! on-demand run over older entries.
+//! Deleting a rule drops its hits, so
+//! entries it hid come back.
+
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  Query,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use pulsewire_core::domain::entry_filter::{
+  RuleAction,
+  parse_filter
+};
+use pulsewire_core::domain::stream::StreamScope;
+
+use super::entries::stream_entries;
+use crate::app_state::AppState;
+use crate::auth::auth_user_id;
+use crate::entry_stream::EntryStream;
+use crate::errors::{
+  ServerError,
+  map_db_error
+};
+use crate::filter_rules::{
+  apply_rule,
+  latest_item_id
+};
+use crate::models::{
+  EntryListResponse,
+  RuleApplyResponse,
+  RuleDryRunQuery,
+  RuleHitRow,
+  RuleRequest,
+  RuleRow
+};
+use crate::pagination::{
+  DATED_SORTS,
+  Page
+};
+
+/// A rule request's checked fields.
+struct RuleFields<'a> {
+  name:     &'a str,
+  filter:   &'a str,
+  action:   &'static str,
+  label_id: Option<i64>
+}
+
+pub async fn list_rules(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  Json<Vec<RuleRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, RuleRow>(
+      "SELECT id, name, filter, action, \
+       label_id FROM filter_rules WHERE \
+       user_id = $1 ORDER BY name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, RuleRow>(
+      "SELECT id, name, filter, action, \
+       label_id FROM filter_rules WHERE \
+       user_id = ?1 ORDER BY name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(Json(rows))
+}
+
+/// New rules act on entries arriving
+/// from now on; apply one to act on
+/// older entries too.
+pub async fn create_rule(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Json(payload): Json<RuleRequest>
+) -> Result<Json<RuleRow>, ServerError>
+{
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let fields = rule_fields(
+    &state, user_id, &payload
+  )
+  .await?;
+
+  let checked =
+    latest_item_id(&state).await?;
+
+  let row = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, RuleRow>(
+      "INSERT INTO filter_rules \
+       (user_id, name, filter, action, \
+       label_id, checked_item_id, \
+       created_at) VALUES ($1, $2, $3, \
+       $4, $5, $6, NOW()) RETURNING id, \
+       name, filter, action, label_id"
+    )
+    .bind(user_id)
+    .bind(fields.name)
+    .bind(fields.filter)
+    .bind(fields.action)
+    .bind(fields.label_id)
+    .bind(checked)
+    .fetch_one(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, RuleRow>(
+      "INSERT INTO filter_rules \
+       (user_id, name, filter, action, \
+       label_id, checked_item_id, \
+       created_at) VALUES (?1, ?2, ?3, \
+       ?4, ?5, ?6, datetime('now')) \
+       RETURNING id, name, filter, \
+       action, label_id"
+    )
+    .bind(user_id)
+    .bind(fields.name)
+    .bind(fields.filter)
+    .bind(fields.action)
+    .bind(fields.label_id)
+    .bind(checked)
+    .fetch_one(pool)
+    .await
+  }
+  .map_err(|e| {
+    map_db_error(e, "rule create failed")
+  })?;
+
+  Ok(Json(row))
+}
+
+/// Replaces a rule. Past hits and
+/// their effects stay.
+pub async fn update_rule(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(rule_id): AxumPath<i64>,
+  Json(payload): Json<RuleRequest>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let fields = rule_fields(
+    &state, user_id, &payload
+  )
+  .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "UPDATE filter_rules SET name = \
+       $1, filter = $2, action = $3, \
+       label_id = $4 WHERE id = $5 \
+       AND user_id = $6"
+    )
+    .bind(fields.name)
+    .bind(fields.filter)
+    .bind(fields.action)
+    .bind(fields.label_id)
+    .bind(rule_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      map_db_error(
+        e,
+        "rule update failed"
+      )
+    })?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "UPDATE filter_rules SET name = \
+       ?1, filter = ?2, action = ?3, \
+       label_id = ?4 WHERE id = ?5 \
+       AND user_id = ?6"
+    )
+    .bind(fields.name)
+    .bind(fields.filter)
+    .bind(fields.action)
+    .bind(fields.label_id)
+    .bind(rule_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      map_db_error(
+        e,
+        "rule update failed"
+      )
+    })?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(rule_not_found());
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_rule(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(rule_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "DELETE FROM filter_rules WHERE \
+       id = $1 AND user_id = $2"
+    )
+    .bind(rule_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "DELETE FROM filter_rules WHERE \
+       id = ?1 AND user_id = ?2"
+    )
+    .bind(rule_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(rule_not_found());
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// Recent entries of the user's
+/// subscriptions a filter matches,
+/// newest first; nothing is changed.
+pub async fn dry_run_rule(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(query): Query<RuleDryRunQuery>
+) -> Result<
+  Json<EntryListResponse>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let page = Page::new(
+    &state,
+    None,
+    query.cursor.as_deref(),
+    query.limit,
+    DATED_SORTS
+  )?;
+
+  let stream = EntryStream::new(
+    user_id,
+    StreamScope::Subscriptions
+  )
+  .with_filter(&state, &query.filter)?;
+
+  stream_entries(&state, &stream, &page)
+    .await
+}
+
+/// Runs a rule over every entry of the
+/// user's subscriptions, including
+/// ones older than the rule.
+pub async fn run_rule(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(rule_id): AxumPath<i64>
+) -> Result<
+  Json<RuleApplyResponse>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let matched = apply_rule(
+    &state, user_id, rule_id
+  )
+  .await?
+  .ok_or_else(rule_not_found)?;
+
+  Ok(Json(RuleApplyResponse {
+    matched
+  }))
+}
+
+/// The user's rules that fired for an
+/// entry.
+pub async fn entry_rule_hits(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(item_id): AxumPath<i64>
+) -> Result<
+  Json<Vec<RuleHitRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, RuleHitRow>(
+      "SELECT h.rule_id, r.name, \
+       h.action FROM rule_hits h JOIN \
+       filter_rules r ON r.id = \
+       h.rule_id WHERE h.item_id = $1 \
+       AND h.user_id = $2 ORDER BY \
+       h.rule_id"
+    )
+    .bind(item_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, RuleHitRow>(
+      "SELECT h.rule_id, r.name, \
+       h.action FROM rule_hits h JOIN \
+       filter_rules r ON r.id = \
+       h.rule_id WHERE h.item_id = ?1 \
+       AND h.user_id = ?2 ORDER BY \
+       h.rule_id"
+    )
+    .bind(item_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(Json(rows))
+}
+
+/// Checks a rule request: a name, a
+/// filter that parses, a known action
+/// and, for `label`, one of the user's
+/// labels.
+async fn rule_fields<'a>(
+  state: &AppState,
+  user_id: i64,
+  payload: &'a RuleRequest
+) -> Result<RuleFields<'a>, ServerError>
+{
+  let bad_request =
+    |message: String| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        message
+      )
+    };
+
+  let name = payload.name.trim();
+
+  if name.is_empty() {
+    return Err(bad_request(
+      "name required".to_string()
+    ));
+  }
+
+  let filter = payload.filter.trim();
+
+  parse_filter(filter)
+    .map_err(bad_request)?;
+
+  let action =
+    RuleAction::parse(&payload.action)
+      .map_err(bad_request)?;
+
+  match (action, payload.label_id) {
+    | (RuleAction::Label, None) => {
+      return Err(bad_request(
+        "label_id required for the \
+         label action"
+          .to_string()
+      ));
+    }
+    | (RuleAction::Label, Some(_)) => {}
+    | (_, Some(_)) => {
+      return Err(bad_request(
+        "label_id only applies to the \
+         label action"
+          .to_string()
+      ));
+    }
+    | (_, None) => {}
+  }
+
+  if let Some(label_id) =
+    payload.label_id
+    && !owns_label(
+      state, user_id, label_id
+    )
+    .await?
+  {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "label not found"
+    ));
+  }
+
+  Ok(RuleFields {
+    name,
+    filter,
+    action: action.as_str(),
+    label_id: payload.label_id
+  })
+}
+
+async fn owns_label(
+  state: &AppState,
+  user_id: i64,
+  label_id: i64
+) -> Result<bool, ServerError> {
+  let found = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, i64>(
+      "SELECT id FROM labels WHERE id = \
+       $1 AND user_id = $2"
+    )
+    .bind(label_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, i64>(
+      "SELECT id FROM labels WHERE id = \
+       ?1 AND user_id = ?2"
+    )
+    .bind(label_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(found.is_some())
+}
+
+fn rule_not_found() -> ServerError {
+  ServerError::new(
+    StatusCode::NOT_FOUND,
+    "rule not found"
+  )
+}
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e.to_string()
+  )
+}
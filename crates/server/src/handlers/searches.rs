@@ -0,0 +1,293 @@
+//! Saved searches: named entry search
+//! queries, run through
+//! `/v1/entries/search`.
+
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use pulsewire_core::domain::search::parse_search;
+
+use crate::app_state::AppState;
+use crate::auth::auth_user_id;
+use crate::errors::{
+  ServerError,
+  map_db_error
+};
+use crate::models::{
+  SavedSearchRequest,
+  SavedSearchRow
+};
+
+pub async fn list_saved_searches(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  Json<Vec<SavedSearchRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, SavedSearchRow>(
+      "SELECT id, name, query FROM \
+       saved_searches WHERE user_id = \
+       $1 ORDER BY name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, SavedSearchRow>(
+      "SELECT id, name, query FROM \
+       saved_searches WHERE user_id = \
+       ?1 ORDER BY name"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(|e| {
+    ServerError::new(
+      StatusCode::INTERNAL_SERVER_ERROR,
+      e.to_string()
+    )
+  })?;
+
+  Ok(Json(rows))
+}
+
+pub async fn create_saved_search(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Json(payload): Json<
+    SavedSearchRequest
+  >
+) -> Result<
+  Json<SavedSearchRow>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let (name, query) =
+    saved_search(&payload)?;
+
+  let row = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, SavedSearchRow>(
+      "INSERT INTO saved_searches \
+       (user_id, name, query, \
+       created_at) VALUES ($1, $2, $3, \
+       NOW()) RETURNING id, name, query"
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(query)
+    .fetch_one(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, SavedSearchRow>(
+      "INSERT INTO saved_searches \
+       (user_id, name, query, \
+       created_at) VALUES (?1, ?2, ?3, \
+       datetime('now')) RETURNING id, \
+       name, query"
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(query)
+    .fetch_one(pool)
+    .await
+  }
+  .map_err(|e| {
+    map_db_error(
+      e,
+      "saved search create failed"
+    )
+  })?;
+
+  Ok(Json(row))
+}
+
+pub async fn update_saved_search(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(search_id): AxumPath<i64>,
+  Json(payload): Json<
+    SavedSearchRequest
+  >
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let (name, query) =
+    saved_search(&payload)?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "UPDATE saved_searches SET name \
+       = $1, query = $2 WHERE id = $3 \
+       AND user_id = $4"
+    )
+    .bind(name)
+    .bind(query)
+    .bind(search_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      map_db_error(
+        e,
+        "saved search update failed"
+      )
+    })?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "UPDATE saved_searches SET name \
+       = ?1, query = ?2 WHERE id = ?3 \
+       AND user_id = ?4"
+    )
+    .bind(name)
+    .bind(query)
+    .bind(search_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      map_db_error(
+        e,
+        "saved search update failed"
+      )
+    })?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "saved search not found"
+    ));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_saved_search(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(search_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "DELETE FROM saved_searches \
+       WHERE id = $1 AND user_id = $2"
+    )
+    .bind(search_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        e.to_string()
+      )
+    })?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "DELETE FROM saved_searches \
+       WHERE id = ?1 AND user_id = ?2"
+    )
+    .bind(search_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        e.to_string()
+      )
+    })?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "saved search not found"
+    ));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// Trimmed name and query; the query
+/// must parse as an entry search.
+fn saved_search(
+  payload: &SavedSearchRequest
+) -> Result<(&str, &str), ServerError> {
+  let bad_request =
+    |message: String| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        message
+      )
+    };
+
+  let name = payload.name.trim();
+
+  if name.is_empty() {
+    return Err(bad_request(
+      "name required".to_string()
+    ));
+  }
+
+  let query = payload.query.trim();
+
+  parse_search(query)
+    .map_err(bad_request)?;
+
+  Ok((name, query))
+}
@@ -0,0 +1,682 @@
+//! `GET /v1/stream`: realtime updates
+//! over Server-Sent Events, or over a
+//! WebSocket when the request asks to
+//! upgrade. Events:
+//!
+//! - `counts`: per-feed unread counts,
+//!   sent first and after `reset`.
+//! - `entries`: new entries of the
+//!   user's subscriptions.
+//! - `unread`: feeds whose unread count
+//!   changed, with the delta.
+//! - `read`: entries another session
+//!   marked read or unread.
+//! - `reset`: events were missed;
+//!   refetch lists.
+//!
+//! Each event has an id; sending the
+//! last one back as `Last-Event-ID`
+//! resumes after it.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use axum::extract::ws::{
+  Message,
+  WebSocket,
+  WebSocketUpgrade
+};
+use axum::extract::{
+  Query,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use axum::response::sse::{
+  Event,
+  KeepAlive,
+  Sse
+};
+use axum::response::{
+  IntoResponse,
+  Response
+};
+use pulsewire_core::domain::realtime::{
+  EventId,
+  unread_changes
+};
+use pulsewire_core::domain::stream::{
+  ReadFilter,
+  StreamScope
+};
+use serde::Serialize;
+use tokio::sync::broadcast::error::{
+  RecvError,
+  TryRecvError
+};
+use tokio::sync::{
+  broadcast,
+  mpsc
+};
+
+use super::entries::{
+  stream_entries,
+  stream_feed_counts
+};
+use crate::app_state::AppState;
+use crate::auth::{
+  auth_user_id,
+  bearer_token,
+  hash_token,
+  token_user_id
+};
+use crate::entry_stream::EntryStream;
+use crate::errors::ServerError;
+use crate::filter_rules::{
+  latest_item_id,
+  run_new_items
+};
+use crate::models::{
+  FeedUnreadCount,
+  StreamCounts,
+  StreamEntries,
+  StreamMessage,
+  StreamQuery,
+  StreamReadChange,
+  StreamUnread
+};
+use crate::pagination::{
+  DATED_SORTS,
+  Page
+};
+use crate::realtime::Signal;
+
+/// New entries sent at once; more
+/// than this and the client is told
+/// to refetch instead.
+const ENTRY_LIMIT: u32 = 100;
+
+pub async fn stream(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(query): Query<StreamQuery>,
+  ws: Option<WebSocketUpgrade>
+) -> Result<Response, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let token = bearer_token(&headers)?;
+
+  let resume = headers
+    .get("last-event-id")
+    .and_then(|v| v.to_str().ok())
+    .map(str::to_string)
+    .or(query.last_event_id)
+    .filter(|id| !id.is_empty())
+    .map(|id| id.parse::<EventId>())
+    .transpose()
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        e
+      )
+    })?;
+
+  let (tx, rx) = mpsc::channel(64);
+
+  let session = Session::open(
+    state, user_id, token, tx
+  )
+  .await?;
+
+  tokio::spawn(async move {
+    if let Err(e) =
+      session.run(resume).await
+    {
+      tracing::warn!(
+        user_id,
+        error = e.message(),
+        "realtime stream failed"
+      );
+    }
+  });
+
+  if let Some(ws) = ws {
+    return Ok(ws.on_upgrade(
+      |socket| forward(socket, rx)
+    ));
+  }
+
+  let events =
+    futures_util::stream::unfold(
+      rx,
+      |mut rx| {
+        async move {
+          let message =
+            rx.recv().await?;
+
+          let event = Event::default()
+            .id(message.id)
+            .event(message.event)
+            .data(
+              message.data.to_string()
+            );
+
+          Some((
+            Ok::<_, Infallible>(event),
+            rx
+          ))
+        }
+      }
+    );
+
+  Ok(
+    Sse::new(events)
+      .keep_alive(KeepAlive::default())
+      .into_response()
+  )
+}
+
+/// Sends a stream's events over a
+/// WebSocket until either side closes.
+async fn forward(
+  mut socket: WebSocket,
+  mut rx: mpsc::Receiver<StreamMessage>
+) {
+  loop {
+    tokio::select! {
+      message = rx.recv() => {
+        let Some(message) = message else {
+          break;
+        };
+
+        let Ok(text) =
+          serde_json::to_string(&message)
+        else {
+          continue;
+        };
+
+        if socket
+          .send(Message::Text(text))
+          .await
+          .is_err()
+        {
+          break;
+        }
+      }
+      incoming = socket.recv() => {
+        match incoming {
+          | Some(Ok(Message::Close(_)))
+          | Some(Err(_))
+          | None => break,
+          | Some(Ok(_)) => {}
+        }
+      }
+    }
+  }
+}
+
+/// One client's stream: what it has
+/// been sent and the unread counts it
+/// knows.
+struct Session {
+  state:   AppState,
+  user_id: i64,
+  token:   String,
+  session: String,
+  signals: broadcast::Receiver<Signal>,
+  tx:      mpsc::Sender<StreamMessage>,
+  id:      EventId,
+  counts:  BTreeMap<String, i64>
+}
+
+impl Session {
+  /// Subscribes before reading the
+  /// current state, so nothing after
+  /// it is missed.
+  async fn open(
+    state: AppState,
+    user_id: i64,
+    token: String,
+    tx: mpsc::Sender<StreamMessage>
+  ) -> Result<Self, ServerError> {
+    let (signals, seq) =
+      state.realtime.subscribe();
+
+    let item_id =
+      latest_item_id(&state).await?;
+
+    run_new_items(&state, user_id)
+      .await?;
+
+    let counts =
+      unread_counts(&state, user_id)
+        .await?;
+
+    Ok(Self {
+      session: hash_token(&token),
+      state,
+      user_id,
+      token,
+      signals,
+      tx,
+      id: EventId {
+        item_id,
+        seq
+      },
+      counts
+    })
+  }
+
+  async fn run(
+    mut self,
+    resume: Option<EventId>
+  ) -> Result<(), ServerError> {
+    match resume {
+      | Some(from) => {
+        self.catch_up(from).await?
+      }
+      | None => self.send_counts().await
+    }
+
+    loop {
+      let first = tokio::select! {
+        signal = self.signals.recv() => signal,
+        () = self.tx.closed() => {
+          return Ok(());
+        }
+      };
+
+      // Signals that queued up are
+      // handled together.
+      let mut batch = vec![first];
+
+      loop {
+        match self.signals.try_recv() {
+          | Ok(signal) => {
+            batch.push(Ok(signal))
+          }
+          | Err(
+            TryRecvError::Lagged(n)
+          ) => {
+            batch.push(Err(
+              RecvError::Lagged(n)
+            ))
+          }
+          | Err(_) => break
+        }
+      }
+
+      if !self.handle(batch).await? {
+        return Ok(());
+      }
+    }
+  }
+
+  /// Handles signals; `false` once the
+  /// stream should end.
+  async fn handle(
+    &mut self,
+    batch: Vec<
+      Result<Signal, RecvError>
+    >
+  ) -> Result<bool, ServerError> {
+    let mut items = false;
+    let mut recount = false;
+    let mut reset = false;
+
+    for signal in batch {
+      match signal {
+        | Ok(Signal::Items) => {
+          items = true
+        }
+        | Ok(Signal::Read(
+          seq,
+          change
+        )) => {
+          self.id.seq =
+            self.id.seq.max(seq);
+
+          if change.user_id
+            != self.user_id
+          {
+            continue;
+          }
+
+          recount = true;
+
+          if change.session.as_deref()
+            != Some(
+              self.session.as_str()
+            )
+          {
+            self
+              .send(
+                "read",
+                StreamReadChange {
+                  item_ids: change
+                    .item_ids
+                    .clone(),
+                  read:     change.read
+                }
+              )
+              .await;
+          }
+        }
+        | Err(RecvError::Lagged(_)) => {
+          reset = true
+        }
+        | Err(RecvError::Closed) => {
+          return Ok(false);
+        }
+      }
+    }
+
+    // Revoked or expired tokens end
+    // the stream.
+    match token_user_id(
+      &self.state,
+      &self.token
+    )
+    .await
+    {
+      | Ok(_) => {}
+      | Err(e)
+        if e.status()
+          == StatusCode::UNAUTHORIZED =>
+      {
+        return Ok(false);
+      }
+      | Err(e) => return Err(e)
+    }
+
+    if reset {
+      self.reset().await?;
+    } else {
+      if items {
+        self.new_entries().await?;
+        recount = true;
+      }
+
+      if recount {
+        self.send_unread().await?;
+      }
+    }
+
+    Ok(true)
+  }
+
+  /// Replays what a resuming client
+  /// missed after the counts: read
+  /// changes still in the log, then
+  /// new entries with their current
+  /// state. Resets if the log no
+  /// longer has them.
+  async fn catch_up(
+    &mut self,
+    from: EventId
+  ) -> Result<(), ServerError> {
+    let Some(changes) = self
+      .state
+      .realtime
+      .read_since(from.seq)
+    else {
+      return self.reset().await;
+    };
+
+    self.send_counts().await;
+
+    let upto = self.id.seq;
+
+    for (seq, change) in changes {
+      // Later ones arrive as signals.
+      if seq > upto {
+        break;
+      }
+
+      if change.user_id == self.user_id
+        && change.session.as_deref()
+          != Some(self.session.as_str())
+      {
+        self
+          .send(
+            "read",
+            StreamReadChange {
+              item_ids: change
+                .item_ids
+                .clone(),
+              read:     change.read
+            }
+          )
+          .await;
+      }
+    }
+
+    if from.item_id < self.id.item_id {
+      let upto = self.id.item_id;
+
+      self
+        .send_entries(
+          from.item_id,
+          upto
+        )
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  async fn new_entries(
+    &mut self
+  ) -> Result<(), ServerError> {
+    let upto =
+      latest_item_id(&self.state)
+        .await?;
+
+    if upto <= self.id.item_id {
+      return Ok(());
+    }
+
+    self
+      .send_entries(
+        self.id.item_id,
+        upto
+      )
+      .await
+  }
+
+  /// Sends entries with ids in
+  /// `(after, upto]`, or resets when
+  /// there are too many.
+  async fn send_entries(
+    &mut self,
+    after: i64,
+    upto: i64
+  ) -> Result<(), ServerError> {
+    run_new_items(
+      &self.state,
+      self.user_id
+    )
+    .await?;
+
+    let mut stream = EntryStream::new(
+      self.user_id,
+      StreamScope::Subscriptions
+    );
+    stream.since = Some(after);
+    stream.before = Some(upto + 1);
+
+    let page = Page::new(
+      &self.state,
+      Some("oldest"),
+      None,
+      Some(ENTRY_LIMIT),
+      DATED_SORTS
+    )?;
+
+    let list = stream_entries(
+      &self.state,
+      &stream,
+      &page
+    )
+    .await?
+    .0;
+
+    self.id.item_id = upto;
+
+    if list.next_cursor.is_some() {
+      return self.reset().await;
+    }
+
+    if !list.items.is_empty() {
+      self
+        .send(
+          "entries",
+          StreamEntries {
+            entries: list.items
+          }
+        )
+        .await;
+    }
+
+    Ok(())
+  }
+
+  /// Tells the client it missed
+  /// events and sends fresh counts.
+  async fn reset(
+    &mut self
+  ) -> Result<(), ServerError> {
+    self.id = EventId {
+      item_id: latest_item_id(
+        &self.state
+      )
+      .await?,
+      seq:     self
+        .state
+        .realtime
+        .last_seq()
+        .max(self.id.seq)
+    };
+
+    run_new_items(
+      &self.state,
+      self.user_id
+    )
+    .await?;
+
+    self.counts = unread_counts(
+      &self.state,
+      self.user_id
+    )
+    .await?;
+
+    self
+      .send(
+        "reset",
+        serde_json::json!({})
+      )
+      .await;
+    self.send_counts().await;
+
+    Ok(())
+  }
+
+  async fn send_counts(&self) {
+    let feeds = self
+      .counts
+      .iter()
+      .map(|(feed_id, count)| {
+        FeedUnreadCount {
+          feed_id:      feed_id.clone(),
+          unread_count: *count
+        }
+      })
+      .collect();
+
+    self
+      .send("counts", StreamCounts {
+        feeds
+      })
+      .await;
+  }
+
+  async fn send_unread(
+    &mut self
+  ) -> Result<(), ServerError> {
+    let counts = unread_counts(
+      &self.state,
+      self.user_id
+    )
+    .await?;
+
+    let feeds = unread_changes(
+      &self.counts,
+      &counts
+    );
+
+    self.counts = counts;
+
+    if !feeds.is_empty() {
+      self
+        .send("unread", StreamUnread {
+          feeds
+        })
+        .await;
+    }
+
+    Ok(())
+  }
+
+  /// Queues an event; a closed stream
+  /// is noticed by the run loop.
+  async fn send(
+    &self,
+    event: &'static str,
+    data: impl Serialize
+  ) {
+    let Ok(data) =
+      serde_json::to_value(data)
+    else {
+      return;
+    };
+
+    let _ = self
+      .tx
+      .send(StreamMessage {
+        id: self.id.to_string(),
+        event,
+        data
+      })
+      .await;
+  }
+}
+
+/// Unread counts per feed of the
+/// user's subscriptions.
+async fn unread_counts(
+  state: &AppState,
+  user_id: i64
+) -> Result<
+  BTreeMap<String, i64>,
+  ServerError
+> {
+  let mut stream = EntryStream::new(
+    user_id,
+    StreamScope::Subscriptions
+  );
+  stream.read = ReadFilter::Unread;
+
+  Ok(
+    stream_feed_counts(state, &stream)
+      .await?
+      .into_iter()
+      .map(|row| {
+        (row.feed_id, row.unread_count)
+      })
+      .collect()
+  )
+}
@@ -7,14 +7,24 @@ use axum::http::{
   HeaderMap,
   StatusCode
 };
+use pulsewire_core::app::registry::{
+  discover,
+  url_domain
+};
+use pulsewire_core::domain::registry::RegistryStatus;
 
 use crate::app_state::AppState;
-use crate::auth::auth_user_id;
+use crate::auth::{
+  auth_user_id,
+  is_admin
+};
 use crate::errors::{
   ServerError,
   map_db_error
 };
+use crate::feed_registry;
 use crate::models::{
+  SubscriptionCreated,
   SubscriptionRequest,
   SubscriptionRow
 };
@@ -74,20 +84,224 @@ pub async fn create_subscription(
   Json(payload): Json<
     SubscriptionRequest
   >
-) -> Result<StatusCode, ServerError> {
+) -> Result<
+  (
+    StatusCode,
+    Json<SubscriptionCreated>
+  ),
+  ServerError
+> {
   let user_id =
     auth_user_id(&state, &headers)
       .await?;
 
-  let feed_id = payload.feed_id.trim();
+  let feed_id = payload
+    .feed_id
+    .as_deref()
+    .map(str::trim)
+    .filter(|id| !id.is_empty());
 
-  if feed_id.is_empty() {
+  let url = payload
+    .url
+    .as_deref()
+    .map(str::trim)
+    .filter(|url| !url.is_empty());
+
+  let created = match (feed_id, url) {
+    | (Some(feed_id), _) => {
+      insert_subscription(
+        &state, user_id, feed_id
+      )
+      .await?;
+
+      SubscriptionCreated {
+        feed_id: feed_id.to_string(),
+        pending: false
+      }
+    }
+    | (None, Some(url)) => {
+      subscribe_url(
+        &state, user_id, url
+      )
+      .await?
+    }
+    | (None, None) => {
+      return Err(ServerError::new(
+        StatusCode::BAD_REQUEST,
+        "feed_id or url required"
+      ));
+    }
+  };
+
+  Ok((
+    StatusCode::CREATED,
+    Json(created)
+  ))
+}
+
+/// Subscribes to the feed at `url`,
+/// adding it to the feed registry
+/// first if nobody has.
+pub(super) async fn subscribe_url(
+  state: &AppState,
+  user_id: i64,
+  url: &str
+) -> Result<
+  SubscriptionCreated,
+  ServerError
+> {
+  if url_domain(url).is_none() {
     return Err(ServerError::new(
       StatusCode::BAD_REQUEST,
-      "feed_id required"
+      "url must be an http(s) URL"
     ));
   }
 
+  if let Some(created) =
+    subscribe_known(state, user_id, url)
+      .await?
+  {
+    return Ok(created);
+  }
+
+  let admin =
+    is_admin(state, user_id).await?;
+
+  if !admin {
+    let quota = feed_registry::quota(
+      state, user_id
+    )
+    .await?;
+
+    if quota.used >= quota.max_feeds {
+      return Err(ServerError::new(
+        StatusCode::FORBIDDEN,
+        "feed quota reached"
+      ));
+    }
+  }
+
+  let discovered =
+    discover(state.http.as_ref(), url)
+      .await
+      .map_err(|e| {
+        ServerError::new(
+      StatusCode::UNPROCESSABLE_ENTITY,
+      e
+    )
+      })?;
+
+  // A page may link to a feed that is
+  // already known.
+  if discovered.url != url
+    && let Some(created) =
+      subscribe_known(
+        state,
+        user_id,
+        &discovered.url
+      )
+      .await?
+  {
+    return Ok(created);
+  }
+
+  let feed = feed_registry::register(
+    state, user_id, discovered, admin
+  )
+  .await?;
+
+  let pending = feed.status
+    == RegistryStatus::Pending;
+
+  if !pending {
+    insert_subscription(
+      state, user_id, &feed.id
+    )
+    .await?;
+  }
+
+  Ok(SubscriptionCreated {
+    feed_id: feed.id,
+    pending
+  })
+}
+
+/// Subscribes to a polled or
+/// registered feed at `url`; `None` if
+/// there is none.
+pub(super) async fn subscribe_known(
+  state: &AppState,
+  user_id: i64,
+  url: &str
+) -> Result<
+  Option<SubscriptionCreated>,
+  ServerError
+> {
+  if let Some(feed_id) =
+    feed_registry::feed_by_url(
+      state, url
+    )
+    .await?
+  {
+    insert_subscription(
+      state, user_id, &feed_id
+    )
+    .await?;
+
+    return Ok(Some(
+      SubscriptionCreated {
+        feed_id,
+        pending: false
+      }
+    ));
+  }
+
+  let Some(feed) =
+    feed_registry::registry_feed(
+      state, url
+    )
+    .await?
+  else {
+    return Ok(None);
+  };
+
+  match feed.status {
+    | RegistryStatus::Pending => {
+      Err(ServerError::new(
+        StatusCode::CONFLICT,
+        "feed is awaiting approval"
+      ))
+    }
+    | RegistryStatus::Rejected => {
+      Err(ServerError::new(
+        StatusCode::CONFLICT,
+        "feed was rejected"
+      ))
+    }
+    | RegistryStatus::Approved => {
+      feed_registry::activate(
+        state, &feed
+      )
+      .await?;
+
+      insert_subscription(
+        state, user_id, &feed.id
+      )
+      .await?;
+
+      Ok(Some(SubscriptionCreated {
+        feed_id: feed.id,
+        pending: false
+      }))
+    }
+  }
+}
+
+pub(super) async fn insert_subscription(
+  state: &AppState,
+  user_id: i64,
+  feed_id: &str
+) -> Result<(), ServerError> {
   if let Some(pool) = &state.postgres {
     sqlx::query(
       "INSERT INTO subscriptions \
@@ -105,7 +319,7 @@ pub async fn create_subscription(
       )
     })?;
 
-    return Ok(StatusCode::CREATED);
+    return Ok(());
   }
 
   let pool = state
@@ -129,7 +343,7 @@ pub async fn create_subscription(
     )
   })?;
 
-  Ok(StatusCode::CREATED)
+  Ok(())
 }
 
 pub async fn delete_subscription(
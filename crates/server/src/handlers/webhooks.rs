@@ -0,0 +1,778 @@
+//! Webhooks: per-user URLs that new
+//! entries of the user's subscriptions
+//! are POSTed to, narrowed by feed,
+//! folder, label and rule, with their
+//! delivery history, the dead-letter
+//! view and redelivery.
+
+use axum::Json;
+use axum::extract::{
+  Path as AxumPath,
+  Query,
+  State
+};
+use axum::http::{
+  HeaderMap,
+  StatusCode
+};
+use pulsewire_core::app::registry::url_domain;
+use pulsewire_core::domain::webhook::DeliveryStatus;
+use pulsewire_core::infra::reqwest_http::check_public_url;
+
+use crate::app_state::AppState;
+use crate::auth::{
+  auth_user_id,
+  generate_token
+};
+use crate::errors::ServerError;
+use crate::filter_rules::latest_item_id;
+use crate::models::{
+  WebhookDeliveryQuery,
+  WebhookDeliveryRow,
+  WebhookRequest,
+  WebhookRow
+};
+
+/// Deliveries listed when no limit is
+/// given, and the most allowed.
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+/// A webhook request's checked fields.
+struct WebhookFields<'a> {
+  url:       &'a str,
+  secret:    Option<&'a str>,
+  feed_id:   Option<&'a str>,
+  folder_id: Option<i64>,
+  label_id:  Option<i64>,
+  rule_id:   Option<i64>
+}
+
+pub async fn list_webhooks(
+  State(state): State<AppState>,
+  headers: HeaderMap
+) -> Result<
+  Json<Vec<WebhookRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, WebhookRow>(
+      "SELECT id, url, feed_id, \
+       folder_id, label_id, rule_id, \
+       (paused_at IS NULL) AS active, \
+       created_at::text AS created_at \
+       FROM webhooks WHERE user_id = $1 \
+       ORDER BY id"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, WebhookRow>(
+      "SELECT id, url, feed_id, \
+       folder_id, label_id, rule_id, \
+       (paused_at IS NULL) AS active, \
+       created_at FROM webhooks WHERE \
+       user_id = ?1 ORDER BY id"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(Json(rows))
+}
+
+/// New webhooks are sent entries
+/// arriving from now on. The secret is
+/// in the response only here.
+pub async fn create_webhook(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Json(payload): Json<WebhookRequest>
+) -> Result<Json<WebhookRow>, ServerError>
+{
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let fields = webhook_fields(
+    &state, user_id, &payload
+  )
+  .await?;
+
+  let secret = fields
+    .secret
+    .map(str::to_string)
+    .unwrap_or_else(generate_token);
+
+  let active =
+    payload.active.unwrap_or(true);
+
+  let checked =
+    latest_item_id(&state).await?;
+
+  let mut row = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, WebhookRow>(
+      "INSERT INTO webhooks (user_id, \
+       url, secret, feed_id, folder_id, \
+       label_id, rule_id, \
+       checked_item_id, paused_at, \
+       created_at) VALUES ($1, $2, $3, \
+       $4, $5, $6, $7, $8, CASE WHEN $9 \
+       THEN NULL ELSE NOW() END, NOW()) \
+       RETURNING id, url, feed_id, \
+       folder_id, label_id, rule_id, \
+       (paused_at IS NULL) AS active, \
+       created_at::text AS created_at"
+    )
+    .bind(user_id)
+    .bind(fields.url)
+    .bind(&secret)
+    .bind(fields.feed_id)
+    .bind(fields.folder_id)
+    .bind(fields.label_id)
+    .bind(fields.rule_id)
+    .bind(checked)
+    .bind(active)
+    .fetch_one(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, WebhookRow>(
+      "INSERT INTO webhooks (user_id, \
+       url, secret, feed_id, folder_id, \
+       label_id, rule_id, \
+       checked_item_id, paused_at, \
+       created_at) VALUES (?1, ?2, ?3, \
+       ?4, ?5, ?6, ?7, ?8, CASE WHEN ?9 \
+       THEN NULL ELSE datetime('now') \
+       END, datetime('now')) RETURNING \
+       id, url, feed_id, folder_id, \
+       label_id, rule_id, (paused_at IS \
+       NULL) AS active, created_at"
+    )
+    .bind(user_id)
+    .bind(fields.url)
+    .bind(&secret)
+    .bind(fields.feed_id)
+    .bind(fields.folder_id)
+    .bind(fields.label_id)
+    .bind(fields.rule_id)
+    .bind(checked)
+    .bind(active)
+    .fetch_one(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  row.secret = Some(secret);
+
+  Ok(Json(row))
+}
+
+/// Replaces a webhook's URL and
+/// filters; the secret and state stay
+/// unless given. Resuming a paused
+/// webhook skips entries that arrived
+/// while it was paused.
+pub async fn update_webhook(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(webhook_id): AxumPath<i64>,
+  Json(payload): Json<WebhookRequest>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let fields = webhook_fields(
+    &state, user_id, &payload
+  )
+  .await?;
+
+  let latest =
+    latest_item_id(&state).await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "UPDATE webhooks SET url = $1, \
+       secret = COALESCE($2, secret), \
+       feed_id = $3, folder_id = $4, \
+       label_id = $5, rule_id = $6, \
+       checked_item_id = CASE WHEN \
+       $7::BOOLEAN AND paused_at IS \
+       NOT NULL THEN $8 ELSE \
+       checked_item_id END, paused_at \
+       = CASE WHEN $7::BOOLEAN IS \
+       NULL THEN paused_at WHEN $7 \
+       THEN NULL ELSE \
+       COALESCE(paused_at, NOW()) END \
+       WHERE id = $9 AND user_id = $10"
+    )
+    .bind(fields.url)
+    .bind(fields.secret)
+    .bind(fields.feed_id)
+    .bind(fields.folder_id)
+    .bind(fields.label_id)
+    .bind(fields.rule_id)
+    .bind(payload.active)
+    .bind(latest)
+    .bind(webhook_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "UPDATE webhooks SET url = ?1, \
+       secret = COALESCE(?2, secret), \
+       feed_id = ?3, folder_id = ?4, \
+       label_id = ?5, rule_id = ?6, \
+       checked_item_id = CASE WHEN ?7 \
+       AND paused_at IS NOT NULL THEN \
+       ?8 ELSE checked_item_id END, \
+       paused_at = CASE WHEN ?7 IS \
+       NULL THEN paused_at WHEN ?7 \
+       THEN NULL ELSE \
+       COALESCE(paused_at, \
+       datetime('now')) END WHERE id \
+       = ?9 AND user_id = ?10"
+    )
+    .bind(fields.url)
+    .bind(fields.secret)
+    .bind(fields.feed_id)
+    .bind(fields.folder_id)
+    .bind(fields.label_id)
+    .bind(fields.rule_id)
+    .bind(payload.active)
+    .bind(latest)
+    .bind(webhook_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(not_found("webhook"));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// Deletes a webhook with its
+/// delivery history.
+pub async fn delete_webhook(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(webhook_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "DELETE FROM webhooks WHERE id \
+       = $1 AND user_id = $2"
+    )
+    .bind(webhook_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "DELETE FROM webhooks WHERE id \
+       = ?1 AND user_id = ?2"
+    )
+    .bind(webhook_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(not_found("webhook"));
+  }
+
+  Ok(StatusCode::NO_CONTENT)
+}
+
+/// A webhook's deliveries, newest
+/// first, optionally of one status.
+pub async fn list_webhook_deliveries(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(webhook_id): AxumPath<i64>,
+  Query(query): Query<
+    WebhookDeliveryQuery
+  >
+) -> Result<
+  Json<Vec<WebhookDeliveryRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  if !owns(
+    &state, "webhooks", webhook_id,
+    user_id
+  )
+  .await?
+  {
+    return Err(not_found("webhook"));
+  }
+
+  let status = query
+    .status
+    .as_deref()
+    .map(DeliveryStatus::parse)
+    .transpose()
+    .map_err(|e| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        e
+      )
+    })?;
+
+  deliveries(
+    &state,
+    user_id,
+    Some(webhook_id),
+    status,
+    &query
+  )
+  .await
+}
+
+/// Deliveries of any of the user's
+/// webhooks that ran out of attempts,
+/// newest first.
+pub async fn list_dead_letters(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  Query(query): Query<
+    WebhookDeliveryQuery
+  >
+) -> Result<
+  Json<Vec<WebhookDeliveryRow>>,
+  ServerError
+> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  deliveries(
+    &state,
+    user_id,
+    None,
+    Some(DeliveryStatus::Dead),
+    &query
+  )
+  .await
+}
+
+/// Sends a delivery that is dead or
+/// was delivered again, with a fresh
+/// set of attempts.
+pub async fn redeliver(
+  State(state): State<AppState>,
+  headers: HeaderMap,
+  AxumPath(delivery_id): AxumPath<i64>
+) -> Result<StatusCode, ServerError> {
+  let user_id =
+    auth_user_id(&state, &headers)
+      .await?;
+
+  let pending =
+    DeliveryStatus::Pending.as_str();
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query(
+      "UPDATE webhook_deliveries SET \
+       status = $1, attempts = 0, \
+       next_attempt_at = NOW(), \
+       delivered_at = NULL WHERE id = \
+       $2 AND status <> $1 AND \
+       webhook_id IN (SELECT id FROM \
+       webhooks WHERE user_id = $3)"
+    )
+    .bind(pending)
+    .bind(delivery_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "UPDATE webhook_deliveries SET \
+       status = ?1, attempts = 0, \
+       next_attempt_at = \
+       datetime('now'), delivered_at \
+       = NULL WHERE id = ?2 AND \
+       status <> ?1 AND webhook_id IN \
+       (SELECT id FROM webhooks WHERE \
+       user_id = ?3)"
+    )
+    .bind(pending)
+    .bind(delivery_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .map_err(query_error)?
+    .rows_affected()
+  };
+
+  if rows == 0 {
+    return Err(ServerError::new(
+      StatusCode::NOT_FOUND,
+      "delivery not found or already \
+       pending"
+    ));
+  }
+
+  Ok(StatusCode::ACCEPTED)
+}
+
+/// The user's deliveries, of one
+/// webhook and status when given.
+async fn deliveries(
+  state: &AppState,
+  user_id: i64,
+  webhook_id: Option<i64>,
+  status: Option<DeliveryStatus>,
+  query: &WebhookDeliveryQuery
+) -> Result<
+  Json<Vec<WebhookDeliveryRow>>,
+  ServerError
+> {
+  let limit = query
+    .limit
+    .unwrap_or(DEFAULT_LIMIT)
+    .clamp(1, MAX_LIMIT)
+    as i64;
+
+  let status =
+    status.map(|s| s.as_str());
+
+  let rows = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<
+      _,
+      WebhookDeliveryRow
+    >(
+      "SELECT d.id, d.webhook_id, \
+       d.event, d.entry_count, \
+       d.status, d.attempts, \
+       d.next_attempt_at::text AS \
+       next_attempt_at, d.last_status, \
+       d.last_error, d.created_at::text \
+       AS created_at, \
+       d.delivered_at::text AS \
+       delivered_at FROM \
+       webhook_deliveries d JOIN \
+       webhooks w ON w.id = \
+       d.webhook_id WHERE w.user_id = \
+       $1 AND ($2::BIGINT IS NULL OR \
+       d.webhook_id = $2) AND \
+       ($3::TEXT IS NULL OR d.status = \
+       $3) AND ($4::BIGINT IS NULL OR \
+       d.id < $4) ORDER BY d.id DESC \
+       LIMIT $5"
+    )
+    .bind(user_id)
+    .bind(webhook_id)
+    .bind(status)
+    .bind(query.before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<
+      _,
+      WebhookDeliveryRow
+    >(
+      "SELECT d.id, d.webhook_id, \
+       d.event, d.entry_count, \
+       d.status, d.attempts, \
+       d.next_attempt_at, \
+       d.last_status, d.last_error, \
+       d.created_at, d.delivered_at \
+       FROM webhook_deliveries d JOIN \
+       webhooks w ON w.id = \
+       d.webhook_id WHERE w.user_id = \
+       ?1 AND (?2 IS NULL OR \
+       d.webhook_id = ?2) AND (?3 IS \
+       NULL OR d.status = ?3) AND (?4 \
+       IS NULL OR d.id < ?4) ORDER BY \
+       d.id DESC LIMIT ?5"
+    )
+    .bind(user_id)
+    .bind(webhook_id)
+    .bind(status)
+    .bind(query.before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(Json(rows))
+}
+
+/// Checks a webhook request: an
+/// http(s) URL of a public host, a
+/// non-empty secret if
+/// one is given, and filters naming
+/// the user's own subscription,
+/// folder, label and rule.
+async fn webhook_fields<'a>(
+  state: &AppState,
+  user_id: i64,
+  payload: &'a WebhookRequest
+) -> Result<
+  WebhookFields<'a>,
+  ServerError
+> {
+  let bad_request = |message: &str| {
+    ServerError::new(
+      StatusCode::BAD_REQUEST,
+      message
+    )
+  };
+
+  let url = payload.url.trim();
+
+  if url_domain(url).is_none() {
+    return Err(bad_request(
+      "url must be an http or https \
+       URL"
+    ));
+  }
+
+  if check_public_url(url)
+    .await
+    .is_err()
+  {
+    return Err(bad_request(
+      "url must reach a public address"
+    ));
+  }
+
+  let secret = payload
+    .secret
+    .as_deref()
+    .map(str::trim);
+
+  if secret.is_some_and(str::is_empty) {
+    return Err(bad_request(
+      "secret must not be empty"
+    ));
+  }
+
+  let feed_id = payload
+    .feed_id
+    .as_deref()
+    .map(str::trim);
+
+  if let Some(feed_id) = feed_id
+    && !subscribed(
+      state, user_id, feed_id
+    )
+    .await?
+  {
+    return Err(not_found(
+      "subscription"
+    ));
+  }
+
+  for (table, what, id) in [
+    (
+      "folders",
+      "folder",
+      payload.folder_id
+    ),
+    (
+      "labels",
+      "label",
+      payload.label_id
+    ),
+    (
+      "filter_rules",
+      "rule",
+      payload.rule_id
+    )
+  ] {
+    if let Some(id) = id
+      && !owns(
+        state, table, id, user_id
+      )
+      .await?
+    {
+      return Err(not_found(what));
+    }
+  }
+
+  Ok(WebhookFields {
+    url,
+    secret,
+    feed_id,
+    folder_id: payload.folder_id,
+    label_id: payload.label_id,
+    rule_id: payload.rule_id
+  })
+}
+
+async fn subscribed(
+  state: &AppState,
+  user_id: i64,
+  feed_id: &str
+) -> Result<bool, ServerError> {
+  let found = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, String>(
+      "SELECT feed_id FROM \
+       subscriptions WHERE user_id = $1 \
+       AND feed_id = $2"
+    )
+    .bind(user_id)
+    .bind(feed_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, String>(
+      "SELECT feed_id FROM \
+       subscriptions WHERE user_id = ?1 \
+       AND feed_id = ?2"
+    )
+    .bind(user_id)
+    .bind(feed_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(found.is_some())
+}
+
+/// Whether the user owns row `id` of
+/// `table`, one of this module's
+/// fixed names.
+async fn owns(
+  state: &AppState,
+  table: &str,
+  id: i64,
+  user_id: i64
+) -> Result<bool, ServerError> {
+  let found = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_scalar::<_, i64>(
+      &format!(
+        "SELECT id FROM {table} WHERE \
+         id = $1 AND user_id = $2"
+      )
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_scalar::<_, i64>(
+      &format!(
+        "SELECT id FROM {table} WHERE \
+         id = ?1 AND user_id = ?2"
+      )
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  Ok(found.is_some())
+}
+
+fn not_found(
+  what: &str
+) -> ServerError {
+  ServerError::new(
+    StatusCode::NOT_FOUND,
+    format!("{what} not found")
+  )
+}
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e.to_string()
+  )
+}
@@ -1,13 +1,20 @@
 mod app_state;
 mod auth;
+mod compat;
 mod config;
 mod db;
+mod entry_stream;
 mod errors;
+mod feed_registry;
+mod filter_rules;
 mod handlers;
 mod logging;
 mod models;
+mod pagination;
+mod realtime;
 mod schema;
 mod startup;
+mod webhooks;
 
 use crate::config::ConfigError;
 
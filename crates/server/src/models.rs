@@ -1,3 +1,4 @@
+use pulsewire_core::domain::realtime::UnreadChange;
 use serde::{
   Deserialize,
   Serialize
@@ -40,25 +41,44 @@ pub struct EntrySummary {
   pub title:           Option<String>,
   pub link:            Option<String>,
   pub published_at_ms: Option<i64>,
-  pub is_read:         bool
+  pub is_read:         bool,
+  pub is_starred:      bool,
+  pub is_queued:       bool
 }
 
 #[derive(Debug, Deserialize)]
 
 pub struct EntryListQuery {
+  pub scope:   Option<String>,
   pub read:    Option<String>,
+  pub starred: Option<bool>,
+  pub queued:  Option<bool>,
+  pub label:   Option<i64>,
   pub limit:   Option<u32>,
-  pub offset:  Option<u32>,
+  pub sort:    Option<String>,
+  pub cursor:  Option<String>,
   pub feed_id: Option<String>,
   pub since:   Option<i64>
 }
 
+/// `scope` parameter of unread counts.
+#[derive(Debug, Deserialize)]
+
+pub struct ScopeQuery {
+  pub scope: Option<String>
+}
+
 #[derive(Debug, Deserialize)]
 
 pub struct SearchQuery {
   pub q:       String,
+  pub scope:   Option<String>,
+  pub starred: Option<bool>,
+  pub queued:  Option<bool>,
+  pub label:   Option<i64>,
   pub limit:   Option<u32>,
-  pub offset:  Option<u32>,
+  pub sort:    Option<String>,
+  pub cursor:  Option<String>,
   pub feed_id: Option<String>,
   pub read:    Option<String>
 }
@@ -67,11 +87,39 @@ pub struct SearchQuery {
 
 pub struct EntryListResponse {
   pub items:       Vec<EntrySummary>,
-  pub next_cursor: Option<i64>,
-  pub next_offset: Option<i64>,
+  pub next_cursor: Option<String>,
+  pub prev_cursor: Option<String>,
   pub since:       Option<i64>
 }
 
+/// A search hit; a higher `rank` is
+/// more relevant and `snippet` wraps
+/// matches in `<mark>` tags.
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct SearchEntry {
+  pub id:              i64,
+  pub feed_id:         String,
+  pub title:           Option<String>,
+  pub link:            Option<String>,
+  pub published_at_ms: Option<i64>,
+  pub is_read:         bool,
+  pub is_starred:      bool,
+  pub is_queued:       bool,
+  pub rank:            f64,
+  pub snippet:         Option<String>
+}
+
+#[derive(Debug, Serialize)]
+
+pub struct SearchResponse {
+  pub items:       Vec<SearchEntry>,
+  pub next_cursor: Option<String>,
+  pub prev_cursor: Option<String>
+}
+
 #[derive(Debug, Deserialize)]
 
 pub struct EntryBatchRequest {
@@ -106,7 +154,8 @@ pub struct FavoriteUnreadCount {
 pub struct FolderEntriesQuery {
   pub read:   Option<String>,
   pub limit:  Option<u32>,
-  pub offset: Option<u32>,
+  pub sort:   Option<String>,
+  pub cursor: Option<String>,
   pub since:  Option<i64>
 }
 
@@ -144,7 +193,21 @@ pub struct EntryDetail {
   pub category:        Option<String>,
   pub description:     Option<String>,
   pub summary:         Option<String>,
-  pub is_read:         bool
+  pub is_read:         bool,
+  pub diff:            Option<String>,
+  #[sqlx(skip)]
+  pub changes: Option<EntryChanges>
+}
+
+/// Added and removed text of a watch
+/// change entry, parsed from its
+/// stored diff.
+#[derive(Debug, Default, Serialize)]
+
+pub struct EntryChanges {
+  pub added:     Vec<String>,
+  pub removed:   Vec<String>,
+  pub truncated: bool
 }
 
 #[derive(
@@ -177,20 +240,28 @@ pub struct UnreadCountResponse {
 )]
 
 pub struct FolderRow {
-  pub id:   i64,
-  pub name: String
+  pub id:     i64,
+  pub name:   String,
+  /// Entry filter of a smart folder;
+  /// `None` for a folder of feeds.
+  pub filter: Option<String>
 }
 
 #[derive(Debug, Deserialize)]
 
 pub struct FolderCreateRequest {
-  pub name: String
+  pub name:   String,
+  pub filter: Option<String>
 }
 
 #[derive(Debug, Deserialize)]
 
 pub struct FolderUpdateRequest {
-  pub name: String
+  pub name:   String,
+  /// Replaces the filter when given;
+  /// an empty one makes the folder a
+  /// folder of feeds again.
+  pub filter: Option<String>
 }
 
 #[derive(
@@ -225,6 +296,95 @@ pub struct FolderUnreadCount {
   pub unread_count: i64
 }
 
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct LabelRow {
+  pub id:   i64,
+  pub name: String
+}
+
+#[derive(Debug, Deserialize)]
+
+pub struct LabelRequest {
+  pub name: String
+}
+
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct LabelUnreadCount {
+  pub label_id:     i64,
+  pub unread_count: i64
+}
+
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct SavedSearchRow {
+  pub id:    i64,
+  pub name:  String,
+  pub query: String
+}
+
+#[derive(Debug, Deserialize)]
+
+pub struct SavedSearchRequest {
+  pub name:  String,
+  pub query: String
+}
+
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct RuleRow {
+  pub id:       i64,
+  pub name:     String,
+  pub filter:   String,
+  pub action:   String,
+  pub label_id: Option<i64>
+}
+
+/// `label_id` is required by, and only
+/// taken with, the `label` action.
+#[derive(Debug, Deserialize)]
+
+pub struct RuleRequest {
+  pub name:     String,
+  pub filter:   String,
+  pub action:   String,
+  pub label_id: Option<i64>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub struct RuleDryRunQuery {
+  pub filter: String,
+  pub limit:  Option<u32>,
+  pub cursor: Option<String>
+}
+
+#[derive(Debug, Serialize)]
+
+pub struct RuleApplyResponse {
+  /// Entries newly matched.
+  pub matched: i64
+}
+
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct RuleHitRow {
+  pub rule_id: i64,
+  pub name:    String,
+  pub action:  String
+}
+
 #[derive(Debug, Serialize)]
 
 pub struct UserResponse {
@@ -242,7 +402,16 @@ pub struct FavoriteRequest {
 
 pub struct FavoriteListQuery {
   pub limit:  Option<u32>,
-  pub offset: Option<u32>
+  pub sort:   Option<String>,
+  pub cursor: Option<String>
+}
+
+#[derive(Debug, Serialize)]
+
+pub struct FavoriteListResponse {
+  pub items:       Vec<FeedSummary>,
+  pub next_cursor: Option<String>,
+  pub prev_cursor: Option<String>
 }
 
 #[derive(Debug, Deserialize)]
@@ -273,7 +442,60 @@ pub struct TokenResponse {
 #[derive(Debug, Deserialize)]
 
 pub struct SubscriptionRequest {
-  pub feed_id: String
+  /// A feed the fetcher already polls.
+  pub feed_id: Option<String>,
+  /// A feed or a page linking to one,
+  /// added to the feed registry if
+  /// new. Used when `feed_id` is
+  /// missing.
+  pub url:     Option<String>
+}
+
+#[derive(Debug, Serialize)]
+
+pub struct SubscriptionCreated {
+  pub feed_id: String,
+  /// The feed waits for an admin; the
+  /// subscription starts on approval.
+  pub pending: bool
+}
+
+#[derive(Debug, Serialize)]
+
+pub struct OpmlImportResponse {
+  /// Outlines whose feed was already
+  /// polled or registered.
+  pub matched:  i64,
+  /// Outlines added to the feed
+  /// registry, `pending` of them
+  /// awaiting approval.
+  pub created:  i64,
+  pub pending:  i64,
+  pub failed:   i64,
+  /// Every feed outline in document
+  /// order.
+  pub outlines: Vec<OpmlOutlineResult>
+}
+
+#[derive(Debug, Serialize)]
+
+pub struct OpmlOutlineResult {
+  pub url:     String,
+  pub title:   Option<String>,
+  pub folder:  Option<String>,
+  /// `matched`, `created`, `pending`
+  /// or `failed`.
+  pub status:  &'static str,
+  pub feed_id: Option<String>,
+  pub error:   Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub struct FeverKeyRequest {
+  /// The user's password; the key is
+  /// derived from it and the username.
+  pub password: String
 }
 
 #[derive(
@@ -283,3 +505,229 @@ pub struct SubscriptionRequest {
 pub struct SubscriptionRow {
   pub feed_id: String
 }
+
+#[derive(Debug, Serialize)]
+
+pub struct RegistryFeedRow {
+  pub id:             String,
+  pub url:            String,
+  pub title:          Option<String>,
+  pub added_by:       i64,
+  pub status:         String,
+  pub created_at_ms:  i64,
+  pub reviewed_at_ms: Option<i64>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub struct RegistryQuery {
+  pub status: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+
+pub struct FeedQuotaRequest {
+  pub max_feeds: i64
+}
+
+#[derive(Debug, Serialize)]
+
+pub struct FeedQuotaRow {
+  pub user_id:   i64,
+  pub max_feeds: i64,
+  /// Feeds the user added that are
+  /// pending or approved.
+  pub used:      i64,
+  /// Set by an admin rather than the
+  /// configured default.
+  pub custom:    bool
+}
+
+#[derive(Debug, Deserialize)]
+
+pub struct HealthQuery {
+  pub events:   Option<u64>,
+  pub payloads: Option<u64>
+}
+
+/// `GET /v1/stream`; WebSocket clients
+/// that cannot set `Last-Event-ID`
+/// pass it here.
+#[derive(Debug, Deserialize)]
+
+pub struct StreamQuery {
+  pub last_event_id: Option<String>
+}
+
+/// A realtime event as sent over a
+/// WebSocket; over SSE the fields are
+/// the event's own.
+#[derive(Debug, Serialize)]
+
+pub struct StreamMessage {
+  pub id:    String,
+  pub event: &'static str,
+  pub data:  serde_json::Value
+}
+
+/// Per-feed unread counts when a
+/// stream opens or resets.
+#[derive(Debug, Serialize)]
+
+pub struct StreamCounts {
+  pub feeds: Vec<FeedUnreadCount>
+}
+
+/// New entries of the user's
+/// subscriptions.
+#[derive(Debug, Serialize)]
+
+pub struct StreamEntries {
+  pub entries: Vec<EntrySummary>
+}
+
+/// Feeds whose unread count changed.
+#[derive(Debug, Serialize)]
+
+pub struct StreamUnread {
+  pub feeds: Vec<UnreadChange>
+}
+
+/// Entries another session marked
+/// read or unread.
+#[derive(Debug, Serialize)]
+
+pub struct StreamReadChange {
+  pub item_ids: Vec<i64>,
+  pub read:     bool
+}
+
+/// A webhook's URL and filters. The
+/// secret is only shown when created.
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct WebhookRow {
+  pub id:         i64,
+  pub url:        String,
+  pub feed_id:    Option<String>,
+  pub folder_id:  Option<i64>,
+  pub label_id:   Option<i64>,
+  pub rule_id:    Option<i64>,
+  pub active:     bool,
+  pub created_at: String,
+  #[sqlx(skip)]
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub secret:     Option<String>
+}
+
+/// Filters left out match every entry
+/// of the user's subscriptions. A
+/// secret is generated when none is
+/// given, and kept on update.
+#[derive(Debug, Deserialize)]
+
+pub struct WebhookRequest {
+  pub url:       String,
+  pub secret:    Option<String>,
+  pub feed_id:   Option<String>,
+  pub folder_id: Option<i64>,
+  pub label_id:  Option<i64>,
+  pub rule_id:   Option<i64>,
+  pub active:    Option<bool>
+}
+
+/// `GET /v1/webhooks/{id}/deliveries`
+/// and the dead-letter view; newest
+/// first, before the `before` id.
+#[derive(Debug, Deserialize)]
+
+pub struct WebhookDeliveryQuery {
+  pub status: Option<String>,
+  pub before: Option<i64>,
+  pub limit:  Option<u32>
+}
+
+/// A delivery and how its attempts
+/// went.
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct WebhookDeliveryRow {
+  pub id:              i64,
+  pub webhook_id:      i64,
+  pub event:           String,
+  pub entry_count:     i64,
+  pub status:          String,
+  pub attempts:        i64,
+  pub next_attempt_at: String,
+  pub last_status:     Option<i64>,
+  pub last_error:      Option<String>,
+  pub created_at:      String,
+  pub delivered_at:    Option<String>
+}
+
+/// The JSON body of a new-entries
+/// delivery.
+#[derive(Debug, Serialize)]
+
+pub struct WebhookPayload {
+  pub event:      &'static str,
+  pub webhook_id: i64,
+  pub entries:    Vec<EntrySummary>
+}
+
+/// A published feed and what it
+/// serves. The token and URLs are only
+/// shown when created.
+#[derive(
+  Debug, Serialize, sqlx::FromRow,
+)]
+
+pub struct PublishedFeedRow {
+  pub id:         i64,
+  pub title:      String,
+  pub source:     String,
+  pub folder_id:  Option<i64>,
+  pub label_id:   Option<i64>,
+  pub search_id:  Option<i64>,
+  pub created_at: String,
+  #[sqlx(skip)]
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub token:      Option<String>,
+  #[sqlx(skip)]
+  #[serde(
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub urls: Option<PublishedFeedUrls>
+}
+
+/// Where a published feed is served in
+/// each format.
+#[derive(Debug, Serialize)]
+
+pub struct PublishedFeedUrls {
+  pub atom: String,
+  pub rss:  String,
+  pub json: String
+}
+
+/// Exactly one of a folder, label or
+/// saved search, or `starred`. The
+/// title defaults to the source's
+/// name.
+#[derive(Debug, Deserialize)]
+
+pub struct PublishedFeedRequest {
+  pub title:     Option<String>,
+  pub folder_id: Option<i64>,
+  pub label_id:  Option<i64>,
+  pub search_id: Option<i64>,
+  pub starred:   Option<bool>
+}
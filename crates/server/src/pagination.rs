@@ -0,0 +1,332 @@
+//! Keyset pagination for list
+//! endpoints. A page is cut on
+//! `(sort key, id)` and handed out as
+//! signed cursor tokens, so deep pages
+//! cost the same as the first and rows
+//! arriving between requests neither
+//! repeat nor get skipped.
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use pulsewire_core::domain::cursor::{
+  Cursor,
+  Direction,
+  SortKey,
+  SortOrder
+};
+use sqlx::{
+  Database,
+  Encode,
+  QueryBuilder,
+  Type
+};
+
+use crate::app_state::AppState;
+use crate::errors::ServerError;
+
+/// Sort orders of lists keyed on a
+/// time; the first is the default.
+pub const DATED_SORTS: &[SortOrder] = &[
+  SortOrder::Newest,
+  SortOrder::Oldest
+];
+
+/// Sort orders of search results.
+pub const SEARCH_SORTS: &[SortOrder] =
+  &[
+    SortOrder::Relevance,
+    SortOrder::Newest,
+    SortOrder::Oldest
+  ];
+
+/// How a list's sort key and id read
+/// in SQL. `bind` wraps the bound key
+/// around `{}` to turn cursor millis
+/// back into the column's type.
+pub struct Keyset {
+  pub key:  &'static str,
+  pub bind:
+    (&'static str, &'static str),
+  pub id:   &'static str
+}
+
+/// Feed items on SQLite.
+pub const SQLITE_ITEMS: Keyset =
+  Keyset {
+    key:  "COALESCE(fi.\
+           published_at_ms, 0)",
+    bind: ("", ""),
+    id:   "fi.id"
+  };
+
+/// Feed items on Postgres; matches the
+/// `idx_feed_items_published` index.
+pub const POSTGRES_ITEMS: Keyset =
+  Keyset {
+    key:  "COALESCE(fi.published_at, \
+           TIMESTAMPTZ 'epoch')",
+    bind: (
+      "TIMESTAMPTZ 'epoch' + ",
+      " * INTERVAL '1 millisecond'"
+    ),
+    id:   "fi.id"
+  };
+
+/// One page request: sort order, size
+/// and the cursor it continues from.
+pub struct Page {
+  pub sort:  SortOrder,
+  pub limit: i64,
+  cursor:    Option<Cursor>,
+  secret:    Arc<[u8]>
+}
+
+/// Rows of a page with the cursors to
+/// its neighbours.
+pub struct Paged<T> {
+  pub items:       Vec<T>,
+  pub next_cursor: Option<String>,
+  pub prev_cursor: Option<String>
+}
+
+impl Page {
+  /// Reads `sort`, `cursor` and
+  /// `limit` parameters. A cursor
+  /// carries its sort order, so `sort`
+  /// may be left out when paging.
+  pub fn new(
+    state: &AppState,
+    sort: Option<&str>,
+    cursor: Option<&str>,
+    limit: Option<u32>,
+    sorts: &[SortOrder]
+  ) -> Result<Self, ServerError> {
+    let bad_request = |message| {
+      ServerError::new(
+        StatusCode::BAD_REQUEST,
+        message
+      )
+    };
+
+    let cursor = cursor
+      .filter(|c| !c.is_empty())
+      .map(|c| {
+        Cursor::decode(
+          c,
+          &state.cursor_secret
+        )
+      })
+      .transpose()
+      .map_err(bad_request)?;
+
+    let requested = sort
+      .map(SortOrder::parse)
+      .transpose()
+      .map_err(bad_request)?;
+
+    let sort =
+      match (&cursor, requested) {
+        | (
+          Some(cursor),
+          Some(sort)
+        ) if cursor.sort != sort => {
+          return Err(bad_request(
+            format!(
+              "cursor was issued for \
+               sort {}",
+              cursor.sort.as_str()
+            )
+          ));
+        }
+        | (Some(cursor), _) => {
+          cursor.sort
+        }
+        | (None, Some(sort)) => sort,
+        | (None, None) => sorts[0]
+      };
+
+    if !sorts.contains(&sort) {
+      return Err(bad_request(format!(
+        "sort {} is not supported here",
+        sort.as_str()
+      )));
+    }
+
+    Ok(Self {
+      sort,
+      limit: limit
+        .unwrap_or(50)
+        .min(200) as i64,
+      cursor,
+      secret: state
+        .cursor_secret
+        .clone()
+    })
+  }
+
+  fn backward(&self) -> bool {
+    self.cursor.as_ref().is_some_and(
+      |c| {
+        c.direction == Direction::Before
+      }
+    )
+  }
+
+  /// Direction to scan in SQL; a
+  /// backward page scans against the
+  /// sort order.
+  pub fn order(&self) -> &'static str {
+    if self.sort.descending()
+      != self.backward()
+    {
+      "DESC"
+    } else {
+      "ASC"
+    }
+  }
+
+  /// One more row than the page holds,
+  /// to tell whether another follows.
+  pub fn fetch_limit(&self) -> i64 {
+    self.limit + 1
+  }
+
+  /// Pushes ` AND (key, id) < (..)`
+  /// (or `>`) for the cursor, if any.
+  /// `int_id` binds the id as an
+  /// integer rather than text.
+  pub fn push_keyset<'a, DB>(
+    &self,
+    builder: &mut QueryBuilder<'a, DB>,
+    keyset: &Keyset,
+    int_id: bool
+  ) -> Result<(), ServerError>
+  where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    f64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>
+  {
+    let Some(cursor) = &self.cursor
+    else {
+      return Ok(());
+    };
+
+    let op = if self.order() == "DESC" {
+      "<"
+    } else {
+      ">"
+    };
+
+    builder.push(format!(
+      " AND ({}, {}) {op} ({}",
+      keyset.key,
+      keyset.id,
+      keyset.bind.0
+    ));
+
+    match cursor.key {
+      | SortKey::Millis(ms) => {
+        builder.push_bind(ms)
+      }
+      | SortKey::Rank(rank) => {
+        builder.push_bind(rank)
+      }
+    };
+
+    builder.push(format!(
+      "{}, ",
+      keyset.bind.1
+    ));
+
+    if int_id {
+      builder.push_bind(
+        cursor.int_id().map_err(
+          |e| {
+            ServerError::new(
+              StatusCode::BAD_REQUEST,
+              e
+            )
+          }
+        )?
+      );
+    } else {
+      builder
+        .push_bind(cursor.id.clone());
+    }
+
+    builder.push(")");
+
+    Ok(())
+  }
+
+  /// Trims the look-ahead row, restores
+  /// sort order and cuts cursors at the
+  /// first and last rows.
+  pub fn finish<T>(
+    &self,
+    mut rows: Vec<T>,
+    cut: impl Fn(&T) -> (SortKey, String)
+  ) -> Paged<T> {
+    let more =
+      rows.len() as i64 > self.limit;
+
+    rows.truncate(self.limit as usize);
+
+    let (has_next, has_prev) =
+      match &self.cursor {
+        | None => (more, false),
+        | Some(c)
+          if c.direction
+            == Direction::Before =>
+        {
+          rows.reverse();
+
+          (true, more)
+        }
+        | Some(_) => (more, true)
+      };
+
+    let token = |row: &T, direction| {
+      let (key, id) = cut(row);
+
+      Cursor {
+        sort: self.sort,
+        direction,
+        key,
+        id
+      }
+      .encode(&self.secret)
+    };
+
+    Paged {
+      next_cursor: rows
+        .last()
+        .filter(|_| has_next)
+        .map(|r| {
+          token(r, Direction::After)
+        }),
+      prev_cursor: rows
+        .first()
+        .filter(|_| has_prev)
+        .map(|r| {
+          token(r, Direction::Before)
+        }),
+      items:       rows
+    }
+  }
+}
+
+/// Cursor cut of a feed item.
+pub fn item_cut(
+  published_at_ms: Option<i64>,
+  id: i64
+) -> (SortKey, String) {
+  (
+    SortKey::Millis(
+      published_at_ms.unwrap_or(0)
+    ),
+    id.to_string()
+  )
+}
@@ -0,0 +1,223 @@
+//! Signals for realtime streams. New
+//! items are announced by the fetcher
+//! over `LISTEN/NOTIFY` on Postgres
+//! and found by polling on SQLite;
+//! read-state changes are published
+//! by the handlers that make them and
+//! kept in a short log for clients
+//! that resume.
+
+use std::sync::{
+  Arc,
+  Mutex
+};
+use std::time::Duration;
+
+use pulsewire_core::domain::realtime::{
+  EventLog,
+  ITEMS_CHANNEL
+};
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+
+use crate::app_state::AppState;
+use crate::filter_rules::latest_item_id;
+
+/// Entries a session marked read or
+/// unread. `session` is the hash of
+/// its token, when it has one.
+#[derive(Debug)]
+
+pub struct ReadChange {
+  pub user_id:  i64,
+  pub session:  Option<String>,
+  pub item_ids: Vec<i64>,
+  pub read:     bool
+}
+
+#[derive(Debug, Clone)]
+
+pub enum Signal {
+  /// The fetcher stored new items.
+  Items,
+  /// A read-state change and its
+  /// number in the log.
+  Read(u64, Arc<ReadChange>)
+}
+
+/// Fans signals out to open streams.
+pub struct Realtime {
+  sender: broadcast::Sender<Signal>,
+  log: Mutex<EventLog<Arc<ReadChange>>>
+}
+
+impl Realtime {
+  /// Numbers start at the current time
+  /// in microseconds, so they keep
+  /// growing across restarts.
+  pub fn new(backlog: usize) -> Self {
+    let (sender, _) =
+      broadcast::channel(256);
+
+    let first = chrono::Utc::now()
+      .timestamp_micros()
+      .max(1) as u64;
+
+    Self {
+      sender,
+      log: Mutex::new(EventLog::new(
+        first, backlog
+      ))
+    }
+  }
+
+  /// Subscribes to signals, with the
+  /// number of the latest read-state
+  /// change already published.
+  pub fn subscribe(
+    &self
+  ) -> (broadcast::Receiver<Signal>, u64)
+  {
+    let log = self.lock();
+
+    (
+      self.sender.subscribe(),
+      log.last()
+    )
+  }
+
+  /// The latest read-state change.
+  pub fn last_seq(&self) -> u64 {
+    self.lock().last()
+  }
+
+  /// Read-state changes after `seq`,
+  /// or `None` if some were dropped.
+  pub fn read_since(
+    &self,
+    seq: u64
+  ) -> Option<Vec<(u64, Arc<ReadChange>)>>
+  {
+    self.lock().since(seq)
+  }
+
+  pub fn items_stored(&self) {
+    // No open streams is not an error.
+    let _ =
+      self.sender.send(Signal::Items);
+  }
+
+  pub fn read_changed(
+    &self,
+    change: ReadChange
+  ) {
+    if change.item_ids.is_empty() {
+      return;
+    }
+
+    let change = Arc::new(change);
+
+    // Sending under the lock keeps
+    // signals in log order.
+    let mut log = self.lock();
+    let seq = log.push(change.clone());
+
+    let _ = self
+      .sender
+      .send(Signal::Read(seq, change));
+  }
+
+  fn lock(
+    &self
+  ) -> std::sync::MutexGuard<
+    '_,
+    EventLog<Arc<ReadChange>>
+  > {
+    self.log.lock().unwrap_or_else(
+      |e| e.into_inner()
+    )
+  }
+}
+
+/// Starts watching for new items:
+/// listening on `ITEMS_CHANNEL` on
+/// Postgres, polling the newest item
+/// id every `poll` on SQLite.
+pub fn spawn_item_watch(
+  state: AppState,
+  poll: Duration
+) {
+  if let Some(pool) =
+    state.postgres.clone()
+  {
+    tokio::spawn(async move {
+      loop {
+        match listen(&state, &pool)
+          .await
+        {
+          | Ok(()) => return,
+          | Err(e) => {
+            tracing::warn!(
+              error = %e,
+              "item notifications \
+               interrupted"
+            );
+
+            tokio::time::sleep(poll)
+              .await;
+          }
+        }
+      }
+    });
+
+    return;
+  }
+
+  tokio::spawn(async move {
+    let mut latest = None;
+
+    loop {
+      match latest_item_id(&state).await
+      {
+        | Ok(id) => {
+          if latest.is_some_and(
+            |seen| id > seen
+          ) {
+            state
+              .realtime
+              .items_stored();
+          }
+
+          latest = Some(id);
+        }
+        | Err(e) => {
+          tracing::warn!(
+            error = e.message(),
+            "item poll failed"
+          );
+        }
+      }
+
+      tokio::time::sleep(poll).await;
+    }
+  });
+}
+
+async fn listen(
+  state: &AppState,
+  pool: &sqlx::PgPool
+) -> Result<(), sqlx::Error> {
+  let mut listener =
+    PgListener::connect_with(pool)
+      .await?;
+
+  listener
+    .listen(ITEMS_CHANNEL)
+    .await?;
+
+  loop {
+    listener.recv().await?;
+
+    state.realtime.items_stored();
+  }
+}
@@ -1,11 +1,12 @@
-use std::path::Path;
-
-use sqlx::{
-  Pool,
-  Postgres,
-  Sqlite
+//! Applies the server's numbered
+//! migrations after checking the
+//! fetcher schema is not newer than
+//! this build.
+
+use pulsewire_core::infra::migrations::{
+  self,
+  Component
 };
-use tokio::fs;
 
 use crate::app_state::AppState;
 use crate::config::{
@@ -18,18 +19,8 @@ use crate::db::quote_ident;
 
 pub async fn apply_server_schema(
   config: &ServerConfig,
-  state: &AppState,
-  config_path: &Path
+  state: &AppState
 ) -> Result<(), ConfigError> {
-  let base_dir = config_path
-    .parent()
-    .ok_or_else(|| {
-    ConfigError::Invalid(
-      "config path has no parent"
-        .into()
-    )
-  })?;
-
   match config.dialect()? {
     | SqlDialect::Sqlite => {
       let pool = state
@@ -42,26 +33,28 @@ pub async fn apply_server_schema(
           )
         })?;
 
-      let schema_path = base_dir
-        .join("sql")
-        .join("sqlite")
-        .join("schema.sql");
-
-      let content = fs::read_to_string(
-        &schema_path
+      migrations::sqlite::status(
+        pool,
+        Component::Fetcher
       )
       .await
-      .map_err(|_| {
-        ConfigError::Invalid(format!(
-          "schema not found at {}",
-          schema_path.display()
-        ))
-      })?;
-
-      execute_schema_sqlite(
-        pool, &content
-      )
-      .await?;
+      .and_then(|status| {
+        status.ensure_supported()
+      })
+      .map_err(ConfigError::Invalid)?;
+
+      let applied =
+        migrations::sqlite::apply_pending(
+          pool,
+          Component::Server
+        )
+        .await
+        .map_err(ConfigError::Invalid)?;
+
+      tracing::info!(
+        applied = applied.len(),
+        "server schema migrated"
+      );
     }
     | SqlDialect::Postgres => {
       let pool = state
@@ -94,104 +87,77 @@ pub async fn apply_server_schema(
           &pg.fetcher_schema
         )?;
 
-      let schema_path = base_dir
-        .join("sql")
-        .join("postgres")
-        .join("schema.sql");
+      let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| {
+          ConfigError::Invalid(format!(
+            "schema apply error: {e}"
+          ))
+        })?;
+
+      let fetcher_path = format!(
+        "SET search_path TO {}",
+        quote_ident(&fetcher_schema)
+      );
+
+      sqlx::query(&fetcher_path)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+          ConfigError::Invalid(format!(
+            "schema apply error: {e}"
+          ))
+        })?;
 
-      let content = fs::read_to_string(
-        &schema_path
+      migrations::postgres::status(
+        &mut conn,
+        Component::Fetcher
       )
       .await
-      .map_err(|_| {
-        ConfigError::Invalid(format!(
-          "schema not found at {}",
-          schema_path.display()
-        ))
-      })?;
-
-      execute_schema_postgres(
-        pool,
-        &content,
+      .and_then(|status| {
+        status.ensure_supported()
+      })
+      .map_err(ConfigError::Invalid)?;
+
+      migrations::postgres::set_server_search_path(
+        &mut conn,
         &schema,
         &fetcher_schema
       )
-      .await?;
-    }
-  }
-
-  Ok(())
-}
-
-async fn execute_schema_sqlite(
-  pool: &Pool<Sqlite>,
-  content: &str
-) -> Result<(), ConfigError> {
-  for stmt in content.split(';') {
-    let trimmed = stmt.trim();
-
-    if trimmed.is_empty() {
-      continue;
-    }
-
-    sqlx::query(trimmed)
-      .execute(pool)
       .await
-      .map_err(|e| {
-        ConfigError::Invalid(format!(
-          "schema apply error: {e}"
-        ))
-      })?;
-  }
-
-  Ok(())
-}
+      .map_err(ConfigError::Invalid)?;
+
+      let applied =
+        migrations::postgres::apply_pending(
+          &mut conn,
+          Component::Server
+        )
+        .await
+        .map_err(ConfigError::Invalid)?;
+
+      // The pooled connection keeps the
+      // search path; restore the one
+      // set on connect.
+      let server_path = format!(
+        "SET search_path TO {}",
+        quote_ident(&schema)
+      );
+
+      sqlx::query(&server_path)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+          ConfigError::Invalid(format!(
+            "schema apply error: {e}"
+          ))
+        })?;
 
-async fn execute_schema_postgres(
-  pool: &Pool<Postgres>,
-  content: &str,
-  schema: &str,
-  fetcher_schema: &str
-) -> Result<(), ConfigError> {
-  let mut conn = pool
-    .acquire()
-    .await
-    .map_err(|e| {
-    ConfigError::Invalid(format!(
-      "schema apply error: {e}"
-    ))
-  })?;
-
-  let search_stmt = format!(
-    "SET search_path TO {}, {}",
-    quote_ident(schema),
-    quote_ident(fetcher_schema)
-  );
-
-  sqlx::query(&search_stmt)
-    .execute(&mut *conn)
-    .await
-    .map_err(|e| {
-      ConfigError::Invalid(format!(
-        "schema apply error: {e}"
-      ))
-    })?;
-
-  for stmt in content.split(';') {
-    let trimmed = stmt.trim();
-
-    if trimmed.is_empty() {
-      continue;
+      tracing::info!(
+        applied = applied.len(),
+        "server schema migrated"
+      );
     }
-
-    sqlx::query(trimmed)
-      .execute(&mut *conn)
-      .await
-      .map_err(|e| {
-        ConfigError::Invalid(format!(
-          "schema apply error: {e}"
-        ))
-      })?;
   }
 
   Ok(())
@@ -1,5 +1,6 @@
 use std::net::SocketAddr;
 use std::path::Path;
+use std::time::Duration;
 
 use pulsewire_schemas::schema_dir;
 
@@ -12,7 +13,9 @@ use crate::{
   db,
   handlers,
   logging,
-  schema
+  realtime,
+  schema,
+  webhooks
 };
 
 pub async fn run()
@@ -67,9 +70,7 @@ pub async fn run()
   .await?;
 
   schema::apply_server_schema(
-    &config,
-    &state,
-    Path::new(&config_path)
+    &config, &state
   )
   .await?;
 
@@ -103,6 +104,18 @@ pub async fn run()
     ))
   })?;
 
+  realtime::spawn_item_watch(
+    state.clone(),
+    Duration::from_secs(
+      config.realtime.poll_seconds
+    )
+  );
+
+  webhooks::spawn_webhooks(
+    state.clone(),
+    config.webhooks.clone()
+  );
+
   let app = handlers::router(state);
 
   let listener =
@@ -0,0 +1,556 @@
+//! Runs users' webhooks. Each webhook
+//! checks new items of the user's
+//! subscriptions its filters match,
+//! queues them as deliveries of up to
+//! `batch_size` entries and sends due
+//! deliveries, retrying failed ones
+//! with backoff until they are dead.
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use pulsewire_core::app::webhook::{
+  Attempt,
+  Delivery,
+  deliver
+};
+use pulsewire_core::domain::stream::StreamScope;
+use pulsewire_core::domain::webhook::{
+  DeliveryStatus,
+  NEW_ENTRIES_EVENT,
+  RetryPolicy
+};
+
+use crate::app_state::AppState;
+use crate::config::WebhooksConfig;
+use crate::entry_stream::EntryStream;
+use crate::errors::ServerError;
+use crate::filter_rules::{
+  latest_item_id,
+  run_new_items
+};
+use crate::handlers::stream_entries;
+use crate::models::WebhookPayload;
+use crate::pagination::{
+  DATED_SORTS,
+  Page
+};
+
+/// Deliveries sent per pass.
+const SEND_LIMIT: i64 = 100;
+
+/// What matching a webhook needs.
+#[derive(sqlx::FromRow)]
+struct HookState {
+  id:              i64,
+  user_id:         i64,
+  feed_id:         Option<String>,
+  folder_id:       Option<i64>,
+  label_id:        Option<i64>,
+  rule_id:         Option<i64>,
+  checked_item_id: i64
+}
+
+/// What sending a delivery needs.
+#[derive(sqlx::FromRow)]
+struct DueDelivery {
+  id:       i64,
+  event:    String,
+  payload:  String,
+  attempts: i64,
+  url:      String,
+  secret:   String
+}
+
+/// Starts queueing and sending
+/// deliveries every `poll_seconds`.
+pub fn spawn_webhooks(
+  state: AppState,
+  config: WebhooksConfig
+) {
+  let policy = RetryPolicy {
+    max_attempts: config.max_attempts,
+    backoff:      Duration::from_secs(
+      config.backoff_seconds
+    ),
+    max_backoff:  Duration::from_secs(
+      config.max_backoff_seconds
+    )
+  };
+
+  tokio::spawn(async move {
+    loop {
+      if let Err(e) = queue_new_items(
+        &state,
+        config.batch_size
+      )
+      .await
+      {
+        tracing::warn!(
+          error = e.message(),
+          "webhook queueing failed"
+        );
+      }
+
+      if let Err(e) =
+        send_due(&state, &policy).await
+      {
+        tracing::warn!(
+          error = e.message(),
+          "webhook delivery failed"
+        );
+      }
+
+      tokio::time::sleep(
+        Duration::from_secs(
+          config.poll_seconds
+        )
+      )
+      .await;
+    }
+  });
+}
+
+/// Queues deliveries for items that
+/// arrived since each active webhook
+/// last checked.
+async fn queue_new_items(
+  state: &AppState,
+  batch_size: u32
+) -> Result<(), ServerError> {
+  let upto =
+    latest_item_id(state).await?;
+
+  let hooks = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, HookState>(
+      "SELECT id, user_id, feed_id, \
+       folder_id, label_id, rule_id, \
+       checked_item_id FROM webhooks \
+       WHERE paused_at IS NULL AND \
+       checked_item_id < $1 ORDER BY id"
+    )
+    .bind(upto)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, HookState>(
+      "SELECT id, user_id, feed_id, \
+       folder_id, label_id, rule_id, \
+       checked_item_id FROM webhooks \
+       WHERE paused_at IS NULL AND \
+       checked_item_id < ?1 ORDER BY id"
+    )
+    .bind(upto)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  for hook in hooks {
+    // One webhook failing must not
+    // hold up the others.
+    if let Err(e) = queue_hook(
+      state, &hook, upto, batch_size
+    )
+    .await
+    {
+      tracing::warn!(
+        webhook_id = hook.id,
+        error = e.message(),
+        "webhook check failed"
+      );
+    }
+  }
+
+  Ok(())
+}
+
+/// Queues the webhook's items after
+/// its checked mark up to `upto`,
+/// oldest first, and moves the mark.
+async fn queue_hook(
+  state: &AppState,
+  hook: &HookState,
+  upto: i64,
+  batch_size: u32
+) -> Result<(), ServerError> {
+  // Labels and rule hits of new items
+  // come from the user's rules.
+  run_new_items(state, hook.user_id)
+    .await?;
+
+  let mut stream = match hook.folder_id
+  {
+    | Some(folder_id) => {
+      EntryStream::folder(
+        state,
+        hook.user_id,
+        folder_id
+      )
+      .await?
+    }
+    | None => {
+      EntryStream::new(
+        hook.user_id,
+        StreamScope::Subscriptions
+      )
+    }
+  };
+
+  stream.feed_id = hook.feed_id.clone();
+  stream.marks.label = hook.label_id;
+  stream.marks.rule = hook.rule_id;
+  stream.since =
+    Some(hook.checked_item_id);
+  stream.before = Some(upto + 1);
+
+  let mut payloads = Vec::new();
+  let mut cursor = None;
+
+  loop {
+    let page = Page::new(
+      state,
+      Some("oldest"),
+      cursor.as_deref(),
+      Some(batch_size),
+      DATED_SORTS
+    )?;
+
+    let list = stream_entries(
+      state, &stream, &page
+    )
+    .await?
+    .0;
+
+    if !list.items.is_empty() {
+      let count =
+        list.items.len() as i64;
+
+      let payload = serde_json::to_string(
+        &WebhookPayload {
+          event:      NEW_ENTRIES_EVENT,
+          webhook_id: hook.id,
+          entries:    list.items
+        }
+      )
+      .map_err(|e| {
+        ServerError::new(
+          StatusCode::INTERNAL_SERVER_ERROR,
+          e.to_string()
+        )
+      })?;
+
+      payloads.push((payload, count));
+    }
+
+    match list.next_cursor {
+      | Some(next) => {
+        cursor = Some(next)
+      }
+      | None => break
+    }
+  }
+
+  store_deliveries(
+    state, hook, upto, &payloads
+  )
+  .await
+}
+
+/// Inserts the deliveries and moves
+/// the checked mark in one go; nothing
+/// is queued if the mark moved since
+/// it was read.
+async fn store_deliveries(
+  state: &AppState,
+  hook: &HookState,
+  upto: i64,
+  payloads: &[(String, i64)]
+) -> Result<(), ServerError> {
+  let pending =
+    DeliveryStatus::Pending.as_str();
+
+  if let Some(pool) = &state.postgres {
+    let mut tx = pool
+      .begin()
+      .await
+      .map_err(query_error)?;
+
+    let moved = sqlx::query(
+      "UPDATE webhooks SET \
+       checked_item_id = $1 WHERE id \
+       = $2 AND checked_item_id = $3"
+    )
+    .bind(upto)
+    .bind(hook.id)
+    .bind(hook.checked_item_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(query_error)?
+    .rows_affected();
+
+    if moved == 0 {
+      return Ok(());
+    }
+
+    for (payload, count) in payloads {
+      sqlx::query(
+        "INSERT INTO \
+         webhook_deliveries \
+         (webhook_id, event, payload, \
+         entry_count, status, \
+         next_attempt_at, created_at) \
+         VALUES ($1, $2, $3, $4, $5, \
+         NOW(), NOW())"
+      )
+      .bind(hook.id)
+      .bind(NEW_ENTRIES_EVENT)
+      .bind(payload)
+      .bind(count)
+      .bind(pending)
+      .execute(&mut *tx)
+      .await
+      .map_err(query_error)?;
+    }
+
+    tx.commit()
+      .await
+      .map_err(query_error)
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    let mut tx = pool
+      .begin()
+      .await
+      .map_err(query_error)?;
+
+    let moved = sqlx::query(
+      "UPDATE webhooks SET \
+       checked_item_id = ?1 WHERE id \
+       = ?2 AND checked_item_id = ?3"
+    )
+    .bind(upto)
+    .bind(hook.id)
+    .bind(hook.checked_item_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(query_error)?
+    .rows_affected();
+
+    if moved == 0 {
+      return Ok(());
+    }
+
+    for (payload, count) in payloads {
+      sqlx::query(
+        "INSERT INTO \
+         webhook_deliveries \
+         (webhook_id, event, payload, \
+         entry_count, status, \
+         next_attempt_at, created_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, \
+         datetime('now'), \
+         datetime('now'))"
+      )
+      .bind(hook.id)
+      .bind(NEW_ENTRIES_EVENT)
+      .bind(payload)
+      .bind(count)
+      .bind(pending)
+      .execute(&mut *tx)
+      .await
+      .map_err(query_error)?;
+    }
+
+    tx.commit()
+      .await
+      .map_err(query_error)
+  }
+}
+
+/// Sends pending deliveries that are
+/// due, oldest first, skipping those
+/// of paused webhooks.
+async fn send_due(
+  state: &AppState,
+  policy: &RetryPolicy
+) -> Result<(), ServerError> {
+  let due = if let Some(pool) =
+    &state.postgres
+  {
+    sqlx::query_as::<_, DueDelivery>(
+      "SELECT d.id, d.event, d.payload, \
+       d.attempts, w.url, w.secret FROM \
+       webhook_deliveries d JOIN \
+       webhooks w ON w.id = \
+       d.webhook_id WHERE d.status = $1 \
+       AND d.next_attempt_at <= NOW() \
+       AND w.paused_at IS NULL ORDER BY \
+       d.id LIMIT $2"
+    )
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(SEND_LIMIT)
+    .fetch_all(pool)
+    .await
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query_as::<_, DueDelivery>(
+      "SELECT d.id, d.event, d.payload, \
+       d.attempts, w.url, w.secret FROM \
+       webhook_deliveries d JOIN \
+       webhooks w ON w.id = \
+       d.webhook_id WHERE d.status = ?1 \
+       AND d.next_attempt_at <= \
+       datetime('now') AND w.paused_at \
+       IS NULL ORDER BY d.id LIMIT ?2"
+    )
+    .bind(DeliveryStatus::Pending.as_str())
+    .bind(SEND_LIMIT)
+    .fetch_all(pool)
+    .await
+  }
+  .map_err(query_error)?;
+
+  for delivery in due {
+    let attempt = deliver(
+      state.http.as_ref(),
+      &Delivery {
+        id:     delivery.id,
+        url:    &delivery.url,
+        secret: &delivery.secret,
+        event:  &delivery.event,
+        body:   delivery
+          .payload
+          .as_bytes()
+      },
+      chrono::Utc::now().timestamp()
+    )
+    .await;
+
+    record_attempt(
+      state, &delivery, &attempt,
+      policy
+    )
+    .await?;
+  }
+
+  Ok(())
+}
+
+/// Records an attempt: delivered, or
+/// pending again after the backoff,
+/// or dead once out of attempts.
+async fn record_attempt(
+  state: &AppState,
+  delivery: &DueDelivery,
+  attempt: &Attempt,
+  policy: &RetryPolicy
+) -> Result<(), ServerError> {
+  let attempts = delivery.attempts + 1;
+
+  let (status, delay) =
+    if attempt.delivered() {
+      (DeliveryStatus::Delivered, 0)
+    } else {
+      match policy
+        .next_delay(attempts as u32)
+      {
+        | Some(delay) => {
+          (
+            DeliveryStatus::Pending,
+            delay.as_secs() as i64
+          )
+        }
+        | None => {
+          (DeliveryStatus::Dead, 0)
+        }
+      }
+    };
+
+  if !attempt.delivered() {
+    tracing::warn!(
+      delivery_id = delivery.id,
+      attempts,
+      status = status.as_str(),
+      error = attempt.error.as_deref(),
+      "webhook attempt failed"
+    );
+  }
+
+  let last_status =
+    attempt.status.map(i64::from);
+
+  if let Some(pool) = &state.postgres {
+    sqlx::query(
+      "UPDATE webhook_deliveries SET \
+       status = $1, attempts = $2, \
+       last_status = $3, last_error = \
+       $4, next_attempt_at = NOW() + $5 \
+       * INTERVAL '1 second', \
+       delivered_at = CASE WHEN $6 THEN \
+       NOW() END WHERE id = $7"
+    )
+    .bind(status.as_str())
+    .bind(attempts)
+    .bind(last_status)
+    .bind(attempt.error.as_deref())
+    .bind(delay as f64)
+    .bind(attempt.delivered())
+    .bind(delivery.id)
+    .execute(pool)
+    .await
+    .map(|_| ())
+  } else {
+    let pool = state
+        .sqlite
+        .as_ref()
+        .ok_or_else(|| ServerError::new(StatusCode::INTERNAL_SERVER_ERROR, "database pool missing"))?;
+
+    sqlx::query(
+      "UPDATE webhook_deliveries SET \
+       status = ?1, attempts = ?2, \
+       last_status = ?3, last_error = \
+       ?4, next_attempt_at = \
+       datetime('now', '+' || ?5 || ' \
+       seconds'), delivered_at = CASE \
+       WHEN ?6 THEN datetime('now') END \
+       WHERE id = ?7"
+    )
+    .bind(status.as_str())
+    .bind(attempts)
+    .bind(last_status)
+    .bind(attempt.error.as_deref())
+    .bind(delay)
+    .bind(attempt.delivered())
+    .bind(delivery.id)
+    .execute(pool)
+    .await
+    .map(|_| ())
+  }
+  .map_err(query_error)?;
+
+  Ok(())
+}
+
+fn query_error(
+  e: sqlx::Error
+) -> ServerError {
+  ServerError::new(
+    StatusCode::INTERNAL_SERVER_ERROR,
+    e.to_string()
+  )
+}
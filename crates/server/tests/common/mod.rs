@@ -0,0 +1,508 @@
+//! A server binary on a fresh SQLite
+//! database: alice subscribes to feed
+//! `a` (items 1 and 2), bob to feed
+//! `b` (item 3). `start_postgres` runs
+//! the same fixture on the Postgres
+//! named by `PULSEWIRE_TEST_POSTGRES`.
+
+// Each test crate uses part of the
+// harness.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::process::{
+  Child,
+  Command,
+  Stdio
+};
+use std::time::Duration;
+
+use pulsewire_core::infra::migrations::{
+  self,
+  Component
+};
+use pulsewire_core::infra::sqlite_repo;
+use reqwest::StatusCode;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::json;
+use sqlx::{
+  Connection,
+  PgConnection,
+  PgPool,
+  SqlitePool
+};
+
+pub const FORM: &str =
+  "application/x-www-form-urlencoded";
+
+pub struct Server {
+  child:        Child,
+  pub dir:      PathBuf,
+  pub base:     String,
+  pub client:   reqwest::Client,
+  /// The fetcher schema, on Postgres.
+  pub postgres: Option<PgPool>
+}
+
+impl Drop for Server {
+  fn drop(&mut self) {
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+    let _ = std::fs::remove_dir_all(
+      &self.dir
+    );
+  }
+}
+
+/// A fresh directory for the config.
+fn temp_dir(name: &str) -> PathBuf {
+  let dir =
+    std::env::temp_dir().join(format!(
+      "pulsewire-server-{name}-{}",
+      std::process::id()
+    ));
+
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir)
+    .unwrap();
+
+  dir
+}
+
+/// Seeds the fetcher tables, starts the
+/// server and subscribes both users.
+pub async fn start(
+  name: &str
+) -> Server {
+  start_with(name, &[]).await
+}
+
+/// `start` with `config` lines added
+/// to the server config.
+pub async fn start_with(
+  name: &str,
+  config: &[&str]
+) -> Server {
+  let dir = temp_dir(name);
+
+  let pool = sqlite_repo::create_pool(
+    &dir.join("rss.db")
+  )
+  .await
+  .unwrap();
+
+  migrations::sqlite::apply_pending(
+    &pool,
+    Component::Fetcher
+  )
+  .await
+  .unwrap();
+
+  for sql in [
+    "INSERT INTO categories(name, \
+     created_at_ms) VALUES ('news', 0)",
+    "INSERT INTO feeds(id, url, \
+     domain, category, \
+     base_poll_seconds, \
+     created_at_ms) VALUES ('a', \
+     'https://a.example/rss', \
+     'a.example', 'news', 60, 0), \
+     ('b', 'https://b.example/rss', \
+     'b.example', 'news', 60, 0)",
+    "INSERT INTO feed_payloads(id, \
+     feed_id, fetched_at_ms) VALUES \
+     (1, 'a', 0), (2, 'b', 0)",
+    "INSERT INTO feed_items(id, \
+     payload_id, feed_id, title, \
+     published_at_ms) VALUES (1, 1, \
+     'a', 'a1', 1000), (2, 1, 'a', \
+     'a2', 2000), (3, 2, 'b', 'b1', \
+     3000)"
+  ] {
+    sqlx::query(sql)
+      .execute(&pool)
+      .await
+      .unwrap();
+  }
+
+  pool.close().await;
+
+  let database = [
+    "[database]",
+    "dialect = \"sqlite\"",
+    "[sqlite]",
+    "path = \"rss.db\""
+  ];
+
+  launch(
+    dir,
+    None,
+    &[&database[..], config].concat()
+  )
+  .await
+}
+
+/// The same fixture in the schemas
+/// `{name}` and `{name}_fetcher`, or
+/// `None` when
+/// `PULSEWIRE_TEST_POSTGRES`
+/// (a `postgres://` URL) is unset.
+pub async fn start_postgres(
+  name: &str
+) -> Option<Server> {
+  let url = std::env::var(
+    "PULSEWIRE_TEST_POSTGRES"
+  )
+  .ok()?;
+  let parsed =
+    reqwest::Url::parse(&url).unwrap();
+  let fetcher =
+    format!("{name}_fetcher");
+
+  let mut conn =
+    PgConnection::connect(&url)
+      .await
+      .unwrap();
+
+  for schema in [name, &fetcher] {
+    sqlx::query(&format!(
+      "DROP SCHEMA IF EXISTS {schema} \
+       CASCADE"
+    ))
+    .execute(&mut conn)
+    .await
+    .unwrap();
+  }
+
+  for sql in [
+    format!("CREATE SCHEMA {fetcher}"),
+    format!(
+      "SET search_path TO {fetcher}"
+    )
+  ] {
+    sqlx::query(&sql)
+      .execute(&mut conn)
+      .await
+      .unwrap();
+  }
+
+  migrations::postgres::apply_pending(
+    &mut conn,
+    Component::Fetcher
+  )
+  .await
+  .unwrap();
+
+  for sql in [
+    "INSERT INTO categories(name, \
+     created_at) VALUES ('news', \
+     to_timestamp(0))",
+    "INSERT INTO feeds(id, url, \
+     domain, category, \
+     base_poll_seconds, created_at) \
+     VALUES ('a', \
+     'https://a.example/rss', \
+     'a.example', 'news', 60, \
+     to_timestamp(0)), ('b', \
+     'https://b.example/rss', \
+     'b.example', 'news', 60, \
+     to_timestamp(0))",
+    "INSERT INTO feed_payloads(id, \
+     feed_id, fetched_at) VALUES (1, \
+     'a', to_timestamp(0)), (2, 'b', \
+     to_timestamp(0))",
+    "INSERT INTO feed_items(id, \
+     payload_id, feed_id, title, \
+     published_at) VALUES (1, 1, 'a', \
+     'a1', to_timestamp(1)), (2, 1, \
+     'a', 'a2', to_timestamp(2)), (3, \
+     2, 'b', 'b1', to_timestamp(3))"
+  ] {
+    sqlx::query(sql)
+      .execute(&mut conn)
+      .await
+      .unwrap();
+  }
+
+  conn.close().await.unwrap();
+
+  let pool = PgPool::connect(&format!(
+    "{url}?options=-csearch_path%\
+     3D{fetcher}"
+  ))
+  .await
+  .unwrap();
+
+  let server = launch(
+    temp_dir(name),
+    Some(pool),
+    &[
+      // The config schema asks for the
+      // SQLite section either way.
+      "[sqlite]",
+      "path = \"rss.db\"",
+      "[database]",
+      "dialect = \"postgres\"",
+      "[postgres]",
+      &format!(
+        "host = \"{}\"",
+        parsed.host_str().unwrap()
+      ),
+      &format!(
+        "port = {}",
+        parsed.port().unwrap_or(5432)
+      ),
+      &format!(
+        "database = \"{}\"",
+        parsed.path().trim_matches('/')
+      ),
+      &format!(
+        "user = \"{}\"",
+        parsed.username()
+      ),
+      &format!(
+        "password = \"{}\"",
+        parsed.password().unwrap_or("")
+      ),
+      "ssl_mode = \"disable\"",
+      &format!("schema = \"{name}\""),
+      &format!(
+        "fetcher_schema = \
+         \"{fetcher}\""
+      )
+    ]
+  )
+  .await;
+
+  Some(server)
+}
+
+/// Starts the server on `database`
+/// config lines and subscribes both
+/// users.
+async fn launch(
+  dir: PathBuf,
+  postgres: Option<PgPool>,
+  database: &[&str]
+) -> Server {
+  let port =
+    std::net::TcpListener::bind(
+      "127.0.0.1:0"
+    )
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port();
+
+  let config = dir.join("server.toml");
+
+  let toml = [
+    "[app]",
+    "mode = \"dev\"",
+    "[http]",
+    "host = \"127.0.0.1\"",
+    &format!("port = {port}")
+  ]
+  .into_iter()
+  .chain(database.iter().copied())
+  .chain([
+    "[logging]",
+    "level = \"error\"",
+    "[auth]",
+    "token_ttl_seconds = 3600",
+    "[dev]",
+    "reset_on_start = false",
+    "[seed]",
+    "username = \"alice\"",
+    "password = \"pw\""
+  ])
+  .collect::<Vec<_>>()
+  .join("\n");
+
+  std::fs::write(&config, toml)
+    .unwrap();
+
+  let child = Command::new(env!(
+    "CARGO_BIN_EXE_pulsewire-server"
+  ))
+  .env("SERVER_CONFIG_PATH", &config)
+  .stdout(Stdio::null())
+  .stderr(Stdio::null())
+  .spawn()
+  .unwrap();
+
+  let server = Server {
+    child,
+    dir,
+    base: format!(
+      "http://127.0.0.1:{port}"
+    ),
+    client: reqwest::Client::new(),
+    postgres
+  };
+
+  for _ in 0..100 {
+    if server
+      .client
+      .get(format!(
+        "{}/openapi.json",
+        server.base
+      ))
+      .send()
+      .await
+      .is_ok()
+    {
+      break;
+    }
+
+    tokio::time::sleep(
+      Duration::from_millis(100)
+    )
+    .await;
+  }
+
+  server
+    .client
+    .post(format!(
+      "{}/v1/users",
+      server.base
+    ))
+    .json(&json!({
+      "username": "bob",
+      "password": "pw"
+    }))
+    .send()
+    .await
+    .unwrap()
+    .error_for_status()
+    .unwrap();
+
+  for (user, feed) in
+    [("alice", "a"), ("bob", "b")]
+  {
+    let token =
+      server.login(user, "pw").await;
+
+    server
+      .client
+      .post(format!(
+        "{}/v1/subscriptions",
+        server.base
+      ))
+      .bearer_auth(token)
+      .json(&json!({ "feed_id": feed }))
+      .send()
+      .await
+      .unwrap()
+      .error_for_status()
+      .unwrap();
+  }
+
+  server
+}
+
+impl Server {
+  /// A ClientLogin token.
+  pub async fn login(
+    &self,
+    user: &str,
+    password: &str
+  ) -> String {
+    let (status, body) = self
+      .client_login(user, password)
+      .await;
+
+    assert_eq!(status, StatusCode::OK);
+
+    body
+      .lines()
+      .find_map(|line| {
+        line.strip_prefix("Auth=")
+      })
+      .unwrap()
+      .to_string()
+  }
+
+  pub async fn client_login(
+    &self,
+    user: &str,
+    password: &str
+  ) -> (StatusCode, String) {
+    let res = self
+      .client
+      .post(format!(
+        "{}/accounts/ClientLogin",
+        self.base
+      ))
+      .header(CONTENT_TYPE, FORM)
+      .body(format!(
+        "Email={user}&\
+         Passwd={password}"
+      ))
+      .send()
+      .await
+      .unwrap();
+
+    (
+      res.status(),
+      res.text().await.unwrap()
+    )
+  }
+
+  /// A GET as the token's user, or
+  /// anonymously.
+  pub async fn get(
+    &self,
+    token: Option<&str>,
+    path: &str
+  ) -> (StatusCode, String) {
+    let mut req = self.client.get(
+      format!("{}{path}", self.base)
+    );
+
+    if let Some(token) = token {
+      req = req.bearer_auth(token);
+    }
+
+    let res = req.send().await.unwrap();
+
+    (
+      res.status(),
+      res.text().await.unwrap()
+    )
+  }
+
+  /// A JSON POST as the token's user.
+  pub async fn post(
+    &self,
+    token: &str,
+    path: &str,
+    body: serde_json::Value
+  ) -> (StatusCode, String) {
+    let res = self
+      .client
+      .post(format!(
+        "{}{path}",
+        self.base
+      ))
+      .bearer_auth(token)
+      .json(&body)
+      .send()
+      .await
+      .unwrap();
+
+    (
+      res.status(),
+      res.text().await.unwrap()
+    )
+  }
+
+  /// The database the server runs on.
+  pub async fn db(&self) -> SqlitePool {
+    sqlite_repo::create_pool(
+      &self.dir.join("rss.db")
+    )
+    .await
+    .unwrap()
+  }
+}
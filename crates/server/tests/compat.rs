@@ -0,0 +1,353 @@
+//! The Google Reader and Fever APIs,
+//! through the server harness.
+
+mod common;
+
+use common::{
+  FORM,
+  start
+};
+use pulsewire_core::domain::compat::fever::api_key;
+use reqwest::StatusCode;
+use reqwest::header::{
+  AUTHORIZATION,
+  CONTENT_TYPE
+};
+use serde_json::{
+  Value,
+  json
+};
+
+const STARRED: &str =
+  "user/-/state/com.google/starred";
+
+impl common::Server {
+  /// POSTs a form to a Google Reader
+  /// endpoint as the token's user.
+  async fn reader(
+    &self,
+    token: Option<&str>,
+    path: &str,
+    form: &str
+  ) -> (StatusCode, String) {
+    let mut req = self
+      .client
+      .post(format!(
+        "{}/reader/api/0/{path}",
+        self.base
+      ))
+      .header(CONTENT_TYPE, FORM)
+      .body(form.to_string());
+
+    if let Some(token) = token {
+      req = req.header(
+        AUTHORIZATION,
+        format!(
+          "GoogleLogin auth={token}"
+        )
+      );
+    }
+
+    let res = req.send().await.unwrap();
+
+    (
+      res.status(),
+      res.text().await.unwrap()
+    )
+  }
+
+  /// Item titles of a Google Reader
+  /// item list.
+  async fn reader_titles(
+    &self,
+    token: &str,
+    path: &str,
+    form: &str
+  ) -> Vec<String> {
+    let (status, body) = self
+      .reader(Some(token), path, form)
+      .await;
+
+    assert_eq!(
+      status,
+      StatusCode::OK,
+      "{body}"
+    );
+
+    let body: Value =
+      serde_json::from_str(&body)
+        .unwrap();
+
+    body["items"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|item| {
+        item["title"]
+          .as_str()
+          .unwrap()
+          .to_string()
+      })
+      .collect()
+  }
+
+  /// Sets the user's Fever key and
+  /// returns it.
+  async fn fever_key(
+    &self,
+    user: &str
+  ) -> String {
+    let token =
+      self.login(user, "pw").await;
+
+    self
+      .client
+      .put(format!(
+        "{}/v1/auth/fever",
+        self.base
+      ))
+      .bearer_auth(token)
+      .json(
+        &json!({ "password": "pw" })
+      )
+      .send()
+      .await
+      .unwrap()
+      .error_for_status()
+      .unwrap();
+
+    api_key(user, "pw")
+  }
+
+  /// A Fever API call with `query`
+  /// after `?api&`.
+  async fn fever(
+    &self,
+    key: &str,
+    query: &str
+  ) -> Value {
+    self
+      .client
+      .post(format!(
+        "{}/fever/?api&{query}",
+        self.base
+      ))
+      .header(CONTENT_TYPE, FORM)
+      .body(format!("api_key={key}"))
+      .send()
+      .await
+      .unwrap()
+      .error_for_status()
+      .unwrap()
+      .json()
+      .await
+      .unwrap()
+  }
+}
+
+#[tokio::test]
+async fn reader_rejects_bad_credentials()
+ {
+  let server =
+    start("reader-auth").await;
+
+  let (status, body) = server
+    .client_login("alice", "wrong")
+    .await;
+
+  assert_eq!(
+    status,
+    StatusCode::UNAUTHORIZED
+  );
+  assert_eq!(
+    body,
+    "Error=BadAuthentication\n"
+  );
+
+  for token in [None, Some("forged")] {
+    let (status, _) = server
+      .reader(
+        token,
+        "stream/items/contents",
+        "i=1"
+      )
+      .await;
+
+    assert_eq!(
+      status,
+      StatusCode::UNAUTHORIZED
+    );
+  }
+}
+
+#[tokio::test]
+async fn reader_serves_only_the_users_items()
+ {
+  let server =
+    start("reader-scope").await;
+
+  let alice =
+    server.login("alice", "pw").await;
+  let bob =
+    server.login("bob", "pw").await;
+
+  assert_eq!(
+    server
+      .reader_titles(
+        &alice,
+        "stream/items/contents",
+        "i=1&i=3"
+      )
+      .await,
+    vec!["a1"]
+  );
+  assert_eq!(
+    server
+      .reader_titles(
+        &bob,
+        "stream/items/contents",
+        "i=1&i=2&i=3"
+      )
+      .await,
+    vec!["b1"]
+  );
+
+  // Bob cannot star his way into
+  // alice's feed.
+  let (status, _) = server
+    .reader(
+      Some(&bob),
+      "edit-tag",
+      &format!("i=1&a={STARRED}")
+    )
+    .await;
+
+  assert_eq!(status, StatusCode::OK);
+  assert!(
+    server
+      .reader_titles(
+        &bob,
+        &format!(
+          "stream/contents/{STARRED}"
+        ),
+        ""
+      )
+      .await
+      .is_empty()
+  );
+
+  // Alice's stars outlive her
+  // subscription.
+  server
+    .reader(
+      Some(&alice),
+      "edit-tag",
+      &format!("i=2&a={STARRED}")
+    )
+    .await;
+  server
+    .client
+    .delete(format!(
+      "{}/v1/subscriptions/a",
+      server.base
+    ))
+    .bearer_auth(&alice)
+    .send()
+    .await
+    .unwrap()
+    .error_for_status()
+    .unwrap();
+
+  assert_eq!(
+    server
+      .reader_titles(
+        &alice,
+        &format!(
+          "stream/contents/{STARRED}"
+        ),
+        ""
+      )
+      .await,
+    vec!["a2"]
+  );
+  assert_eq!(
+    server
+      .reader_titles(
+        &alice,
+        "stream/items/contents",
+        "i=1&i=2"
+      )
+      .await,
+    vec!["a2"]
+  );
+}
+
+#[tokio::test]
+async fn fever_rejects_bad_keys() {
+  let server =
+    start("fever-auth").await;
+
+  server.fever_key("alice").await;
+
+  for key in [
+    "",
+    "0123456789abcdef0123456789abcdef"
+  ] {
+    let res = server
+      .fever(key, "items&with_ids=1")
+      .await;
+
+    assert_eq!(res["auth"], 0);
+    assert!(res.get("items").is_none());
+  }
+}
+
+#[tokio::test]
+async fn fever_serves_only_the_users_items()
+ {
+  let server =
+    start("fever-scope").await;
+
+  let alice =
+    server.fever_key("alice").await;
+  let bob =
+    server.fever_key("bob").await;
+
+  let ids = |res: &Value| {
+    res["items"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|item| {
+        item["id"].as_i64().unwrap()
+      })
+      .collect::<Vec<_>>()
+  };
+
+  let res = server
+    .fever(&alice, "items&with_ids=1,3")
+    .await;
+
+  assert_eq!(res["auth"], 1);
+  assert_eq!(ids(&res), vec![1]);
+  assert_eq!(res["total_items"], 2);
+
+  let res = server
+    .fever(&bob, "items&with_ids=1,2,3")
+    .await;
+
+  assert_eq!(ids(&res), vec![3]);
+
+  server
+    .fever(
+      &bob,
+      "mark=item&as=saved&id=1"
+    )
+    .await;
+
+  let res = server
+    .fever(&bob, "saved_item_ids")
+    .await;
+
+  assert_eq!(res["saved_item_ids"], "");
+}
@@ -0,0 +1,184 @@
+//! OPML import limits and reports
+//! through the server harness.
+
+mod common;
+
+use common::{
+  Server,
+  start_with
+};
+use reqwest::StatusCode;
+use serde_json::Value;
+
+/// Imports an OPML body with these
+/// `(folder, xmlUrl)` feed outlines.
+async fn import(
+  server: &Server,
+  token: &str,
+  feeds: &[(Option<&str>, &str)]
+) -> (StatusCode, String) {
+  let outlines = feeds
+    .iter()
+    .map(|(folder, url)| {
+      let feed = format!(
+        "<outline text='{url}' \
+         xmlUrl='{url}'/>"
+      );
+
+      match folder {
+        | Some(folder) => {
+          format!(
+            "<outline text='{folder}'\
+             >{feed}</outline>"
+          )
+        }
+        | None => feed
+      }
+    })
+    .collect::<String>();
+
+  let res = server
+    .client
+    .post(format!(
+      "{}/v1/opml/import",
+      server.base
+    ))
+    .bearer_auth(token)
+    .body(format!(
+      "<opml version='2.0'\
+       ><body>{outlines}</body></opml>"
+    ))
+    .send()
+    .await
+    .unwrap();
+
+  (
+    res.status(),
+    res.text().await.unwrap()
+  )
+}
+
+#[tokio::test]
+
+async fn imports_are_bounded_and_reported_per_outline()
+ {
+  let server = start_with("opml", &[
+    "[registry]",
+    "default_quota = 1",
+    "import_limit = 4"
+  ])
+  .await;
+
+  let alice =
+    server.login("alice", "pw").await;
+
+  let (status, body) = import(
+    &server,
+    &alice,
+    &[(None, "https://a.example/rss");
+      5]
+  )
+  .await;
+
+  assert_eq!(
+    status,
+    StatusCode::PAYLOAD_TOO_LARGE,
+    "{body}"
+  );
+
+  // Two new feeds need two quota
+  // slots; nothing is looked up.
+  let (status, body) =
+    import(&server, &alice, &[
+      (None, "https://c.example/rss"),
+      (None, "https://d.example/rss")
+    ])
+    .await;
+
+  assert_eq!(
+    status,
+    StatusCode::FORBIDDEN,
+    "{body}"
+  );
+
+  let (status, body) =
+    import(&server, &alice, &[
+      (
+        Some("News"),
+        "https://b.example/rss"
+      ),
+      (
+        Some("News"),
+        "https://a.example/rss"
+      ),
+      (None, "https://b.example/rss"),
+      (None, "ftp://c.example/rss")
+    ])
+    .await;
+
+  assert_eq!(
+    status,
+    StatusCode::OK,
+    "{body}"
+  );
+
+  let report: Value =
+    serde_json::from_str(&body)
+      .unwrap();
+
+  assert_eq!(report["matched"], 3);
+  assert_eq!(report["created"], 0);
+  assert_eq!(report["failed"], 1);
+
+  let outlines: Vec<(&str, &str)> =
+    report["outlines"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|o| {
+        (
+          o["url"].as_str().unwrap(),
+          o["status"].as_str().unwrap()
+        )
+      })
+      .collect();
+
+  assert_eq!(outlines, [
+    (
+      "https://b.example/rss",
+      "matched"
+    ),
+    (
+      "https://a.example/rss",
+      "matched"
+    ),
+    (
+      "https://b.example/rss",
+      "matched"
+    ),
+    ("ftp://c.example/rss", "failed")
+  ]);
+  assert_eq!(
+    report["outlines"][0]["feed_id"],
+    "b"
+  );
+  assert_eq!(
+    report["outlines"][3]["error"],
+    "url must be an http(s) URL"
+  );
+
+  let (status, body) = server
+    .get(
+      Some(&alice),
+      "/v1/subscriptions"
+    )
+    .await;
+
+  assert_eq!(status, StatusCode::OK);
+
+  let subscribed: Vec<Value> =
+    serde_json::from_str(&body)
+      .unwrap();
+
+  assert_eq!(subscribed.len(), 2);
+}
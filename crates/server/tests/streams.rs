@@ -0,0 +1,505 @@
+//! Entry lists, entry details and
+//! published feeds through the server
+//! harness.
+
+mod common;
+
+use common::{
+  start,
+  start_postgres
+};
+use reqwest::StatusCode;
+use reqwest::header::{
+  ETAG,
+  IF_MODIFIED_SINCE,
+  IF_NONE_MATCH
+};
+use serde_json::{
+  Value,
+  json
+};
+
+/// Titles of an entry list.
+fn titles(body: &str) -> Vec<String> {
+  let body: Value =
+    serde_json::from_str(body).unwrap();
+
+  body["items"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|item| {
+      item["title"]
+        .as_str()
+        .unwrap()
+        .to_string()
+    })
+    .collect()
+}
+
+#[tokio::test]
+async fn entry_lists_and_search_hold_only_subscribed_feeds()
+ {
+  let server = start("lists").await;
+
+  let alice =
+    server.login("alice", "pw").await;
+  let bob =
+    server.login("bob", "pw").await;
+
+  let (status, body) = server
+    .get(Some(&alice), "/v1/entries")
+    .await;
+
+  assert_eq!(
+    status,
+    StatusCode::OK,
+    "{body}"
+  );
+  assert_eq!(titles(&body), vec![
+    "a2", "a1"
+  ]);
+
+  let (_, body) = server
+    .get(Some(&bob), "/v1/entries")
+    .await;
+
+  assert_eq!(titles(&body), vec!["b1"]);
+
+  // Search draws from the same
+  // stream.
+  for (token, want) in [
+    (&alice, Vec::<&str>::new()),
+    (&bob, vec!["b1"])
+  ] {
+    let (status, body) = server
+      .get(
+        Some(token),
+        "/v1/entries/search?q=b1"
+      )
+      .await;
+
+    assert_eq!(
+      status,
+      StatusCode::OK,
+      "{body}"
+    );
+    assert_eq!(titles(&body), want);
+  }
+
+  let (status, _) = server
+    .get(
+      Some(&alice),
+      "/v1/entries?scope=all"
+    )
+    .await;
+
+  assert_eq!(
+    status,
+    StatusCode::FORBIDDEN
+  );
+
+  let (status, _) = server
+    .get(None, "/v1/entries")
+    .await;
+
+  assert_eq!(
+    status,
+    StatusCode::UNAUTHORIZED
+  );
+}
+
+/// Runs when `PULSEWIRE_TEST_POSTGRES`
+/// is set.
+#[tokio::test]
+async fn postgres_search_parses_the_query_in_the_item_language()
+ {
+  let Some(server) =
+    start_postgres("search_language")
+      .await
+  else {
+    return;
+  };
+
+  // German stems "Hunde" to "hund", so
+  // a query parsed with another
+  // configuration would not exclude
+  // the first item.
+  for sql in [
+    "UPDATE feeds SET language = 'de' \
+     WHERE id = 'a'",
+    "UPDATE feed_items SET language = \
+     'de', title = CASE id WHEN 1 \
+     THEN 'Berlin Hunde' ELSE 'Berlin \
+     Katzen' END WHERE feed_id = 'a'"
+  ] {
+    sqlx::query(sql)
+      .execute(
+        server
+          .postgres
+          .as_ref()
+          .unwrap()
+      )
+      .await
+      .unwrap();
+  }
+
+  let alice =
+    server.login("alice", "pw").await;
+
+  let (status, body) = server
+    .get(
+      Some(&alice),
+      "/v1/entries/search?q=berlin%\
+       20-hunde"
+    )
+    .await;
+
+  assert_eq!(
+    status,
+    StatusCode::OK,
+    "{body}"
+  );
+  assert_eq!(titles(&body), vec![
+    "Berlin Katzen"
+  ]);
+}
+
+#[tokio::test]
+async fn entry_detail_needs_a_subscription_or_star()
+ {
+  let server = start("detail").await;
+
+  let alice =
+    server.login("alice", "pw").await;
+  let bob =
+    server.login("bob", "pw").await;
+
+  let detail =
+    |token: String, id: i64| {
+      let server = &server;
+
+      async move {
+        server
+          .get(
+            Some(&token),
+            &format!(
+              "/v1/entries/{id}"
+            )
+          )
+          .await
+          .0
+      }
+    };
+
+  assert_eq!(
+    detail(alice.clone(), 1).await,
+    StatusCode::OK
+  );
+  assert_eq!(
+    detail(alice.clone(), 3).await,
+    StatusCode::NOT_FOUND
+  );
+  assert_eq!(
+    detail(bob.clone(), 1).await,
+    StatusCode::NOT_FOUND
+  );
+  assert_eq!(
+    detail(bob.clone(), 3).await,
+    StatusCode::OK
+  );
+
+  // Alice's star keeps entry 2 hers
+  // once she leaves feed `a`.
+  let (status, _) = server
+    .post(
+      &alice,
+      "/v1/entries/2/star",
+      json!({})
+    )
+    .await;
+
+  assert!(status.is_success());
+
+  server
+    .client
+    .delete(format!(
+      "{}/v1/subscriptions/a",
+      server.base
+    ))
+    .bearer_auth(&alice)
+    .send()
+    .await
+    .unwrap()
+    .error_for_status()
+    .unwrap();
+
+  assert_eq!(
+    detail(alice.clone(), 2).await,
+    StatusCode::OK
+  );
+  assert_eq!(
+    detail(alice.clone(), 1).await,
+    StatusCode::NOT_FOUND
+  );
+}
+
+#[tokio::test]
+async fn published_feeds_are_public_and_read_only()
+ {
+  let server = start("published").await;
+
+  let alice =
+    server.login("alice", "pw").await;
+
+  let (status, body) = server
+    .post(
+      &alice,
+      "/v1/folders",
+      json!({ "name": "News" })
+    )
+    .await;
+
+  assert!(
+    status.is_success(),
+    "{body}"
+  );
+
+  let folder: Value =
+    serde_json::from_str(&body)
+      .unwrap();
+  let folder_id =
+    folder["id"].as_i64().unwrap();
+
+  let (status, body) = server
+    .post(
+      &alice,
+      &format!(
+        "/v1/folders/{folder_id}/feeds"
+      ),
+      json!({ "feed_id": "a" })
+    )
+    .await;
+
+  assert!(
+    status.is_success(),
+    "{body}"
+  );
+
+  let (status, body) = server
+    .post(
+      &alice,
+      "/v1/rules",
+      json!({
+        "name": "star a",
+        "filter": "feed:a",
+        "action": "star"
+      })
+    )
+    .await;
+
+  assert!(
+    status.is_success(),
+    "{body}"
+  );
+
+  let (status, body) = server
+    .post(
+      &alice,
+      "/v1/published",
+      json!({ "folder_id": folder_id })
+    )
+    .await;
+
+  assert!(
+    status.is_success(),
+    "{body}"
+  );
+
+  let published: Value =
+    serde_json::from_str(&body)
+      .unwrap();
+  let token = published["token"]
+    .as_str()
+    .unwrap()
+    .to_string();
+
+  // An item that arrives after the
+  // rule, for it to hit.
+  let db = server.db().await;
+
+  sqlx::query(
+    "INSERT INTO feed_items(id, \
+     payload_id, feed_id, title, \
+     published_at_ms) VALUES (4, 1, \
+     'a', 'a3', 4000)"
+  )
+  .execute(&db)
+  .await
+  .unwrap();
+
+  let (status, body) = server
+    .get(
+      None,
+      &format!("/p/{token}/feed.json")
+    )
+    .await;
+
+  assert_eq!(
+    status,
+    StatusCode::OK,
+    "{body}"
+  );
+
+  let feed: Value =
+    serde_json::from_str(&body)
+      .unwrap();
+  let items: Vec<&str> = feed["items"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|item| {
+      item["title"].as_str().unwrap()
+    })
+    .collect();
+
+  assert_eq!(items, ["a3", "a2", "a1"]);
+
+  let hits = || {
+    let db = db.clone();
+
+    async move {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM \
+         rule_hits"
+      )
+      .fetch_one(&db)
+      .await
+      .unwrap()
+    }
+  };
+
+  assert_eq!(hits().await, 0);
+
+  for bad in [
+    format!("/p/{token}/feed.txt"),
+    "/p/nope/feed.json".to_string()
+  ] {
+    assert_eq!(
+      server.get(None, &bad).await.0,
+      StatusCode::NOT_FOUND,
+      "{bad}"
+    );
+  }
+
+  // The owner's own reads run the
+  // rule.
+  let (status, _) = server
+    .get(
+      Some(&alice),
+      &format!(
+        "/v1/folders/{folder_id}/\
+         entries"
+      )
+    )
+    .await;
+
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(hits().await, 1);
+}
+
+#[tokio::test]
+async fn starring_an_older_entry_changes_the_published_feed()
+ {
+  let server =
+    start("conditional").await;
+
+  let alice =
+    server.login("alice", "pw").await;
+
+  let (status, body) = server
+    .post(
+      &alice,
+      "/v1/published",
+      json!({ "starred": true })
+    )
+    .await;
+
+  assert!(
+    status.is_success(),
+    "{body}"
+  );
+
+  let published: Value =
+    serde_json::from_str(&body)
+      .unwrap();
+  let url = format!(
+    "{}/p/{}/feed.json",
+    server.base,
+    published["token"]
+      .as_str()
+      .unwrap()
+  );
+
+  // Conditional GET with the tag and
+  // date of an earlier answer.
+  let fetch = |tag: Option<String>| {
+    let mut req =
+      server.client.get(&url).header(
+        IF_MODIFIED_SINCE,
+        "Fri, 01 Jan 2100 00:00:00 GMT"
+      );
+
+    if let Some(tag) = tag {
+      req =
+        req.header(IF_NONE_MATCH, tag);
+    }
+
+    async move {
+      let res =
+        req.send().await.unwrap();
+      let tag = res.headers()[ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+      (res.status(), tag)
+    }
+  };
+
+  let (status, _) = server
+    .post(
+      &alice,
+      "/v1/entries/2/star",
+      json!({})
+    )
+    .await;
+
+  assert!(status.is_success());
+
+  let (status, tag) = fetch(None).await;
+
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(
+    fetch(Some(tag.clone())).await.0,
+    StatusCode::NOT_MODIFIED
+  );
+
+  // The older entry leaves the newest
+  // date as it was.
+  let (status, _) = server
+    .post(
+      &alice,
+      "/v1/entries/1/star",
+      json!({})
+    )
+    .await;
+
+  assert!(status.is_success());
+
+  let (status, changed) =
+    fetch(Some(tag.clone())).await;
+
+  assert_eq!(status, StatusCode::OK);
+  assert_ne!(changed, tag);
+}
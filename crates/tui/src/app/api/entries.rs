@@ -53,8 +53,10 @@ impl App {
       )?;
 
     self.entries = data.items;
-    self.entries_next_offset =
-      data.next_offset;
+    self.entries_next_cursor =
+      data.next_cursor;
+    self.entries_prev_cursor =
+      data.prev_cursor;
 
     if self.selected_entry
       >= self.entries.len()
@@ -67,15 +69,14 @@ impl App {
     {
       self.status = format!(
         "Loaded {} entries (page \
-         {current}/{total}, offset {})",
-        self.entries.len(),
-        self.entries_offset
+         {current}/{total})",
+        self.entries.len()
       );
     } else {
       self.status = format!(
-        "Loaded {} entries (offset {})",
+        "Loaded {} entries (page {})",
         self.entries.len(),
-        self.entries_offset
+        self.entries_page + 1
       );
     }
 
@@ -125,11 +126,22 @@ impl App {
           .folders
           .get(self.selected_folder)
           .cloned();
+        let search = self
+          .selected_saved_search()
+          .cloned();
         if let Some(folder) = folder {
           self.entries_mode =
             EntriesMode::Folder(
               folder.id
             );
+        } else if let Some(search) =
+          search
+        {
+          self.entries_mode =
+            EntriesMode::Search {
+              query:   search.query,
+              feed_id: None
+            };
         } else {
           self.status =
             "No folder selected"
@@ -160,7 +172,7 @@ impl App {
       | _ => {}
     }
 
-    self.entries_offset = 0;
+    self.reset_entries_page();
     self.selected_entry = 0;
     self.tab = 1;
     self.refresh_entries()?;
@@ -173,7 +185,7 @@ impl App {
   ) -> Result<()> {
     self.entries_mode =
       EntriesMode::All;
-    self.entries_offset = 0;
+    self.reset_entries_page();
     self.selected_entry = 0;
     self.tab = 1;
     self.refresh_entries()?;
@@ -200,7 +212,7 @@ impl App {
         query,
         feed_id
       };
-    self.entries_offset = 0;
+    self.reset_entries_page();
     self.selected_entry = 0;
     self.tab = 1;
     self.refresh_entries()?;
@@ -215,9 +227,10 @@ impl App {
     }
 
     if let Some(next) =
-      self.entries_next_offset
+      self.entries_next_cursor.take()
     {
-      self.entries_offset = next;
+      self.entries_cursor = Some(next);
+      self.entries_page += 1;
       self.refresh_entries()?;
     }
 
@@ -231,20 +244,37 @@ impl App {
       return Ok(());
     }
 
-    if self.entries_offset == 0 {
+    if self.entries_page == 0 {
       return Ok(());
     }
 
-    let size =
-      self.entries_page_size as i64;
-    self.entries_offset =
-      (self.entries_offset - size)
-        .max(0);
+    self.entries_page -= 1;
+
+    // Page one is loaded without a
+    // cursor so entries that arrived
+    // since show up at its top.
+    self.entries_cursor =
+      if self.entries_page == 0 {
+        None
+      } else {
+        self.entries_prev_cursor.take()
+      };
     self.refresh_entries()?;
 
     Ok(())
   }
 
+  /// Goes back to the first page of
+  /// entries.
+  pub(crate) fn reset_entries_page(
+    &mut self
+  ) {
+    self.entries_cursor = None;
+    self.entries_next_cursor = None;
+    self.entries_prev_cursor = None;
+    self.entries_page = 0;
+  }
+
   pub(crate) fn entries_url(
     &self
   ) -> Option<String> {
@@ -307,19 +337,20 @@ impl App {
     {
       let mut pairs =
         url.query_pairs_mut();
-      pairs
-        .append_pair(
-          "limit",
-          &self
-            .entries_page_size
-            .to_string()
-        )
-        .append_pair(
-          "offset",
-          &self
-            .entries_offset
-            .to_string()
+      pairs.append_pair(
+        "limit",
+        &self
+          .entries_page_size
+          .to_string()
+      );
+
+      if let Some(cursor) =
+        &self.entries_cursor
+      {
+        pairs.append_pair(
+          "cursor", cursor
         );
+      }
 
       if let Some(feed_id) = feed_id {
         pairs.append_pair(
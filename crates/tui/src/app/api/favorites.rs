@@ -2,9 +2,11 @@ use anyhow::{
   Context,
   Result
 };
+use reqwest::Url;
 
 use super::super::App;
 use super::super::util::ensure_offset;
+use crate::models::FavoriteListResponse;
 
 impl App {
   pub(crate) fn refresh_favorites(
@@ -15,33 +17,65 @@ impl App {
       .as_deref()
       .unwrap_or_default();
 
-    let url = format!(
-      "{}/v1/favorites",
-      self.base_url
-    );
+    let mut favorites = Vec::new();
+    let mut cursor: Option<String> =
+      None;
 
-    let resp = self
-      .client
-      .get(url)
-      .bearer_auth(token)
-      .send()
-      .context(
-        "favorites request failed"
-      )?;
-
-    if !resp.status().is_success() {
-      self.status = format!(
-        "Failed to load favorites ({})",
-        resp.status()
-      );
+    loop {
+      let mut url = Url::parse(
+        &format!(
+          "{}/v1/favorites",
+          self.base_url
+        )
+      )
+      .context("invalid server url")?;
+
+      url
+        .query_pairs_mut()
+        .append_pair("limit", "200");
+
+      if let Some(cursor) = &cursor {
+        url
+          .query_pairs_mut()
+          .append_pair(
+            "cursor", cursor
+          );
+      }
+
+      let resp = self
+        .client
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .context(
+          "favorites request failed"
+        )?;
+
+      if !resp.status().is_success() {
+        self.status = format!(
+          "Failed to load favorites \
+           ({})",
+          resp.status()
+        );
+
+        return Ok(());
+      }
+
+      let page: FavoriteListResponse =
+        resp.json().context(
+          "failed to parse favorites"
+        )?;
+
+      favorites.extend(page.items);
+
+      cursor = page.next_cursor;
 
-      return Ok(());
+      if cursor.is_none() {
+        break;
+      }
     }
 
-    self.favorites =
-      resp.json().context(
-        "failed to parse favorites"
-      )?;
+    self.favorites = favorites;
     self.favorite_ids = self
       .favorites
       .iter()
@@ -43,8 +43,31 @@ impl App {
         "failed to parse folders"
       )?;
 
+    let url = format!(
+      "{}/v1/searches",
+      self.base_url
+    );
+
+    self.saved_searches = self
+      .client
+      .get(url)
+      .bearer_auth(token)
+      .send()
+      .context(
+        "saved searches request failed"
+      )?
+      .error_for_status()
+      .context(
+        "saved searches request failed"
+      )?
+      .json()
+      .context(
+        "failed to parse saved \
+         searches"
+      )?;
+
     if self.selected_folder
-      >= self.folders.len()
+      >= self.folders_tab_len()
     {
       self.selected_folder = 0;
       self.folders_offset = 0;
@@ -54,12 +77,14 @@ impl App {
       self.selected_folder,
       self.folders_offset,
       self.folders_page_size as usize,
-      self.folders.len()
+      self.folders_tab_len()
     );
 
     self.status = format!(
-      "Loaded {} folders",
-      self.folders.len()
+      "Loaded {} folders, {} saved \
+       searches",
+      self.folders.len(),
+      self.saved_searches.len()
     );
 
     Ok(())
@@ -8,16 +8,18 @@ use crate::models::{
   FeedDetail,
   FeedEntryCounts,
   FeedSummary,
-  FolderRow
+  FolderRow,
+  SavedSearchRow
 };
 
 pub(crate) enum AppEvent {
   RefreshAll {
-    feeds:         Vec<FeedSummary>,
-    subscriptions: Vec<String>,
-    feed_counts:   Vec<FeedEntryCounts>,
-    favorites:     Vec<FeedSummary>,
-    folders:       Vec<FolderRow>,
+    feeds:          Vec<FeedSummary>,
+    subscriptions:  Vec<String>,
+    feed_counts: Vec<FeedEntryCounts>,
+    favorites:      Vec<FeedSummary>,
+    folders:        Vec<FolderRow>,
+    saved_searches: Vec<SavedSearchRow>,
     entries: Option<EntryListResponse>
   },
   RefreshFolders {
@@ -85,6 +87,7 @@ impl App {
         feed_counts,
         favorites,
         folders,
+        saved_searches,
         entries
       } => {
         self.feeds = feeds;
@@ -105,10 +108,14 @@ impl App {
           .map(|row| row.id.clone())
           .collect();
         self.folders = folders;
+        self.saved_searches =
+          saved_searches;
         if let Some(data) = entries {
           self.entries = data.items;
-          self.entries_next_offset =
-            data.next_offset;
+          self.entries_next_cursor =
+            data.next_cursor;
+          self.entries_prev_cursor =
+            data.prev_cursor;
         }
 
         self.rebuild_views();
@@ -129,7 +136,7 @@ impl App {
         }
 
         if self.selected_folder
-          >= self.folders.len()
+          >= self.folders_tab_len()
         {
           self.selected_folder = 0;
           self.folders_offset = 0;
@@ -165,7 +172,7 @@ impl App {
       } => {
         self.folders = folders;
         if self.selected_folder
-          >= self.folders.len()
+          >= self.folders_tab_len()
         {
           self.selected_folder = 0;
           self.folders_offset = 0;
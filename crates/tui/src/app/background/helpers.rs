@@ -5,6 +5,10 @@ use super::super::{
   EntriesMode,
   EntriesReadFilter
 };
+use crate::models::{
+  FavoriteListResponse,
+  FeedSummary
+};
 
 pub(super) fn get_json<
   T: DeserializeOwned
@@ -33,12 +37,56 @@ pub(super) fn get_json<
     .map_err(|e| e.to_string())
 }
 
+/// Fetches every favorite, following
+/// the list's cursors.
+pub(super) fn get_favorites(
+  client: &reqwest::blocking::Client,
+  base_url: &str,
+  token: &str
+) -> Result<Vec<FeedSummary>, String> {
+  let mut favorites = Vec::new();
+  let mut cursor: Option<String> = None;
+
+  loop {
+    let mut url = Url::parse(&format!(
+      "{base_url}/v1/favorites"
+    ))
+    .map_err(|e| e.to_string())?;
+
+    url
+      .query_pairs_mut()
+      .append_pair("limit", "200");
+
+    if let Some(cursor) = &cursor {
+      url
+        .query_pairs_mut()
+        .append_pair("cursor", cursor);
+    }
+
+    let page: FavoriteListResponse =
+      get_json(
+        client,
+        url.as_str(),
+        token
+      )?;
+
+    favorites.extend(page.items);
+
+    match page.next_cursor {
+      | Some(next) => {
+        cursor = Some(next)
+      }
+      | None => return Ok(favorites)
+    }
+  }
+}
+
 pub(super) fn build_entries_url(
   base_url: &str,
   mode: &EntriesMode,
   read_filter: EntriesReadFilter,
   limit: u32,
-  offset: i64
+  cursor: Option<&str>
 ) -> Option<String> {
   let (path, feed_id, query) =
     match mode {
@@ -96,15 +144,15 @@ pub(super) fn build_entries_url(
   {
     let mut pairs =
       url.query_pairs_mut();
-    pairs
-      .append_pair(
-        "limit",
-        &limit.to_string()
-      )
-      .append_pair(
-        "offset",
-        &offset.to_string()
-      );
+    pairs.append_pair(
+      "limit",
+      &limit.to_string()
+    );
+
+    if let Some(cursor) = cursor {
+      pairs
+        .append_pair("cursor", cursor);
+    }
 
     if let Some(feed_id) = feed_id {
       pairs.append_pair(
@@ -4,6 +4,7 @@ use super::super::super::App;
 use super::super::events::AppEvent;
 use super::super::helpers::{
   build_entries_url,
+  get_favorites,
   get_json
 };
 use crate::models::{
@@ -11,6 +12,7 @@ use crate::models::{
   FeedEntryCounts,
   FeedSummary,
   FolderRow,
+  SavedSearchRow,
   SubscriptionRow
 };
 
@@ -38,8 +40,8 @@ impl App {
       self.entries_read_filter;
     let entries_page_size =
       self.entries_page_size;
-    let entries_offset =
-      self.entries_offset;
+    let entries_cursor =
+      self.entries_cursor.clone();
 
     self.pending_requests += 1;
     self.loading = true;
@@ -108,13 +110,9 @@ impl App {
         }
       };
 
-      let favorites: Vec<FeedSummary> =
-        match get_json(
-          &client,
-          &format!(
-            "{base_url}/v1/favorites"
-          ),
-          &token
+      let favorites =
+        match get_favorites(
+          &client, &base_url, &token
         ) {
           | Ok(data) => data,
           | Err(err) => {
@@ -146,6 +144,26 @@ impl App {
           }
         };
 
+      let saved_searches: Vec<
+        SavedSearchRow
+      > = match get_json(
+        &client,
+        &format!(
+          "{base_url}/v1/searches"
+        ),
+        &token
+      ) {
+        | Ok(data) => data,
+        | Err(err) => {
+          let _ = sender.send(
+            AppEvent::Error {
+              message: err
+            }
+          );
+          return;
+        }
+      };
+
       let entries: Option<
         EntryListResponse
       > = build_entries_url(
@@ -153,7 +171,7 @@ impl App {
         &entries_mode,
         entries_read_filter,
         entries_page_size,
-        entries_offset
+        entries_cursor.as_deref()
       )
       .and_then(|url| {
         match get_json(
@@ -184,6 +202,7 @@ impl App {
           feed_counts,
           favorites,
           folders,
+          saved_searches,
           entries
         }
       );
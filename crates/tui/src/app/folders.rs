@@ -1,8 +1,29 @@
 use std::collections::HashMap;
 
 use super::App;
+use crate::models::SavedSearchRow;
 
 impl App {
+  /// Rows in the folders tab: folders
+  /// first, then saved searches.
+  pub(crate) fn folders_tab_len(
+    &self
+  ) -> usize {
+    self.folders.len()
+      + self.saved_searches.len()
+  }
+
+  pub(crate) fn selected_saved_search(
+    &self
+  ) -> Option<&SavedSearchRow> {
+    self
+      .selected_folder
+      .checked_sub(self.folders.len())
+      .and_then(|idx| {
+        self.saved_searches.get(idx)
+      })
+  }
+
   pub(crate) fn request_folder_feeds(
     &mut self
   ) {
@@ -248,7 +248,7 @@ impl App {
             EntriesReadFilter::All
           }
         };
-      self.entries_offset = 0;
+      self.reset_entries_page();
       if self.tab == 1 {
         self.refresh_entries()?;
       }
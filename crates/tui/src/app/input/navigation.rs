@@ -49,7 +49,8 @@ impl App {
         self.ensure_visible_for_tab();
       }
       | 3 => {
-        let len = self.folders.len();
+        let len =
+          self.folders_tab_len();
         let before =
           self.selected_folder;
         self.selected_folder =
@@ -131,9 +132,9 @@ impl App {
         }
       }
       | 3 => {
-        if !self.folders.is_empty() {
+        if self.folders_tab_len() > 0 {
           self.selected_folder =
-            self.folders.len() / 2;
+            self.folders_tab_len() / 2;
           self.ensure_visible_for_tab();
           self.request_folder_feeds();
         }
@@ -179,9 +180,9 @@ impl App {
         }
       }
       | 3 => {
-        if !self.folders.is_empty() {
+        if self.folders_tab_len() > 0 {
           self.selected_folder =
-            self.folders.len() - 1;
+            self.folders_tab_len() - 1;
           self.ensure_visible_for_tab();
           self.request_folder_feeds();
         }
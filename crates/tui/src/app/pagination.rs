@@ -130,9 +130,7 @@ impl App {
       ((total + page_size - 1)
         / page_size) as usize;
     let current_page =
-      (self.entries_offset / page_size)
-        as usize
-        + 1;
+      self.entries_page + 1;
 
     Some((current_page, total_pages))
   }
@@ -143,7 +141,7 @@ impl App {
     match self.tab {
       | 0 => self.feeds_view.len(),
       | 2 => self.favorites.len(),
-      | 3 => self.folders.len(),
+      | 3 => self.folders_tab_len(),
       | 4 => {
         self.subscriptions_view.len()
       }
@@ -17,7 +17,8 @@ use crate::models::{
   FeedDetail,
   FeedEntryCounts,
   FeedSummary,
-  FolderRow
+  FolderRow,
+  SavedSearchRow
 };
 
 #[derive(
@@ -116,6 +117,10 @@ pub(crate) struct App {
   pub(crate) favorite_ids:
     HashSet<String>,
   pub(crate) folders: Vec<FolderRow>,
+  /// Listed after the folders in the
+  /// folders tab.
+  pub(crate) saved_searches:
+    Vec<SavedSearchRow>,
   pub(crate) folder_feed_ids:
     Vec<String>,
   pub(crate) folder_feeds:
@@ -165,9 +170,17 @@ pub(crate) struct App {
   pub(crate) subscriptions_offset:
     usize,
   pub(crate) keys: ResolvedKeybindings,
-  pub(crate) entries_offset: i64,
-  pub(crate) entries_next_offset:
-    Option<i64>,
+  /// Cursor the shown entries page was
+  /// loaded from; `None` is the first.
+  pub(crate) entries_cursor:
+    Option<String>,
+  pub(crate) entries_next_cursor:
+    Option<String>,
+  pub(crate) entries_prev_cursor:
+    Option<String>,
+  /// Zero-based number of the shown
+  /// entries page.
+  pub(crate) entries_page: usize,
   pub(crate) base_url: String,
   pub(crate) client: Client
 }
@@ -208,6 +221,7 @@ impl App {
       favorites: Vec::new(),
       favorite_ids: HashSet::new(),
       folders: Vec::new(),
+      saved_searches: Vec::new(),
       folder_feed_ids: Vec::new(),
       folder_feeds: Vec::new(),
       subscriptions: HashSet::new(),
@@ -260,8 +274,10 @@ impl App {
       folders_offset: 0,
       subscriptions_offset: 0,
       keys,
-      entries_offset: 0,
-      entries_next_offset: None,
+      entries_cursor: None,
+      entries_next_cursor: None,
+      entries_prev_cursor: None,
+      entries_page: 0,
       base_url: config
         .server
         .url
@@ -34,8 +34,17 @@ pub(crate) struct FeedEntryCounts {
 
 #[derive(Debug, Deserialize, Clone)]
 pub(crate) struct FolderRow {
-  pub(crate) id:   i64,
-  pub(crate) name: String
+  pub(crate) id:     i64,
+  pub(crate) name:   String,
+  /// Set for smart folders.
+  #[serde(default)]
+  pub(crate) filter: Option<String>
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct SavedSearchRow {
+  pub(crate) name:  String,
+  pub(crate) query: String
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -68,16 +77,32 @@ pub(crate) struct EntryDetail {
   pub(crate) description:
     Option<String>,
   pub(crate) summary: Option<String>,
-  pub(crate) is_read:         bool
+  pub(crate) is_read:         bool,
+  pub(crate) changes:
+    Option<EntryChanges>
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct EntryChanges {
+  pub(crate) added:     Vec<String>,
+  pub(crate) removed:   Vec<String>,
+  pub(crate) truncated: bool
 }
 
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 pub(crate) struct EntryListResponse {
   pub(crate) items: Vec<EntrySummary>,
-  pub(crate) next_cursor: Option<i64>,
-  pub(crate) next_offset: Option<i64>,
-  pub(crate) since:       Option<i64>
+  pub(crate) next_cursor:
+    Option<String>,
+  pub(crate) prev_cursor:
+    Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct FavoriteListResponse {
+  pub(crate) items: Vec<FeedSummary>,
+  pub(crate) next_cursor:
+    Option<String>
 }
 
 #[derive(Debug, Deserialize)]
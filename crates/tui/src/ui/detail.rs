@@ -1,5 +1,9 @@
 use ratatui::Frame;
 use ratatui::layout::Rect;
+use ratatui::style::{
+  Color,
+  Style
+};
 use ratatui::text::Line;
 use ratatui::widgets::{
   Block,
@@ -9,6 +13,7 @@ use ratatui::widgets::{
 };
 
 use crate::models::{
+  EntryChanges,
   EntryDetail,
   EntrySummary,
   FeedDetail,
@@ -118,7 +123,7 @@ pub(crate) fn draw_entry_detail(
       })
       .unwrap_or("-");
 
-    vec![
+    let mut lines = vec![
       Line::from(format!(
         "id: {}",
         entry.id
@@ -159,7 +164,18 @@ pub(crate) fn draw_entry_detail(
         "description: {}",
         description
       )),
-    ]
+    ];
+
+    if let Some(changes) = detail
+      .and_then(|row| {
+        row.changes.as_ref()
+      })
+    {
+      lines
+        .extend(change_lines(changes));
+    }
+
+    lines
   } else {
     vec![Line::from("No selection")]
   };
@@ -176,3 +192,40 @@ pub(crate) fn draw_entry_detail(
 
   frame.render_widget(widget, area);
 }
+
+fn change_lines(
+  changes: &EntryChanges
+) -> Vec<Line<'static>> {
+  let mut lines =
+    vec![Line::from("changes:")];
+
+  lines.extend(
+    changes.removed.iter().map(
+      |text| {
+        Line::styled(
+          format!("- {text}"),
+          Style::default()
+            .fg(Color::Red)
+        )
+      }
+    )
+  );
+
+  lines.extend(
+    changes.added.iter().map(|text| {
+      Line::styled(
+        format!("+ {text}"),
+        Style::default()
+          .fg(Color::Green)
+      )
+    })
+  );
+
+  if changes.truncated {
+    lines.push(Line::from(
+      "(diff truncated)"
+    ));
+  }
+
+  lines
+}
@@ -253,6 +253,7 @@ pub(crate) fn draw_main(
         frame,
         content[0],
         &app.folders,
+        &app.saved_searches,
         app.selected_folder,
         app.folders_offset,
         app.folders_page_size as usize
@@ -25,7 +25,8 @@ use crate::models::{
   EntrySummary,
   FeedEntryCounts,
   FeedSummary,
-  FolderRow
+  FolderRow,
+  SavedSearchRow
 };
 
 #[allow(clippy::too_many_arguments)]
@@ -298,27 +299,51 @@ pub(crate) fn draw_entries_list(
   );
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn draw_folder_list(
   frame: &mut Frame,
   area: Rect,
   folders: &[FolderRow],
+  saved_searches: &[SavedSearchRow],
   selected: usize,
   offset: usize,
   page_size: usize
 ) {
+  let rows = folders
+    .iter()
+    .map(|folder| {
+      if folder.filter.is_some() {
+        format!(
+          "{} (#{}) [smart]",
+          folder.name, folder.id
+        )
+      } else {
+        format!(
+          "{} (#{})",
+          folder.name, folder.id
+        )
+      }
+    })
+    .chain(saved_searches.iter().map(
+      |search| {
+        format!(
+          "⌕ {}: {}",
+          search.name, search.query
+        )
+      }
+    ))
+    .collect::<Vec<_>>();
+
   let (start, end) = page_bounds(
-    folders.len(),
+    rows.len(),
     offset,
     page_size
   );
 
-  let items = folders[start..end]
+  let items = rows[start..end]
     .iter()
-    .map(|folder| {
-      ListItem::new(format!(
-        "{} (#{})",
-        folder.name, folder.id
-      ))
+    .map(|row| {
+      ListItem::new(row.as_str())
     })
     .collect::<Vec<_>>();
 